@@ -0,0 +1,295 @@
+// The commands and the events of the app, see `app/runner/src`. Keep in
+// sync with the Rust types: the OCPP payloads are generated in
+// `ocpp-1.6.d.ts` by the build script of the app.
+
+import type {
+  Action,
+  MeterValue,
+  Requests,
+  Responses,
+  StatusNotificationErrorCode,
+  StatusNotificationStatus,
+  StopTransactionReason,
+} from './ocpp-1.6';
+
+export * from './ocpp-1.6';
+
+/** An RFC 3339 date and time, e.g. `2013-02-01T20:53:32.486Z`. */
+export type DateTime = string;
+
+/** The error code of a `CallError`, or any other code, verbatim. */
+export type ErrorCode =
+  | 'NotImplemented'
+  | 'NotSupported'
+  | 'InternalError'
+  | 'ProtocolError'
+  | 'SecurityError'
+  | 'FormationViolation'
+  | 'FormatViolation'
+  | 'PropertyConstraintViolation'
+  | 'OccurenceConstraintViolation'
+  | 'OccurrenceConstraintViolation'
+  | 'TypeConstraintViolation'
+  | 'MessageTypeNotSupported'
+  | 'RpcFrameworkError'
+  | 'GenericError'
+  | (string & {});
+
+/** An OCPP-J frame, in its wire format. */
+export type Message =
+  | [2, string, Action, unknown]
+  | [3, string, unknown]
+  | [4, string, ErrorCode, string, unknown];
+
+export type Direction = 'incoming' | 'outgoing';
+
+/** `store::Connector`. */
+export interface Connector {
+  connectorId: number;
+  status: StatusNotificationStatus;
+  errorCode: StatusNotificationErrorCode;
+  info: string | null;
+  updatedAt: DateTime;
+}
+
+/** `store::ChargePoint`. */
+export interface ChargePoint {
+  id: string;
+  connected: boolean;
+  connectedAt: DateTime;
+  /** The connectors, by ID. The connector 0 is the Charge Point itself. */
+  connectors: Record<number, Connector>;
+}
+
+/** `store::LoggedMessage`. */
+export interface LoggedMessage {
+  timestamp: DateTime;
+  chargePointId: string;
+  direction: Direction;
+  /** The action of the `Call`, or of the `Call` being responded to. */
+  action: string;
+  message: Message;
+}
+
+/** `dashboard::ConnectorStatus`. */
+export interface ConnectorStatus {
+  chargePointId: string;
+  connector: Connector;
+}
+
+/** `ocppx_store::TransactionRecord`. */
+export interface TransactionRecord {
+  id: number;
+  chargePointId: string;
+  connectorId: number;
+  idTag: string;
+  reservationId: number | null;
+  /** Meter value at the start of the transaction, in Wh. */
+  meterStart: number;
+  startedAt: DateTime;
+  meterValues: MeterValue[];
+  stop: TransactionStopRecord | null;
+}
+
+/** `ocppx_store::TransactionStopRecord`. */
+export interface TransactionStopRecord {
+  idTag: string | null;
+  /** Meter value at the end of the transaction, in Wh. */
+  meterStop: number;
+  stoppedAt: DateTime;
+  reason: StopTransactionReason;
+}
+
+/** `store::MessageFilter`, all the messages when empty. */
+export interface MessageFilter {
+  chargePointId?: string;
+  action?: string;
+  direction?: Direction;
+  /** The messages at or after this instant. */
+  since?: DateTime;
+  /** The messages strictly before this instant. */
+  until?: DateTime;
+}
+
+/** `export::ExportFormat`. */
+export type ExportFormat = 'json' | 'csv';
+
+/** `composer::ActionSchema`. */
+export interface ActionSchema {
+  action: Action;
+  requestSchema: object;
+  responseSchema: object;
+}
+
+/** `composer::SendError`. */
+export type SendError =
+  | { kind: 'unknownAction'; action: string }
+  | { kind: 'invalidPayload'; action: Action; violations: string[] }
+  | {
+      kind: 'callError';
+      errorCode: ErrorCode;
+      errorDescription: string;
+      errorDetails: unknown;
+    }
+  | { kind: 'server'; message: string };
+
+/** `emulator::ChargePointConfig`, the defaults of the simulator when omitted. */
+export interface ChargePointConfig {
+  id: string;
+  /** The Central System of the app when omitted. */
+  csmsUrl?: string;
+  vendor?: string;
+  model?: string;
+  firmwareVersion?: string;
+  connectors?: number;
+  /** The power delivered by a charging connector, in W. */
+  chargingPower?: number;
+}
+
+/** `emulator::EmulatedConnector`. */
+export interface EmulatedConnector {
+  connectorId: number;
+  status: StatusNotificationStatus;
+  transactionId: number | null;
+}
+
+/** `emulator::EmulatedChargePointState`. */
+export interface EmulatedChargePoint {
+  id: string;
+  csmsUrl: string;
+  /** `null` while connecting or disconnecting. */
+  connected: boolean | null;
+  connectors: EmulatedConnector[];
+}
+
+/**
+ * A duration: a number of seconds, or a string with a unit, e.g. `500ms`,
+ * `30s`, `20m` or `1h`.
+ */
+export type ScenarioDuration = number | string;
+
+/** `ocppx_simulator::ScenarioAction`. */
+export type ScenarioAction =
+  /** Then one `every` interval until the end of the scenario, if any. */
+  | { action: 'heartbeat'; every?: ScenarioDuration }
+  | { action: 'plugIn'; connectorId: number }
+  | { action: 'startTransaction'; connectorId: number; idTag: string }
+  | { action: 'stopTransaction'; connectorId: number }
+  /** Deliver `energy` Wh over `duration`, in a transaction. */
+  | {
+      action: 'charge';
+      connectorId: number;
+      idTag: string;
+      energy: number;
+      duration: ScenarioDuration;
+    }
+  | { action: 'unplug'; connectorId: number };
+
+/** `ocppx_simulator::ScenarioStep`. */
+export type ScenarioStep = {
+  /** From the start of the scenario. */
+  at: ScenarioDuration;
+} & ScenarioAction;
+
+/** `ocppx_simulator::Scenario`. */
+export interface Scenario {
+  name?: string;
+  steps: ScenarioStep[];
+}
+
+/** `ocppx_simulator::ScenarioProgress`, with the index of the steps. */
+export type ScenarioProgress =
+  | { kind: 'stepStarted'; step: number }
+  | { kind: 'stepDone'; step: number }
+  | { kind: 'heartbeat' };
+
+/** `emulator::ScenarioProgressEvent`. */
+export interface ScenarioProgressEvent {
+  chargePointId: string;
+  scenario: string;
+  progress: ScenarioProgress;
+}
+
+/** `emulator::EmulatorError`. */
+export type EmulatorError =
+  | { kind: 'unknownChargePoint'; id: string }
+  | { kind: 'alreadyExists'; id: string }
+  | { kind: 'alreadyConnected'; id: string }
+  | { kind: 'notConnected'; id: string }
+  | { kind: 'simulator'; message: string };
+
+/**
+ * The commands, by name, with their arguments and their result. The
+ * arguments are in camel case, as Tauri renames them.
+ */
+export interface Commands {
+  charge_points: { args: {}; result: ChargePoint[] };
+  messages: { args: { filter?: MessageFilter }; result: LoggedMessage[] };
+  /** The result is the number of exported messages. */
+  export_messages: {
+    args: { filter?: MessageFilter; format: ExportFormat; path: string };
+    result: number;
+  };
+  transactions: { args: { chargePointId: string }; result: TransactionRecord[] };
+  actions: { args: {}; result: ActionSchema[] };
+  send_message: {
+    args: { chargePointId: string; action: Action; payload: Requests[Action] };
+    result: Responses[Action];
+  };
+  emulated_charge_points: { args: {}; result: EmulatedChargePoint[] };
+  create_charge_point: { args: { config: ChargePointConfig }; result: null };
+  connect: { args: { id: string }; result: null };
+  disconnect: { args: { id: string }; result: null };
+  plug_in: { args: { id: string; connector: number }; result: null };
+  unplug: { args: { id: string; connector: number }; result: null };
+  /** The result is the ID of the transaction. */
+  start_charging: {
+    args: { id: string; connector: number; idTag: string };
+    result: number;
+  };
+  stop_charging: { args: { id: string; connector: number }; result: null };
+  /** The result comes at the end of the scenario. */
+  run_scenario: { args: { id: string; scenario: Scenario }; result: null };
+}
+
+/**
+ * The events pushed by `dashboard::Dashboard` and by `emulator`, by name,
+ * with their payload.
+ */
+export interface Events {
+  'charge-point-connected': ChargePoint;
+  /** The ID of the Charge Point. */
+  'charge-point-disconnected': string;
+  'connector-status': ConnectorStatus;
+  message: LoggedMessage;
+  'scenario-progress': ScenarioProgressEvent;
+}
+
+/** `window.__TAURI__`, with the commands and the events of the app. */
+export interface Tauri {
+  invoke<C extends keyof Commands>(
+    command: C,
+    ...args: {} extends Commands[C]['args'] ? [] : [Commands[C]['args']]
+  ): Promise<Commands[C]['result']>;
+
+  dialog: {
+    /** The path chosen by the user, `null` if cancelled. */
+    save(options?: {
+      defaultPath?: string;
+      filters?: { name: string; extensions: string[] }[];
+    }): Promise<string | null>;
+  };
+
+  event: {
+    listen<E extends keyof Events>(
+      event: E,
+      handler: (event: { event: E; id: number; payload: Events[E] }) => void,
+    ): Promise<() => void>;
+  };
+}
+
+declare global {
+  interface Window {
+    __TAURI__: Tauri;
+  }
+}
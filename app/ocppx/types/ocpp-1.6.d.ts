@@ -0,0 +1,500 @@
+// Generated by `ocppx-types` from the OCPP 1.6 JSON schemas.
+
+export interface AuthorizeRequest {
+  idTag: string;
+}
+
+export interface AuthorizeResponse {
+  idTagInfo: IdTagInfo;
+}
+
+export interface BootNotificationRequest {
+  chargeBoxSerialNumber?: string;
+  chargePointModel: string;
+  chargePointSerialNumber?: string;
+  chargePointVendor: string;
+  firmwareVersion?: string;
+  iccid?: string;
+  imsi?: string;
+  meterSerialNumber?: string;
+  meterType?: string;
+}
+
+export interface BootNotificationResponse {
+  currentTime: string;
+  interval: number;
+  status: BootNotificationStatus;
+}
+
+export interface CancelReservationRequest {
+  reservationId: number;
+}
+
+export interface CancelReservationResponse {
+  status: CancelReservationStatus;
+}
+
+export interface ChangeAvailabilityRequest {
+  connectorId: number;
+  type: ChangeAvailabilityType;
+}
+
+export interface ChangeAvailabilityResponse {
+  status: ChangeAvailabilityStatus;
+}
+
+export interface ChangeConfigurationRequest {
+  key: string;
+  value: string;
+}
+
+export interface ChangeConfigurationResponse {
+  status: ChangeConfigurationStatus;
+}
+
+export interface ChargingProfile {
+  chargingProfileId: number;
+  chargingProfileKind: ChargingProfileKind;
+  chargingProfilePurpose: ChargingProfilePurpose;
+  chargingSchedule: ChargingSchedule;
+  recurrencyKind?: ChargingProfileRecurrencyKind;
+  stackLevel: number;
+  transactionId?: number;
+  validFrom?: string;
+  validTo?: string;
+}
+
+export interface ChargingSchedule {
+  chargingRateUnit: ChargingScheduleChargingRateUnit;
+  chargingSchedulePeriod: ChargingSchedulePeriod[];
+  duration?: number;
+  minChargingRate?: number;
+  startSchedule?: string;
+}
+
+export interface ChargingSchedulePeriod {
+  limit: number;
+  numberPhases?: number;
+  startPeriod: number;
+}
+
+export interface ClearCacheRequest {
+}
+
+export interface ClearCacheResponse {
+  status: ClearCacheStatus;
+}
+
+export interface ClearChargingProfileRequest {
+  chargingProfilePurpose?: ClearChargingProfileChargingProfilePurpose;
+  connectorId?: number;
+  id?: number;
+  stackLevel?: number;
+}
+
+export interface ClearChargingProfileResponse {
+  status: ClearChargingProfileStatus;
+}
+
+export interface ConfigurationKey {
+  key: string;
+  readonly: boolean;
+  value?: string;
+}
+
+export interface CsChargingProfiles {
+  chargingProfileId: number;
+  chargingProfileKind: CsChargingProfilesChargingProfileKind;
+  chargingProfilePurpose: CsChargingProfilesChargingProfilePurpose;
+  chargingSchedule: ChargingSchedule;
+  recurrencyKind?: CsChargingProfilesRecurrencyKind;
+  stackLevel: number;
+  transactionId?: number;
+  validFrom?: string;
+  validTo?: string;
+}
+
+export interface DataTransferRequest {
+  data?: string;
+  messageId?: string;
+  vendorId: string;
+}
+
+export interface DataTransferResponse {
+  data?: string;
+  status: DataTransferStatus;
+}
+
+export interface DiagnosticsStatusNotificationRequest {
+  status: DiagnosticsStatusNotificationStatus;
+}
+
+export interface DiagnosticsStatusNotificationResponse {
+}
+
+export interface FirmwareStatusNotificationRequest {
+  status: FirmwareStatusNotificationStatus;
+}
+
+export interface FirmwareStatusNotificationResponse {
+}
+
+export interface GetCompositeScheduleRequest {
+  chargingRateUnit?: GetCompositeScheduleChargingRateUnit;
+  connectorId: number;
+  duration: number;
+}
+
+export interface GetCompositeScheduleResponse {
+  chargingSchedule?: ChargingSchedule;
+  connectorId?: number;
+  scheduleStart?: string;
+  status: GetCompositeScheduleStatus;
+}
+
+export interface GetConfigurationRequest {
+  key?: string[];
+}
+
+export interface GetConfigurationResponse {
+  configurationKey?: ConfigurationKey[];
+  unknownKey?: string[];
+}
+
+export interface GetDiagnosticsRequest {
+  location: string;
+  retries?: number;
+  retryInterval?: number;
+  startTime?: string;
+  stopTime?: string;
+}
+
+export interface GetDiagnosticsResponse {
+  fileName?: string;
+}
+
+export interface GetLocalListVersionRequest {
+}
+
+export interface GetLocalListVersionResponse {
+  listVersion: number;
+}
+
+export interface HeartbeatRequest {
+}
+
+export interface HeartbeatResponse {
+  currentTime: string;
+}
+
+export interface IdTagInfo {
+  expiryDate?: string;
+  parentIdTag?: string;
+  status: IdTagInfoStatus;
+}
+
+export interface LocalAuthorizationList {
+  idTag: string;
+  idTagInfo?: IdTagInfo;
+}
+
+export interface MeterValue {
+  sampledValue: SampledValue[];
+  timestamp: string;
+}
+
+export interface MeterValuesRequest {
+  connectorId: number;
+  meterValue: MeterValue[];
+  transactionId?: number;
+}
+
+export interface MeterValuesResponse {
+}
+
+export interface RemoteStartTransactionRequest {
+  chargingProfile?: ChargingProfile;
+  connectorId?: number;
+  idTag: string;
+}
+
+export interface RemoteStartTransactionResponse {
+  status: RemoteStartTransactionStatus;
+}
+
+export interface RemoteStopTransactionRequest {
+  transactionId: number;
+}
+
+export interface RemoteStopTransactionResponse {
+  status: RemoteStopTransactionStatus;
+}
+
+export interface ReserveNowRequest {
+  connectorId: number;
+  expiryDate: string;
+  idTag: string;
+  parentIdTag?: string;
+  reservationId: number;
+}
+
+export interface ReserveNowResponse {
+  status: ReserveNowStatus;
+}
+
+export interface ResetRequest {
+  type: ResetType;
+}
+
+export interface ResetResponse {
+  status: ResetStatus;
+}
+
+export interface SampledValue {
+  context?: SampledValueContext;
+  format?: SampledValueFormat;
+  location?: SampledValueLocation;
+  measurand?: SampledValueMeasurand;
+  phase?: SampledValuePhase;
+  unit?: SampledValueUnit;
+  value: string;
+}
+
+export interface SendLocalListRequest {
+  listVersion: number;
+  localAuthorizationList?: LocalAuthorizationList[];
+  updateType: SendLocalListUpdateType;
+}
+
+export interface SendLocalListResponse {
+  status: SendLocalListStatus;
+}
+
+export interface SetChargingProfileRequest {
+  connectorId: number;
+  csChargingProfiles: CsChargingProfiles;
+}
+
+export interface SetChargingProfileResponse {
+  status: SetChargingProfileStatus;
+}
+
+export interface StartTransactionRequest {
+  connectorId: number;
+  idTag: string;
+  meterStart: number;
+  reservationId?: number;
+  timestamp: string;
+}
+
+export interface StartTransactionResponse {
+  idTagInfo: IdTagInfo;
+  transactionId: number;
+}
+
+export interface StatusNotificationRequest {
+  connectorId: number;
+  errorCode: StatusNotificationErrorCode;
+  info?: string;
+  status: StatusNotificationStatus;
+  timestamp?: string;
+  vendorErrorCode?: string;
+  vendorId?: string;
+}
+
+export interface StatusNotificationResponse {
+}
+
+export interface StopTransactionRequest {
+  idTag?: string;
+  meterStop: number;
+  reason?: StopTransactionReason;
+  timestamp: string;
+  transactionData?: TransactionData[];
+  transactionId: number;
+}
+
+export interface StopTransactionResponse {
+  idTagInfo?: IdTagInfo;
+}
+
+export interface TransactionData {
+  sampledValue: SampledValue[];
+  timestamp: string;
+}
+
+export interface TriggerMessageRequest {
+  connectorId?: number;
+  requestedMessage: TriggerMessageRequestedMessage;
+}
+
+export interface TriggerMessageResponse {
+  status: TriggerMessageStatus;
+}
+
+export interface UnlockConnectorRequest {
+  connectorId: number;
+}
+
+export interface UnlockConnectorResponse {
+  status: UnlockConnectorStatus;
+}
+
+export interface UpdateFirmwareRequest {
+  location: string;
+  retries?: number;
+  retrieveDate: string;
+  retryInterval?: number;
+}
+
+export interface UpdateFirmwareResponse {
+}
+
+export type BootNotificationStatus = "Accepted" | "Pending" | "Rejected";
+
+export type CancelReservationStatus = "Accepted" | "Rejected";
+
+export type ChangeAvailabilityStatus = "Accepted" | "Rejected" | "Scheduled";
+
+export type ChangeAvailabilityType = "Inoperative" | "Operative";
+
+export type ChangeConfigurationStatus = "Accepted" | "Rejected" | "RebootRequired" | "NotSupported";
+
+export type ChargingProfileKind = "Absolute" | "Recurring" | "Relative";
+
+export type ChargingProfilePurpose = "ChargePointMaxProfile" | "TxDefaultProfile" | "TxProfile";
+
+export type ChargingProfileRecurrencyKind = "Daily" | "Weekly";
+
+export type ChargingScheduleChargingRateUnit = "A" | "W";
+
+export type ClearCacheStatus = "Accepted" | "Rejected";
+
+export type ClearChargingProfileChargingProfilePurpose = "ChargePointMaxProfile" | "TxDefaultProfile" | "TxProfile";
+
+export type ClearChargingProfileStatus = "Accepted" | "Unknown";
+
+export type CsChargingProfilesChargingProfileKind = "Absolute" | "Recurring" | "Relative";
+
+export type CsChargingProfilesChargingProfilePurpose = "ChargePointMaxProfile" | "TxDefaultProfile" | "TxProfile";
+
+export type CsChargingProfilesRecurrencyKind = "Daily" | "Weekly";
+
+export type DataTransferStatus = "Accepted" | "Rejected" | "UnknownMessageId" | "UnknownVendorId";
+
+export type DiagnosticsStatusNotificationStatus = "Idle" | "Uploaded" | "UploadFailed" | "Uploading";
+
+export type FirmwareStatusNotificationStatus = "Downloaded" | "DownloadFailed" | "Downloading" | "Idle" | "InstallationFailed" | "Installing" | "Installed";
+
+export type GetCompositeScheduleChargingRateUnit = "A" | "W";
+
+export type GetCompositeScheduleStatus = "Accepted" | "Rejected";
+
+export type IdTagInfoStatus = "Accepted" | "Blocked" | "Expired" | "Invalid" | "ConcurrentTx";
+
+export type RemoteStartTransactionStatus = "Accepted" | "Rejected";
+
+export type RemoteStopTransactionStatus = "Accepted" | "Rejected";
+
+export type ReserveNowStatus = "Accepted" | "Faulted" | "Occupied" | "Rejected" | "Unavailable";
+
+export type ResetStatus = "Accepted" | "Rejected";
+
+export type ResetType = "Hard" | "Soft";
+
+export type SampledValueContext = "Interruption.Begin" | "Interruption.End" | "Sample.Clock" | "Sample.Periodic" | "Transaction.Begin" | "Transaction.End" | "Trigger" | "Other";
+
+export type SampledValueFormat = "Raw" | "SignedData";
+
+export type SampledValueLocation = "Cable" | "EV" | "Inlet" | "Outlet" | "Body";
+
+export type SampledValueMeasurand = "Energy.Active.Export.Register" | "Energy.Active.Import.Register" | "Energy.Reactive.Export.Register" | "Energy.Reactive.Import.Register" | "Energy.Active.Export.Interval" | "Energy.Active.Import.Interval" | "Energy.Reactive.Export.Interval" | "Energy.Reactive.Import.Interval" | "Power.Active.Export" | "Power.Active.Import" | "Power.Offered" | "Power.Reactive.Export" | "Power.Reactive.Import" | "Power.Factor" | "Current.Import" | "Current.Export" | "Current.Offered" | "Voltage" | "Frequency" | "Temperature" | "SoC" | "RPM";
+
+export type SampledValuePhase = "L1" | "L2" | "L3" | "N" | "L1-N" | "L2-N" | "L3-N" | "L1-L2" | "L2-L3" | "L3-L1";
+
+export type SampledValueUnit = "Wh" | "kWh" | "varh" | "kvarh" | "W" | "kW" | "VA" | "kVA" | "var" | "kvar" | "A" | "V" | "K" | "Celcius" | "Celsius" | "Fahrenheit" | "Percent";
+
+export type SendLocalListStatus = "Accepted" | "Failed" | "NotSupported" | "VersionMismatch";
+
+export type SendLocalListUpdateType = "Differential" | "Full";
+
+export type SetChargingProfileStatus = "Accepted" | "Rejected" | "NotSupported";
+
+export type StatusNotificationErrorCode = "ConnectorLockFailure" | "EVCommunicationError" | "GroundFailure" | "HighTemperature" | "InternalError" | "LocalListConflict" | "NoError" | "OtherError" | "OverCurrentFailure" | "PowerMeterFailure" | "PowerSwitchFailure" | "ReaderFailure" | "ResetFailure" | "UnderVoltage" | "OverVoltage" | "WeakSignal";
+
+export type StatusNotificationStatus = "Available" | "Preparing" | "Charging" | "SuspendedEVSE" | "SuspendedEV" | "Finishing" | "Reserved" | "Unavailable" | "Faulted";
+
+export type StopTransactionReason = "EmergencyStop" | "EVDisconnected" | "HardReset" | "Local" | "Other" | "PowerLoss" | "Reboot" | "Remote" | "SoftReset" | "UnlockCommand" | "DeAuthorized";
+
+export type TriggerMessageRequestedMessage = "BootNotification" | "DiagnosticsStatusNotification" | "FirmwareStatusNotification" | "Heartbeat" | "MeterValues" | "StatusNotification";
+
+export type TriggerMessageStatus = "Accepted" | "Rejected" | "NotImplemented";
+
+export type UnlockConnectorStatus = "Unlocked" | "UnlockFailed" | "NotSupported";
+
+/** The action of a `Call`. */
+export type Action = "Authorize" | "BootNotification" | "CancelReservation" | "ChangeAvailability" | "ChangeConfiguration" | "ClearCache" | "ClearChargingProfile" | "DataTransfer" | "DiagnosticsStatusNotification" | "FirmwareStatusNotification" | "GetCompositeSchedule" | "GetConfiguration" | "GetDiagnostics" | "GetLocalListVersion" | "Heartbeat" | "MeterValues" | "RemoteStartTransaction" | "RemoteStopTransaction" | "ReserveNow" | "Reset" | "SendLocalList" | "SetChargingProfile" | "StartTransaction" | "StatusNotification" | "StopTransaction" | "TriggerMessage" | "UnlockConnector" | "UpdateFirmware";
+
+/** The request payload of each action. */
+export interface Requests {
+  Authorize: AuthorizeRequest;
+  BootNotification: BootNotificationRequest;
+  CancelReservation: CancelReservationRequest;
+  ChangeAvailability: ChangeAvailabilityRequest;
+  ChangeConfiguration: ChangeConfigurationRequest;
+  ClearCache: ClearCacheRequest;
+  ClearChargingProfile: ClearChargingProfileRequest;
+  DataTransfer: DataTransferRequest;
+  DiagnosticsStatusNotification: DiagnosticsStatusNotificationRequest;
+  FirmwareStatusNotification: FirmwareStatusNotificationRequest;
+  GetCompositeSchedule: GetCompositeScheduleRequest;
+  GetConfiguration: GetConfigurationRequest;
+  GetDiagnostics: GetDiagnosticsRequest;
+  GetLocalListVersion: GetLocalListVersionRequest;
+  Heartbeat: HeartbeatRequest;
+  MeterValues: MeterValuesRequest;
+  RemoteStartTransaction: RemoteStartTransactionRequest;
+  RemoteStopTransaction: RemoteStopTransactionRequest;
+  ReserveNow: ReserveNowRequest;
+  Reset: ResetRequest;
+  SendLocalList: SendLocalListRequest;
+  SetChargingProfile: SetChargingProfileRequest;
+  StartTransaction: StartTransactionRequest;
+  StatusNotification: StatusNotificationRequest;
+  StopTransaction: StopTransactionRequest;
+  TriggerMessage: TriggerMessageRequest;
+  UnlockConnector: UnlockConnectorRequest;
+  UpdateFirmware: UpdateFirmwareRequest;
+}
+
+/** The response payload of each action. */
+export interface Responses {
+  Authorize: AuthorizeResponse;
+  BootNotification: BootNotificationResponse;
+  CancelReservation: CancelReservationResponse;
+  ChangeAvailability: ChangeAvailabilityResponse;
+  ChangeConfiguration: ChangeConfigurationResponse;
+  ClearCache: ClearCacheResponse;
+  ClearChargingProfile: ClearChargingProfileResponse;
+  DataTransfer: DataTransferResponse;
+  DiagnosticsStatusNotification: DiagnosticsStatusNotificationResponse;
+  FirmwareStatusNotification: FirmwareStatusNotificationResponse;
+  GetCompositeSchedule: GetCompositeScheduleResponse;
+  GetConfiguration: GetConfigurationResponse;
+  GetDiagnostics: GetDiagnosticsResponse;
+  GetLocalListVersion: GetLocalListVersionResponse;
+  Heartbeat: HeartbeatResponse;
+  MeterValues: MeterValuesResponse;
+  RemoteStartTransaction: RemoteStartTransactionResponse;
+  RemoteStopTransaction: RemoteStopTransactionResponse;
+  ReserveNow: ReserveNowResponse;
+  Reset: ResetResponse;
+  SendLocalList: SendLocalListResponse;
+  SetChargingProfile: SetChargingProfileResponse;
+  StartTransaction: StartTransactionResponse;
+  StatusNotification: StatusNotificationResponse;
+  StopTransaction: StopTransactionResponse;
+  TriggerMessage: TriggerMessageResponse;
+  UnlockConnector: UnlockConnectorResponse;
+  UpdateFirmware: UpdateFirmwareResponse;
+}
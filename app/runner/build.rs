@@ -1,3 +1,16 @@
+use std::{fs, path::Path};
+
+/// The TypeScript definitions of the OCPP payloads, for the frontend.
+const OCPP_TYPES: &str = "../ocppx/types/ocpp-1.6.d.ts";
+
 fn main() {
+  // Only written when they change, not to trigger a reload of the frontend.
+  let ocpp_types = Path::new(OCPP_TYPES);
+
+  if fs::read_to_string(ocpp_types).ok().as_deref() != Some(ocppx_types::v1_6::TYPESCRIPT) {
+    fs::write(ocpp_types, ocppx_types::v1_6::TYPESCRIPT)
+      .expect("cannot write the TypeScript definitions");
+  }
+
   tauri_build::build()
 }
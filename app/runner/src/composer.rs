@@ -0,0 +1,89 @@
+use crate::AppServer;
+use ocppx_rpc::ErrorCode;
+use ocppx_types::v1_6::{self, Action};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::State;
+use thiserror::Error;
+
+/// An action, with the JSON schemas of its payloads, to render a form.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionSchema {
+    pub action: Action,
+    pub request_schema: Value,
+    pub response_schema: Value,
+}
+
+/// Why a composed message has not been sent, or has been refused by the
+/// Charge Point.
+#[derive(Error, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SendError {
+    #[error("unknown action `{action}`")]
+    UnknownAction { action: String },
+
+    #[error("the payload does not match the schema of `{action}`")]
+    InvalidPayload {
+        action: Action,
+        violations: Vec<String>,
+    },
+
+    #[error("the Charge Point responded with an error: `{error_code}`")]
+    #[serde(rename_all = "camelCase")]
+    CallError {
+        error_code: ErrorCode,
+        error_description: String,
+        error_details: Value,
+    },
+
+    #[error("{message}")]
+    Server { message: String },
+}
+
+/// All the OCPP 1.6 actions, with their schemas.
+#[tauri::command]
+pub fn actions() -> Vec<ActionSchema> {
+    Action::iter()
+        .map(|action| ActionSchema {
+            action,
+            request_schema: serde_json::from_str(action.request_schema())
+                .expect("the schemas are valid JSON"),
+            response_schema: serde_json::from_str(action.response_schema())
+                .expect("the schemas are valid JSON"),
+        })
+        .collect()
+}
+
+/// Validate a composed `Call`, send it to `charge_point_id`, and return the
+/// payload of its response. Both are logged by the `CallLog` of the server.
+#[tauri::command]
+pub async fn send_message(
+    server: State<'_, AppServer>,
+    charge_point_id: String,
+    action: String,
+    payload: Value,
+) -> Result<Value, SendError> {
+    let action = action
+        .parse::<Action>()
+        .map_err(|_| SendError::UnknownAction { action })?;
+
+    v1_6::validate(action, &payload).map_err(|error| SendError::InvalidPayload {
+        action,
+        violations: error.violations.iter().map(ToString::to_string).collect(),
+    })?;
+
+    server
+        .call(&charge_point_id, action.as_str(), &payload)
+        .await
+        .map_err(|error| match error {
+            ocppx_server::Error::CallError(call_error) => SendError::CallError {
+                error_code: call_error.error_code,
+                error_description: call_error.error_description,
+                error_details: call_error.error_details,
+            },
+            error => SendError::Server {
+                message: error.to_string(),
+            },
+        })
+}
@@ -0,0 +1,198 @@
+use crate::store::{LoggedMessage, Store};
+use chrono::Utc;
+use ocppx_rpc::{Call, CallError, CallResult, Direction, Message};
+use ocppx_server::{CsmsHandler, Interceptor};
+use ocppx_types::v1_6::StatusNotificationRequest;
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+
+/// The events pushed to the frontend.
+pub mod events {
+    /// A Charge Point has connected, with its [`ChargePoint`](crate::store::ChargePoint).
+    pub const CHARGE_POINT_CONNECTED: &str = "charge-point-connected";
+    /// A Charge Point has disconnected, with its ID.
+    pub const CHARGE_POINT_DISCONNECTED: &str = "charge-point-disconnected";
+    /// A connector has changed, with a [`ConnectorStatus`](super::ConnectorStatus).
+    pub const CONNECTOR_STATUS: &str = "connector-status";
+    /// A message has been exchanged, with its [`LoggedMessage`](crate::store::LoggedMessage).
+    pub const MESSAGE: &str = "message";
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectorStatus {
+    pub charge_point_id: String,
+    pub connector: crate::store::Connector,
+}
+
+/// A handler feeding the [`Store`] with the traffic of the Charge Points,
+/// and pushing the changes to the frontend, before delegating to `H`.
+pub struct Dashboard<H> {
+    handler: H,
+    store: Arc<Store>,
+    app: AppHandle,
+}
+
+impl<H> Dashboard<H> {
+    pub fn new(handler: H, store: Arc<Store>, app: AppHandle) -> Self {
+        Self {
+            handler,
+            store,
+            app,
+        }
+    }
+
+    fn emit<S>(&self, event: &str, payload: S)
+    where
+        S: Serialize + Clone,
+    {
+        emit(&self.app, event, payload);
+    }
+
+    fn log(&self, charge_point_id: &str, direction: Direction, action: &str, message: Message) {
+        log(
+            &self.store,
+            &self.app,
+            charge_point_id,
+            direction,
+            action,
+            message,
+        );
+    }
+}
+
+/// An interceptor feeding the [`Store`] with the `Call`s sent by the
+/// Central System, e.g. by the composer, and with their responses: the
+/// other messages are logged by [`Dashboard`].
+pub struct CallLog {
+    store: Arc<Store>,
+    app: AppHandle,
+}
+
+impl CallLog {
+    pub fn new(store: Arc<Store>, app: AppHandle) -> Self {
+        Self { store, app }
+    }
+}
+
+impl Interceptor for CallLog {
+    fn incoming(&self, charge_point_id: &str, action: &str, message: &mut Message) {
+        if !matches!(message, Message::Call(_)) {
+            log(
+                &self.store,
+                &self.app,
+                charge_point_id,
+                Direction::Incoming,
+                action,
+                message.clone(),
+            );
+        }
+    }
+
+    fn outgoing(&self, charge_point_id: &str, action: &str, message: &mut Message) {
+        if matches!(message, Message::Call(_)) {
+            log(
+                &self.store,
+                &self.app,
+                charge_point_id,
+                Direction::Outgoing,
+                action,
+                message.clone(),
+            );
+        }
+    }
+}
+
+fn emit<S>(app: &AppHandle, event: &str, payload: S)
+where
+    S: Serialize + Clone,
+{
+    // No window to notify is not an error: the store is queried when a
+    // window opens.
+    let _ = app.emit_all(event, payload);
+}
+
+fn log(
+    store: &Store,
+    app: &AppHandle,
+    charge_point_id: &str,
+    direction: Direction,
+    action: &str,
+    message: Message,
+) {
+    let message = LoggedMessage {
+        timestamp: Utc::now(),
+        charge_point_id: charge_point_id.to_owned(),
+        direction,
+        action: action.to_owned(),
+        message,
+    };
+
+    store.log(message.clone());
+    emit(app, events::MESSAGE, message);
+}
+
+impl<H> CsmsHandler for Dashboard<H>
+where
+    H: CsmsHandler,
+{
+    async fn handle_call(
+        &self,
+        charge_point_id: &str,
+        call: Call,
+    ) -> Result<CallResult, CallError> {
+        let action = call.action.clone();
+        self.log(
+            charge_point_id,
+            Direction::Incoming,
+            &action,
+            Message::Call(call.clone()),
+        );
+
+        if action == "StatusNotification" {
+            if let Ok(request) =
+                serde_json::from_value::<StatusNotificationRequest>(call.payload.clone())
+            {
+                let connector = self.store.status_notification(charge_point_id, &request);
+                self.emit(
+                    events::CONNECTOR_STATUS,
+                    ConnectorStatus {
+                        charge_point_id: charge_point_id.to_owned(),
+                        connector,
+                    },
+                );
+            }
+        }
+
+        let response = self.handler.handle_call(charge_point_id, call).await;
+        self.log(
+            charge_point_id,
+            Direction::Outgoing,
+            &action,
+            match &response {
+                Ok(call_result) => Message::CallResult(call_result.clone()),
+                Err(call_error) => Message::CallError(call_error.clone()),
+            },
+        );
+
+        response
+    }
+
+    async fn connected(&self, charge_point_id: &str) {
+        let charge_point = self.store.connected(charge_point_id);
+        self.emit(events::CHARGE_POINT_CONNECTED, charge_point);
+
+        self.handler.connected(charge_point_id).await
+    }
+
+    async fn disconnected(&self, charge_point_id: &str) {
+        self.store.disconnected(charge_point_id);
+        self.emit(
+            events::CHARGE_POINT_DISCONNECTED,
+            charge_point_id.to_owned(),
+        );
+
+        self.handler.disconnected(charge_point_id).await
+    }
+}
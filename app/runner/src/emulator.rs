@@ -0,0 +1,316 @@
+use ocppx_simulator::{ConnectorStatus, Scenario, ScenarioProgress, Simulator, SimulatorConfig};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+use tauri::{AppHandle, Manager, State};
+use thiserror::Error;
+use tokio::sync::RwLock;
+
+/// A step of a scenario has started or is done, with a
+/// [`ScenarioProgressEvent`].
+pub const SCENARIO_PROGRESS: &str = "scenario-progress";
+
+/// The Charge Points emulated by the app, with the simulator: they can
+/// connect to the Central System of the app, or to any other one.
+pub struct Emulator {
+    /// The Central System of the app, when the configuration has no URL.
+    default_csms_url: String,
+    charge_points: Mutex<BTreeMap<String, Arc<EmulatedChargePoint>>>,
+}
+
+struct EmulatedChargePoint {
+    config: SimulatorConfig,
+    /// `None` when disconnected. Locked for writing while connecting and
+    /// disconnecting.
+    simulator: RwLock<Option<Simulator>>,
+}
+
+impl Emulator {
+    pub fn new(default_csms_url: String) -> Self {
+        Self {
+            default_csms_url,
+            charge_points: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn charge_point(&self, id: &str) -> Result<Arc<EmulatedChargePoint>, EmulatorError> {
+        self.charge_points
+            .lock()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| EmulatorError::UnknownChargePoint { id: id.to_owned() })
+    }
+}
+
+/// The configuration of an emulated Charge Point, from the frontend.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChargePointConfig {
+    pub id: String,
+    /// The Central System of the app when omitted.
+    pub csms_url: Option<String>,
+    pub vendor: Option<String>,
+    pub model: Option<String>,
+    pub firmware_version: Option<String>,
+    /// `1` when omitted.
+    pub connectors: Option<i32>,
+    /// The power delivered by a charging connector, in W.
+    pub charging_power: Option<u32>,
+}
+
+/// An emulated Charge Point, for the frontend.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmulatedChargePointState {
+    pub id: String,
+    pub csms_url: String,
+    /// `None` while connecting or disconnecting.
+    pub connected: Option<bool>,
+    pub connectors: Vec<EmulatedConnector>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmulatedConnector {
+    pub connector_id: i32,
+    pub status: ConnectorStatus,
+    pub transaction_id: Option<i32>,
+}
+
+/// The progress of a scenario run by an emulated Charge Point.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScenarioProgressEvent {
+    pub charge_point_id: String,
+    pub scenario: String,
+    pub progress: ScenarioProgress,
+}
+
+/// Why a command of the emulator has failed.
+#[derive(Error, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum EmulatorError {
+    #[error("unknown Charge Point `{id}`")]
+    UnknownChargePoint { id: String },
+
+    #[error("the Charge Point `{id}` already exists")]
+    AlreadyExists { id: String },
+
+    #[error("the Charge Point `{id}` is already connected")]
+    AlreadyConnected { id: String },
+
+    #[error("the Charge Point `{id}` is not connected")]
+    NotConnected { id: String },
+
+    #[error("{message}")]
+    Simulator { message: String },
+}
+
+impl From<ocppx_simulator::Error> for EmulatorError {
+    fn from(error: ocppx_simulator::Error) -> Self {
+        Self::Simulator {
+            message: error.to_string(),
+        }
+    }
+}
+
+/// The emulated Charge Points, sorted by ID.
+#[tauri::command]
+pub fn emulated_charge_points(emulator: State<'_, Emulator>) -> Vec<EmulatedChargePointState> {
+    emulator
+        .charge_points
+        .lock()
+        .unwrap()
+        .values()
+        .map(|charge_point| {
+            let simulator = charge_point.simulator.try_read().ok();
+            let connectors = simulator
+                .as_ref()
+                .and_then(|simulator| simulator.as_ref())
+                .map(Simulator::connectors)
+                .unwrap_or_default();
+
+            EmulatedChargePointState {
+                id: charge_point.config.charge_point_id.clone(),
+                csms_url: charge_point.config.csms_url.clone(),
+                connected: simulator.map(|simulator| simulator.is_some()),
+                connectors: connectors
+                    .into_iter()
+                    .map(|connector| EmulatedConnector {
+                        connector_id: connector.id,
+                        status: connector.status,
+                        transaction_id: connector.transaction.map(|transaction| transaction.id),
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Create an emulated Charge Point, disconnected.
+#[tauri::command]
+pub fn create_charge_point(
+    emulator: State<'_, Emulator>,
+    config: ChargePointConfig,
+) -> Result<(), EmulatorError> {
+    let mut simulator_config = SimulatorConfig::new(
+        config
+            .csms_url
+            .unwrap_or_else(|| emulator.default_csms_url.clone()),
+        config.id.clone(),
+    );
+    simulator_config.vendor = config.vendor.unwrap_or(simulator_config.vendor);
+    simulator_config.model = config.model.unwrap_or(simulator_config.model);
+    simulator_config.firmware_version = config.firmware_version;
+    simulator_config.connectors = config.connectors.unwrap_or(simulator_config.connectors);
+    simulator_config.charging_power = config
+        .charging_power
+        .unwrap_or(simulator_config.charging_power);
+
+    let mut charge_points = emulator.charge_points.lock().unwrap();
+
+    if charge_points.contains_key(&config.id) {
+        return Err(EmulatorError::AlreadyExists { id: config.id });
+    }
+
+    charge_points.insert(
+        config.id,
+        Arc::new(EmulatedChargePoint {
+            config: simulator_config,
+            simulator: RwLock::new(None),
+        }),
+    );
+
+    Ok(())
+}
+
+/// Connect an emulated Charge Point, and boot it.
+#[tauri::command]
+pub async fn connect(emulator: State<'_, Emulator>, id: String) -> Result<(), EmulatorError> {
+    let charge_point = emulator.charge_point(&id)?;
+    let mut simulator = charge_point.simulator.write().await;
+
+    if simulator.is_some() {
+        return Err(EmulatorError::AlreadyConnected { id });
+    }
+
+    *simulator = Some(Simulator::start(charge_point.config.clone()).await?);
+
+    Ok(())
+}
+
+/// Disconnect an emulated Charge Point. Its transactions are lost.
+#[tauri::command]
+pub async fn disconnect(emulator: State<'_, Emulator>, id: String) -> Result<(), EmulatorError> {
+    let charge_point = emulator.charge_point(&id)?;
+    let simulator = charge_point.simulator.write().await.take();
+
+    simulator
+        .ok_or(EmulatorError::NotConnected { id })?
+        .stop()
+        .await?;
+
+    Ok(())
+}
+
+/// Plug a cable in a connector of an emulated Charge Point.
+#[tauri::command]
+pub async fn plug_in(
+    emulator: State<'_, Emulator>,
+    id: String,
+    connector: i32,
+) -> Result<(), EmulatorError> {
+    let charge_point = emulator.charge_point(&id)?;
+    let simulator = charge_point.simulator.read().await;
+
+    Ok(connected(&simulator, id)?.plug_in(connector).await?)
+}
+
+/// Unplug the cable from a connector of an emulated Charge Point.
+#[tauri::command]
+pub async fn unplug(
+    emulator: State<'_, Emulator>,
+    id: String,
+    connector: i32,
+) -> Result<(), EmulatorError> {
+    let charge_point = emulator.charge_point(&id)?;
+    let simulator = charge_point.simulator.read().await;
+
+    Ok(connected(&simulator, id)?.unplug(connector).await?)
+}
+
+/// Start a transaction on a plugged connector of an emulated Charge Point,
+/// and return its ID.
+#[tauri::command]
+pub async fn start_charging(
+    emulator: State<'_, Emulator>,
+    id: String,
+    connector: i32,
+    id_tag: String,
+) -> Result<i32, EmulatorError> {
+    let charge_point = emulator.charge_point(&id)?;
+    let simulator = charge_point.simulator.read().await;
+
+    Ok(connected(&simulator, id)?
+        .start_transaction(connector, &id_tag)
+        .await?)
+}
+
+/// Stop the transaction on a connector of an emulated Charge Point.
+#[tauri::command]
+pub async fn stop_charging(
+    emulator: State<'_, Emulator>,
+    id: String,
+    connector: i32,
+) -> Result<(), EmulatorError> {
+    let charge_point = emulator.charge_point(&id)?;
+    let simulator = charge_point.simulator.read().await;
+
+    Ok(connected(&simulator, id)?
+        .stop_transaction(connector)
+        .await?)
+}
+
+/// Run a scenario on an emulated Charge Point, which is connected, and
+/// then booted, first if needed. The progress is pushed with
+/// [`SCENARIO_PROGRESS`] events. The Charge Point is disconnected only at
+/// the end of the scenario.
+#[tauri::command]
+pub async fn run_scenario(
+    app: AppHandle,
+    emulator: State<'_, Emulator>,
+    id: String,
+    scenario: Scenario,
+) -> Result<(), EmulatorError> {
+    let charge_point = emulator.charge_point(&id)?;
+    let simulator = {
+        let mut simulator = charge_point.simulator.write().await;
+
+        if simulator.is_none() {
+            *simulator = Some(Simulator::start(charge_point.config.clone()).await?);
+        }
+
+        simulator.downgrade()
+    };
+
+    Ok(connected(&simulator, id.clone())?
+        .run_scenario(&scenario, |progress| {
+            // No window to notify is not an error: the scenario goes on.
+            let _ = app.emit_all(
+                SCENARIO_PROGRESS,
+                ScenarioProgressEvent {
+                    charge_point_id: id.clone(),
+                    scenario: scenario.name.clone(),
+                    progress,
+                },
+            );
+        })
+        .await?)
+}
+
+fn connected(simulator: &Option<Simulator>, id: String) -> Result<&Simulator, EmulatorError> {
+    simulator.as_ref().ok_or(EmulatorError::NotConnected { id })
+}
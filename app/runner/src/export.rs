@@ -0,0 +1,95 @@
+use crate::store::LoggedMessage;
+use chrono::SecondsFormat;
+use serde::Deserialize;
+use std::fmt::Write;
+
+/// The formats the messages are exported to, see [`export`].
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// An array of [`LoggedMessage`]s.
+    Json,
+    /// A row per message, with the message in its wire format in the last
+    /// column.
+    Csv,
+}
+
+/// Export `messages` to `format`.
+pub fn export(messages: &[LoggedMessage], format: ExportFormat) -> Result<String, String> {
+    match format {
+        ExportFormat::Json => {
+            serde_json::to_string_pretty(messages).map_err(|error| error.to_string())
+        }
+        ExportFormat::Csv => {
+            let mut csv = String::from("timestamp,chargePointId,direction,action,message\r\n");
+
+            for message in messages {
+                let wire =
+                    serde_json::to_string(&message.message).map_err(|error| error.to_string())?;
+
+                let _ = write!(
+                    csv,
+                    "{},{},{},{},{}\r\n",
+                    message
+                        .timestamp
+                        .to_rfc3339_opts(SecondsFormat::Millis, true),
+                    csv_field(&message.charge_point_id),
+                    message.direction.as_str(),
+                    csv_field(&message.action),
+                    csv_field(&wire),
+                );
+            }
+
+            Ok(csv)
+        }
+    }
+}
+
+/// `value` as a field of RFC 4180: quoted if needed, with the quotes
+/// doubled.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use ocppx_rpc::{Call, Direction, Message};
+    use serde_json::json;
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("CP001"), "CP001");
+        assert_eq!(csv_field("CP,001"), "\"CP,001\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("line\nbreak"), "\"line\nbreak\"");
+        assert_eq!(csv_field("line\r\nbreak"), "\"line\r\nbreak\"");
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let messages = [LoggedMessage {
+            timestamp: Utc.timestamp_opt(0, 0).unwrap(),
+            charge_point_id: "CP,001".to_owned(),
+            direction: Direction::Incoming,
+            action: "DataTransfer".to_owned(),
+            message: Message::Call(Call {
+                unique_id: "1".to_owned(),
+                action: "DataTransfer".to_owned(),
+                payload: json!({"vendorId": "ACME", "data": "a\nb"}),
+            }),
+        }];
+
+        assert_eq!(
+            export(&messages, ExportFormat::Csv).unwrap(),
+            "timestamp,chargePointId,direction,action,message\r\n\
+             1970-01-01T00:00:00.000Z,\"CP,001\",incoming,DataTransfer,\
+             \"[2,\"\"1\"\",\"\"DataTransfer\"\",{\"\"data\"\":\"\"a\\nb\"\",\"\"vendorId\"\":\"\"ACME\"\"}]\"\r\n"
+        );
+    }
+}
@@ -3,19 +3,144 @@
     windows_subsystem = "windows"
 )]
 
+mod composer;
+mod dashboard;
+mod emulator;
+mod export;
+mod responder;
+mod store;
+
+use dashboard::{CallLog, Dashboard};
+use emulator::Emulator;
+use export::ExportFormat;
+use ocppx_server::{
+    HandlerService, Interceptors, Middleware, Server, ServerConfig, StorageLayer, Storing,
+};
+use ocppx_store::{SqliteStorage, Storage, TransactionRecord};
+use responder::Responder;
+use std::{env, fs, path::PathBuf, sync::Arc};
+use store::{ChargePoint, LoggedMessage, MessageFilter, Store};
+use tauri::{Manager, State};
+
+/// The Central System the Charge Points connect to.
+pub type AppServer =
+    Server<Dashboard<Middleware<Responder, Storing<SqliteStorage, HandlerService<Responder>>>>>;
+
+/// The address the Charge Points connect to, unless `OCPPX_LISTEN` is set.
+const DEFAULT_ADDRESS: &str = "0.0.0.0:9000";
+
+/// The Charge Points, with their connectors.
 #[tauri::command]
-fn greet(name: &str) -> String {
-    format!("Hello, {}!", name)
+fn charge_points(store: State<'_, Arc<Store>>) -> Vec<ChargePoint> {
+    store.charge_points()
+}
+
+/// The last messages exchanged with the Charge Points, all of them if
+/// there is no filter.
+#[tauri::command]
+fn messages(store: State<'_, Arc<Store>>, filter: Option<MessageFilter>) -> Vec<LoggedMessage> {
+    store.messages(&filter.unwrap_or_default())
+}
+
+/// Export the last messages matching `filter` to the file at `path`, and
+/// return how many they are.
+#[tauri::command]
+async fn export_messages(
+    store: State<'_, Arc<Store>>,
+    filter: Option<MessageFilter>,
+    format: ExportFormat,
+    path: PathBuf,
+) -> Result<usize, String> {
+    let messages = store.messages(&filter.unwrap_or_default());
+
+    fs::write(&path, export::export(&messages, format)?)
+        .map_err(|error| format!("cannot write `{}`: {error}", path.display()))?;
+
+    Ok(messages.len())
 }
 
+/// The transactions of a Charge Point, from the storage: they survive the
+/// restarts of the app.
 #[tauri::command]
-fn test() -> String {
-    greet(&format!("{:?}", ocppx_types::v1_6::Status::Accepted))
+async fn transactions(
+    storage: State<'_, Arc<SqliteStorage>>,
+    charge_point_id: String,
+) -> Result<Vec<TransactionRecord>, String> {
+    storage
+        .transactions(&charge_point_id)
+        .await
+        .map_err(|error| error.to_string())
+}
+
+/// The URL of the Central System listening on `address`, for the emulated
+/// Charge Points.
+fn local_csms_url(address: &str) -> String {
+    let port = address.rsplit(':').next().unwrap_or_default();
+
+    format!("ws://127.0.0.1:{port}")
+}
+
+/// The journal of the storage, unless `OCPPX_STORE` is set.
+fn storage_path(app: &tauri::App) -> PathBuf {
+    env::var_os("OCPPX_STORE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            let directory = app
+                .path_resolver()
+                .app_data_dir()
+                .unwrap_or_else(env::temp_dir);
+            let _ = fs::create_dir_all(&directory);
+
+            directory.join("store.sqlite")
+        })
 }
 
 fn main() {
     tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![greet, test])
+        .setup(|app| {
+            let store = Arc::new(Store::default());
+            app.manage(store.clone());
+
+            let address = env::var("OCPPX_LISTEN").unwrap_or_else(|_| DEFAULT_ADDRESS.to_owned());
+            app.manage(Emulator::new(local_csms_url(&address)));
+            let storage = Arc::new(SqliteStorage::open(storage_path(app))?);
+            app.manage(storage.clone());
+
+            let handler = Middleware::new(Responder::default()).layer(StorageLayer::new(storage));
+            let config = ServerConfig {
+                interceptors: Interceptors::default()
+                    .with(CallLog::new(store.clone(), app.handle())),
+                ..Default::default()
+            };
+            let server: AppServer =
+                Server::with_config(Dashboard::new(handler, store, app.handle()), config);
+            app.manage(server.clone());
+
+            tauri::async_runtime::spawn(async move {
+                if let Err(error) = server.listen(&address).await {
+                    eprintln!("cannot listen on {address}: {error}");
+                }
+            });
+
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            charge_points,
+            messages,
+            export_messages,
+            transactions,
+            composer::actions,
+            composer::send_message,
+            emulator::emulated_charge_points,
+            emulator::create_charge_point,
+            emulator::connect,
+            emulator::disconnect,
+            emulator::plug_in,
+            emulator::unplug,
+            emulator::start_charging,
+            emulator::stop_charging,
+            emulator::run_scenario
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
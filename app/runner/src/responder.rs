@@ -0,0 +1,102 @@
+use chrono::Utc;
+use ocppx_rpc::{Call, CallError, CallResult, ErrorCode};
+use ocppx_server::CsmsHandler;
+use ocppx_types::v1_6::{
+    AuthorizeResponse, BootNotificationResponse, BootNotificationStatus, HeartbeatResponse,
+    IdTagInfo, IdTagInfoStatus, StartTransactionResponse, StopTransactionResponse,
+};
+use serde::Serialize;
+use serde_json::json;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// The heartbeat interval sent to the Charge Points, in seconds.
+const HEARTBEAT_INTERVAL: i32 = 300;
+
+/// A handler accepting every Charge Point and every `idTag`, so that the
+/// dashboard can watch any Charge Point.
+#[derive(Debug)]
+pub struct Responder {
+    next_transaction_id: AtomicI32,
+}
+
+impl Default for Responder {
+    fn default() -> Self {
+        Self {
+            next_transaction_id: AtomicI32::new(1),
+        }
+    }
+}
+
+impl CsmsHandler for Responder {
+    async fn handle_call(
+        &self,
+        _charge_point_id: &str,
+        call: Call,
+    ) -> Result<CallResult, CallError> {
+        let accepted = || IdTagInfo {
+            expiry_date: None,
+            parent_id_tag: None,
+            status: IdTagInfoStatus::Accepted,
+        };
+
+        match call.action.as_str() {
+            "BootNotification" => result(
+                call,
+                &BootNotificationResponse {
+                    current_time: Utc::now(),
+                    interval: HEARTBEAT_INTERVAL,
+                    status: BootNotificationStatus::Accepted,
+                },
+            ),
+            "Heartbeat" => result(
+                call,
+                &HeartbeatResponse {
+                    current_time: Utc::now(),
+                },
+            ),
+            "Authorize" => result(
+                call,
+                &AuthorizeResponse {
+                    id_tag_info: accepted(),
+                },
+            ),
+            "StartTransaction" => result(
+                call,
+                &StartTransactionResponse {
+                    id_tag_info: accepted(),
+                    transaction_id: self.next_transaction_id.fetch_add(1, Ordering::Relaxed),
+                },
+            ),
+            "StopTransaction" => result(
+                call,
+                &StopTransactionResponse {
+                    id_tag_info: Some(accepted()),
+                },
+            ),
+            "StatusNotification"
+            | "MeterValues"
+            | "DiagnosticsStatusNotification"
+            | "FirmwareStatusNotification" => result(call, &json!({})),
+            _ => Err(CallError::new(
+                call.unique_id,
+                ErrorCode::NotImplemented,
+                "",
+                None,
+            )),
+        }
+    }
+}
+
+fn result<P>(call: Call, payload: &P) -> Result<CallResult, CallError>
+where
+    P: Serialize,
+{
+    CallResult::new(&call.unique_id, payload).map_err(|error| {
+        CallError::new(
+            call.unique_id,
+            ErrorCode::InternalError,
+            error.to_string(),
+            None,
+        )
+    })
+}
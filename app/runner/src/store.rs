@@ -0,0 +1,323 @@
+use chrono::{DateTime, Utc};
+use ocppx_rpc::{Direction, Message};
+use ocppx_types::v1_6::{
+    StatusNotificationErrorCode, StatusNotificationRequest, StatusNotificationStatus,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Mutex,
+};
+
+/// The number of messages kept per Charge Point by default, see
+/// [`Store::new`].
+pub const DEFAULT_MESSAGES_CAPACITY: usize = 1_000;
+
+/// A Charge Point known by the dashboard.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChargePoint {
+    pub id: String,
+    pub connected: bool,
+    pub connected_at: DateTime<Utc>,
+    /// The connectors, by ID, as reported by `StatusNotification`s. The
+    /// connector 0 is the Charge Point itself.
+    pub connectors: BTreeMap<i32, Connector>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Connector {
+    pub connector_id: i32,
+    pub status: StatusNotificationStatus,
+    pub error_code: StatusNotificationErrorCode,
+    pub info: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A message exchanged with a Charge Point.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoggedMessage {
+    pub timestamp: DateTime<Utc>,
+    pub charge_point_id: String,
+    pub direction: Direction,
+    /// The action of the `Call`, or of the `Call` being responded to.
+    pub action: String,
+    /// The message, in its wire format.
+    pub message: Message,
+}
+
+/// Which messages to return, see [`Store::messages`]. All of them by
+/// default.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageFilter {
+    pub charge_point_id: Option<String>,
+    /// The action of the `Call`, or of the `Call` being responded to.
+    pub action: Option<String>,
+    pub direction: Option<Direction>,
+    /// The messages at or after this instant.
+    pub since: Option<DateTime<Utc>>,
+    /// The messages strictly before this instant.
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl MessageFilter {
+    pub fn matches(&self, message: &LoggedMessage) -> bool {
+        self.charge_point_id
+            .as_ref()
+            .map_or(true, |id| *id == message.charge_point_id)
+            && self
+                .action
+                .as_ref()
+                .map_or(true, |action| *action == message.action)
+            && self
+                .direction
+                .map_or(true, |direction| direction == message.direction)
+            && self.since.map_or(true, |since| message.timestamp >= since)
+            && self.until.map_or(true, |until| message.timestamp < until)
+    }
+}
+
+/// The state of the dashboard: the Charge Points, their connectors, and the
+/// last messages of each Charge Point.
+#[derive(Debug)]
+pub struct Store {
+    inner: Mutex<Inner>,
+    messages_capacity: usize,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    charge_points: BTreeMap<String, ChargePoint>,
+    /// A ring buffer of messages per Charge Point, so that a chatty
+    /// Charge Point does not evict the messages of the others.
+    messages: BTreeMap<String, VecDeque<LoggedMessage>>,
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Self::new(DEFAULT_MESSAGES_CAPACITY)
+    }
+}
+
+impl Store {
+    /// A store keeping the last `messages_capacity` messages of each Charge
+    /// Point.
+    pub fn new(messages_capacity: usize) -> Self {
+        Self {
+            inner: Mutex::new(Inner::default()),
+            messages_capacity,
+        }
+    }
+
+    /// The Charge Points, connected or not, sorted by ID.
+    pub fn charge_points(&self) -> Vec<ChargePoint> {
+        self.inner
+            .lock()
+            .unwrap()
+            .charge_points
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// The last messages matching `filter`, from the oldest to the newest.
+    pub fn messages(&self, filter: &MessageFilter) -> Vec<LoggedMessage> {
+        let mut messages = self
+            .inner
+            .lock()
+            .unwrap()
+            .messages
+            .values()
+            .flatten()
+            .filter(|message| filter.matches(message))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        // Stable: the messages of a Charge Point stay in their order.
+        messages.sort_by_key(|message| message.timestamp);
+
+        messages
+    }
+
+    /// Register the connection of `charge_point_id`. The connectors of a
+    /// Charge Point that reconnects are kept until it reports them again.
+    pub fn connected(&self, charge_point_id: &str) -> ChargePoint {
+        let mut inner = self.inner.lock().unwrap();
+        let charge_point = inner
+            .charge_points
+            .entry(charge_point_id.to_owned())
+            .or_insert_with(|| ChargePoint {
+                id: charge_point_id.to_owned(),
+                connected: true,
+                connected_at: Utc::now(),
+                connectors: BTreeMap::new(),
+            });
+
+        charge_point.connected = true;
+        charge_point.connected_at = Utc::now();
+
+        charge_point.clone()
+    }
+
+    pub fn disconnected(&self, charge_point_id: &str) {
+        if let Some(charge_point) = self
+            .inner
+            .lock()
+            .unwrap()
+            .charge_points
+            .get_mut(charge_point_id)
+        {
+            charge_point.connected = false;
+        }
+    }
+
+    /// Update the connector reported by a `StatusNotification`.
+    pub fn status_notification(
+        &self,
+        charge_point_id: &str,
+        request: &StatusNotificationRequest,
+    ) -> Connector {
+        let connector = Connector {
+            connector_id: request.connector_id,
+            status: request.status,
+            error_code: request.error_code,
+            info: request.info.as_deref().map(str::to_owned),
+            updated_at: request.timestamp.unwrap_or_else(Utc::now),
+        };
+
+        if let Some(charge_point) = self
+            .inner
+            .lock()
+            .unwrap()
+            .charge_points
+            .get_mut(charge_point_id)
+        {
+            charge_point
+                .connectors
+                .insert(connector.connector_id, connector.clone());
+        }
+
+        connector
+    }
+
+    /// Log a message, forgetting the oldest one of its Charge Point if they
+    /// are too many.
+    pub fn log(&self, message: LoggedMessage) {
+        if self.messages_capacity == 0 {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        let messages = inner
+            .messages
+            .entry(message.charge_point_id.clone())
+            .or_default();
+
+        if messages.len() == self.messages_capacity {
+            messages.pop_front();
+        }
+
+        messages.push_back(message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use ocppx_rpc::Call;
+    use serde_json::json;
+
+    fn message(
+        charge_point_id: &str,
+        direction: Direction,
+        action: &str,
+        at: i64,
+    ) -> LoggedMessage {
+        LoggedMessage {
+            timestamp: Utc.timestamp_opt(at, 0).unwrap(),
+            charge_point_id: charge_point_id.to_owned(),
+            direction,
+            action: action.to_owned(),
+            message: Message::Call(Call {
+                unique_id: at.to_string(),
+                action: action.to_owned(),
+                payload: json!({}),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_message_filter() {
+        let heartbeat = message("CP001", Direction::Incoming, "Heartbeat", 10);
+
+        assert!(MessageFilter::default().matches(&heartbeat));
+
+        let filter = |filter: MessageFilter| filter.matches(&heartbeat);
+
+        assert!(filter(MessageFilter {
+            charge_point_id: Some("CP001".to_owned()),
+            action: Some("Heartbeat".to_owned()),
+            direction: Some(Direction::Incoming),
+            ..Default::default()
+        }));
+        assert!(!filter(MessageFilter {
+            charge_point_id: Some("CP002".to_owned()),
+            ..Default::default()
+        }));
+        assert!(!filter(MessageFilter {
+            action: Some("Authorize".to_owned()),
+            ..Default::default()
+        }));
+        assert!(!filter(MessageFilter {
+            direction: Some(Direction::Outgoing),
+            ..Default::default()
+        }));
+
+        // `since` is inclusive, `until` is exclusive.
+        assert!(filter(MessageFilter {
+            since: Some(heartbeat.timestamp),
+            until: Some(Utc.timestamp_opt(11, 0).unwrap()),
+            ..Default::default()
+        }));
+        assert!(!filter(MessageFilter {
+            since: Some(Utc.timestamp_opt(11, 0).unwrap()),
+            ..Default::default()
+        }));
+        assert!(!filter(MessageFilter {
+            until: Some(heartbeat.timestamp),
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn test_messages_eviction() {
+        let store = Store::new(2);
+
+        for at in 0..3 {
+            store.log(message("CP001", Direction::Incoming, "Heartbeat", at));
+        }
+        store.log(message("CP002", Direction::Outgoing, "Reset", 1));
+
+        // The oldest message of `CP001` is evicted, not the one of `CP002`,
+        // and the messages are sorted by timestamp.
+        let messages = store.messages(&MessageFilter::default());
+        assert_eq!(
+            messages
+                .iter()
+                .map(|message| (
+                    message.charge_point_id.as_str(),
+                    message.timestamp.timestamp()
+                ))
+                .collect::<Vec<_>>(),
+            [("CP001", 1), ("CP002", 1), ("CP001", 2)]
+        );
+
+        let disabled = Store::new(0);
+        disabled.log(message("CP001", Direction::Incoming, "Heartbeat", 0));
+        assert!(disabled.messages(&MessageFilter::default()).is_empty());
+    }
+}
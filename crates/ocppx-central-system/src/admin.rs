@@ -0,0 +1,217 @@
+use crate::csms::Csms;
+use ocppx_server::{
+    http::{self, error, respond},
+    Error, Server, Transaction,
+};
+use ocppx_types::v1_6::{Action, Request};
+use serde_json::{json, Value};
+use std::io;
+use tokio::net::{TcpListener, TcpStream};
+
+/// Size of the largest request accepted by the admin API, in bytes.
+const MAX_REQUEST_SIZE: usize = 1 << 20;
+
+/// The admin API of `ocppx-csms`, in JSON over HTTP/1.1:
+///
+/// - `GET /charge-points`: the Charge Points, with their boot information
+///   and the status of their connectors,
+/// - `GET /transactions`: the transactions, ongoing or stopped,
+/// - `POST /charge-points/{id}/{action}`: send a `Call` to a Charge Point,
+///   e.g. `POST /charge-points/CP001/RemoteStartTransaction` with
+///   `{"idTag": "ABC"}`, and respond with its response.
+///
+/// A `Call` to a Charge Point that is not connected is `404 Not Found`, a
+/// `CallError` is `502 Bad Gateway`, and no response is `504 Gateway
+/// Timeout`.
+pub async fn serve(listener: TcpListener, server: Server<Csms>, csms: Csms) -> io::Result<()> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let server = server.clone();
+        let csms = csms.clone();
+
+        tokio::spawn(async move {
+            if let Err(error) = handle(stream, &server, &csms).await {
+                log::debug!(error:% = error; "admin connection failed");
+            }
+        });
+    }
+}
+
+async fn handle(mut stream: TcpStream, server: &Server<Csms>, csms: &Csms) -> io::Result<()> {
+    let request = match http::read_request(&mut stream, MAX_REQUEST_SIZE).await {
+        Ok(request) => request,
+        Err(error) => return http::reject(&mut stream, error).await,
+    };
+    let (status, response) = route(server, csms, &request).await;
+
+    respond(&mut stream, status, &response).await
+}
+
+async fn route(server: &Server<Csms>, csms: &Csms, request: &http::HttpRequest) -> (u16, Value) {
+    let segments = request.segments();
+    let segments = segments.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["charge-points"]) => (200, charge_points(csms)),
+        ("GET", ["transactions"]) => (
+            200,
+            Value::Array(
+                csms.transactions()
+                    .transactions(|_| true)
+                    .iter()
+                    .map(transaction)
+                    .collect(),
+            ),
+        ),
+        ("POST", ["charge-points", charge_point_id, action]) => {
+            call(server, charge_point_id, action, &request.body).await
+        }
+        (_, ["charge-points"] | ["transactions"] | ["charge-points", _, _]) => {
+            (405, error("method not allowed"))
+        }
+        _ => (404, error("not found")),
+    }
+}
+
+async fn call(
+    server: &Server<Csms>,
+    charge_point_id: &str,
+    action: &str,
+    body: &[u8],
+) -> (u16, Value) {
+    let Ok(action) = action.parse::<Action>() else {
+        return (400, error(&format!("unknown action `{action}`")));
+    };
+
+    let payload = if body.is_empty() {
+        json!({})
+    } else {
+        match serde_json::from_slice::<Value>(body) {
+            Ok(payload) => payload,
+            Err(error) => return (400, self::error(&error.to_string())),
+        }
+    };
+
+    if let Err(error) = Request::from_payload(action, payload.clone()) {
+        return (400, self::error(&error.to_string()));
+    }
+
+    match server
+        .call::<Value, Value>(charge_point_id, &action.to_string(), &payload)
+        .await
+    {
+        Ok(response) => (200, response),
+        Err(error @ Error::ChargePointNotConnected(_)) => (404, self::error(&error.to_string())),
+        Err(Error::CallError(call_error)) => (
+            502,
+            json!({
+                "errorCode": call_error.error_code,
+                "errorDescription": call_error.error_description,
+                "errorDetails": call_error.error_details,
+            }),
+        ),
+        Err(error @ Error::Timeout { .. }) => (504, self::error(&error.to_string())),
+        Err(error) => (500, self::error(&error.to_string())),
+    }
+}
+
+fn charge_points(csms: &Csms) -> Value {
+    Value::Array(
+        csms.charge_points()
+            .into_iter()
+            .map(|(id, charge_point)| {
+                let (booted_at, boot) = charge_point.boot.unzip();
+
+                json!({
+                    "id": id,
+                    "connected": charge_point.connected,
+                    "bootedAt": booted_at,
+                    "bootNotification": boot,
+                    "lastHeartbeat": charge_point.last_heartbeat,
+                    "connectors": charge_point.connectors.values().collect::<Vec<_>>(),
+                })
+            })
+            .collect(),
+    )
+}
+
+fn transaction(transaction: &Transaction) -> Value {
+    json!({
+        "id": transaction.id,
+        "chargePointId": transaction.charge_point_id,
+        "connectorId": transaction.connector_id,
+        "idTag": transaction.id_tag,
+        "idTagStatus": transaction.id_tag_status,
+        "meterStart": transaction.meter_start,
+        "startedAt": transaction.started_at,
+        "stop": transaction.stop.as_ref().map(|stop| json!({
+            "idTag": stop.id_tag,
+            "meterStop": stop.meter_stop,
+            "stoppedAt": stop.stopped_at,
+            "reason": stop.reason,
+        })),
+        "energy": transaction.energy(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    async fn request(port: u16, request: &str) -> String {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        response
+    }
+
+    #[tokio::test]
+    async fn test_admin_api() {
+        let csms = Csms::new(&Config::default());
+        let server = Server::new(csms.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(serve(listener, server, csms));
+
+        let response = request(port, "GET /charge-points HTTP/1.1\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("\r\n\r\n[]"));
+
+        let body = r#"{"type":"Soft"}"#;
+        let response = request(
+            port,
+            &format!(
+                "POST /charge-points/CP001/Reset HTTP/1.1\r\nContent-Length: {}\r\n\r\n{body}",
+                body.len()
+            ),
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(response.contains("the charge point `CP001` is not connected"));
+
+        let response = request(
+            port,
+            "POST /charge-points/CP001/Reset HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}",
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+
+        let response = request(
+            port,
+            &format!(
+                "POST /charge-points/CP001/Reset HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+                usize::MAX
+            ),
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 413 Payload Too Large\r\n"));
+
+        let response = request(port, "DELETE /transactions HTTP/1.1\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+    }
+}
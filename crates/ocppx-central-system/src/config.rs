@@ -0,0 +1,229 @@
+use crate::{Error, Result};
+use ocppx_types::{v1_6::IdTagInfoStatus, IdTag};
+use std::{collections::HashMap, fs, path::PathBuf};
+use toml_edit::{DocumentMut, Item, Table};
+
+/// Configuration of `ocppx-csms`, read from a TOML file:
+///
+/// ```toml
+/// [server]
+/// address = "0.0.0.0:9000"
+/// heartbeat_interval = 300
+///
+/// # Optional, for the security profiles 2 and 3.
+/// [tls]
+/// certificate_chain = "csms.pem"
+/// private_key = "csms.key"
+/// # Optional, the security profile 3 is used when set.
+/// client_root_certificates = "charge-points-ca.pem"
+///
+/// [admin]
+/// address = "127.0.0.1:8080"
+///
+/// # Optional, all the ID tags are accepted when omitted.
+/// [auth_list]
+/// "04E91C5A" = "Accepted"
+/// "04A2B3C4" = "Blocked"
+/// ```
+///
+/// Every key is optional.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// Where to accept the Charge Points.
+    pub address: String,
+    /// The heartbeat interval sent to the Charge Points in the
+    /// `BootNotification` responses, in seconds.
+    pub heartbeat_interval: i32,
+    pub tls: Option<TlsFiles>,
+    /// Where to serve the admin API.
+    pub admin_address: String,
+    /// The status of the known ID tags. The unknown ID tags are `Invalid`.
+    pub auth_list: Option<HashMap<IdTag, IdTagInfoStatus>>,
+}
+
+/// The PEM files of the TLS configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlsFiles {
+    pub certificate_chain: PathBuf,
+    pub private_key: PathBuf,
+    pub client_root_certificates: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            address: "0.0.0.0:9000".to_owned(),
+            heartbeat_interval: 300,
+            tls: None,
+            admin_address: "127.0.0.1:8080".to_owned(),
+            auth_list: None,
+        }
+    }
+}
+
+impl Config {
+    pub fn from_file(path: &str) -> Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(toml: &str) -> Result<Self> {
+        let document = toml
+            .parse::<DocumentMut>()
+            .map_err(|error| Error::Config(error.to_string()))?;
+        let mut config = Self::default();
+
+        if let Some(server) = table(&document, "server")? {
+            if let Some(address) = string(server, "server.address")? {
+                config.address = address;
+            }
+
+            if let Some(heartbeat_interval) = get(server, "server.heartbeat_interval") {
+                config.heartbeat_interval = heartbeat_interval
+                    .as_integer()
+                    .and_then(|interval| i32::try_from(interval).ok())
+                    .filter(|interval| *interval > 0)
+                    .ok_or_else(|| invalid("server.heartbeat_interval", "a positive integer"))?;
+            }
+        }
+
+        if let Some(tls) = table(&document, "tls")? {
+            let required = |key: &str| {
+                string(tls, key)?
+                    .map(PathBuf::from)
+                    .ok_or_else(|| Error::Config(format!("`{key}` is missing")))
+            };
+
+            config.tls = Some(TlsFiles {
+                certificate_chain: required("tls.certificate_chain")?,
+                private_key: required("tls.private_key")?,
+                client_root_certificates: string(tls, "tls.client_root_certificates")?
+                    .map(PathBuf::from),
+            });
+        }
+
+        if let Some(admin) = table(&document, "admin")? {
+            if let Some(address) = string(admin, "admin.address")? {
+                config.admin_address = address;
+            }
+        }
+
+        if let Some(auth_list) = table(&document, "auth_list")? {
+            config.auth_list = Some(
+                auth_list
+                    .iter()
+                    .map(|(id_tag, status)| {
+                        let path = format!("auth_list.{id_tag}");
+                        let id_tag = IdTag::try_from(id_tag).map_err(|error| {
+                            Error::Config(format!("`{path}` is not an ID tag: {error}"))
+                        })?;
+                        let status = status
+                            .as_str()
+                            .and_then(|status| status.parse().ok())
+                            .ok_or_else(|| invalid(&path, "an ID tag status"))?;
+
+                        Ok((id_tag, status))
+                    })
+                    .collect::<Result<_>>()?,
+            );
+        }
+
+        Ok(config)
+    }
+}
+
+fn table<'a>(table: &'a Table, key: &str) -> Result<Option<&'a Table>> {
+    table
+        .get(key)
+        .map(|item| item.as_table().ok_or_else(|| invalid(key, "a table")))
+        .transpose()
+}
+
+/// The item at `path`, whose last segment is the key in `table`.
+fn get<'a>(table: &'a Table, path: &str) -> Option<&'a Item> {
+    table.get(path.rsplit('.').next().unwrap_or(path))
+}
+
+fn string(table: &Table, path: &str) -> Result<Option<String>> {
+    get(table, path)
+        .map(|item| {
+            item.as_str()
+                .map(str::to_owned)
+                .ok_or_else(|| invalid(path, "a string"))
+        })
+        .transpose()
+}
+
+fn invalid(path: &str, expected: &str) -> Error {
+    Error::Config(format!("`{path}` must be {expected}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+
+        let config = Config::parse(
+            r#"
+            [server]
+            address = "127.0.0.1:9001"
+            heartbeat_interval = 60
+
+            [tls]
+            certificate_chain = "csms.pem"
+            private_key = "csms.key"
+
+            [auth_list]
+            ABC = "Accepted"
+            DEF = "Blocked"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.address, "127.0.0.1:9001");
+        assert_eq!(config.heartbeat_interval, 60);
+        assert_eq!(
+            config.tls,
+            Some(TlsFiles {
+                certificate_chain: "csms.pem".into(),
+                private_key: "csms.key".into(),
+                client_root_certificates: None,
+            })
+        );
+        assert_eq!(config.admin_address, "127.0.0.1:8080");
+        assert_eq!(
+            config.auth_list,
+            Some(HashMap::from([
+                (IdTag::try_from("ABC").unwrap(), IdTagInfoStatus::Accepted),
+                (IdTag::try_from("DEF").unwrap(), IdTagInfoStatus::Blocked),
+            ]))
+        );
+
+        for (toml, error) in [
+            (
+                "[server]\nheartbeat_interval = 0",
+                "`server.heartbeat_interval` must be a positive integer",
+            ),
+            (
+                "[tls]\nprivate_key = \"csms.key\"",
+                "`tls.certificate_chain` is missing",
+            ),
+            (
+                "[auth_list]\nABC = \"Maybe\"",
+                "`auth_list.ABC` must be an ID tag status",
+            ),
+            (
+                "[auth_list]\nAN-ID-TAG-LONGER-THAN-20 = \"Accepted\"",
+                "`auth_list.AN-ID-TAG-LONGER-THAN-20` is not an ID tag: longer than 20 characters",
+            ),
+            ("admin = 1", "`admin` must be a table"),
+        ] {
+            assert_eq!(
+                Config::parse(toml).unwrap_err().to_string(),
+                format!("invalid configuration: {error}")
+            );
+        }
+    }
+}
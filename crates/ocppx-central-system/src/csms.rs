@@ -0,0 +1,412 @@
+use crate::config::Config;
+use chrono::{DateTime, Utc};
+use ocppx_rpc::{Call, CallError, CallResult, ErrorCode};
+use ocppx_server::{
+    AuthorizationProvider, CsmsHandler, StaticAuthorization, TransactionError, TransactionManager,
+};
+use ocppx_types::v1_6::{
+    Action, BootNotificationRequest, BootNotificationResponse, BootNotificationStatus,
+    DataTransferResponse, DataTransferStatus, DiagnosticsStatusNotificationResponse,
+    FirmwareStatusNotificationResponse, HeartbeatResponse, IdTagInfo, MeterValuesResponse, Request,
+    StatusNotificationRequest, StatusNotificationResponse, StopTransactionResponse,
+};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
+
+/// What the Central System knows about a Charge Point.
+#[derive(Debug, Clone, Default)]
+pub struct ChargePoint {
+    pub connected: bool,
+    /// The last `BootNotification`, with when it was received.
+    pub boot: Option<(DateTime<Utc>, BootNotificationRequest)>,
+    pub last_heartbeat: Option<DateTime<Utc>>,
+    /// The last `StatusNotification` of each connector.
+    pub connectors: BTreeMap<i32, StatusNotificationRequest>,
+}
+
+/// The auth list of the configuration, or accept all the ID tags.
+pub struct AuthList(Option<StaticAuthorization>);
+
+impl AuthorizationProvider for AuthList {
+    async fn authorize(&self, id_tag: &str) -> IdTagInfo {
+        match &self.0 {
+            Some(auth_list) => auth_list.authorize(id_tag).await,
+            None => ().authorize(id_tag).await,
+        }
+    }
+}
+
+struct Inner {
+    heartbeat_interval: i32,
+    transactions: TransactionManager<AuthList>,
+    charge_points: Mutex<HashMap<String, ChargePoint>>,
+}
+
+/// The handler of `ocppx-csms`: it accepts all the boots, authorizes the ID
+/// tags with the auth list, tracks the transactions, and records the state
+/// of the Charge Points for the admin API.
+///
+/// The Central System does not send anything on its own: the `Call`s to
+/// the Charge Points come from the admin API.
+#[derive(Clone)]
+pub struct Csms {
+    inner: Arc<Inner>,
+}
+
+impl Csms {
+    pub fn new(config: &Config) -> Self {
+        let auth_list = config.auth_list.as_ref().map(|auth_list| {
+            StaticAuthorization::new(auth_list.iter().map(|(id_tag, status)| {
+                (id_tag.clone(), IdTagInfo::builder().status(*status).build())
+            }))
+        });
+
+        Self {
+            inner: Arc::new(Inner {
+                heartbeat_interval: config.heartbeat_interval,
+                transactions: TransactionManager::with_authorization_provider(AuthList(auth_list)),
+                charge_points: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    pub fn transactions(&self) -> &TransactionManager<AuthList> {
+        &self.inner.transactions
+    }
+
+    /// The Charge Points that have connected at least once, sorted by ID.
+    pub fn charge_points(&self) -> Vec<(String, ChargePoint)> {
+        let mut charge_points = self
+            .inner
+            .charge_points
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, charge_point)| (id.clone(), charge_point.clone()))
+            .collect::<Vec<_>>();
+        charge_points.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        charge_points
+    }
+
+    fn update<F>(&self, charge_point_id: &str, update: F)
+    where
+        F: FnOnce(&mut ChargePoint),
+    {
+        update(
+            self.inner
+                .charge_points
+                .lock()
+                .unwrap()
+                .entry(charge_point_id.to_owned())
+                .or_default(),
+        )
+    }
+
+    async fn respond(&self, charge_point_id: &str, call: &Call) -> Result<CallResult, CallError> {
+        let action = call.action.parse::<Action>().map_err(|_| {
+            CallError::new(
+                &call.unique_id,
+                ErrorCode::NotImplemented,
+                format!("unknown action `{}`", call.action),
+                None,
+            )
+        })?;
+
+        let request = Request::from_payload(action, call.payload.clone()).map_err(|error| {
+            CallError::new(
+                &call.unique_id,
+                ErrorCode::FormationViolation,
+                error.to_string(),
+                None,
+            )
+        })?;
+        let transaction_error = |error: TransactionError| {
+            CallError::new(
+                &call.unique_id,
+                ErrorCode::PropertyConstraintViolation,
+                error.to_string(),
+                None,
+            )
+        };
+        let now = Utc::now();
+
+        match request {
+            Request::BootNotification(request) => {
+                self.update(charge_point_id, |charge_point| {
+                    charge_point.boot = Some((now, request));
+                });
+
+                result(
+                    call,
+                    &BootNotificationResponse::builder()
+                        .current_time(now)
+                        .interval(self.inner.heartbeat_interval)
+                        .status(BootNotificationStatus::Accepted)
+                        .build(),
+                )
+            }
+            Request::Heartbeat(_) => {
+                self.update(charge_point_id, |charge_point| {
+                    charge_point.last_heartbeat = Some(now);
+                });
+
+                result(
+                    call,
+                    &HeartbeatResponse::builder().current_time(now).build(),
+                )
+            }
+            Request::StatusNotification(request) => {
+                self.update(charge_point_id, |charge_point| {
+                    charge_point
+                        .connectors
+                        .insert(request.connector_id, request);
+                });
+
+                result(call, &StatusNotificationResponse::builder().build())
+            }
+            Request::Authorize(request) => {
+                result(call, &self.inner.transactions.authorize(&request).await)
+            }
+            Request::StartTransaction(request) => result(
+                call,
+                &self
+                    .inner
+                    .transactions
+                    .start(charge_point_id, &request)
+                    .await,
+            ),
+            Request::MeterValues(request) => {
+                match self
+                    .inner
+                    .transactions
+                    .meter_values(charge_point_id, &request)
+                {
+                    Err(TransactionError::UnknownTransaction(transaction_id)) => {
+                        unknown_transaction(charge_point_id, call, transaction_id)
+                    }
+                    result => result.map_err(transaction_error)?,
+                }
+
+                result(call, &MeterValuesResponse::builder().build())
+            }
+            Request::StopTransaction(request) => {
+                let response = match self
+                    .inner
+                    .transactions
+                    .stop(charge_point_id, &request)
+                    .await
+                {
+                    Err(TransactionError::UnknownTransaction(transaction_id)) => {
+                        unknown_transaction(charge_point_id, call, transaction_id);
+
+                        StopTransactionResponse::builder().build()
+                    }
+                    response => response.map_err(transaction_error)?,
+                };
+
+                result(call, &response)
+            }
+            Request::DataTransfer(_) => result(
+                call,
+                &DataTransferResponse::builder()
+                    .status(DataTransferStatus::Accepted)
+                    .build(),
+            ),
+            Request::DiagnosticsStatusNotification(_) => result(
+                call,
+                &DiagnosticsStatusNotificationResponse::builder().build(),
+            ),
+            Request::FirmwareStatusNotification(_) => {
+                result(call, &FirmwareStatusNotificationResponse::builder().build())
+            }
+            _ => Err(CallError::new(
+                &call.unique_id,
+                ErrorCode::NotSupported,
+                format!("`{action}` is not sent by a Charge Point"),
+                None,
+            )),
+        }
+    }
+}
+
+/// A Charge Point reports a transaction that is not known, e.g. one
+/// started before a restart of the Central System: it is accepted anyway,
+/// or the Charge Point would send it again and again.
+fn unknown_transaction(charge_point_id: &str, call: &Call, transaction_id: i32) {
+    log::warn!(
+        charge_point_id = charge_point_id,
+        unique_id = call.unique_id.as_str(),
+        action = call.action.as_str(),
+        transaction_id = transaction_id;
+        "unknown transaction, accepted anyway"
+    );
+}
+
+fn result<P>(call: &Call, payload: &P) -> Result<CallResult, CallError>
+where
+    P: Serialize,
+{
+    CallResult::new(&call.unique_id, payload).map_err(|error| {
+        CallError::new(
+            &call.unique_id,
+            ErrorCode::InternalError,
+            error.to_string(),
+            None,
+        )
+    })
+}
+
+impl CsmsHandler for Csms {
+    async fn handle_call(
+        &self,
+        charge_point_id: &str,
+        call: Call,
+    ) -> Result<CallResult, CallError> {
+        let response = self.respond(charge_point_id, &call).await;
+
+        if let Err(error) = &response {
+            log::warn!(
+                charge_point_id,
+                action:% = call.action,
+                error_code:% = error.error_code;
+                "rejected a call: {}",
+                error.error_description
+            );
+        }
+
+        response
+    }
+
+    async fn connected(&self, charge_point_id: &str) {
+        log::info!(charge_point_id; "charge point connected");
+        self.update(charge_point_id, |charge_point| {
+            charge_point.connected = true
+        });
+    }
+
+    async fn disconnected(&self, charge_point_id: &str) {
+        log::info!(charge_point_id; "charge point disconnected");
+        self.update(charge_point_id, |charge_point| {
+            charge_point.connected = false
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ocppx_types::{v1_6::IdTagInfoStatus, IdTag};
+    use serde_json::{json, Value};
+
+    async fn call(csms: &Csms, action: &str, payload: Value) -> Result<Value, CallError> {
+        csms.handle_call(
+            "CP001",
+            Call {
+                unique_id: "1".to_owned(),
+                action: action.to_owned(),
+                payload,
+            },
+        )
+        .await
+        .map(|call_result| call_result.payload)
+    }
+
+    #[tokio::test]
+    async fn test_csms() {
+        let csms = Csms::new(&Config {
+            heartbeat_interval: 60,
+            auth_list: Some(HashMap::from([(
+                IdTag::try_from("ABC").unwrap(),
+                IdTagInfoStatus::Accepted,
+            )])),
+            ..Default::default()
+        });
+
+        let boot = call(
+            &csms,
+            "BootNotification",
+            json!({"chargePointVendor": "X", "chargePointModel": "Y"}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(boot["status"], "Accepted");
+        assert_eq!(boot["interval"], 60);
+
+        call(
+            &csms,
+            "StatusNotification",
+            json!({"connectorId": 1, "errorCode": "NoError", "status": "Available"}),
+        )
+        .await
+        .unwrap();
+
+        let charge_points = csms.charge_points();
+        assert_eq!(charge_points.len(), 1);
+        assert_eq!(
+            charge_points[0]
+                .1
+                .boot
+                .as_ref()
+                .unwrap()
+                .1
+                .charge_point_vendor,
+            "X"
+        );
+        assert!(charge_points[0].1.connectors.contains_key(&1));
+
+        for (id_tag, status) in [("ABC", "Accepted"), ("DEF", "Invalid")] {
+            assert_eq!(
+                call(&csms, "Authorize", json!({"idTag": id_tag}))
+                    .await
+                    .unwrap()["idTagInfo"]["status"],
+                status
+            );
+        }
+
+        let start = call(
+            &csms,
+            "StartTransaction",
+            json!({"connectorId": 1, "idTag": "ABC", "meterStart": 0, "timestamp": "2013-02-01T20:53:32.486Z"}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(csms.transactions().active_transactions().len(), 1);
+
+        let stop = json!({"transactionId": start["transactionId"], "meterStop": 1000, "timestamp": "2013-02-01T21:53:32.486Z"});
+        call(&csms, "StopTransaction", stop.clone()).await.unwrap();
+        // A transaction stopped again, or unknown, is accepted anyway.
+        assert_eq!(
+            call(&csms, "StopTransaction", stop).await.unwrap(),
+            json!({})
+        );
+        assert_eq!(
+            call(
+                &csms,
+                "MeterValues",
+                json!({"connectorId": 1, "transactionId": 7, "meterValue": []}),
+            )
+            .await
+            .unwrap(),
+            json!({})
+        );
+
+        assert_eq!(
+            call(&csms, "Reset", json!({"type": "Soft"}))
+                .await
+                .unwrap_err()
+                .error_code,
+            ErrorCode::NotSupported
+        );
+        assert_eq!(
+            call(&csms, "Authorize", json!({}))
+                .await
+                .unwrap_err()
+                .error_code,
+            ErrorCode::FormationViolation
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! `ocppx-csms`, a reference OCPP-J 1.6 Central System: a drop-in target
+//! to test Charge Points against.
+//!
+//! It accepts all the boots, authorizes the ID tags with the auth list of
+//! its configuration (or all of them), tracks the transactions, and serves
+//! an admin API to inspect the Charge Points and to send them `Call`s, see
+//! [`admin::serve`]. The configuration is a TOML file, see
+//! [`config::Config`]:
+//!
+//! ```text
+//! ocppx-csms [--config <path>]
+//! ```
+
+mod admin;
+mod config;
+mod csms;
+
+use config::Config;
+use csms::Csms;
+use log::{LevelFilter, Log, Metadata, Record};
+use ocppx_server::{
+    rustls::{
+        pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+        RootCertStore,
+    },
+    Server, ServerConfig, TlsConfig,
+};
+use std::{env, error::Error as _, process::ExitCode, time::Duration};
+use thiserror::Error;
+use tokio::net::TcpListener;
+
+const USAGE: &str = "\
+Usage: ocppx-csms [--config <path>]
+
+Options:
+    --config <path>  The TOML configuration file; the defaults are used
+                     when omitted.
+    --help           Print this message.
+";
+
+/// Time given to the Charge Points to disconnect on Ctrl-C.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("{0}\n\n{USAGE}")]
+    Usage(String),
+
+    #[error("invalid configuration: {0}")]
+    Config(String),
+
+    #[error("I/O error")]
+    Io(#[from] std::io::Error),
+
+    #[error("invalid PEM file")]
+    Pem(#[from] ocppx_server::rustls::pki_types::pem::Error),
+
+    #[error("server error")]
+    Server(#[from] ocppx_server::Error),
+}
+
+/// Print the logs to stderr.
+struct Logger;
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        struct Visitor(String);
+
+        impl<'kvs> log::kv::VisitSource<'kvs> for Visitor {
+            fn visit_pair(
+                &mut self,
+                key: log::kv::Key<'kvs>,
+                value: log::kv::Value<'kvs>,
+            ) -> std::result::Result<(), log::kv::Error> {
+                self.0.push_str(&format!(" {key}={value}"));
+
+                Ok(())
+            }
+        }
+
+        let mut visitor = Visitor(String::new());
+        let _ = record.key_values().visit(&mut visitor);
+
+        eprintln!(
+            "{} {:5} {}{}",
+            chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            record.level(),
+            record.args(),
+            visitor.0
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+/// What to do, from the command-line arguments.
+enum Command {
+    /// Run with the configuration file, if any.
+    Run(Option<String>),
+    Help,
+}
+
+fn parse_arguments<I>(mut arguments: I) -> Result<Command>
+where
+    I: Iterator<Item = String>,
+{
+    let mut config = None;
+
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--config" => {
+                config = Some(
+                    arguments
+                        .next()
+                        .ok_or_else(|| Error::Usage("`--config` expects a path".to_owned()))?,
+                )
+            }
+            "--help" | "-h" => return Ok(Command::Help),
+            argument => return Err(Error::Usage(format!("unexpected argument `{argument}`"))),
+        }
+    }
+
+    Ok(Command::Run(config))
+}
+
+fn tls_config(tls: &config::TlsFiles) -> Result<TlsConfig> {
+    let certificate_chain = CertificateDer::pem_file_iter(&tls.certificate_chain)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let private_key = PrivateKeyDer::from_pem_file(&tls.private_key)?;
+
+    Ok(match &tls.client_root_certificates {
+        Some(client_root_certificates) => {
+            let mut client_root_store = RootCertStore::empty();
+            client_root_store.add_parsable_certificates(
+                CertificateDer::pem_file_iter(client_root_certificates)?
+                    .collect::<std::result::Result<Vec<_>, _>>()?,
+            );
+
+            TlsConfig::security_profile_3(certificate_chain, private_key, client_root_store)?
+        }
+        None => TlsConfig::security_profile_2(certificate_chain, private_key)?,
+    })
+}
+
+async fn run() -> Result<()> {
+    let config = match parse_arguments(env::args().skip(1))? {
+        Command::Run(Some(path)) => Config::from_file(&path)?,
+        Command::Run(None) => Config::default(),
+        Command::Help => {
+            print!("{USAGE}");
+
+            return Ok(());
+        }
+    };
+
+    let csms = Csms::new(&config);
+    let server = Server::with_config(
+        csms.clone(),
+        ServerConfig {
+            tls: config.tls.as_ref().map(tls_config).transpose()?,
+            ..Default::default()
+        },
+    );
+
+    let listener = TcpListener::bind(&config.address).await?;
+    let admin_listener = TcpListener::bind(&config.admin_address).await?;
+    let scheme = if config.tls.is_some() { "wss" } else { "ws" };
+
+    log::info!(
+        "accepting charge points on {scheme}://{}",
+        listener.local_addr()?
+    );
+    log::info!(
+        "serving the admin API on http://{}",
+        admin_listener.local_addr()?
+    );
+
+    tokio::select! {
+        result = server.serve(listener) => result?,
+        result = admin::serve(admin_listener, server.clone(), csms) => result?,
+        result = tokio::signal::ctrl_c() => {
+            result?;
+            log::info!("shutting down");
+            server.shutdown(SHUTDOWN_TIMEOUT).await;
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let _ = log::set_logger(&Logger);
+    log::set_max_level(LevelFilter::Info);
+
+    match run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprint!("error: {error}");
+
+            let mut source = error.source();
+
+            while let Some(error) = source {
+                eprint!(": {error}");
+                source = error.source();
+            }
+
+            eprintln!();
+
+            ExitCode::FAILURE
+        }
+    }
+}
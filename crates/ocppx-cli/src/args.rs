@@ -0,0 +1,266 @@
+use crate::{Error, Result};
+use ocppx_types::v1_6::{Action, Request};
+use serde_json::{Map, Value};
+
+pub const USAGE: &str = "\
+Usage:
+    ocppx send <action> --url <url> [--id <charge point id>] [--password <password>]
+               [--payload <json>] [--<field> <value>]...
+    ocppx listen [--host <host>] [--port <port>]
+    ocppx help
+
+Commands:
+    send      Connect as a Charge Point, send a `Call`, and print its response.
+              The action is in kebab case, e.g. `boot-notification`. The payload
+              is `--payload`, completed by the `--<field>` options, with the
+              fields in kebab case, e.g. `--charge-point-vendor`. The identity of
+              the Charge Point is the last segment of the URL, unless `--id`
+              is given.
+    listen    Accept Charge Points, print the frames they send, and answer them
+              with default responses. Listens on 0.0.0.0:9000 by default.
+
+Example:
+    ocppx send boot-notification --url ws://localhost:9000/ocpp/CP001 --vendor X --model Y
+";
+
+/// The command line, parsed.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Send(Send),
+    Listen(Listen),
+    Help,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Send {
+    pub csms_url: String,
+    pub charge_point_id: String,
+    pub password: Option<String>,
+    pub action: Action,
+    pub payload: Value,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Listen {
+    pub address: String,
+}
+
+/// Shorter names of some fields, by action.
+const ALIASES: &[(Action, &str, &str)] = &[
+    (Action::BootNotification, "vendor", "chargePointVendor"),
+    (Action::BootNotification, "model", "chargePointModel"),
+];
+
+impl Command {
+    /// Parse the arguments, without the name of the program.
+    pub fn parse<I>(arguments: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut arguments = arguments.into_iter();
+
+        match arguments.next().as_deref() {
+            Some("send") => Self::parse_send(arguments),
+            Some("listen") => Self::parse_listen(arguments),
+            Some("help" | "--help" | "-h") | None => Ok(Self::Help),
+            Some(command) => Err(usage(format!("unknown command `{command}`"))),
+        }
+    }
+
+    fn parse_send(mut arguments: impl Iterator<Item = String>) -> Result<Self> {
+        let action = arguments
+            .next()
+            .ok_or_else(|| usage("missing action".to_owned()))?;
+        let action = pascal_case(&action)
+            .parse::<Action>()
+            .map_err(|_| usage(format!("unknown action `{action}`")))?;
+
+        let mut url = None;
+        let mut charge_point_id = None;
+        let mut password = None;
+        let mut payload = Map::new();
+        let mut fields = vec![];
+
+        for (option, value) in options(arguments)? {
+            match option.as_str() {
+                "url" => url = Some(value),
+                "id" => charge_point_id = Some(value),
+                "password" => password = Some(value),
+                "payload" => {
+                    payload = match serde_json::from_str(&value) {
+                        Ok(Value::Object(payload)) => payload,
+                        _ => return Err(usage("`--payload` must be a JSON object".to_owned())),
+                    }
+                }
+                _ => fields.push((option, value)),
+            }
+        }
+
+        let url = url.ok_or_else(|| usage("missing `--url`".to_owned()))?;
+        let (csms_url, charge_point_id) = match charge_point_id {
+            Some(charge_point_id) => (url, charge_point_id),
+            None => match url.trim_end_matches('/').rsplit_once('/') {
+                Some((csms_url, charge_point_id)) if !csms_url.ends_with('/') => {
+                    (csms_url.to_owned(), charge_point_id.to_owned())
+                }
+                _ => return Err(usage("missing `--id`".to_owned())),
+            },
+        };
+
+        // The values are typed after the JSON schema of the action, e.g.
+        // `--connector-id 1` is a number, but `--id-tag 1` is a string.
+        let schema: Value =
+            serde_json::from_str(action.request_schema()).expect("the schemas are valid JSON");
+
+        for (field, value) in fields {
+            let name = ALIASES
+                .iter()
+                .find(|(alias_action, alias, _)| *alias_action == action && *alias == field)
+                .map_or_else(|| camel_case(&field), |(_, _, name)| (*name).to_owned());
+
+            let value = match schema["properties"][&name]["type"].as_str() {
+                Some("string") => Value::String(value),
+                Some(_) => serde_json::from_str(&value)
+                    .map_err(|_| usage(format!("invalid value `{value}` for `--{field}`")))?,
+                None => return Err(usage(format!("unknown field `--{field}` for `{action}`"))),
+            };
+
+            payload.insert(name, value);
+        }
+
+        let payload = Value::Object(payload);
+
+        if let Err(error) = Request::from_payload(action, payload.clone()) {
+            return Err(usage(format!("invalid `{action}` payload: {error}")));
+        }
+
+        Ok(Self::Send(Send {
+            csms_url,
+            charge_point_id,
+            password,
+            action,
+            payload,
+        }))
+    }
+
+    fn parse_listen(arguments: impl Iterator<Item = String>) -> Result<Self> {
+        let mut host = "0.0.0.0".to_owned();
+        let mut port = "9000".to_owned();
+
+        for (option, value) in options(arguments)? {
+            match option.as_str() {
+                "host" => host = value,
+                "port" => port = value,
+                _ => return Err(usage(format!("unknown option `--{option}`"))),
+            }
+        }
+
+        Ok(Self::Listen(Listen {
+            address: format!("{host}:{port}"),
+        }))
+    }
+}
+
+fn usage(message: String) -> Error {
+    Error::Usage(message)
+}
+
+/// The `--<option> <value>` pairs.
+fn options(mut arguments: impl Iterator<Item = String>) -> Result<Vec<(String, String)>> {
+    let mut options = vec![];
+
+    while let Some(argument) = arguments.next() {
+        let Some(option) = argument.strip_prefix("--") else {
+            return Err(usage(format!("unexpected argument `{argument}`")));
+        };
+        let value = arguments
+            .next()
+            .ok_or_else(|| usage(format!("missing value for `--{option}`")))?;
+
+        options.push((option.to_owned(), value));
+    }
+
+    Ok(options)
+}
+
+/// `boot-notification` as `BootNotification`.
+fn pascal_case(name: &str) -> String {
+    name.split('-')
+        .map(|word| {
+            let mut characters = word.chars();
+
+            characters
+                .next()
+                .map(|first| first.to_uppercase().chain(characters).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// `charge-point-vendor` as `chargePointVendor`.
+fn camel_case(name: &str) -> String {
+    let name = pascal_case(name);
+    let mut characters = name.chars();
+
+    characters
+        .next()
+        .map(|first| first.to_lowercase().chain(characters).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse(arguments: &str) -> Result<Command> {
+        Command::parse(arguments.split(' ').map(str::to_owned))
+    }
+
+    #[test]
+    fn test_send() {
+        assert_eq!(
+            parse("send boot-notification --url ws://localhost:9000/ocpp/CP001 --vendor X --model Y --firmware-version 1.0").unwrap(),
+            Command::Send(Send {
+                csms_url: "ws://localhost:9000/ocpp".to_owned(),
+                charge_point_id: "CP001".to_owned(),
+                password: None,
+                action: Action::BootNotification,
+                payload: json!({
+                    "chargePointVendor": "X",
+                    "chargePointModel": "Y",
+                    "firmwareVersion": "1.0",
+                }),
+            })
+        );
+
+        let Command::Send(send) =
+            parse("send StartTransaction --url ws://csms --id CP002 --connector-id 1 --id-tag 1 --meter-start 0 --timestamp 2013-02-01T20:53:32.486Z").unwrap()
+        else {
+            panic!("`send` is parsed");
+        };
+        assert_eq!(send.payload["connectorId"], 1);
+        assert_eq!(send.payload["idTag"], "1");
+
+        assert!(matches!(
+            parse("send heartbeat --url ws://csms/CP001 --foo 1"),
+            Err(Error::Usage(message)) if message == "unknown field `--foo` for `Heartbeat`"
+        ));
+        assert!(matches!(
+            parse("send boot-notification --url ws://csms/CP001 --vendor X"),
+            Err(Error::Usage(message)) if message.starts_with("invalid `BootNotification` payload")
+        ));
+    }
+
+    #[test]
+    fn test_listen() {
+        assert_eq!(
+            parse("listen --port 9001").unwrap(),
+            Command::Listen(Listen {
+                address: "0.0.0.0:9001".to_owned()
+            })
+        );
+        assert_eq!(parse("help").unwrap(), Command::Help);
+        assert!(parse("listen --port").is_err());
+    }
+}
@@ -0,0 +1,200 @@
+use crate::args::Listen;
+use chrono::{SecondsFormat, Utc};
+use ocppx_rpc::{Call, CallError, CallResult, ErrorCode, Message};
+use ocppx_server::{CsmsHandler, Server};
+use ocppx_types::v1_6::{
+    Action, AuthorizeResponse, BootNotificationResponse, BootNotificationStatus,
+    DataTransferResponse, DataTransferStatus, DiagnosticsStatusNotificationResponse,
+    FirmwareStatusNotificationResponse, HeartbeatResponse, IdTagInfo, IdTagInfoStatus,
+    MeterValuesResponse, Request, StartTransactionResponse, StatusNotificationResponse,
+    StopTransactionResponse,
+};
+use serde::Serialize;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// Accept Charge Points, and print every frame exchanged with them.
+pub async fn listen(listen: Listen) -> crate::Result<()> {
+    println!("Listening on ws://{}", listen.address);
+
+    Server::new(Printer::default())
+        .listen(&listen.address)
+        .await?;
+
+    Ok(())
+}
+
+/// A handler printing the `Call`s it receives, and answering them with a
+/// default response: every request is accepted.
+#[derive(Debug)]
+struct Printer {
+    next_transaction_id: AtomicI32,
+}
+
+impl Default for Printer {
+    fn default() -> Self {
+        Self {
+            next_transaction_id: AtomicI32::new(1),
+        }
+    }
+}
+
+impl Printer {
+    fn respond(&self, call: &Call) -> Result<CallResult, CallError> {
+        let action = call.action.parse::<Action>().map_err(|_| {
+            CallError::new(
+                &call.unique_id,
+                ErrorCode::NotImplemented,
+                format!("unknown action `{}`", call.action),
+                None,
+            )
+        })?;
+
+        if let Err(error) = Request::from_payload(action, call.payload.clone()) {
+            return Err(CallError::new(
+                &call.unique_id,
+                ErrorCode::FormationViolation,
+                error.to_string(),
+                None,
+            ));
+        }
+
+        let accepted = || {
+            IdTagInfo::builder()
+                .status(IdTagInfoStatus::Accepted)
+                .build()
+        };
+
+        match action {
+            Action::BootNotification => result(
+                call,
+                &BootNotificationResponse::builder()
+                    .current_time(Utc::now())
+                    .interval(300)
+                    .status(BootNotificationStatus::Accepted)
+                    .build(),
+            ),
+            Action::Heartbeat => result(
+                call,
+                &HeartbeatResponse::builder()
+                    .current_time(Utc::now())
+                    .build(),
+            ),
+            Action::Authorize => result(
+                call,
+                &AuthorizeResponse::builder().id_tag_info(accepted()).build(),
+            ),
+            Action::StartTransaction => result(
+                call,
+                &StartTransactionResponse::builder()
+                    .id_tag_info(accepted())
+                    .transaction_id(self.next_transaction_id.fetch_add(1, Ordering::Relaxed))
+                    .build(),
+            ),
+            Action::StopTransaction => result(
+                call,
+                &StopTransactionResponse::builder()
+                    .id_tag_info(accepted())
+                    .build(),
+            ),
+            Action::DataTransfer => result(
+                call,
+                &DataTransferResponse::builder()
+                    .status(DataTransferStatus::Accepted)
+                    .build(),
+            ),
+            Action::StatusNotification => {
+                result(call, &StatusNotificationResponse::builder().build())
+            }
+            Action::MeterValues => result(call, &MeterValuesResponse::builder().build()),
+            Action::DiagnosticsStatusNotification => result(
+                call,
+                &DiagnosticsStatusNotificationResponse::builder().build(),
+            ),
+            Action::FirmwareStatusNotification => {
+                result(call, &FirmwareStatusNotificationResponse::builder().build())
+            }
+            _ => Err(CallError::new(
+                &call.unique_id,
+                ErrorCode::NotSupported,
+                format!("`{action}` is not sent by a Charge Point"),
+                None,
+            )),
+        }
+    }
+}
+
+fn result<P>(call: &Call, payload: &P) -> Result<CallResult, CallError>
+where
+    P: Serialize,
+{
+    CallResult::new(&call.unique_id, payload).map_err(|error| {
+        CallError::new(
+            &call.unique_id,
+            ErrorCode::InternalError,
+            error.to_string(),
+            None,
+        )
+    })
+}
+
+impl CsmsHandler for Printer {
+    async fn handle_call(
+        &self,
+        charge_point_id: &str,
+        call: Call,
+    ) -> Result<CallResult, CallError> {
+        let response = self.respond(&call);
+
+        print(charge_point_id, "→", &Message::Call(call));
+        print(
+            charge_point_id,
+            "←",
+            &match &response {
+                Ok(call_result) => Message::CallResult(call_result.clone()),
+                Err(call_error) => Message::CallError(call_error.clone()),
+            },
+        );
+
+        response
+    }
+
+    async fn connected(&self, charge_point_id: &str) {
+        println!("[{}] {charge_point_id} connected\n", now());
+    }
+
+    async fn disconnected(&self, charge_point_id: &str) {
+        println!("[{}] {charge_point_id} disconnected\n", now());
+    }
+}
+
+/// Print a frame, with a header and its payload as pretty JSON. An empty
+/// unique ID, e.g. unknown to the caller, is omitted.
+pub fn print(charge_point_id: &str, arrow: &str, message: &Message) {
+    let unique_id = match message.unique_id() {
+        "" => String::new(),
+        unique_id => format!(" ({unique_id})"),
+    };
+    let (header, payload) = match message {
+        Message::Call(call) => (format!("Call {}{unique_id}", call.action), &call.payload),
+        Message::CallResult(call_result) => {
+            (format!("CallResult{unique_id}"), &call_result.payload)
+        }
+        Message::CallError(call_error) => (
+            format!(
+                "CallError {}{unique_id}: {}",
+                call_error.error_code, call_error.error_description
+            ),
+            &call_error.error_details,
+        ),
+    };
+
+    println!(
+        "[{}] {charge_point_id} {arrow} {header}\n{}\n",
+        now(),
+        serde_json::to_string_pretty(payload).unwrap_or_default()
+    );
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
@@ -0,0 +1,57 @@
+//! `ocppx`, to send ad-hoc OCPP 1.6 messages to a Central System, or to
+//! listen to Charge Points and print what they send. Run `ocppx help` for
+//! the usage.
+
+mod args;
+mod listen;
+mod send;
+
+use args::{Command, USAGE};
+use std::{env, error::Error as _, process::ExitCode};
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("{0}\n\n{USAGE}")]
+    Usage(String),
+
+    #[error("client error")]
+    Client(#[from] ocppx_client::Error),
+
+    #[error("server error")]
+    Server(#[from] ocppx_server::Error),
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let result = match Command::parse(env::args().skip(1)) {
+        Ok(Command::Send(send)) => send::send(send).await,
+        Ok(Command::Listen(listen)) => listen::listen(listen).await,
+        Ok(Command::Help) => {
+            print!("{USAGE}");
+
+            Ok(())
+        }
+        Err(error) => Err(error),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprint!("error: {error}");
+
+            let mut source = error.source();
+
+            while let Some(error) = source {
+                eprint!(": {error}");
+                source = error.source();
+            }
+
+            eprintln!();
+
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use crate::{args::Send, listen::print, Result};
+use ocppx_client::{ChargePointClient, ClientConfig, Error};
+use ocppx_rpc::{Call, CallResult, Message};
+use serde_json::Value;
+
+/// Connect as a Charge Point, send one `Call`, and print its response.
+pub async fn send(send: Send) -> Result<()> {
+    let client = ChargePointClient::connect_with_config(
+        &send.csms_url,
+        &send.charge_point_id,
+        ClientConfig {
+            basic_auth_password: send.password,
+            reconnect: None,
+            heartbeat: false,
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let action = send.action.as_str();
+    print(
+        &send.charge_point_id,
+        "→",
+        &Message::Call(Call {
+            unique_id: String::new(),
+            action: action.to_owned(),
+            payload: send.payload.clone(),
+        }),
+    );
+
+    let response = client.call::<Value, Value>(action, &send.payload).await;
+    let response = match response {
+        Ok(payload) => Message::CallResult(CallResult {
+            unique_id: String::new(),
+            payload,
+        }),
+        Err(Error::CallError(call_error)) => Message::CallError(call_error),
+        Err(error) => return Err(error.into()),
+    };
+    print(&send.charge_point_id, "←", &response);
+
+    client.close().await?;
+
+    Ok(())
+}
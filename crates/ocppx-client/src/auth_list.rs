@@ -0,0 +1,275 @@
+use chrono::{DateTime, Utc};
+use ocppx_types::{
+    v1_6::{
+        IdTagInfo, IdTagInfoStatus, SendLocalListRequest, SendLocalListStatus,
+        SendLocalListUpdateType,
+    },
+    IdTag,
+};
+use std::collections::BTreeMap;
+
+/// The Local Authorization List, managed by the Central System with
+/// `SendLocalList`.
+///
+/// It authorizes ID tags while the Charge Point is offline, and takes
+/// precedence over the [`AuthorizationCache`]. The ID tags are
+/// normalized, see [`IdTag`].
+#[derive(Debug, Clone, Default)]
+pub struct LocalAuthList {
+    version: i32,
+    max_length: Option<usize>,
+    entries: BTreeMap<IdTag, IdTagInfo>,
+}
+
+impl LocalAuthList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the number of entries, as advertised by the
+    /// `LocalAuthListMaxLength` configuration key.
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+
+        self
+    }
+
+    /// The version of the list, as sent in `GetLocalListVersion`: `0`
+    /// when the list is empty.
+    pub fn version(&self) -> i32 {
+        if self.entries.is_empty() {
+            0
+        } else {
+            self.version
+        }
+    }
+
+    /// The authorization of `id_tag`, if it is in the list.
+    pub fn get(&self, id_tag: &IdTag) -> Option<&IdTagInfo> {
+        self.entries.get(id_tag)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Apply a `SendLocalList`, and return the status of its response.
+    ///
+    /// A full update replaces the list. A differential update adds or
+    /// updates the entries with an `idTagInfo`, and removes the others; it
+    /// is rejected with `VersionMismatch` if its version is not newer than
+    /// the one of the list. The list is left untouched if the update fails.
+    pub fn apply(&mut self, request: &SendLocalListRequest) -> SendLocalListStatus {
+        let updates = request.local_authorization_list.iter().flatten();
+
+        let entries = match request.update_type {
+            SendLocalListUpdateType::Full => updates
+                .filter_map(|entry| Some((entry.id_tag.clone(), entry.id_tag_info.clone()?)))
+                .collect(),
+
+            SendLocalListUpdateType::Differential => {
+                if request.list_version <= self.version {
+                    return SendLocalListStatus::VersionMismatch;
+                }
+
+                let mut entries = self.entries.clone();
+
+                for entry in updates {
+                    match &entry.id_tag_info {
+                        Some(id_tag_info) => {
+                            entries.insert(entry.id_tag.clone(), id_tag_info.clone());
+                        }
+                        None => {
+                            entries.remove(&entry.id_tag);
+                        }
+                    }
+                }
+
+                entries
+            }
+        };
+
+        if self
+            .max_length
+            .is_some_and(|max_length| entries.len() > max_length)
+        {
+            return SendLocalListStatus::Failed;
+        }
+
+        self.entries = entries;
+        self.version = request.list_version;
+
+        SendLocalListStatus::Accepted
+    }
+}
+
+/// The ID tags recently authorized by the Central System, from the
+/// `idTagInfo` of the `Authorize`, `StartTransaction` and
+/// `StopTransaction` responses.
+///
+/// It is consulted while the Charge Point is offline, and cleared by
+/// `ClearCache`.
+#[derive(Debug, Clone, Default)]
+pub struct AuthorizationCache {
+    entries: BTreeMap<IdTag, IdTagInfo>,
+}
+
+impl AuthorizationCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the latest authorization of `id_tag`.
+    pub fn update(&mut self, id_tag: &IdTag, id_tag_info: &IdTagInfo) {
+        self.entries.insert(id_tag.clone(), id_tag_info.clone());
+    }
+
+    /// The cached authorization of `id_tag`, which is `Expired` once its
+    /// expiry date is past `now`.
+    pub fn get(&self, id_tag: &IdTag, now: DateTime<Utc>) -> Option<IdTagInfo> {
+        let mut id_tag_info = self.entries.get(id_tag)?.clone();
+
+        if id_tag_info.status == IdTagInfoStatus::Accepted
+            && id_tag_info
+                .expiry_date
+                .is_some_and(|expiry_date| expiry_date <= now)
+        {
+            id_tag_info.status = IdTagInfoStatus::Expired;
+        }
+
+        Some(id_tag_info)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Authorize `id_tag` without the Central System: the Local Authorization
+/// List is consulted first, then the cache.
+pub fn authorize_offline(
+    local_auth_list: &LocalAuthList,
+    cache: &AuthorizationCache,
+    id_tag: &IdTag,
+    now: DateTime<Utc>,
+) -> Option<IdTagInfo> {
+    local_auth_list
+        .get(id_tag)
+        .cloned()
+        .or_else(|| cache.get(id_tag, now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ocppx_types::v1_6::LocalAuthorizationList;
+
+    fn id_tag(id_tag: &str) -> IdTag {
+        IdTag::try_from(id_tag).unwrap()
+    }
+
+    fn entry(id_tag: &str, status: Option<IdTagInfoStatus>) -> LocalAuthorizationList {
+        LocalAuthorizationList::builder()
+            .id_tag(self::id_tag(id_tag))
+            .id_tag_info_opt(status.map(|status| IdTagInfo::builder().status(status).build()))
+            .build()
+    }
+
+    #[test]
+    fn test_send_local_list() {
+        let mut list = LocalAuthList::new().with_max_length(2);
+        assert_eq!(list.version(), 0);
+
+        let full = SendLocalListRequest::builder()
+            .list_version(3)
+            .update_type(SendLocalListUpdateType::Full)
+            .local_authorization_list(vec![
+                entry("A", Some(IdTagInfoStatus::Accepted)),
+                entry("B", Some(IdTagInfoStatus::Blocked)),
+            ])
+            .build();
+        assert_eq!(list.apply(&full), SendLocalListStatus::Accepted);
+        assert_eq!(list.version(), 3);
+
+        let differential = |version, entries| {
+            SendLocalListRequest::builder()
+                .list_version(version)
+                .update_type(SendLocalListUpdateType::Differential)
+                .local_authorization_list(entries)
+                .build()
+        };
+
+        assert_eq!(
+            list.apply(&differential(3, vec![entry("A", None)])),
+            SendLocalListStatus::VersionMismatch
+        );
+        assert_eq!(
+            list.apply(&differential(
+                4,
+                vec![entry("C", Some(IdTagInfoStatus::Accepted))]
+            )),
+            SendLocalListStatus::Failed
+        );
+        assert_eq!(
+            list.apply(&differential(
+                4,
+                vec![
+                    entry("A", None),
+                    entry("C", Some(IdTagInfoStatus::Accepted))
+                ]
+            )),
+            SendLocalListStatus::Accepted
+        );
+        assert!(list.get(&id_tag("A")).is_none());
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.version(), 4);
+    }
+
+    #[test]
+    fn test_authorize_offline() {
+        let now: DateTime<Utc> = "2013-02-01T20:53:32.486Z".parse().unwrap();
+        let mut list = LocalAuthList::new();
+        list.apply(
+            &SendLocalListRequest::builder()
+                .list_version(1)
+                .update_type(SendLocalListUpdateType::Full)
+                .local_authorization_list(vec![entry("A", Some(IdTagInfoStatus::Blocked))])
+                .build(),
+        );
+
+        let mut cache = AuthorizationCache::new();
+        let accepted = IdTagInfo::builder()
+            .status(IdTagInfoStatus::Accepted)
+            .expiry_date(now)
+            .build();
+        cache.update(&id_tag("A"), &accepted);
+        cache.update(&id_tag("b "), &accepted);
+
+        // The list takes precedence over the cache.
+        assert_eq!(
+            authorize_offline(&list, &cache, &id_tag("A"), now)
+                .unwrap()
+                .status,
+            IdTagInfoStatus::Blocked
+        );
+        assert_eq!(
+            authorize_offline(&list, &cache, &id_tag("B"), now)
+                .unwrap()
+                .status,
+            IdTagInfoStatus::Expired
+        );
+        assert!(authorize_offline(&list, &cache, &id_tag("C"), now).is_none());
+    }
+}
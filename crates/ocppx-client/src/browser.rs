@@ -0,0 +1,298 @@
+use crate::SUBPROTOCOL;
+use futures_util::{Sink, Stream};
+use ocppx_rpc::{ConnectFuture, Connector, Frame, TransportError};
+use std::{
+    borrow::Cow,
+    future::Future,
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::protocol::{frame::coding::CloseCode, CloseFrame};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+
+/// The `WebSocket` API of the browser.
+mod js {
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen]
+    extern "C" {
+        pub type WebSocket;
+
+        #[wasm_bindgen(constructor, catch)]
+        pub fn new(url: &str, protocols: &str) -> Result<WebSocket, JsValue>;
+
+        #[wasm_bindgen(method, catch)]
+        pub fn send(this: &WebSocket, data: &str) -> Result<(), JsValue>;
+
+        #[wasm_bindgen(method, catch, js_name = send)]
+        pub fn send_bytes(this: &WebSocket, data: &[u8]) -> Result<(), JsValue>;
+
+        #[wasm_bindgen(method, catch)]
+        pub fn close(this: &WebSocket, code: u16, reason: &str) -> Result<(), JsValue>;
+
+        #[wasm_bindgen(method, setter = binaryType)]
+        pub fn set_binary_type(this: &WebSocket, binary_type: &str);
+
+        #[wasm_bindgen(method, setter)]
+        pub fn set_onopen(this: &WebSocket, listener: &JsValue);
+
+        #[wasm_bindgen(method, setter)]
+        pub fn set_onmessage(this: &WebSocket, listener: &JsValue);
+
+        #[wasm_bindgen(method, setter)]
+        pub fn set_onclose(this: &WebSocket, listener: &JsValue);
+
+        #[wasm_bindgen(method, setter)]
+        pub fn set_onerror(this: &WebSocket, listener: &JsValue);
+
+        pub type MessageEvent;
+
+        #[wasm_bindgen(method, getter)]
+        pub fn data(this: &MessageEvent) -> JsValue;
+
+        pub type CloseEvent;
+
+        #[wasm_bindgen(method, getter)]
+        pub fn code(this: &CloseEvent) -> u16;
+
+        #[wasm_bindgen(method, getter)]
+        pub fn reason(this: &CloseEvent) -> String;
+    }
+}
+
+/// What happens to a [`js::WebSocket`].
+enum Event {
+    Open,
+    Frame(Frame),
+    Error,
+}
+
+/// A [`Connector`] opening the WebSocket connections with the `WebSocket`
+/// API of the browser, to run a Charge Point in a web page, or in a
+/// webview, e.g. the one of Tauri. Used with
+/// [`ChargePointClient::connect_with_connector`][crate::ChargePointClient::connect_with_connector].
+///
+/// The browser answers the pings of the Central System, but cannot send
+/// its own: [`ClientConfig::keep_alive`][crate::ClientConfig::keep_alive]
+/// must be `None`. The proxy, the TLS and the compression settings are the
+/// ones of the browser too.
+///
+/// It only works on `wasm32-unknown-unknown`: connecting panics on the
+/// other targets.
+#[derive(Debug, Clone)]
+pub struct BrowserConnector {
+    url: String,
+    subprotocol: &'static str,
+}
+
+impl BrowserConnector {
+    /// Connect to `csms_url`, e.g. `wss://csms.example.org/ocpp`, as
+    /// `charge_point_id`, with the [`SUBPROTOCOL`].
+    pub fn new(csms_url: &str, charge_point_id: &str) -> Self {
+        Self {
+            url: format!(
+                "{csms_url}/{charge_point_id}",
+                csms_url = csms_url.trim_end_matches('/')
+            ),
+            subprotocol: SUBPROTOCOL,
+        }
+    }
+
+    /// Negotiate `subprotocol` instead of the [`SUBPROTOCOL`].
+    pub fn subprotocol(mut self, subprotocol: &'static str) -> Self {
+        self.subprotocol = subprotocol;
+
+        self
+    }
+}
+
+impl Connector for BrowserConnector {
+    fn connect(&self) -> ConnectFuture<'_> {
+        Box::pin(SingleThreaded(async move {
+            let mut socket =
+                BrowserWebSocket::open(&self.url, self.subprotocol).map_err(|error| {
+                    TransportError::Io(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("cannot connect to `{}`: {error:?}", self.url),
+                    ))
+                })?;
+
+            match socket.events.recv().await {
+                Some(Event::Open) => Ok(Box::pin(socket) as _),
+                _ => Err(TransportError::Io(io::Error::new(
+                    io::ErrorKind::ConnectionRefused,
+                    format!("cannot connect to `{}`", self.url),
+                ))),
+            }
+        }))
+    }
+}
+
+/// A WebSocket connection opened by the browser, see [`BrowserConnector`].
+struct BrowserWebSocket {
+    socket: js::WebSocket,
+    events: mpsc::UnboundedReceiver<Event>,
+    /// The listeners, kept alive as long as the connection.
+    _listeners: [Closure<dyn FnMut(JsValue)>; 4],
+    /// Whether a close frame has been sent: nothing is sent after it.
+    closed: bool,
+}
+
+// SAFETY: `wasm32-unknown-unknown` runs on a single thread, the JavaScript
+// values never cross one.
+unsafe impl Send for BrowserWebSocket {}
+
+impl BrowserWebSocket {
+    fn open(url: &str, subprotocol: &str) -> Result<Self, JsValue> {
+        let socket = js::WebSocket::new(url, subprotocol)?;
+        socket.set_binary_type("arraybuffer");
+
+        let (sender, events) = mpsc::unbounded_channel();
+        let listener = |on_event: fn(JsValue) -> Event| {
+            let sender = sender.clone();
+
+            // A closed receiver means that the connection is dropped.
+            Closure::<dyn FnMut(JsValue)>::new(move |event| {
+                let _ = sender.send(on_event(event));
+            })
+        };
+
+        let listeners = [
+            listener(|_| Event::Open),
+            listener(|event| {
+                let data = event.unchecked_into::<js::MessageEvent>().data();
+
+                Event::Frame(match data.as_string() {
+                    Some(text) => Frame::Text(text),
+                    None => Frame::Binary(js_sys::Uint8Array::new(&data).to_vec()),
+                })
+            }),
+            listener(|event| {
+                let event = event.unchecked_into::<js::CloseEvent>();
+
+                Event::Frame(Frame::Close(Some(CloseFrame {
+                    code: CloseCode::from(event.code()),
+                    reason: Cow::Owned(event.reason()),
+                })))
+            }),
+            listener(|_| Event::Error),
+        ];
+        socket.set_onopen(listeners[0].as_ref());
+        socket.set_onmessage(listeners[1].as_ref());
+        socket.set_onclose(listeners[2].as_ref());
+        socket.set_onerror(listeners[3].as_ref());
+
+        Ok(Self {
+            socket,
+            events,
+            _listeners: listeners,
+            closed: false,
+        })
+    }
+}
+
+impl Drop for BrowserWebSocket {
+    fn drop(&mut self) {
+        if !self.closed {
+            let _ = self.socket.close(1000, "");
+        }
+    }
+}
+
+impl Stream for BrowserWebSocket {
+    type Item = Result<Frame, TransportError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match ready!(self.events.poll_recv(context)) {
+                Some(Event::Open) => {}
+                Some(Event::Frame(frame)) => return Poll::Ready(Some(Ok(frame))),
+                Some(Event::Error) => {
+                    return Poll::Ready(Some(Err(TransportError::Io(io::Error::new(
+                        io::ErrorKind::ConnectionReset,
+                        "the WebSocket connection failed",
+                    )))))
+                }
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl Sink<Frame> for BrowserWebSocket {
+    type Error = TransportError;
+
+    fn poll_ready(
+        self: Pin<&mut Self>,
+        _context: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(match self.closed {
+            false => Ok(()),
+            true => Err(TransportError::AlreadyClosed),
+        })
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, frame: Frame) -> Result<(), Self::Error> {
+        if self.closed {
+            return Err(TransportError::AlreadyClosed);
+        }
+
+        // The browser buffers the frames itself.
+        let sent = match frame {
+            Frame::Text(text) => self.socket.send(&text),
+            Frame::Binary(bytes) => self.socket.send_bytes(&bytes),
+            Frame::Close(close) => {
+                self.closed = true;
+
+                match close {
+                    Some(close) => self.socket.close(close.code.into(), &close.reason),
+                    None => self.socket.close(1000, ""),
+                }
+            }
+            // The browser handles the pings and the pongs itself.
+            Frame::Ping(_) | Frame::Pong(_) | Frame::Frame(_) => Ok(()),
+        };
+
+        sent.map_err(|_| TransportError::ConnectionClosed)
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        _context: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+        mut self: Pin<&mut Self>,
+        _context: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        if !self.closed {
+            self.closed = true;
+            let _ = self.socket.close(1000, "");
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A future holding JavaScript values, which is `Send` because
+/// `wasm32-unknown-unknown` runs on a single thread.
+struct SingleThreaded<F>(F);
+
+// SAFETY: see `SingleThreaded`.
+unsafe impl<F> Send for SingleThreaded<F> {}
+
+impl<F> Future for SingleThreaded<F>
+where
+    F: Future,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: the future is never moved out of its pin.
+        unsafe { self.map_unchecked_mut(|this| &mut this.0) }.poll(context)
+    }
+}
@@ -0,0 +1,714 @@
+use crate::Result;
+use chrono::{DateTime, Utc};
+use ocppx_pki::{Certificate, HashAlgorithm};
+use ocppx_types::v1_6_security::{
+    CertificateHashData, CertificateHashDataHashAlgorithm, CertificateSignedRequest,
+    CertificateSignedStatus, DeleteCertificateRequest, DeleteCertificateStatus,
+    GetInstalledCertificateIdsCertificateType, GetInstalledCertificateIdsRequest,
+    GetInstalledCertificateIdsResponse, GetInstalledCertificateIdsStatus,
+    InstallCertificateRequest, InstallCertificateStatus, SignCertificateRequest,
+};
+use rustls::{
+    pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, UnixTime},
+    server::WebPkiClientVerifier,
+    RootCertStore,
+};
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// The name of the certificate chain of the Charge Point in the
+/// [`KeyStore`].
+const CHARGE_POINT_CERTIFICATE: &str = "ChargePointCertificate";
+
+/// The name of the private key of the Charge Point in the [`KeyStore`].
+const CHARGE_POINT_KEY: &str = "ChargePointKey";
+
+/// The name of the private key of the last `SignCertificate` in the
+/// [`KeyStore`], until the Central System sends the signed certificate.
+const PENDING_KEY: &str = "ChargePointPendingKey";
+
+/// Store the certificates and the private keys of a Charge Point, so that
+/// they survive a restart.
+///
+/// The entries are PEM documents, identified by a name. `()` stores
+/// nothing.
+pub trait KeyStore: fmt::Debug + Send + Sync + 'static {
+    /// The entries saved so far, with their name.
+    fn load(&self) -> io::Result<Vec<(String, String)>>;
+
+    /// Save the entry `name`, replacing the previous one if any.
+    fn save(&self, name: &str, pem: &str) -> io::Result<()>;
+
+    /// Remove the entry `name`, if it exists.
+    fn remove(&self, name: &str) -> io::Result<()>;
+}
+
+impl KeyStore for () {
+    fn load(&self) -> io::Result<Vec<(String, String)>> {
+        Ok(Vec::new())
+    }
+
+    fn save(&self, _name: &str, _pem: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn remove(&self, _name: &str) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A [`KeyStore`] in a directory, one `<name>.pem` file per entry.
+///
+/// The private keys are not encrypted: the directory must only be readable
+/// by the Charge Point.
+#[derive(Debug)]
+pub struct FileKeyStore {
+    directory: PathBuf,
+}
+
+impl FileKeyStore {
+    /// Open the key store in `directory`, creating it if needed.
+    pub fn open(directory: impl AsRef<Path>) -> io::Result<Self> {
+        let directory = directory.as_ref().to_owned();
+        fs::create_dir_all(&directory)?;
+
+        Ok(Self { directory })
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.directory.join(format!("{name}.pem"))
+    }
+}
+
+impl KeyStore for FileKeyStore {
+    fn load(&self) -> io::Result<Vec<(String, String)>> {
+        let mut entries = Vec::new();
+
+        for entry in fs::read_dir(&self.directory)? {
+            let path = entry?.path();
+
+            if path.extension().is_some_and(|extension| extension == "pem") {
+                if let Some(name) = path.file_stem().and_then(|name| name.to_str()) {
+                    entries.push((name.to_owned(), fs::read_to_string(&path)?));
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn save(&self, name: &str, pem: &str) -> io::Result<()> {
+        // Write the entry atomically.
+        let path = self.path(name);
+        let temporary_path = path.with_extension("tmp");
+        let mut file = fs::File::create(&temporary_path)?;
+
+        file.write_all(pem.as_bytes())?;
+        file.sync_data()?;
+
+        fs::rename(temporary_path, path)
+    }
+
+    fn remove(&self, name: &str) -> io::Result<()> {
+        match fs::remove_file(self.path(name)) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The name of `certificate` in the [`KeyStore`].
+fn name(
+    certificate: &Certificate,
+    certificate_type: GetInstalledCertificateIdsCertificateType,
+) -> String {
+    let fingerprint = hash(CertificateHashDataHashAlgorithm::SHA256, &certificate.der);
+
+    format!("{certificate_type}-{}", &fingerprint[..16])
+}
+
+/// Identify `certificate`, with the public key of `issuer`.
+fn hash_data(
+    certificate: &Certificate,
+    hash_algorithm: CertificateHashDataHashAlgorithm,
+    issuer: &Certificate,
+) -> CertificateHashData {
+    CertificateHashData::builder()
+        .hash_algorithm(hash_algorithm)
+        .issuer_name_hash(hash(hash_algorithm, &certificate.issuer))
+        .issuer_key_hash(hash(hash_algorithm, &issuer.public_key))
+        .serial_number(
+            ocppx_types::bounded_string::<40>(&certificate.serial_number_hex())
+                .expect("the serial number is at most 20 bytes long"),
+        )
+        .build()
+}
+
+fn hash(hash_algorithm: CertificateHashDataHashAlgorithm, data: &[u8]) -> String {
+    ocppx_pki::hash(
+        match hash_algorithm {
+            CertificateHashDataHashAlgorithm::SHA256 => HashAlgorithm::Sha256,
+            CertificateHashDataHashAlgorithm::SHA384 => HashAlgorithm::Sha384,
+            CertificateHashDataHashAlgorithm::SHA512 => HashAlgorithm::Sha512,
+        },
+        data,
+    )
+}
+
+/// The certificates of a Charge Point using the security profile 3,
+/// managed by the Central System with the messages of the Security
+/// Whitepaper:
+///
+/// * `InstallCertificate`, `DeleteCertificate` and
+///   `GetInstalledCertificateIds` for the root certificates,
+/// * `SignCertificate` and `CertificateSigned` to renew the certificate of
+///   the Charge Point, with a new private key generated by the Charge
+///   Point.
+///
+/// The root certificates of the Central System and the certificate of the
+/// Charge Point are used to connect, see [`Self::root_store`] and
+/// [`Self::charge_point_certificate`].
+///
+/// The certificates are identified by their issuer, hashed with the
+/// public key of the certificate itself if it is self-signed, or of its
+/// installed issuer otherwise. A certificate whose issuer is not installed
+/// is hashed with its own public key.
+#[derive(Debug)]
+pub struct CertificateManager {
+    roots: Vec<(GetInstalledCertificateIdsCertificateType, Certificate)>,
+    charge_point: Option<(Vec<Certificate>, String)>,
+    pending_key: Option<String>,
+    max_certificates: usize,
+    keystore: Box<dyn KeyStore>,
+}
+
+impl Default for CertificateManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CertificateManager {
+    /// A manager without certificates, that stores nothing.
+    pub fn new() -> Self {
+        Self {
+            roots: Vec::new(),
+            charge_point: None,
+            pending_key: None,
+            max_certificates: usize::MAX,
+            keystore: Box::new(()),
+        }
+    }
+
+    /// A manager with the certificates and the keys saved in `keystore`.
+    /// The changes are saved in `keystore`.
+    ///
+    /// The entries that cannot be parsed are ignored.
+    pub fn with_keystore<K>(keystore: K) -> io::Result<Self>
+    where
+        K: KeyStore,
+    {
+        let mut manager = Self::new();
+        let mut charge_point_certificate = None;
+        let mut charge_point_key = None;
+
+        for (name, pem) in keystore.load()? {
+            match name.as_str() {
+                CHARGE_POINT_CERTIFICATE => charge_point_certificate = Certificate::parse_pem(&pem),
+                CHARGE_POINT_KEY => charge_point_key = Some(pem),
+                PENDING_KEY => manager.pending_key = Some(pem),
+                _ => {
+                    let certificate_type = GetInstalledCertificateIdsCertificateType::VARIANTS
+                        .iter()
+                        .find(|certificate_type| name.starts_with(&format!("{certificate_type}-")));
+                    let certificate = Certificate::parse_pem(&pem)
+                        .and_then(|certificates| certificates.into_iter().next());
+
+                    match (certificate_type, certificate) {
+                        (Some(certificate_type), Some(certificate)) => {
+                            manager.roots.push((*certificate_type, certificate))
+                        }
+                        _ => log::warn!(name:%; "Ignore an invalid entry of the key store"),
+                    }
+                }
+            }
+        }
+
+        manager.charge_point = charge_point_certificate.zip(charge_point_key);
+        manager.keystore = Box::new(keystore);
+
+        Ok(manager)
+    }
+
+    /// Install at most `max_certificates` root certificates, as advertised
+    /// by the `CertificateStoreMaxLength` configuration key.
+    pub fn with_max_certificates(mut self, max_certificates: usize) -> Self {
+        self.max_certificates = max_certificates;
+
+        self
+    }
+
+    /// Apply an `InstallCertificate`, and return the status of its
+    /// response.
+    ///
+    /// A certificate that cannot be parsed, that is not valid at `now`, or
+    /// that exceeds the maximum number of certificates is `Rejected`.
+    pub fn install_certificate(
+        &mut self,
+        request: &InstallCertificateRequest,
+        now: DateTime<Utc>,
+    ) -> InstallCertificateStatus {
+        let Some(certificate) = Certificate::parse_pem(&request.certificate)
+            .filter(|certificates| certificates.len() == 1)
+            .and_then(|certificates| certificates.into_iter().next())
+            .filter(|certificate| certificate.is_valid_at(now))
+        else {
+            return InstallCertificateStatus::Rejected;
+        };
+
+        if self.roots.iter().any(|(certificate_type, installed)| {
+            *certificate_type == request.certificate_type && installed.der == certificate.der
+        }) {
+            return InstallCertificateStatus::Accepted;
+        }
+
+        if self.roots.len() >= self.max_certificates {
+            return InstallCertificateStatus::Rejected;
+        }
+
+        let name = name(&certificate, request.certificate_type);
+
+        if let Err(error) = self.keystore.save(&name, &certificate.to_pem()) {
+            log::error!(name:%, error:%; "Failed to save a certificate");
+
+            return InstallCertificateStatus::Failed;
+        }
+
+        self.roots.push((request.certificate_type, certificate));
+
+        InstallCertificateStatus::Accepted
+    }
+
+    /// Apply a `DeleteCertificate`, and return the status of its response.
+    pub fn delete_certificate(
+        &mut self,
+        request: &DeleteCertificateRequest,
+    ) -> DeleteCertificateStatus {
+        let expected = &request.certificate_hash_data;
+        let Some(index) = self.roots.iter().position(|(_, certificate)| {
+            let hash_data = hash_data(
+                certificate,
+                expected.hash_algorithm,
+                self.issuer(certificate),
+            );
+
+            hash_data
+                .issuer_name_hash
+                .eq_ignore_ascii_case(&expected.issuer_name_hash)
+                && hash_data
+                    .issuer_key_hash
+                    .eq_ignore_ascii_case(&expected.issuer_key_hash)
+                && hash_data
+                    .serial_number
+                    .trim_start_matches('0')
+                    .eq_ignore_ascii_case(expected.serial_number.trim_start_matches('0'))
+        }) else {
+            return DeleteCertificateStatus::NotFound;
+        };
+
+        let (certificate_type, certificate) = &self.roots[index];
+        let name = name(certificate, *certificate_type);
+
+        if let Err(error) = self.keystore.remove(&name) {
+            log::error!(name:%, error:%; "Failed to remove a certificate");
+
+            return DeleteCertificateStatus::Failed;
+        }
+
+        self.roots.remove(index);
+
+        DeleteCertificateStatus::Accepted
+    }
+
+    /// Answer a `GetInstalledCertificateIds`, with SHA-256 hashes.
+    pub fn installed_certificate_ids(
+        &self,
+        request: &GetInstalledCertificateIdsRequest,
+    ) -> GetInstalledCertificateIdsResponse {
+        let certificate_hash_data = self
+            .roots
+            .iter()
+            .filter(|(certificate_type, _)| *certificate_type == request.certificate_type)
+            .map(|(_, certificate)| {
+                hash_data(
+                    certificate,
+                    CertificateHashDataHashAlgorithm::SHA256,
+                    self.issuer(certificate),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if certificate_hash_data.is_empty() {
+            GetInstalledCertificateIdsResponse::builder()
+                .status(GetInstalledCertificateIdsStatus::NotFound)
+                .build()
+        } else {
+            GetInstalledCertificateIdsResponse::builder()
+                .status(GetInstalledCertificateIdsStatus::Accepted)
+                .certificate_hash_data(certificate_hash_data)
+                .build()
+        }
+    }
+
+    /// Generate a new private key, and the `SignCertificate` asking the
+    /// Central System to sign it.
+    ///
+    /// The subject of the certificate signing request has the
+    /// `common_name`, e.g. the serial number of the Charge Point, and the
+    /// `organization`, i.e. the `CpoName` configuration key. The key is
+    /// kept until the Central System sends the certificate with
+    /// `CertificateSigned`; a new request replaces it.
+    pub fn sign_certificate(
+        &mut self,
+        common_name: &str,
+        organization: &str,
+    ) -> Result<SignCertificateRequest> {
+        let key_pair = rcgen::KeyPair::generate()?;
+        let mut params = rcgen::CertificateParams::default();
+        params.distinguished_name = rcgen::DistinguishedName::new();
+        params
+            .distinguished_name
+            .push(rcgen::DnType::CommonName, common_name);
+        params
+            .distinguished_name
+            .push(rcgen::DnType::OrganizationName, organization);
+
+        let csr = params.serialize_request(&key_pair)?.pem()?;
+        let key = key_pair.serialize_pem();
+
+        self.keystore.save(PENDING_KEY, &key)?;
+        self.pending_key = Some(key);
+
+        Ok(SignCertificateRequest::builder().csr(csr).build())
+    }
+
+    /// Apply a `CertificateSigned`, and return the status of its response.
+    ///
+    /// The certificate chain is `Accepted` if its first certificate is for
+    /// the key of the last `SignCertificate`, and if it is valid at `now`
+    /// for a client authentication, signed by an installed root
+    /// certificate of the Central System. It replaces the certificate of
+    /// the Charge Point.
+    pub fn certificate_signed(
+        &mut self,
+        request: &CertificateSignedRequest,
+        now: DateTime<Utc>,
+    ) -> CertificateSignedStatus {
+        let (Some(chain), Some(key)) = (
+            Certificate::parse_pem(&request.certificate_chain),
+            self.pending_key.as_ref(),
+        ) else {
+            return CertificateSignedStatus::Rejected;
+        };
+
+        let same_key = rcgen::KeyPair::from_pem(key)
+            .is_ok_and(|key_pair| key_pair.public_key_raw() == chain[0].public_key);
+
+        if !same_key || !self.verify(&chain, now) {
+            return CertificateSignedStatus::Rejected;
+        }
+
+        let chain_pem = chain.iter().map(Certificate::to_pem).collect::<String>();
+
+        if let Err(error) = self
+            .keystore
+            .save(CHARGE_POINT_CERTIFICATE, &chain_pem)
+            .and_then(|()| self.keystore.save(CHARGE_POINT_KEY, key))
+            .and_then(|()| self.keystore.remove(PENDING_KEY))
+        {
+            log::error!(error:%; "Failed to save the certificate of the Charge Point");
+
+            return CertificateSignedStatus::Rejected;
+        }
+
+        self.charge_point = self.pending_key.take().map(|key| (chain, key));
+
+        CertificateSignedStatus::Accepted
+    }
+
+    /// The root certificates of the Central System, to validate its
+    /// certificate, see `TlsConfig::security_profile_3`.
+    pub fn root_store(&self) -> RootCertStore {
+        let mut root_store = RootCertStore::empty();
+
+        for certificate in self.root_certificates(
+            GetInstalledCertificateIdsCertificateType::CentralSystemRootCertificate,
+        ) {
+            if let Err(error) = root_store.add(certificate) {
+                log::warn!(error:%; "Ignore an invalid root certificate");
+            }
+        }
+
+        root_store
+    }
+
+    /// The installed root certificates of `certificate_type`.
+    pub fn root_certificates(
+        &self,
+        certificate_type: GetInstalledCertificateIdsCertificateType,
+    ) -> Vec<CertificateDer<'static>> {
+        self.roots
+            .iter()
+            .filter(|(installed_type, _)| *installed_type == certificate_type)
+            .map(|(_, certificate)| CertificateDer::from(certificate.der.clone()))
+            .collect()
+    }
+
+    /// The certificate chain and the private key of the Charge Point, to
+    /// authenticate with, see `TlsConfig::security_profile_3`.
+    pub fn charge_point_certificate(
+        &self,
+    ) -> Option<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)> {
+        let (chain, key) = self.charge_point.as_ref()?;
+        let key = pem::parse(key).ok()?;
+
+        Some((
+            chain
+                .iter()
+                .map(|certificate| CertificateDer::from(certificate.der.clone()))
+                .collect(),
+            PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key.into_contents())),
+        ))
+    }
+
+    /// When the certificate of the Charge Point expires, to renew it with
+    /// [`Self::sign_certificate`] beforehand.
+    pub fn charge_point_certificate_expiry(&self) -> Option<DateTime<Utc>> {
+        self.charge_point
+            .as_ref()
+            .map(|(chain, _)| chain[0].not_after)
+    }
+
+    /// The installed issuer of `certificate`.
+    fn issuer<'a>(&'a self, certificate: &'a Certificate) -> &'a Certificate {
+        if certificate.is_self_signed() {
+            return certificate;
+        }
+
+        self.roots
+            .iter()
+            .map(|(_, issuer)| issuer)
+            .find(|issuer| issuer.subject == certificate.issuer)
+            .unwrap_or(certificate)
+    }
+
+    fn verify(&self, chain: &[Certificate], now: DateTime<Utc>) -> bool {
+        let Ok(verifier) = WebPkiClientVerifier::builder(Arc::new(self.root_store())).build()
+        else {
+            return false;
+        };
+        let certificates = chain
+            .iter()
+            .map(|certificate| CertificateDer::from(certificate.der.as_slice()))
+            .collect::<Vec<_>>();
+        let now =
+            UnixTime::since_unix_epoch((now - DateTime::UNIX_EPOCH).to_std().unwrap_or_default());
+
+        verifier
+            .verify_client_cert(&certificates[0], &certificates[1..], now)
+            .is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    fn root(common_name: &str) -> (rcgen::Certificate, rcgen::KeyPair) {
+        let key_pair = rcgen::KeyPair::generate().unwrap();
+        let mut params = rcgen::CertificateParams::default();
+        params
+            .distinguished_name
+            .push(rcgen::DnType::CommonName, common_name);
+        params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+
+        (params.self_signed(&key_pair).unwrap(), key_pair)
+    }
+
+    fn install_request(
+        certificate: &rcgen::Certificate,
+        certificate_type: GetInstalledCertificateIdsCertificateType,
+    ) -> InstallCertificateRequest {
+        InstallCertificateRequest::builder()
+            .certificate(certificate.pem())
+            .certificate_type(certificate_type)
+            .build()
+    }
+
+    #[test]
+    fn test_root_certificates() {
+        let now = Utc::now();
+        let path = std::env::temp_dir().join(format!("ocppx-keystore-{}", std::process::id()));
+        let (central_system, _) = root("Central System");
+        let (manufacturer, _) = root("Manufacturer");
+
+        {
+            let mut manager = CertificateManager::with_keystore(FileKeyStore::open(&path).unwrap())
+                .unwrap()
+                .with_max_certificates(2);
+
+            assert_eq!(
+                manager.install_certificate(
+                    &install_request(
+                        &central_system,
+                        GetInstalledCertificateIdsCertificateType::CentralSystemRootCertificate
+                    ),
+                    now
+                ),
+                InstallCertificateStatus::Accepted
+            );
+            assert_eq!(
+                manager.install_certificate(
+                    &install_request(
+                        &manufacturer,
+                        GetInstalledCertificateIdsCertificateType::ManufacturerRootCertificate
+                    ),
+                    now
+                ),
+                InstallCertificateStatus::Accepted
+            );
+            assert_eq!(
+                manager.install_certificate(
+                    &install_request(
+                        &root("Other").0,
+                        GetInstalledCertificateIdsCertificateType::ManufacturerRootCertificate
+                    ),
+                    now
+                ),
+                InstallCertificateStatus::Rejected
+            );
+            assert_eq!(
+                manager.install_certificate(
+                    &InstallCertificateRequest::builder()
+                        .certificate("garbage")
+                        .certificate_type(
+                            GetInstalledCertificateIdsCertificateType::ManufacturerRootCertificate
+                        )
+                        .build(),
+                    now
+                ),
+                InstallCertificateStatus::Rejected
+            );
+        }
+
+        // The certificates are restored from the key store.
+        let mut manager =
+            CertificateManager::with_keystore(FileKeyStore::open(&path).unwrap()).unwrap();
+        assert_eq!(manager.root_store().len(), 1);
+
+        let response = manager.installed_certificate_ids(
+            &GetInstalledCertificateIdsRequest::builder()
+                .certificate_type(
+                    GetInstalledCertificateIdsCertificateType::ManufacturerRootCertificate,
+                )
+                .build(),
+        );
+        assert_eq!(response.status, GetInstalledCertificateIdsStatus::Accepted);
+
+        let hash_data = response.certificate_hash_data.unwrap().remove(0);
+        let parsed = Certificate::parse_pem(&manufacturer.pem())
+            .unwrap()
+            .remove(0);
+        assert!(!hash_data.serial_number.starts_with('0'));
+        assert_eq!(
+            hash_data.issuer_key_hash,
+            hash(CertificateHashDataHashAlgorithm::SHA256, &parsed.public_key)
+        );
+
+        let request = DeleteCertificateRequest::builder()
+            .certificate_hash_data(hash_data)
+            .build();
+        assert_eq!(
+            manager.delete_certificate(&request),
+            DeleteCertificateStatus::Accepted
+        );
+        assert_eq!(
+            manager.delete_certificate(&request),
+            DeleteCertificateStatus::NotFound
+        );
+
+        let manager =
+            CertificateManager::with_keystore(FileKeyStore::open(&path).unwrap()).unwrap();
+        assert_eq!(manager.roots.len(), 1);
+
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn test_sign_certificate() {
+        let now = Utc::now();
+        let (central_system, central_system_key) = root("Central System");
+        let mut manager = CertificateManager::new();
+        manager.install_certificate(
+            &install_request(
+                &central_system,
+                GetInstalledCertificateIdsCertificateType::CentralSystemRootCertificate,
+            ),
+            now,
+        );
+
+        let request = manager.sign_certificate("CP001", "ocppx").unwrap();
+        assert!(request
+            .csr
+            .starts_with("-----BEGIN CERTIFICATE REQUEST-----"));
+
+        // The Central System signs the key of the request.
+        let sign = |key_pair: &rcgen::KeyPair| {
+            let mut params = rcgen::CertificateParams::default();
+            params
+                .distinguished_name
+                .push(rcgen::DnType::CommonName, "CP001");
+            params.extended_key_usages = vec![rcgen::ExtendedKeyUsagePurpose::ClientAuth];
+
+            CertificateSignedRequest::builder()
+                .certificate_chain(
+                    params
+                        .signed_by(key_pair, &central_system, &central_system_key)
+                        .unwrap()
+                        .pem(),
+                )
+                .build()
+        };
+
+        // Another key is rejected.
+        assert_eq!(
+            manager.certificate_signed(&sign(&rcgen::KeyPair::generate().unwrap()), now),
+            CertificateSignedStatus::Rejected
+        );
+
+        let pending_key = rcgen::KeyPair::from_pem(manager.pending_key.as_ref().unwrap()).unwrap();
+        let signed = sign(&pending_key);
+
+        // So is an expired certificate.
+        assert_eq!(
+            manager.certificate_signed(&signed, now + TimeDelta::days(365 * 5000)),
+            CertificateSignedStatus::Rejected
+        );
+        assert!(manager.charge_point_certificate().is_none());
+
+        assert_eq!(
+            manager.certificate_signed(&signed, now),
+            CertificateSignedStatus::Accepted
+        );
+
+        let (chain, _) = manager.charge_point_certificate().unwrap();
+        assert_eq!(chain.len(), 1);
+        assert!(manager.pending_key.is_none());
+        assert!(manager.charge_point_certificate_expiry().unwrap() > now);
+    }
+}
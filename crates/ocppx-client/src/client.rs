@@ -0,0 +1,1343 @@
+#[cfg(feature = "native")]
+use crate::Proxy;
+use crate::{
+    heartbeat::Heartbeat, outgoing::OutgoingQueue, ClientConfig, ConnectionState, Error,
+    MemoryQueue, MessageQueue, Result, SyncedClock, QUEUED_ACTIONS,
+};
+#[cfg(feature = "native")]
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::{DateTime, Utc};
+use futures_util::{SinkExt, StreamExt};
+#[cfg(feature = "native")]
+use ocppx_rpc::Compressed;
+use ocppx_rpc::{
+    Call, CallWindow, ConnectionEvent, Connector, KeepAliveAction, KeepAliveTimer, Message,
+    PendingCallError, PendingCalls, Span, Transport,
+};
+use ocppx_types::{v1_6::*, OcppRequest};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::HashSet,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+#[cfg(feature = "native")]
+use tokio::net::TcpStream;
+use tokio::{
+    sync::{broadcast, mpsc, watch, Notify},
+    task::JoinHandle,
+    time::{self, Instant},
+};
+use tokio_tungstenite::tungstenite::Message as Frame;
+#[cfg(feature = "native")]
+use tokio_tungstenite::{
+    client_async,
+    tungstenite::{
+        client::IntoClientRequest,
+        http::{
+            header::{AUTHORIZATION, SEC_WEBSOCKET_EXTENSIONS, SEC_WEBSOCKET_PROTOCOL},
+            HeaderValue,
+        },
+    },
+    MaybeTlsStream, WebSocketStream,
+};
+
+/// The WebSocket subprotocol negotiated with the Central System, by
+/// default.
+pub const SUBPROTOCOL: &str = "ocpp1.6";
+
+/// A Charge Point connected to a Central System.
+pub struct ChargePointClient {
+    incoming_calls: tokio::sync::Mutex<mpsc::UnboundedReceiver<Call>>,
+    shared: Arc<Shared>,
+    state: watch::Receiver<ConnectionState>,
+    connection: JoinHandle<()>,
+}
+
+impl ChargePointClient {
+    /// Connect to the Central System at `csms_url`, identifying as
+    /// `charge_point_id`, e.g. `ws://csms.example.org/ocpp` and `CP001`
+    /// will connect to `ws://csms.example.org/ocpp/CP001`.
+    #[cfg(feature = "native")]
+    pub async fn connect(csms_url: &str, charge_point_id: &str) -> Result<Self> {
+        Self::connect_with_config(csms_url, charge_point_id, ClientConfig::default()).await
+    }
+
+    /// Like [`Self::connect`], with a custom configuration.
+    ///
+    /// Only the first connection attempt is made here: once connected, the
+    /// client reconnects according to [`ClientConfig::reconnect`].
+    #[cfg(feature = "native")]
+    pub async fn connect_with_config(
+        csms_url: &str,
+        charge_point_id: &str,
+        config: ClientConfig,
+    ) -> Result<Self> {
+        Self::connect_to(Endpoint {
+            csms_url: csms_url.to_owned(),
+            charge_point_id: charge_point_id.to_owned(),
+            config,
+            connector: None,
+        })
+        .await
+    }
+
+    /// Connect to the Central System over the [`Transport`]s opened by
+    /// `connector`, e.g. a
+    /// [`FramedTransport`][ocppx_rpc::FramedTransport] over a plain TCP
+    /// connection, identifying as `charge_point_id`.
+    ///
+    /// Like with [`Self::connect_with_config`], the client reconnects
+    /// according to [`ClientConfig::reconnect`], with a new transport
+    /// opened by `connector`.
+    pub async fn connect_with_connector<C>(
+        charge_point_id: &str,
+        connector: C,
+        config: ClientConfig,
+    ) -> Result<Self>
+    where
+        C: Connector,
+    {
+        Self::connect_to(Endpoint {
+            csms_url: String::new(),
+            charge_point_id: charge_point_id.to_owned(),
+            config,
+            connector: Some(Arc::new(connector)),
+        })
+        .await
+    }
+
+    /// Run over a [`Transport`] opened by the caller, e.g. one end of a
+    /// [`MemoryTransport`][ocppx_rpc::MemoryTransport] whose other end is
+    /// given to the Central System. It must be called from a Tokio
+    /// runtime.
+    ///
+    /// The transport cannot be re-opened: the client does not reconnect,
+    /// whatever [`ClientConfig::reconnect`].
+    pub fn with_transport<T>(charge_point_id: &str, transport: T, config: ClientConfig) -> Self
+    where
+        T: Transport,
+    {
+        Self::start(
+            Endpoint {
+                csms_url: String::new(),
+                charge_point_id: charge_point_id.to_owned(),
+                config: ClientConfig {
+                    reconnect: None,
+                    ..config
+                },
+                connector: None,
+            },
+            Box::pin(transport),
+        )
+    }
+
+    async fn connect_to(endpoint: Endpoint) -> Result<Self> {
+        let stream = endpoint.open().await?;
+
+        Ok(Self::start(endpoint, stream))
+    }
+
+    fn start(endpoint: Endpoint, stream: Stream) -> Self {
+        let (incoming_calls_sender, incoming_calls_receiver) = mpsc::unbounded_channel();
+        let (state_sender, state_receiver) = watch::channel(ConnectionState::Connected);
+
+        let shared = Arc::new(Shared {
+            pending_calls: PendingCalls::new(
+                endpoint.config.max_outstanding_calls,
+                endpoint.config.call_timeout,
+            ),
+            queue: endpoint
+                .config
+                .message_queue
+                .clone()
+                .unwrap_or_else(|| Arc::new(MemoryQueue::default())),
+            queue_changed: Notify::new(),
+            outgoing: OutgoingQueue::new(endpoint.config.outgoing_queue),
+            reset: watch::Sender::new(()),
+            dropped_calls: Mutex::default(),
+            events: broadcast::Sender::new(EVENTS_CAPACITY),
+            heartbeat: Heartbeat::new(endpoint.config.clock.clone()),
+            message_ids: endpoint.config.message_ids.clone(),
+            charge_point_id: endpoint.charge_point_id.clone(),
+            #[cfg(feature = "metrics")]
+            metrics: endpoint.config.metrics.clone(),
+            recorder: endpoint.config.recorder.clone(),
+        });
+
+        let connection = tokio::spawn(Span::charge_point(&endpoint.charge_point_id).instrument(
+            run(
+                endpoint,
+                stream,
+                incoming_calls_sender,
+                shared.clone(),
+                state_sender,
+            ),
+        ));
+
+        Self {
+            incoming_calls: tokio::sync::Mutex::new(incoming_calls_receiver),
+            shared,
+            state: state_receiver,
+            connection,
+        }
+    }
+
+    /// The state of the connection to the Central System. The receiver is
+    /// notified of every change, e.g. to show an online/offline status.
+    pub fn connection_state(&self) -> watch::Receiver<ConnectionState> {
+        self.state.clone()
+    }
+
+    /// The events of the transport, e.g. [`ConnectionEvent::Stale`] when
+    /// the Central System stops answering the pings of
+    /// [`ClientConfig::keep_alive`], from now on.
+    pub fn connection_events(&self) -> broadcast::Receiver<ConnectionEvent> {
+        self.shared.events.subscribe()
+    }
+
+    /// Whether the connection is closed for good, i.e. it will not
+    /// reconnect anymore.
+    pub fn is_closed(&self) -> bool {
+        *self.state.borrow() == ConnectionState::Closed
+    }
+
+    /// The heartbeat interval given by the Central System when it accepted
+    /// the last `BootNotification`.
+    pub fn heartbeat_interval(&self) -> Option<Duration> {
+        *self.shared.heartbeat.interval.borrow()
+    }
+
+    /// The current time according to the Central System, i.e. the local
+    /// [`ClientConfig::clock`] adjusted with the `currentTime` of the last `BootNotification`
+    /// or `Heartbeat` response.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.shared.heartbeat.now()
+    }
+
+    /// The clock behind [`Self::now`], to timestamp the messages from
+    /// elsewhere.
+    pub fn clock(&self) -> Arc<SyncedClock> {
+        self.shared.heartbeat.clock.clone()
+    }
+
+    /// Send a `Call` with a typed payload, and wait for its typed
+    /// response.
+    ///
+    /// The `Call` is sent once the previous outstanding `Call`s, if any,
+    /// have been answered or have timed out.
+    ///
+    /// The `Call`s of the [`QUEUED_ACTIONS`] go through the message queue:
+    /// they are delivered in order, and sent again after a reconnection
+    /// until they are answered. If this method fails with a timeout or
+    /// because the connection has dropped, the `Call` is still delivered
+    /// later.
+    pub async fn call<P, R>(&self, action: &str, payload: &P) -> Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        self.send_call(action, payload, false).await
+    }
+
+    /// Send a `Call` relayed for another Charge Point, e.g. by a gateway,
+    /// and wait for its response.
+    ///
+    /// Unlike [`Self::call`], the `Call` never goes through the message
+    /// queue, since the other Charge Point sends it again itself if needed,
+    /// and it is neither merged, dropped nor held back with a low priority:
+    /// it waits for room in [`OutgoingQueueConfig::normal`] instead.
+    ///
+    /// [`OutgoingQueueConfig::normal`]: crate::OutgoingQueueConfig::normal
+    pub async fn relay<P, R>(&self, action: &str, payload: &P) -> Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        self.send_call(action, payload, true).await
+    }
+
+    async fn send_call<P, R>(&self, action: &str, payload: &P, relayed: bool) -> Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let pending_call = self
+            .shared
+            .pending_calls
+            .register_unique(&self.shared.message_ids)
+            .await?;
+        let unique_id = pending_call.unique_id().to_owned();
+        let call = Call::new(unique_id.clone(), action, payload)?;
+
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        #[cfg(feature = "metrics")]
+        self.shared
+            .record(|metrics| metrics.record_message(ocppx_rpc::Direction::Outgoing, action));
+
+        if !relayed && QUEUED_ACTIONS.contains(&action) {
+            self.shared.queue.push(&call)?;
+            self.shared.queue_changed.notify_one();
+        } else {
+            let dropped = if relayed {
+                self.shared.outgoing.push_relayed_call(call).await?
+            } else {
+                self.shared.outgoing.push_call(call).await?
+            };
+
+            for dropped in dropped {
+                if dropped == unique_id {
+                    return Err(Error::Dropped(action.to_owned()));
+                }
+
+                self.shared.drop_call(&dropped);
+            }
+        }
+
+        log::debug!(
+            charge_point_id = self.shared.charge_point_id.as_str(),
+            unique_id = unique_id.as_str(),
+            action = action;
+            "Call sent"
+        );
+
+        let response = Span::call(&self.shared.charge_point_id, &unique_id, action)
+            .instrument(pending_call.wait())
+            .await
+            .map_err(|error| match error {
+                PendingCallError::Timeout(timeout) => Error::Timeout {
+                    action: action.to_owned(),
+                    timeout,
+                },
+                PendingCallError::Cancelled
+                    if self.shared.dropped_calls.lock().unwrap().remove(&unique_id) =>
+                {
+                    Error::Dropped(action.to_owned())
+                }
+                PendingCallError::Cancelled => Error::ConnectionClosed,
+            })?;
+
+        log::debug!(
+            charge_point_id = self.shared.charge_point_id.as_str(),
+            unique_id = unique_id.as_str(),
+            action = action;
+            "response received"
+        );
+
+        #[cfg(feature = "metrics")]
+        self.shared.record(|metrics| {
+            metrics.record_call_latency(action, started.elapsed());
+
+            if let Message::CallError(call_error) = &response {
+                metrics.record_call_error(&call_error.error_code);
+            }
+        });
+
+        match response {
+            Message::CallResult(call_result) => {
+                self.shared.heartbeat.update(action, &call_result.payload);
+
+                Ok(call_result.payload()?)
+            }
+            Message::CallError(call_error) => Err(Error::CallError(call_error)),
+            Message::Call(_) => unreachable!("only responses are registered as pending calls"),
+        }
+    }
+
+    /// Send a typed request, and wait for its typed response.
+    pub async fn send<R>(&self, request: R) -> Result<R::Response>
+    where
+        R: OcppRequest,
+    {
+        self.call(R::ACTION, &request).await
+    }
+
+    /// Wait for the next `Call` sent by the Central System. The Charge
+    /// Point is expected to answer it with [`Self::respond`].
+    ///
+    /// Returns `None` once the connection is closed.
+    pub async fn next_call(&self) -> Option<Call> {
+        self.incoming_calls.lock().await.recv().await
+    }
+
+    /// Respond to a `Call` sent by the Central System, with either a
+    /// `CallResult` or a `CallError`.
+    pub fn respond<M>(&self, response: M) -> Result<()>
+    where
+        M: Into<Message>,
+    {
+        let response = response.into();
+        log::debug!(
+            charge_point_id = self.shared.charge_point_id.as_str(),
+            unique_id = response.unique_id();
+            "response sent"
+        );
+
+        #[cfg(feature = "metrics")]
+        if let Message::CallError(call_error) = &response {
+            self.shared
+                .record(|metrics| metrics.record_call_error(&call_error.error_code));
+        }
+
+        self.shared.outgoing.push_response(response)
+    }
+
+    /// Send a text frame as is, e.g. a malformed message to check how the
+    /// Central System handles it. The response, if any, is not waited
+    /// for: it is dropped like a late response.
+    pub fn send_raw(&self, text: impl Into<String>) -> Result<()> {
+        let text = text.into();
+        log::debug!(charge_point_id = self.shared.charge_point_id.as_str(); "raw frame sent");
+
+        self.shared.outgoing.push_raw(text)
+    }
+
+    /// Drop the connection abruptly, without a close frame, e.g. to check
+    /// how the Central System handles a Charge Point that vanishes. The
+    /// client then reconnects according to [`ClientConfig::reconnect`].
+    pub fn reset(&self) {
+        self.shared.reset.send_modify(|_| {});
+    }
+
+    /// Close the connection.
+    pub async fn close(mut self) -> Result<()> {
+        // The connection task sends the close frame and stops once the
+        // outgoing queue is closed, and empty.
+        self.shared.outgoing.close();
+
+        (&mut self.connection)
+            .await
+            .map_err(|_| Error::ConnectionClosed)
+    }
+}
+
+impl Drop for ChargePointClient {
+    fn drop(&mut self) {
+        self.shared.outgoing.close();
+    }
+}
+
+macro_rules! send_methods {
+    ( $( $method:ident => $request:ident ),* $(,)? ) => {
+        impl ChargePointClient {
+            $(
+                #[doc = concat!("Send a `", stringify!($request), "`.")]
+                pub async fn $method(
+                    &self,
+                    request: $request,
+                ) -> Result<<$request as OcppRequest>::Response> {
+                    self.send(request).await
+                }
+            )*
+        }
+    };
+}
+
+send_methods! {
+    send_authorize => AuthorizeRequest,
+    send_boot_notification => BootNotificationRequest,
+    send_data_transfer => DataTransferRequest,
+    send_diagnostics_status_notification => DiagnosticsStatusNotificationRequest,
+    send_firmware_status_notification => FirmwareStatusNotificationRequest,
+    send_heartbeat => HeartbeatRequest,
+    send_meter_values => MeterValuesRequest,
+    send_start_transaction => StartTransactionRequest,
+    send_status_notification => StatusNotificationRequest,
+    send_stop_transaction => StopTransactionRequest,
+}
+
+/// The connection to the Central System.
+type Stream = Pin<Box<dyn Transport>>;
+
+#[cfg(feature = "native")]
+type WebSocket = WebSocketStream<Compressed<MaybeTlsStream<TcpStream>>>;
+
+/// Capacity of the channel of the [`ConnectionEvent`]s.
+const EVENTS_CAPACITY: usize = 16;
+
+/// The state shared by the client and the connection task.
+struct Shared {
+    pending_calls: PendingCalls,
+    queue: Arc<dyn MessageQueue>,
+    /// Notified when a `Call` is pushed in the queue.
+    queue_changed: Notify,
+    /// The other frames waiting to be sent.
+    outgoing: OutgoingQueue,
+    /// Changed to drop the connection, see [`ChargePointClient::reset`].
+    /// Each connection subscribes when it starts, so that a reset while
+    /// disconnected does not drop the next connection.
+    reset: watch::Sender<()>,
+    /// The `Call`s dropped from `outgoing` whose callers are still waiting.
+    dropped_calls: Mutex<HashSet<String>>,
+    events: broadcast::Sender<ConnectionEvent>,
+    heartbeat: Heartbeat,
+    message_ids: ocppx_rpc::MessageIds,
+    /// For the logs.
+    charge_point_id: String,
+    #[cfg(feature = "metrics")]
+    metrics: Option<ocppx_rpc::Metrics>,
+    recorder: Option<ocppx_rpc::Recorder>,
+}
+
+impl Shared {
+    /// Record something in the metrics, if they are enabled.
+    #[cfg(feature = "metrics")]
+    fn record<F>(&self, record: F)
+    where
+        F: FnOnce(&ocppx_rpc::Metrics),
+    {
+        if let Some(metrics) = &self.metrics {
+            record(metrics);
+        }
+    }
+
+    /// Fail the pending `Call` identified by `unique_id` with
+    /// [`Error::Dropped`], as it has been dropped from the outgoing queue.
+    fn drop_call(&self, unique_id: &str) {
+        self.dropped_calls
+            .lock()
+            .unwrap()
+            .insert(unique_id.to_owned());
+
+        // Its caller has given up already.
+        if !self.pending_calls.cancel(unique_id) {
+            self.dropped_calls.lock().unwrap().remove(unique_id);
+        }
+    }
+
+    /// Capture a text frame, if a recorder is configured.
+    fn capture(&self, direction: ocppx_rpc::Direction, frame: &Frame) {
+        if let (Some(recorder), Frame::Text(text)) = (&self.recorder, frame) {
+            recorder.record(direction, &self.charge_point_id, text);
+        }
+    }
+}
+
+/// Everything needed to open, and re-open, the connection.
+struct Endpoint {
+    #[cfg_attr(not(feature = "native"), allow(dead_code))]
+    csms_url: String,
+    charge_point_id: String,
+    config: ClientConfig,
+    /// What opens the transports, instead of connecting to `csms_url`
+    /// over WebSocket.
+    connector: Option<Arc<dyn Connector>>,
+}
+
+impl Endpoint {
+    async fn open(&self) -> Result<Stream> {
+        if let Some(connector) = &self.connector {
+            return Ok(connector.connect().await?);
+        }
+
+        #[cfg(feature = "native")]
+        return Ok(Box::pin(self.open_websocket().await?));
+
+        // Only a connector opens the transports without `native`.
+        #[cfg(not(feature = "native"))]
+        Err(Error::Io(std::io::ErrorKind::Unsupported.into()))
+    }
+
+    #[cfg(feature = "native")]
+    async fn open_websocket(&self) -> Result<WebSocket> {
+        let Self {
+            csms_url,
+            charge_point_id,
+            config,
+            ..
+        } = self;
+
+        let mut request = format!(
+            "{csms_url}/{charge_point_id}",
+            csms_url = csms_url.trim_end_matches('/'),
+        )
+        .into_client_request()?;
+        request.headers_mut().insert(
+            SEC_WEBSOCKET_PROTOCOL,
+            HeaderValue::from_static(config.subprotocol),
+        );
+
+        if let Some(compression) = &config.compression {
+            request.headers_mut().insert(
+                SEC_WEBSOCKET_EXTENSIONS,
+                HeaderValue::from_str(&compression.offer())
+                    .expect("the offer is a valid header value"),
+            );
+        }
+
+        if let Some(password) = &config.basic_auth_password {
+            let credentials = STANDARD.encode(format!("{charge_point_id}:{password}"));
+
+            request.headers_mut().insert(
+                AUTHORIZATION,
+                HeaderValue::from_str(&format!("Basic {credentials}"))
+                    .expect("base64 is a valid header value"),
+            );
+        }
+
+        let secure = request.uri().scheme_str() == Some("wss");
+        let host = request
+            .uri()
+            .host()
+            .unwrap_or_default()
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_owned();
+        let port = request
+            .uri()
+            .port_u16()
+            .unwrap_or(if secure { 443 } else { 80 });
+
+        let proxy = config.proxy.clone().or_else(|| {
+            config
+                .proxy_from_env
+                .then(|| Proxy::from_env(&host, secure))
+                .flatten()
+        });
+        let tunnel = match &proxy {
+            Some(proxy) => Some(proxy.connect(&host, port).await?),
+            None => None,
+        };
+
+        let stream = match tunnel {
+            Some(tunnel) => tunnel,
+            None => TcpStream::connect((host.as_str(), port)).await?,
+        };
+
+        #[cfg(feature = "tls")]
+        let stream = match (&config.tls, secure) {
+            (Some(tls), _) => connect_tls(stream, tls, &host).await?,
+            // Without root certificates, as `tungstenite` would do.
+            (None, true) => {
+                let tls = crate::TlsConfig::security_profile_2(rustls::RootCertStore::empty());
+
+                connect_tls(stream, &tls, &host).await?
+            }
+            (None, false) => MaybeTlsStream::Plain(stream),
+        };
+
+        #[cfg(not(feature = "tls"))]
+        let stream = if secure {
+            return Err(tokio_tungstenite::tungstenite::Error::Url(
+                tokio_tungstenite::tungstenite::error::UrlError::TlsFeatureNotEnabled,
+            )
+            .into());
+        } else {
+            MaybeTlsStream::Plain(stream)
+        };
+
+        let (stream, response) =
+            client_async(request, Compressed::client(stream, config.compression)).await?;
+
+        match response.headers().get(SEC_WEBSOCKET_PROTOCOL) {
+            Some(subprotocol) if subprotocol == config.subprotocol => Ok(stream),
+            _ => Err(Error::SubprotocolNotNegotiated(config.subprotocol)),
+        }
+    }
+}
+
+/// Open the TLS connection ourselves, so that the server name can differ
+/// from the host of the URL.
+#[cfg(feature = "tls")]
+async fn connect_tls(
+    stream: TcpStream,
+    tls: &crate::TlsConfig,
+    host: &str,
+) -> Result<MaybeTlsStream<TcpStream>> {
+    use rustls::pki_types::ServerName;
+    use tokio_rustls::TlsConnector;
+
+    let server_name = match &tls.server_name {
+        Some(server_name) => server_name.clone(),
+        None => ServerName::try_from(host.to_owned())
+            .map_err(|_| Error::InvalidServerName(host.to_owned()))?,
+    };
+
+    let stream = TlsConnector::from(tls.config.clone())
+        .connect(server_name, stream)
+        .await?;
+
+    Ok(MaybeTlsStream::Rustls(stream))
+}
+
+/// Run the connection, and reconnect when it drops, until the client is
+/// closed or the reconnection attempts are exhausted.
+async fn run(
+    endpoint: Endpoint,
+    mut stream: Stream,
+    incoming_calls: mpsc::UnboundedSender<Call>,
+    shared: Arc<Shared>,
+    state: watch::Sender<ConnectionState>,
+) {
+    loop {
+        let closed = run_connection(stream, &incoming_calls, &shared, &endpoint.config).await;
+
+        // Cancelling the pending calls wakes up their callers with a
+        // `ConnectionClosed` error: their responses cannot arrive on
+        // another connection.
+        shared.pending_calls.cancel_all();
+
+        if !closed {
+            log::warn!(charge_point_id = endpoint.charge_point_id.as_str(); "connection dropped");
+        }
+
+        let Some(policy) = endpoint.config.reconnect.as_ref().filter(|_| !closed) else {
+            break;
+        };
+
+        let mut attempt = 0;
+
+        stream = loop {
+            attempt += 1;
+
+            if policy
+                .max_attempts
+                .is_some_and(|max_attempts| attempt > max_attempts)
+            {
+                state.send_replace(ConnectionState::Closed);
+
+                return;
+            }
+
+            state.send_replace(ConnectionState::Reconnecting { attempt });
+            log::info!(
+                charge_point_id = endpoint.charge_point_id.as_str(),
+                attempt = attempt;
+                "reconnecting"
+            );
+
+            let delay = time::sleep(policy.delay(attempt));
+            tokio::pin!(delay);
+
+            // The frames sent while waiting stay in the outgoing queue;
+            // stop if the client is closed.
+            tokio::select! {
+                _ = &mut delay => {}
+
+                _ = shared.outgoing.closed() => {
+                    state.send_replace(ConnectionState::Closed);
+
+                    return;
+                }
+            }
+
+            if let Ok(stream) = endpoint.open().await {
+                break stream;
+            }
+        };
+
+        log::info!(charge_point_id = endpoint.charge_point_id.as_str(); "reconnected");
+
+        #[cfg(feature = "metrics")]
+        shared.record(|metrics| metrics.record_reconnect());
+
+        state.send_replace(ConnectionState::Connected);
+    }
+
+    state.send_replace(ConnectionState::Closed);
+}
+
+/// Run a single connection. Returns `true` if the client has been closed,
+/// `false` if the connection has dropped.
+async fn run_connection(
+    stream: Stream,
+    incoming_calls: &mpsc::UnboundedSender<Call>,
+    shared: &Shared,
+    config: &ClientConfig,
+) -> bool {
+    let (mut sink, mut stream) = stream.split();
+    let mut reset = shared.reset.subscribe();
+
+    // The unique ID of the queued `Call` being sent, and when to send it
+    // again if it is not answered.
+    let mut in_flight = None;
+    let mut deadline = Instant::now();
+
+    // A `Heartbeat` is only sent when nothing else has been sent during
+    // the heartbeat interval.
+    let mut heartbeat_interval = shared.heartbeat.interval.subscribe();
+    let mut heartbeat_in_flight = None;
+    let mut last_sent = Instant::now();
+
+    let mut keep_alive = config
+        .keep_alive
+        .map(|keep_alive| KeepAliveTimer::new(keep_alive, Instant::now()));
+
+    // All the `Call`s, whatever their origin, wait for the responses to the
+    // previous ones.
+    let mut window = CallWindow::new(config.max_outstanding_calls, config.call_timeout);
+
+    loop {
+        if in_flight.is_none() && window.is_open() {
+            if let Ok(Some(call)) = shared.queue.front() {
+                in_flight = Some(call.unique_id.clone());
+                deadline = Instant::now() + config.call_timeout;
+                last_sent = Instant::now();
+                window.sent(call.unique_id.clone(), last_sent);
+
+                let frame = Frame::Text(Message::from(call).to_string());
+                shared.capture(ocppx_rpc::Direction::Outgoing, &frame);
+
+                if sink.send(frame).await.is_err() {
+                    return false;
+                }
+            }
+        }
+
+        let next_heartbeat = (*heartbeat_interval.borrow_and_update())
+            .filter(|_| config.heartbeat)
+            .map(|interval| last_sent + interval);
+
+        tokio::select! {
+            // Dropping the sink and the stream closes the socket.
+            _ = reset.changed() => return false,
+
+            _ = shared.queue_changed.notified(), if in_flight.is_none() => {}
+
+            _ = time::sleep_until(deadline), if in_flight.is_some() => {
+                in_flight = None;
+            }
+
+            _ = heartbeat_interval.changed() => {}
+
+            _ = time::sleep_until(window.deadline().unwrap_or(deadline)), if window.deadline().is_some() => {
+                window.expire(Instant::now());
+            }
+
+            _ = time::sleep_until(next_heartbeat.unwrap_or(deadline)), if next_heartbeat.is_some() && window.is_open() => {
+                let unique_id = shared.message_ids.next().into_string();
+                let Ok(call) = Call::new(unique_id.clone(), "Heartbeat", &serde_json::json!({})) else {
+                    continue;
+                };
+
+                heartbeat_in_flight = Some(unique_id.clone());
+                last_sent = Instant::now();
+                window.sent(unique_id, last_sent);
+
+                let frame = Frame::Text(Message::from(call).to_string());
+                shared.capture(ocppx_rpc::Direction::Outgoing, &frame);
+
+                if sink.send(frame).await.is_err() {
+                    return false;
+                }
+            }
+
+            _ = time::sleep_until(keep_alive.as_ref().map_or(deadline, KeepAliveTimer::deadline)), if keep_alive.is_some() => {
+                let now = Instant::now();
+
+                match keep_alive.as_mut().and_then(|keep_alive| keep_alive.poll(now)) {
+                    Some(KeepAliveAction::Ping) => {
+                        let sent = sink.send(Frame::Ping(Vec::new())).await;
+
+                        if sent.is_err() {
+                            return false;
+                        }
+                    }
+
+                    Some(KeepAliveAction::Stale) => {
+                        let silence = keep_alive.as_ref().map_or(Duration::ZERO, |keep_alive| keep_alive.silence(now));
+                        log::warn!(
+                            charge_point_id = shared.charge_point_id.as_str(),
+                            silence:? = silence;
+                            "connection stale"
+                        );
+
+                        // Nobody listening is fine.
+                        let _ = shared.events.send(ConnectionEvent::Stale {
+                            charge_point_id: shared.charge_point_id.clone(),
+                            silence,
+                        });
+
+                        return false;
+                    }
+
+                    None => {}
+                }
+            }
+
+            // The `Heartbeat`s and the `StatusNotification`s wait for the
+            // transaction-related `Call`s.
+            frame = shared.outgoing.pop(window.is_open(), shared.queue.is_empty()) => {
+                let Some((frame, call)) = frame else {
+                    let _ = sink.close().await;
+
+                    return true;
+                };
+
+                last_sent = Instant::now();
+
+                if let Some(unique_id) = call {
+                    window.sent(unique_id, last_sent);
+                }
+                shared.capture(ocppx_rpc::Direction::Outgoing, &frame);
+
+                if sink.send(frame).await.is_err() {
+                    return false;
+                }
+            }
+
+            frame = stream.next() => {
+                // Any frame, not only a pong, proves that the Central
+                // System is alive.
+                if let (Some(keep_alive), Some(Ok(_))) = (keep_alive.as_mut(), &frame) {
+                    keep_alive.received(Instant::now());
+                }
+
+                match frame {
+                    Some(Ok(Frame::Text(frame))) => {
+                        if let Some(recorder) = &shared.recorder {
+                            recorder.record(ocppx_rpc::Direction::Incoming, &shared.charge_point_id, &frame);
+                        }
+
+                        // Frames that cannot be parsed have no unique ID to
+                        // respond to, they are ignored.
+                        let Ok(message) = frame.parse::<Message>() else {
+                            continue;
+                        };
+
+                        match message {
+                            Message::Call(call) => {
+                                log::debug!(
+                                    charge_point_id = shared.charge_point_id.as_str(),
+                                    unique_id = call.unique_id.as_str(),
+                                    action = call.action.as_str();
+                                    "Call received"
+                                );
+
+                                #[cfg(feature = "metrics")]
+                                shared.record(|metrics| {
+                                    metrics.record_message(ocppx_rpc::Direction::Incoming, &call.action)
+                                });
+
+                                let _ = incoming_calls.send(call);
+                            }
+
+                            // Late responses, e.g. after a timeout, are
+                            // dropped.
+                            response => {
+                                window.received(response.unique_id());
+
+                                if in_flight.as_deref() == Some(response.unique_id()) {
+                                    // If the queue cannot be updated, the
+                                    // `Call` is sent again.
+                                    let _ = shared.queue.pop();
+                                    in_flight = None;
+                                }
+
+                                if heartbeat_in_flight.as_deref() == Some(response.unique_id()) {
+                                    heartbeat_in_flight = None;
+
+                                    if let Message::CallResult(call_result) = &response {
+                                        shared.heartbeat.update("Heartbeat", &call_result.payload);
+                                    }
+
+                                    continue;
+                                }
+
+                                let _ = shared.pending_calls.resolve(response);
+                            }
+                        }
+                    }
+
+                    // Pongs are queued by `tungstenite` when a ping is
+                    // received; flush to send them immediately.
+                    Some(Ok(Frame::Ping(_))) => {
+                        if sink.flush().await.is_err() {
+                            return false;
+                        }
+                    }
+
+                    Some(Ok(Frame::Close(_))) | Some(Err(_)) | None => return false,
+
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::ReconnectPolicy;
+    use ocppx_rpc::CallResult;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::{accept_hdr_async, tungstenite::handshake::server};
+
+    struct AcceptSubprotocol;
+
+    impl server::Callback for AcceptSubprotocol {
+        fn on_request(
+            self,
+            _request: &server::Request,
+            mut response: server::Response,
+        ) -> std::result::Result<server::Response, server::ErrorResponse> {
+            response.headers_mut().insert(
+                SEC_WEBSOCKET_PROTOCOL,
+                HeaderValue::from_static(SUBPROTOCOL),
+            );
+
+            Ok(response)
+        }
+    }
+
+    async fn accept(listener: &TcpListener) -> WebSocket {
+        let (stream, _) = listener.accept().await.unwrap();
+
+        accept_hdr_async(
+            Compressed::server(MaybeTlsStream::Plain(stream), None),
+            AcceptSubprotocol,
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            // The first connection drops immediately.
+            accept(&listener).await.close(None).await.unwrap();
+
+            let mut stream = accept(&listener).await;
+
+            // The call sent while disconnected is received once
+            // reconnected.
+            let frame = stream.next().await.unwrap().unwrap();
+            let call =
+                Call::try_from(frame.to_text().unwrap().parse::<Message>().unwrap()).unwrap();
+            assert_eq!(call.action, "Heartbeat");
+
+            let call_result = CallResult::new(call.unique_id, &serde_json::json!({})).unwrap();
+            stream
+                .send(Frame::Text(Message::from(call_result).to_string()))
+                .await
+                .unwrap();
+
+            stream
+        });
+
+        let client = ChargePointClient::connect_with_config(
+            &format!("ws://{address}/ocpp"),
+            "CP001",
+            ClientConfig {
+                reconnect: Some(ReconnectPolicy {
+                    initial_delay: Duration::from_millis(50),
+                    jitter: 0.,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let mut state = client.connection_state();
+        state
+            .wait_for(|state| *state == ConnectionState::Reconnecting { attempt: 1 })
+            .await
+            .unwrap();
+
+        let payload = serde_json::json!({});
+        let response = client.call::<_, serde_json::Value>("Heartbeat", &payload);
+        assert!(response.await.is_ok());
+
+        let _stream = server.await.unwrap();
+        assert_eq!(*state.borrow(), ConnectionState::Connected);
+
+        client.close().await.unwrap();
+        assert_eq!(*state.borrow(), ConnectionState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_send_raw_and_reset() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let client = tokio::spawn(async move {
+            ChargePointClient::connect_with_config(
+                &format!("ws://{address}/ocpp"),
+                "CP001",
+                ClientConfig {
+                    reconnect: Some(ReconnectPolicy {
+                        initial_delay: Duration::from_millis(50),
+                        jitter: 0.,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap()
+        });
+        let mut stream = accept(&listener).await;
+        let client = client.await.unwrap();
+
+        client.send_raw(r#"[2,"1","Heartbeat",{"#).unwrap();
+        let frame = stream.next().await.unwrap().unwrap();
+        assert_eq!(frame.to_text().unwrap(), r#"[2,"1","Heartbeat",{"#);
+
+        // The connection drops without a close frame, and the client
+        // reconnects.
+        client.reset();
+        assert!(!matches!(stream.next().await, Some(Ok(Frame::Close(_)))));
+
+        // A reset while disconnected does not drop the next connection.
+        client
+            .connection_state()
+            .wait_for(|state| *state != ConnectionState::Connected)
+            .await
+            .unwrap();
+        client.reset();
+
+        let mut stream = accept(&listener).await;
+        client
+            .connection_state()
+            .wait_for(|state| *state == ConnectionState::Connected)
+            .await
+            .unwrap();
+
+        client.send_raw("[]").unwrap();
+        let frame = stream.next().await.unwrap().unwrap();
+        assert_eq!(frame.to_text().unwrap(), "[]");
+    }
+
+    #[tokio::test]
+    async fn test_message_queue_is_replayed_in_order() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        // `Call`s queued, e.g., by a previous process.
+        let queue = Arc::new(MemoryQueue::default());
+        queue
+            .push(&Call::new("1", "StartTransaction", &serde_json::json!({})).unwrap())
+            .unwrap();
+        queue
+            .push(&Call::new("2", "StopTransaction", &serde_json::json!({})).unwrap())
+            .unwrap();
+
+        let server = tokio::spawn(async move {
+            let mut stream = accept(&listener).await;
+            let mut actions = Vec::new();
+
+            for _ in 0..2 {
+                let frame = stream.next().await.unwrap().unwrap();
+                let call =
+                    Call::try_from(frame.to_text().unwrap().parse::<Message>().unwrap()).unwrap();
+                actions.push(call.action);
+
+                let call_result = CallResult::new(call.unique_id, &serde_json::json!({})).unwrap();
+                stream
+                    .send(Frame::Text(Message::from(call_result).to_string()))
+                    .await
+                    .unwrap();
+            }
+
+            (stream, actions)
+        });
+
+        let client = ChargePointClient::connect_with_config(
+            &format!("ws://{address}/ocpp"),
+            "CP001",
+            ClientConfig {
+                message_queue: Some(queue.clone()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let (_stream, actions) = server.await.unwrap();
+        assert_eq!(actions, ["StartTransaction", "StopTransaction"]);
+
+        // The `Call`s are popped once answered.
+        time::timeout(Duration::from_secs(1), async {
+            while !queue.is_empty() {
+                time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        client.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_call_ordering() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let next_call = |frame: Frame| {
+            Call::try_from(frame.to_text().unwrap().parse::<Message>().unwrap()).unwrap()
+        };
+
+        let server = tokio::spawn(async move {
+            let mut stream = accept(&listener).await;
+
+            let first = next_call(stream.next().await.unwrap().unwrap());
+            assert_eq!(first.action, "Authorize");
+
+            // The second `Call` is held back, but the `Call`s of the
+            // Central System are still answered.
+            let call =
+                Call::new("server-1", "GetLocalListVersion", &serde_json::json!({})).unwrap();
+            stream
+                .send(Frame::Text(Message::from(call).to_string()))
+                .await
+                .unwrap();
+            let frame = stream.next().await.unwrap().unwrap();
+            let response = frame.to_text().unwrap().parse::<Message>().unwrap();
+            assert_eq!(response.unique_id(), "server-1");
+
+            let call_result = CallResult::new(first.unique_id, &serde_json::json!({})).unwrap();
+            stream
+                .send(Frame::Text(Message::from(call_result).to_string()))
+                .await
+                .unwrap();
+
+            let second = next_call(stream.next().await.unwrap().unwrap());
+            assert_eq!(second.action, "DataTransfer");
+
+            let call_result = CallResult::new(second.unique_id, &serde_json::json!({})).unwrap();
+            stream
+                .send(Frame::Text(Message::from(call_result).to_string()))
+                .await
+                .unwrap();
+
+            stream
+        });
+
+        let client = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001")
+            .await
+            .unwrap();
+
+        // The caller gives up on the first `Call`, which is still
+        // outstanding on the wire.
+        let payload = serde_json::json!({ "idTag": "ABC" });
+        let first = client.call::<_, serde_json::Value>("Authorize", &payload);
+        assert!(time::timeout(Duration::from_millis(50), first)
+            .await
+            .is_err());
+
+        let payload = serde_json::json!({ "vendorId": "ocppx" });
+        let second = client.call::<_, serde_json::Value>("DataTransfer", &payload);
+        let respond = async {
+            let call = client.next_call().await.unwrap();
+            // Give the second `Call` a chance to overtake the response.
+            time::sleep(Duration::from_millis(50)).await;
+            let payload = serde_json::json!({ "listVersion": 1 });
+            client
+                .respond(CallResult::new(call.unique_id, &payload).unwrap())
+                .unwrap();
+
+            server.await.unwrap()
+        };
+        let (second, _stream) = tokio::join!(second, respond);
+        assert!(second.is_ok());
+
+        client.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let mut stream = accept(&listener).await;
+            let mut actions = Vec::new();
+
+            for payload in [
+                serde_json::json!({
+                    "status": "Accepted",
+                    "currentTime": "2013-02-01T20:53:32.486Z",
+                    "interval": 1,
+                }),
+                serde_json::json!({ "currentTime": "2013-02-01T20:53:33.486Z" }),
+            ] {
+                let frame = stream.next().await.unwrap().unwrap();
+                let call =
+                    Call::try_from(frame.to_text().unwrap().parse::<Message>().unwrap()).unwrap();
+                actions.push(call.action);
+
+                let call_result = CallResult::new(call.unique_id, &payload).unwrap();
+                stream
+                    .send(Frame::Text(Message::from(call_result).to_string()))
+                    .await
+                    .unwrap();
+            }
+
+            (stream, actions)
+        });
+
+        let client = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001")
+            .await
+            .unwrap();
+
+        client
+            .call::<_, serde_json::Value>(
+                "BootNotification",
+                &serde_json::json!({
+                    "chargePointVendor": "ocppx",
+                    "chargePointModel": "Test",
+                }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(client.heartbeat_interval(), Some(Duration::from_secs(1)));
+
+        let (_stream, actions) = server.await.unwrap();
+        assert_eq!(actions, ["BootNotification", "Heartbeat"]);
+        assert!(client.now() < "2014-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+
+        client.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_keep_alive() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let mut stream = accept(&listener).await;
+
+            // Reading the ping answers it.
+            assert!(matches!(stream.next().await, Some(Ok(Frame::Ping(_)))));
+
+            // Not reading anymore does not: the Central System looks dead.
+            stream
+        });
+
+        let client = ChargePointClient::connect_with_config(
+            &format!("ws://{address}/ocpp"),
+            "CP001",
+            ClientConfig {
+                keep_alive: Some(ocppx_rpc::KeepAlive {
+                    ping_interval: Duration::from_millis(50),
+                    pong_timeout: Duration::from_millis(50),
+                }),
+                reconnect: None,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let mut events = client.connection_events();
+        let _stream = server.await.unwrap();
+
+        assert!(matches!(
+            events.recv().await,
+            Ok(ConnectionEvent::Stale { charge_point_id, silence })
+                if charge_point_id == "CP001" && silence >= Duration::from_millis(100)
+        ));
+
+        let mut state = client.connection_state();
+        state
+            .wait_for(|state| *state == ConnectionState::Closed)
+            .await
+            .unwrap();
+    }
+}
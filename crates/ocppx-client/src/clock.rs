@@ -0,0 +1,96 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+/// Where the timestamps come from.
+pub trait ClockSource: fmt::Debug + Send + Sync {
+    /// The current time.
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The clock of the system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl ClockSource for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// The clock of the system, off by `skew`, e.g. to simulate a Charge
+/// Point whose clock is wrong.
+#[derive(Debug, Clone, Copy)]
+pub struct SkewedClock {
+    pub skew: TimeDelta,
+}
+
+impl ClockSource for SkewedClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now() + self.skew
+    }
+}
+
+/// A local clock, adjusted to the clock of the Central System.
+///
+/// The offset is learned from the `currentTime` of the `BootNotification`
+/// and `Heartbeat` responses, so that the timestamps sent are coherent
+/// with the Central System even if the local clock is wrong.
+#[derive(Debug)]
+pub struct SyncedClock {
+    local: Arc<dyn ClockSource>,
+    /// Difference between the clock of the Central System and the local
+    /// clock.
+    offset: Mutex<TimeDelta>,
+}
+
+impl SyncedClock {
+    pub fn new(local: Arc<dyn ClockSource>) -> Self {
+        Self {
+            local,
+            offset: Mutex::new(TimeDelta::zero()),
+        }
+    }
+
+    /// The Central System says that it is `current_time`.
+    pub fn sync(&self, current_time: DateTime<Utc>) {
+        *self.offset.lock().unwrap() = current_time - self.local.now();
+    }
+
+    /// Difference between the clock of the Central System and the local
+    /// clock, zero until synced.
+    pub fn offset(&self) -> TimeDelta {
+        *self.offset.lock().unwrap()
+    }
+}
+
+impl Default for SyncedClock {
+    fn default() -> Self {
+        Self::new(Arc::new(SystemClock))
+    }
+}
+
+impl ClockSource for SyncedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.local.now() + self.offset()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synced_clock() {
+        // The local clock is a year late.
+        let skew = -TimeDelta::days(365);
+        let clock = SyncedClock::new(Arc::new(SkewedClock { skew }));
+        assert!(clock.now() < Utc::now() - TimeDelta::days(364));
+
+        clock.sync(Utc::now());
+        assert!((clock.offset() + skew).abs() < TimeDelta::seconds(1));
+        assert!((clock.now() - Utc::now()).abs() < TimeDelta::seconds(1));
+    }
+}
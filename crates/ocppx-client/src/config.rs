@@ -0,0 +1,96 @@
+use crate::{MessageQueue, ReconnectPolicy};
+use ocppx_rpc::{MessageIds, DEFAULT_CALL_TIMEOUT, DEFAULT_MAX_OUTSTANDING_CALLS};
+use std::{sync::Arc, time::Duration};
+
+/// Configuration of a [`ChargePointClient`][crate::ChargePointClient].
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    /// Time to wait for the response to a `Call` before giving up.
+    pub call_timeout: Duration,
+    /// Number of `Call`s that can wait for a response at once. OCPP-J
+    /// allows a single one: a `Call` is not sent before the previous one
+    /// is answered, or has timed out. More pipelines the `Call`s, for the
+    /// tolerant Central Systems.
+    pub max_outstanding_calls: usize,
+    /// Generator of the unique IDs of the `Call`s, counting from the
+    /// current time by default so that they differ from the ones of the
+    /// `Call`s queued by a previous process. A generated ID still waiting
+    /// for a response is skipped.
+    pub message_ids: MessageIds,
+    /// WebSocket subprotocol to negotiate, [`SUBPROTOCOL`] by default. The
+    /// client does not translate the messages: changing it is only useful
+    /// to relay the messages of another OCPP version.
+    ///
+    /// [`SUBPROTOCOL`]: crate::SUBPROTOCOL
+    pub subprotocol: &'static str,
+    /// Password sent with HTTP Basic Authentication in the WebSocket
+    /// handshake, for the security profiles 1 and 2. The username is the
+    /// Charge Point identity.
+    pub basic_auth_password: Option<String>,
+    /// How to reconnect when the connection drops, or `None` to stay
+    /// disconnected.
+    pub reconnect: Option<ReconnectPolicy>,
+    /// Send `Heartbeat`s at the interval given by the Central System in
+    /// the `BootNotification` response, when nothing else is sent.
+    pub heartbeat: bool,
+    /// Ping the Central System at the WebSocket level, and reconnect if it
+    /// stops answering, see
+    /// [`ChargePointClient::connection_events`][crate::ChargePointClient::connection_events].
+    pub keep_alive: Option<ocppx_rpc::KeepAlive>,
+    /// Queue of the transaction-related `Call`s, e.g. a [`FileQueue`] to
+    /// keep them across restarts. A [`MemoryQueue`] is used when `None`.
+    ///
+    /// [`FileQueue`]: crate::FileQueue
+    /// [`MemoryQueue`]: crate::MemoryQueue
+    pub message_queue: Option<Arc<dyn MessageQueue>>,
+    /// Priorities and bounds of the frames waiting to be sent, other than
+    /// the ones of the [`ClientConfig::message_queue`].
+    pub outgoing_queue: crate::OutgoingQueueConfig,
+    /// Proxy to connect to the Central System through.
+    pub proxy: Option<crate::Proxy>,
+    /// When [`ClientConfig::proxy`] is `None`, use the proxy given by the
+    /// `HTTPS_PROXY`, `HTTP_PROXY`, `ALL_PROXY` and `NO_PROXY` environment
+    /// variables, see [`Proxy::from_env`][crate::Proxy::from_env].
+    pub proxy_from_env: bool,
+    /// TLS configuration, required to connect to `wss://` URLs.
+    #[cfg(feature = "tls")]
+    pub tls: Option<crate::TlsConfig>,
+    /// Where to count the OCPP traffic, and the reconnections.
+    #[cfg(feature = "metrics")]
+    pub metrics: Option<ocppx_rpc::Metrics>,
+    /// Where to capture the frames received and sent.
+    pub recorder: Option<ocppx_rpc::Recorder>,
+    /// Offer the `permessage-deflate` WebSocket extension to the Central
+    /// System. The messages are sent as they are when `None`.
+    pub compression: Option<ocppx_rpc::Compression>,
+    /// The local clock, adjusted to the clock of the Central System to
+    /// timestamp the messages, see
+    /// [`ChargePointClient::now`][crate::ChargePointClient::now].
+    pub clock: Arc<dyn crate::ClockSource>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            call_timeout: DEFAULT_CALL_TIMEOUT,
+            max_outstanding_calls: DEFAULT_MAX_OUTSTANDING_CALLS,
+            message_ids: MessageIds::counter_from_now(),
+            subprotocol: crate::SUBPROTOCOL,
+            basic_auth_password: None,
+            reconnect: Some(ReconnectPolicy::default()),
+            heartbeat: true,
+            keep_alive: None,
+            message_queue: None,
+            outgoing_queue: crate::OutgoingQueueConfig::default(),
+            proxy: None,
+            proxy_from_env: false,
+            #[cfg(feature = "tls")]
+            tls: None,
+            #[cfg(feature = "metrics")]
+            metrics: None,
+            recorder: None,
+            compression: None,
+            clock: Arc::new(crate::SystemClock),
+        }
+    }
+}
@@ -0,0 +1,488 @@
+use ocppx_types::{
+    v1_6::{
+        ChangeConfigurationRequest, ChangeConfigurationResponse, ChangeConfigurationStatus,
+        ConfigurationKey, GetConfigurationRequest, GetConfigurationResponse,
+    },
+    CiString50, CiString500,
+};
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// The maximum length of a configuration value.
+const MAX_VALUE_LENGTH: usize = 500;
+
+/// The type of the value of a configuration key.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ValueKind {
+    /// `true` or `false`.
+    Boolean,
+    /// A non-negative integer.
+    Integer,
+    /// Any string.
+    String,
+    /// A comma-separated list of strings.
+    CsvList,
+}
+
+impl ValueKind {
+    /// Normalize `value`, or return `None` if it is not of this kind.
+    fn parse(&self, value: &str) -> Option<String> {
+        let value = value.trim();
+
+        match self {
+            Self::Boolean => match value.to_ascii_lowercase().as_str() {
+                boolean @ ("true" | "false") => Some(boolean.to_owned()),
+                _ => None,
+            },
+            Self::Integer => value.parse::<u32>().ok().map(|integer| integer.to_string()),
+            Self::String => Some(value.to_owned()),
+            Self::CsvList => Some(
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+        }
+    }
+}
+
+/// The definition of a configuration key.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct KeyDefinition {
+    pub key: &'static str,
+    pub kind: ValueKind,
+    /// Whether the Central System can change the value.
+    pub readonly: bool,
+    /// Whether a changed value only takes effect after a reboot.
+    pub reboot_required: bool,
+    pub default: &'static str,
+}
+
+macro_rules! standard_keys {
+    ( $( $key:ident: $kind:ident $( $flag:ident )* = $default:literal ),* $(,)? ) => {
+        /// The standard configuration keys of OCPP 1.6, with their default
+        /// value.
+        pub const STANDARD_KEYS: &[KeyDefinition] = &[
+            $(
+                KeyDefinition {
+                    key: stringify!($key),
+                    kind: ValueKind::$kind,
+                    readonly: standard_keys!(@readonly $( $flag )*),
+                    reboot_required: false,
+                    default: $default,
+                },
+            )*
+        ];
+    };
+
+    (@readonly readonly) => { true };
+    (@readonly) => { false };
+}
+
+standard_keys! {
+    // Core.
+    AllowOfflineTxForUnknownId: Boolean = "false",
+    AuthorizationCacheEnabled: Boolean = "true",
+    AuthorizeRemoteTxRequests: Boolean = "true",
+    ClockAlignedDataInterval: Integer = "0",
+    ConnectionTimeOut: Integer = "60",
+    ConnectorPhaseRotation: CsvList = "0.RST",
+    GetConfigurationMaxKeys: Integer readonly = "100",
+    HeartbeatInterval: Integer = "300",
+    LocalAuthorizeOffline: Boolean = "true",
+    LocalPreAuthorize: Boolean = "false",
+    MeterValuesAlignedData: CsvList = "Energy.Active.Import.Register",
+    MeterValuesSampledData: CsvList = "Energy.Active.Import.Register",
+    MeterValueSampleInterval: Integer = "60",
+    MinimumStatusDuration: Integer = "0",
+    NumberOfConnectors: Integer readonly = "1",
+    ResetRetries: Integer = "3",
+    StopTransactionOnEVSideDisconnect: Boolean = "true",
+    StopTransactionOnInvalidId: Boolean = "true",
+    StopTxnAlignedData: CsvList = "",
+    StopTxnSampledData: CsvList = "",
+    SupportedFeatureProfiles: CsvList readonly =
+        "Core,FirmwareManagement,LocalAuthListManagement,Reservation,SmartCharging,RemoteTrigger",
+    TransactionMessageAttempts: Integer = "3",
+    TransactionMessageRetryInterval: Integer = "60",
+    UnlockConnectorOnEVSideDisconnect: Boolean = "true",
+    WebSocketPingInterval: Integer = "0",
+    // Local Auth List Management.
+    LocalAuthListEnabled: Boolean = "true",
+    LocalAuthListMaxLength: Integer readonly = "1000",
+    SendLocalListMaxLength: Integer readonly = "100",
+    // Reservation.
+    ReserveConnectorZeroSupported: Boolean readonly = "false",
+    // Smart Charging.
+    ChargeProfileMaxStackLevel: Integer readonly = "10",
+    ChargingScheduleAllowedChargingRateUnit: CsvList readonly = "Current,Power",
+    ChargingScheduleMaxPeriods: Integer readonly = "24",
+    MaxChargingProfilesInstalled: Integer readonly = "10",
+}
+
+/// Persist the configuration values changed by the Central System, so
+/// that they survive a restart.
+///
+/// `()` persists nothing.
+pub trait ConfigurationPersistence: fmt::Debug + Send + Sync + 'static {
+    /// The values saved so far.
+    fn load(&self) -> io::Result<Vec<(String, String)>>;
+
+    /// Save the new value of `key`.
+    fn save(&self, key: &str, value: &str) -> io::Result<()>;
+}
+
+impl ConfigurationPersistence for () {
+    fn load(&self) -> io::Result<Vec<(String, String)>> {
+        Ok(Vec::new())
+    }
+
+    fn save(&self, _key: &str, _value: &str) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A [`ConfigurationPersistence`] in a file, one `key=value` per line.
+#[derive(Debug)]
+pub struct FileConfiguration {
+    path: PathBuf,
+    values: Mutex<BTreeMap<String, String>>,
+}
+
+impl FileConfiguration {
+    /// Open the configuration stored at `path`, if any.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+        let mut values = BTreeMap::new();
+
+        match fs::File::open(&path) {
+            Ok(file) => {
+                for line in BufReader::new(file).lines() {
+                    if let Some((key, value)) = line?.split_once('=') {
+                        values.insert(key.to_owned(), value.to_owned());
+                    }
+                }
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error),
+        }
+
+        Ok(Self {
+            path,
+            values: Mutex::new(values),
+        })
+    }
+}
+
+impl ConfigurationPersistence for FileConfiguration {
+    fn load(&self) -> io::Result<Vec<(String, String)>> {
+        Ok(self
+            .values
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect())
+    }
+
+    fn save(&self, key: &str, value: &str) -> io::Result<()> {
+        let mut values = self.values.lock().unwrap();
+        values.insert(key.to_owned(), value.to_owned());
+
+        // Rewrite the values atomically.
+        let temporary_path = self.path.with_extension("tmp");
+        let mut file = fs::File::create(&temporary_path)?;
+
+        for (key, value) in values.iter() {
+            writeln!(file, "{key}={value}")?;
+        }
+
+        file.sync_data()?;
+        fs::rename(temporary_path, &self.path)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    kind: ValueKind,
+    readonly: bool,
+    reboot_required: bool,
+    value: String,
+}
+
+/// The configuration keys of a Charge Point, read by the Central System
+/// with `GetConfiguration`, and changed with `ChangeConfiguration`.
+#[derive(Debug)]
+pub struct ConfigurationStore {
+    entries: BTreeMap<String, Entry>,
+    persistence: Box<dyn ConfigurationPersistence>,
+}
+
+impl Default for ConfigurationStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConfigurationStore {
+    /// Create a store with the [`STANDARD_KEYS`], set to their default
+    /// value.
+    pub fn new() -> Self {
+        let mut store = Self {
+            entries: BTreeMap::new(),
+            persistence: Box::new(()),
+        };
+
+        for definition in STANDARD_KEYS {
+            store.define(*definition);
+        }
+
+        store
+    }
+
+    /// Create a store with the [`STANDARD_KEYS`], set to the values saved
+    /// by `persistence`, or to their default value. The changes are saved
+    /// by `persistence`.
+    pub fn with_persistence<P>(persistence: P) -> io::Result<Self>
+    where
+        P: ConfigurationPersistence,
+    {
+        let mut store = Self::new();
+
+        for (key, value) in persistence.load()? {
+            if let Some(entry) = store.entries.get_mut(&key) {
+                entry.value = value;
+            }
+        }
+
+        store.persistence = Box::new(persistence);
+
+        Ok(store)
+    }
+
+    /// Define a key, e.g. a vendor-specific one, or redefine a standard
+    /// key.
+    pub fn define(&mut self, definition: KeyDefinition) {
+        self.entries.insert(
+            definition.key.to_owned(),
+            Entry {
+                kind: definition.kind,
+                readonly: definition.readonly,
+                reboot_required: definition.reboot_required,
+                value: definition.default.to_owned(),
+            },
+        );
+    }
+
+    /// The value of `key`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(|entry| entry.value.as_str())
+    }
+
+    /// The value of the boolean `key`.
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        self.get(key)?.parse().ok()
+    }
+
+    /// The value of the integer `key`.
+    pub fn get_integer(&self, key: &str) -> Option<u32> {
+        self.get(key)?.parse().ok()
+    }
+
+    /// The items of the comma-separated list `key`.
+    pub fn get_list(&self, key: &str) -> Option<Vec<&str>> {
+        Some(
+            self.get(key)?
+                .split(',')
+                .filter(|item| !item.is_empty())
+                .collect(),
+        )
+    }
+
+    /// Set the value of `key` on behalf of the Charge Point itself: the
+    /// key may be read-only, and the value is not persisted.
+    pub fn set(&mut self, key: &str, value: impl Into<String>) {
+        if let Some(entry) = self.entries.get_mut(key) {
+            entry.value = value.into();
+        }
+    }
+
+    /// Handle a `GetConfiguration`: all the keys when the request names
+    /// none.
+    pub fn get_configuration(&self, request: &GetConfigurationRequest) -> GetConfigurationResponse {
+        // The keys that are not `CiString50`s are left out, and so are the
+        // values that are not `CiString500`s.
+        let configuration_key = |(key, entry): (&String, &Entry)| {
+            Some(
+                ConfigurationKey::builder()
+                    .key(CiString50::try_from(key.as_str()).ok()?)
+                    .readonly(entry.readonly)
+                    .value_opt(CiString500::try_from(entry.value.as_str()).ok())
+                    .build(),
+            )
+        };
+
+        match &request.key {
+            Some(keys) if !keys.is_empty() => {
+                let (known, unknown): (Vec<_>, Vec<_>) = keys
+                    .iter()
+                    .partition(|key| self.entries.contains_key(key.as_str()));
+
+                GetConfigurationResponse::builder()
+                    .configuration_key(
+                        known
+                            .into_iter()
+                            .filter_map(|key| self.entries.get_key_value(key.as_str()))
+                            .filter_map(configuration_key)
+                            .collect::<Vec<_>>(),
+                    )
+                    .unknown_key_opt(
+                        (!unknown.is_empty()).then(|| unknown.into_iter().cloned().collect()),
+                    )
+                    .build()
+            }
+            _ => GetConfigurationResponse::builder()
+                .configuration_key(
+                    self.entries
+                        .iter()
+                        .filter_map(configuration_key)
+                        .collect::<Vec<_>>(),
+                )
+                .build(),
+        }
+    }
+
+    /// Handle a `ChangeConfiguration`.
+    ///
+    /// The value is rejected if the key is read-only, if it is not of the
+    /// type of the key, if it is a list longer than allowed by the
+    /// `<key>MaxLength` key, or if it cannot be persisted.
+    pub fn change_configuration(
+        &mut self,
+        request: &ChangeConfigurationRequest,
+    ) -> ChangeConfigurationResponse {
+        let status = |status| {
+            ChangeConfigurationResponse::builder()
+                .status(status)
+                .build()
+        };
+
+        let Some(entry) = self.entries.get(request.key.as_str()) else {
+            return status(ChangeConfigurationStatus::NotSupported);
+        };
+
+        let max_length = self.get_integer(&format!("{}MaxLength", request.key));
+
+        let Some(value) = entry
+            .kind
+            .parse(&request.value)
+            .filter(|value| value.len() <= MAX_VALUE_LENGTH)
+            .filter(|value| {
+                entry.kind != ValueKind::CsvList
+                    || max_length.is_none_or(|max_length| {
+                        value.split(',').filter(|item| !item.is_empty()).count()
+                            <= max_length as usize
+                    })
+            })
+            .filter(|_| !entry.readonly)
+        else {
+            return status(ChangeConfigurationStatus::Rejected);
+        };
+
+        if self.persistence.save(&request.key, &value).is_err() {
+            return status(ChangeConfigurationStatus::Rejected);
+        }
+
+        let reboot_required = entry.reboot_required;
+        self.set(&request.key, value);
+
+        status(if reboot_required {
+            ChangeConfigurationStatus::RebootRequired
+        } else {
+            ChangeConfigurationStatus::Accepted
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(store: &mut ConfigurationStore, key: &str, value: &str) -> ChangeConfigurationStatus {
+        store
+            .change_configuration(
+                &ChangeConfigurationRequest::builder()
+                    .key(CiString50::try_from(key).unwrap())
+                    .value(CiString500::try_from(value).unwrap())
+                    .build(),
+            )
+            .status
+    }
+
+    #[test]
+    fn test_change_configuration() {
+        let path = std::env::temp_dir().join(format!("ocppx-configuration-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        {
+            let mut store =
+                ConfigurationStore::with_persistence(FileConfiguration::open(&path).unwrap())
+                    .unwrap();
+            store.define(KeyDefinition {
+                key: "VendorMode",
+                kind: ValueKind::String,
+                readonly: false,
+                reboot_required: true,
+                default: "eco",
+            });
+
+            assert_eq!(
+                change(&mut store, "HeartbeatInterval", "60"),
+                ChangeConfigurationStatus::Accepted
+            );
+            assert_eq!(
+                change(&mut store, "HeartbeatInterval", "often"),
+                ChangeConfigurationStatus::Rejected
+            );
+            assert_eq!(
+                change(&mut store, "NumberOfConnectors", "2"),
+                ChangeConfigurationStatus::Rejected
+            );
+            assert_eq!(
+                change(&mut store, "Unknown", "1"),
+                ChangeConfigurationStatus::NotSupported
+            );
+            assert_eq!(
+                change(&mut store, "VendorMode", "boost"),
+                ChangeConfigurationStatus::RebootRequired
+            );
+            assert_eq!(store.get_integer("HeartbeatInterval"), Some(60));
+        }
+
+        // The changes survive a restart.
+        let store =
+            ConfigurationStore::with_persistence(FileConfiguration::open(&path).unwrap()).unwrap();
+        let response = store.get_configuration(
+            &GetConfigurationRequest::builder()
+                .key(vec![
+                    CiString50::try_from("HeartbeatInterval").unwrap(),
+                    CiString50::try_from("Unknown").unwrap(),
+                ])
+                .build(),
+        );
+        let configuration_key = response.configuration_key.unwrap();
+
+        assert_eq!(configuration_key.len(), 1);
+        assert_eq!(configuration_key[0].value.as_deref(), Some("60"));
+        assert_eq!(response.unknown_key.unwrap(), ["Unknown"]);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{ClockSource, SyncedClock};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::{sync::Arc, time::Duration};
+use tokio::sync::watch;
+
+/// The heartbeat interval and the clock, both given by the Central System.
+#[derive(Debug)]
+pub(crate) struct Heartbeat {
+    /// The interval from the last accepted `BootNotification`, if any.
+    pub(crate) interval: watch::Sender<Option<Duration>>,
+    /// The local clock, synced with the Central System.
+    pub(crate) clock: Arc<SyncedClock>,
+}
+
+impl Heartbeat {
+    pub(crate) fn new(local_clock: Arc<dyn ClockSource>) -> Self {
+        Self {
+            interval: watch::Sender::new(None),
+            clock: Arc::new(SyncedClock::new(local_clock)),
+        }
+    }
+
+    /// The current time, according to the Central System.
+    pub(crate) fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    /// Update the interval and the clock from the response to an `action`
+    /// `Call`.
+    pub(crate) fn update(&self, action: &str, payload: &Value) {
+        if let Some(current_time) = payload
+            .get("currentTime")
+            .and_then(Value::as_str)
+            .and_then(|current_time| current_time.parse::<DateTime<Utc>>().ok())
+        {
+            self.clock.sync(current_time);
+        }
+
+        // The interval of a pending or rejected `BootNotification` is the
+        // delay before the next `BootNotification`, not a heartbeat
+        // interval.
+        if action == "BootNotification"
+            && payload.get("status").and_then(Value::as_str) == Some("Accepted")
+        {
+            if let Some(interval) = payload.get("interval").and_then(Value::as_u64) {
+                self.interval
+                    .send_replace((interval > 0).then(|| Duration::from_secs(interval)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_update() {
+        let heartbeat = Heartbeat::new(Arc::new(crate::SystemClock));
+
+        heartbeat.update(
+            "BootNotification",
+            &json!({"status": "Pending", "currentTime": "2013-02-01T20:53:32.486Z", "interval": 10}),
+        );
+        assert_eq!(*heartbeat.interval.borrow(), None);
+        assert!(heartbeat.now() < "2014-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+
+        heartbeat.update(
+            "BootNotification",
+            &json!({"status": "Accepted", "currentTime": "2013-02-01T20:53:32.486Z", "interval": 300}),
+        );
+        assert_eq!(*heartbeat.interval.borrow(), Some(Duration::from_secs(300)));
+    }
+}
@@ -0,0 +1,159 @@
+//! An OCPP-J 1.6 Charge Point client.
+//!
+//! [`ChargePointClient`] connects to a Central System over WebSocket,
+//! negotiates the `ocpp1.6` subprotocol, and exposes typed methods to
+//! send the Charge Point-initiated messages, e.g.
+//! [`ChargePointClient::send_boot_notification`].
+//!
+//! With the `tls` feature (enabled by default), `wss://` URLs are supported
+//! through [`ClientConfig::tls`], see `TlsConfig` for the security profiles
+//! presets.
+//!
+//! With the `metrics` feature, the traffic and the reconnections are
+//! counted in `ClientConfig::metrics`, to be scraped by Prometheus.
+//!
+//! With the `certificates` feature (enabled by default), the root
+//! certificates and the certificate of the Charge Point are managed by the
+//! Central System through a [`CertificateManager`], stored in a
+//! [`KeyStore`].
+//!
+//! Other carriers than WebSocket, e.g. plain TCP or a serial line, are
+//! plugged in with [`ChargePointClient::connect_with_connector`].
+//!
+//! Behind a corporate network, the client connects through an HTTP
+//! `CONNECT` or a SOCKS5 [`Proxy`], see [`ClientConfig::proxy`].
+//!
+//! The frames waiting to be sent, e.g. while disconnected, are queued by
+//! priority, see [`ClientConfig::outgoing_queue`].
+//!
+//! The timestamps come from a [`ClockSource`], synced with the
+//! `currentTime` given by the Central System, see
+//! [`ChargePointClient::now`].
+//!
+//! The frames received and sent can be captured with
+//! [`ClientConfig::recorder`], to be replayed with `ocppx_rpc::Replayer`.
+//!
+//! With the `native` feature (enabled by default), the client opens its
+//! own TCP connections, see [`ChargePointClient::connect`]. Without it,
+//! e.g. on `wasm32`, the transports come from an `ocppx_rpc::Connector`.
+//!
+//! With the `wasm` feature, a Charge Point running in a browser, or in a
+//! webview, connects with the `WebSocket` API of the browser through a
+//! [`BrowserConnector`], without the default features:
+//!
+//! ```sh
+//! cargo build --target wasm32-unknown-unknown --no-default-features --features wasm
+//! ```
+//!
+//! With the `test-utils` feature, the logic of a Charge Point is tested
+//! against a `mock::MockCsms`, an in-process Central System.
+
+mod auth_list;
+#[cfg(feature = "wasm")]
+mod browser;
+#[cfg(feature = "certificates")]
+mod certificates;
+mod client;
+mod clock;
+mod config;
+mod configuration;
+mod heartbeat;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod mock;
+mod outgoing;
+mod proxy;
+mod queue;
+mod reconnect;
+mod reservation;
+#[cfg(feature = "tls")]
+mod tls;
+
+pub use auth_list::{authorize_offline, AuthorizationCache, LocalAuthList};
+#[cfg(feature = "wasm")]
+pub use browser::BrowserConnector;
+#[cfg(feature = "certificates")]
+pub use certificates::{CertificateManager, FileKeyStore, KeyStore};
+pub use client::{ChargePointClient, SUBPROTOCOL};
+pub use clock::{ClockSource, SkewedClock, SyncedClock, SystemClock};
+pub use config::ClientConfig;
+pub use configuration::{
+    ConfigurationPersistence, ConfigurationStore, FileConfiguration, KeyDefinition, ValueKind,
+    STANDARD_KEYS,
+};
+pub use outgoing::{OutgoingQueueConfig, Overflow, QueueLimit};
+pub use proxy::{Proxy, ProxyAuth, ProxyKind};
+pub use queue::{FileQueue, MemoryQueue, MessageQueue, QUEUED_ACTIONS};
+pub use reconnect::{ConnectionState, ReconnectPolicy};
+pub use reservation::{Reservation, ReservationManager};
+#[cfg(feature = "tls")]
+pub use rustls;
+use std::time::Duration;
+use thiserror::Error;
+#[cfg(feature = "tls")]
+pub use tls::TlsConfig;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("I/O error")]
+    Io(#[from] std::io::Error),
+
+    #[error("WebSocket error")]
+    WebSocket(#[from] Box<tokio_tungstenite::tungstenite::Error>),
+
+    #[error("RPC error")]
+    Rpc(#[from] ocppx_rpc::Error),
+
+    #[error("invalid unique ID")]
+    MessageId(#[from] ocppx_rpc::MessageIdError),
+
+    #[cfg(feature = "tls")]
+    #[error("TLS error")]
+    Tls(#[from] rustls::Error),
+
+    #[cfg(feature = "tls")]
+    #[error("invalid server name `{0}`")]
+    InvalidServerName(String),
+
+    #[cfg(feature = "certificates")]
+    #[error("certificate error")]
+    Certificate(#[from] rcgen::Error),
+
+    #[error("invalid proxy URL `{0}`")]
+    InvalidProxyUrl(String),
+
+    #[error("the proxy refused the connection: {0}")]
+    Proxy(String),
+
+    #[error("the Central System did not accept the `{0}` subprotocol")]
+    SubprotocolNotNegotiated(&'static str),
+
+    #[error("the Central System rejected the credentials")]
+    Unauthorized,
+
+    #[error("the connection is closed")]
+    ConnectionClosed,
+
+    #[error("the `{0}` Call has been dropped from the outgoing queue")]
+    Dropped(String),
+
+    #[error("the Central System did not respond to `{action}` after {timeout:?}")]
+    Timeout { action: String, timeout: Duration },
+
+    #[error("the Central System responded with an error: `{}`", .0.error_code)]
+    CallError(ocppx_rpc::CallError),
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for Error {
+    fn from(error: tokio_tungstenite::tungstenite::Error) -> Self {
+        use tokio_tungstenite::tungstenite::{http::StatusCode, Error};
+
+        match error {
+            Error::Http(response) if response.status() == StatusCode::UNAUTHORIZED => {
+                Self::Unauthorized
+            }
+            error => Self::WebSocket(Box::new(error)),
+        }
+    }
+}
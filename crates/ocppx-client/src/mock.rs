@@ -0,0 +1,608 @@
+//! An in-process Central System, to unit-test the logic of a Charge Point
+//! without a network.
+//!
+//! A [`MockCsms`] answers the `Call`s of the clients connected with
+//! [`MockCsms::connect`] with the [`MockResponse`]s enqueued for their
+//! action, or with a default response accepting everything. The `Call`s it
+//! receives are kept, to be asserted with [`CallMatcher`]s.
+
+use crate::{ChargePointClient, ClientConfig, Result};
+use chrono::{SecondsFormat, Utc};
+use futures_util::{SinkExt, StreamExt};
+use ocppx_rpc::{
+    Call, CallError, CallResult, ErrorCode, Frame, MemoryTransport, Message, TransportError,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    sync::{
+        atomic::{AtomicI32, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::{mpsc, oneshot, watch},
+    task::JoinHandle,
+};
+
+/// Time to wait for a `Call` or a response before failing an assertion.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A canned response of a [`MockCsms`].
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    response: std::result::Result<Value, (ErrorCode, String)>,
+    delay: Duration,
+}
+
+impl MockResponse {
+    /// A `CallResult` with a typed payload, e.g.
+    /// `ocppx_types::v1_6::AuthorizeResponse`, or a `serde_json::Value`.
+    pub fn result<P>(payload: &P) -> Self
+    where
+        P: Serialize,
+    {
+        Self {
+            response: Ok(serde_json::to_value(payload).expect("the payload is serializable")),
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// A `CallError`.
+    pub fn error(error_code: ErrorCode, error_description: impl Into<String>) -> Self {
+        Self {
+            response: Err((error_code, error_description.into())),
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// Send the response after `delay`, e.g. to trigger the timeout of the
+    /// client.
+    pub fn delayed(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+
+        self
+    }
+}
+
+/// A matcher of the `Call`s received by a [`MockCsms`].
+#[derive(Clone)]
+pub struct CallMatcher {
+    action: String,
+    payload: Option<Value>,
+    predicate: Option<Arc<Predicate>>,
+}
+
+type Predicate = dyn Fn(&Call) -> bool + Send + Sync;
+
+impl CallMatcher {
+    /// Match the `Call`s of `action`.
+    pub fn action(action: impl Into<String>) -> Self {
+        Self {
+            action: action.into(),
+            payload: None,
+            predicate: None,
+        }
+    }
+
+    /// Match the `Call`s whose payload contains `payload`: the properties
+    /// of its objects must be in the payload, with the same values.
+    pub fn payload<P>(mut self, payload: &P) -> Self
+    where
+        P: Serialize,
+    {
+        self.payload = Some(serde_json::to_value(payload).expect("the payload is serializable"));
+
+        self
+    }
+
+    /// Match the `Call`s satisfying `predicate` too.
+    pub fn with<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&Call) -> bool + Send + Sync + 'static,
+    {
+        self.predicate = Some(Arc::new(predicate));
+
+        self
+    }
+
+    pub fn matches(&self, call: &Call) -> bool {
+        call.action == self.action
+            && self
+                .payload
+                .as_ref()
+                .is_none_or(|payload| contains(&call.payload, payload))
+            && self
+                .predicate
+                .as_ref()
+                .is_none_or(|predicate| predicate(call))
+    }
+}
+
+impl fmt::Debug for CallMatcher {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("CallMatcher")
+            .field("action", &self.action)
+            .field("payload", &self.payload)
+            .field("predicate", &self.predicate.is_some())
+            .finish()
+    }
+}
+
+/// Whether `value` contains `expected`, see [`CallMatcher::payload`].
+fn contains(value: &Value, expected: &Value) -> bool {
+    match (value, expected) {
+        (Value::Object(value), Value::Object(expected)) => {
+            expected.iter().all(|(name, expected)| {
+                value
+                    .get(name)
+                    .is_some_and(|value| contains(value, expected))
+            })
+        }
+        _ => value == expected,
+    }
+}
+
+/// An in-process Central System, see the [module][self].
+///
+/// It must be created in a Tokio runtime.
+pub struct MockCsms {
+    shared: Arc<Shared>,
+    connections: mpsc::UnboundedSender<MemoryTransport>,
+    task: JoinHandle<()>,
+}
+
+struct Shared {
+    responses: Mutex<HashMap<String, VecDeque<MockResponse>>>,
+    received: watch::Sender<Vec<Call>>,
+    /// The frames to send on the current connection, if any.
+    connection: watch::Sender<Option<mpsc::UnboundedSender<Frame>>>,
+    /// The `Call`s sent with [`MockCsms::call`], waiting for a response.
+    pending_calls: Mutex<HashMap<String, oneshot::Sender<Message>>>,
+    next_unique_id: AtomicU64,
+    next_transaction_id: AtomicI32,
+}
+
+impl MockCsms {
+    pub fn new() -> Self {
+        let shared = Arc::new(Shared {
+            responses: Mutex::default(),
+            received: watch::Sender::new(Vec::new()),
+            connection: watch::Sender::new(None),
+            pending_calls: Mutex::default(),
+            next_unique_id: AtomicU64::new(1),
+            next_transaction_id: AtomicI32::new(1),
+        });
+        let (connections, mut accepted) = mpsc::unbounded_channel();
+
+        let task = tokio::spawn({
+            let shared = shared.clone();
+
+            async move {
+                while let Some(stream) = accepted.recv().await {
+                    tokio::spawn(run_connection(shared.clone(), stream));
+                }
+            }
+        });
+
+        Self {
+            shared,
+            connections,
+            task,
+        }
+    }
+
+    /// Connect a client to the mock, identifying as `charge_point_id`.
+    pub async fn connect(&self, charge_point_id: &str) -> Result<ChargePointClient> {
+        self.connect_with_config(charge_point_id, ClientConfig::default())
+            .await
+    }
+
+    /// Like [`Self::connect`], with a custom configuration. The client
+    /// reconnects to the mock, after [`Self::disconnect`] for example.
+    pub async fn connect_with_config(
+        &self,
+        charge_point_id: &str,
+        config: ClientConfig,
+    ) -> Result<ChargePointClient> {
+        let connections = self.connections.clone();
+        let connector = move || {
+            let (transport, mock_transport) = MemoryTransport::pair();
+            let sent = connections.send(mock_transport);
+
+            async move {
+                sent.map(|()| transport)
+                    .map_err(|_| TransportError::ConnectionClosed)
+            }
+        };
+
+        ChargePointClient::connect_with_connector(charge_point_id, connector, config).await
+    }
+
+    /// Enqueue a response to the next `Call` of `action`. The responses of
+    /// an action are used in order, and once they are exhausted the
+    /// `Call`s get a default response again.
+    pub fn enqueue(&self, action: &str, response: MockResponse) {
+        self.shared
+            .responses
+            .lock()
+            .unwrap()
+            .entry(action.to_owned())
+            .or_default()
+            .push_back(response);
+    }
+
+    /// The `Call`s received so far, in order.
+    pub fn received(&self) -> Vec<Call> {
+        self.shared.received.borrow().clone()
+    }
+
+    /// Wait for a `Call` matching `matcher`, received already or not.
+    ///
+    /// # Panics
+    ///
+    /// If no such `Call` is received within 5 seconds.
+    pub async fn assert_received(&self, matcher: &CallMatcher) -> Call {
+        let mut received = self.shared.received.subscribe();
+        let found = tokio::time::timeout(
+            TIMEOUT,
+            received.wait_for(|calls| calls.iter().any(|call| matcher.matches(call))),
+        )
+        .await;
+
+        match found {
+            Ok(Ok(calls)) => calls
+                .iter()
+                .find(|call| matcher.matches(call))
+                .cloned()
+                .expect("a `Call` matches"),
+            _ => panic!(
+                "no `Call` matches {matcher:?}, received: {:#?}",
+                self.received()
+            ),
+        }
+    }
+
+    /// # Panics
+    ///
+    /// If a `Call` matching `matcher` has been received so far.
+    pub fn assert_not_received(&self, matcher: &CallMatcher) {
+        if let Some(call) = self.received().iter().find(|call| matcher.matches(call)) {
+            panic!("{call:?} matches {matcher:?}");
+        }
+    }
+
+    /// Send a `Call` to the connected client, and wait for its response:
+    /// the payload of the `CallResult`, or the `CallError`.
+    ///
+    /// # Panics
+    ///
+    /// If no client connects, or if it does not respond, within 5 seconds.
+    pub async fn call<P>(&self, action: &str, payload: &P) -> std::result::Result<Value, CallError>
+    where
+        P: Serialize,
+    {
+        let unique_id = format!(
+            "mock-{}",
+            self.shared.next_unique_id.fetch_add(1, Ordering::Relaxed)
+        );
+        let call = Call::new(&unique_id, action, payload).expect("the payload is serializable");
+        let (responder, response) = oneshot::channel();
+
+        self.shared
+            .pending_calls
+            .lock()
+            .unwrap()
+            .insert(unique_id, responder);
+        self.shared.send(Message::Call(call)).await;
+
+        match tokio::time::timeout(TIMEOUT, response).await {
+            Ok(Ok(Message::CallResult(call_result))) => Ok(call_result.payload),
+            Ok(Ok(Message::CallError(call_error))) => Err(call_error),
+            _ => panic!("the client did not respond to `{action}`"),
+        }
+    }
+
+    /// Close the current connection, e.g. to test the reconnection of
+    /// the client.
+    pub fn disconnect(&self) {
+        if let Some(connection) = self.shared.connection.send_replace(None) {
+            let _ = connection.send(Frame::Close(None));
+        }
+    }
+}
+
+impl Default for MockCsms {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for MockCsms {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl Shared {
+    /// Send a frame on the current connection, or on the next one.
+    ///
+    /// # Panics
+    ///
+    /// If no client connects within 5 seconds.
+    async fn send(&self, message: Message) {
+        let mut connection = self.connection.subscribe();
+        let connection =
+            match tokio::time::timeout(TIMEOUT, connection.wait_for(Option::is_some)).await {
+                Ok(Ok(connection)) => connection.clone(),
+                _ => None,
+            }
+            .expect("a client is connected");
+
+        let _ = connection.send(Frame::Text(message.to_string()));
+    }
+
+    /// The response to `call`, and when to send it.
+    fn respond(&self, call: &Call) -> (Message, Duration) {
+        let canned = self
+            .responses
+            .lock()
+            .unwrap()
+            .get_mut(&call.action)
+            .and_then(VecDeque::pop_front);
+
+        let Some(MockResponse { response, delay }) = canned else {
+            return (self.respond_by_default(call), Duration::ZERO);
+        };
+
+        let response = match response {
+            Ok(payload) => CallResult {
+                unique_id: call.unique_id.clone(),
+                payload,
+            }
+            .into(),
+            Err((error_code, error_description)) => {
+                CallError::new(&call.unique_id, error_code, error_description, None).into()
+            }
+        };
+
+        (response, delay)
+    }
+
+    /// Everything is accepted.
+    fn respond_by_default(&self, call: &Call) -> Message {
+        let now = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        let accepted = json!({ "status": "Accepted" });
+
+        let payload = match call.action.as_str() {
+            "BootNotification" => json!({
+                "status": "Accepted",
+                "currentTime": now,
+                "interval": 300,
+            }),
+            "Heartbeat" => json!({ "currentTime": now }),
+            "Authorize" => json!({ "idTagInfo": accepted }),
+            "StartTransaction" => json!({
+                "idTagInfo": accepted,
+                "transactionId": self.next_transaction_id.fetch_add(1, Ordering::Relaxed),
+            }),
+            "StopTransaction"
+            | "StatusNotification"
+            | "MeterValues"
+            | "DiagnosticsStatusNotification"
+            | "FirmwareStatusNotification" => json!({}),
+            "DataTransfer" => json!({ "status": "UnknownVendorId" }),
+            action => {
+                return CallError::new(
+                    &call.unique_id,
+                    ErrorCode::NotImplemented,
+                    format!("unknown action `{action}`"),
+                    None,
+                )
+                .into()
+            }
+        };
+
+        CallResult {
+            unique_id: call.unique_id.clone(),
+            payload,
+        }
+        .into()
+    }
+}
+
+async fn run_connection(shared: Arc<Shared>, transport: MemoryTransport) {
+    let (mut sink, mut stream) = transport.split();
+    let (frames, mut outgoing) = mpsc::unbounded_channel();
+
+    shared.connection.send_replace(Some(frames.clone()));
+
+    loop {
+        tokio::select! {
+            Some(frame) = outgoing.recv() => {
+                let close = matches!(frame, Frame::Close(_));
+
+                if sink.send(frame).await.is_err() || close {
+                    break;
+                }
+            }
+
+            frame = stream.next() => match frame {
+                Some(Ok(Frame::Text(text))) => match text.parse::<Message>() {
+                    Ok(Message::Call(call)) => {
+                        let (response, delay) = shared.respond(&call);
+                        shared.received.send_modify(|received| received.push(call));
+
+                        let frames = frames.clone();
+                        tokio::spawn(async move {
+                            tokio::time::sleep(delay).await;
+                            let _ = frames.send(Frame::Text(response.to_string()));
+                        });
+                    }
+                    Ok(response) => {
+                        let responder = shared
+                            .pending_calls
+                            .lock()
+                            .unwrap()
+                            .remove(response.unique_id());
+
+                        if let Some(responder) = responder {
+                            let _ = responder.send(response);
+                        }
+                    }
+                    Err(_) => {}
+                },
+                Some(Ok(Frame::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    shared.connection.send_if_modified(|connection| {
+        let current = connection
+            .as_ref()
+            .is_some_and(|connection| connection.same_channel(&frames));
+
+        if current {
+            *connection = None;
+        }
+
+        current
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConnectionState, Error, ReconnectPolicy};
+    use ocppx_types::{
+        v1_6::{AuthorizeRequest, AuthorizeResponse, IdTagInfo, IdTagInfoStatus},
+        IdTag,
+    };
+
+    #[tokio::test]
+    async fn test_mock_csms() {
+        let csms = MockCsms::new();
+        csms.enqueue(
+            "Authorize",
+            MockResponse::result(
+                &AuthorizeResponse::builder()
+                    .id_tag_info(
+                        IdTagInfo::builder()
+                            .status(IdTagInfoStatus::Blocked)
+                            .build(),
+                    )
+                    .build(),
+            ),
+        );
+        csms.enqueue(
+            "Authorize",
+            MockResponse::error(ErrorCode::InternalError, "down"),
+        );
+        csms.enqueue(
+            "Heartbeat",
+            MockResponse::result(&json!({})).delayed(Duration::from_secs(1)),
+        );
+
+        let client = csms
+            .connect_with_config(
+                "CP001",
+                ClientConfig {
+                    call_timeout: Duration::from_millis(100),
+                    heartbeat: false,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        let authorize = |id_tag: &str| {
+            AuthorizeRequest::builder()
+                .id_tag(IdTag::try_from(id_tag).unwrap())
+                .build()
+        };
+
+        // The canned responses, in order, then the default one.
+        let response = client.send(authorize("A")).await.unwrap();
+        assert_eq!(response.id_tag_info.status, IdTagInfoStatus::Blocked);
+        assert!(matches!(
+            client.send(authorize("B")).await,
+            Err(Error::CallError(call_error)) if call_error.error_code == ErrorCode::InternalError
+        ));
+        let response = client.send(authorize("C")).await.unwrap();
+        assert_eq!(response.id_tag_info.status, IdTagInfoStatus::Accepted);
+
+        // Delayed past the timeout of the client.
+        assert!(matches!(
+            client.call::<_, Value>("Heartbeat", &json!({})).await,
+            Err(Error::Timeout { .. })
+        ));
+
+        let call = csms
+            .assert_received(&CallMatcher::action("Authorize").payload(&json!({ "idTag": "B" })))
+            .await;
+        assert_eq!(call.payload, json!({ "idTag": "B" }));
+        csms.assert_received(
+            &CallMatcher::action("Authorize").with(|call| call.payload["idTag"] == "C"),
+        )
+        .await;
+        csms.assert_not_received(
+            &CallMatcher::action("Authorize").payload(&json!({ "idTag": "D" })),
+        );
+        csms.assert_not_received(&CallMatcher::action("StartTransaction"));
+        assert_eq!(csms.received().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_mock_csms_calls_and_reconnections() {
+        let csms = MockCsms::new();
+        let client = csms
+            .connect_with_config(
+                "CP001",
+                ClientConfig {
+                    reconnect: Some(ReconnectPolicy {
+                        initial_delay: Duration::from_millis(10),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        let payload = json!({});
+        let (response, ()) = tokio::join!(csms.call("GetConfiguration", &payload), async {
+            let call = client.next_call().await.unwrap();
+            client
+                .respond(CallError::new(
+                    call.unique_id,
+                    ErrorCode::NotSupported,
+                    "",
+                    None,
+                ))
+                .unwrap();
+        });
+        assert_eq!(response.unwrap_err().error_code, ErrorCode::NotSupported);
+
+        let mut state = client.connection_state();
+        csms.disconnect();
+        state
+            .wait_for(|state| matches!(state, ConnectionState::Reconnecting { .. }))
+            .await
+            .unwrap();
+        state
+            .wait_for(|state| *state == ConnectionState::Connected)
+            .await
+            .unwrap();
+
+        client
+            .call::<_, Value>("Heartbeat", &json!({}))
+            .await
+            .unwrap();
+        csms.assert_received(&CallMatcher::action("Heartbeat"))
+            .await;
+    }
+}
@@ -0,0 +1,448 @@
+use crate::{Error, Result};
+use ocppx_rpc::{Call, Message};
+use std::{collections::VecDeque, sync::Mutex};
+use tokio::sync::Notify;
+use tokio_tungstenite::tungstenite::Message as Frame;
+
+/// What to do with a `Call` pushed in a full priority of the outgoing
+/// queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Wait until there is room, which pushes back on the caller.
+    Wait,
+    /// Drop the oldest `Call` of the priority, to make room.
+    DropOldest,
+    /// Drop the new `Call`.
+    DropNewest,
+}
+
+/// The bound of a priority of the outgoing queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueLimit {
+    /// Number of `Call`s waiting to be sent.
+    pub capacity: usize,
+    pub overflow: Overflow,
+}
+
+/// The queue of the frames waiting to be sent to the Central System, e.g.
+/// while disconnected, by priority:
+///
+/// 1. the responses to the `Call`s of the Central System, which are not
+///    bounded,
+/// 2. the `Call`s of the [`QUEUED_ACTIONS`], which go through the
+///    [`MessageQueue`],
+/// 3. the other `Call`s, bounded by [`Self::normal`],
+/// 4. the `Heartbeat`s and the `StatusNotification`s, bounded by
+///    [`Self::low`], which wait until the [`MessageQueue`] is empty.
+///
+/// The `Call`s dropped from the queue fail with [`Error::Dropped`].
+///
+/// [`QUEUED_ACTIONS`]: crate::QUEUED_ACTIONS
+/// [`MessageQueue`]: crate::MessageQueue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutgoingQueueConfig {
+    pub normal: QueueLimit,
+    pub low: QueueLimit,
+    /// Keep only the last `StatusNotification` of each connector waiting
+    /// to be sent: the previous ones are outdated.
+    pub merge_status_notifications: bool,
+}
+
+impl Default for OutgoingQueueConfig {
+    fn default() -> Self {
+        Self {
+            normal: QueueLimit {
+                capacity: 64,
+                overflow: Overflow::Wait,
+            },
+            low: QueueLimit {
+                capacity: 32,
+                overflow: Overflow::DropOldest,
+            },
+            merge_status_notifications: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Priority {
+    Response,
+    Normal,
+    Low,
+}
+
+impl Priority {
+    fn of(action: &str) -> Self {
+        match action {
+            "Heartbeat" | "StatusNotification" => Self::Low,
+            _ => Self::Normal,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    frame: Frame,
+    unique_id: String,
+    /// The connector of a `StatusNotification`.
+    status_connector: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// The entries of each [`Priority`].
+    entries: [VecDeque<Entry>; 3],
+    closed: bool,
+}
+
+/// The [`OutgoingQueueConfig`] in action, shared by the client and its
+/// connection task.
+#[derive(Debug)]
+pub(crate) struct OutgoingQueue {
+    config: OutgoingQueueConfig,
+    state: Mutex<State>,
+    /// Notified when an entry is pushed, or when the queue is closed.
+    pushed: Notify,
+    /// Notified when an entry is popped.
+    popped: Notify,
+}
+
+impl OutgoingQueue {
+    pub(crate) fn new(config: OutgoingQueueConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::default(),
+            pushed: Notify::new(),
+            popped: Notify::new(),
+        }
+    }
+
+    pub(crate) fn push_response(&self, response: Message) -> Result<()> {
+        self.push_unbounded(
+            response.unique_id().to_owned(),
+            Frame::Text(response.to_string()),
+        )
+    }
+
+    /// Push a text frame as is, along with the responses: the window of
+    /// the `Call`s does not wait for its response, if any.
+    pub(crate) fn push_raw(&self, text: String) -> Result<()> {
+        self.push_unbounded(String::new(), Frame::Text(text))
+    }
+
+    fn push_unbounded(&self, unique_id: String, frame: Frame) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.closed {
+            return Err(Error::ConnectionClosed);
+        }
+
+        state.entries[Priority::Response as usize].push_back(Entry {
+            unique_id,
+            frame,
+            status_connector: None,
+        });
+        self.pushed.notify_one();
+
+        Ok(())
+    }
+
+    /// Push `call`, and return the unique IDs of the `Call`s dropped to
+    /// make room, possibly `call` itself.
+    pub(crate) async fn push_call(&self, call: Call) -> Result<Vec<String>> {
+        let priority = Priority::of(&call.action);
+        let status_connector = (self.config.merge_status_notifications
+            && call.action == "StatusNotification")
+            .then(|| call.payload["connectorId"].as_u64())
+            .flatten();
+
+        self.push(call, priority, status_connector).await
+    }
+
+    /// Push `call` relayed for another Charge Point: with the normal
+    /// priority, whatever its action, and without merging it.
+    pub(crate) async fn push_relayed_call(&self, call: Call) -> Result<Vec<String>> {
+        self.push(call, Priority::Normal, None).await
+    }
+
+    async fn push(
+        &self,
+        call: Call,
+        priority: Priority,
+        status_connector: Option<u64>,
+    ) -> Result<Vec<String>> {
+        let limit = match priority {
+            Priority::Low => self.config.low,
+            _ => self.config.normal,
+        };
+        let entry = Entry {
+            unique_id: call.unique_id.clone(),
+            frame: Frame::Text(Message::from(call).to_string()),
+            status_connector,
+        };
+
+        loop {
+            let popped = self.popped.notified();
+            tokio::pin!(popped);
+            popped.as_mut().enable();
+
+            {
+                let mut state = self.state.lock().unwrap();
+
+                if state.closed {
+                    return Err(Error::ConnectionClosed);
+                }
+
+                let entries = &mut state.entries[priority as usize];
+                let merged = entry.status_connector.and_then(|status_connector| {
+                    entries
+                        .iter()
+                        .position(|queued| queued.status_connector == Some(status_connector))
+                });
+                let mut dropped = Vec::new();
+
+                let room = match merged {
+                    Some(index) => {
+                        dropped.extend(entries.remove(index).map(|queued| queued.unique_id));
+
+                        true
+                    }
+                    None if entries.len() >= limit.capacity => match limit.overflow {
+                        Overflow::Wait if limit.capacity > 0 => false,
+                        Overflow::DropOldest if limit.capacity > 0 => {
+                            dropped.extend(entries.pop_front().map(|queued| queued.unique_id));
+
+                            true
+                        }
+                        _ => return Ok(vec![entry.unique_id]),
+                    },
+                    None => true,
+                };
+
+                if room {
+                    entries.push_back(entry);
+                    self.pushed.notify_one();
+
+                    return Ok(dropped);
+                }
+            }
+
+            popped.await;
+        }
+    }
+
+    /// Close the queue: the frames already pushed can still be popped.
+    pub(crate) fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.pushed.notify_one();
+    }
+
+    /// Wait for the next frame to send, by priority, with the unique ID of
+    /// the `Call` it carries, if any. The `Call`s are held back unless
+    /// `calls` is set, and the low priority ones unless `low` is set too.
+    /// Returns `None` once the queue is closed and empty.
+    pub(crate) async fn pop(&self, calls: bool, low: bool) -> Option<(Frame, Option<String>)> {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                // The low priority is not held back when closing.
+                let priorities = match (calls, low || state.closed) {
+                    (false, _) => 1,
+                    (true, false) => 2,
+                    (true, true) => 3,
+                };
+
+                if let Some((priority, entry)) = state.entries[..priorities]
+                    .iter_mut()
+                    .enumerate()
+                    .find_map(|(priority, entries)| Some((priority, entries.pop_front()?)))
+                {
+                    self.popped.notify_waiters();
+                    let call = (priority != Priority::Response as usize).then_some(entry.unique_id);
+
+                    return Some((entry.frame, call));
+                }
+
+                if state.closed {
+                    return None;
+                }
+            }
+
+            self.pushed.notified().await;
+        }
+    }
+
+    /// Wait until the queue is closed.
+    pub(crate) async fn closed(&self) {
+        while !self.state.lock().unwrap().closed {
+            self.pushed.notified().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn status_notification(unique_id: &str, connector_id: u64, status: &str) -> Call {
+        Call::new(
+            unique_id,
+            "StatusNotification",
+            &json!({"connectorId": connector_id, "errorCode": "NoError", "status": status}),
+        )
+        .unwrap()
+    }
+
+    async fn unique_id(queue: &OutgoingQueue, low: bool) -> String {
+        let (frame, _) = queue.pop(true, low).await.unwrap();
+
+        frame
+            .to_text()
+            .unwrap()
+            .parse::<Message>()
+            .unwrap()
+            .unique_id()
+            .to_owned()
+    }
+
+    #[tokio::test]
+    async fn test_relayed_calls() {
+        let queue = OutgoingQueue::new(OutgoingQueueConfig::default());
+
+        // Neither merged, nor held back with the low priority.
+        for (unique_id, status) in [("1", "Preparing"), ("2", "Charging")] {
+            assert!(queue
+                .push_relayed_call(status_notification(unique_id, 1, status))
+                .await
+                .unwrap()
+                .is_empty());
+        }
+        let heartbeat = Call::new("3", "Heartbeat", &json!({})).unwrap();
+        queue.push_relayed_call(heartbeat).await.unwrap();
+
+        assert_eq!(unique_id(&queue, false).await, "1");
+        assert_eq!(unique_id(&queue, false).await, "2");
+        assert_eq!(unique_id(&queue, false).await, "3");
+    }
+
+    #[tokio::test]
+    async fn test_priorities() {
+        let queue = OutgoingQueue::new(OutgoingQueueConfig::default());
+
+        let heartbeat = Call::new("1", "Heartbeat", &json!({})).unwrap();
+        assert!(queue.push_call(heartbeat).await.unwrap().is_empty());
+        assert!(queue
+            .push_call(status_notification("2", 1, "Preparing"))
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(queue
+            .push_call(status_notification("3", 2, "Available"))
+            .await
+            .unwrap()
+            .is_empty());
+        // The previous status of the connector 1 is outdated.
+        assert_eq!(
+            queue
+                .push_call(status_notification("4", 1, "Charging"))
+                .await
+                .unwrap(),
+            ["2"]
+        );
+        let authorize = Call::new("5", "Authorize", &json!({"idTag": "ABC"})).unwrap();
+        queue.push_call(authorize).await.unwrap();
+        queue
+            .push_response(Message::CallResult(
+                ocppx_rpc::CallResult::new("6", &json!({})).unwrap(),
+            ))
+            .unwrap();
+
+        // The `Call`s are held back, not the responses.
+        assert_eq!(
+            queue.pop(false, false).await.map(|(_, call)| call),
+            Some(None)
+        );
+        assert!(
+            tokio::time::timeout(Duration::from_millis(10), queue.pop(false, true))
+                .await
+                .is_err()
+        );
+        assert_eq!(
+            queue.pop(true, false).await.map(|(_, call)| call),
+            Some(Some("5".to_owned()))
+        );
+        // The low priority is held back.
+        assert!(
+            tokio::time::timeout(Duration::from_millis(10), queue.pop(true, false))
+                .await
+                .is_err()
+        );
+        assert_eq!(unique_id(&queue, true).await, "1");
+        assert_eq!(unique_id(&queue, true).await, "3");
+        assert_eq!(unique_id(&queue, true).await, "4");
+
+        queue.close();
+        assert!(queue.pop(true, true).await.is_none());
+        assert!(matches!(
+            queue.push_response(Message::CallResult(
+                ocppx_rpc::CallResult::new("7", &json!({})).unwrap()
+            )),
+            Err(Error::ConnectionClosed)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_overflow() {
+        let limit = |overflow| QueueLimit {
+            capacity: 1,
+            overflow,
+        };
+        let queue = OutgoingQueue::new(OutgoingQueueConfig {
+            normal: limit(Overflow::DropNewest),
+            low: limit(Overflow::DropOldest),
+            merge_status_notifications: false,
+        });
+        let call =
+            |unique_id: &str, action: &str| Call::new(unique_id, action, &json!({})).unwrap();
+
+        assert!(queue
+            .push_call(call("1", "Authorize"))
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            queue.push_call(call("2", "Authorize")).await.unwrap(),
+            ["2"]
+        );
+        assert!(queue
+            .push_call(call("3", "Heartbeat"))
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            queue.push_call(call("4", "Heartbeat")).await.unwrap(),
+            ["3"]
+        );
+
+        let queue = std::sync::Arc::new(OutgoingQueue::new(OutgoingQueueConfig {
+            normal: limit(Overflow::Wait),
+            ..OutgoingQueueConfig::default()
+        }));
+        queue.push_call(call("1", "Authorize")).await.unwrap();
+
+        // The second `Call` waits for the first one to be sent.
+        let push = tokio::spawn({
+            let queue = queue.clone();
+
+            async move { queue.push_call(call("2", "Authorize")).await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!push.is_finished());
+
+        assert_eq!(unique_id(&queue, false).await, "1");
+        assert!(push.await.unwrap().unwrap().is_empty());
+        assert_eq!(unique_id(&queue, false).await, "2");
+    }
+}
@@ -0,0 +1,175 @@
+use ocppx_rpc::{Call, Message};
+use std::{
+    collections::VecDeque,
+    fmt, fs,
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// The actions whose `Call`s go through the [`MessageQueue`], i.e. the
+/// transaction-related messages that must be delivered, in order, even if
+/// the connection drops.
+pub const QUEUED_ACTIONS: &[&str] = &["StartTransaction", "StopTransaction", "MeterValues"];
+
+/// A FIFO queue of `Call`s waiting to be delivered to the Central System.
+///
+/// A `Call` is pushed before being sent, and popped once its response has
+/// been received, so that it is sent again after a reconnection if needed.
+pub trait MessageQueue: fmt::Debug + Send + Sync + 'static {
+    /// Push a `Call` at the back of the queue.
+    fn push(&self, call: &Call) -> io::Result<()>;
+
+    /// The `Call` at the front of the queue, if any.
+    fn front(&self) -> io::Result<Option<Call>>;
+
+    /// Remove the `Call` at the front of the queue.
+    fn pop(&self) -> io::Result<()>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A [`MessageQueue`] in memory: the queued `Call`s are lost when the
+/// process stops.
+#[derive(Debug, Default)]
+pub struct MemoryQueue {
+    calls: Mutex<VecDeque<Call>>,
+}
+
+impl MessageQueue for MemoryQueue {
+    fn push(&self, call: &Call) -> io::Result<()> {
+        self.calls.lock().unwrap().push_back(call.clone());
+
+        Ok(())
+    }
+
+    fn front(&self) -> io::Result<Option<Call>> {
+        Ok(self.calls.lock().unwrap().front().cloned())
+    }
+
+    fn pop(&self) -> io::Result<()> {
+        self.calls.lock().unwrap().pop_front();
+
+        Ok(())
+    }
+
+    fn len(&self) -> usize {
+        self.calls.lock().unwrap().len()
+    }
+}
+
+/// A [`MessageQueue`] persisted in a file, one `Call` per line in its
+/// OCPP-J wire format, so that the queued `Call`s survive a restart.
+#[derive(Debug)]
+pub struct FileQueue {
+    path: PathBuf,
+    calls: Mutex<VecDeque<Call>>,
+}
+
+impl FileQueue {
+    /// Open the queue stored at `path`, creating it if it does not exist.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .read(true)
+            .open(&path)?;
+
+        let mut calls = VecDeque::new();
+
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let call = line
+                .parse::<Message>()
+                .and_then(Call::try_from)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+            calls.push_back(call);
+        }
+
+        Ok(Self {
+            path,
+            calls: Mutex::new(calls),
+        })
+    }
+}
+
+impl MessageQueue for FileQueue {
+    fn push(&self, call: &Call) -> io::Result<()> {
+        let mut calls = self.calls.lock().unwrap();
+
+        let mut file = fs::OpenOptions::new().append(true).open(&self.path)?;
+        writeln!(file, "{}", Message::from(call.clone()))?;
+        file.sync_data()?;
+
+        calls.push_back(call.clone());
+
+        Ok(())
+    }
+
+    fn front(&self) -> io::Result<Option<Call>> {
+        Ok(self.calls.lock().unwrap().front().cloned())
+    }
+
+    fn pop(&self) -> io::Result<()> {
+        let mut calls = self.calls.lock().unwrap();
+        calls.pop_front();
+
+        // Rewrite the remaining `Call`s atomically.
+        let temporary_path = self.path.with_extension("tmp");
+        let mut file = fs::File::create(&temporary_path)?;
+
+        for call in calls.iter() {
+            writeln!(file, "{}", Message::from(call.clone()))?;
+        }
+
+        file.sync_data()?;
+        fs::rename(temporary_path, &self.path)
+    }
+
+    fn len(&self) -> usize {
+        self.calls.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_file_queue() {
+        let path = std::env::temp_dir().join(format!("ocppx-queue-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        {
+            let queue = FileQueue::open(&path).unwrap();
+            queue
+                .push(&Call::new("1", "StartTransaction", &json!({})).unwrap())
+                .unwrap();
+            queue
+                .push(&Call::new("2", "MeterValues", &json!({})).unwrap())
+                .unwrap();
+            queue
+                .push(&Call::new("3", "StopTransaction", &json!({})).unwrap())
+                .unwrap();
+            queue.pop().unwrap();
+        }
+
+        let queue = FileQueue::open(&path).unwrap();
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.front().unwrap().unwrap().unique_id, "2");
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,94 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+/// How a [`ChargePointClient`][crate::ChargePointClient] reconnects when
+/// the connection drops: after each failed attempt, the delay before the
+/// next one is multiplied by `multiplier`, up to `max_delay`, and randomized
+/// by `jitter`.
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    /// Delay before the first attempt.
+    pub initial_delay: Duration,
+    /// Upper bound of the delay, before jitter.
+    pub max_delay: Duration,
+    pub multiplier: u32,
+    /// Ratio of the delay added or removed randomly, between 0 and 1, so
+    /// that Charge Points disconnected at once do not reconnect at once.
+    pub jitter: f64,
+    /// Number of attempts before giving up, or `None` to try forever.
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            multiplier: 2,
+            jitter: 0.2,
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// The delay before the attempt number `attempt`, starting from 1.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let delay = self
+            .multiplier
+            .checked_pow(attempt.saturating_sub(1))
+            .and_then(|factor| self.initial_delay.checked_mul(factor))
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay));
+
+        // A uniform value in `[-1, 1]`.
+        let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64 * 2. - 1.;
+
+        delay.mul_f64((1. + self.jitter.clamp(0., 1.) * random).max(0.))
+    }
+}
+
+/// The state of the connection to the Central System, see
+/// [`ChargePointClient::connection_state`][crate::ChargePointClient::connection_state].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConnectionState {
+    Connected,
+    /// The connection has dropped, and the client waits before the
+    /// reconnection attempt number `attempt`.
+    Reconnecting {
+        attempt: u32,
+    },
+    /// The connection is closed for good, either by
+    /// [`ChargePointClient::close`][crate::ChargePointClient::close] or
+    /// because the reconnection attempts are exhausted.
+    Closed,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay() {
+        let policy = ReconnectPolicy {
+            jitter: 0.,
+            ..Default::default()
+        };
+
+        assert_eq!(policy.delay(1), Duration::from_secs(1));
+        assert_eq!(policy.delay(2), Duration::from_secs(2));
+        assert_eq!(policy.delay(4), Duration::from_secs(8));
+        assert_eq!(policy.delay(10), Duration::from_secs(60));
+        assert_eq!(policy.delay(100), Duration::from_secs(60));
+
+        let policy = ReconnectPolicy::default();
+
+        for _ in 0..100 {
+            let delay = policy.delay(2);
+
+            assert!(delay >= Duration::from_millis(1600) && delay <= Duration::from_millis(2400));
+        }
+    }
+}
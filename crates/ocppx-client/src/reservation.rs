@@ -0,0 +1,193 @@
+use chrono::{DateTime, Utc};
+use ocppx_types::{
+    v1_6::{ReserveNowRequest, ReserveNowStatus},
+    IdTag,
+};
+use std::collections::BTreeMap;
+
+/// A reservation of a connector, made with `ReserveNow`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reservation {
+    pub id: i32,
+    /// The reserved connector, or `0` for any connector of the Charge
+    /// Point.
+    pub connector_id: i32,
+    pub id_tag: IdTag,
+    pub parent_id_tag: Option<IdTag>,
+    pub expiry_date: DateTime<Utc>,
+}
+
+impl Reservation {
+    /// Whether the reservation can be used by `id_tag`, itself in the group
+    /// of `parent_id_tag`.
+    pub fn matches(&self, id_tag: &IdTag, parent_id_tag: Option<&IdTag>) -> bool {
+        &self.id_tag == id_tag
+            || self
+                .parent_id_tag
+                .as_ref()
+                .is_some_and(|reserved| Some(reserved) == parent_id_tag)
+    }
+}
+
+/// The reservations of the connectors of a Charge Point, managed by the
+/// Central System with `ReserveNow` and `CancelReservation`.
+///
+/// The manager does not know the status of the connectors: checking that a
+/// connector is available before reserving it is up to the caller.
+#[derive(Debug, Clone, Default)]
+pub struct ReservationManager {
+    connector_zero: bool,
+    reservations: BTreeMap<i32, Reservation>,
+}
+
+impl ReservationManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept reservations of the connector 0, as advertised by the
+    /// `ReserveConnectorZeroSupported` configuration key.
+    pub fn with_connector_zero(mut self, supported: bool) -> Self {
+        self.connector_zero = supported;
+
+        self
+    }
+
+    /// Apply a `ReserveNow`, and return the status of its response.
+    ///
+    /// A request with the ID of an existing reservation replaces it. A
+    /// connector that is reserved by another reservation is `Occupied`.
+    pub fn reserve(&mut self, request: &ReserveNowRequest, now: DateTime<Utc>) -> ReserveNowStatus {
+        if request.expiry_date <= now || (request.connector_id == 0 && !self.connector_zero) {
+            return ReserveNowStatus::Rejected;
+        }
+
+        if self.reservations.values().any(|reservation| {
+            reservation.connector_id == request.connector_id
+                && reservation.id != request.reservation_id
+        }) {
+            return ReserveNowStatus::Occupied;
+        }
+
+        self.reservations.insert(
+            request.reservation_id,
+            Reservation {
+                id: request.reservation_id,
+                connector_id: request.connector_id,
+                id_tag: request.id_tag.clone(),
+                parent_id_tag: request.parent_id_tag.clone(),
+                expiry_date: request.expiry_date,
+            },
+        );
+
+        ReserveNowStatus::Accepted
+    }
+
+    /// Cancel a reservation, and return it if it exists.
+    pub fn cancel(&mut self, reservation_id: i32) -> Option<Reservation> {
+        self.reservations.remove(&reservation_id)
+    }
+
+    /// The reservation of `connector_id`, if any.
+    pub fn reservation(&self, connector_id: i32) -> Option<&Reservation> {
+        self.reservations
+            .values()
+            .find(|reservation| reservation.connector_id == connector_id)
+    }
+
+    /// Whether a transaction can start on `connector_id` for `id_tag`: the
+    /// connector is not reserved, or its reservation matches.
+    pub fn check(&self, connector_id: i32, id_tag: &IdTag, parent_id_tag: Option<&IdTag>) -> bool {
+        self.reservation(connector_id)
+            .is_none_or(|reservation| reservation.matches(id_tag, parent_id_tag))
+    }
+
+    /// Use the reservation of `connector_id` for a transaction of `id_tag`,
+    /// or the reservation of the connector 0, and return it. A used
+    /// reservation ends.
+    pub fn consume(
+        &mut self,
+        connector_id: i32,
+        id_tag: &IdTag,
+        parent_id_tag: Option<&IdTag>,
+    ) -> Option<Reservation> {
+        let id = [connector_id, 0].into_iter().find_map(|connector_id| {
+            self.reservation(connector_id)
+                .filter(|reservation| reservation.matches(id_tag, parent_id_tag))
+                .map(|reservation| reservation.id)
+        })?;
+
+        self.reservations.remove(&id)
+    }
+
+    /// Remove the reservations that have expired at `now`, and return them.
+    pub fn expire(&mut self, now: DateTime<Utc>) -> Vec<Reservation> {
+        let expired = self
+            .reservations
+            .values()
+            .filter(|reservation| reservation.expiry_date <= now)
+            .map(|reservation| reservation.id)
+            .collect::<Vec<_>>();
+
+        expired
+            .into_iter()
+            .filter_map(|id| self.reservations.remove(&id))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.reservations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reservations.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    #[test]
+    fn test_reservations() {
+        let id_tag = |id_tag| IdTag::try_from(id_tag).unwrap();
+        let now: DateTime<Utc> = "2013-02-01T20:53:32.486Z".parse().unwrap();
+        let mut reservations = ReservationManager::new();
+        let request = |reservation_id, connector_id| {
+            ReserveNowRequest::builder()
+                .connector_id(connector_id)
+                .expiry_date(now + TimeDelta::minutes(15))
+                .id_tag(id_tag("A"))
+                .parent_id_tag(id_tag("GROUP"))
+                .reservation_id(reservation_id)
+                .build()
+        };
+
+        assert_eq!(
+            reservations.reserve(&request(1, 1), now),
+            ReserveNowStatus::Accepted
+        );
+        assert_eq!(
+            reservations.reserve(&request(2, 1), now),
+            ReserveNowStatus::Occupied
+        );
+        assert_eq!(
+            reservations.reserve(&request(2, 0), now),
+            ReserveNowStatus::Rejected
+        );
+
+        assert!(!reservations.check(1, &id_tag("B"), None));
+        assert!(reservations.check(1, &id_tag("B"), Some(&id_tag("group"))));
+        assert!(reservations.check(2, &id_tag("B"), None));
+
+        assert_eq!(reservations.consume(1, &id_tag("B"), None), None);
+        assert_eq!(reservations.consume(1, &id_tag("a"), None).unwrap().id, 1);
+        assert!(reservations.is_empty());
+
+        reservations.reserve(&request(3, 2), now);
+        assert!(reservations.expire(now).is_empty());
+        assert_eq!(reservations.expire(now + TimeDelta::minutes(15))[0].id, 3);
+        assert!(reservations.cancel(3).is_none());
+    }
+}
@@ -0,0 +1,73 @@
+use crate::Result;
+use rustls::{
+    client::danger::ServerCertVerifier,
+    pki_types::{CertificateDer, PrivateKeyDer, ServerName},
+    ClientConfig, RootCertStore,
+};
+use std::sync::Arc;
+
+/// TLS configuration of a [`ChargePointClient`][crate::ChargePointClient],
+/// used to connect to `wss://` URLs.
+///
+/// [`Self::security_profile_2`] and [`Self::security_profile_3`] are presets
+/// for the OCPP security profiles; any other setup can be built with
+/// [`Self::from_rustls`].
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    pub(crate) config: Arc<ClientConfig>,
+    pub(crate) server_name: Option<ServerName<'static>>,
+}
+
+impl TlsConfig {
+    /// Security profile 2: TLS with Basic Authentication. The Central
+    /// System certificate is validated against `root_store`; the Charge
+    /// Point authenticates with its Basic Authentication credentials.
+    pub fn security_profile_2(root_store: RootCertStore) -> Self {
+        Self::from_rustls(Arc::new(
+            ClientConfig::builder()
+                .with_root_certificates(root_store)
+                .with_no_client_auth(),
+        ))
+    }
+
+    /// Security profile 3: TLS with client side certificates. The Central
+    /// System certificate is validated against `root_store`; the Charge
+    /// Point authenticates with `certificate_chain` and `private_key`.
+    pub fn security_profile_3(
+        root_store: RootCertStore,
+        certificate_chain: Vec<CertificateDer<'static>>,
+        private_key: PrivateKeyDer<'static>,
+    ) -> Result<Self> {
+        Ok(Self::from_rustls(Arc::new(
+            ClientConfig::builder()
+                .with_root_certificates(root_store)
+                .with_client_auth_cert(certificate_chain, private_key)?,
+        )))
+    }
+
+    /// Use a custom `rustls` configuration.
+    pub fn from_rustls(config: Arc<ClientConfig>) -> Self {
+        Self {
+            config,
+            server_name: None,
+        }
+    }
+
+    /// Validate the certificate chain of the Central System with
+    /// `verifier`, instead of the root certificates.
+    pub fn with_certificate_verifier(mut self, verifier: Arc<dyn ServerCertVerifier>) -> Self {
+        Arc::make_mut(&mut self.config)
+            .dangerous()
+            .set_certificate_verifier(verifier);
+
+        self
+    }
+
+    /// Send `server_name` as SNI, and expect it in the certificate of the
+    /// Central System, instead of the host of the URL.
+    pub fn with_server_name(mut self, server_name: ServerName<'static>) -> Self {
+        self.server_name = Some(server_name);
+
+        self
+    }
+}
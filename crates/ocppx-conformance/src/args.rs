@@ -0,0 +1,204 @@
+use ocppx_conformance::ConformanceConfig;
+use std::time::Duration;
+
+pub const USAGE: &str = "\
+Usage:
+    ocppx-conformance central-system --url <url> [--id <charge point id>]
+                                     [--password <password>] [<options>]
+    ocppx-conformance charge-point [--host <host>] [--port <port>] [<options>]
+    ocppx-conformance list
+    ocppx-conformance help
+
+Commands:
+    central-system    Test the Central System at `--url`, as a Charge Point. The
+                      identity of the Charge Point is the last segment of the
+                      URL, unless `--id` is given.
+    charge-point      Test the Charge Point connecting to the suite, as a Central
+                      System. Listens on 0.0.0.0:9000 by default. The Charge
+                      Point must connect, and boot, once the suite has started.
+    list              List the scenarios.
+
+Options:
+    --id-tag <idTag>            An idTag accepted by the endpoint under test,
+                                `OCPPX-ACCEPTED` by default.
+    --invalid-id-tag <idTag>    An idTag unknown to the endpoint under test,
+                                `OCPPX-INVALID` by default.
+    --connector-id <id>         A connector of the Charge Point, 1 by default.
+    --timeout <seconds>         Time to wait at each step, 30 by default.
+    --only <id>[,<id>]...       Run these scenarios only.
+
+The exit code is 0 when all the scenarios pass.
+";
+
+/// The command line, parsed.
+#[derive(Debug)]
+pub enum Command {
+    CentralSystem {
+        csms_url: String,
+        charge_point_id: String,
+        options: Options,
+    },
+    ChargePoint {
+        address: String,
+        options: Options,
+    },
+    List,
+    Help,
+}
+
+/// The options shared by the commands.
+#[derive(Debug, Default)]
+pub struct Options {
+    pub config: ConformanceConfig,
+    /// The IDs of the scenarios to run, all of them when empty.
+    pub only: Vec<String>,
+}
+
+impl Command {
+    /// Parse the arguments, without the name of the program.
+    pub fn parse<I>(arguments: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut arguments = arguments.into_iter();
+
+        match arguments.next().as_deref() {
+            Some("central-system") => Self::parse_central_system(arguments),
+            Some("charge-point") => Self::parse_charge_point(arguments),
+            Some("list") => Ok(Self::List),
+            Some("help" | "--help" | "-h") | None => Ok(Self::Help),
+            Some(command) => Err(format!("unknown command `{command}`")),
+        }
+    }
+
+    fn parse_central_system(arguments: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut url = None;
+        let mut charge_point_id = None;
+        let mut options = Options::default();
+
+        for (option, value) in pairs(arguments)? {
+            match option.as_str() {
+                "url" => url = Some(value),
+                "id" => charge_point_id = Some(value),
+                "password" => options.config.password = Some(value),
+                _ => options.parse(&option, value)?,
+            }
+        }
+
+        let url = url.ok_or_else(|| "missing `--url`".to_owned())?;
+        let (csms_url, charge_point_id) = match charge_point_id {
+            Some(charge_point_id) => (url, charge_point_id),
+            None => match url.trim_end_matches('/').rsplit_once('/') {
+                Some((csms_url, charge_point_id)) if !csms_url.ends_with('/') => {
+                    (csms_url.to_owned(), charge_point_id.to_owned())
+                }
+                _ => return Err("missing `--id`".to_owned()),
+            },
+        };
+
+        Ok(Self::CentralSystem {
+            csms_url,
+            charge_point_id,
+            options,
+        })
+    }
+
+    fn parse_charge_point(arguments: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut host = "0.0.0.0".to_owned();
+        let mut port = "9000".to_owned();
+        let mut options = Options::default();
+
+        for (option, value) in pairs(arguments)? {
+            match option.as_str() {
+                "host" => host = value,
+                "port" => port = value,
+                _ => options.parse(&option, value)?,
+            }
+        }
+
+        Ok(Self::ChargePoint {
+            address: format!("{host}:{port}"),
+            options,
+        })
+    }
+}
+
+impl Options {
+    fn parse(&mut self, option: &str, value: String) -> Result<(), String> {
+        let invalid = || format!("invalid value `{value}` for `--{option}`");
+
+        match option {
+            "id-tag" => self.config.id_tag = value,
+            "invalid-id-tag" => self.config.invalid_id_tag = value,
+            "connector-id" => self.config.connector_id = value.parse().map_err(|_| invalid())?,
+            "timeout" => {
+                self.config.step_timeout =
+                    Duration::from_secs(value.parse().map_err(|_| invalid())?)
+            }
+            "only" => self.only = value.split(',').map(str::to_owned).collect(),
+            _ => return Err(format!("unknown option `--{option}`")),
+        }
+
+        Ok(())
+    }
+}
+
+/// The `--<option> <value>` pairs.
+fn pairs(mut arguments: impl Iterator<Item = String>) -> Result<Vec<(String, String)>, String> {
+    let mut pairs = vec![];
+
+    while let Some(argument) = arguments.next() {
+        let Some(option) = argument.strip_prefix("--") else {
+            return Err(format!("unexpected argument `{argument}`"));
+        };
+        let value = arguments
+            .next()
+            .ok_or_else(|| format!("missing value for `--{option}`"))?;
+
+        pairs.push((option.to_owned(), value));
+    }
+
+    Ok(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(arguments: &str) -> Result<Command, String> {
+        Command::parse(arguments.split(' ').map(str::to_owned))
+    }
+
+    #[test]
+    fn test_parse() {
+        let Ok(Command::CentralSystem {
+            csms_url,
+            charge_point_id,
+            options,
+        }) = parse("central-system --url ws://localhost:9000/ocpp/CP001 --id-tag ABC --only TC_001_CSMS,TC_003_CSMS")
+        else {
+            panic!("`central-system` is parsed");
+        };
+        assert_eq!(csms_url, "ws://localhost:9000/ocpp");
+        assert_eq!(charge_point_id, "CP001");
+        assert_eq!(options.config.id_tag, "ABC");
+        assert_eq!(options.only, ["TC_001_CSMS", "TC_003_CSMS"]);
+
+        let Ok(Command::ChargePoint { address, options }) =
+            parse("charge-point --port 9001 --timeout 5")
+        else {
+            panic!("`charge-point` is parsed");
+        };
+        assert_eq!(address, "0.0.0.0:9001");
+        assert_eq!(options.config.step_timeout, Duration::from_secs(5));
+
+        assert_eq!(
+            parse("charge-point --connector-id one").unwrap_err(),
+            "invalid value `one` for `--connector-id`"
+        );
+        assert_eq!(
+            parse("central-system --id CP001").unwrap_err(),
+            "missing `--url`"
+        );
+    }
+}
@@ -0,0 +1,350 @@
+//! The scenarios of the Core profile.
+//!
+//! The IDs are the ones of the OCA conformance test cases they mirror,
+//! suffixed by `_CSMS` when testing a Central System, and by `_CS` when
+//! testing a Charge Point. The `OCPPX_` ones have no OCA counterpart.
+
+use crate::{Expect, Scenario, Step, Target};
+use ocppx_rpc::ErrorCode;
+use serde_json::json;
+use std::time::Duration;
+
+/// The error codes of a malformed payload.
+const FORMAT_ERRORS: &[ErrorCode] = &[
+    ErrorCode::FormationViolation,
+    ErrorCode::FormatViolation,
+    ErrorCode::TypeConstraintViolation,
+    ErrorCode::PropertyConstraintViolation,
+    ErrorCode::OccurenceConstraintViolation,
+    ErrorCode::OccurrenceConstraintViolation,
+    ErrorCode::ProtocolError,
+];
+
+/// All the scenarios, in the order they must run: the scenarios testing a
+/// Charge Point share the same connection, and the last one resets it.
+pub fn scenarios() -> Vec<Scenario> {
+    vec![
+        // Testing a Central System.
+        Scenario {
+            id: "TC_001_CSMS",
+            name: "Cold boot of the Charge Point",
+            target: Target::CentralSystem,
+            steps: vec![
+                boot_notification(),
+                status_notification(json!(0), "Available"),
+                status_notification(json!("$connectorId"), "Available"),
+                Step::call(
+                    "Heartbeat",
+                    json!({}),
+                    Expect::result(json!({ "currentTime": "*" })),
+                ),
+            ],
+        },
+        Scenario {
+            id: "TC_003_CSMS",
+            name: "Regular charging session, plugged in first",
+            target: Target::CentralSystem,
+            steps: vec![
+                boot_notification(),
+                status_notification(json!("$connectorId"), "Preparing"),
+                Step::call(
+                    "Authorize",
+                    json!({ "idTag": "$idTag" }),
+                    Expect::result(json!({ "idTagInfo": { "status": "Accepted" } })),
+                ),
+                Step::call(
+                    "StartTransaction",
+                    json!({
+                        "connectorId": "$connectorId",
+                        "idTag": "$idTag",
+                        "meterStart": 0,
+                        "timestamp": "$now",
+                    }),
+                    Expect::result(json!({
+                        "idTagInfo": { "status": "Accepted" },
+                        "transactionId": "*",
+                    }))
+                    .save("transactionId", "/transactionId"),
+                ),
+                status_notification(json!("$connectorId"), "Charging"),
+                Step::call(
+                    "MeterValues",
+                    json!({
+                        "connectorId": "$connectorId",
+                        "transactionId": "$transactionId",
+                        "meterValue": [{
+                            "timestamp": "$now",
+                            "sampledValue": [{ "value": "1000" }],
+                        }],
+                    }),
+                    Expect::result(json!({})),
+                ),
+                Step::call(
+                    "StopTransaction",
+                    json!({
+                        "transactionId": "$transactionId",
+                        "idTag": "$idTag",
+                        "meterStop": 1000,
+                        "timestamp": "$now",
+                        "reason": "Local",
+                    }),
+                    Expect::result(json!({})),
+                ),
+                status_notification(json!("$connectorId"), "Finishing"),
+                status_notification(json!("$connectorId"), "Available"),
+            ],
+        },
+        Scenario {
+            id: "TC_023_1_CSMS",
+            name: "Authorize an invalid idTag",
+            target: Target::CentralSystem,
+            steps: vec![
+                boot_notification(),
+                Step::call(
+                    "Authorize",
+                    json!({ "idTag": "$invalidIdTag" }),
+                    Expect::result(json!({ "idTagInfo": { "status": "Invalid" } })),
+                ),
+            ],
+        },
+        Scenario {
+            id: "OCPPX_ERR_1_CSMS",
+            name: "Unknown action",
+            target: Target::CentralSystem,
+            steps: vec![
+                boot_notification(),
+                Step::call(
+                    "OcppxUnknownAction",
+                    json!({}),
+                    Expect::Error(&[ErrorCode::NotImplemented]),
+                ),
+            ],
+        },
+        Scenario {
+            id: "OCPPX_ERR_2_CSMS",
+            name: "Malformed payload",
+            target: Target::CentralSystem,
+            steps: vec![Step::call(
+                "BootNotification",
+                json!({ "chargePointVendor": 42 }),
+                Expect::Error(FORMAT_ERRORS),
+            )],
+        },
+        // Testing a Charge Point.
+        Scenario {
+            id: "TC_001_CS",
+            name: "Cold boot of the Charge Point",
+            target: Target::ChargePoint,
+            steps: vec![
+                Step::receive(
+                    "BootNotification",
+                    json!({
+                        "status": "Accepted",
+                        "currentTime": "$now",
+                        "interval": 300,
+                    }),
+                )
+                .matching(json!({
+                    "chargePointVendor": "*",
+                    "chargePointModel": "*",
+                })),
+                Step::receive("StatusNotification", json!({})).matching(json!({
+                    "connectorId": "*",
+                    "status": "*",
+                })),
+            ],
+        },
+        Scenario {
+            id: "TC_019_1_CS",
+            name: "Retrieve all the configuration keys",
+            target: Target::ChargePoint,
+            steps: vec![Step::call(
+                "GetConfiguration",
+                json!({}),
+                Expect::result(json!({ "configurationKey": "*" })),
+            )],
+        },
+        Scenario {
+            id: "TC_021_CS",
+            name: "Change a configuration key",
+            target: Target::ChargePoint,
+            steps: vec![
+                Step::call(
+                    "ChangeConfiguration",
+                    json!({ "key": "MeterValueSampleInterval", "value": "60" }),
+                    Expect::result(json!({ "status": "Accepted" })),
+                ),
+                Step::call(
+                    "GetConfiguration",
+                    json!({ "key": ["MeterValueSampleInterval"] }),
+                    Expect::result(json!({
+                        "configurationKey": [{ "key": "MeterValueSampleInterval", "value": "60" }],
+                    })),
+                ),
+            ],
+        },
+        Scenario {
+            id: "TC_040_1_CS",
+            name: "Unknown configuration key",
+            target: Target::ChargePoint,
+            steps: vec![
+                Step::call(
+                    "ChangeConfiguration",
+                    json!({ "key": "OcppxUnknownKey", "value": "1" }),
+                    Expect::result(json!({ "status": "NotSupported" })),
+                ),
+                Step::call(
+                    "GetConfiguration",
+                    json!({ "key": ["OcppxUnknownKey"] }),
+                    Expect::result(json!({ "unknownKey": ["OcppxUnknownKey"] })),
+                ),
+            ],
+        },
+        Scenario {
+            id: "TC_010_CS",
+            name: "Remote start and stop of a charging session",
+            target: Target::ChargePoint,
+            steps: vec![
+                Step::call(
+                    "RemoteStartTransaction",
+                    json!({ "connectorId": "$connectorId", "idTag": "$idTag" }),
+                    Expect::result(json!({ "status": "Accepted" })),
+                ),
+                Step::receive(
+                    "StartTransaction",
+                    json!({
+                        "idTagInfo": { "status": "Accepted" },
+                        "transactionId": 42,
+                    }),
+                )
+                .matching(json!({ "connectorId": "$connectorId", "idTag": "$idTag" })),
+                Step::call(
+                    "RemoteStopTransaction",
+                    json!({ "transactionId": 42 }),
+                    Expect::result(json!({ "status": "Accepted" })),
+                ),
+                Step::receive("StopTransaction", json!({}))
+                    .matching(json!({ "transactionId": 42 })),
+            ],
+        },
+        Scenario {
+            id: "TC_031_CS",
+            name: "Unlock an unknown connector",
+            target: Target::ChargePoint,
+            steps: vec![Step::call(
+                "UnlockConnector",
+                json!({ "connectorId": 99 }),
+                Expect::result(json!({ "status": "NotSupported" })),
+            )],
+        },
+        Scenario {
+            id: "OCPPX_ERR_1_CS",
+            name: "Unknown action",
+            target: Target::ChargePoint,
+            steps: vec![Step::call(
+                "OcppxUnknownAction",
+                json!({}),
+                Expect::Error(&[ErrorCode::NotImplemented]),
+            )],
+        },
+        Scenario {
+            id: "OCPPX_ERR_2_CS",
+            name: "Malformed payload",
+            target: Target::ChargePoint,
+            steps: vec![Step::call(
+                "ChangeAvailability",
+                json!({ "connectorId": "one", "type": "Inoperative" }),
+                Expect::Error(FORMAT_ERRORS),
+            )],
+        },
+        Scenario {
+            id: "TC_013_CS",
+            name: "Hard reset without a transaction",
+            target: Target::ChargePoint,
+            steps: vec![
+                Step::call(
+                    "Reset",
+                    json!({ "type": "Hard" }),
+                    Expect::result(json!({ "status": "Accepted" })),
+                ),
+                Step::Wait(Duration::from_secs(1)),
+                Step::receive(
+                    "BootNotification",
+                    json!({
+                        "status": "Accepted",
+                        "currentTime": "$now",
+                        "interval": 300,
+                    }),
+                ),
+            ],
+        },
+    ]
+}
+
+fn boot_notification() -> Step {
+    Step::call(
+        "BootNotification",
+        json!({
+            "chargePointVendor": "ocppx",
+            "chargePointModel": "ocppx-conformance",
+        }),
+        Expect::result(json!({
+            "status": "Accepted",
+            "currentTime": "*",
+            "interval": "*",
+        })),
+    )
+}
+
+fn status_notification(connector_id: serde_json::Value, status: &str) -> Step {
+    Step::call(
+        "StatusNotification",
+        json!({
+            "connectorId": connector_id,
+            "errorCode": "NoError",
+            "status": status,
+        }),
+        Expect::result(json!({})),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{scenario::Variables, ConformanceConfig};
+    use ocppx_types::v1_6::{validate, Action};
+
+    #[test]
+    fn test_scenarios_are_valid() {
+        let scenarios = scenarios();
+        let mut variables = Variables::new(&ConformanceConfig::default());
+        variables.0.insert("transactionId".to_owned(), json!(1));
+
+        for (index, scenario) in scenarios.iter().enumerate() {
+            assert!(
+                scenarios[..index]
+                    .iter()
+                    .all(|other| other.id != scenario.id),
+                "`{}` is duplicated",
+                scenario.id
+            );
+
+            // The payloads are valid, but the ones testing the errors.
+            if scenario.id.starts_with("OCPPX_ERR") {
+                continue;
+            }
+
+            for step in &scenario.steps {
+                if let Step::Call {
+                    action, payload, ..
+                } = step
+                {
+                    let payload = variables.substitute(payload).unwrap();
+
+                    validate(action.parse::<Action>().unwrap(), &payload)
+                        .unwrap_or_else(|error| panic!("`{}`: {error}", scenario.id));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! Conformance tests of OCPP-J 1.6 endpoints, after the test cases of the
+//! Core profile of the OCA certification: boot, transaction and error
+//! flows.
+//!
+//! A [`Scenario`] is a script of [`Step`]s: `Call`s to send and their
+//! expected responses, `Call`s to wait for and how to respond to them.
+//! The scenarios of [`core_profile::scenarios`] test either a Central
+//! System, with [`test_central_system`], the suite playing a Charge Point,
+//! or a Charge Point, with [`test_charge_point`], the suite playing a
+//! Central System. Both give a [`Report`], with the outcome of every
+//! scenario.
+//!
+//! The `ocppx-conformance` binary runs them from the command line, run
+//! `ocppx-conformance help` for the usage.
+
+pub mod core_profile;
+mod peer;
+mod report;
+mod runner;
+mod scenario;
+
+pub use report::{Outcome, Report, ScenarioResult};
+pub use runner::{test_central_system, test_charge_point};
+pub use scenario::{Expect, Scenario, Step, Target};
+use std::time::Duration;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("I/O error")]
+    Io(#[from] std::io::Error),
+
+    #[error("client error")]
+    Client(#[from] ocppx_client::Error),
+
+    #[error("server error")]
+    Server(#[from] ocppx_server::Error),
+
+    #[error("RPC error")]
+    Rpc(#[from] ocppx_rpc::Error),
+
+    #[error("no Charge Point connected after {0:?}")]
+    NotConnected(Duration),
+
+    #[error("no `{action}` received after {timeout:?}")]
+    NotReceived { action: String, timeout: Duration },
+
+    #[error("the connection is closed")]
+    ConnectionClosed,
+}
+
+/// Configuration of a conformance run.
+///
+/// The values are the variables of the scenarios: `$idTag`,
+/// `$invalidIdTag` and `$connectorId`.
+#[derive(Debug, Clone)]
+pub struct ConformanceConfig {
+    /// An idTag accepted by the endpoint under test.
+    pub id_tag: String,
+    /// An idTag unknown to the endpoint under test.
+    pub invalid_id_tag: String,
+    /// A connector of the Charge Point.
+    pub connector_id: i32,
+    /// Password sent with HTTP Basic Authentication to the Central System
+    /// under test.
+    pub password: Option<String>,
+    /// Time to wait for a response, a `Call`, or a connection, before
+    /// failing the step.
+    pub step_timeout: Duration,
+}
+
+impl Default for ConformanceConfig {
+    fn default() -> Self {
+        Self {
+            id_tag: "OCPPX-ACCEPTED".to_owned(),
+            invalid_id_tag: "OCPPX-INVALID".to_owned(),
+            connector_id: 1,
+            password: None,
+            step_timeout: Duration::from_secs(30),
+        }
+    }
+}
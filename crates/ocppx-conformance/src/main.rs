@@ -0,0 +1,77 @@
+//! `ocppx-conformance`, to run the conformance scenarios against a Central
+//! System or a Charge Point, and print the report. Run
+//! `ocppx-conformance help` for the usage.
+
+mod args;
+
+use args::{Command, Options, USAGE};
+use ocppx_conformance::{core_profile, test_central_system, test_charge_point, Report, Scenario};
+use std::{env, process::ExitCode};
+use tokio::net::TcpListener;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let report = match Command::parse(env::args().skip(1)) {
+        Ok(Command::CentralSystem {
+            csms_url,
+            charge_point_id,
+            options,
+        }) => {
+            let scenarios = select(&options);
+
+            test_central_system(&csms_url, &charge_point_id, &options.config, &scenarios).await
+        }
+        Ok(Command::ChargePoint { address, options }) => {
+            let listener = match TcpListener::bind(&address).await {
+                Ok(listener) => listener,
+                Err(error) => {
+                    eprintln!("error: cannot listen on {address}: {error}");
+
+                    return ExitCode::FAILURE;
+                }
+            };
+            println!("Waiting for the Charge Point on ws://{address}");
+
+            test_charge_point(listener, &options.config, &select(&options)).await
+        }
+        Ok(Command::List) => {
+            for scenario in core_profile::scenarios() {
+                println!("{:<18} {}", scenario.id, scenario.name);
+            }
+
+            return ExitCode::SUCCESS;
+        }
+        Ok(Command::Help) => {
+            print!("{USAGE}");
+
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprint!("error: {error}\n\n{USAGE}");
+
+            return ExitCode::FAILURE;
+        }
+    };
+
+    print_report(&report)
+}
+
+/// The scenarios of `--only`, or all of them.
+fn select(options: &Options) -> Vec<Scenario> {
+    core_profile::scenarios()
+        .into_iter()
+        .filter(|scenario| {
+            options.only.is_empty() || options.only.iter().any(|id| id == scenario.id)
+        })
+        .collect()
+}
+
+fn print_report(report: &Report) -> ExitCode {
+    println!("{report}");
+
+    if report.is_success() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
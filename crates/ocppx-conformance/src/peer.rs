@@ -0,0 +1,391 @@
+//! The suite, playing either a Charge Point or a Central System in front
+//! of the endpoint under test.
+
+use crate::{ConformanceConfig, Error, Result};
+use chrono::{SecondsFormat, Utc};
+use ocppx_client::{ChargePointClient, ClientConfig};
+use ocppx_rpc::{Call, CallError, CallResult, ErrorCode, Message};
+use ocppx_server::{CsmsHandler, Server, ServerConfig};
+use serde_json::{json, Value};
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+use tokio::{
+    sync::{mpsc, oneshot, watch},
+    time::{timeout_at, Instant},
+};
+
+/// The side of the connection played by the suite.
+pub(crate) trait Peer {
+    /// Send a `Call` to the endpoint under test, and wait for its response:
+    /// the payload of the `CallResult`, or the `CallError`.
+    async fn call(
+        &mut self,
+        action: &str,
+        payload: Value,
+    ) -> Result<std::result::Result<Value, CallError>>;
+
+    /// Wait for a `Call` of `action` from the endpoint under test. The
+    /// other `Call`s get a default response. The `Call` must be answered
+    /// with [`Self::respond`].
+    async fn receive(&mut self, action: &str) -> Result<Call>;
+
+    /// Respond to a `Call` returned by [`Self::receive`].
+    fn respond(&mut self, response: Message) -> Result<()>;
+
+    /// Answer the `Call`s left unanswered, at the end of a scenario.
+    async fn settle(&mut self);
+}
+
+/// The suite as a Charge Point, to test a Central System.
+pub(crate) struct CentralSystemPeer {
+    client: ChargePointClient,
+    /// The `Call`s received while waiting for a response.
+    backlog: VecDeque<Call>,
+    timeout: Duration,
+}
+
+impl CentralSystemPeer {
+    pub(crate) async fn connect(
+        csms_url: &str,
+        charge_point_id: &str,
+        config: &ConformanceConfig,
+    ) -> Result<Self> {
+        let client = ChargePointClient::connect_with_config(
+            csms_url,
+            charge_point_id,
+            ClientConfig {
+                call_timeout: config.step_timeout,
+                basic_auth_password: config.password.clone(),
+                reconnect: None,
+                // The scenarios send their own `Heartbeat`s.
+                heartbeat: false,
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        Ok(Self {
+            client,
+            backlog: VecDeque::new(),
+            timeout: config.step_timeout,
+        })
+    }
+
+    pub(crate) async fn close(self) {
+        let _ = self.client.close().await;
+    }
+
+    /// The Central System sent a `Call` the scenario does not expect.
+    fn respond_by_default(&self, call: Call) {
+        let _ = self.client.respond(CallError::new(
+            call.unique_id,
+            ErrorCode::NotImplemented,
+            format!("`{}` is not expected by the scenario", call.action),
+            None,
+        ));
+    }
+}
+
+impl Peer for CentralSystemPeer {
+    async fn call(
+        &mut self,
+        action: &str,
+        payload: Value,
+    ) -> Result<std::result::Result<Value, CallError>> {
+        let call = self.client.call::<Value, Value>(action, &payload);
+        tokio::pin!(call);
+
+        loop {
+            tokio::select! {
+                response = &mut call => return match response {
+                    Ok(payload) => Ok(Ok(payload)),
+                    Err(ocppx_client::Error::CallError(call_error)) => Ok(Err(call_error)),
+                    Err(error) => Err(error.into()),
+                },
+                Some(call) = self.client.next_call() => self.backlog.push_back(call),
+            }
+        }
+    }
+
+    async fn receive(&mut self, action: &str) -> Result<Call> {
+        while let Some(call) = self.backlog.pop_front() {
+            if call.action == action {
+                return Ok(call);
+            }
+
+            self.respond_by_default(call);
+        }
+
+        let deadline = Instant::now() + self.timeout;
+
+        loop {
+            match timeout_at(deadline, self.client.next_call()).await {
+                Ok(Some(call)) if call.action == action => return Ok(call),
+                Ok(Some(call)) => self.respond_by_default(call),
+                Ok(None) => return Err(Error::ConnectionClosed),
+                Err(_) => {
+                    return Err(Error::NotReceived {
+                        action: action.to_owned(),
+                        timeout: self.timeout,
+                    })
+                }
+            }
+        }
+    }
+
+    fn respond(&mut self, response: Message) -> Result<()> {
+        Ok(self.client.respond(response)?)
+    }
+
+    async fn settle(&mut self) {
+        while let Some(call) = self.backlog.pop_front() {
+            self.respond_by_default(call);
+        }
+    }
+}
+
+/// A `Call` of the Charge Point, waiting for the scenario to respond.
+struct Incoming {
+    call: Call,
+    responder: oneshot::Sender<Message>,
+}
+
+/// The handler of the suite as a Central System: the `Call`s are handed
+/// to the scenario.
+pub(crate) struct ScriptedHandler {
+    calls: mpsc::UnboundedSender<Incoming>,
+    charge_point: watch::Sender<Option<String>>,
+}
+
+impl CsmsHandler for ScriptedHandler {
+    async fn handle_call(
+        &self,
+        _charge_point_id: &str,
+        call: Call,
+    ) -> std::result::Result<CallResult, CallError> {
+        let unique_id = call.unique_id.clone();
+        let (responder, response) = oneshot::channel();
+        let _ = self.calls.send(Incoming { call, responder });
+
+        match response.await {
+            Ok(Message::CallResult(call_result)) => Ok(call_result),
+            Ok(Message::CallError(call_error)) => Err(call_error),
+            Ok(Message::Call(_)) | Err(_) => Err(CallError::new(
+                unique_id,
+                ErrorCode::InternalError,
+                "the scenario did not respond",
+                None,
+            )),
+        }
+    }
+
+    async fn connected(&self, charge_point_id: &str) {
+        self.charge_point
+            .send_replace(Some(charge_point_id.to_owned()));
+    }
+
+    async fn disconnected(&self, charge_point_id: &str) {
+        self.charge_point.send_if_modified(|charge_point| {
+            if charge_point.as_deref() == Some(charge_point_id) {
+                *charge_point = None;
+
+                true
+            } else {
+                false
+            }
+        });
+    }
+}
+
+/// The suite as a Central System, to test the Charge Point connected
+/// last.
+pub(crate) struct ChargePointPeer {
+    server: Server<ScriptedHandler>,
+    charge_point: watch::Receiver<Option<String>>,
+    calls: mpsc::UnboundedReceiver<Incoming>,
+    /// The `Call`s received while waiting for a response.
+    backlog: VecDeque<Incoming>,
+    /// The `Call`s returned by [`Peer::receive`], by unique ID.
+    received: HashMap<String, oneshot::Sender<Message>>,
+    next_transaction_id: i32,
+    timeout: Duration,
+}
+
+impl ChargePointPeer {
+    pub(crate) fn new(config: &ConformanceConfig) -> Self {
+        let (calls_sender, calls) = mpsc::unbounded_channel();
+        let (charge_point_sender, charge_point) = watch::channel(None);
+
+        let server = Server::with_config(
+            ScriptedHandler {
+                calls: calls_sender,
+                charge_point: charge_point_sender,
+            },
+            ServerConfig {
+                call_timeout: config.step_timeout,
+                ..Default::default()
+            },
+        );
+
+        Self {
+            server,
+            charge_point,
+            calls,
+            backlog: VecDeque::new(),
+            received: HashMap::new(),
+            next_transaction_id: 1,
+            timeout: config.step_timeout,
+        }
+    }
+
+    pub(crate) fn server(&self) -> &Server<ScriptedHandler> {
+        &self.server
+    }
+
+    /// The identity of the connected Charge Point.
+    async fn charge_point_id(&mut self) -> Result<String> {
+        let charge_point =
+            tokio::time::timeout(self.timeout, self.charge_point.wait_for(Option::is_some))
+                .await
+                .map_err(|_| Error::NotConnected(self.timeout))?
+                .map_err(|_| Error::ConnectionClosed)?;
+
+        Ok(charge_point.clone().unwrap_or_default())
+    }
+
+    /// The Charge Point sent a `Call` the scenario does not expect:
+    /// everything is accepted.
+    fn respond_by_default(&mut self, incoming: Incoming) {
+        let Incoming { call, responder } = incoming;
+        let now = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        let accepted = json!({ "status": "Accepted" });
+
+        let payload = match call.action.as_str() {
+            "BootNotification" => json!({
+                "status": "Accepted",
+                "currentTime": now,
+                "interval": 300,
+            }),
+            "Heartbeat" => json!({ "currentTime": now }),
+            "Authorize" => json!({ "idTagInfo": accepted }),
+            "StartTransaction" => {
+                let transaction_id = self.next_transaction_id;
+                self.next_transaction_id += 1;
+
+                json!({ "idTagInfo": accepted, "transactionId": transaction_id })
+            }
+            "StopTransaction"
+            | "StatusNotification"
+            | "MeterValues"
+            | "DiagnosticsStatusNotification"
+            | "FirmwareStatusNotification" => json!({}),
+            "DataTransfer" => json!({ "status": "UnknownVendorId" }),
+            action => {
+                let _ = responder.send(
+                    CallError::new(
+                        call.unique_id,
+                        ErrorCode::NotImplemented,
+                        format!("unknown action `{action}`"),
+                        None,
+                    )
+                    .into(),
+                );
+
+                return;
+            }
+        };
+
+        let _ = responder.send(
+            CallResult {
+                unique_id: call.unique_id,
+                payload,
+            }
+            .into(),
+        );
+    }
+
+    fn accept(&mut self, incoming: Incoming) -> Call {
+        self.received
+            .insert(incoming.call.unique_id.clone(), incoming.responder);
+
+        incoming.call
+    }
+}
+
+impl Peer for ChargePointPeer {
+    async fn call(
+        &mut self,
+        action: &str,
+        payload: Value,
+    ) -> Result<std::result::Result<Value, CallError>> {
+        let charge_point_id = self.charge_point_id().await?;
+        let call = self
+            .server
+            .call::<Value, Value>(&charge_point_id, action, &payload);
+        tokio::pin!(call);
+
+        loop {
+            tokio::select! {
+                response = &mut call => return match response {
+                    Ok(payload) => Ok(Ok(payload)),
+                    Err(ocppx_server::Error::CallError(call_error)) => Ok(Err(call_error)),
+                    Err(error) => Err(error.into()),
+                },
+                Some(incoming) = self.calls.recv() => self.backlog.push_back(incoming),
+            }
+        }
+    }
+
+    async fn receive(&mut self, action: &str) -> Result<Call> {
+        while let Some(incoming) = self.backlog.pop_front() {
+            if incoming.call.action == action {
+                return Ok(self.accept(incoming));
+            }
+
+            self.respond_by_default(incoming);
+        }
+
+        let deadline = Instant::now() + self.timeout;
+
+        loop {
+            match timeout_at(deadline, self.calls.recv()).await {
+                Ok(Some(incoming)) if incoming.call.action == action => {
+                    return Ok(self.accept(incoming))
+                }
+                Ok(Some(incoming)) => self.respond_by_default(incoming),
+                Ok(None) => return Err(Error::ConnectionClosed),
+                Err(_) => {
+                    return Err(Error::NotReceived {
+                        action: action.to_owned(),
+                        timeout: self.timeout,
+                    })
+                }
+            }
+        }
+    }
+
+    fn respond(&mut self, response: Message) -> Result<()> {
+        let responder = self
+            .received
+            .remove(response.unique_id())
+            .ok_or(Error::ConnectionClosed)?;
+
+        responder
+            .send(response)
+            .map_err(|_| Error::ConnectionClosed)
+    }
+
+    async fn settle(&mut self) {
+        while let Some(incoming) = self.backlog.pop_front() {
+            self.respond_by_default(incoming);
+        }
+
+        while let Ok(incoming) = self.calls.try_recv() {
+            self.respond_by_default(incoming);
+        }
+
+        self.received.clear();
+    }
+}
@@ -0,0 +1,107 @@
+use std::{fmt, time::Duration};
+
+/// The outcome of a [`Scenario`][crate::Scenario].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    /// The scenario stopped at a failing step, with the reason.
+    Failed(String),
+}
+
+/// The result of a [`Scenario`][crate::Scenario] in a [`Report`].
+#[derive(Debug, Clone)]
+pub struct ScenarioResult {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub outcome: Outcome,
+    pub duration: Duration,
+}
+
+/// The results of a conformance run, printed as one line per scenario,
+/// and a summary.
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    pub results: Vec<ScenarioResult>,
+}
+
+impl Report {
+    pub fn passed(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|result| result.outcome == Outcome::Passed)
+            .count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results.len() - self.passed()
+    }
+
+    /// Whether all the scenarios have passed.
+    pub fn is_success(&self) -> bool {
+        self.failed() == 0
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for result in &self.results {
+            let (status, reason) = match &result.outcome {
+                Outcome::Passed => ("PASS", None),
+                Outcome::Failed(reason) => ("FAIL", Some(reason)),
+            };
+
+            writeln!(
+                formatter,
+                "{status} {id} {name} ({duration:.2?})",
+                id = result.id,
+                name = result.name,
+                duration = result.duration,
+            )?;
+
+            if let Some(reason) = reason {
+                writeln!(formatter, "     {reason}")?;
+            }
+        }
+
+        write!(
+            formatter,
+            "{} passed, {} failed",
+            self.passed(),
+            self.failed()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let report = Report {
+            results: vec![
+                ScenarioResult {
+                    id: "TC_001_CSMS",
+                    name: "Cold boot",
+                    outcome: Outcome::Passed,
+                    duration: Duration::from_millis(12),
+                },
+                ScenarioResult {
+                    id: "TC_023_CSMS",
+                    name: "Authorize an invalid idTag",
+                    outcome: Outcome::Failed("`/idTagInfo/status`: expected ...".to_owned()),
+                    duration: Duration::from_millis(3),
+                },
+            ],
+        };
+
+        assert!(!report.is_success());
+        assert_eq!(
+            report.to_string(),
+            "PASS TC_001_CSMS Cold boot (12.00ms)\n\
+             FAIL TC_023_CSMS Authorize an invalid idTag (3.00ms)\n     \
+             `/idTagInfo/status`: expected ...\n\
+             1 passed, 1 failed"
+        );
+    }
+}
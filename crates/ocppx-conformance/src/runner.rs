@@ -0,0 +1,436 @@
+use crate::{
+    peer::{CentralSystemPeer, ChargePointPeer, Peer},
+    scenario::{self, Variables},
+    ConformanceConfig, Expect, Outcome, Report, Scenario, ScenarioResult, Step, Target,
+};
+use ocppx_rpc::{CallError, CallResult, ErrorCode};
+use ocppx_types::v1_6::{validate, validate_response, Action};
+use std::{error::Error as _, time::Duration};
+use tokio::{net::TcpListener, time::Instant};
+
+/// Run the scenarios targeting a Central System against the Central System
+/// at `csms_url`, as the Charge Point `charge_point_id`. Every scenario
+/// runs on a new connection.
+pub async fn test_central_system(
+    csms_url: &str,
+    charge_point_id: &str,
+    config: &ConformanceConfig,
+    scenarios: &[Scenario],
+) -> Report {
+    let mut report = Report::default();
+
+    for scenario in scenarios
+        .iter()
+        .filter(|scenario| scenario.target == Target::CentralSystem)
+    {
+        let started = Instant::now();
+        let outcome = match CentralSystemPeer::connect(csms_url, charge_point_id, config).await {
+            Ok(mut peer) => {
+                let outcome = run(&mut peer, scenario, config).await;
+                peer.close().await;
+
+                outcome
+            }
+            Err(error) => Outcome::Failed(format!("cannot connect: {}", describe(&error))),
+        };
+
+        report.results.push(result(scenario, outcome, started));
+    }
+
+    report
+}
+
+/// Run the scenarios targeting a Charge Point against the Charge Point
+/// connecting to `listener`, in order, on the same connection, e.g. the
+/// first scenario waits for its `BootNotification`.
+pub async fn test_charge_point(
+    listener: TcpListener,
+    config: &ConformanceConfig,
+    scenarios: &[Scenario],
+) -> Report {
+    let mut peer = ChargePointPeer::new(config);
+    let server = peer.server().clone();
+    let serving = tokio::spawn(async move { server.serve(listener).await });
+
+    let mut report = Report::default();
+
+    for scenario in scenarios
+        .iter()
+        .filter(|scenario| scenario.target == Target::ChargePoint)
+    {
+        let started = Instant::now();
+        let outcome = run(&mut peer, scenario, config).await;
+
+        report.results.push(result(scenario, outcome, started));
+    }
+
+    peer.server().shutdown(Duration::from_secs(1)).await;
+    serving.abort();
+
+    report
+}
+
+fn result(scenario: &Scenario, outcome: Outcome, started: Instant) -> ScenarioResult {
+    ScenarioResult {
+        id: scenario.id,
+        name: scenario.name,
+        outcome,
+        duration: started.elapsed(),
+    }
+}
+
+/// Run the steps of `scenario`, up to the first failing one.
+async fn run<P: Peer>(peer: &mut P, scenario: &Scenario, config: &ConformanceConfig) -> Outcome {
+    let mut variables = Variables::new(config);
+
+    let mut outcome = Outcome::Passed;
+
+    for (index, step) in scenario.steps.iter().enumerate() {
+        if let Err(reason) = run_step(peer, step, &mut variables).await {
+            outcome = Outcome::Failed(format!(
+                "step {} ({}): {reason}",
+                index + 1,
+                step.action().unwrap_or("Wait"),
+            ));
+
+            break;
+        }
+    }
+
+    peer.settle().await;
+
+    outcome
+}
+
+async fn run_step<P: Peer>(
+    peer: &mut P,
+    step: &Step,
+    variables: &mut Variables,
+) -> Result<(), String> {
+    match step {
+        Step::Call {
+            action,
+            payload,
+            expect,
+        } => {
+            let payload = variables.substitute(payload)?;
+            let response = peer
+                .call(action, payload)
+                .await
+                .map_err(|error| describe(&error))?;
+
+            match (expect, response) {
+                (Expect::Result { matches, save }, Ok(payload)) => {
+                    if let Ok(action) = action.parse::<Action>() {
+                        validate_response(action, &payload)
+                            .map_err(|error| format!("invalid response: {error}"))?;
+                    }
+
+                    scenario::matches(&variables.substitute(matches)?, &payload)?;
+                    variables.save(save, &payload)
+                }
+                (Expect::Result { .. }, Err(call_error)) => Err(format!(
+                    "expected a `CallResult`, got a `{}` `CallError`: {}",
+                    call_error.error_code, call_error.error_description,
+                )),
+                (Expect::Error(error_codes), Err(call_error))
+                    if error_codes.contains(&call_error.error_code) =>
+                {
+                    Ok(())
+                }
+                (Expect::Error(error_codes), response) => Err(format!(
+                    "expected a `CallError` with {}, got {}",
+                    error_codes
+                        .iter()
+                        .map(|error_code| format!("`{error_code}`"))
+                        .collect::<Vec<_>>()
+                        .join(" or "),
+                    match response {
+                        Ok(payload) => format!("a `CallResult`: `{payload}`"),
+                        Err(call_error) => format!("`{}`", call_error.error_code),
+                    },
+                )),
+            }
+        }
+
+        Step::Receive {
+            action,
+            matches,
+            save,
+            respond,
+        } => {
+            let call = peer
+                .receive(action)
+                .await
+                .map_err(|error| describe(&error))?;
+
+            if let Ok(action) = action.parse::<Action>() {
+                if let Err(error) = validate(action, &call.payload) {
+                    let reason = format!("invalid request: {error}");
+                    let _ = peer.respond(
+                        CallError::new(
+                            &call.unique_id,
+                            ErrorCode::FormationViolation,
+                            &reason,
+                            None,
+                        )
+                        .into(),
+                    );
+
+                    return Err(reason);
+                }
+            }
+
+            // The `Call` is answered even if it is not the expected one,
+            // for the Charge Point not to wait for the timeout.
+            let checked = variables
+                .substitute(matches)
+                .and_then(|matches| scenario::matches(&matches, &call.payload))
+                .and_then(|()| variables.save(save, &call.payload));
+            let payload = variables.substitute(respond)?;
+
+            peer.respond(
+                CallResult {
+                    unique_id: call.unique_id,
+                    payload,
+                }
+                .into(),
+            )
+            .map_err(|error| describe(&error))?;
+
+            checked
+        }
+
+        Step::Wait(duration) => {
+            tokio::time::sleep(*duration).await;
+
+            Ok(())
+        }
+    }
+}
+
+/// The error, and its sources.
+fn describe(error: &crate::Error) -> String {
+    let mut description = error.to_string();
+    let mut source = error.source();
+
+    while let Some(error) = source {
+        description.push_str(&format!(": {error}"));
+        source = error.source();
+    }
+
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_profile;
+    use ocppx_client::ChargePointClient;
+    use ocppx_server::{CsmsHandler, Server};
+    use ocppx_types::v1_6::Request;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+
+    /// A Central System accepting `OCPPX-ACCEPTED` only.
+    struct CentralSystem;
+
+    impl CsmsHandler for CentralSystem {
+        async fn handle_call(
+            &self,
+            _charge_point_id: &str,
+            call: ocppx_rpc::Call,
+        ) -> Result<CallResult, CallError> {
+            let error = |error_code| CallError::new(&call.unique_id, error_code, "", None);
+            let action = call
+                .action
+                .parse::<Action>()
+                .map_err(|_| error(ErrorCode::NotImplemented))?;
+            Request::from_payload(action, call.payload.clone())
+                .map_err(|_| error(ErrorCode::FormationViolation))?;
+
+            let id_tag_info = || {
+                json!({
+                    "status": if call.payload["idTag"] == "OCPPX-ACCEPTED" { "Accepted" } else { "Invalid" },
+                })
+            };
+            let now = "2013-02-01T20:53:32.486Z";
+            let payload = match action {
+                Action::BootNotification => {
+                    json!({ "status": "Accepted", "currentTime": now, "interval": 300 })
+                }
+                Action::Heartbeat => json!({ "currentTime": now }),
+                Action::Authorize => json!({ "idTagInfo": id_tag_info() }),
+                Action::StartTransaction => {
+                    json!({ "idTagInfo": id_tag_info(), "transactionId": 7 })
+                }
+                _ => json!({}),
+            };
+
+            Ok(CallResult {
+                unique_id: call.unique_id,
+                payload,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_central_system_scenarios() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { Server::new(CentralSystem).serve(listener).await });
+
+        let url = format!("ws://{address}/ocpp");
+        let scenarios = core_profile::scenarios();
+        let config = ConformanceConfig {
+            step_timeout: Duration::from_secs(5),
+            ..Default::default()
+        };
+
+        let report = test_central_system(&url, "CP001", &config, &scenarios).await;
+        assert!(report.is_success(), "{report}");
+        assert_eq!(report.passed(), 5);
+
+        // The invalid idTag is accepted.
+        let report = test_central_system(
+            &url,
+            "CP001",
+            &ConformanceConfig {
+                invalid_id_tag: config.id_tag.clone(),
+                ..config
+            },
+            &scenarios,
+        )
+        .await;
+        let failed = report
+            .results
+            .iter()
+            .filter(|result| result.outcome != Outcome::Passed)
+            .map(|result| result.id)
+            .collect::<Vec<_>>();
+        assert_eq!(failed, ["TC_023_1_CSMS"]);
+    }
+
+    /// A Charge Point with a single connector, and a single configuration
+    /// key.
+    async fn charge_point(client: Arc<ChargePointClient>) {
+        let boot = json!({ "chargePointVendor": "X", "chargePointModel": "Y" });
+        client
+            .call::<_, Value>("BootNotification", &boot)
+            .await
+            .unwrap();
+        client
+            .call::<_, Value>(
+                "StatusNotification",
+                &json!({ "connectorId": 1, "errorCode": "NoError", "status": "Available" }),
+            )
+            .await
+            .unwrap();
+
+        while let Some(call) = client.next_call().await {
+            let known = |key: &Value| key == "MeterValueSampleInterval";
+            let (payload, then) = match call.action.as_str() {
+                "GetConfiguration" => {
+                    let keys = call.payload["key"].as_array().cloned().unwrap_or_default();
+                    let (known_keys, unknown_keys): (Vec<_>, Vec<_>) =
+                        keys.into_iter().partition(known);
+
+                    (
+                        json!({
+                            "configurationKey": known_keys
+                                .iter()
+                                .map(|key| json!({ "key": key, "readonly": false, "value": "60" }))
+                                .collect::<Vec<_>>(),
+                            "unknownKey": unknown_keys,
+                        }),
+                        None,
+                    )
+                }
+                "ChangeConfiguration" if known(&call.payload["key"]) => {
+                    (json!({ "status": "Accepted" }), None)
+                }
+                "ChangeConfiguration" => (json!({ "status": "NotSupported" }), None),
+                "RemoteStartTransaction" => (
+                    json!({ "status": "Accepted" }),
+                    Some((
+                        "StartTransaction",
+                        json!({
+                            "connectorId": 1,
+                            "idTag": call.payload["idTag"],
+                            "meterStart": 0,
+                            "timestamp": "2013-02-01T20:53:32.486Z",
+                        }),
+                    )),
+                ),
+                "RemoteStopTransaction" => (
+                    json!({ "status": "Accepted" }),
+                    Some((
+                        "StopTransaction",
+                        json!({
+                            "transactionId": call.payload["transactionId"],
+                            "meterStop": 10,
+                            "timestamp": "2013-02-01T20:53:32.486Z",
+                        }),
+                    )),
+                ),
+                _ => {
+                    client
+                        .respond(CallError::new(
+                            call.unique_id,
+                            ErrorCode::NotImplemented,
+                            "",
+                            None,
+                        ))
+                        .unwrap();
+
+                    continue;
+                }
+            };
+
+            client
+                .respond(CallResult {
+                    unique_id: call.unique_id,
+                    payload,
+                })
+                .unwrap();
+
+            if let Some((action, payload)) = then {
+                let client = client.clone();
+                tokio::spawn(async move { client.call::<_, Value>(action, &payload).await });
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_charge_point_scenarios() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let scenarios = core_profile::scenarios()
+            .into_iter()
+            .filter(|scenario| {
+                [
+                    "TC_001_CS",
+                    "TC_021_CS",
+                    "TC_040_1_CS",
+                    "TC_010_CS",
+                    "OCPPX_ERR_1_CS",
+                ]
+                .contains(&scenario.id)
+            })
+            .collect::<Vec<_>>();
+        let config = ConformanceConfig {
+            step_timeout: Duration::from_secs(5),
+            ..Default::default()
+        };
+
+        let (report, ()) = tokio::join!(test_charge_point(listener, &config, &scenarios), async {
+            let client = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001")
+                .await
+                .unwrap();
+            tokio::spawn(charge_point(Arc::new(client)));
+        });
+
+        assert!(report.is_success(), "{report}");
+        assert_eq!(report.passed(), 5);
+    }
+}
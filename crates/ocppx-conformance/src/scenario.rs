@@ -0,0 +1,287 @@
+use crate::ConformanceConfig;
+use chrono::{SecondsFormat, Utc};
+use ocppx_rpc::ErrorCode;
+use serde_json::Value;
+use std::{collections::HashMap, time::Duration};
+
+/// The endpoint under test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    /// A Central System: the suite plays the Charge Point.
+    CentralSystem,
+    /// A Charge Point: the suite plays the Central System.
+    ChargePoint,
+}
+
+/// A test case: a sequence of steps against the endpoint under test,
+/// stopping at the first failing one.
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    /// The ID of the test case, e.g. `TC_001_CSMS`, after the OCA
+    /// conformance test cases when there is one.
+    pub id: &'static str,
+    pub name: &'static str,
+    pub target: Target,
+    pub steps: Vec<Step>,
+}
+
+/// A step of a [`Scenario`].
+///
+/// The payloads are templates: a string `"$name"` is replaced by the
+/// variable `name`, e.g. `$idTag` from the [`ConformanceConfig`], a value
+/// saved by a previous step, or `$now` for the current time.
+///
+/// [`ConformanceConfig`]: crate::ConformanceConfig
+#[derive(Debug, Clone)]
+pub enum Step {
+    /// Send a `Call` to the endpoint under test, and check its response.
+    Call {
+        action: &'static str,
+        payload: Value,
+        expect: Expect,
+    },
+    /// Wait for a `Call` of the endpoint under test, check it, and respond
+    /// to it. The other `Call`s received in the meantime get default
+    /// responses.
+    Receive {
+        action: &'static str,
+        /// What the payload must contain, see [`Expect::Result`].
+        matches: Value,
+        /// The variables to save from the payload, by JSON pointer.
+        save: Vec<(&'static str, &'static str)>,
+        respond: Value,
+    },
+    /// Let the endpoint under test work, e.g. reboot.
+    Wait(Duration),
+}
+
+impl Step {
+    pub fn call(action: &'static str, payload: Value, expect: Expect) -> Self {
+        Self::Call {
+            action,
+            payload,
+            expect,
+        }
+    }
+
+    pub fn receive(action: &'static str, respond: Value) -> Self {
+        Self::Receive {
+            action,
+            matches: Value::Object(Default::default()),
+            save: Vec::new(),
+            respond,
+        }
+    }
+
+    /// What the payload of a [`Step::Receive`] must contain.
+    pub fn matching(mut self, payload: Value) -> Self {
+        if let Self::Receive { matches, .. } = &mut self {
+            *matches = payload;
+        }
+
+        self
+    }
+
+    /// The action of the `Call` sent or received.
+    pub fn action(&self) -> Option<&'static str> {
+        match self {
+            Self::Call { action, .. } | Self::Receive { action, .. } => Some(action),
+            Self::Wait(_) => None,
+        }
+    }
+}
+
+/// The expected response to a [`Step::Call`].
+#[derive(Debug, Clone)]
+pub enum Expect {
+    /// A `CallResult`, valid against the schema of the action, and
+    /// containing `matches`: the properties of its objects must be in the
+    /// payload, with the same values, and `"*"` stands for any value.
+    Result {
+        matches: Value,
+        /// The variables to save from the payload, by JSON pointer.
+        save: Vec<(&'static str, &'static str)>,
+    },
+    /// A `CallError` with one of these codes.
+    Error(&'static [ErrorCode]),
+}
+
+impl Expect {
+    pub fn result(matches: Value) -> Self {
+        Self::Result {
+            matches,
+            save: Vec::new(),
+        }
+    }
+
+    /// Save the value at `pointer` in the payload as the variable `name`.
+    pub fn save(mut self, name: &'static str, pointer: &'static str) -> Self {
+        if let Self::Result { save, .. } = &mut self {
+            save.push((name, pointer));
+        }
+
+        self
+    }
+}
+
+/// The variables of a scenario.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Variables(pub(crate) HashMap<String, Value>);
+
+impl Variables {
+    /// The variables of the configuration.
+    pub(crate) fn new(config: &ConformanceConfig) -> Self {
+        Self(HashMap::from([
+            ("idTag".to_owned(), Value::from(config.id_tag.as_str())),
+            (
+                "invalidIdTag".to_owned(),
+                Value::from(config.invalid_id_tag.as_str()),
+            ),
+            ("connectorId".to_owned(), Value::from(config.connector_id)),
+        ]))
+    }
+
+    /// Replace the `"$name"` strings of `template`.
+    pub(crate) fn substitute(&self, template: &Value) -> Result<Value, String> {
+        Ok(match template {
+            Value::String(string) => match string.strip_prefix('$') {
+                Some("now") => {
+                    Value::String(Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true))
+                }
+                Some(name) => self
+                    .0
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| format!("unknown variable `${name}`"))?,
+                None => template.clone(),
+            },
+            Value::Array(items) => Value::Array(
+                items
+                    .iter()
+                    .map(|item| self.substitute(item))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Object(properties) => Value::Object(
+                properties
+                    .iter()
+                    .map(|(name, value)| Ok((name.clone(), self.substitute(value)?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+            _ => template.clone(),
+        })
+    }
+
+    /// Save the values at the JSON pointers of `save` in `payload`.
+    pub(crate) fn save(
+        &mut self,
+        save: &[(&'static str, &'static str)],
+        payload: &Value,
+    ) -> Result<(), String> {
+        for (name, pointer) in save {
+            let value = payload
+                .pointer(pointer)
+                .ok_or_else(|| format!("`{pointer}` is missing, cannot save `${name}`"))?;
+
+            self.0.insert((*name).to_owned(), value.clone());
+        }
+
+        Ok(())
+    }
+}
+
+/// Check that `actual` contains `expected`, see [`Expect::Result`].
+pub(crate) fn matches(expected: &Value, actual: &Value) -> Result<(), String> {
+    fn check(path: &mut String, expected: &Value, actual: &Value) -> Result<(), String> {
+        match (expected, actual) {
+            (Value::String(any), _) if any == "*" => Ok(()),
+            (Value::Object(expected), Value::Object(actual)) => {
+                for (name, expected) in expected {
+                    let length = path.len();
+                    path.push('/');
+                    path.push_str(name);
+
+                    let actual = actual
+                        .get(name)
+                        .ok_or_else(|| format!("`{path}` is missing"))?;
+                    check(path, expected, actual)?;
+
+                    path.truncate(length);
+                }
+
+                Ok(())
+            }
+            (Value::Array(expected), Value::Array(actual)) if expected.len() == actual.len() => {
+                for (index, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+                    let length = path.len();
+                    path.push_str(&format!("/{index}"));
+
+                    check(path, expected, actual)?;
+
+                    path.truncate(length);
+                }
+
+                Ok(())
+            }
+            _ if expected == actual => Ok(()),
+            _ => Err(format!(
+                "`{path}`: expected `{expected}`, got `{actual}`",
+                path = if path.is_empty() { "/" } else { path },
+            )),
+        }
+    }
+
+    check(&mut String::new(), expected, actual)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_variables() {
+        let mut variables = Variables::default();
+        variables.0.insert("idTag".to_owned(), json!("ABC"));
+
+        variables
+            .save(
+                &[("transactionId", "/transactionId")],
+                &json!({"transactionId": 42}),
+            )
+            .unwrap();
+        assert_eq!(
+            variables
+                .substitute(&json!({"idTag": "$idTag", "ids": ["$transactionId", 1]}))
+                .unwrap(),
+            json!({"idTag": "ABC", "ids": [42, 1]})
+        );
+        assert!(variables.substitute(&json!("$now")).unwrap().is_string());
+        assert!(variables.substitute(&json!("$unknown")).is_err());
+        assert!(variables.save(&[("x", "/missing")], &json!({})).is_err());
+    }
+
+    #[test]
+    fn test_matches() {
+        let actual = json!({
+            "idTagInfo": {"status": "Accepted", "expiryDate": "2013-02-01T20:53:32.486Z"},
+            "transactionId": 42,
+            "unknownKey": ["A"],
+        });
+
+        assert!(matches(&json!({}), &actual).is_ok());
+        assert!(matches(
+            &json!({"idTagInfo": {"status": "Accepted"}, "transactionId": "*"}),
+            &actual
+        )
+        .is_ok());
+        assert_eq!(
+            matches(&json!({"idTagInfo": {"status": "Invalid"}}), &actual),
+            Err("`/idTagInfo/status`: expected `\"Invalid\"`, got `\"Accepted\"`".to_owned())
+        );
+        assert_eq!(
+            matches(&json!({"currentTime": "*"}), &actual),
+            Err("`/currentTime` is missing".to_owned())
+        );
+        assert!(matches(&json!({"unknownKey": ["B"]}), &actual).is_err());
+    }
+}
@@ -0,0 +1,25 @@
+//! The OCPP 2.0.1 Device Model, on the Charging Station side.
+//!
+//! A Charging Station is described by a tree of components, e.g. the
+//! `OCPPCommCtrlr` or the `Connector` of an EVSE, each of them with
+//! variables, e.g. its `HeartbeatInterval` or its `AvailabilityState`. A
+//! variable has up to four attributes (`Actual`, `Target`, `MinSet` and
+//! `MaxSet`), and characteristics: a data type, a mutability, limits, a
+//! unit, etc.
+//!
+//! [`DeviceModel`] stores the variables, defined with a
+//! [`VariableDefinition`], and answers the messages of the CSMS:
+//!
+//! * `GetVariables` and `SetVariables`, checking the mutability and the
+//!   values against the data types and the limits,
+//! * `GetBaseReport`, with the `NotifyReport`s carrying the report.
+//!
+//! The changes of the variables supporting monitoring are reported to the
+//! hooks registered with [`DeviceModel::on_change`], on top of which the
+//! monitors of the CSMS can be evaluated.
+
+mod model;
+mod variable;
+
+pub use model::{DeviceModel, VariableChange, SUMMARY_VARIABLES};
+pub use variable::VariableDefinition;
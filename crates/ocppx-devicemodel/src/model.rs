@@ -0,0 +1,697 @@
+use crate::VariableDefinition;
+use chrono::{DateTime, Utc};
+use ocppx_types::v2_0_1::{
+    AttributeEnum, Component, GenericDeviceModelStatusEnum, GetBaseReportRequest,
+    GetBaseReportResponse, GetVariableData, GetVariableResult, GetVariableStatusEnum,
+    GetVariablesRequest, GetVariablesResponse, MutabilityEnum, NotifyReportRequest, ReportBaseEnum,
+    ReportData, SetVariableData, SetVariableResult, SetVariableStatusEnum, SetVariablesRequest,
+    SetVariablesResponse, StatusInfo, Variable, VariableAttribute, VariableCharacteristics,
+};
+use std::{collections::BTreeMap, fmt};
+
+/// The variables reported by a `SummaryInventory` base report: those
+/// telling whether a component is available or has a problem.
+pub const SUMMARY_VARIABLES: &[&str] = &[
+    "AvailabilityState",
+    "Available",
+    "Fallback",
+    "Overload",
+    "Problem",
+    "Tripped",
+];
+
+/// The identity of a component, compared case-insensitively.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct ComponentKey {
+    name: String,
+    instance: Option<String>,
+    evse: Option<(i32, Option<i32>)>,
+}
+
+impl From<&Component> for ComponentKey {
+    fn from(component: &Component) -> Self {
+        Self {
+            name: component.name.to_lowercase(),
+            instance: component.instance.as_deref().map(str::to_lowercase),
+            evse: component
+                .evse
+                .as_ref()
+                .map(|evse| (evse.id, evse.connector_id)),
+        }
+    }
+}
+
+/// The identity of a variable, compared case-insensitively.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct VariableKey {
+    name: String,
+    instance: Option<String>,
+}
+
+impl From<&Variable> for VariableKey {
+    fn from(variable: &Variable) -> Self {
+        Self {
+            name: variable.name.to_lowercase(),
+            instance: variable.instance.as_deref().map(str::to_lowercase),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    component: Component,
+    variable: Variable,
+    definition: VariableDefinition,
+}
+
+impl Entry {
+    fn value(&self, attribute: AttributeEnum) -> Option<&str> {
+        self.definition
+            .attributes
+            .iter()
+            .find(|(type_, _)| *type_ == attribute)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn report_data(&self) -> ReportData {
+        let definition = &self.definition;
+        let mut characteristics = VariableCharacteristics::builder()
+            .data_type(definition.data_type)
+            .supports_monitoring(definition.supports_monitoring)
+            .unit_opt(
+                definition
+                    .unit
+                    .as_deref()
+                    .and_then(ocppx_types::bounded_string::<16>),
+            )
+            .values_list_opt(
+                (!definition.values_list.is_empty()).then(|| definition.values_list.join(",")),
+            )
+            .build();
+
+        // The limits are `f64` or `Decimal`, depending on the features of
+        // `ocppx-types`.
+        characteristics.min_limit = definition
+            .min_limit
+            .and_then(|limit| limit.to_string().parse().ok());
+        characteristics.max_limit = definition
+            .max_limit
+            .and_then(|limit| limit.to_string().parse().ok());
+
+        ReportData::builder()
+            .component(self.component.clone())
+            .variable(self.variable.clone())
+            .variable_attribute(
+                definition
+                    .attributes
+                    .iter()
+                    .map(|(type_, value)| {
+                        VariableAttribute::builder()
+                            .r#type(*type_)
+                            .value_opt(
+                                (definition.mutability != MutabilityEnum::WriteOnly)
+                                    .then(|| value.clone()),
+                            )
+                            .mutability(definition.mutability)
+                            .persistent(definition.persistent)
+                            .constant(definition.constant)
+                            .build()
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .variable_characteristics(characteristics)
+            .build()
+    }
+}
+
+/// A change of the value of a monitored variable, see
+/// [`DeviceModel::on_change`].
+#[derive(Debug, Clone)]
+pub struct VariableChange<'a> {
+    pub component: &'a Component,
+    pub variable: &'a Variable,
+    pub attribute: AttributeEnum,
+    pub value: &'a str,
+}
+
+type Hook = Box<dyn FnMut(&VariableChange<'_>) + Send>;
+
+/// The Device Model of an OCPP 2.0.1 Charging Station: its components,
+/// their variables, and the attributes of the variables.
+///
+/// The components and the variables are identified by their name and
+/// instance, compared case-insensitively, and by their EVSE.
+pub struct DeviceModel {
+    entries: BTreeMap<(ComponentKey, VariableKey), Entry>,
+    hooks: Vec<Hook>,
+}
+
+impl Default for DeviceModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for DeviceModel {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_list()
+            .entries(self.entries.values())
+            .finish()
+    }
+}
+
+impl DeviceModel {
+    /// Create an empty Device Model.
+    pub fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            hooks: Vec::new(),
+        }
+    }
+
+    /// Define the `variable` of `component`, or redefine it.
+    pub fn define(
+        &mut self,
+        component: Component,
+        variable: Variable,
+        mut definition: VariableDefinition,
+    ) {
+        if !definition
+            .attributes
+            .iter()
+            .any(|(type_, _)| *type_ == AttributeEnum::Actual)
+        {
+            definition
+                .attributes
+                .insert(0, (AttributeEnum::Actual, String::new()));
+        }
+
+        self.entries.insert(
+            ((&component).into(), (&variable).into()),
+            Entry {
+                component,
+                variable,
+                definition,
+            },
+        );
+    }
+
+    /// Call `hook` whenever the value of a variable supporting monitoring
+    /// changes, by the CSMS or by the Charging Station, e.g. to send the
+    /// `NotifyEvent`s of the monitors.
+    pub fn on_change<F>(&mut self, hook: F)
+    where
+        F: FnMut(&VariableChange<'_>) + Send + 'static,
+    {
+        self.hooks.push(Box::new(hook));
+    }
+
+    /// The definition of the `variable` of `component`.
+    pub fn definition(
+        &self,
+        component: &Component,
+        variable: &Variable,
+    ) -> Option<&VariableDefinition> {
+        self.entry(component, variable)
+            .map(|entry| &entry.definition)
+    }
+
+    /// The value of the `attribute` of the `variable` of `component`.
+    pub fn get(
+        &self,
+        component: &Component,
+        variable: &Variable,
+        attribute: AttributeEnum,
+    ) -> Option<&str> {
+        self.entry(component, variable)?.value(attribute)
+    }
+
+    /// Set the value of an attribute on behalf of the Charging Station
+    /// itself, e.g. a measure: the variable may be read-only, and the value
+    /// is not checked. Return `false` if the attribute does not exist or
+    /// is constant.
+    pub fn set(
+        &mut self,
+        component: &Component,
+        variable: &Variable,
+        attribute: AttributeEnum,
+        value: impl Into<String>,
+    ) -> bool {
+        let key = (component.into(), variable.into());
+
+        match self.entries.get(&key) {
+            Some(entry) if entry.value(attribute).is_some() && !entry.definition.constant => {
+                self.write(&key, attribute, value.into());
+
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Handle a `GetVariables`.
+    pub fn get_variables(&self, request: &GetVariablesRequest) -> GetVariablesResponse {
+        GetVariablesResponse::builder()
+            .get_variable_result(
+                request
+                    .get_variable_data
+                    .iter()
+                    .map(|data| self.get_variable(data))
+                    .collect::<Vec<_>>(),
+            )
+            .build()
+    }
+
+    fn get_variable(&self, data: &GetVariableData) -> GetVariableResult {
+        let attribute = data.attribute_type.unwrap_or(AttributeEnum::Actual);
+        let result = |status, value: Option<&str>| {
+            GetVariableResult::builder()
+                .attribute_status(status)
+                .attribute_type_opt(data.attribute_type)
+                .attribute_value_opt(value.map(ToOwned::to_owned))
+                .component(data.component.clone())
+                .variable(data.variable.clone())
+                .build()
+        };
+
+        let entry = match self.lookup(&data.component, &data.variable) {
+            Ok(entry) => entry,
+            Err(Unknown::Component) => {
+                return result(GetVariableStatusEnum::UnknownComponent, None)
+            }
+            Err(Unknown::Variable) => return result(GetVariableStatusEnum::UnknownVariable, None),
+        };
+
+        match entry.value(attribute) {
+            None => result(GetVariableStatusEnum::NotSupportedAttributeType, None),
+            Some(_) if entry.definition.mutability == MutabilityEnum::WriteOnly => {
+                result(GetVariableStatusEnum::Rejected, None)
+            }
+            Some(value) => result(GetVariableStatusEnum::Accepted, Some(value)),
+        }
+    }
+
+    /// Handle a `SetVariables`. Each value is set independently of the
+    /// others.
+    ///
+    /// A value is rejected if the variable is read-only or constant, or if
+    /// it is not valid for its data type and its limits, see
+    /// [`VariableDefinition::accepts`].
+    pub fn set_variables(&mut self, request: &SetVariablesRequest) -> SetVariablesResponse {
+        SetVariablesResponse::builder()
+            .set_variable_result(
+                request
+                    .set_variable_data
+                    .iter()
+                    .map(|data| self.set_variable(data))
+                    .collect::<Vec<_>>(),
+            )
+            .build()
+    }
+
+    fn set_variable(&mut self, data: &SetVariableData) -> SetVariableResult {
+        let attribute = data.attribute_type.unwrap_or(AttributeEnum::Actual);
+        let result = |status, reason: Option<&str>| {
+            SetVariableResult::builder()
+                .attribute_status(status)
+                .attribute_status_info_opt(
+                    reason
+                        .and_then(ocppx_types::bounded_string::<20>)
+                        .map(|reason| StatusInfo::builder().reason_code(reason).build()),
+                )
+                .attribute_type_opt(data.attribute_type)
+                .component(data.component.clone())
+                .variable(data.variable.clone())
+                .build()
+        };
+
+        let entry = match self.lookup(&data.component, &data.variable) {
+            Ok(entry) => entry,
+            Err(Unknown::Component) => {
+                return result(SetVariableStatusEnum::UnknownComponent, None)
+            }
+            Err(Unknown::Variable) => return result(SetVariableStatusEnum::UnknownVariable, None),
+        };
+        let definition = &entry.definition;
+
+        if entry.value(attribute).is_none() {
+            return result(SetVariableStatusEnum::NotSupportedAttributeType, None);
+        }
+
+        if definition.mutability == MutabilityEnum::ReadOnly || definition.constant {
+            return result(SetVariableStatusEnum::Rejected, Some("ReadOnly"));
+        }
+
+        if !definition.accepts(&data.attribute_value) {
+            return result(SetVariableStatusEnum::Rejected, Some("InvalidValue"));
+        }
+
+        let status = if definition.reboot_required {
+            SetVariableStatusEnum::RebootRequired
+        } else {
+            SetVariableStatusEnum::Accepted
+        };
+
+        self.write(
+            &((&data.component).into(), (&data.variable).into()),
+            attribute,
+            data.attribute_value.clone(),
+        );
+
+        result(status, None)
+    }
+
+    /// Handle a `GetBaseReport`: all the report bases are supported. The
+    /// report itself is sent with [`DeviceModel::notify_reports`].
+    pub fn get_base_report(&self, _request: &GetBaseReportRequest) -> GetBaseReportResponse {
+        GetBaseReportResponse::builder()
+            .status(GenericDeviceModelStatusEnum::Accepted)
+            .build()
+    }
+
+    /// The variables of a base report:
+    ///
+    /// * `FullInventory`: all of them,
+    /// * `ConfigurationInventory`: those that the CSMS can set,
+    /// * `SummaryInventory`: the [`SUMMARY_VARIABLES`].
+    ///
+    /// The values of the write-only variables are not reported.
+    pub fn report(&self, report_base: ReportBaseEnum) -> Vec<ReportData> {
+        self.entries
+            .values()
+            .filter(|entry| match report_base {
+                ReportBaseEnum::FullInventory => true,
+                ReportBaseEnum::ConfigurationInventory => {
+                    entry.definition.mutability != MutabilityEnum::ReadOnly
+                        && !entry.definition.constant
+                }
+                ReportBaseEnum::SummaryInventory => SUMMARY_VARIABLES
+                    .iter()
+                    .any(|name| name.eq_ignore_ascii_case(&entry.variable.name)),
+            })
+            .map(Entry::report_data)
+            .collect()
+    }
+
+    /// The `NotifyReport`s carrying the base report of a `GetBaseReport`,
+    /// with at most `items_per_message` variables each.
+    pub fn notify_reports(
+        &self,
+        request: &GetBaseReportRequest,
+        generated_at: DateTime<Utc>,
+        items_per_message: usize,
+    ) -> Vec<NotifyReportRequest> {
+        let report = self.report(request.report_base);
+        let chunks = report
+            .chunks(items_per_message.max(1))
+            .map(|chunk| Some(chunk.to_vec()))
+            .collect::<Vec<_>>();
+        // An empty report is still sent, without data.
+        let chunks = if chunks.is_empty() {
+            vec![None]
+        } else {
+            chunks
+        };
+        let last = chunks.len() - 1;
+
+        chunks
+            .into_iter()
+            .enumerate()
+            .map(|(seq_no, report_data)| {
+                NotifyReportRequest::builder()
+                    .request_id(request.request_id)
+                    .generated_at(generated_at)
+                    .seq_no(seq_no as i32)
+                    .tbc_opt((seq_no < last).then_some(true))
+                    .report_data_opt(report_data)
+                    .build()
+            })
+            .collect()
+    }
+
+    fn entry(&self, component: &Component, variable: &Variable) -> Option<&Entry> {
+        self.entries.get(&(component.into(), variable.into()))
+    }
+
+    fn lookup(&self, component: &Component, variable: &Variable) -> Result<&Entry, Unknown> {
+        let component: ComponentKey = component.into();
+
+        self.entries
+            .get(&(component.clone(), variable.into()))
+            .ok_or_else(|| {
+                if self.entries.keys().any(|(key, _)| *key == component) {
+                    Unknown::Variable
+                } else {
+                    Unknown::Component
+                }
+            })
+    }
+
+    fn write(
+        &mut self,
+        key: &(ComponentKey, VariableKey),
+        attribute: AttributeEnum,
+        value: String,
+    ) {
+        let Some(entry) = self.entries.get_mut(key) else {
+            return;
+        };
+        let Some((_, current)) = entry
+            .definition
+            .attributes
+            .iter_mut()
+            .find(|(type_, _)| *type_ == attribute)
+        else {
+            return;
+        };
+
+        if *current == value {
+            return;
+        }
+
+        *current = value;
+
+        if entry.definition.supports_monitoring {
+            let change = VariableChange {
+                component: &entry.component,
+                variable: &entry.variable,
+                attribute,
+                value: current,
+            };
+
+            for hook in &mut self.hooks {
+                hook(&change);
+            }
+        }
+    }
+}
+
+enum Unknown {
+    Component,
+    Variable,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ocppx_types::{
+        bounded_string,
+        v2_0_1::{DataEnum, EVSE},
+    };
+    use std::sync::{Arc, Mutex};
+
+    fn component(name: &str) -> Component {
+        Component::builder()
+            .name(bounded_string::<50>(name).unwrap())
+            .build()
+    }
+
+    fn variable(name: &str) -> Variable {
+        Variable::builder()
+            .name(bounded_string::<50>(name).unwrap())
+            .build()
+    }
+
+    fn device_model() -> DeviceModel {
+        let mut device_model = DeviceModel::new();
+        device_model.define(
+            component("OCPPCommCtrlr"),
+            variable("HeartbeatInterval"),
+            VariableDefinition::new(DataEnum::Integer, MutabilityEnum::ReadWrite, "300")
+                .unit("s")
+                .limits(Some(1.), None),
+        );
+        device_model.define(
+            component("SecurityCtrlr"),
+            variable("BasicAuthPassword"),
+            VariableDefinition::new(DataEnum::String, MutabilityEnum::WriteOnly, "secret"),
+        );
+        device_model.define(
+            Component::builder()
+                .name(bounded_string::<50>("Connector").unwrap())
+                .evse(EVSE::builder().id(1).connector_id(1).build())
+                .build(),
+            variable("AvailabilityState"),
+            VariableDefinition::new(DataEnum::OptionList, MutabilityEnum::ReadOnly, "Available")
+                .values_list(["Available", "Occupied", "Faulted"])
+                .supports_monitoring(),
+        );
+
+        device_model
+    }
+
+    #[test]
+    fn test_get_set_variables() {
+        let mut device_model = device_model();
+
+        let set = |device_model: &mut DeviceModel, component: &str, variable: &str, value: &str| {
+            let response = device_model.set_variables(
+                &SetVariablesRequest::builder()
+                    .set_variable_data(vec![SetVariableData::builder()
+                        .component(self::component(component))
+                        .variable(self::variable(variable))
+                        .attribute_value(value)
+                        .build()])
+                    .build(),
+            );
+
+            response.set_variable_result[0].attribute_status
+        };
+
+        assert_eq!(
+            set(
+                &mut device_model,
+                "ocppcommctrlr",
+                "heartbeatinterval",
+                "60"
+            ),
+            SetVariableStatusEnum::Accepted
+        );
+        assert_eq!(
+            set(&mut device_model, "OCPPCommCtrlr", "HeartbeatInterval", "0"),
+            SetVariableStatusEnum::Rejected
+        );
+        assert_eq!(
+            set(&mut device_model, "OCPPCommCtrlr", "Unknown", "0"),
+            SetVariableStatusEnum::UnknownVariable
+        );
+        assert_eq!(
+            set(&mut device_model, "Unknown", "HeartbeatInterval", "0"),
+            SetVariableStatusEnum::UnknownComponent
+        );
+
+        let response = device_model.get_variables(
+            &GetVariablesRequest::builder()
+                .get_variable_data(vec![
+                    GetVariableData::builder()
+                        .component(component("OCPPCommCtrlr"))
+                        .variable(variable("HeartbeatInterval"))
+                        .build(),
+                    GetVariableData::builder()
+                        .component(component("OCPPCommCtrlr"))
+                        .variable(variable("HeartbeatInterval"))
+                        .attribute_type(AttributeEnum::Target)
+                        .build(),
+                    GetVariableData::builder()
+                        .component(component("SecurityCtrlr"))
+                        .variable(variable("BasicAuthPassword"))
+                        .build(),
+                ])
+                .build(),
+        );
+        let results = response
+            .get_variable_result
+            .iter()
+            .map(|result| (result.attribute_status, result.attribute_value.as_deref()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            results,
+            [
+                (GetVariableStatusEnum::Accepted, Some("60")),
+                (GetVariableStatusEnum::NotSupportedAttributeType, None),
+                (GetVariableStatusEnum::Rejected, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_monitoring_and_reports() {
+        let mut device_model = device_model();
+        let changes = Arc::new(Mutex::new(Vec::new()));
+
+        device_model.on_change({
+            let changes = changes.clone();
+
+            move |change| {
+                changes
+                    .lock()
+                    .unwrap()
+                    .push((change.variable.name.to_string(), change.value.to_owned()))
+            }
+        });
+
+        let connector = Component::builder()
+            .name(bounded_string::<50>("Connector").unwrap())
+            .evse(EVSE::builder().id(1).connector_id(1).build())
+            .build();
+
+        // Only the monitored variables are reported to the hooks.
+        assert!(device_model.set(
+            &connector,
+            &variable("AvailabilityState"),
+            AttributeEnum::Actual,
+            "Occupied"
+        ));
+        assert!(device_model.set(
+            &component("OCPPCommCtrlr"),
+            &variable("HeartbeatInterval"),
+            AttributeEnum::Actual,
+            "30"
+        ));
+        assert_eq!(
+            *changes.lock().unwrap(),
+            [("AvailabilityState".to_owned(), "Occupied".to_owned())]
+        );
+
+        let variables = |report_base| {
+            device_model
+                .report(report_base)
+                .into_iter()
+                .map(|report_data| report_data.variable.name.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            variables(ReportBaseEnum::ConfigurationInventory),
+            ["HeartbeatInterval", "BasicAuthPassword"]
+        );
+        assert_eq!(
+            variables(ReportBaseEnum::SummaryInventory),
+            ["AvailabilityState"]
+        );
+
+        let reports = device_model.notify_reports(
+            &GetBaseReportRequest::builder()
+                .request_id(7)
+                .report_base(ReportBaseEnum::FullInventory)
+                .build(),
+            Utc::now(),
+            2,
+        );
+
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].tbc, Some(true));
+        assert_eq!(reports[1].seq_no, 1);
+        assert_eq!(reports[1].tbc, None);
+
+        // The write-only values are not reported.
+        let password = reports
+            .iter()
+            .flat_map(|report| report.report_data.iter().flatten())
+            .find(|report_data| report_data.variable.name.as_str() == "BasicAuthPassword")
+            .unwrap();
+        assert_eq!(password.variable_attribute[0].value, None);
+    }
+}
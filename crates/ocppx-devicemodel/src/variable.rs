@@ -0,0 +1,200 @@
+use chrono::{DateTime, FixedOffset};
+use ocppx_types::v2_0_1::{AttributeEnum, DataEnum, MutabilityEnum};
+
+/// The maximum length of a value set by the CSMS.
+pub(crate) const MAX_VALUE_LENGTH: usize = 1000;
+
+/// The definition of a variable of a component: its characteristics, and
+/// its attributes with their initial value.
+///
+/// ```
+/// # use ocppx_devicemodel::VariableDefinition;
+/// # use ocppx_types::v2_0_1::{DataEnum, MutabilityEnum};
+/// let interval = VariableDefinition::new(DataEnum::Integer, MutabilityEnum::ReadWrite, "300")
+///     .unit("s")
+///     .limits(Some(1.), Some(86400.));
+///
+/// assert!(interval.accepts("60"));
+/// assert!(!interval.accepts("0"));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariableDefinition {
+    pub data_type: DataEnum,
+    /// The mutability of all the attributes.
+    pub mutability: MutabilityEnum,
+    pub unit: Option<String>,
+    /// For the numbers, the lowest value.
+    pub min_limit: Option<f64>,
+    /// For the numbers, the highest value; for the strings and the lists,
+    /// the maximum length.
+    pub max_limit: Option<f64>,
+    /// For the `OptionList`, `MemberList` and `SequenceList`, the allowed
+    /// values.
+    pub values_list: Vec<String>,
+    /// Whether the changes of the value are reported to the monitoring
+    /// hooks, see [`DeviceModel::on_change`][crate::DeviceModel::on_change].
+    pub supports_monitoring: bool,
+    /// Whether the value survives a reboot.
+    pub persistent: bool,
+    /// Whether the value never changes, even for the Charging Station.
+    pub constant: bool,
+    /// Whether a changed value only takes effect after a reboot.
+    pub reboot_required: bool,
+    /// The supported attributes, with their initial value. `Actual` is
+    /// always supported.
+    pub attributes: Vec<(AttributeEnum, String)>,
+}
+
+impl VariableDefinition {
+    /// A variable with an `Actual` attribute set to `actual`.
+    pub fn new(data_type: DataEnum, mutability: MutabilityEnum, actual: impl Into<String>) -> Self {
+        Self {
+            data_type,
+            mutability,
+            unit: None,
+            min_limit: None,
+            max_limit: None,
+            values_list: Vec::new(),
+            supports_monitoring: false,
+            persistent: false,
+            constant: false,
+            reboot_required: false,
+            attributes: vec![(AttributeEnum::Actual, actual.into())],
+        }
+    }
+
+    pub fn unit(mut self, unit: impl Into<String>) -> Self {
+        self.unit = Some(unit.into());
+
+        self
+    }
+
+    pub fn limits(mut self, min_limit: Option<f64>, max_limit: Option<f64>) -> Self {
+        self.min_limit = min_limit;
+        self.max_limit = max_limit;
+
+        self
+    }
+
+    pub fn values_list<I, V>(mut self, values: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<String>,
+    {
+        self.values_list = values.into_iter().map(Into::into).collect();
+
+        self
+    }
+
+    pub fn supports_monitoring(mut self) -> Self {
+        self.supports_monitoring = true;
+
+        self
+    }
+
+    pub fn persistent(mut self) -> Self {
+        self.persistent = true;
+
+        self
+    }
+
+    pub fn constant(mut self) -> Self {
+        self.constant = true;
+
+        self
+    }
+
+    pub fn reboot_required(mut self) -> Self {
+        self.reboot_required = true;
+
+        self
+    }
+
+    /// Support the `attribute` attribute, with an initial `value`.
+    pub fn attribute(mut self, attribute: AttributeEnum, value: impl Into<String>) -> Self {
+        match self
+            .attributes
+            .iter_mut()
+            .find(|(type_, _)| *type_ == attribute)
+        {
+            Some((_, current)) => *current = value.into(),
+            None => self.attributes.push((attribute, value.into())),
+        }
+
+        self
+    }
+
+    /// Whether `value` is valid for the data type and the limits of the
+    /// variable.
+    pub fn accepts(&self, value: &str) -> bool {
+        let in_limits = |number: f64| {
+            number.is_finite()
+                && self.min_limit.is_none_or(|min_limit| number >= min_limit)
+                && self.max_limit.is_none_or(|max_limit| number <= max_limit)
+        };
+        let max_length = |length: usize| {
+            length <= MAX_VALUE_LENGTH
+                && self
+                    .max_limit
+                    .is_none_or(|max_limit| length as f64 <= max_limit)
+        };
+        let listed = |item: &str| self.values_list.iter().any(|allowed| allowed == item);
+
+        match self.data_type {
+            DataEnum::String => max_length(value.chars().count()),
+            DataEnum::Decimal => value.parse().is_ok_and(in_limits),
+            DataEnum::Integer => value
+                .parse::<i64>()
+                .is_ok_and(|integer| in_limits(integer as f64)),
+            DataEnum::DateTime => DateTime::<FixedOffset>::parse_from_rfc3339(value).is_ok(),
+            DataEnum::Boolean => matches!(value, "true" | "false"),
+            DataEnum::OptionList => listed(value),
+            DataEnum::SequenceList | DataEnum::MemberList => {
+                let items = value.split(',').collect::<Vec<_>>();
+                let unique = self.data_type == DataEnum::SequenceList
+                    || items
+                        .iter()
+                        .enumerate()
+                        .all(|(index, item)| !items[..index].contains(item));
+
+                max_length(value.len()) && unique && items.into_iter().all(listed)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accepts() {
+        let definition =
+            |data_type| VariableDefinition::new(data_type, MutabilityEnum::ReadWrite, "");
+
+        let decimal = definition(DataEnum::Decimal).limits(Some(0.), Some(32.));
+        assert!(decimal.accepts("16.5"));
+        assert!(!decimal.accepts("32.1"));
+        assert!(!decimal.accepts("NaN"));
+
+        assert!(definition(DataEnum::Integer).accepts("-3"));
+        assert!(!definition(DataEnum::Integer).accepts("3.5"));
+        assert!(definition(DataEnum::Boolean).accepts("true"));
+        assert!(!definition(DataEnum::Boolean).accepts("yes"));
+        assert!(definition(DataEnum::DateTime).accepts("2013-02-01T20:53:32.486Z"));
+        assert!(!definition(DataEnum::String)
+            .limits(None, Some(3.))
+            .accepts("abcd"));
+
+        let members = definition(DataEnum::MemberList).values_list(["Cable", "RFID", "Local"]);
+        assert!(members.accepts("RFID,Cable"));
+        assert!(!members.accepts("RFID,RFID"));
+        assert!(!members.accepts("RFID,PIN"));
+        assert!(definition(DataEnum::SequenceList)
+            .values_list(["A", "B"])
+            .accepts("B,A,B"));
+        assert!(!definition(DataEnum::OptionList)
+            .values_list(["A", "B"])
+            .accepts("C"));
+    }
+}
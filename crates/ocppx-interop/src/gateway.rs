@@ -0,0 +1,779 @@
+use crate::Result;
+use ocppx_client::{ChargePointClient, ClientConfig, OutgoingQueueConfig, Overflow, QueueLimit};
+use ocppx_rpc::{Call, CallError, CallResult, ErrorCode, Message};
+use ocppx_server::{AuthProvider, Credentials, CsmsHandler, Server, ServerConfig};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+use tokio::{
+    net::{TcpListener, ToSocketAddrs},
+    sync::{mpsc, Notify},
+};
+
+/// What a [`Gateway`] does with the messages whose payload does not match
+/// the JSON schema of their action.
+#[cfg(feature = "json-schema")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GatewayValidation {
+    /// Log them, and forward them anyway.
+    Log,
+    /// Answer the invalid `Call`s with a `FormationViolation` `CallError`
+    /// instead of forwarding them, and replace the invalid responses with
+    /// one.
+    Reject,
+}
+
+/// The connection of a Charge Point to the upstream CSMS.
+struct Upstream {
+    client: ChargePointClient,
+    #[cfg(feature = "json-schema")]
+    validation: Option<GatewayValidation>,
+    /// Notified when the Charge Point disconnects.
+    closed: Notify,
+}
+
+impl Upstream {
+    /// Check the payload of a message of `action`, a response if
+    /// `response`. Return the description of the `FormationViolation` to
+    /// send instead of the message, if it is rejected.
+    #[cfg(feature = "json-schema")]
+    fn check(
+        &self,
+        charge_point_id: &str,
+        action: &str,
+        payload: &Value,
+        response: bool,
+    ) -> std::result::Result<(), String> {
+        use ocppx_types::v1_6;
+
+        let (Some(validation), Ok(action)) = (self.validation, action.parse::<v1_6::Action>())
+        else {
+            return Ok(());
+        };
+
+        let checked = if response {
+            v1_6::validate_response(action, payload)
+        } else {
+            v1_6::validate(action, payload)
+        };
+
+        let Err(error) = checked else {
+            return Ok(());
+        };
+
+        log::warn!(
+            charge_point_id = charge_point_id,
+            action = action.as_str(),
+            response = response,
+            error:% = error;
+            "invalid payload"
+        );
+
+        match validation {
+            GatewayValidation::Log => Ok(()),
+            GatewayValidation::Reject => Err(error.to_string()),
+        }
+    }
+
+    #[cfg(not(feature = "json-schema"))]
+    fn check(
+        &self,
+        _charge_point_id: &str,
+        _action: &str,
+        _payload: &Value,
+        _response: bool,
+    ) -> std::result::Result<(), String> {
+        Ok(())
+    }
+}
+
+type Connected = (String, Arc<Upstream>);
+
+/// A gateway between OCPP 1.6 Charge Points and a 1.6 CSMS, e.g. to watch
+/// or to fix their exchanges while integrating them.
+///
+/// Like [`InteropProxy`], each Charge Point connected to the gateway is
+/// connected to the upstream CSMS, with the same identity, but its messages
+/// are forwarded as they are, in both directions. On the way:
+///
+/// - the messages are logged, with their payload at the `debug` level, and
+///   [`ServerConfig::recorder`] captures the frames of the Charge Points;
+/// - [`ServerConfig::interceptors`] rewrite the messages of the Charge
+///   Points, and the ones sent to them;
+/// - with the `json-schema` feature, the payloads can be validated, see
+///   [`Gateway::with_validation`].
+///
+/// The messages are forwarded with new unique IDs, the ones of each
+/// connection. The `Call`s of the Charge Points are relayed as they come,
+/// see [`ChargePointClient::relay`]: they are neither merged, dropped, nor
+/// sent again by the gateway, since the Charge Points do it themselves. The
+/// `Call`s of a Charge Point that could not connect to the upstream CSMS
+/// are answered with an `InternalError` `CallError`.
+///
+/// The Charge Points are authenticated by the upstream CSMS: each one is
+/// connected to it with the credentials it has sent to the gateway, see
+/// [`GatewayAuth`]. The Charge Points sending no credentials are rejected,
+/// unless allowed with [`Gateway::allow_anonymous`].
+///
+/// [`InteropProxy`]: crate::InteropProxy
+pub struct Gateway {
+    server: Server<GatewayHandler, GatewayAuth>,
+    connected: tokio::sync::Mutex<mpsc::UnboundedReceiver<Connected>>,
+    anonymous: Arc<AtomicBool>,
+}
+
+impl Gateway {
+    /// Create a gateway to the CSMS at `upstream_url`, e.g.
+    /// `ws://csms.example.org/ocpp`. The
+    /// [`ClientConfig::basic_auth_password`] of `upstream_config` is only
+    /// used for the Charge Points sending no credentials, see
+    /// [`Gateway::allow_anonymous`].
+    pub fn new(upstream_url: &str, upstream_config: ClientConfig, config: ServerConfig) -> Self {
+        Self::build(GatewayHandler::new(upstream_url, upstream_config), config)
+    }
+
+    /// Create a gateway validating the payloads of the messages, in both
+    /// directions, see [`Gateway::new`].
+    #[cfg(feature = "json-schema")]
+    pub fn with_validation(
+        upstream_url: &str,
+        upstream_config: ClientConfig,
+        config: ServerConfig,
+        validation: GatewayValidation,
+    ) -> Self {
+        let (mut handler, connected) = GatewayHandler::new(upstream_url, upstream_config);
+        handler.validation = Some(validation);
+
+        Self::build((handler, connected), config)
+    }
+
+    fn build(
+        (handler, connected): (GatewayHandler, mpsc::UnboundedReceiver<Connected>),
+        config: ServerConfig,
+    ) -> Self {
+        let auth = GatewayAuth {
+            passwords: handler.passwords.clone(),
+            anonymous: handler.anonymous.clone(),
+        };
+
+        Self {
+            anonymous: handler.anonymous.clone(),
+            server: Server::with_auth_provider(handler, config, auth),
+            connected: tokio::sync::Mutex::new(connected),
+        }
+    }
+
+    /// Accept the Charge Points sending no credentials, and connect them to
+    /// the upstream CSMS with the [`ClientConfig::basic_auth_password`] of
+    /// the upstream configuration, if any.
+    ///
+    /// Any client reaching the gateway can then be connected upstream as
+    /// any Charge Point, with this password: only allow it on a trusted
+    /// network.
+    pub fn allow_anonymous(self) -> Self {
+        self.anonymous.store(true, Ordering::Relaxed);
+
+        self
+    }
+
+    /// The server accepting the Charge Points.
+    pub fn server(&self) -> &Server<GatewayHandler, GatewayAuth> {
+        &self.server
+    }
+
+    /// Listen on `address` and accept Charge Points forever.
+    pub async fn listen<T>(&self, address: T) -> Result<()>
+    where
+        T: ToSocketAddrs,
+    {
+        self.serve(
+            TcpListener::bind(address)
+                .await
+                .map_err(ocppx_server::Error::from)?,
+        )
+        .await
+    }
+
+    /// Accept Charge Points on `listener` forever.
+    pub async fn serve(&self, listener: TcpListener) -> Result<()> {
+        let mut connected = self.connected.lock().await;
+
+        // The `Call`s of the upstream CSMS are forwarded to the Charge
+        // Points through the server, which is only known here.
+        let forward = async {
+            while let Some((charge_point_id, upstream)) = connected.recv().await {
+                tokio::spawn(forward_csms_calls(
+                    self.server.clone(),
+                    charge_point_id,
+                    upstream,
+                ));
+            }
+        };
+
+        tokio::select! {
+            result = self.server.serve(listener) => Ok(result?),
+            _ = forward => Ok(()),
+        }
+    }
+}
+
+/// The [`AuthProvider`] of a [`Gateway`]: it accepts the Charge Points
+/// sending credentials, and keeps their password until they are connected
+/// to the upstream CSMS with it, which authenticates them. The Charge
+/// Points sending none are rejected, unless allowed with
+/// [`Gateway::allow_anonymous`].
+pub struct GatewayAuth {
+    passwords: Arc<Mutex<HashMap<String, String>>>,
+    anonymous: Arc<AtomicBool>,
+}
+
+impl AuthProvider for GatewayAuth {
+    async fn authenticate(&self, charge_point_id: &str, credentials: Option<&Credentials>) -> bool {
+        match credentials {
+            Some(credentials) => {
+                self.passwords
+                    .lock()
+                    .unwrap()
+                    .insert(charge_point_id.to_owned(), credentials.password.clone());
+
+                true
+            }
+            None if self.anonymous.load(Ordering::Relaxed) => {
+                self.passwords.lock().unwrap().remove(charge_point_id);
+
+                true
+            }
+            None => {
+                log::warn!(charge_point_id; "Charge Point without credentials rejected");
+
+                false
+            }
+        }
+    }
+}
+
+/// The handler of the Charge Points connected to a [`Gateway`].
+pub struct GatewayHandler {
+    upstream_url: String,
+    upstream_config: ClientConfig,
+    /// The password of each Charge Point authenticated, but not connected
+    /// to the upstream CSMS yet.
+    passwords: Arc<Mutex<HashMap<String, String>>>,
+    /// Whether the Charge Points sending no credentials are accepted, see
+    /// [`Gateway::allow_anonymous`].
+    anonymous: Arc<AtomicBool>,
+    #[cfg(feature = "json-schema")]
+    validation: Option<GatewayValidation>,
+    upstreams: Mutex<HashMap<String, Arc<Upstream>>>,
+    connected: mpsc::UnboundedSender<Connected>,
+}
+
+impl GatewayHandler {
+    fn new(
+        upstream_url: &str,
+        upstream_config: ClientConfig,
+    ) -> (Self, mpsc::UnboundedReceiver<Connected>) {
+        let (connected_sender, connected_receiver) = mpsc::unbounded_channel();
+
+        (
+            Self {
+                upstream_url: upstream_url.to_owned(),
+                upstream_config: ClientConfig {
+                    // The Charge Points send their own `Heartbeat`s.
+                    heartbeat: false,
+                    // The relayed `Call`s wait for room, instead of being
+                    // dropped.
+                    outgoing_queue: OutgoingQueueConfig {
+                        normal: QueueLimit {
+                            overflow: Overflow::Wait,
+                            ..upstream_config.outgoing_queue.normal
+                        },
+                        merge_status_notifications: false,
+                        ..upstream_config.outgoing_queue
+                    },
+                    ..upstream_config
+                },
+                passwords: Arc::default(),
+                anonymous: Arc::default(),
+                #[cfg(feature = "json-schema")]
+                validation: None,
+                upstreams: Mutex::new(HashMap::new()),
+                connected: connected_sender,
+            },
+            connected_receiver,
+        )
+    }
+}
+
+impl CsmsHandler for GatewayHandler {
+    async fn handle_call(
+        &self,
+        charge_point_id: &str,
+        call: Call,
+    ) -> std::result::Result<CallResult, CallError> {
+        let Some(upstream) = self.upstreams.lock().unwrap().get(charge_point_id).cloned() else {
+            return Err(CallError::new(
+                call.unique_id,
+                ErrorCode::InternalError,
+                "not connected to the upstream CSMS",
+                None,
+            ));
+        };
+
+        log::debug!(
+            charge_point_id = charge_point_id,
+            action = call.action.as_str(),
+            payload:% = call.payload;
+            "Call of the Charge Point"
+        );
+
+        if let Err(description) =
+            upstream.check(charge_point_id, &call.action, &call.payload, false)
+        {
+            return Err(CallError::new(
+                call.unique_id,
+                ErrorCode::FormationViolation,
+                description,
+                None,
+            ));
+        }
+
+        let response = upstream
+            .client
+            .relay::<Value, Value>(&call.action, &call.payload)
+            .await;
+
+        let response = match response {
+            Ok(payload) => upstream
+                .check(charge_point_id, &call.action, &payload, true)
+                .map(|()| CallResult {
+                    unique_id: call.unique_id.clone(),
+                    payload,
+                })
+                .map_err(|description| {
+                    CallError::new(
+                        call.unique_id,
+                        ErrorCode::FormationViolation,
+                        description,
+                        None,
+                    )
+                }),
+            Err(ocppx_client::Error::CallError(call_error)) => Err(CallError {
+                unique_id: call.unique_id,
+                ..call_error
+            }),
+            Err(error) => Err(CallError::new(
+                call.unique_id,
+                ErrorCode::InternalError,
+                error.to_string(),
+                None,
+            )),
+        };
+
+        log_response(
+            charge_point_id,
+            &call.action,
+            "response of the CSMS",
+            &response,
+        );
+
+        response
+    }
+
+    async fn connected(&self, charge_point_id: &str) {
+        let password = self.passwords.lock().unwrap().remove(charge_point_id);
+        let client = ChargePointClient::connect_with_config(
+            &self.upstream_url,
+            charge_point_id,
+            ClientConfig {
+                basic_auth_password: password.or_else(|| {
+                    self.anonymous
+                        .load(Ordering::Relaxed)
+                        .then(|| self.upstream_config.basic_auth_password.clone())
+                        .flatten()
+                }),
+                ..self.upstream_config.clone()
+            },
+        )
+        .await;
+
+        match client {
+            Ok(client) => {
+                let upstream = Arc::new(Upstream {
+                    client,
+                    #[cfg(feature = "json-schema")]
+                    validation: self.validation,
+                    closed: Notify::new(),
+                });
+
+                self.upstreams
+                    .lock()
+                    .unwrap()
+                    .insert(charge_point_id.to_owned(), upstream.clone());
+                let _ = self.connected.send((charge_point_id.to_owned(), upstream));
+            }
+
+            Err(error) => {
+                log::warn!(
+                    charge_point_id = charge_point_id,
+                    error:% = error;
+                    "cannot connect to the upstream CSMS"
+                );
+            }
+        }
+    }
+
+    async fn disconnected(&self, charge_point_id: &str) {
+        let upstream = self.upstreams.lock().unwrap().remove(charge_point_id);
+
+        // Once the forwarding has stopped, the last reference to the client
+        // is dropped, which closes the upstream connection.
+        if let Some(upstream) = upstream {
+            upstream.closed.notify_one();
+        }
+    }
+}
+
+/// Forward the `Call`s of the upstream CSMS to the Charge Point, until it
+/// disconnects.
+async fn forward_csms_calls(
+    server: Server<GatewayHandler, GatewayAuth>,
+    charge_point_id: String,
+    upstream: Arc<Upstream>,
+) {
+    loop {
+        let call = tokio::select! {
+            call = upstream.client.next_call() => call,
+            _ = upstream.closed.notified() => None,
+        };
+
+        let Some(call) = call else {
+            break;
+        };
+
+        log::debug!(
+            charge_point_id = charge_point_id.as_str(),
+            action = call.action.as_str(),
+            payload:% = call.payload;
+            "Call of the CSMS"
+        );
+
+        let response = match upstream.check(&charge_point_id, &call.action, &call.payload, false) {
+            Ok(()) => {
+                let response = server
+                    .call::<Value, Value>(&charge_point_id, &call.action, &call.payload)
+                    .await;
+
+                match response {
+                    Ok(payload) => upstream
+                        .check(&charge_point_id, &call.action, &payload, true)
+                        .map(|()| CallResult {
+                            unique_id: call.unique_id.clone(),
+                            payload,
+                        })
+                        .map_err(|description| {
+                            CallError::new(
+                                call.unique_id.clone(),
+                                ErrorCode::FormationViolation,
+                                description,
+                                None,
+                            )
+                        }),
+                    Err(ocppx_server::Error::CallError(call_error)) => Err(CallError {
+                        unique_id: call.unique_id.clone(),
+                        ..call_error
+                    }),
+                    Err(error) => Err(CallError::new(
+                        call.unique_id.clone(),
+                        ErrorCode::InternalError,
+                        error.to_string(),
+                        None,
+                    )),
+                }
+            }
+
+            Err(description) => Err(CallError::new(
+                call.unique_id.clone(),
+                ErrorCode::FormationViolation,
+                description,
+                None,
+            )),
+        };
+
+        log_response(
+            &charge_point_id,
+            &call.action,
+            "response of the Charge Point",
+            &response,
+        );
+
+        let response: Message = match response {
+            Ok(call_result) => call_result.into(),
+            Err(call_error) => call_error.into(),
+        };
+
+        if upstream.client.respond(response).is_err() {
+            break;
+        }
+    }
+}
+
+fn log_response(
+    charge_point_id: &str,
+    action: &str,
+    message: &str,
+    response: &std::result::Result<CallResult, CallError>,
+) {
+    match response {
+        Ok(call_result) => log::debug!(
+            charge_point_id = charge_point_id,
+            action = action,
+            payload:% = call_result.payload;
+            "{message}"
+        ),
+        Err(call_error) => log::debug!(
+            charge_point_id = charge_point_id,
+            action = action,
+            error_code = call_error.error_code.as_str(),
+            error_description = call_error.error_description.as_str();
+            "{message}"
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ocppx_types::{v1_6, CiString20};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A 1.6 CSMS, accepting the boots, and counting the
+    /// `StatusNotification`s.
+    #[derive(Default)]
+    struct Csms {
+        status_notifications: Arc<AtomicUsize>,
+    }
+
+    impl CsmsHandler for Csms {
+        async fn handle_call(
+            &self,
+            charge_point_id: &str,
+            call: Call,
+        ) -> std::result::Result<CallResult, CallError> {
+            assert_eq!(charge_point_id, "CP001");
+
+            let payload = match call.action.as_str() {
+                "BootNotification" => json!({
+                    "currentTime": "2013-02-01T20:53:32.486Z",
+                    "interval": 300,
+                    "status": "Accepted",
+                }),
+                "StatusNotification" => {
+                    self.status_notifications.fetch_add(1, Ordering::Relaxed);
+
+                    json!({})
+                }
+                action => panic!("unexpected `{action}`"),
+            };
+
+            Ok(CallResult::new(call.unique_id, &payload).unwrap())
+        }
+    }
+
+    /// Accept `CP001` with its own password only.
+    struct Auth;
+
+    impl AuthProvider for Auth {
+        async fn authenticate(
+            &self,
+            charge_point_id: &str,
+            credentials: Option<&Credentials>,
+        ) -> bool {
+            charge_point_id == "CP001"
+                && credentials.is_some_and(|credentials| credentials.password == "secret")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gateway() {
+        let upstream_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream_address = upstream_listener.local_addr().unwrap();
+        let status_notifications = Arc::new(AtomicUsize::new(0));
+        let csms = Server::with_auth_provider(
+            Csms {
+                status_notifications: status_notifications.clone(),
+            },
+            ServerConfig::default(),
+            Auth,
+        );
+        tokio::spawn({
+            let csms = csms.clone();
+
+            async move { csms.serve(upstream_listener).await }
+        });
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let gateway = Arc::new(Gateway::new(
+            &format!("ws://{upstream_address}/ocpp"),
+            ClientConfig::default(),
+            ServerConfig::default(),
+        ));
+        tokio::spawn({
+            let gateway = gateway.clone();
+
+            async move { gateway.serve(listener).await }
+        });
+
+        // The Charge Point is connected upstream with its own password.
+        let client = ChargePointClient::connect_with_config(
+            &format!("ws://{address}/ocpp"),
+            "CP001",
+            ClientConfig {
+                basic_auth_password: Some("secret".to_owned()),
+                ..ClientConfig::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let response = client
+            .send_boot_notification(
+                v1_6::BootNotificationRequest::builder()
+                    .charge_point_vendor(CiString20::try_from("VendorX").unwrap())
+                    .charge_point_model(CiString20::try_from("SingleSocketCharger").unwrap())
+                    .build(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status, v1_6::BootNotificationStatus::Accepted);
+        assert_eq!(csms.connected_charge_points(), ["CP001"]);
+
+        // A storm of `StatusNotification`s is relayed as it is: none is
+        // merged, nor dropped.
+        let client = Arc::new(client);
+        let storm = (0..100)
+            .map(|_| {
+                let client = client.clone();
+
+                tokio::spawn(async move {
+                    client
+                        .call::<_, Value>(
+                            "StatusNotification",
+                            &json!({
+                                "connectorId": 1,
+                                "errorCode": "NoError",
+                                "status": "Available",
+                            }),
+                        )
+                        .await
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for call in storm {
+            call.await.unwrap().unwrap();
+        }
+
+        assert_eq!(status_notifications.load(Ordering::Relaxed), 100);
+
+        // The `Call`s of the CSMS, and their errors, are forwarded too.
+        let reset = tokio::spawn({
+            let csms = csms.clone();
+
+            async move {
+                csms.call::<_, Value>("CP001", "Reset", &json!({"type": "Soft"}))
+                    .await
+            }
+        });
+
+        let call = client.next_call().await.unwrap();
+        assert_eq!(call.action, "Reset");
+        assert_eq!(call.payload, json!({"type": "Soft"}));
+        client
+            .respond(CallError::new(
+                call.unique_id,
+                ErrorCode::NotImplemented,
+                "no reset",
+                None,
+            ))
+            .unwrap();
+
+        let Err(ocppx_server::Error::CallError(call_error)) = reset.await.unwrap() else {
+            panic!("the `Reset` is not implemented");
+        };
+        assert_eq!(call_error.error_code, ErrorCode::NotImplemented);
+        assert_eq!(call_error.error_description, "no reset");
+    }
+
+    #[tokio::test]
+    async fn test_gateway_anonymous() {
+        let upstream_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream_address = upstream_listener.local_addr().unwrap();
+        let csms = Server::with_auth_provider(
+            Csms {
+                status_notifications: Arc::default(),
+            },
+            ServerConfig::default(),
+            Auth,
+        );
+        tokio::spawn({
+            let csms = csms.clone();
+
+            async move { csms.serve(upstream_listener).await }
+        });
+
+        let gateway = |gateway: Gateway| async move {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap();
+            tokio::spawn(async move { gateway.serve(listener).await });
+
+            ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001").await
+        };
+        let upstream_config = ClientConfig {
+            basic_auth_password: Some("secret".to_owned()),
+            ..ClientConfig::default()
+        };
+
+        // The Charge Points without credentials are rejected by default.
+        assert!(gateway(Gateway::new(
+            &format!("ws://{upstream_address}/ocpp"),
+            upstream_config.clone(),
+            ServerConfig::default(),
+        ))
+        .await
+        .is_err());
+        assert!(csms.connected_charge_points().is_empty());
+
+        // Once allowed, they are connected upstream with the password of the
+        // gateway.
+        let client = gateway(
+            Gateway::new(
+                &format!("ws://{upstream_address}/ocpp"),
+                upstream_config,
+                ServerConfig::default(),
+            )
+            .allow_anonymous(),
+        )
+        .await
+        .unwrap();
+
+        let response = client
+            .send_boot_notification(
+                v1_6::BootNotificationRequest::builder()
+                    .charge_point_vendor(CiString20::try_from("VendorX").unwrap())
+                    .charge_point_model(CiString20::try_from("SingleSocketCharger").unwrap())
+                    .build(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status, v1_6::BootNotificationStatus::Accepted);
+        assert_eq!(csms.connected_charge_points(), ["CP001"]);
+    }
+}
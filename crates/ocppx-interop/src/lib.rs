@@ -0,0 +1,44 @@
+//! Interoperability between OCPP 1.6 and OCPP 2.0.1, and between the
+//! Charge Points and the CSMSs of a same version.
+//!
+//! [`Translator`] maps the messages that have an equivalent in the other
+//! version, e.g. a 1.6 `StartTransaction` to a 2.0.1 `TransactionEvent`,
+//! and translates their responses back.
+//!
+//! [`InteropProxy`] builds on it so that a 2.0.1 CSMS accepts 1.6 Charge
+//! Points: it accepts the `ocpp1.6` connections of the Charge Points, and
+//! opens an `ocpp2.0.1` connection to the CSMS for each of them, with the
+//! same identity.
+//!
+//! [`Gateway`] forwards the messages between 1.6 Charge Points and a 1.6
+//! CSMS as they are, logging them, and possibly rewriting them or, with
+//! the `json-schema` feature, validating them on the way: a
+//! man-in-the-middle to debug an integration.
+
+mod gateway;
+mod proxy;
+mod translator;
+
+#[cfg(feature = "json-schema")]
+pub use gateway::GatewayValidation;
+pub use gateway::{Gateway, GatewayAuth, GatewayHandler};
+pub use proxy::{InteropProxy, ProxyHandler, UPSTREAM_SUBPROTOCOL};
+use thiserror::Error;
+pub use translator::{Translated, Translation, Translator};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("RPC error")]
+    Rpc(#[from] ocppx_rpc::Error),
+
+    #[error("JSON error")]
+    Json(#[from] serde_json::Error),
+
+    #[error("server error")]
+    Server(#[from] ocppx_server::Error),
+
+    #[error("`{0}` has no equivalent in the other OCPP version")]
+    Untranslatable(String),
+}
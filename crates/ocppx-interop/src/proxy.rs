@@ -0,0 +1,420 @@
+use crate::{Error, Result, Translated, Translation, Translator};
+use ocppx_client::{ChargePointClient, ClientConfig};
+use ocppx_rpc::{Call, CallError, CallResult, ErrorCode, Message};
+use ocppx_server::{CsmsHandler, Server, ServerConfig};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    net::{TcpListener, ToSocketAddrs},
+    sync::{mpsc, Notify},
+};
+
+/// The WebSocket subprotocol negotiated with the upstream CSMS.
+pub const UPSTREAM_SUBPROTOCOL: &str = "ocpp2.0.1";
+
+/// The connection of a Charge Point to the upstream CSMS.
+struct Upstream {
+    client: ChargePointClient,
+    translator: Mutex<Translator>,
+    /// Notified when the Charge Point disconnects.
+    closed: Notify,
+}
+
+type Connected = (String, Arc<Upstream>);
+
+/// A proxy letting 1.6 Charge Points connect to a 2.0.1 CSMS.
+///
+/// Each Charge Point connected to the proxy is connected to the upstream
+/// CSMS, with the same identity, and its messages are translated by a
+/// [`Translator`] in both directions. The messages that cannot be
+/// translated are answered with a `NotSupported` `CallError`.
+///
+/// The connections to the upstream CSMS follow the [`ClientConfig`] given
+/// to the proxy, e.g. they reconnect when they drop. The `Call`s of a
+/// Charge Point that could not connect to the upstream CSMS are answered
+/// with an `InternalError` `CallError`.
+pub struct InteropProxy {
+    server: Server<ProxyHandler>,
+    connected: tokio::sync::Mutex<mpsc::UnboundedReceiver<Connected>>,
+}
+
+impl InteropProxy {
+    /// Create a proxy to the 2.0.1 CSMS at `upstream_url`, e.g.
+    /// `ws://csms.example.org/ocpp`. The subprotocol of `upstream_config`
+    /// is replaced by [`UPSTREAM_SUBPROTOCOL`].
+    pub fn new(upstream_url: &str, upstream_config: ClientConfig, config: ServerConfig) -> Self {
+        let (connected_sender, connected_receiver) = mpsc::unbounded_channel();
+
+        Self {
+            server: Server::with_config(
+                ProxyHandler {
+                    upstream_url: upstream_url.to_owned(),
+                    upstream_config: ClientConfig {
+                        subprotocol: UPSTREAM_SUBPROTOCOL,
+                        // The Charge Points send their own `Heartbeat`s.
+                        heartbeat: false,
+                        ..upstream_config
+                    },
+                    upstreams: Mutex::new(HashMap::new()),
+                    connected: connected_sender,
+                },
+                config,
+            ),
+            connected: tokio::sync::Mutex::new(connected_receiver),
+        }
+    }
+
+    /// The server accepting the Charge Points.
+    pub fn server(&self) -> &Server<ProxyHandler> {
+        &self.server
+    }
+
+    /// Listen on `address` and accept Charge Points forever.
+    pub async fn listen<T>(&self, address: T) -> Result<()>
+    where
+        T: ToSocketAddrs,
+    {
+        self.serve(
+            TcpListener::bind(address)
+                .await
+                .map_err(ocppx_server::Error::from)?,
+        )
+        .await
+    }
+
+    /// Accept Charge Points on `listener` forever.
+    pub async fn serve(&self, listener: TcpListener) -> Result<()> {
+        let mut connected = self.connected.lock().await;
+
+        // The `Call`s of the upstream CSMS are relayed to the Charge Points
+        // through the server, which is only known here.
+        let relay = async {
+            while let Some((charge_point_id, upstream)) = connected.recv().await {
+                tokio::spawn(relay_csms_calls(
+                    self.server.clone(),
+                    charge_point_id,
+                    upstream,
+                ));
+            }
+        };
+
+        tokio::select! {
+            result = self.server.serve(listener) => Ok(result?),
+            _ = relay => Ok(()),
+        }
+    }
+}
+
+/// The handler of the Charge Points connected to an [`InteropProxy`].
+pub struct ProxyHandler {
+    upstream_url: String,
+    upstream_config: ClientConfig,
+    upstreams: Mutex<HashMap<String, Arc<Upstream>>>,
+    connected: mpsc::UnboundedSender<Connected>,
+}
+
+impl CsmsHandler for ProxyHandler {
+    async fn handle_call(
+        &self,
+        charge_point_id: &str,
+        call: Call,
+    ) -> std::result::Result<CallResult, CallError> {
+        let Some(upstream) = self.upstreams.lock().unwrap().get(charge_point_id).cloned() else {
+            return Err(CallError::new(
+                call.unique_id,
+                ErrorCode::InternalError,
+                "not connected to the upstream CSMS",
+                None,
+            ));
+        };
+
+        let translated = upstream.translator.lock().unwrap().charge_point_call(&call);
+
+        match translated {
+            Ok(Translated::Call(translated_call, translation)) => {
+                let response = upstream
+                    .client
+                    .call::<Value, Value>(&translated_call.action, &translated_call.payload)
+                    .await;
+
+                match response {
+                    Ok(payload) => translate_call_result(&translation, payload),
+                    Err(ocppx_client::Error::CallError(call_error)) => {
+                        Err(translation.call_error(call_error))
+                    }
+                    Err(error) => Err(CallError::new(
+                        call.unique_id,
+                        ErrorCode::InternalError,
+                        error.to_string(),
+                        None,
+                    )),
+                }
+            }
+            Ok(Translated::Response(call_result)) => Ok(call_result),
+            Err(error) => Err(untranslatable(
+                call.unique_id,
+                error,
+                ErrorCode::FormationViolation,
+            )),
+        }
+    }
+
+    async fn connected(&self, charge_point_id: &str) {
+        let client = ChargePointClient::connect_with_config(
+            &self.upstream_url,
+            charge_point_id,
+            self.upstream_config.clone(),
+        )
+        .await;
+
+        match client {
+            Ok(client) => {
+                let upstream = Arc::new(Upstream {
+                    client,
+                    translator: Mutex::new(Translator::new()),
+                    closed: Notify::new(),
+                });
+
+                self.upstreams
+                    .lock()
+                    .unwrap()
+                    .insert(charge_point_id.to_owned(), upstream.clone());
+                let _ = self.connected.send((charge_point_id.to_owned(), upstream));
+            }
+
+            Err(error) => {
+                log::warn!(
+                    charge_point_id = charge_point_id,
+                    error:% = error;
+                    "cannot connect to the upstream CSMS"
+                );
+            }
+        }
+    }
+
+    async fn disconnected(&self, charge_point_id: &str) {
+        let upstream = self.upstreams.lock().unwrap().remove(charge_point_id);
+
+        // Once the relay has stopped, the last reference to the client is
+        // dropped, which closes the upstream connection.
+        if let Some(upstream) = upstream {
+            upstream.closed.notify_one();
+        }
+    }
+}
+
+/// Relay the `Call`s of the upstream CSMS to the Charge Point, until it
+/// disconnects.
+async fn relay_csms_calls(
+    server: Server<ProxyHandler>,
+    charge_point_id: String,
+    upstream: Arc<Upstream>,
+) {
+    loop {
+        let call = tokio::select! {
+            call = upstream.client.next_call() => call,
+            _ = upstream.closed.notified() => None,
+        };
+
+        let Some(call) = call else {
+            break;
+        };
+
+        let translated = upstream.translator.lock().unwrap().csms_call(&call);
+
+        let response: Message = match translated {
+            Ok(Translated::Call(translated_call, translation)) => {
+                let response = server
+                    .call::<Value, Value>(
+                        &charge_point_id,
+                        &translated_call.action,
+                        &translated_call.payload,
+                    )
+                    .await;
+
+                match response {
+                    Ok(payload) => match translate_call_result(&translation, payload) {
+                        Ok(call_result) => call_result.into(),
+                        Err(call_error) => call_error.into(),
+                    },
+                    Err(ocppx_server::Error::CallError(call_error)) => {
+                        translation.call_error(call_error).into()
+                    }
+                    Err(error) => CallError::new(
+                        call.unique_id,
+                        ErrorCode::InternalError,
+                        error.to_string(),
+                        None,
+                    )
+                    .into(),
+                }
+            }
+            Ok(Translated::Response(call_result)) => call_result.into(),
+            Err(error) => untranslatable(call.unique_id, error, ErrorCode::FormatViolation).into(),
+        };
+
+        if upstream.client.respond(response).is_err() {
+            break;
+        }
+    }
+}
+
+fn translate_call_result(
+    translation: &Translation,
+    payload: Value,
+) -> std::result::Result<CallResult, CallError> {
+    translation
+        .call_result(&CallResult {
+            unique_id: translation.unique_id().to_owned(),
+            payload,
+        })
+        .map_err(|error| {
+            CallError::new(
+                translation.unique_id(),
+                ErrorCode::InternalError,
+                format!("cannot translate the response: {error}"),
+                None,
+            )
+        })
+}
+
+/// The error of a `Call` that cannot be translated. `payload_error_code`
+/// is the code of an invalid payload in the OCPP version of the sender.
+fn untranslatable(unique_id: String, error: Error, payload_error_code: ErrorCode) -> CallError {
+    let error_code = match error {
+        Error::Untranslatable(_) => ErrorCode::NotSupported,
+        Error::Rpc(_) | Error::Json(_) => payload_error_code,
+        Error::Server(_) => ErrorCode::InternalError,
+    };
+
+    CallError::new(unique_id, error_code, error.to_string(), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::{SinkExt, StreamExt};
+    use ocppx_types::{v1_6, CiString20};
+    use serde_json::json;
+    use tokio_tungstenite::{
+        accept_hdr_async,
+        tungstenite::{
+            handshake::server,
+            http::{header::SEC_WEBSOCKET_PROTOCOL, HeaderValue},
+            Message as Frame,
+        },
+    };
+
+    struct AcceptSubprotocol;
+
+    impl server::Callback for AcceptSubprotocol {
+        fn on_request(
+            self,
+            request: &server::Request,
+            mut response: server::Response,
+        ) -> std::result::Result<server::Response, server::ErrorResponse> {
+            assert_eq!(request.uri().path(), "/ocpp/CP001");
+
+            response.headers_mut().insert(
+                SEC_WEBSOCKET_PROTOCOL,
+                HeaderValue::from_static(UPSTREAM_SUBPROTOCOL),
+            );
+
+            Ok(response)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_proxy() {
+        // A 2.0.1 CSMS.
+        let upstream_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream_address = upstream_listener.local_addr().unwrap();
+
+        let csms = tokio::spawn(async move {
+            let (stream, _) = upstream_listener.accept().await.unwrap();
+            let mut stream = accept_hdr_async(stream, AcceptSubprotocol).await.unwrap();
+
+            let frame = stream.next().await.unwrap().unwrap();
+            let call =
+                Call::try_from(frame.to_text().unwrap().parse::<Message>().unwrap()).unwrap();
+            assert_eq!(call.action, "BootNotification");
+            assert_eq!(
+                call.payload,
+                json!({
+                    "chargingStation": { "model": "SingleSocketCharger", "vendorName": "VendorX" },
+                    "reason": "PowerUp",
+                })
+            );
+
+            let call_result = CallResult::new(
+                call.unique_id,
+                &json!({
+                    "currentTime": "2013-02-01T20:53:32.486Z",
+                    "interval": 300,
+                    "status": "Accepted",
+                }),
+            )
+            .unwrap();
+            stream
+                .send(Frame::Text(Message::from(call_result).to_string()))
+                .await
+                .unwrap();
+
+            let reset = Call::new("1", "Reset", &json!({ "type": "Immediate" })).unwrap();
+            stream
+                .send(Frame::Text(Message::from(reset).to_string()))
+                .await
+                .unwrap();
+
+            let frame = stream.next().await.unwrap().unwrap();
+            frame.to_text().unwrap().parse::<Message>().unwrap()
+        });
+
+        // The proxy.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let proxy = Arc::new(InteropProxy::new(
+            &format!("ws://{upstream_address}/ocpp"),
+            ClientConfig::default(),
+            ServerConfig::default(),
+        ));
+        tokio::spawn({
+            let proxy = proxy.clone();
+
+            async move { proxy.serve(listener).await }
+        });
+
+        // A 1.6 Charge Point.
+        let client = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001")
+            .await
+            .unwrap();
+
+        let response = client
+            .send_boot_notification(
+                v1_6::BootNotificationRequest::builder()
+                    .charge_point_vendor(CiString20::try_from("VendorX").unwrap())
+                    .charge_point_model(CiString20::try_from("SingleSocketCharger").unwrap())
+                    .build(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status, v1_6::BootNotificationStatus::Accepted);
+        assert_eq!(response.interval, 300);
+
+        let reset = client.next_call().await.unwrap();
+        assert_eq!(reset.action, "Reset");
+        assert_eq!(reset.payload, json!({ "type": "Hard" }));
+        client
+            .respond(CallResult::new(reset.unique_id, &json!({ "status": "Accepted" })).unwrap())
+            .unwrap();
+
+        let Message::CallResult(call_result) = csms.await.unwrap() else {
+            panic!("the `Reset` is accepted");
+        };
+        assert_eq!(call_result.unique_id, "1");
+        assert_eq!(call_result.payload, json!({ "status": "Accepted" }));
+    }
+}
@@ -0,0 +1,1151 @@
+use crate::{Error, Result};
+use chrono::Utc;
+use ocppx_rpc::{Call, CallError, CallResult, ErrorCode};
+use ocppx_types::{v1_6, v2_0_1, BoundedString, CiString255, IdTag};
+use serde::Serialize;
+use serde_json::Value;
+use std::{collections::HashMap, fmt::Display, str::FromStr, time::SystemTime};
+
+/// What a `Call` has been translated to.
+#[derive(Debug)]
+pub enum Translated {
+    /// The `Call` to send to the peer. Its response is translated back with
+    /// the [`Translation`].
+    Call(Call, Translation),
+    /// The `Call` has no equivalent for the peer: the translator responds
+    /// to it itself.
+    Response(CallResult),
+}
+
+/// How to translate back the response to a translated `Call`.
+#[derive(Debug)]
+pub struct Translation {
+    /// The unique ID of the `Call` before its translation.
+    unique_id: String,
+    response: Response,
+}
+
+/// The response to translate, named after the action of the `Call` before
+/// its translation.
+#[derive(Debug)]
+enum Response {
+    // The `Call`s of the Charge Point, from 1.6 to 2.0.1.
+    BootNotification,
+    Heartbeat,
+    Authorize,
+    StatusNotification,
+    StartTransaction { transaction_id: i32 },
+    StopTransaction,
+    MeterValues,
+    ChargePointDataTransfer,
+
+    // The `Call`s of the CSMS, from 2.0.1 to 1.6.
+    Reset,
+    RequestStartTransaction,
+    RequestStopTransaction,
+    ChangeAvailability,
+    UnlockConnector,
+    CsmsDataTransfer,
+}
+
+impl Translation {
+    /// The unique ID of the `Call` before its translation, i.e. the one to
+    /// respond to.
+    pub fn unique_id(&self) -> &str {
+        &self.unique_id
+    }
+
+    /// Translate the response of the peer.
+    pub fn call_result(&self, call_result: &CallResult) -> Result<CallResult> {
+        let unique_id = self.unique_id.as_str();
+
+        match self.response {
+            Response::BootNotification => {
+                let response: v2_0_1::BootNotificationResponse = call_result.payload()?;
+
+                respond(
+                    unique_id,
+                    &v1_6::BootNotificationResponse::builder()
+                        .current_time(response.current_time)
+                        .interval(response.interval)
+                        .status(
+                            convert(response.status)
+                                .unwrap_or(v1_6::BootNotificationStatus::Rejected),
+                        )
+                        .build(),
+                )
+            }
+
+            Response::Heartbeat => {
+                let response: v2_0_1::HeartbeatResponse = call_result.payload()?;
+
+                respond(
+                    unique_id,
+                    &v1_6::HeartbeatResponse::builder()
+                        .current_time(response.current_time)
+                        .build(),
+                )
+            }
+
+            Response::Authorize => {
+                let response: v2_0_1::AuthorizeResponse = call_result.payload()?;
+
+                respond(
+                    unique_id,
+                    &v1_6::AuthorizeResponse::builder()
+                        .id_tag_info(id_tag_info(Some(response.id_token_info)))
+                        .build(),
+                )
+            }
+
+            // Whether it has been sent as a `StatusNotification` or a
+            // `TransactionEvent`, the response is empty.
+            Response::StatusNotification => respond(
+                unique_id,
+                &v1_6::StatusNotificationResponse::builder().build(),
+            ),
+
+            Response::StartTransaction { transaction_id } => {
+                let response: v2_0_1::TransactionEventResponse = call_result.payload()?;
+
+                respond(
+                    unique_id,
+                    &v1_6::StartTransactionResponse::builder()
+                        .id_tag_info(id_tag_info(response.id_token_info))
+                        .transaction_id(transaction_id)
+                        .build(),
+                )
+            }
+
+            Response::StopTransaction => {
+                let response: v2_0_1::TransactionEventResponse = call_result.payload()?;
+
+                respond(
+                    unique_id,
+                    &v1_6::StopTransactionResponse::builder()
+                        .id_tag_info_opt(
+                            response
+                                .id_token_info
+                                .map(|id_token_info| id_tag_info(Some(id_token_info))),
+                        )
+                        .build(),
+                )
+            }
+
+            Response::MeterValues => {
+                respond(unique_id, &v1_6::MeterValuesResponse::builder().build())
+            }
+
+            Response::ChargePointDataTransfer => {
+                let response: v2_0_1::DataTransferResponse = call_result.payload()?;
+
+                respond(
+                    unique_id,
+                    &v1_6::DataTransferResponse::builder()
+                        .data_opt(response.data.map(|data| match data {
+                            Value::String(data) => data,
+                            data => data.to_string(),
+                        }))
+                        .status(
+                            convert(response.status).unwrap_or(v1_6::DataTransferStatus::Rejected),
+                        )
+                        .build(),
+                )
+            }
+
+            Response::Reset => {
+                let response: v1_6::ResetResponse = call_result.payload()?;
+
+                respond(
+                    unique_id,
+                    &v2_0_1::ResetResponse::builder()
+                        .status(
+                            convert(response.status).unwrap_or(v2_0_1::ResetStatusEnum::Rejected),
+                        )
+                        .build(),
+                )
+            }
+
+            Response::RequestStartTransaction => {
+                let response: v1_6::RemoteStartTransactionResponse = call_result.payload()?;
+
+                respond(
+                    unique_id,
+                    &v2_0_1::RequestStartTransactionResponse::builder()
+                        .status(
+                            convert(response.status)
+                                .unwrap_or(v2_0_1::RequestStartStopStatusEnum::Rejected),
+                        )
+                        .build(),
+                )
+            }
+
+            Response::RequestStopTransaction => {
+                let response: v1_6::RemoteStopTransactionResponse = call_result.payload()?;
+
+                respond(
+                    unique_id,
+                    &v2_0_1::RequestStopTransactionResponse::builder()
+                        .status(
+                            convert(response.status)
+                                .unwrap_or(v2_0_1::RequestStartStopStatusEnum::Rejected),
+                        )
+                        .build(),
+                )
+            }
+
+            Response::ChangeAvailability => {
+                let response: v1_6::ChangeAvailabilityResponse = call_result.payload()?;
+
+                respond(
+                    unique_id,
+                    &v2_0_1::ChangeAvailabilityResponse::builder()
+                        .status(
+                            convert(response.status)
+                                .unwrap_or(v2_0_1::ChangeAvailabilityStatusEnum::Rejected),
+                        )
+                        .build(),
+                )
+            }
+
+            Response::UnlockConnector => {
+                let response: v1_6::UnlockConnectorResponse = call_result.payload()?;
+
+                respond(
+                    unique_id,
+                    &v2_0_1::UnlockConnectorResponse::builder()
+                        .status(
+                            convert(response.status)
+                                .unwrap_or(v2_0_1::UnlockStatusEnum::UnlockFailed),
+                        )
+                        .build(),
+                )
+            }
+
+            Response::CsmsDataTransfer => {
+                let response: v1_6::DataTransferResponse = call_result.payload()?;
+
+                respond(
+                    unique_id,
+                    &v2_0_1::DataTransferResponse::builder()
+                        .data_opt(response.data.map(Value::String))
+                        .status(
+                            convert(response.status)
+                                .unwrap_or(v2_0_1::DataTransferStatusEnum::Rejected),
+                        )
+                        .build(),
+                )
+            }
+        }
+    }
+
+    /// Translate the error of the peer, i.e. its unique ID and the error
+    /// codes that differ between the versions.
+    pub fn call_error(&self, call_error: CallError) -> CallError {
+        let to_v1_6 = matches!(
+            self.response,
+            Response::BootNotification
+                | Response::Heartbeat
+                | Response::Authorize
+                | Response::StatusNotification
+                | Response::StartTransaction { .. }
+                | Response::StopTransaction
+                | Response::MeterValues
+                | Response::ChargePointDataTransfer
+        );
+
+        let error_code = match (call_error.error_code, to_v1_6) {
+            (ErrorCode::FormatViolation, true) => ErrorCode::FormationViolation,
+            (ErrorCode::OccurrenceConstraintViolation, true) => {
+                ErrorCode::OccurenceConstraintViolation
+            }
+            (ErrorCode::MessageTypeNotSupported | ErrorCode::RpcFrameworkError, true) => {
+                ErrorCode::ProtocolError
+            }
+            (ErrorCode::FormationViolation, false) => ErrorCode::FormatViolation,
+            (ErrorCode::OccurenceConstraintViolation, false) => {
+                ErrorCode::OccurrenceConstraintViolation
+            }
+            (error_code, _) => error_code,
+        };
+
+        CallError {
+            unique_id: self.unique_id.clone(),
+            error_code,
+            ..call_error
+        }
+    }
+}
+
+/// Translates the messages of a 1.6 Charge Point to 2.0.1, and the
+/// messages of a 2.0.1 CSMS to 1.6.
+///
+/// The messages that have an equivalent in the other version are
+/// translated:
+///
+/// | Charge Point (1.6)   | CSMS (2.0.1)                                          |
+/// |----------------------|-------------------------------------------------------|
+/// | `BootNotification`   | `BootNotification`                                    |
+/// | `Heartbeat`          | `Heartbeat`                                           |
+/// | `Authorize`          | `Authorize`                                           |
+/// | `StatusNotification` | `StatusNotification`, or `TransactionEvent` (`Updated`) for the charging states |
+/// | `StartTransaction`   | `TransactionEvent` (`Started`)                        |
+/// | `StopTransaction`    | `TransactionEvent` (`Ended`)                          |
+/// | `MeterValues`        | `MeterValues`, or `TransactionEvent` (`Updated`) within a transaction |
+/// | `DataTransfer`       | `DataTransfer`                                        |
+///
+/// | CSMS (2.0.1)               | Charge Point (1.6)        |
+/// |----------------------------|---------------------------|
+/// | `Reset`                    | `Reset`                   |
+/// | `RequestStartTransaction`  | `RemoteStartTransaction`  |
+/// | `RequestStopTransaction`   | `RemoteStopTransaction`   |
+/// | `ChangeAvailability`       | `ChangeAvailability`      |
+/// | `UnlockConnector`          | `UnlockConnector`         |
+/// | `DataTransfer`             | `DataTransfer`            |
+///
+/// A 1.6 connector is a 2.0.1 EVSE with a single connector. The integer
+/// transaction IDs of 1.6 are given by the translator, and sent as strings
+/// to the CSMS.
+///
+/// The translator keeps the state of the ongoing transactions: there must
+/// be one translator per Charge Point.
+#[derive(Debug)]
+pub struct Translator {
+    transactions: HashMap<i32, OngoingTransaction>,
+    next_transaction_id: i32,
+    /// The `remoteStartId`s of the `RequestStartTransaction`s, by ID tag,
+    /// to be reported by the transaction they start.
+    remote_starts: HashMap<String, i32>,
+}
+
+#[derive(Debug)]
+struct OngoingTransaction {
+    evse_id: i32,
+    seq_no: i32,
+}
+
+impl OngoingTransaction {
+    fn next_seq_no(&mut self) -> i32 {
+        self.seq_no += 1;
+
+        self.seq_no
+    }
+
+    fn evse(&self) -> v2_0_1::EVSE {
+        evse(self.evse_id)
+    }
+}
+
+impl Default for Translator {
+    fn default() -> Self {
+        // The transaction IDs start from the current time, so that they
+        // differ from the ones given by a previous process.
+        let first_transaction_id = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(1, |duration| (duration.as_secs() % i32::MAX as u64) as i32);
+
+        Self::with_first_transaction_id(first_transaction_id)
+    }
+}
+
+impl Translator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Give the transaction IDs from `first_transaction_id`.
+    pub fn with_first_transaction_id(first_transaction_id: i32) -> Self {
+        Self {
+            transactions: HashMap::new(),
+            next_transaction_id: first_transaction_id,
+            remote_starts: HashMap::new(),
+        }
+    }
+
+    /// Translate a `Call` sent by the Charge Point, from 1.6 to 2.0.1.
+    pub fn charge_point_call(&mut self, call: &Call) -> Result<Translated> {
+        let unique_id = call.unique_id.clone();
+
+        match call.action.as_str() {
+            "BootNotification" => {
+                let request: v1_6::BootNotificationRequest = call.payload()?;
+                let modem = (request.iccid.is_some() || request.imsi.is_some())
+                    .then(|| -> Result<_> {
+                        Ok(v2_0_1::Modem::builder()
+                            .iccid_opt(translate_option(request.iccid.as_deref())?)
+                            .imsi_opt(translate_option(request.imsi.as_deref())?)
+                            .build())
+                    })
+                    .transpose()?;
+
+                translated(
+                    unique_id,
+                    "BootNotification",
+                    Response::BootNotification,
+                    &v2_0_1::BootNotificationRequest::builder()
+                        .charging_station(
+                            v2_0_1::ChargingStation::builder()
+                                .firmware_version_opt(translate_option(
+                                    request.firmware_version.as_deref(),
+                                )?)
+                                .model(translate_string::<BoundedString<20>>(
+                                    &request.charge_point_model,
+                                )?)
+                                .modem_opt(modem)
+                                .serial_number_opt(translate_option(
+                                    request.charge_point_serial_number.as_deref(),
+                                )?)
+                                .vendor_name(translate_string::<BoundedString<50>>(
+                                    &request.charge_point_vendor,
+                                )?)
+                                .build(),
+                        )
+                        .reason(v2_0_1::BootReasonEnum::PowerUp)
+                        .build(),
+                )
+            }
+
+            "Heartbeat" => translated(
+                unique_id,
+                "Heartbeat",
+                Response::Heartbeat,
+                &v2_0_1::HeartbeatRequest::builder().build(),
+            ),
+
+            "Authorize" => {
+                let request: v1_6::AuthorizeRequest = call.payload()?;
+
+                translated(
+                    unique_id,
+                    "Authorize",
+                    Response::Authorize,
+                    &v2_0_1::AuthorizeRequest::builder()
+                        .id_token(id_token(&request.id_tag))
+                        .build(),
+                )
+            }
+
+            "StatusNotification" => {
+                let request: v1_6::StatusNotificationRequest = call.payload()?;
+                self.status_notification(unique_id, request)
+            }
+
+            "StartTransaction" => {
+                let request: v1_6::StartTransactionRequest = call.payload()?;
+                self.start_transaction(unique_id, request)
+            }
+
+            "StopTransaction" => {
+                let request: v1_6::StopTransactionRequest = call.payload()?;
+                self.stop_transaction(unique_id, request)
+            }
+
+            "MeterValues" => {
+                let request: v1_6::MeterValuesRequest = call.payload()?;
+                self.meter_values(unique_id, request)
+            }
+
+            "DataTransfer" => {
+                let request: v1_6::DataTransferRequest = call.payload()?;
+
+                translated(
+                    unique_id,
+                    "DataTransfer",
+                    Response::ChargePointDataTransfer,
+                    &v2_0_1::DataTransferRequest::builder()
+                        .data_opt(request.data.map(Value::String))
+                        .message_id_opt(translate_option(request.message_id.as_deref())?)
+                        .vendor_id(request.vendor_id)
+                        .build(),
+                )
+            }
+
+            action => Err(Error::Untranslatable(action.to_owned())),
+        }
+    }
+
+    /// Translate a `Call` sent by the CSMS, from 2.0.1 to 1.6.
+    pub fn csms_call(&mut self, call: &Call) -> Result<Translated> {
+        let unique_id = call.unique_id.clone();
+
+        match call.action.as_str() {
+            "Reset" => {
+                let request: v2_0_1::ResetRequest = call.payload()?;
+
+                // A 1.6 Charge Point resets as a whole.
+                if request.evse_id.is_some() {
+                    return respond(
+                        &unique_id,
+                        &v2_0_1::ResetResponse::builder()
+                            .status(v2_0_1::ResetStatusEnum::Rejected)
+                            .build(),
+                    )
+                    .map(Translated::Response);
+                }
+
+                translated(
+                    unique_id,
+                    "Reset",
+                    Response::Reset,
+                    &v1_6::ResetRequest::builder()
+                        .r#type(match request.r#type {
+                            v2_0_1::ResetEnum::Immediate => v1_6::ResetType::Hard,
+                            v2_0_1::ResetEnum::OnIdle => v1_6::ResetType::Soft,
+                        })
+                        .build(),
+                )
+            }
+
+            "RequestStartTransaction" => {
+                let request: v2_0_1::RequestStartTransactionRequest = call.payload()?;
+                self.remote_starts.insert(
+                    request.id_token.id_token.to_string(),
+                    request.remote_start_id,
+                );
+
+                // The charging profiles differ too much between the
+                // versions, they are not translated.
+                translated(
+                    unique_id,
+                    "RemoteStartTransaction",
+                    Response::RequestStartTransaction,
+                    &v1_6::RemoteStartTransactionRequest::builder()
+                        .connector_id_opt(request.evse_id)
+                        .id_tag(translate_string::<IdTag>(&request.id_token.id_token)?)
+                        .build(),
+                )
+            }
+
+            "RequestStopTransaction" => {
+                let request: v2_0_1::RequestStopTransactionRequest = call.payload()?;
+
+                match request.transaction_id.parse() {
+                    Ok(transaction_id) if self.transactions.contains_key(&transaction_id) => {
+                        translated(
+                            unique_id,
+                            "RemoteStopTransaction",
+                            Response::RequestStopTransaction,
+                            &v1_6::RemoteStopTransactionRequest::builder()
+                                .transaction_id(transaction_id)
+                                .build(),
+                        )
+                    }
+
+                    _ => respond(
+                        &unique_id,
+                        &v2_0_1::RequestStopTransactionResponse::builder()
+                            .status(v2_0_1::RequestStartStopStatusEnum::Rejected)
+                            .build(),
+                    )
+                    .map(Translated::Response),
+                }
+            }
+
+            "ChangeAvailability" => {
+                let request: v2_0_1::ChangeAvailabilityRequest = call.payload()?;
+
+                translated(
+                    unique_id,
+                    "ChangeAvailability",
+                    Response::ChangeAvailability,
+                    &v1_6::ChangeAvailabilityRequest::builder()
+                        .connector_id(request.evse.map_or(0, |evse| evse.id))
+                        .r#type(match request.operational_status {
+                            v2_0_1::OperationalStatusEnum::Inoperative => {
+                                v1_6::ChangeAvailabilityType::Inoperative
+                            }
+                            v2_0_1::OperationalStatusEnum::Operative => {
+                                v1_6::ChangeAvailabilityType::Operative
+                            }
+                        })
+                        .build(),
+                )
+            }
+
+            "UnlockConnector" => {
+                let request: v2_0_1::UnlockConnectorRequest = call.payload()?;
+
+                translated(
+                    unique_id,
+                    "UnlockConnector",
+                    Response::UnlockConnector,
+                    &v1_6::UnlockConnectorRequest::builder()
+                        .connector_id(request.evse_id)
+                        .build(),
+                )
+            }
+
+            "DataTransfer" => {
+                let request: v2_0_1::DataTransferRequest = call.payload()?;
+
+                translated(
+                    unique_id,
+                    "DataTransfer",
+                    Response::CsmsDataTransfer,
+                    &v1_6::DataTransferRequest::builder()
+                        .data_opt(request.data.map(|data| match data {
+                            Value::String(data) => data,
+                            data => data.to_string(),
+                        }))
+                        .message_id_opt(translate_option(request.message_id.as_deref())?)
+                        .vendor_id(translate_string::<CiString255>(&request.vendor_id)?)
+                        .build(),
+                )
+            }
+
+            action => Err(Error::Untranslatable(action.to_owned())),
+        }
+    }
+
+    fn status_notification(
+        &mut self,
+        unique_id: String,
+        request: v1_6::StatusNotificationRequest,
+    ) -> Result<Translated> {
+        use v1_6::StatusNotificationStatus as Status;
+
+        let timestamp = request.timestamp.unwrap_or_else(Utc::now);
+
+        // The charging states are reported by the `TransactionEvent`s in
+        // 2.0.1.
+        let charging_state = match request.status {
+            Status::Charging => Some(v2_0_1::ChargingStateEnum::Charging),
+            Status::SuspendedEV => Some(v2_0_1::ChargingStateEnum::SuspendedEV),
+            Status::SuspendedEVSE => Some(v2_0_1::ChargingStateEnum::SuspendedEVSE),
+            _ => None,
+        };
+        let transaction = self
+            .transactions
+            .iter_mut()
+            .find(|(_, transaction)| transaction.evse_id == request.connector_id);
+
+        if let (Some(charging_state), Some((transaction_id, transaction))) =
+            (charging_state, transaction)
+        {
+            return translated(
+                unique_id,
+                "TransactionEvent",
+                Response::StatusNotification,
+                &v2_0_1::TransactionEventRequest::builder()
+                    .event_type(v2_0_1::TransactionEventEnum::Updated)
+                    .timestamp(timestamp)
+                    .trigger_reason(v2_0_1::TriggerReasonEnum::ChargingStateChanged)
+                    .seq_no(transaction.next_seq_no())
+                    .transaction_info(
+                        v2_0_1::Transaction::builder()
+                            .transaction_id(transaction_id_string(*transaction_id))
+                            .charging_state(charging_state)
+                            .build(),
+                    )
+                    .evse(transaction.evse())
+                    .build(),
+            );
+        }
+
+        // The status of the Charge Point as a whole has no equivalent.
+        if request.connector_id == 0 {
+            return respond(
+                &unique_id,
+                &v1_6::StatusNotificationResponse::builder().build(),
+            )
+            .map(Translated::Response);
+        }
+
+        translated(
+            unique_id,
+            "StatusNotification",
+            Response::StatusNotification,
+            &v2_0_1::StatusNotificationRequest::builder()
+                .connector_id(1)
+                .connector_status(match request.status {
+                    Status::Available => v2_0_1::ConnectorStatusEnum::Available,
+                    Status::Preparing
+                    | Status::Charging
+                    | Status::SuspendedEVSE
+                    | Status::SuspendedEV
+                    | Status::Finishing => v2_0_1::ConnectorStatusEnum::Occupied,
+                    Status::Reserved => v2_0_1::ConnectorStatusEnum::Reserved,
+                    Status::Unavailable => v2_0_1::ConnectorStatusEnum::Unavailable,
+                    Status::Faulted => v2_0_1::ConnectorStatusEnum::Faulted,
+                })
+                .evse_id(request.connector_id)
+                .timestamp(timestamp)
+                .build(),
+        )
+    }
+
+    fn start_transaction(
+        &mut self,
+        unique_id: String,
+        request: v1_6::StartTransactionRequest,
+    ) -> Result<Translated> {
+        let transaction_id = self.next_transaction_id;
+        self.next_transaction_id = self.next_transaction_id.checked_add(1).unwrap_or(1);
+
+        let transaction = OngoingTransaction {
+            evse_id: request.connector_id,
+            seq_no: 0,
+        };
+        let remote_start_id = self.remote_starts.remove(request.id_tag.as_str());
+
+        let event = v2_0_1::TransactionEventRequest::builder()
+            .event_type(v2_0_1::TransactionEventEnum::Started)
+            .timestamp(request.timestamp)
+            .trigger_reason(if remote_start_id.is_some() {
+                v2_0_1::TriggerReasonEnum::RemoteStart
+            } else {
+                v2_0_1::TriggerReasonEnum::Authorized
+            })
+            .seq_no(transaction.seq_no)
+            .transaction_info(
+                v2_0_1::Transaction::builder()
+                    .remote_start_id_opt(remote_start_id)
+                    .transaction_id(transaction_id_string(transaction_id))
+                    .build(),
+            )
+            .evse(transaction.evse())
+            .id_token(id_token(&request.id_tag))
+            .meter_value(vec![energy(
+                request.timestamp,
+                request.meter_start,
+                "Transaction.Begin",
+            )?])
+            .build();
+
+        let event = v2_0_1::TransactionEventRequest {
+            reservation_id: request.reservation_id,
+            ..event
+        };
+
+        self.transactions.insert(transaction_id, transaction);
+
+        translated(
+            unique_id,
+            "TransactionEvent",
+            Response::StartTransaction { transaction_id },
+            &event,
+        )
+    }
+
+    fn stop_transaction(
+        &mut self,
+        unique_id: String,
+        request: v1_6::StopTransactionRequest,
+    ) -> Result<Translated> {
+        use v1_6::StopTransactionReason as Reason;
+        use v2_0_1::{ReasonEnum, TriggerReasonEnum};
+
+        // A transaction unknown to the translator, e.g. started before a
+        // restart, is still ended.
+        let mut transaction =
+            self.transactions
+                .remove(&request.transaction_id)
+                .unwrap_or(OngoingTransaction {
+                    evse_id: 0,
+                    seq_no: 0,
+                });
+
+        let (trigger_reason, stopped_reason) = match request.reason.unwrap_or(Reason::Local) {
+            Reason::Local => (TriggerReasonEnum::StopAuthorized, ReasonEnum::Local),
+            Reason::Remote => (TriggerReasonEnum::RemoteStop, ReasonEnum::Remote),
+            Reason::DeAuthorized => (TriggerReasonEnum::Deauthorized, ReasonEnum::DeAuthorized),
+            Reason::EVDisconnected => (
+                TriggerReasonEnum::EVCommunicationLost,
+                ReasonEnum::EVDisconnected,
+            ),
+            Reason::HardReset | Reason::SoftReset => {
+                (TriggerReasonEnum::ResetCommand, ReasonEnum::ImmediateReset)
+            }
+            Reason::UnlockCommand => (TriggerReasonEnum::UnlockCommand, ReasonEnum::Other),
+            reason => (
+                TriggerReasonEnum::AbnormalCondition,
+                convert(reason).unwrap_or(ReasonEnum::Other),
+            ),
+        };
+
+        let mut meter_value = request
+            .transaction_data
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|transaction_data| {
+                meter_value(
+                    v1_6::MeterValue::builder()
+                        .sampled_value(transaction_data.sampled_value)
+                        .timestamp(transaction_data.timestamp)
+                        .build(),
+                )
+            })
+            .collect::<Vec<_>>();
+        meter_value.push(energy(
+            request.timestamp,
+            request.meter_stop,
+            "Transaction.End",
+        )?);
+
+        let event = v2_0_1::TransactionEventRequest::builder()
+            .event_type(v2_0_1::TransactionEventEnum::Ended)
+            .timestamp(request.timestamp)
+            .trigger_reason(trigger_reason)
+            .seq_no(transaction.next_seq_no())
+            .transaction_info(
+                v2_0_1::Transaction::builder()
+                    .transaction_id(transaction_id_string(request.transaction_id))
+                    .stopped_reason(stopped_reason)
+                    .build(),
+            )
+            .meter_value(meter_value)
+            .build();
+
+        let event = v2_0_1::TransactionEventRequest {
+            evse: (transaction.evse_id > 0).then(|| transaction.evse()),
+            id_token: request.id_tag.as_deref().map(id_token),
+            ..event
+        };
+
+        translated(
+            unique_id,
+            "TransactionEvent",
+            Response::StopTransaction,
+            &event,
+        )
+    }
+
+    fn meter_values(
+        &mut self,
+        unique_id: String,
+        request: v1_6::MeterValuesRequest,
+    ) -> Result<Translated> {
+        let meter_value = request
+            .meter_value
+            .into_iter()
+            .filter_map(meter_value)
+            .collect::<Vec<_>>();
+
+        // The 2.0.1 messages require at least one meter value.
+        if meter_value.is_empty() {
+            return respond(&unique_id, &v1_6::MeterValuesResponse::builder().build())
+                .map(Translated::Response);
+        }
+
+        let transaction = request.transaction_id.and_then(|transaction_id| {
+            self.transactions
+                .get_mut(&transaction_id)
+                .map(|transaction| (transaction_id, transaction))
+        });
+
+        match transaction {
+            Some((transaction_id, transaction)) => translated(
+                unique_id,
+                "TransactionEvent",
+                Response::MeterValues,
+                &v2_0_1::TransactionEventRequest::builder()
+                    .event_type(v2_0_1::TransactionEventEnum::Updated)
+                    .timestamp(meter_value[0].timestamp)
+                    .trigger_reason(v2_0_1::TriggerReasonEnum::MeterValuePeriodic)
+                    .seq_no(transaction.next_seq_no())
+                    .transaction_info(
+                        v2_0_1::Transaction::builder()
+                            .transaction_id(transaction_id_string(transaction_id))
+                            .build(),
+                    )
+                    .evse(transaction.evse())
+                    .meter_value(meter_value)
+                    .build(),
+            ),
+
+            None => translated(
+                unique_id,
+                "MeterValues",
+                Response::MeterValues,
+                &v2_0_1::MeterValuesRequest::builder()
+                    .evse_id(request.connector_id)
+                    .meter_value(meter_value)
+                    .build(),
+            ),
+        }
+    }
+}
+
+fn translated<P>(
+    unique_id: String,
+    action: &str,
+    response: Response,
+    payload: &P,
+) -> Result<Translated>
+where
+    P: Serialize,
+{
+    Ok(Translated::Call(
+        Call::new(unique_id.clone(), action, payload)?,
+        Translation {
+            unique_id,
+            response,
+        },
+    ))
+}
+
+fn respond<P>(unique_id: &str, payload: &P) -> Result<CallResult>
+where
+    P: Serialize,
+{
+    Ok(CallResult::new(unique_id, payload)?)
+}
+
+/// Convert between the enums of the two versions that share some variants.
+fn convert<T>(value: impl Display) -> Option<T>
+where
+    T: FromStr,
+{
+    value.to_string().parse().ok()
+}
+
+/// `value` as a string property of the other version, `Untranslatable` if
+/// it is not valid in it, e.g. too long.
+fn translate_string<'a, T>(value: &'a str) -> Result<T>
+where
+    T: TryFrom<&'a str>,
+{
+    T::try_from(value).map_err(|_| Error::Untranslatable(value.to_owned()))
+}
+
+/// An optional string property, see [`translate_string`].
+fn translate_option<'a, T>(value: Option<&'a str>) -> Result<Option<T>>
+where
+    T: TryFrom<&'a str>,
+{
+    value.map(translate_string).transpose()
+}
+
+/// The 1.6 transaction IDs are numbers, the 2.0.1 ones are strings.
+fn transaction_id_string(transaction_id: i32) -> BoundedString<36> {
+    ocppx_types::bounded_string::<36>(&transaction_id.to_string())
+        .expect("an `i32` is at most 11 characters long")
+}
+
+/// The 1.6 ID tags are usually the IDs of RFID cards.
+fn id_token(id_tag: &str) -> v2_0_1::IdToken {
+    v2_0_1::IdToken::builder()
+        .id_token(
+            ocppx_types::bounded_string::<36>(id_tag)
+                .expect("an ID tag is shorter than an ID token"),
+        )
+        .r#type(v2_0_1::IdTokenEnum::ISO14443)
+        .build()
+}
+
+/// The 2.0.1 `idTokenInfo` as 1.6 `idTagInfo`. A missing `idTokenInfo`
+/// means that the ID token is accepted.
+fn id_tag_info(id_token_info: Option<v2_0_1::IdTokenInfo>) -> v1_6::IdTagInfo {
+    let Some(id_token_info) = id_token_info else {
+        return v1_6::IdTagInfo::builder()
+            .status(v1_6::IdTagInfoStatus::Accepted)
+            .build();
+    };
+
+    v1_6::IdTagInfo::builder()
+        .expiry_date_opt(id_token_info.cache_expiry_date_time)
+        .parent_id_tag_opt(
+            id_token_info
+                .group_id_token
+                .and_then(|group_id_token| group_id_token.id_token.as_str().try_into().ok()),
+        )
+        .status(convert(id_token_info.status).unwrap_or(v1_6::IdTagInfoStatus::Invalid))
+        .build()
+}
+
+fn evse(evse_id: i32) -> v2_0_1::EVSE {
+    v2_0_1::EVSE::builder().connector_id(1).id(evse_id).build()
+}
+
+/// The meter value of the energy register at the start or the end of a
+/// transaction.
+fn energy(
+    timestamp: chrono::DateTime<Utc>,
+    value: i32,
+    context: &str,
+) -> Result<v2_0_1::MeterValue> {
+    Ok(serde_json::from_value(serde_json::json!({
+        "timestamp": timestamp,
+        "sampledValue": [{
+            "value": value,
+            "context": context,
+            "measurand": "Energy.Active.Import.Register",
+            "unitOfMeasure": { "unit": "Wh" },
+        }],
+    }))?)
+}
+
+/// The 1.6 meter value as 2.0.1 meter value, without the samples that are
+/// not numbers, e.g. signed data. `None` if no sample is left.
+fn meter_value(meter_value: v1_6::MeterValue) -> Option<v2_0_1::MeterValue> {
+    let sampled_value = meter_value
+        .sampled_value
+        .into_iter()
+        .filter_map(|sampled_value| {
+            let mut sample = v2_0_1::SampledValue::builder()
+                .context_opt(sampled_value.context.and_then(convert))
+                .location_opt(sampled_value.location.and_then(convert))
+                .measurand_opt(sampled_value.measurand.and_then(convert))
+                .phase_opt(sampled_value.phase.and_then(convert))
+                .unit_of_measure_opt(
+                    sampled_value
+                        .unit
+                        .and_then(convert::<BoundedString<20>>)
+                        .map(|unit| v2_0_1::UnitOfMeasure::builder().unit(unit).build()),
+                )
+                .value(0)
+                .build();
+            // Parsed as the type of `value`, which depends on the `decimal`
+            // feature.
+            sample.value = sampled_value.value.parse().ok()?;
+
+            Some(sample)
+        })
+        .collect::<Vec<_>>();
+
+    (!sampled_value.is_empty()).then(|| {
+        v2_0_1::MeterValue::builder()
+            .sampled_value(sampled_value)
+            .timestamp(meter_value.timestamp)
+            .build()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn call(action: &str, payload: Value) -> Call {
+        Call {
+            unique_id: "19223201".to_owned(),
+            action: action.to_owned(),
+            payload,
+        }
+    }
+
+    #[test]
+    fn test_transaction() {
+        let mut translator = Translator::with_first_transaction_id(42);
+
+        let Translated::Call(start, translation) = translator
+            .charge_point_call(&call(
+                "StartTransaction",
+                json!({
+                    "connectorId": 2,
+                    "idTag": "A",
+                    "meterStart": 1000,
+                    "timestamp": "2013-02-01T20:53:32.486Z",
+                }),
+            ))
+            .unwrap()
+        else {
+            panic!("`StartTransaction` is translated");
+        };
+
+        assert_eq!(start.action, "TransactionEvent");
+        assert_eq!(start.payload["eventType"], "Started");
+        assert_eq!(start.payload["transactionInfo"]["transactionId"], "42");
+        assert_eq!(start.payload["evse"], json!({ "id": 2, "connectorId": 1 }));
+        assert_eq!(
+            start.payload["meterValue"][0]["sampledValue"][0]["value"],
+            1000.0
+        );
+        start.payload::<v2_0_1::TransactionEventRequest>().unwrap();
+
+        let response = translation
+            .call_result(&CallResult {
+                unique_id: "1".to_owned(),
+                payload: json!({ "idTokenInfo": { "status": "Accepted" } }),
+            })
+            .unwrap();
+        assert_eq!(response.unique_id, "19223201");
+        assert_eq!(
+            response.payload,
+            json!({ "idTagInfo": { "status": "Accepted" }, "transactionId": 42 })
+        );
+
+        let Translated::Call(charging, _) = translator
+            .charge_point_call(&call(
+                "StatusNotification",
+                json!({ "connectorId": 2, "errorCode": "NoError", "status": "Charging" }),
+            ))
+            .unwrap()
+        else {
+            panic!("`StatusNotification` is translated");
+        };
+
+        assert_eq!(charging.action, "TransactionEvent");
+        assert_eq!(charging.payload["seqNo"], 1);
+        assert_eq!(
+            charging.payload["transactionInfo"]["chargingState"],
+            "Charging"
+        );
+
+        let Translated::Call(stop, _) = translator
+            .csms_call(&call(
+                "RequestStopTransaction",
+                json!({ "transactionId": "42" }),
+            ))
+            .unwrap()
+        else {
+            panic!("`RequestStopTransaction` is translated");
+        };
+
+        assert_eq!(stop.action, "RemoteStopTransaction");
+        assert_eq!(stop.payload, json!({ "transactionId": 42 }));
+
+        let Translated::Call(stop, _) = translator
+            .charge_point_call(&call(
+                "StopTransaction",
+                json!({
+                    "transactionId": 42,
+                    "meterStop": 1500,
+                    "timestamp": "2013-02-01T21:53:32.486Z",
+                    "reason": "Remote",
+                }),
+            ))
+            .unwrap()
+        else {
+            panic!("`StopTransaction` is translated");
+        };
+
+        assert_eq!(stop.payload["eventType"], "Ended");
+        assert_eq!(stop.payload["triggerReason"], "RemoteStop");
+        assert_eq!(stop.payload["seqNo"], 2);
+        stop.payload::<v2_0_1::TransactionEventRequest>().unwrap();
+
+        // The transaction has ended.
+        assert!(matches!(
+            translator
+                .csms_call(&call(
+                    "RequestStopTransaction",
+                    json!({ "transactionId": "42" })
+                ))
+                .unwrap(),
+            Translated::Response(_)
+        ));
+    }
+
+    #[test]
+    fn test_untranslatable() {
+        let mut translator = Translator::new();
+
+        assert!(matches!(
+            translator.charge_point_call(&call("GetInstalledCertificateIds", json!({}))),
+            Err(Error::Untranslatable(action)) if action == "GetInstalledCertificateIds"
+        ));
+
+        let Translated::Response(response) = translator
+            .charge_point_call(&call(
+                "StatusNotification",
+                json!({ "connectorId": 0, "errorCode": "NoError", "status": "Available" }),
+            ))
+            .unwrap()
+        else {
+            panic!("the status of the Charge Point is not translated");
+        };
+
+        assert_eq!(response.payload, json!({}));
+    }
+}
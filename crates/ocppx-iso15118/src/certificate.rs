@@ -0,0 +1,101 @@
+use crate::{Error, Result};
+use ocppx_pki::{Certificate, HashAlgorithm};
+use ocppx_types::v2_0_1::HashAlgorithmEnum;
+
+/// Parse the certificates of a PEM document, in order.
+pub(crate) fn parse_pem(pem: &str) -> Result<Vec<Certificate>> {
+    Certificate::parse_pem(pem).ok_or(Error::InvalidCertificate)
+}
+
+pub(crate) fn hash(hash_algorithm: HashAlgorithmEnum, data: &[u8]) -> String {
+    ocppx_pki::hash(
+        match hash_algorithm {
+            HashAlgorithmEnum::SHA256 => HashAlgorithm::Sha256,
+            HashAlgorithmEnum::SHA384 => HashAlgorithm::Sha384,
+            HashAlgorithmEnum::SHA512 => HashAlgorithm::Sha512,
+        },
+        data,
+    )
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use yasna::{models::ObjectIdentifier, Tag};
+
+    /// The `id-pe-authorityInfoAccess` extension.
+    const AUTHORITY_INFO_ACCESS: &[u64] = &[1, 3, 6, 1, 5, 5, 7, 1, 1];
+
+    /// The `id-ad-ocsp` access method.
+    const OCSP_ACCESS: &[u64] = &[1, 3, 6, 1, 5, 5, 7, 48, 1];
+
+    pub(crate) const RESPONDER_URL: &str = "http://ocsp.v2g.example";
+
+    /// A V2G root, a sub-CA, and a contract certificate whose OCSP
+    /// responder is [`RESPONDER_URL`], with the key of the sub-CA.
+    pub(crate) fn contract_chain() -> (
+        rcgen::Certificate,
+        rcgen::Certificate,
+        rcgen::KeyPair,
+        rcgen::Certificate,
+    ) {
+        let ca = |common_name: &str| {
+            let mut params = rcgen::CertificateParams::default();
+            params
+                .distinguished_name
+                .push(rcgen::DnType::CommonName, common_name);
+            params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+
+            params
+        };
+        let authority_info_access = yasna::construct_der(|writer| {
+            writer.write_sequence(|writer| {
+                writer.next().write_sequence(|writer| {
+                    writer
+                        .next()
+                        .write_oid(&ObjectIdentifier::from_slice(OCSP_ACCESS));
+                    writer
+                        .next()
+                        .write_tagged_implicit(Tag::context(6), |writer| {
+                            writer.write_ia5_string(RESPONDER_URL)
+                        });
+                })
+            })
+        });
+
+        let root_key = rcgen::KeyPair::generate().unwrap();
+        let root = ca("V2G Root").self_signed(&root_key).unwrap();
+        let sub_ca_key = rcgen::KeyPair::generate().unwrap();
+        let mut sub_ca = ca("MO Sub-CA");
+        sub_ca.custom_extensions = vec![rcgen::CustomExtension::from_oid_content(
+            AUTHORITY_INFO_ACCESS,
+            authority_info_access.clone(),
+        )];
+        let sub_ca = sub_ca.signed_by(&sub_ca_key, &root, &root_key).unwrap();
+
+        let mut contract = rcgen::CertificateParams::default();
+        contract
+            .distinguished_name
+            .push(rcgen::DnType::CommonName, "FRXYZ123456789");
+        contract.custom_extensions = vec![rcgen::CustomExtension::from_oid_content(
+            AUTHORITY_INFO_ACCESS,
+            authority_info_access,
+        )];
+        let contract = contract
+            .signed_by(&rcgen::KeyPair::generate().unwrap(), &sub_ca, &sub_ca_key)
+            .unwrap();
+
+        (root, sub_ca, sub_ca_key, contract)
+    }
+
+    #[test]
+    fn test_parse_pem() {
+        let (root, sub_ca, _, contract) = contract_chain();
+        let chain =
+            parse_pem(&format!("{}{}{}", contract.pem(), sub_ca.pem(), root.pem())).unwrap();
+
+        assert_eq!(chain.len(), 3);
+        assert_eq!(chain[0].ocsp_responder_url.as_deref(), Some(RESPONDER_URL));
+        assert_eq!(parse_pem("garbage").unwrap_err(), Error::InvalidCertificate);
+    }
+}
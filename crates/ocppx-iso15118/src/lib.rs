@@ -0,0 +1,105 @@
+//! ISO 15118 Plug & Charge with OCPP 2.0.1.
+//!
+//! With Plug & Charge, the EV authenticates with a contract certificate,
+//! issued by its Mobility Operator under a V2G PKI. The messages are
+//! generated in `ocppx_types::v2_0_1`:
+//!
+//! * `Authorize`, with the `iso15118CertificateHashData` of the contract
+//!   certificate chain, see [`contract_authorize_request`],
+//! * `GetCertificateStatus`, asking the CSMS for the OCSP status of a
+//!   certificate,
+//! * `Get15118EVCertificate`, forwarding the EXI stream of the
+//!   `CertificateInstallationReq` of the EV, as it is, to the CSMS.
+//!
+//! The OCSP helpers compute the `OCSPRequestData` of a chain with
+//! [`ocsp_request_data`], encode the OCSP requests with
+//! [`encode_ocsp_request`], and parse the OCSP responses with
+//! [`OcspResponse`], verifying the signature of their responder. An
+//! [`OcspResponder`] signs responses, so that the whole flow can be
+//! prototyped without a V2G PKI.
+
+mod certificate;
+mod ocsp;
+
+use ocppx_types::v2_0_1::{AuthorizeRequest, HashAlgorithmEnum, IdToken, IdTokenEnum};
+pub use ocsp::{
+    encode_ocsp_request, ocsp_request_data, CertificateStatus, OcspResponder, OcspResponse,
+    SingleResponse,
+};
+use thiserror::Error;
+
+/// The maximum number of `iso15118CertificateHashData` in an `Authorize`.
+pub const MAX_CERTIFICATE_HASH_DATA: usize = 4;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    #[error("invalid certificate")]
+    InvalidCertificate,
+
+    #[error("invalid private key")]
+    InvalidPrivateKey,
+
+    #[error("a certificate of the chain has no OCSP responder URL")]
+    MissingResponderUrl,
+
+    #[error("invalid certificate hash data")]
+    InvalidHashData,
+
+    #[error("invalid OCSP response")]
+    InvalidOcspResponse,
+
+    #[error("the OCSP response is not signed by a trusted responder")]
+    InvalidOcspSignature,
+
+    #[error("the OCSP responder failed with the status {0}")]
+    OcspResponderError(i64),
+
+    #[error("the EMAID is too long")]
+    EmaidTooLong,
+}
+
+/// The `Authorize` of a Charging Station for the contract of an EV,
+/// identified by its `emaid`, with the `iso15118CertificateHashData` of
+/// the contract certificate `chain`, see [`ocsp_request_data`].
+///
+/// A Charging Station that cannot validate the chain itself also sends
+/// it, in the `certificate` field.
+pub fn contract_authorize_request(emaid: &str, chain: &str) -> Result<AuthorizeRequest> {
+    let mut hash_data = ocsp_request_data(chain, HashAlgorithmEnum::SHA256)?;
+    hash_data.truncate(MAX_CERTIFICATE_HASH_DATA);
+
+    Ok(AuthorizeRequest::builder()
+        .id_token(
+            IdToken::builder()
+                .id_token(ocppx_types::bounded_string::<36>(emaid).ok_or(Error::EmaidTooLong)?)
+                .r#type(IdTokenEnum::EMAID)
+                .build(),
+        )
+        .iso15118_certificate_hash_data_opt((!hash_data.is_empty()).then_some(hash_data))
+        .build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::certificate::tests::contract_chain;
+
+    #[test]
+    fn test_contract_authorize_request() {
+        let (root, sub_ca, _, contract) = contract_chain();
+        let request = contract_authorize_request(
+            "FRXYZ123456789",
+            &format!("{}{}{}", contract.pem(), sub_ca.pem(), root.pem()),
+        )
+        .unwrap();
+
+        assert_eq!(request.id_token.r#type, IdTokenEnum::EMAID);
+        assert_eq!(request.iso15118_certificate_hash_data.unwrap().len(), 2);
+        assert_eq!(
+            contract_authorize_request("FRXYZ123456789", "garbage").unwrap_err(),
+            Error::InvalidCertificate
+        );
+    }
+}
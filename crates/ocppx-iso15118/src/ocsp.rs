@@ -0,0 +1,778 @@
+use crate::{
+    certificate::{hash, parse_pem},
+    Error, Result,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, TimeDelta, Utc};
+use ocppx_pki::{generalized_time, hex, parse_time, unhex, verify_signature, Certificate};
+use ocppx_types::v2_0_1::{
+    AuthorizeCertificateStatusEnum, GetCertificateStatusEnum, GetCertificateStatusRequest,
+    GetCertificateStatusResponse, HashAlgorithmEnum, OCSPRequestData,
+};
+use ring::{
+    rand::SystemRandom,
+    signature::{EcdsaKeyPair, ECDSA_P256_SHA256_ASN1_SIGNING},
+};
+use yasna::{models::ObjectIdentifier, DERWriter, Tag};
+
+/// The `id-pkix-ocsp-basic` response type.
+const OCSP_BASIC: &[u64] = &[1, 3, 6, 1, 5, 5, 7, 48, 1, 1];
+
+/// The `ecdsa-with-SHA256` signature algorithm.
+const ECDSA_WITH_SHA256: &[u64] = &[1, 2, 840, 10045, 4, 3, 2];
+
+/// The OIDs of the hash algorithms, see RFC 5754.
+fn hash_algorithm_oid(hash_algorithm: HashAlgorithmEnum) -> ObjectIdentifier {
+    let last = match hash_algorithm {
+        HashAlgorithmEnum::SHA256 => 1,
+        HashAlgorithmEnum::SHA384 => 2,
+        HashAlgorithmEnum::SHA512 => 3,
+    };
+
+    ObjectIdentifier::from_slice(&[2, 16, 840, 1, 101, 3, 4, 2, last])
+}
+
+/// Compute the `OCSPRequestData` of the certificates of `chain`, a PEM
+/// document starting with the leaf certificate, e.g. the contract
+/// certificate of an EV followed by its sub-CAs.
+///
+/// The hash data of a certificate needs the public key of its issuer, so
+/// it is computed for the certificates whose issuer is in `chain` only:
+/// include the root certificate to get the hash data of the last sub-CA.
+/// The root certificate itself is skipped.
+pub fn ocsp_request_data(
+    chain: &str,
+    hash_algorithm: HashAlgorithmEnum,
+) -> Result<Vec<OCSPRequestData>> {
+    let chain = parse_pem(chain)?;
+
+    chain
+        .iter()
+        .filter(|certificate| certificate.issuer != certificate.subject)
+        .filter_map(|certificate| {
+            let issuer = chain
+                .iter()
+                .find(|issuer| issuer.subject == certificate.issuer)?;
+
+            Some(
+                certificate
+                    .ocsp_responder_url
+                    .clone()
+                    .ok_or(Error::MissingResponderUrl)
+                    .and_then(|responder_url| {
+                        Ok(OCSPRequestData::builder()
+                            .hash_algorithm(hash_algorithm)
+                            .issuer_name_hash(hash(hash_algorithm, &certificate.issuer))
+                            .issuer_key_hash(hash(hash_algorithm, &issuer.public_key))
+                            .serial_number(
+                                // At most 20 bytes, per RFC 5280.
+                                ocppx_types::bounded_string::<40>(&certificate.serial_number_hex())
+                                    .ok_or(Error::InvalidCertificate)?,
+                            )
+                            .responder_u_r_l(responder_url)
+                            .build())
+                    }),
+            )
+        })
+        .collect()
+}
+
+/// The `CertID` of RFC 6960, identifying a certificate.
+struct CertId {
+    hash_algorithm: HashAlgorithmEnum,
+    issuer_name_hash: Vec<u8>,
+    issuer_key_hash: Vec<u8>,
+    serial_number: Vec<u8>,
+}
+
+impl CertId {
+    fn new(data: &OCSPRequestData) -> Result<Self> {
+        let (Some(issuer_name_hash), Some(issuer_key_hash), Some(serial_number)) = (
+            unhex(&data.issuer_name_hash),
+            unhex(&data.issuer_key_hash),
+            unhex(&data.serial_number),
+        ) else {
+            return Err(Error::InvalidHashData);
+        };
+
+        Ok(Self {
+            hash_algorithm: data.hash_algorithm,
+            issuer_name_hash,
+            issuer_key_hash,
+            serial_number,
+        })
+    }
+
+    fn write(&self, writer: DERWriter) {
+        writer.write_sequence(|writer| {
+            writer.next().write_sequence(|writer| {
+                writer
+                    .next()
+                    .write_oid(&hash_algorithm_oid(self.hash_algorithm));
+                writer.next().write_null();
+            });
+            writer.next().write_bytes(&self.issuer_name_hash);
+            writer.next().write_bytes(&self.issuer_key_hash);
+            writer.next().write_bigint_bytes(&self.serial_number, true);
+        })
+    }
+}
+
+/// Encode the DER `OCSPRequest` of RFC 6960 for `requests`, to be sent to
+/// their responder URL, e.g. with a `POST` of the
+/// `application/ocsp-request` content type.
+pub fn encode_ocsp_request(requests: &[OCSPRequestData]) -> Result<Vec<u8>> {
+    let cert_ids = requests
+        .iter()
+        .map(CertId::new)
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(yasna::construct_der(|writer| {
+        writer.write_sequence(|writer| {
+            writer.next().write_sequence(|writer| {
+                writer.next().write_sequence_of(|writer| {
+                    for cert_id in &cert_ids {
+                        writer
+                            .next()
+                            .write_sequence(|writer| cert_id.write(writer.next()));
+                    }
+                })
+            })
+        })
+    }))
+}
+
+/// The status of a certificate in an OCSP response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CertificateStatus {
+    Good,
+    Revoked {
+        revoked_at: DateTime<Utc>,
+    },
+    /// The responder does not know the certificate.
+    Unknown,
+}
+
+/// The status of a certificate in an [`OcspResponse`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SingleResponse {
+    /// The hash of the name of the issuer, in hexadecimal.
+    pub issuer_name_hash: String,
+    /// The hash of the public key of the issuer, in hexadecimal.
+    pub issuer_key_hash: String,
+    /// The serial number, in hexadecimal without leading zeros.
+    pub serial_number: String,
+    pub status: CertificateStatus,
+    pub this_update: DateTime<Utc>,
+    /// When a newer status will be available; the status is stale after
+    /// it.
+    pub next_update: Option<DateTime<Utc>>,
+}
+
+impl SingleResponse {
+    /// Whether the response is about the certificate of `data`.
+    pub fn matches(&self, data: &OCSPRequestData) -> bool {
+        self.issuer_name_hash
+            .eq_ignore_ascii_case(&data.issuer_name_hash)
+            && self
+                .issuer_key_hash
+                .eq_ignore_ascii_case(&data.issuer_key_hash)
+            && self
+                .serial_number
+                .eq_ignore_ascii_case(data.serial_number.trim_start_matches('0'))
+    }
+}
+
+/// A successful OCSP response of RFC 6960, e.g. the `ocspResult` of a
+/// `GetCertificateStatus` response.
+///
+/// The response is signed by the issuer of the certificates, or by a
+/// delegated responder, i.e. a certificate with the `id-kp-OCSPSigning`
+/// key purpose, issued by the issuer and included in the response. Only
+/// the statuses of the certificates of this issuer are trusted, see
+/// [`Self::status`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OcspResponse {
+    pub produced_at: DateTime<Utc>,
+    pub responses: Vec<SingleResponse>,
+    /// The subject of the issuer that has signed the response, directly or
+    /// through a delegated responder.
+    signer_name: Vec<u8>,
+    /// The public key of this issuer.
+    signer_key: Vec<u8>,
+}
+
+impl OcspResponse {
+    /// Parse a DER `OCSPResponse`, and verify its signature. `issuers` is
+    /// a PEM document with the certificates trusted to sign it, e.g. the
+    /// sub-CAs of the contract certificate chain.
+    pub fn parse(der: &[u8], issuers: &str) -> Result<Self> {
+        let issuers = parse_pem(issuers)?;
+        let (status, basic) = yasna::parse_der(der, |reader| {
+            reader.read_sequence(|reader| {
+                let status = reader.next().read_enum()?;
+                let basic = reader.read_optional(|reader| {
+                    reader.read_tagged(Tag::context(0), |reader| {
+                        reader.read_sequence(|reader| {
+                            let response_type = reader.next().read_oid()?;
+                            let response = reader.next().read_bytes()?;
+
+                            Ok((response_type, response))
+                        })
+                    })
+                })?;
+
+                Ok((status, basic))
+            })
+        })
+        .map_err(|_| Error::InvalidOcspResponse)?;
+
+        let response = match basic {
+            _ if status != 0 => return Err(Error::OcspResponderError(status)),
+            Some((response_type, response))
+                if response_type == ObjectIdentifier::from_slice(OCSP_BASIC) =>
+            {
+                response
+            }
+            _ => return Err(Error::InvalidOcspResponse),
+        };
+
+        let (response_data, signature_algorithm, signature, certificates) =
+            yasna::parse_der(&response, |reader| {
+                reader.read_sequence(|reader| {
+                    let response_data = reader.next().read_der()?;
+                    let signature_algorithm = reader.next().read_sequence(|reader| {
+                        let algorithm = reader.next().read_oid()?;
+                        // The parameters.
+                        reader.read_optional(|reader| reader.read_der())?;
+
+                        Ok(algorithm)
+                    })?;
+                    let (signature, _) = reader.next().read_bitvec_bytes()?;
+                    let certificates = reader
+                        .read_optional(|reader| {
+                            reader.read_tagged(Tag::context(0), |reader| {
+                                reader.collect_sequence_of(|reader| reader.read_der())
+                            })
+                        })?
+                        .unwrap_or_default();
+
+                    Ok((response_data, signature_algorithm, signature, certificates))
+                })
+            })
+            .map_err(|_| Error::InvalidOcspResponse)?;
+
+        let response = yasna::parse_der(&response_data, |reader| {
+            reader.read_sequence(|reader| {
+                reader.read_optional(|reader| {
+                    reader.read_tagged(Tag::context(0), |reader| reader.read_i64())
+                })?;
+                // The responder ID.
+                reader.next().read_der()?;
+                let produced_at = parse_time(reader.next().read_tagged_der()?)?;
+                let mut responses = Vec::new();
+
+                reader.next().read_sequence_of(|reader| {
+                    responses.push(reader.read_sequence(|reader| {
+                        let (issuer_name_hash, issuer_key_hash, serial_number) =
+                            reader.next().read_sequence(|reader| {
+                                reader.next().read_der()?;
+
+                                Ok((
+                                    hex(&reader.next().read_bytes()?),
+                                    hex(&reader.next().read_bytes()?),
+                                    reader.next().read_bigint_bytes()?.0,
+                                ))
+                            })?;
+                        let status = {
+                            let reader = reader.next();
+
+                            match reader.lookahead_tag()? {
+                                tag if tag == Tag::context(0) => {
+                                    reader
+                                        .read_tagged_implicit(tag, |reader| reader.read_null())?;
+
+                                    CertificateStatus::Good
+                                }
+                                tag if tag == Tag::context(1) => CertificateStatus::Revoked {
+                                    revoked_at: reader.read_tagged_implicit(tag, |reader| {
+                                        reader.read_sequence(|reader| {
+                                            let revoked_at =
+                                                parse_time(reader.next().read_tagged_der()?)?;
+                                            // The revocation reason.
+                                            reader.read_optional(|reader| reader.read_der())?;
+
+                                            Ok(revoked_at)
+                                        })
+                                    })?,
+                                },
+                                _ => {
+                                    reader.read_der()?;
+
+                                    CertificateStatus::Unknown
+                                }
+                            }
+                        };
+                        let this_update = parse_time(reader.next().read_tagged_der()?)?;
+                        let next_update = reader.read_optional(|reader| {
+                            reader.read_tagged(Tag::context(0), |reader| {
+                                parse_time(reader.read_tagged_der()?)
+                            })
+                        })?;
+                        reader.read_optional(|reader| reader.read_der())?;
+
+                        let serial_number = hex(&serial_number);
+
+                        Ok(SingleResponse {
+                            issuer_name_hash,
+                            issuer_key_hash,
+                            serial_number: serial_number.trim_start_matches('0').to_owned(),
+                            status,
+                            this_update,
+                            next_update,
+                        })
+                    })?);
+
+                    Ok(())
+                })?;
+
+                // The extensions.
+                reader.read_optional(|reader| reader.read_der())?;
+
+                Ok((produced_at, responses))
+            })
+        })
+        .map_err(|_| Error::InvalidOcspResponse)?;
+        let (produced_at, responses) = response;
+
+        // The responder is one of the issuers, or a delegated responder
+        // issued by one of them: the signer is this issuer.
+        let delegated = certificates
+            .into_iter()
+            .filter_map(Certificate::from_der)
+            .filter(|certificate| certificate.ocsp_signing && certificate.is_valid_at(produced_at))
+            .collect::<Vec<_>>();
+        let is_signed_with = |public_key: &[u8]| {
+            verify_signature(&signature_algorithm, public_key, &response_data, &signature)
+        };
+        let signer = issuers
+            .into_iter()
+            .find(|issuer| {
+                is_signed_with(&issuer.public_key)
+                    || delegated.iter().any(|responder| {
+                        responder.is_signed_by(issuer) && is_signed_with(&responder.public_key)
+                    })
+            })
+            .ok_or(Error::InvalidOcspSignature)?;
+
+        Ok(Self {
+            produced_at,
+            responses,
+            signer_name: signer.subject,
+            signer_key: signer.public_key,
+        })
+    }
+
+    /// Parse a Base64-encoded DER `OCSPResponse`, as found in
+    /// `GetCertificateStatus`, see [`Self::parse`].
+    pub fn from_base64(ocsp_result: &str, issuers: &str) -> Result<Self> {
+        Self::parse(
+            &STANDARD
+                .decode(ocsp_result)
+                .map_err(|_| Error::InvalidOcspResponse)?,
+            issuers,
+        )
+    }
+
+    /// The status of the certificate of `data`, if it is in the response,
+    /// and if it has been issued by the signer of the response: a responder
+    /// only tells the statuses of the certificates of its issuer, see RFC
+    /// 6960, section 4.2.2.2.
+    pub fn status(&self, data: &OCSPRequestData) -> Option<&SingleResponse> {
+        let is_signer = hash(data.hash_algorithm, &self.signer_name)
+            .eq_ignore_ascii_case(&data.issuer_name_hash)
+            && hash(data.hash_algorithm, &self.signer_key)
+                .eq_ignore_ascii_case(&data.issuer_key_hash);
+
+        if !is_signer {
+            return None;
+        }
+
+        self.responses
+            .iter()
+            .find(|response| response.matches(data))
+    }
+
+    /// The `certificateStatus` of an `Authorize` response for a contract
+    /// certificate chain, given the `iso15118CertificateHashData` of its
+    /// request.
+    ///
+    /// A revoked certificate is `CertificateRevoked`; a missing, unknown
+    /// or stale status at `now`, a status from the future, or no hash data
+    /// at all, is a `CertChainError`.
+    pub fn authorize_status(
+        &self,
+        hash_data: &[OCSPRequestData],
+        now: DateTime<Utc>,
+    ) -> AuthorizeCertificateStatusEnum {
+        let statuses = hash_data
+            .iter()
+            .map(|data| {
+                self.status(data)
+                    .filter(|response| {
+                        response.this_update <= now
+                            && response.next_update.is_none_or(|next| now <= next)
+                    })
+                    .map(|response| response.status)
+            })
+            .collect::<Vec<_>>();
+
+        if statuses.is_empty() {
+            AuthorizeCertificateStatusEnum::CertChainError
+        } else if statuses
+            .iter()
+            .any(|status| matches!(status, Some(CertificateStatus::Revoked { .. })))
+        {
+            AuthorizeCertificateStatusEnum::CertificateRevoked
+        } else if statuses
+            .iter()
+            .all(|status| *status == Some(CertificateStatus::Good))
+        {
+            AuthorizeCertificateStatusEnum::Accepted
+        } else {
+            AuthorizeCertificateStatusEnum::CertChainError
+        }
+    }
+}
+
+/// A minimal OCSP responder, signing its responses with an ECDSA P-256
+/// key, to prototype Plug & Charge without a V2G PKI.
+///
+/// Every certificate is `good`, unless it has been revoked with
+/// [`Self::revoke`].
+pub struct OcspResponder {
+    certificate: Certificate,
+    key_pair: EcdsaKeyPair,
+    revoked: Vec<(OCSPRequestData, DateTime<Utc>)>,
+    validity: TimeDelta,
+}
+
+impl std::fmt::Debug for OcspResponder {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("OcspResponder")
+            .field("revoked", &self.revoked)
+            .field("validity", &self.validity)
+            .finish_non_exhaustive()
+    }
+}
+
+impl OcspResponder {
+    /// A responder with a certificate, and its PKCS #8 private key, both
+    /// in PEM. The certificate is included in the responses.
+    pub fn new(certificate: &str, private_key: &str) -> Result<Self> {
+        let certificate = parse_pem(certificate)?.remove(0);
+        let private_key = pem::parse(private_key).map_err(|_| Error::InvalidPrivateKey)?;
+        let key_pair = EcdsaKeyPair::from_pkcs8(
+            &ECDSA_P256_SHA256_ASN1_SIGNING,
+            private_key.contents(),
+            &SystemRandom::new(),
+        )
+        .map_err(|_| Error::InvalidPrivateKey)?;
+
+        Ok(Self {
+            certificate,
+            key_pair,
+            revoked: Vec::new(),
+            validity: TimeDelta::days(1),
+        })
+    }
+
+    /// Announce the next update of the statuses after `validity`, one day
+    /// by default.
+    pub fn with_validity(mut self, validity: TimeDelta) -> Self {
+        self.validity = validity;
+
+        self
+    }
+
+    /// Revoke the certificate of `data` at `revoked_at`.
+    pub fn revoke(&mut self, data: OCSPRequestData, revoked_at: DateTime<Utc>) {
+        self.revoked.push((data, revoked_at));
+    }
+
+    /// Sign a DER `OCSPResponse` with the statuses of `requests`.
+    pub fn respond(&self, requests: &[OCSPRequestData], now: DateTime<Utc>) -> Result<Vec<u8>> {
+        let cert_ids = requests
+            .iter()
+            .map(CertId::new)
+            .collect::<Result<Vec<_>>>()?;
+        let response_data = yasna::construct_der(|writer| {
+            writer.write_sequence(|writer| {
+                writer.next().write_tagged(Tag::context(1), |writer| {
+                    writer.write_der(&self.certificate.subject)
+                });
+                writer.next().write_tagged_der(&generalized_time(now));
+                writer.next().write_sequence_of(|writer| {
+                    for (data, cert_id) in requests.iter().zip(&cert_ids) {
+                        writer.next().write_sequence(|writer| {
+                            cert_id.write(writer.next());
+
+                            let revoked_at = self
+                                .revoked
+                                .iter()
+                                .find(|(revoked, _)| {
+                                    revoked
+                                        .issuer_name_hash
+                                        .eq_ignore_ascii_case(&data.issuer_name_hash)
+                                        && revoked
+                                            .issuer_key_hash
+                                            .eq_ignore_ascii_case(&data.issuer_key_hash)
+                                        && revoked
+                                            .serial_number
+                                            .eq_ignore_ascii_case(&data.serial_number)
+                                })
+                                .map(|(_, revoked_at)| *revoked_at);
+
+                            match revoked_at {
+                                None => writer
+                                    .next()
+                                    .write_tagged_implicit(Tag::context(0), |writer| {
+                                        writer.write_null()
+                                    }),
+                                Some(revoked_at) => {
+                                    writer
+                                        .next()
+                                        .write_tagged_implicit(Tag::context(1), |writer| {
+                                            writer.write_sequence(|writer| {
+                                                writer
+                                                    .next()
+                                                    .write_tagged_der(&generalized_time(revoked_at))
+                                            })
+                                        })
+                                }
+                            }
+
+                            writer.next().write_tagged_der(&generalized_time(now));
+                            writer.next().write_tagged(Tag::context(0), |writer| {
+                                writer.write_tagged_der(&generalized_time(now + self.validity))
+                            });
+                        });
+                    }
+                });
+            })
+        });
+
+        let signature = self
+            .key_pair
+            .sign(&SystemRandom::new(), &response_data)
+            .map_err(|_| Error::InvalidPrivateKey)?;
+
+        let basic = yasna::construct_der(|writer| {
+            writer.write_sequence(|writer| {
+                writer.next().write_der(&response_data);
+                writer.next().write_sequence(|writer| {
+                    writer
+                        .next()
+                        .write_oid(&ObjectIdentifier::from_slice(ECDSA_WITH_SHA256));
+                });
+                writer
+                    .next()
+                    .write_bitvec_bytes(signature.as_ref(), signature.as_ref().len() * 8);
+                writer.next().write_tagged(Tag::context(0), |writer| {
+                    writer.write_sequence(|writer| writer.next().write_der(&self.certificate.der))
+                });
+            })
+        });
+
+        Ok(yasna::construct_der(|writer| {
+            writer.write_sequence(|writer| {
+                // `successful`.
+                writer.next().write_enum(0);
+                writer.next().write_tagged(Tag::context(0), |writer| {
+                    writer.write_sequence(|writer| {
+                        writer
+                            .next()
+                            .write_oid(&ObjectIdentifier::from_slice(OCSP_BASIC));
+                        writer.next().write_bytes(&basic);
+                    })
+                });
+            })
+        }))
+    }
+
+    /// Answer a `GetCertificateStatus`, as the CSMS would after asking the
+    /// responder.
+    pub fn get_certificate_status(
+        &self,
+        request: &GetCertificateStatusRequest,
+        now: DateTime<Utc>,
+    ) -> GetCertificateStatusResponse {
+        match self.respond(std::slice::from_ref(&request.ocsp_request_data), now) {
+            Ok(response) => GetCertificateStatusResponse::builder()
+                .status(GetCertificateStatusEnum::Accepted)
+                .ocsp_result(STANDARD.encode(response))
+                .build(),
+            Err(_) => GetCertificateStatusResponse::builder()
+                .status(GetCertificateStatusEnum::Failed)
+                .build(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::certificate::tests::{contract_chain, RESPONDER_URL};
+
+    #[test]
+    fn test_ocsp() {
+        let now = Utc::now();
+        let (root, sub_ca, sub_ca_key, contract) = contract_chain();
+
+        // Without the root, the sub-CA is skipped.
+        let hash_data = ocsp_request_data(
+            &format!("{}{}", contract.pem(), sub_ca.pem()),
+            HashAlgorithmEnum::SHA256,
+        )
+        .unwrap();
+        assert_eq!(hash_data.len(), 1);
+
+        let hash_data = ocsp_request_data(
+            &format!("{}{}{}", contract.pem(), sub_ca.pem(), root.pem()),
+            HashAlgorithmEnum::SHA256,
+        )
+        .unwrap();
+        assert_eq!(hash_data.len(), 2);
+        assert_eq!(hash_data[0].responder_u_r_l, RESPONDER_URL);
+        assert!(!encode_ocsp_request(&hash_data).unwrap().is_empty());
+
+        let issuers = format!("{}{}", sub_ca.pem(), root.pem());
+        let mut responder = OcspResponder::new(&sub_ca.pem(), &sub_ca_key.serialize_pem()).unwrap();
+        let response =
+            OcspResponse::parse(&responder.respond(&hash_data, now).unwrap(), &issuers).unwrap();
+        assert_eq!(response.responses.len(), 2);
+        assert_eq!(
+            response.status(&hash_data[0]).unwrap().status,
+            CertificateStatus::Good
+        );
+        assert_eq!(
+            response.authorize_status(&hash_data[..1], now),
+            AuthorizeCertificateStatusEnum::Accepted
+        );
+        assert_eq!(
+            response.authorize_status(&hash_data[..1], now + TimeDelta::days(2)),
+            AuthorizeCertificateStatusEnum::CertChainError
+        );
+        // A status from the future.
+        assert_eq!(
+            response.authorize_status(&hash_data[..1], now - TimeDelta::minutes(5)),
+            AuthorizeCertificateStatusEnum::CertChainError
+        );
+
+        // The sub-CA, trusted to sign the statuses of its certificates, does
+        // not tell the status of a certificate issued by the root, i.e.
+        // itself.
+        assert_eq!(response.status(&hash_data[1]), None);
+        assert_eq!(
+            response.authorize_status(&hash_data, now),
+            AuthorizeCertificateStatusEnum::CertChainError
+        );
+        assert_eq!(
+            response.authorize_status(&[], now),
+            AuthorizeCertificateStatusEnum::CertChainError
+        );
+
+        // The root does not sign for the sub-CA.
+        assert_eq!(
+            OcspResponse::parse(&responder.respond(&hash_data, now).unwrap(), &root.pem())
+                .unwrap_err(),
+            Error::InvalidOcspSignature
+        );
+
+        // The contract is revoked.
+        let revoked_at = "2013-02-01T20:00:00Z".parse().unwrap();
+        responder.revoke(hash_data[0].clone(), revoked_at);
+
+        let response = responder.get_certificate_status(
+            &GetCertificateStatusRequest::builder()
+                .ocsp_request_data(hash_data[0].clone())
+                .build(),
+            now,
+        );
+        assert_eq!(response.status, GetCertificateStatusEnum::Accepted);
+
+        let response = OcspResponse::from_base64(&response.ocsp_result.unwrap(), &issuers).unwrap();
+        assert_eq!(
+            response.status(&hash_data[0]).unwrap().status,
+            CertificateStatus::Revoked { revoked_at }
+        );
+        assert_eq!(
+            response.authorize_status(&hash_data, now),
+            AuthorizeCertificateStatusEnum::CertificateRevoked
+        );
+    }
+
+    #[test]
+    fn test_delegated_responder() {
+        let now = Utc::now();
+        let (root, sub_ca, sub_ca_key, contract) = contract_chain();
+        let hash_data = ocsp_request_data(
+            &format!("{}{}", contract.pem(), sub_ca.pem()),
+            HashAlgorithmEnum::SHA256,
+        )
+        .unwrap();
+        let delegated = |ocsp_signing: bool| {
+            let mut params = rcgen::CertificateParams::default();
+            params
+                .distinguished_name
+                .push(rcgen::DnType::CommonName, "OCSP Responder");
+            if ocsp_signing {
+                params.extended_key_usages = vec![rcgen::ExtendedKeyUsagePurpose::OcspSigning];
+            }
+            let key = rcgen::KeyPair::generate().unwrap();
+            let certificate = params.signed_by(&key, &sub_ca, &sub_ca_key).unwrap();
+
+            OcspResponder::new(&certificate.pem(), &key.serialize_pem()).unwrap()
+        };
+
+        let response = delegated(true).respond(&hash_data, now).unwrap();
+        assert_eq!(
+            OcspResponse::parse(&response, &sub_ca.pem())
+                .unwrap()
+                .authorize_status(&hash_data, now),
+            AuthorizeCertificateStatusEnum::Accepted
+        );
+        assert_eq!(
+            OcspResponse::parse(&response, &root.pem()).unwrap_err(),
+            Error::InvalidOcspSignature
+        );
+
+        // Without the `id-kp-OCSPSigning` key purpose.
+        let response = delegated(false).respond(&hash_data, now).unwrap();
+        assert_eq!(
+            OcspResponse::parse(&response, &sub_ca.pem()).unwrap_err(),
+            Error::InvalidOcspSignature
+        );
+
+        // The responder of the sub-CA does not tell the status of the
+        // certificates of another trusted sub-CA.
+        let (_, other_sub_ca, _, other_contract) = contract_chain();
+        let other_hash_data = ocsp_request_data(
+            &format!("{}{}", other_contract.pem(), other_sub_ca.pem()),
+            HashAlgorithmEnum::SHA256,
+        )
+        .unwrap();
+        let response = OcspResponse::parse(
+            &delegated(true).respond(&other_hash_data, now).unwrap(),
+            &format!("{}{}", sub_ca.pem(), other_sub_ca.pem()),
+        )
+        .unwrap();
+        assert_eq!(response.responses.len(), 1);
+        assert_eq!(response.status(&other_hash_data[0]), None);
+        assert_eq!(
+            response.authorize_status(&other_hash_data, now),
+            AuthorizeCertificateStatusEnum::CertChainError
+        );
+    }
+}
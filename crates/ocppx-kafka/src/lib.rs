@@ -0,0 +1,50 @@
+//! Send the events of the Charge Points to Kafka, for the fleet analytics
+//! pipelines.
+//!
+//! [`KafkaSink`] is an [`EventSink`][ocppx_server::EventSink]: given to an
+//! [`EventSinkLayer`][ocppx_server::EventSinkLayer], it produces the
+//! accepted boots, the status changes, the transactions and the meter
+//! values of the Charge Points as JSON records, keyed by the identity of
+//! the Charge Point:
+//!
+//! ```rust,ignore
+//! let sink = KafkaSink::connect(KafkaOptions::new("localhost:9092", "ocpp-events")).await?;
+//! let server = Server::new(Middleware::new(handler).layer(EventSinkLayer::new(Arc::new(sink))));
+//! ```
+//!
+//! The events are queued and produced by a background task: the `Call`s do
+//! not wait for Kafka. The producer speaks just enough of the Kafka protocol
+//! for this: it reads the partitions of the topic, again when their leader
+//! moves, and produces uncompressed, non-idempotent records.
+
+mod protocol;
+mod sink;
+
+pub use sink::{KafkaOptions, KafkaSink};
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("I/O error")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON error")]
+    Json(#[from] serde_json::Error),
+
+    #[error("malformed Kafka response")]
+    MalformedResponse,
+
+    #[error("the broker responded with the error code {0}")]
+    Broker(i16),
+
+    #[error("the topic `{0}` does not exist")]
+    UnknownTopic(String),
+
+    #[error("the broker {0} is not in the metadata")]
+    UnknownBroker(i32),
+
+    #[error("the queue of the events to produce is full")]
+    QueueFull,
+}
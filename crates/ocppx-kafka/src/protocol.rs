@@ -0,0 +1,418 @@
+//! The few messages of the Kafka protocol needed to produce records:
+//! `Metadata` v1 and `Produce` v3, with the record batches of the message
+//! format v2.
+
+use crate::{Error, Result};
+
+pub(crate) const PRODUCE: i16 = 0;
+pub(crate) const METADATA: i16 = 3;
+
+/// A record to produce.
+pub(crate) struct Record<'a> {
+    pub(crate) key: &'a [u8],
+    pub(crate) value: &'a [u8],
+    /// Milliseconds since the Unix epoch.
+    pub(crate) timestamp: i64,
+}
+
+/// The partitions of a topic, with their leader, and the address of the
+/// brokers.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Metadata {
+    pub(crate) brokers: Vec<(i32, String)>,
+    /// The leader of each partition, by partition index.
+    pub(crate) leaders: Vec<i32>,
+}
+
+/// A request, prefixed by its size.
+pub(crate) fn request(
+    api_key: i16,
+    api_version: i16,
+    correlation_id: i32,
+    client_id: &str,
+    body: &[u8],
+) -> Vec<u8> {
+    let mut request = Vec::new();
+    request.extend_from_slice(&api_key.to_be_bytes());
+    request.extend_from_slice(&api_version.to_be_bytes());
+    request.extend_from_slice(&correlation_id.to_be_bytes());
+    write_string(&mut request, client_id);
+    request.extend_from_slice(body);
+
+    let mut framed = (request.len() as i32).to_be_bytes().to_vec();
+    framed.extend_from_slice(&request);
+
+    framed
+}
+
+pub(crate) fn metadata_request(topic: &str) -> Vec<u8> {
+    let mut body = 1i32.to_be_bytes().to_vec();
+    write_string(&mut body, topic);
+
+    body
+}
+
+/// Decode a `Metadata` v1 response, without its correlation ID.
+pub(crate) fn metadata_response(body: &[u8], topic: &str) -> Result<Metadata> {
+    let mut reader = Reader(body);
+    let mut brokers = Vec::new();
+
+    for _ in 0..reader.i32()? {
+        let node_id = reader.i32()?;
+        let host = reader.string()?;
+        let port = reader.i32()?;
+        // The rack.
+        reader.nullable_string()?;
+
+        brokers.push((node_id, format!("{host}:{port}")));
+    }
+
+    // The controller.
+    reader.i32()?;
+
+    for _ in 0..reader.i32()? {
+        let error_code = reader.i16()?;
+        let name = reader.string()?;
+        // Whether the topic is internal.
+        reader.bytes(1)?;
+
+        let mut leaders = Vec::new();
+
+        for _ in 0..reader.i32()? {
+            reader.i16()?;
+            let index = reader.i32()?;
+            let leader = reader.i32()?;
+
+            // The replicas, and the in-sync replicas.
+            for _ in 0..2 {
+                let count = reader.i32()?;
+                reader.bytes(4 * usize::try_from(count).map_err(|_| Error::MalformedResponse)?)?;
+            }
+
+            leaders.push((index, leader));
+        }
+
+        if name != topic {
+            continue;
+        }
+
+        if error_code != 0 {
+            return Err(Error::Broker(error_code));
+        }
+
+        leaders.sort();
+
+        return Ok(Metadata {
+            brokers,
+            leaders: leaders.into_iter().map(|(_, leader)| leader).collect(),
+        });
+    }
+
+    Err(Error::UnknownTopic(topic.to_owned()))
+}
+
+/// A `Produce` v3 request of `records` to one partition.
+pub(crate) fn produce_request(
+    topic: &str,
+    partition: i32,
+    acks: i16,
+    timeout_ms: i32,
+    records: &[Record],
+) -> Vec<u8> {
+    let batch = record_batch(records);
+
+    // No transactional ID.
+    let mut body = (-1i16).to_be_bytes().to_vec();
+    body.extend_from_slice(&acks.to_be_bytes());
+    body.extend_from_slice(&timeout_ms.to_be_bytes());
+    body.extend_from_slice(&1i32.to_be_bytes());
+    write_string(&mut body, topic);
+    body.extend_from_slice(&1i32.to_be_bytes());
+    body.extend_from_slice(&partition.to_be_bytes());
+    body.extend_from_slice(&(batch.len() as i32).to_be_bytes());
+    body.extend_from_slice(&batch);
+
+    body
+}
+
+/// Decode a `Produce` v3 response, without its correlation ID, and fail
+/// with the first error code of its partitions.
+pub(crate) fn produce_response(body: &[u8]) -> Result<()> {
+    let mut reader = Reader(body);
+
+    for _ in 0..reader.i32()? {
+        reader.string()?;
+
+        for _ in 0..reader.i32()? {
+            reader.i32()?;
+            let error_code = reader.i16()?;
+            // The base offset, and the log append time.
+            reader.bytes(16)?;
+
+            if error_code != 0 {
+                return Err(Error::Broker(error_code));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A record batch of the message format v2, uncompressed.
+pub(crate) fn record_batch(records: &[Record]) -> Vec<u8> {
+    let base_timestamp = records.iter().map(|record| record.timestamp).min();
+    let max_timestamp = records.iter().map(|record| record.timestamp).max();
+    let base_timestamp = base_timestamp.unwrap_or_default();
+
+    // From the attributes to the end: the part covered by the CRC.
+    let mut tail = Vec::new();
+    tail.extend_from_slice(&0i16.to_be_bytes());
+    tail.extend_from_slice(&(records.len() as i32 - 1).to_be_bytes());
+    tail.extend_from_slice(&base_timestamp.to_be_bytes());
+    tail.extend_from_slice(&max_timestamp.unwrap_or_default().to_be_bytes());
+    // No producer ID, epoch and base sequence: not idempotent.
+    tail.extend_from_slice(&(-1i64).to_be_bytes());
+    tail.extend_from_slice(&(-1i16).to_be_bytes());
+    tail.extend_from_slice(&(-1i32).to_be_bytes());
+    tail.extend_from_slice(&(records.len() as i32).to_be_bytes());
+
+    for (offset_delta, record) in records.iter().enumerate() {
+        let mut encoded = vec![0];
+        write_varint(&mut encoded, record.timestamp - base_timestamp);
+        write_varint(&mut encoded, offset_delta as i64);
+        write_varint(&mut encoded, record.key.len() as i64);
+        encoded.extend_from_slice(record.key);
+        write_varint(&mut encoded, record.value.len() as i64);
+        encoded.extend_from_slice(record.value);
+        // No headers.
+        write_varint(&mut encoded, 0);
+
+        write_varint(&mut tail, encoded.len() as i64);
+        tail.extend_from_slice(&encoded);
+    }
+
+    let mut batch = Vec::new();
+    // The base offset, assigned by the broker.
+    batch.extend_from_slice(&0i64.to_be_bytes());
+    // The length of what follows: the leader epoch, the magic byte, the
+    // CRC, and the tail.
+    batch.extend_from_slice(&(4 + 1 + 4 + tail.len() as i32).to_be_bytes());
+    batch.extend_from_slice(&(-1i32).to_be_bytes());
+    batch.push(2);
+    batch.extend_from_slice(&crc32c(&tail).to_be_bytes());
+    batch.extend_from_slice(&tail);
+
+    batch
+}
+
+/// The partition of `key` among `partitions`, as chosen by the default
+/// partitioner of the Java producer, so that both send the records of a
+/// Charge Point to the same partition.
+pub(crate) fn partition(key: &[u8], partitions: usize) -> usize {
+    (murmur2(key) & 0x7fff_ffff) as usize % partitions
+}
+
+/// The MurmurHash2 of the Java producer.
+fn murmur2(data: &[u8]) -> u32 {
+    const SEED: u32 = 0x9747_b28c;
+    const M: u32 = 0x5bd1_e995;
+
+    let mut hash = SEED ^ data.len() as u32;
+    let mut chunks = data.chunks_exact(4);
+
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(M);
+        k ^= k >> 24;
+        k = k.wrapping_mul(M);
+
+        hash = hash.wrapping_mul(M) ^ k;
+    }
+
+    let rest = chunks.remainder();
+
+    if !rest.is_empty() {
+        for (index, byte) in rest.iter().enumerate().rev() {
+            hash ^= u32::from(*byte) << (8 * index);
+        }
+
+        hash = hash.wrapping_mul(M);
+    }
+
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(M);
+    hash ^= hash >> 15;
+
+    hash
+}
+
+/// The CRC-32C (Castagnoli) of the record batches.
+fn crc32c(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for byte in data {
+        crc ^= u32::from(*byte);
+
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0x82f6_3b78
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+fn write_string(buffer: &mut Vec<u8>, string: &str) {
+    buffer.extend_from_slice(&(string.len() as i16).to_be_bytes());
+    buffer.extend_from_slice(string.as_bytes());
+}
+
+/// A zig-zag variable-length integer.
+fn write_varint(buffer: &mut Vec<u8>, value: i64) {
+    let mut value = ((value << 1) ^ (value >> 63)) as u64;
+
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+
+    buffer.push(value as u8);
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8]> {
+        if self.0.len() < length {
+            return Err(Error::MalformedResponse);
+        }
+
+        let (bytes, rest) = self.0.split_at(length);
+        self.0 = rest;
+
+        Ok(bytes)
+    }
+
+    fn i16(&mut self) -> Result<i16> {
+        let bytes = self.bytes(2)?;
+
+        Ok(i16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        let bytes = self.bytes(4)?;
+
+        Ok(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn nullable_string(&mut self) -> Result<Option<String>> {
+        let length = self.i16()?;
+
+        if length < 0 {
+            return Ok(None);
+        }
+
+        String::from_utf8(self.bytes(length as usize)?.to_vec())
+            .map(Some)
+            .map_err(|_| Error::MalformedResponse)
+    }
+
+    fn string(&mut self) -> Result<String> {
+        self.nullable_string()?.ok_or(Error::MalformedResponse)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A `Metadata` v1 response body of `topic`, with a single broker at
+    /// `host:port` leading its `partitions`.
+    pub(crate) fn metadata(topic: &str, host: &str, port: u16, partitions: i32) -> Vec<u8> {
+        let mut body = 1i32.to_be_bytes().to_vec();
+        body.extend_from_slice(&7i32.to_be_bytes());
+        write_string(&mut body, host);
+        body.extend_from_slice(&i32::from(port).to_be_bytes());
+        body.extend_from_slice(&(-1i16).to_be_bytes());
+        body.extend_from_slice(&7i32.to_be_bytes());
+        body.extend_from_slice(&1i32.to_be_bytes());
+        body.extend_from_slice(&0i16.to_be_bytes());
+        write_string(&mut body, topic);
+        body.push(0);
+        body.extend_from_slice(&partitions.to_be_bytes());
+
+        for index in (0..partitions).rev() {
+            body.extend_from_slice(&0i16.to_be_bytes());
+            body.extend_from_slice(&index.to_be_bytes());
+            body.extend_from_slice(&7i32.to_be_bytes());
+            body.extend_from_slice(&1i32.to_be_bytes());
+            body.extend_from_slice(&7i32.to_be_bytes());
+            body.extend_from_slice(&0i32.to_be_bytes());
+        }
+
+        body
+    }
+
+    #[test]
+    fn test_hashes() {
+        assert_eq!(crc32c(b"123456789"), 0xe306_9283);
+
+        // The test vectors of the Java producer.
+        for (key, hash) in [
+            ("21", -973932308),
+            ("foobar", -790332482),
+            ("a-little-bit-long-string", -985981536),
+            ("a-little-bit-longer-string", -1486304829),
+            (
+                "lkjh234lh9fiuh90y23oiuhsafujhadof229phr9h19h89h8",
+                -58897971,
+            ),
+            ("abc", 479470107),
+        ] {
+            assert_eq!(murmur2(key.as_bytes()) as i32, hash, "{key}");
+        }
+    }
+
+    #[test]
+    fn test_messages() {
+        assert_eq!(
+            metadata_response(&metadata("ocpp", "localhost", 9092, 3), "ocpp").unwrap(),
+            Metadata {
+                brokers: vec![(7, "localhost:9092".to_owned())],
+                leaders: vec![7, 7, 7],
+            }
+        );
+        assert!(matches!(
+            metadata_response(&metadata("ocpp", "localhost", 9092, 3), "other"),
+            Err(Error::UnknownTopic(_))
+        ));
+
+        let batch = record_batch(&[
+            Record {
+                key: b"CP001",
+                value: b"{}",
+                timestamp: 1000,
+            },
+            Record {
+                key: b"CP001",
+                value: b"[]",
+                timestamp: 1500,
+            },
+        ]);
+        let length = i32::from_be_bytes(batch[8..12].try_into().unwrap());
+        assert_eq!(length as usize, batch.len() - 12);
+        assert_eq!(batch[16], 2);
+        assert_eq!(
+            u32::from_be_bytes(batch[17..21].try_into().unwrap()),
+            crc32c(&batch[21..])
+        );
+        // The second record: its timestamp and offset deltas, its key and
+        // its value.
+        assert!(batch.ends_with(&[
+            0x1c, 0, 0xe8, 0x07, 2, 10, b'C', b'P', b'0', b'0', b'1', 4, b'[', b']', 0
+        ]));
+    }
+}
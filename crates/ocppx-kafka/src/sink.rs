@@ -0,0 +1,428 @@
+use crate::{
+    protocol::{self, Metadata, Record},
+    Error, Result,
+};
+use ocppx_server::EventSink;
+use ocppx_store::Entry;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    sync::{mpsc, Mutex},
+};
+
+/// The error codes telling that the leader of a partition has moved:
+/// `LEADER_NOT_AVAILABLE` and `NOT_LEADER_OR_FOLLOWER`.
+const LEADER_ERRORS: [i16; 2] = [5, 6];
+
+/// Options of a [`KafkaSink`].
+#[derive(Debug, Clone)]
+pub struct KafkaOptions {
+    bootstrap_server: String,
+    topic: String,
+    client_id: String,
+    acks: i16,
+    timeout: Duration,
+    queue_capacity: usize,
+    retries: u32,
+}
+
+impl KafkaOptions {
+    /// Produce to `topic`, with the cluster of `bootstrap_server`, e.g.
+    /// `localhost:9092`.
+    pub fn new(bootstrap_server: impl Into<String>, topic: impl Into<String>) -> Self {
+        Self {
+            bootstrap_server: bootstrap_server.into(),
+            topic: topic.into(),
+            client_id: "ocppx".to_owned(),
+            acks: 1,
+            timeout: Duration::from_secs(30),
+            queue_capacity: 1024,
+            retries: 3,
+        }
+    }
+
+    pub fn client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = client_id.into();
+        self
+    }
+
+    /// The acknowledgements to wait for: `0` for none, `1` for the leader
+    /// (the default), or `-1` for all the in-sync replicas.
+    pub fn acks(mut self, acks: i16) -> Self {
+        self.acks = acks;
+        self
+    }
+
+    /// The time given to the brokers to acknowledge a record. Defaults to
+    /// 30 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The number of events waiting to be produced, beyond which the new
+    /// ones are dropped. Defaults to 1024.
+    pub fn queue_capacity(mut self, queue_capacity: usize) -> Self {
+        self.queue_capacity = queue_capacity.max(1);
+        self
+    }
+
+    /// The number of times a record is produced again after its leader has
+    /// moved, with refreshed metadata. Defaults to 3.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+}
+
+/// An [`EventSink`] producing the events to a Kafka topic, as JSON records
+/// keyed by the identity of the Charge Point: all the events of a Charge
+/// Point land in the same partition, in order.
+///
+/// The events are queued, and produced one at a time by a background task,
+/// so that the `Call`s of the Charge Points never wait for the brokers; the
+/// events are dropped when the queue is full. The partitions and their
+/// leaders are read on [`KafkaSink::connect`], and read again when a leader
+/// has moved. The records are produced without compression.
+#[derive(Debug)]
+pub struct KafkaSink {
+    producer: Arc<Producer>,
+    records: mpsc::Sender<QueuedRecord>,
+}
+
+/// A record waiting in the queue of a [`KafkaSink`].
+#[derive(Debug)]
+struct QueuedRecord {
+    key: Vec<u8>,
+    value: Vec<u8>,
+    timestamp: i64,
+}
+
+#[derive(Debug)]
+struct Producer {
+    options: KafkaOptions,
+    metadata: RwLock<Arc<Metadata>>,
+    /// A connection per leader, locked for a whole round trip.
+    connections: std::sync::Mutex<HashMap<i32, Arc<Mutex<Option<TcpStream>>>>>,
+    next_correlation_id: AtomicI32,
+}
+
+impl KafkaSink {
+    /// Read the partitions of the topic from the bootstrap server, and
+    /// start producing the events.
+    pub async fn connect(options: KafkaOptions) -> Result<Self> {
+        let metadata = fetch_metadata(&options).await?;
+        let (records, receiver) = mpsc::channel(options.queue_capacity);
+        let producer = Arc::new(Producer {
+            options,
+            metadata: RwLock::new(Arc::new(metadata)),
+            connections: std::sync::Mutex::new(HashMap::new()),
+            next_correlation_id: AtomicI32::new(1),
+        });
+
+        tokio::spawn(drain(producer.clone(), receiver));
+
+        Ok(Self { producer, records })
+    }
+
+    /// Produce `value`, keyed by `key`, right away, besides the queued
+    /// events.
+    pub async fn produce(&self, key: &[u8], value: &[u8], timestamp: i64) -> Result<()> {
+        self.producer.produce(key, value, timestamp).await
+    }
+}
+
+impl EventSink for KafkaSink {
+    type Error = Error;
+
+    async fn send(&self, entry: &Entry) -> Result<()> {
+        self.records
+            .try_send(QueuedRecord {
+                key: entry.charge_point_id.clone().into_bytes(),
+                value: serde_json::to_vec(entry)?,
+                timestamp: entry.timestamp.timestamp_millis(),
+            })
+            .map_err(|_| Error::QueueFull)
+    }
+}
+
+/// Produce the queued records, until the sink is dropped.
+async fn drain(producer: Arc<Producer>, mut records: mpsc::Receiver<QueuedRecord>) {
+    while let Some(record) = records.recv().await {
+        if let Err(error) = producer
+            .produce(&record.key, &record.value, record.timestamp)
+            .await
+        {
+            log::warn!(
+                topic = producer.options.topic.as_str(),
+                error:% = error;
+                "cannot produce the event to Kafka"
+            );
+        }
+    }
+}
+
+impl Producer {
+    /// Produce `value`, keyed by `key`, refreshing the metadata when its
+    /// leader has moved.
+    async fn produce(&self, key: &[u8], value: &[u8], timestamp: i64) -> Result<()> {
+        let mut retries = 0;
+
+        loop {
+            match self.produce_once(key, value, timestamp).await {
+                Err(Error::Broker(error_code))
+                    if LEADER_ERRORS.contains(&error_code) && retries < self.options.retries =>
+                {
+                    retries += 1;
+                    self.refresh_metadata().await?;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn refresh_metadata(&self) -> Result<()> {
+        let metadata = fetch_metadata(&self.options).await?;
+        *self.metadata.write().unwrap() = Arc::new(metadata);
+
+        // The connections to the former leaders are not needed anymore.
+        self.connections.lock().unwrap().clear();
+
+        Ok(())
+    }
+
+    async fn produce_once(&self, key: &[u8], value: &[u8], timestamp: i64) -> Result<()> {
+        let metadata = self.metadata.read().unwrap().clone();
+        let partition = protocol::partition(key, metadata.leaders.len());
+        let leader = metadata.leaders[partition];
+        let correlation_id = self.next_correlation_id.fetch_add(1, Ordering::Relaxed);
+        let request = protocol::request(
+            protocol::PRODUCE,
+            3,
+            correlation_id,
+            &self.options.client_id,
+            &protocol::produce_request(
+                &self.options.topic,
+                partition as i32,
+                self.options.acks,
+                self.options
+                    .timeout
+                    .as_millis()
+                    .try_into()
+                    .unwrap_or(i32::MAX),
+                &[Record {
+                    key,
+                    value,
+                    timestamp,
+                }],
+            ),
+        );
+
+        let connection = self
+            .connections
+            .lock()
+            .unwrap()
+            .entry(leader)
+            .or_default()
+            .clone();
+        let mut connection = connection.lock().await;
+
+        if connection.is_none() {
+            let (_, address) = metadata
+                .brokers
+                .iter()
+                .find(|(node_id, _)| *node_id == leader)
+                .ok_or(Error::UnknownBroker(leader))?;
+
+            *connection = Some(TcpStream::connect(address).await?);
+        }
+
+        let stream = connection.as_mut().unwrap();
+
+        // Without acknowledgements, the broker does not respond.
+        if self.options.acks == 0 {
+            let result = stream.write_all(&request).await;
+
+            if result.is_err() {
+                *connection = None;
+            }
+
+            return Ok(result?);
+        }
+
+        match round_trip(stream, request, correlation_id).await {
+            Ok(body) => protocol::produce_response(&body),
+            Err(error) => {
+                // Reconnect on the next record.
+                *connection = None;
+
+                Err(error)
+            }
+        }
+    }
+}
+
+/// Read the partitions of the topic, and their leader, from the bootstrap
+/// server.
+async fn fetch_metadata(options: &KafkaOptions) -> Result<Metadata> {
+    let mut stream = TcpStream::connect(&options.bootstrap_server).await?;
+    let body = round_trip(
+        &mut stream,
+        protocol::request(
+            protocol::METADATA,
+            1,
+            0,
+            &options.client_id,
+            &protocol::metadata_request(&options.topic),
+        ),
+        0,
+    )
+    .await?;
+    let metadata = protocol::metadata_response(&body, &options.topic)?;
+
+    if metadata.leaders.is_empty() {
+        return Err(Error::UnknownTopic(options.topic.clone()));
+    }
+
+    Ok(metadata)
+}
+
+/// Send `request`, and read the body of its response.
+async fn round_trip(
+    stream: &mut TcpStream,
+    request: Vec<u8>,
+    correlation_id: i32,
+) -> Result<Vec<u8>> {
+    stream.write_all(&request).await?;
+
+    let length = stream.read_i32().await?;
+    let mut response = vec![0; usize::try_from(length).map_err(|_| Error::MalformedResponse)?];
+    stream.read_exact(&mut response).await?;
+
+    if response.len() < 4 || response[..4] != correlation_id.to_be_bytes() {
+        return Err(Error::MalformedResponse);
+    }
+
+    response.drain(..4);
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::tests::metadata;
+    use ocppx_store::Event;
+    use ocppx_types::v1_6::StopTransactionRequest;
+    use tokio::net::TcpListener;
+
+    /// Read a request, and return its API key and its correlation ID.
+    async fn read_request(stream: &mut TcpStream) -> (i16, i32, Vec<u8>) {
+        let length = stream.read_i32().await.unwrap();
+        let mut request = vec![0; length as usize];
+        stream.read_exact(&mut request).await.unwrap();
+
+        (
+            i16::from_be_bytes([request[0], request[1]]),
+            i32::from_be_bytes([request[4], request[5], request[6], request[7]]),
+            request,
+        )
+    }
+
+    async fn respond(stream: &mut TcpStream, correlation_id: i32, body: &[u8]) {
+        stream
+            .write_all(&(body.len() as i32 + 4).to_be_bytes())
+            .await
+            .unwrap();
+        stream
+            .write_all(&correlation_id.to_be_bytes())
+            .await
+            .unwrap();
+        stream.write_all(body).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_kafka_sink() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let broker = tokio::spawn(async move {
+            let metadata = |stream: TcpStream| async move {
+                let mut stream = stream;
+                let (api_key, correlation_id, _) = read_request(&mut stream).await;
+                assert_eq!(api_key, protocol::METADATA);
+                respond(
+                    &mut stream,
+                    correlation_id,
+                    &metadata("ocpp-events", "127.0.0.1", port, 4),
+                )
+                .await;
+            };
+            let produce = |error_code: i16| {
+                let mut body = 1i32.to_be_bytes().to_vec();
+                body.extend_from_slice(&11i16.to_be_bytes());
+                body.extend_from_slice(b"ocpp-events");
+                body.extend_from_slice(&1i32.to_be_bytes());
+                body.extend_from_slice(&0i32.to_be_bytes());
+                body.extend_from_slice(&error_code.to_be_bytes());
+                body.extend_from_slice(&[0; 16]);
+                body.extend_from_slice(&0i32.to_be_bytes());
+
+                body
+            };
+
+            metadata(listener.accept().await.unwrap().0).await;
+
+            // `NOT_LEADER_OR_FOLLOWER`: the metadata are read again, and the
+            // record is produced to the new leader.
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let (api_key, correlation_id, _) = read_request(&mut stream).await;
+            assert_eq!(api_key, protocol::PRODUCE);
+            respond(&mut stream, correlation_id, &produce(6)).await;
+
+            metadata(listener.accept().await.unwrap().0).await;
+
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let (api_key, correlation_id, request) = read_request(&mut stream).await;
+            assert_eq!(api_key, protocol::PRODUCE);
+            respond(&mut stream, correlation_id, &produce(0)).await;
+
+            request
+        });
+
+        let sink = KafkaSink::connect(KafkaOptions::new(
+            format!("127.0.0.1:{port}"),
+            "ocpp-events",
+        ))
+        .await
+        .unwrap();
+        let entry = Entry::new(
+            "CP001",
+            Event::TransactionStopped(
+                StopTransactionRequest::builder()
+                    .meter_stop(1000)
+                    .timestamp(chrono::Utc::now())
+                    .transaction_id(1)
+                    .build(),
+            ),
+        );
+
+        // Queued, without waiting for the broker.
+        sink.send(&entry).await.unwrap();
+
+        let request = broker.await.unwrap();
+        // The key of the record, then its value, without headers.
+        assert!(request.windows(6).any(|window| window == b"\x0aCP001"));
+        let mut record = serde_json::to_vec(&entry).unwrap();
+        record.push(0);
+        assert!(request.ends_with(&record));
+    }
+}
@@ -0,0 +1,512 @@
+use crate::{Error, MqttClient, Result};
+use ocppx_rpc::{Call, CallError, CallResult, ConnectionEvent};
+use ocppx_server::{AuthProvider, CsmsHandler, Layer, Server, Service, SessionEvent};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+
+/// A topic template, where `{charge_point_id}` and `{action}` are replaced
+/// by the identity of a Charge Point and by an OCPP action, e.g.
+/// `ocpp/{charge_point_id}/{action}`.
+///
+/// The placeholders must span whole topic levels. The `%`, `/`, `+` and
+/// `#` characters of their values, which have a meaning in the topics, are
+/// percent-encoded, e.g. the Charge Point `CP/1` gives `ocpp/CP%2F1/...`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Topic(String);
+
+impl Topic {
+    pub fn new(template: impl Into<String>) -> Self {
+        Self(template.into())
+    }
+
+    /// The topic of the Charge Point `charge_point_id` and of `action`.
+    pub fn format(&self, charge_point_id: &str, action: &str) -> String {
+        self.0
+            .replace("{charge_point_id}", &escape(charge_point_id))
+            .replace("{action}", &escape(action))
+    }
+
+    /// The filter matching the topics of all the Charge Points and actions:
+    /// the placeholders are replaced by the `+` wildcard.
+    pub fn filter(&self) -> String {
+        self.0
+            .split('/')
+            .map(|level| if is_placeholder(level) { "+" } else { level })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// The Charge Point identity and the action of `topic`, if it matches
+    /// the template. A placeholder missing from the template gives an empty
+    /// string.
+    pub fn parse(&self, topic: &str) -> Option<(String, String)> {
+        let mut charge_point_id = String::new();
+        let mut action = String::new();
+        let mut levels = topic.split('/');
+
+        for template_level in self.0.split('/') {
+            let level = levels.next()?;
+
+            match template_level {
+                "{charge_point_id}" => charge_point_id = unescape(level)?,
+                "{action}" => action = unescape(level)?,
+                _ if template_level == level => {}
+                _ => return None,
+            }
+        }
+
+        if levels.next().is_some() {
+            return None;
+        }
+
+        Some((charge_point_id, action))
+    }
+}
+
+fn is_placeholder(level: &str) -> bool {
+    matches!(level, "{charge_point_id}" | "{action}")
+}
+
+/// Percent-encode the characters of `value` which have a meaning in the
+/// topics.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for character in value.chars() {
+        match character {
+            '%' => escaped.push_str("%25"),
+            '/' => escaped.push_str("%2F"),
+            '+' => escaped.push_str("%2B"),
+            '#' => escaped.push_str("%23"),
+            _ => escaped.push(character),
+        }
+    }
+
+    escaped
+}
+
+/// Decode a topic level encoded by [`escape`]. `None` if it is malformed.
+fn unescape(level: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(level.len());
+    let mut parts = level.split('%');
+
+    unescaped.push_str(parts.next()?);
+
+    for part in parts {
+        let character = match part.get(..2)? {
+            "25" => '%',
+            "2F" | "2f" => '/',
+            "2B" | "2b" => '+',
+            "23" => '#',
+            _ => return None,
+        };
+
+        unescaped.push(character);
+        unescaped.push_str(&part[2..]);
+    }
+
+    Some(unescaped)
+}
+
+/// The topics of an [`MqttBridge`].
+#[derive(Debug, Clone)]
+pub struct Topics {
+    /// Where the `Call`s sent by the Charge Points are published, with
+    /// their responses.
+    pub calls: Topic,
+    /// Where the connection events of the Charge Points are published.
+    pub events: Topic,
+    /// Where the commands to the Charge Points are received: the payload
+    /// of the `Call` to send.
+    pub commands: Topic,
+    /// Where the responses of the Charge Points to the commands are
+    /// published.
+    pub responses: Topic,
+}
+
+impl Default for Topics {
+    fn default() -> Self {
+        Self {
+            calls: Topic::new("ocpp/{charge_point_id}/{action}"),
+            events: Topic::new("ocpp/{charge_point_id}/connection"),
+            commands: Topic::new("ocpp/{charge_point_id}/command/{action}"),
+            responses: Topic::new("ocpp/{charge_point_id}/command/{action}/response"),
+        }
+    }
+}
+
+/// A bridge between a [`Server`] and an MQTT broker:
+///
+/// * the `Call`s sent by the Charge Points are published on
+///   [`Topics::calls`] with their responses, by the layer of
+///   [`Self::layer`],
+/// * the connection events are published on [`Topics::events`],
+/// * the payloads published on [`Topics::commands`] are sent as `Call`s to
+///   the Charge Points, and their responses are published on
+///   [`Topics::responses`].
+///
+/// ```rust,ignore
+/// let client = MqttClient::connect(MqttOptions::new("localhost", 1883, "ocppx")).await?;
+/// let bridge = MqttBridge::new(client, Topics::default());
+/// let server = Server::new(Middleware::new(handler).layer(bridge.layer()));
+///
+/// tokio::spawn(bridge.clone().run(server.clone()));
+/// ```
+#[derive(Clone)]
+pub struct MqttBridge {
+    client: Arc<MqttClient>,
+    topics: Arc<Topics>,
+}
+
+impl MqttBridge {
+    pub fn new(client: MqttClient, topics: Topics) -> Self {
+        Self {
+            client: Arc::new(client),
+            topics: Arc::new(topics),
+        }
+    }
+
+    /// The layer publishing the `Call`s sent by the Charge Points.
+    pub fn layer(&self) -> MqttLayer {
+        MqttLayer {
+            bridge: self.clone(),
+        }
+    }
+
+    /// Publish the connection events of `server`, and forward the commands
+    /// to its Charge Points, until the connection to the broker is closed.
+    pub async fn run<H, A>(self, server: Server<H, A>) -> Result<()>
+    where
+        H: CsmsHandler,
+        A: AuthProvider,
+    {
+        let mut session_events = server.sessions().subscribe();
+        let mut connection_events = server.connection_events();
+
+        self.client.subscribe(self.topics.commands.filter()).await?;
+
+        loop {
+            tokio::select! {
+                message = self.client.next_message() => {
+                    let message = message.ok_or(Error::ConnectionClosed)?;
+
+                    match self.topics.commands.parse(&message.topic) {
+                        Some((charge_point_id, action)) => {
+                            tokio::spawn(self.clone().command(
+                                server.clone(),
+                                charge_point_id,
+                                action,
+                                message.payload,
+                            ));
+                        }
+                        None => log::debug!(topic:% = message.topic; "ignored an MQTT message"),
+                    }
+                }
+                event = session_events.recv() => match event {
+                    Ok(event) => self.publish_session_event(event).await?,
+                    Err(RecvError::Lagged(missed)) => log::warn!(missed; "MQTT bridge missed session events"),
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+                event = connection_events.recv() => match event {
+                    Ok(ConnectionEvent::Stale { charge_point_id, silence }) => {
+                        self.publish_event(
+                            &charge_point_id,
+                            json!({ "event": "stale", "silence": silence.as_secs_f64() }),
+                        )
+                        .await?
+                    }
+                    Err(RecvError::Lagged(missed)) => log::warn!(missed; "MQTT bridge missed connection events"),
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+            }
+        }
+    }
+
+    async fn publish_session_event(&self, event: SessionEvent) -> Result<()> {
+        let (charge_point_id, payload) = match event {
+            SessionEvent::Opened {
+                charge_point_id,
+                session_id,
+            } => (
+                charge_point_id,
+                json!({ "event": "opened", "sessionId": session_id }),
+            ),
+            SessionEvent::Superseded {
+                charge_point_id,
+                session_id,
+                by,
+            } => (
+                charge_point_id,
+                json!({ "event": "superseded", "sessionId": session_id, "by": by }),
+            ),
+            SessionEvent::Closed {
+                charge_point_id,
+                session_id,
+            } => (
+                charge_point_id,
+                json!({ "event": "closed", "sessionId": session_id }),
+            ),
+        };
+
+        self.publish_event(&charge_point_id, payload).await
+    }
+
+    async fn publish_event(&self, charge_point_id: &str, payload: Value) -> Result<()> {
+        self.client
+            .publish(
+                self.topics.events.format(charge_point_id, ""),
+                payload.to_string(),
+            )
+            .await
+    }
+
+    /// Send the command `payload` to the Charge Point, and publish its
+    /// response.
+    async fn command<H, A>(
+        self,
+        server: Server<H, A>,
+        charge_point_id: String,
+        action: String,
+        payload: Vec<u8>,
+    ) where
+        H: CsmsHandler,
+        A: AuthProvider,
+    {
+        let response = match serde_json::from_slice::<Value>(&payload) {
+            Ok(payload) => match server
+                .call::<Value, Value>(&charge_point_id, &action, &payload)
+                .await
+            {
+                Ok(response) => json!({ "response": response }),
+                Err(ocppx_server::Error::CallError(call_error)) => json!({
+                    "error": {
+                        "errorCode": call_error.error_code,
+                        "errorDescription": call_error.error_description,
+                        "errorDetails": call_error.error_details,
+                    }
+                }),
+                Err(error) => json!({ "error": { "description": error.to_string() } }),
+            },
+            Err(error) => json!({ "error": { "description": error.to_string() } }),
+        };
+
+        if let Err(error) = self
+            .client
+            .publish(
+                self.topics.responses.format(&charge_point_id, &action),
+                response.to_string(),
+            )
+            .await
+        {
+            log::warn!(charge_point_id:% = charge_point_id, error:% = error; "cannot publish a command response");
+        }
+    }
+}
+
+/// A [`Layer`] publishing the `Call`s sent by the Charge Points, with their
+/// responses, on [`Topics::calls`], see [`MqttBridge::layer`].
+///
+/// The message is published once the inner service has responded; a
+/// failure to publish it is logged, and does not change the response.
+pub struct MqttLayer {
+    bridge: MqttBridge,
+}
+
+impl<S> Layer<S> for MqttLayer
+where
+    S: Service,
+{
+    type Service = Publishing<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Publishing {
+            bridge: self.bridge.clone(),
+            inner,
+        }
+    }
+}
+
+/// The service created by [`MqttLayer`].
+pub struct Publishing<S> {
+    bridge: MqttBridge,
+    inner: S,
+}
+
+impl<S> Service for Publishing<S>
+where
+    S: Service,
+{
+    async fn call(
+        &self,
+        charge_point_id: &str,
+        call: Call,
+    ) -> std::result::Result<CallResult, CallError> {
+        let unique_id = call.unique_id.clone();
+        let action = call.action.clone();
+        let request = call.payload.clone();
+
+        let response = self.inner.call(charge_point_id, call).await;
+
+        let message = match &response {
+            Ok(call_result) => json!({
+                "uniqueId": unique_id,
+                "request": request,
+                "response": call_result.payload,
+            }),
+            Err(call_error) => json!({
+                "uniqueId": unique_id,
+                "request": request,
+                "error": {
+                    "errorCode": call_error.error_code,
+                    "errorDescription": call_error.error_description,
+                    "errorDetails": call_error.error_details,
+                },
+            }),
+        };
+
+        if let Err(error) = self
+            .bridge
+            .client
+            .publish(
+                self.bridge.topics.calls.format(charge_point_id, &action),
+                message.to_string(),
+            )
+            .await
+        {
+            log::warn!(charge_point_id, action:% = action, error:% = error; "cannot publish a call");
+        }
+
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{client::tests::broker, packet::Packet, MqttOptions};
+    use ocppx_client::ChargePointClient;
+    use ocppx_rpc::ErrorCode;
+    use ocppx_server::Middleware;
+    use tokio::net::TcpListener;
+
+    struct Handler;
+
+    impl CsmsHandler for Handler {
+        async fn handle_call(
+            &self,
+            _charge_point_id: &str,
+            call: Call,
+        ) -> std::result::Result<CallResult, CallError> {
+            Err(CallError::new(
+                call.unique_id,
+                ErrorCode::NotImplemented,
+                "",
+                None,
+            ))
+        }
+    }
+
+    #[test]
+    fn test_topic() {
+        let topic = Topic::new("ocpp/{charge_point_id}/command/{action}");
+
+        assert_eq!(topic.format("CP001", "Reset"), "ocpp/CP001/command/Reset");
+        assert_eq!(topic.filter(), "ocpp/+/command/+");
+        assert_eq!(
+            topic.parse("ocpp/CP001/command/Reset"),
+            Some(("CP001".to_owned(), "Reset".to_owned()))
+        );
+        assert_eq!(topic.parse("ocpp/CP001/Reset"), None);
+        assert_eq!(topic.parse("ocpp/CP001/command/Reset/response"), None);
+        assert_eq!(
+            Topic::new("ocpp/{charge_point_id}/connection").parse("ocpp/CP001/connection"),
+            Some(("CP001".to_owned(), String::new()))
+        );
+
+        // The values cannot add levels, nor wildcards.
+        let topic_name = topic.format("CP/+#%1", "Reset");
+        assert_eq!(topic_name, "ocpp/CP%2F%2B%23%251/command/Reset");
+        assert_eq!(
+            topic.parse(&topic_name),
+            Some(("CP/+#%1".to_owned(), "Reset".to_owned()))
+        );
+        assert_eq!(topic.parse("ocpp/CP%1/command/Reset"), None);
+    }
+
+    #[tokio::test]
+    async fn test_bridge() {
+        let (port, mut received, sender) = broker().await;
+        let client = MqttClient::connect(MqttOptions::new("127.0.0.1", port, "ocppx"))
+            .await
+            .unwrap();
+        assert!(matches!(
+            received.recv().await,
+            Some(Packet::Connect { .. })
+        ));
+
+        let bridge = MqttBridge::new(client, Topics::default());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::new(Middleware::new(Handler).layer(bridge.layer()));
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+        tokio::spawn(bridge.run(server));
+
+        assert_eq!(
+            received.recv().await,
+            Some(Packet::Subscribe {
+                packet_id: 1,
+                filters: vec!["ocpp/+/command/+".to_owned()],
+            })
+        );
+
+        let charge_point = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001")
+            .await
+            .unwrap();
+        let Some(Packet::Publish { topic, payload }) = received.recv().await else {
+            panic!("expected a publication");
+        };
+        assert_eq!(topic, "ocpp/CP001/connection");
+        assert_eq!(
+            serde_json::from_slice::<Value>(&payload).unwrap()["event"],
+            "opened"
+        );
+
+        assert!(charge_point
+            .call::<_, Value>("Heartbeat", &json!({}))
+            .await
+            .is_err());
+        let Some(Packet::Publish { topic, payload }) = received.recv().await else {
+            panic!("expected a publication");
+        };
+        assert_eq!(topic, "ocpp/CP001/Heartbeat");
+        assert_eq!(
+            serde_json::from_slice::<Value>(&payload).unwrap()["error"]["errorCode"],
+            "NotImplemented"
+        );
+
+        sender
+            .send(Packet::Publish {
+                topic: "ocpp/CP001/command/Reset".to_owned(),
+                payload: br#"{"type":"Soft"}"#.to_vec(),
+            })
+            .await
+            .unwrap();
+        let call = charge_point.next_call().await.unwrap();
+        assert_eq!(call.action, "Reset");
+        charge_point
+            .respond(CallResult::new(call.unique_id, &json!({"status": "Accepted"})).unwrap())
+            .unwrap();
+        assert_eq!(
+            received.recv().await,
+            Some(Packet::Publish {
+                topic: "ocpp/CP001/command/Reset/response".to_owned(),
+                payload: br#"{"response":{"status":"Accepted"}}"#.to_vec(),
+            })
+        );
+    }
+}
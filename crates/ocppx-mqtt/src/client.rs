@@ -0,0 +1,575 @@
+use crate::{packet::Packet, Error, Result};
+use ocppx_client::ReconnectPolicy;
+use std::{collections::HashMap, time::Duration};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    sync::{mpsc, oneshot, Mutex},
+    time::{self, Instant},
+};
+
+/// Number of packets waiting to be sent, or to be read.
+const QUEUE_CAPACITY: usize = 256;
+
+/// Options of an [`MqttClient`].
+#[derive(Debug, Clone)]
+pub struct MqttOptions {
+    host: String,
+    port: u16,
+    client_id: String,
+    keep_alive: Duration,
+    credentials: Option<(String, String)>,
+    reconnect: Option<ReconnectPolicy>,
+}
+
+impl MqttOptions {
+    pub fn new(host: impl Into<String>, port: u16, client_id: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            client_id: client_id.into(),
+            keep_alive: Duration::from_secs(30),
+            credentials: None,
+            reconnect: Some(ReconnectPolicy::default()),
+        }
+    }
+
+    /// The time after which the broker closes a silent connection. The
+    /// client pings the broker at this interval. Defaults to 30 seconds.
+    pub fn keep_alive(mut self, keep_alive: Duration) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    pub fn credentials(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.credentials = Some((username.into(), password.into()));
+        self
+    }
+
+    /// How to reconnect when the connection drops, or `None` to stay
+    /// disconnected. Defaults to [`ReconnectPolicy::default`].
+    pub fn reconnect(mut self, reconnect: Option<ReconnectPolicy>) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    /// Open a connection, and wait for the broker to accept it. Return
+    /// the stream, with what has been read after the `CONNACK`.
+    async fn open(&self) -> Result<(TcpStream, Vec<u8>)> {
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
+
+        stream
+            .write_all(
+                &Packet::Connect {
+                    client_id: self.client_id.clone(),
+                    keep_alive: self.keep_alive_secs(),
+                    credentials: self.credentials.clone(),
+                }
+                .encode(),
+            )
+            .await?;
+
+        let mut buffer = Vec::new();
+
+        match read_packet(&mut stream, &mut buffer).await? {
+            Some(Packet::ConnAck { return_code: 0 }) => Ok((stream, buffer)),
+            Some(Packet::ConnAck { return_code }) => Err(Error::ConnectionRefused(return_code)),
+            _ => Err(Error::MalformedPacket),
+        }
+    }
+
+    fn keep_alive_secs(&self) -> u16 {
+        self.keep_alive.as_secs().clamp(1, u16::MAX.into()) as u16
+    }
+}
+
+/// A message received on a subscribed topic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MqttMessage {
+    pub topic: String,
+    pub payload: Vec<u8>,
+}
+
+/// A minimal MQTT 3.1.1 client, over plain TCP, publishing and receiving
+/// messages with the QoS 0, i.e. at most once.
+///
+/// The connection is re-established when it is lost, according to
+/// [`MqttOptions::reconnect`], and the subscriptions are renewed. The
+/// messages published meanwhile are dropped, as the QoS 0 allows. Once the
+/// client is disconnected, or the reconnection attempts are exhausted, the
+/// publications fail with [`Error::ConnectionClosed`], and
+/// [`Self::next_message`] returns `None`.
+#[derive(Debug)]
+pub struct MqttClient {
+    commands: mpsc::Sender<Command>,
+    incoming: Mutex<mpsc::Receiver<MqttMessage>>,
+}
+
+/// What the connection task is asked to do.
+#[derive(Debug)]
+enum Command {
+    Send(Packet),
+    Subscribe {
+        filter: String,
+        acknowledged: oneshot::Sender<Result<()>>,
+    },
+}
+
+impl MqttClient {
+    /// Connect to the broker, and wait for it to accept the connection.
+    pub async fn connect(options: MqttOptions) -> Result<Self> {
+        let connection = options.open().await?;
+        let (commands, commands_receiver) = mpsc::channel(QUEUE_CAPACITY);
+        let (incoming_sender, incoming) = mpsc::channel(QUEUE_CAPACITY);
+
+        tokio::spawn(run(options, connection, commands_receiver, incoming_sender));
+
+        Ok(Self {
+            commands,
+            incoming: Mutex::new(incoming),
+        })
+    }
+
+    /// Publish `payload` on `topic`.
+    pub async fn publish(
+        &self,
+        topic: impl Into<String>,
+        payload: impl Into<Vec<u8>>,
+    ) -> Result<()> {
+        self.send(Command::Send(Packet::Publish {
+            topic: topic.into(),
+            payload: payload.into(),
+        }))
+        .await
+    }
+
+    /// Subscribe to the topics matching `filter`, e.g. `ocpp/+/command/+`,
+    /// and wait for the broker to acknowledge it. The subscription is
+    /// renewed after a reconnection.
+    pub async fn subscribe(&self, filter: impl Into<String>) -> Result<()> {
+        let (acknowledged, acknowledgement) = oneshot::channel();
+
+        self.send(Command::Subscribe {
+            filter: filter.into(),
+            acknowledged,
+        })
+        .await?;
+
+        acknowledgement.await.map_err(|_| Error::ConnectionClosed)?
+    }
+
+    /// Wait for the next message received on a subscribed topic.
+    ///
+    /// Returns `None` once the connection is closed.
+    pub async fn next_message(&self) -> Option<MqttMessage> {
+        self.incoming.lock().await.recv().await
+    }
+
+    /// Disconnect from the broker.
+    pub async fn disconnect(&self) -> Result<()> {
+        self.send(Command::Send(Packet::Disconnect)).await
+    }
+
+    async fn send(&self, command: Command) -> Result<()> {
+        self.commands
+            .send(command)
+            .await
+            .map_err(|_| Error::ConnectionClosed)
+    }
+}
+
+/// Read the next packet, or `None` if the connection is closed.
+async fn read_packet(stream: &mut TcpStream, buffer: &mut Vec<u8>) -> Result<Option<Packet>> {
+    loop {
+        if let Some((packet, length)) = Packet::decode(buffer)? {
+            buffer.drain(..length);
+
+            return Ok(Some(packet));
+        }
+
+        let mut chunk = [0; 4096];
+        let length = stream.read(&mut chunk).await?;
+
+        if length == 0 {
+            return Ok(None);
+        }
+
+        buffer.extend_from_slice(&chunk[..length]);
+    }
+}
+
+/// The subscriptions of a client, across its connections.
+#[derive(Debug, Default)]
+struct Subscriptions {
+    /// The filters subscribed to, acknowledged or not.
+    filters: Vec<String>,
+    /// Who waits for the acknowledgement of each filter.
+    waiting: HashMap<String, Vec<oneshot::Sender<Result<()>>>>,
+    /// The filter of each `SUBSCRIBE` waiting for its `SUBACK`.
+    in_flight: HashMap<u16, String>,
+    next_packet_id: u16,
+}
+
+impl Subscriptions {
+    fn add(&mut self, filter: String, acknowledged: oneshot::Sender<Result<()>>) {
+        if !self.filters.contains(&filter) {
+            self.filters.push(filter.clone());
+        }
+
+        self.waiting.entry(filter).or_default().push(acknowledged);
+    }
+
+    /// The `SUBSCRIBE` of `filter`, waiting for its `SUBACK`.
+    fn subscribe(&mut self, filter: String) -> Packet {
+        self.next_packet_id = self.next_packet_id.wrapping_add(1).max(1);
+        self.in_flight.insert(self.next_packet_id, filter.clone());
+
+        Packet::Subscribe {
+            packet_id: self.next_packet_id,
+            filters: vec![filter],
+        }
+    }
+
+    fn acknowledge(&mut self, packet_id: u16, return_code: u8) {
+        let Some(filter) = self.in_flight.remove(&packet_id) else {
+            return;
+        };
+        // The maximum QoS granted, or `0x80` for a failure.
+        let refused = return_code & 0x80 != 0;
+
+        if refused {
+            log::warn!(filter:% = filter; "the MQTT broker refused a subscription");
+            self.filters.retain(|subscribed| *subscribed != filter);
+        }
+
+        for acknowledged in self.waiting.remove(&filter).unwrap_or_default() {
+            let _ = acknowledged.send(if refused {
+                Err(Error::SubscriptionRefused(filter.clone()))
+            } else {
+                Ok(())
+            });
+        }
+    }
+}
+
+/// The connection task: run the connection, and reconnect when it drops,
+/// until the client is disconnected or the reconnection attempts are
+/// exhausted.
+async fn run(
+    options: MqttOptions,
+    mut connection: (TcpStream, Vec<u8>),
+    mut commands: mpsc::Receiver<Command>,
+    incoming: mpsc::Sender<MqttMessage>,
+) {
+    let keep_alive = Duration::from_secs(options.keep_alive_secs().into());
+    let mut subscriptions = Subscriptions::default();
+
+    loop {
+        match run_connection(
+            connection,
+            &mut commands,
+            &incoming,
+            &mut subscriptions,
+            keep_alive,
+        )
+        .await
+        {
+            Ok(true) => return,
+            Ok(false) => log::warn!("MQTT connection lost"),
+            Err(error) => log::warn!(error:% = error; "MQTT connection lost"),
+        }
+
+        let Some(policy) = &options.reconnect else {
+            return;
+        };
+
+        let mut attempt = 0;
+
+        connection = loop {
+            attempt += 1;
+
+            if policy
+                .max_attempts
+                .is_some_and(|max_attempts| attempt > max_attempts)
+            {
+                return;
+            }
+
+            log::info!(attempt; "reconnecting to the MQTT broker");
+
+            let delay = time::sleep(policy.delay(attempt));
+            tokio::pin!(delay);
+
+            // The publications are dropped meanwhile, and the
+            // subscriptions are sent once reconnected.
+            loop {
+                tokio::select! {
+                    _ = &mut delay => break,
+
+                    command = commands.recv() => match command {
+                        Some(Command::Send(Packet::Disconnect)) | None => return,
+                        Some(Command::Send(_)) => {}
+                        Some(Command::Subscribe { filter, acknowledged }) => {
+                            subscriptions.add(filter, acknowledged);
+                        }
+                    },
+                }
+            }
+
+            match options.open().await {
+                Ok(connection) => break connection,
+                Err(error) => log::debug!(error:% = error; "cannot reconnect to the MQTT broker"),
+            }
+        };
+
+        log::info!("reconnected to the MQTT broker");
+    }
+}
+
+/// Run a single connection: renew the subscriptions, send the outgoing
+/// packets, dispatch the incoming messages, and ping the broker when
+/// nothing has been sent for `keep_alive`. Return `true` if the client has
+/// been disconnected, `false` if the connection has dropped.
+async fn run_connection(
+    (mut stream, mut buffer): (TcpStream, Vec<u8>),
+    commands: &mut mpsc::Receiver<Command>,
+    incoming: &mpsc::Sender<MqttMessage>,
+    subscriptions: &mut Subscriptions,
+    keep_alive: Duration,
+) -> Result<bool> {
+    subscriptions.in_flight.clear();
+
+    for filter in subscriptions.filters.clone() {
+        stream
+            .write_all(&subscriptions.subscribe(filter).encode())
+            .await?;
+    }
+
+    let mut last_sent = Instant::now();
+    let mut chunk = [0; 4096];
+
+    loop {
+        while let Some((packet, length)) = Packet::decode(&buffer)? {
+            buffer.drain(..length);
+
+            match packet {
+                Packet::Publish { topic, payload } => {
+                    // Nobody is reading the messages anymore.
+                    let _ = incoming.send(MqttMessage { topic, payload }).await;
+                }
+                Packet::SubAck {
+                    packet_id,
+                    return_code,
+                } => subscriptions.acknowledge(packet_id, return_code),
+                _ => {}
+            }
+        }
+
+        let packet = tokio::select! {
+            length = stream.read(&mut chunk) => {
+                match length? {
+                    0 => return Ok(false),
+                    length => buffer.extend_from_slice(&chunk[..length]),
+                }
+
+                continue;
+            }
+
+            command = commands.recv() => match command {
+                Some(Command::Send(packet)) => packet,
+                Some(Command::Subscribe { filter, acknowledged }) => {
+                    subscriptions.add(filter.clone(), acknowledged);
+
+                    subscriptions.subscribe(filter)
+                }
+                None => Packet::Disconnect,
+            },
+
+            _ = time::sleep_until(last_sent + keep_alive) => Packet::PingReq,
+        };
+
+        stream.write_all(&packet.encode()).await?;
+        last_sent = Instant::now();
+
+        if packet == Packet::Disconnect {
+            return Ok(true);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// A broker accepting a client, which sends the packets it receives to
+    /// the returned channel, and sends to the client the packets it is
+    /// given. It acknowledges the connections and the subscriptions, except
+    /// the subscriptions to `refused/#`. Sending it a `DISCONNECT` drops the
+    /// connection, and the broker accepts the next one.
+    pub(crate) async fn broker() -> (u16, mpsc::Receiver<Packet>, mpsc::Sender<Packet>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (received, receiver) = mpsc::channel(16);
+        let (sender, mut to_send) = mpsc::channel::<Packet>(16);
+
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buffer = Vec::new();
+
+                loop {
+                    tokio::select! {
+                        packet = read_packet(&mut stream, &mut buffer) => {
+                            let Some(packet) = packet.unwrap() else { return };
+
+                            let response = match &packet {
+                                Packet::Connect { .. } => Some(Packet::ConnAck { return_code: 0 }),
+                                Packet::Subscribe { packet_id, filters } => Some(Packet::SubAck {
+                                    packet_id: *packet_id,
+                                    return_code: if filters[0] == "refused/#" { 0x80 } else { 0 },
+                                }),
+                                _ => None,
+                            };
+
+                            if let Some(response) = response {
+                                stream.write_all(&response.encode()).await.unwrap();
+                            }
+
+                            received.send(packet).await.unwrap();
+                        }
+                        Some(packet) = to_send.recv() => {
+                            if packet == Packet::Disconnect {
+                                break;
+                            }
+
+                            stream.write_all(&packet.encode()).await.unwrap();
+                        }
+                    }
+                }
+            }
+        });
+
+        (port, receiver, sender)
+    }
+
+    #[tokio::test]
+    async fn test_client() {
+        let (port, mut received, sender) = broker().await;
+        let client = MqttClient::connect(
+            MqttOptions::new("127.0.0.1", port, "ocppx").credentials("user", "secret"),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            received.recv().await,
+            Some(Packet::Connect {
+                client_id: "ocppx".to_owned(),
+                keep_alive: 30,
+                credentials: Some(("user".to_owned(), "secret".to_owned())),
+            })
+        );
+
+        client.subscribe("ocpp/#").await.unwrap();
+        assert_eq!(
+            received.recv().await,
+            Some(Packet::Subscribe {
+                packet_id: 1,
+                filters: vec!["ocpp/#".to_owned()],
+            })
+        );
+
+        client.publish("ocpp/CP001/Heartbeat", "{}").await.unwrap();
+        assert_eq!(
+            received.recv().await,
+            Some(Packet::Publish {
+                topic: "ocpp/CP001/Heartbeat".to_owned(),
+                payload: b"{}".to_vec(),
+            })
+        );
+
+        sender
+            .send(Packet::Publish {
+                topic: "ocpp/CP001/command/Reset".to_owned(),
+                payload: b"{\"type\":\"Soft\"}".to_vec(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            client.next_message().await,
+            Some(MqttMessage {
+                topic: "ocpp/CP001/command/Reset".to_owned(),
+                payload: b"{\"type\":\"Soft\"}".to_vec(),
+            })
+        );
+
+        client.disconnect().await.unwrap();
+        assert_eq!(received.recv().await, Some(Packet::Disconnect));
+    }
+
+    #[tokio::test]
+    async fn test_reconnect() {
+        let (port, mut received, sender) = broker().await;
+        let client = MqttClient::connect(MqttOptions::new("127.0.0.1", port, "ocppx").reconnect(
+            Some(ReconnectPolicy {
+                initial_delay: Duration::from_millis(10),
+                jitter: 0.,
+                ..ReconnectPolicy::default()
+            }),
+        ))
+        .await
+        .unwrap();
+        assert!(matches!(
+            received.recv().await,
+            Some(Packet::Connect { .. })
+        ));
+
+        client.subscribe("ocpp/#").await.unwrap();
+        assert!(matches!(
+            received.recv().await,
+            Some(Packet::Subscribe { .. })
+        ));
+        assert!(matches!(
+            client.subscribe("refused/#").await,
+            Err(Error::SubscriptionRefused(filter)) if filter == "refused/#"
+        ));
+        assert!(matches!(
+            received.recv().await,
+            Some(Packet::Subscribe { .. })
+        ));
+
+        // The connection drops: the client reconnects, and renews its
+        // subscriptions.
+        sender.send(Packet::Disconnect).await.unwrap();
+        assert!(matches!(
+            received.recv().await,
+            Some(Packet::Connect { .. })
+        ));
+        assert_eq!(
+            received.recv().await,
+            Some(Packet::Subscribe {
+                packet_id: 3,
+                filters: vec!["ocpp/#".to_owned()],
+            })
+        );
+
+        sender
+            .send(Packet::Publish {
+                topic: "ocpp/CP001/command/Reset".to_owned(),
+                payload: b"{}".to_vec(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            client.next_message().await.unwrap().topic,
+            "ocpp/CP001/command/Reset"
+        );
+
+        client.publish("ocpp/CP001/Heartbeat", "{}").await.unwrap();
+        assert!(matches!(
+            received.recv().await,
+            Some(Packet::Publish { .. })
+        ));
+    }
+}
@@ -0,0 +1,41 @@
+//! Bridge an OCPP server to an MQTT broker, for home-automation and data
+//! pipelines.
+//!
+//! [`MqttBridge`] publishes the `Call`s sent by the Charge Points, with
+//! their responses, and their connection events on configurable topics,
+//! e.g. `ocpp/CP001/StatusNotification` or `ocpp/CP001/connection`, see
+//! [`Topics`]. It also accepts commands: a payload published on
+//! `ocpp/CP001/command/Reset` is sent to the Charge Point `CP001` as a
+//! `Reset` `Call`, and its response is published on
+//! `ocpp/CP001/command/Reset/response`.
+//!
+//! [`MqttClient`] is a minimal MQTT 3.1.1 client, with the QoS 0 only,
+//! reconnecting when its connection drops.
+
+mod bridge;
+mod client;
+mod packet;
+
+pub use bridge::{MqttBridge, MqttLayer, Publishing, Topic, Topics};
+pub use client::{MqttClient, MqttMessage, MqttOptions};
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("I/O error")]
+    Io(#[from] std::io::Error),
+
+    #[error("malformed MQTT packet")]
+    MalformedPacket,
+
+    #[error("the broker refused the connection with the return code {0}")]
+    ConnectionRefused(u8),
+
+    #[error("the connection to the broker is closed")]
+    ConnectionClosed,
+
+    #[error("the broker refused the subscription to `{0}`")]
+    SubscriptionRefused(String),
+}
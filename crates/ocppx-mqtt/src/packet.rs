@@ -0,0 +1,293 @@
+use crate::{Error, Result};
+
+/// The MQTT 3.1.1 control packets used by the [`MqttClient`][crate::MqttClient].
+///
+/// Only the QoS 0 is supported: the messages are published at most once,
+/// and the subscriptions request QoS 0 deliveries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Packet {
+    Connect {
+        client_id: String,
+        keep_alive: u16,
+        credentials: Option<(String, String)>,
+    },
+    ConnAck {
+        return_code: u8,
+    },
+    Publish {
+        topic: String,
+        payload: Vec<u8>,
+    },
+    Subscribe {
+        packet_id: u16,
+        filters: Vec<String>,
+    },
+    SubAck {
+        packet_id: u16,
+        /// The maximum QoS granted, or `0x80` for a failure.
+        return_code: u8,
+    },
+    PingReq,
+    PingResp,
+    Disconnect,
+}
+
+impl Packet {
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+
+        let header = match self {
+            Self::Connect {
+                client_id,
+                keep_alive,
+                credentials,
+            } => {
+                write_string(&mut body, "MQTT");
+                // The protocol level of MQTT 3.1.1.
+                body.push(4);
+
+                // A clean session, with the credentials if any.
+                let flags = if credentials.is_some() { 0xc2 } else { 0x02 };
+                body.push(flags);
+                body.extend_from_slice(&keep_alive.to_be_bytes());
+                write_string(&mut body, client_id);
+
+                if let Some((username, password)) = credentials {
+                    write_string(&mut body, username);
+                    write_string(&mut body, password);
+                }
+
+                0x10
+            }
+            Self::ConnAck { return_code } => {
+                body.extend_from_slice(&[0, *return_code]);
+
+                0x20
+            }
+            Self::Publish { topic, payload } => {
+                write_string(&mut body, topic);
+                body.extend_from_slice(payload);
+
+                0x30
+            }
+            Self::Subscribe { packet_id, filters } => {
+                body.extend_from_slice(&packet_id.to_be_bytes());
+
+                for filter in filters {
+                    write_string(&mut body, filter);
+                    // The requested QoS.
+                    body.push(0);
+                }
+
+                0x82
+            }
+            Self::SubAck {
+                packet_id,
+                return_code,
+            } => {
+                body.extend_from_slice(&packet_id.to_be_bytes());
+                body.push(*return_code);
+
+                0x90
+            }
+            Self::PingReq => 0xc0,
+            Self::PingResp => 0xd0,
+            Self::Disconnect => 0xe0,
+        };
+
+        let mut packet = vec![header];
+        let mut length = body.len();
+
+        // The remaining length, 7 bits at a time.
+        loop {
+            let mut byte = (length % 128) as u8;
+            length /= 128;
+
+            if length > 0 {
+                byte |= 0x80;
+            }
+
+            packet.push(byte);
+
+            if length == 0 {
+                break;
+            }
+        }
+
+        packet.extend_from_slice(&body);
+        packet
+    }
+
+    /// Decode the packet at the start of `buffer`, with its size, or `None`
+    /// if it is not complete yet.
+    pub(crate) fn decode(buffer: &[u8]) -> Result<Option<(Self, usize)>> {
+        let Some(&header) = buffer.first() else {
+            return Ok(None);
+        };
+
+        let mut length = 0;
+        let mut offset = 1;
+
+        loop {
+            let Some(&byte) = buffer.get(offset) else {
+                return Ok(None);
+            };
+
+            if offset > 4 {
+                return Err(Error::MalformedPacket);
+            }
+
+            length += usize::from(byte & 0x7f) << (7 * (offset - 1));
+            offset += 1;
+
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+
+        let Some(body) = buffer.get(offset..offset + length) else {
+            return Ok(None);
+        };
+        let mut reader = Reader(body);
+
+        let packet = match header >> 4 {
+            1 => {
+                reader.string()?;
+                reader.bytes(1)?;
+                let flags = reader.bytes(1)?[0];
+                let keep_alive = reader.u16()?;
+                let client_id = reader.string()?;
+                let credentials = if flags & 0xc0 == 0xc0 {
+                    Some((reader.string()?, reader.string()?))
+                } else {
+                    None
+                };
+
+                Self::Connect {
+                    client_id,
+                    keep_alive,
+                    credentials,
+                }
+            }
+            2 => Self::ConnAck {
+                return_code: reader.bytes(2)?[1],
+            },
+            3 => {
+                let topic = reader.string()?;
+
+                // The packet identifier of the QoS 1 and 2 deliveries.
+                if (header >> 1) & 0x03 > 0 {
+                    reader.u16()?;
+                }
+
+                Self::Publish {
+                    topic,
+                    payload: reader.0.to_vec(),
+                }
+            }
+            8 => {
+                let packet_id = reader.u16()?;
+                let mut filters = Vec::new();
+
+                while !reader.0.is_empty() {
+                    filters.push(reader.string()?);
+                    reader.bytes(1)?;
+                }
+
+                Self::Subscribe { packet_id, filters }
+            }
+            9 => Self::SubAck {
+                packet_id: reader.u16()?,
+                return_code: reader.bytes(1)?[0],
+            },
+            12 => Self::PingReq,
+            13 => Self::PingResp,
+            14 => Self::Disconnect,
+            _ => return Err(Error::MalformedPacket),
+        };
+
+        Ok(Some((packet, offset + length)))
+    }
+}
+
+fn write_string(buffer: &mut Vec<u8>, string: &str) {
+    buffer.extend_from_slice(&(string.len() as u16).to_be_bytes());
+    buffer.extend_from_slice(string.as_bytes());
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8]> {
+        if self.0.len() < length {
+            return Err(Error::MalformedPacket);
+        }
+
+        let (bytes, rest) = self.0.split_at(length);
+        self.0 = rest;
+
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.bytes(2)?;
+
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let length = self.u16()?;
+
+        String::from_utf8(self.bytes(length.into())?.to_vec()).map_err(|_| Error::MalformedPacket)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode() {
+        for packet in [
+            Packet::Connect {
+                client_id: "ocppx".to_owned(),
+                keep_alive: 30,
+                credentials: Some(("user".to_owned(), "secret".to_owned())),
+            },
+            Packet::ConnAck { return_code: 5 },
+            Packet::Publish {
+                topic: "ocpp/CP001/StatusNotification".to_owned(),
+                // Long enough for a remaining length on 2 bytes.
+                payload: vec![b'x'; 200],
+            },
+            Packet::Subscribe {
+                packet_id: 1,
+                filters: vec!["ocpp/+/command/+".to_owned()],
+            },
+            Packet::SubAck {
+                packet_id: 1,
+                return_code: 0x80,
+            },
+            Packet::PingReq,
+            Packet::PingResp,
+            Packet::Disconnect,
+        ] {
+            let mut encoded = packet.encode();
+            let length = encoded.len();
+            encoded.extend_from_slice(&Packet::PingReq.encode());
+
+            assert_eq!(Packet::decode(&encoded).unwrap(), Some((packet, length)));
+            assert_eq!(Packet::decode(&encoded[..length - 1]).unwrap(), None);
+        }
+
+        assert_eq!(
+            Packet::Publish {
+                topic: "a".to_owned(),
+                payload: b"b".to_vec(),
+            }
+            .encode(),
+            [0x30, 4, 0, 1, b'a', b'b']
+        );
+        assert!(Packet::decode(&[0xf0, 0]).is_err());
+    }
+}
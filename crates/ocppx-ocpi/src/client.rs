@@ -0,0 +1,272 @@
+use crate::{
+    model::{Cdr, Evse, Party, Session},
+    Error, Result,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ocppx_server::http::{HttpClient, HttpUrl};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "tls")]
+use std::sync::Arc;
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use tokio::time;
+
+/// An endpoint of a module of an eMSP, e.g.
+/// `https://emsp.example.org/ocpi/2.2.1/sessions`.
+#[derive(Debug, Clone)]
+struct Endpoint {
+    url: HttpUrl,
+}
+
+impl Endpoint {
+    fn parse(url: &str) -> Result<Self> {
+        let mut parsed = HttpUrl::parse(url).ok_or_else(|| Error::InvalidUrl(url.to_owned()))?;
+        parsed
+            .path
+            .truncate(parsed.path.trim_end_matches('/').len());
+
+        Ok(Self { url: parsed })
+    }
+}
+
+/// The envelope of the OCPI responses.
+#[derive(Debug, Deserialize)]
+struct Envelope {
+    status_code: u32,
+    status_message: Option<String>,
+}
+
+/// Push the sessions, the CDRs and the EVSE statuses of a CPO to the
+/// receiver interfaces of an eMSP.
+///
+/// The endpoints of the modules are the ones exchanged during the OCPI
+/// registration, which is not handled here, as the token of the eMSP. The
+/// `https://` endpoints need the `tls` feature, and the root certificates
+/// given with [`Self::tls`].
+#[derive(Debug)]
+pub struct EmspClient {
+    party: Party,
+    token: String,
+    sessions: Option<Endpoint>,
+    cdrs: Option<Endpoint>,
+    locations: Option<Endpoint>,
+    timeout: Duration,
+    http: HttpClient,
+    next_request_id: AtomicU64,
+}
+
+impl EmspClient {
+    /// A client for the CPO `party`, with the token given by the eMSP.
+    pub fn new(party: Party, token: impl Into<String>) -> Self {
+        Self {
+            party,
+            token: token.into(),
+            sessions: None,
+            cdrs: None,
+            locations: None,
+            timeout: Duration::from_secs(10),
+            http: HttpClient::default(),
+            next_request_id: AtomicU64::new(1),
+        }
+    }
+
+    /// The endpoint of the `sessions` module of the eMSP.
+    pub fn sessions(mut self, url: &str) -> Result<Self> {
+        self.sessions = Some(Endpoint::parse(url)?);
+
+        Ok(self)
+    }
+
+    /// The endpoint of the `cdrs` module of the eMSP.
+    pub fn cdrs(mut self, url: &str) -> Result<Self> {
+        self.cdrs = Some(Endpoint::parse(url)?);
+
+        Ok(self)
+    }
+
+    /// The endpoint of the `locations` module of the eMSP.
+    pub fn locations(mut self, url: &str) -> Result<Self> {
+        self.locations = Some(Endpoint::parse(url)?);
+
+        Ok(self)
+    }
+
+    /// The time to wait for the eMSP. Defaults to 10 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Connect to the `https://` endpoints with `config`.
+    #[cfg(feature = "tls")]
+    pub fn tls(mut self, config: Arc<ocppx_server::rustls::ClientConfig>) -> Self {
+        self.http = self.http.tls(config);
+        self
+    }
+
+    /// The size of the largest response accepted from the eMSP, in bytes.
+    /// Defaults to 1 MiB.
+    pub fn max_response_size(mut self, max_response_size: usize) -> Self {
+        self.http = self.http.max_response_size(max_response_size);
+        self
+    }
+
+    /// Create or replace `session`.
+    pub async fn push_session(&self, session: &Session) -> Result<()> {
+        let endpoint = self
+            .sessions
+            .as_ref()
+            .ok_or(Error::MissingEndpoint("sessions"))?;
+        let path = format!(
+            "{}/{}/{}/{}",
+            endpoint.url.path, session.country_code, session.party_id, session.id
+        );
+
+        self.send(endpoint, "PUT", &path, session).await
+    }
+
+    /// Send `cdr`, once: the CDRs cannot be updated.
+    pub async fn push_cdr(&self, cdr: &Cdr) -> Result<()> {
+        let endpoint = self.cdrs.as_ref().ok_or(Error::MissingEndpoint("cdrs"))?;
+
+        self.send(endpoint, "POST", &endpoint.url.path, cdr).await
+    }
+
+    /// Create or replace `evse`, in the location `location_id`.
+    pub async fn push_evse(&self, location_id: &str, evse: &Evse) -> Result<()> {
+        let endpoint = self
+            .locations
+            .as_ref()
+            .ok_or(Error::MissingEndpoint("locations"))?;
+        let path = format!(
+            "{}/{}/{}/{location_id}/{}",
+            endpoint.url.path, self.party.country_code, self.party.party_id, evse.uid
+        );
+
+        self.send(endpoint, "PUT", &path, evse).await
+    }
+
+    async fn send<T>(&self, endpoint: &Endpoint, method: &str, path: &str, object: &T) -> Result<()>
+    where
+        T: Serialize,
+    {
+        let body = serde_json::to_vec(object)?;
+        let request_id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+
+        // HTTP/1.0, so that the body is neither chunked nor kept alive: it
+        // ends with the connection.
+        let mut request = format!(
+            "{method} {path} HTTP/1.0\r\n\
+             Host: {host}\r\n\
+             Authorization: Token {token}\r\n\
+             Content-Type: application/json\r\n\
+             Content-Length: {length}\r\n\
+             X-Request-ID: {request_id}\r\n\
+             X-Correlation-ID: {request_id}\r\n\
+             OCPI-from-country-code: {country_code}\r\n\
+             OCPI-from-party-id: {party_id}\r\n\r\n",
+            host = endpoint.url.host_header(),
+            token = STANDARD.encode(&self.token),
+            length = body.len(),
+            country_code = self.party.country_code,
+            party_id = self.party.party_id,
+        )
+        .into_bytes();
+        request.extend_from_slice(&body);
+
+        let response = time::timeout(self.timeout, self.http.send(&endpoint.url, &request))
+            .await
+            .map_err(|_| Error::Timeout(self.timeout))??;
+
+        if !(200..300).contains(&response.status) {
+            return Err(Error::Http(response.status));
+        }
+
+        let envelope = serde_json::from_slice::<Envelope>(&response.body)?;
+
+        // The 1xxx codes are the successes.
+        if !(1000..2000).contains(&envelope.status_code) {
+            return Err(Error::Ocpi {
+                status_code: envelope.status_code,
+                status_message: envelope.status_message.unwrap_or_default(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EvseStatus;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    #[tokio::test]
+    async fn test_emsp_client() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let emsp = tokio::spawn(async move {
+            let mut requests = Vec::new();
+
+            for response in [
+                r#"{"status_code": 1000, "timestamp": "2024-01-01T10:00:00Z"}"#,
+                r#"{"status_code": 2001, "status_message": "Unknown location", "timestamp": "2024-01-01T10:00:00Z"}"#,
+            ] {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = [0; 4096];
+                let length = stream.read(&mut request).await.unwrap();
+                requests.push(String::from_utf8_lossy(&request[..length]).into_owned());
+
+                stream
+                    .write_all(format!("HTTP/1.0 200 OK\r\n\r\n{response}").as_bytes())
+                    .await
+                    .unwrap();
+            }
+
+            requests
+        });
+
+        let client = EmspClient::new(
+            Party {
+                country_code: "FR".to_owned(),
+                party_id: "ABC".to_owned(),
+            },
+            "secret",
+        )
+        .locations(&format!(
+            "http://127.0.0.1:{port}/ocpi/emsp/2.2.1/locations/"
+        ))
+        .unwrap();
+        let evse = Evse {
+            uid: "CP001-1".to_owned(),
+            evse_id: "FR*ABC*E0001".to_owned(),
+            status: EvseStatus::Available,
+            connectors: Vec::new(),
+            last_updated: "2024-01-01T10:00:00Z".parse().unwrap(),
+        };
+
+        client.push_evse("LOC1", &evse).await.unwrap();
+        assert!(matches!(
+            client.push_evse("LOC1", &evse).await,
+            Err(Error::Ocpi {
+                status_code: 2001,
+                ..
+            })
+        ));
+        let requests = emsp.await.unwrap();
+        assert!(requests[0]
+            .starts_with("PUT /ocpi/emsp/2.2.1/locations/FR/ABC/LOC1/CP001-1 HTTP/1.0\r\n"));
+        assert!(requests[0].contains("Authorization: Token c2VjcmV0\r\n"));
+        assert!(requests[0].contains("OCPI-from-party-id: ABC\r\n"));
+        assert!(requests[0].ends_with(
+            r#""status":"AVAILABLE","connectors":[],"last_updated":"2024-01-01T10:00:00Z"}"#
+        ));
+    }
+}
@@ -0,0 +1,63 @@
+//! Map the transactions and the connector statuses of ocppx to OCPI 2.2.1
+//! objects, for the roaming platforms.
+//!
+//! [`Mapper`] turns a [`Transaction`][ocppx_server::Transaction] into a
+//! [`Session`] while it is ongoing, and into a [`Cdr`] once it has stopped,
+//! and a `StatusNotification` into the [`Evse`] of its connector.
+//! [`EmspClient`] pushes them to the receiver interfaces of an eMSP:
+//!
+//! ```rust,ignore
+//! let mapper = Mapper::new(party.clone(), "EUR").connector("CP001", 1, location);
+//! let emsp = EmspClient::new(party, token).sessions("http://emsp.example.org/ocpi/2.2.1/sessions")?;
+//!
+//! if let Some(session) = mapper.session(&transaction) {
+//!     emsp.push_session(&session).await?;
+//! }
+//! ```
+//!
+//! The `https://` endpoints need the `tls` feature, enabled by default, and
+//! the root certificates of the eMSP, given with [`EmspClient::tls`].
+//!
+//! The tariffs are not modelled: the cost of a CDR is computed by the
+//! caller.
+
+mod client;
+mod mapping;
+pub mod model;
+
+pub use client::EmspClient;
+pub use mapping::{evse_status, EvseLocation, Mapper};
+pub use model::{Cdr, Evse, Party, Session};
+#[cfg(feature = "tls")]
+pub use ocppx_server::rustls;
+use std::time::Duration;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("I/O error")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON error")]
+    Json(#[from] serde_json::Error),
+
+    #[error("invalid URL `{0}`")]
+    InvalidUrl(String),
+
+    #[error("the endpoint of the `{0}` module is not configured")]
+    MissingEndpoint(&'static str),
+
+    #[error("unexpected HTTP status {0}")]
+    Http(u16),
+
+    #[error("the eMSP responded with the OCPI status {status_code}: {status_message}")]
+    Ocpi {
+        status_code: u32,
+        status_message: String,
+    },
+
+    #[error("no response after {0:?}")]
+    Timeout(Duration),
+}
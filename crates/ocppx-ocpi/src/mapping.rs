@@ -0,0 +1,394 @@
+use crate::model::{
+    AuthMethod, Cdr, CdrDimension, CdrDimensionType, CdrLocation, CdrToken, ChargingPeriod,
+    Connector, ConnectorFormat, ConnectorStandard, Evse, EvseStatus, Party, PowerType, Price,
+    Session, SessionStatus, TokenType,
+};
+use chrono::{DateTime, Utc};
+use ocppx_server::Transaction;
+use ocppx_types::v1_6::{
+    SampledValueMeasurand, SampledValueUnit, StatusNotificationRequest, StatusNotificationStatus,
+};
+use std::collections::HashMap;
+
+/// Where a connector of a Charge Point is, in OCPI: its location, and its
+/// EVSE.
+#[derive(Debug, Clone)]
+pub struct EvseLocation {
+    pub location_id: String,
+    pub address: String,
+    pub city: String,
+    /// ISO 3166-1 alpha-3 code, e.g. `FRA`.
+    pub country: String,
+    pub evse_uid: String,
+    /// The eMI3 EVSE ID, e.g. `FR*ABC*E0001`.
+    pub evse_id: String,
+    pub connector_id: String,
+    pub standard: ConnectorStandard,
+    pub format: ConnectorFormat,
+    pub power_type: PowerType,
+    pub max_voltage: i32,
+    pub max_amperage: i32,
+}
+
+/// Map the transactions and the connector statuses of the Charge Points to
+/// OCPI objects.
+///
+/// Each connector is an EVSE of a location, given with [`Mapper::connector`];
+/// the transactions and statuses of the other connectors are not mapped.
+/// The ID tags are the `uid` and the `contract_id` of RFID tokens, issued
+/// by [`Mapper::token_issuer`].
+#[derive(Debug, Clone)]
+pub struct Mapper {
+    party: Party,
+    token_issuer: Party,
+    currency: String,
+    evses: HashMap<(String, i32), EvseLocation>,
+}
+
+impl Mapper {
+    /// A mapper for the CPO `party`, billing in `currency`, e.g. `EUR`.
+    pub fn new(party: Party, currency: impl Into<String>) -> Self {
+        Self {
+            token_issuer: party.clone(),
+            party,
+            currency: currency.into(),
+            evses: HashMap::new(),
+        }
+    }
+
+    /// The party issuing the tokens. Defaults to the CPO.
+    pub fn token_issuer(mut self, token_issuer: Party) -> Self {
+        self.token_issuer = token_issuer;
+        self
+    }
+
+    /// Map the connector `connector_id` of `charge_point_id` to `location`.
+    pub fn connector(
+        mut self,
+        charge_point_id: impl Into<String>,
+        connector_id: i32,
+        location: EvseLocation,
+    ) -> Self {
+        self.evses
+            .insert((charge_point_id.into(), connector_id), location);
+        self
+    }
+
+    fn location(&self, charge_point_id: &str, connector_id: i32) -> Option<&EvseLocation> {
+        self.evses.get(&(charge_point_id.to_owned(), connector_id))
+    }
+
+    fn cdr_token(&self, id_tag: &str) -> CdrToken {
+        CdrToken {
+            country_code: self.token_issuer.country_code.clone(),
+            party_id: self.token_issuer.party_id.clone(),
+            uid: id_tag.to_owned(),
+            token_type: TokenType::Rfid,
+            contract_id: id_tag.to_owned(),
+        }
+    }
+
+    /// The session of `transaction`, `ACTIVE` or `COMPLETED`.
+    pub fn session(&self, transaction: &Transaction) -> Option<Session> {
+        let location = self.location(&transaction.charge_point_id, transaction.connector_id)?;
+        let (energy, last_updated) = energy(transaction);
+        let kwh = f64::from(energy) / 1000.;
+
+        Some(Session {
+            country_code: self.party.country_code.clone(),
+            party_id: self.party.party_id.clone(),
+            id: transaction.id.to_string(),
+            start_date_time: transaction.started_at,
+            end_date_time: transaction.stop.as_ref().map(|stop| stop.stopped_at),
+            kwh,
+            cdr_token: self.cdr_token(&transaction.id_tag),
+            auth_method: AuthMethod::AuthRequest,
+            location_id: location.location_id.clone(),
+            evse_uid: location.evse_uid.clone(),
+            connector_id: location.connector_id.clone(),
+            currency: self.currency.clone(),
+            charging_periods: vec![ChargingPeriod {
+                start_date_time: transaction.started_at,
+                dimensions: vec![CdrDimension {
+                    dimension_type: CdrDimensionType::Energy,
+                    volume: kwh,
+                }],
+            }],
+            status: if transaction.is_active() {
+                SessionStatus::Active
+            } else {
+                SessionStatus::Completed
+            },
+            last_updated,
+        })
+    }
+
+    /// The CDR of `transaction`, once it has stopped. The cost depends on
+    /// the tariffs, and is given by the caller.
+    pub fn cdr(&self, transaction: &Transaction, total_cost: Price) -> Option<Cdr> {
+        let stop = transaction.stop.as_ref()?;
+        let location = self.location(&transaction.charge_point_id, transaction.connector_id)?;
+        let total_energy = f64::from(transaction.energy()?.max(0)) / 1000.;
+        let total_time = (stop.stopped_at - transaction.started_at)
+            .num_milliseconds()
+            .max(0) as f64
+            / 3_600_000.;
+
+        Some(Cdr {
+            country_code: self.party.country_code.clone(),
+            party_id: self.party.party_id.clone(),
+            id: transaction.id.to_string(),
+            start_date_time: transaction.started_at,
+            end_date_time: stop.stopped_at,
+            session_id: transaction.id.to_string(),
+            cdr_token: self.cdr_token(&transaction.id_tag),
+            auth_method: AuthMethod::AuthRequest,
+            cdr_location: CdrLocation {
+                id: location.location_id.clone(),
+                address: location.address.clone(),
+                city: location.city.clone(),
+                country: location.country.clone(),
+                evse_uid: location.evse_uid.clone(),
+                evse_id: location.evse_id.clone(),
+                connector_id: location.connector_id.clone(),
+                connector_standard: location.standard,
+                connector_format: location.format,
+                connector_power_type: location.power_type,
+            },
+            currency: self.currency.clone(),
+            charging_periods: vec![ChargingPeriod {
+                start_date_time: transaction.started_at,
+                dimensions: vec![
+                    CdrDimension {
+                        dimension_type: CdrDimensionType::Energy,
+                        volume: total_energy,
+                    },
+                    CdrDimension {
+                        dimension_type: CdrDimensionType::Time,
+                        volume: total_time,
+                    },
+                ],
+            }],
+            total_cost,
+            total_energy,
+            total_time,
+            last_updated: stop.stopped_at,
+        })
+    }
+
+    /// The EVSE of the connector of a `StatusNotification`, with the ID of
+    /// its location, or `None` if the connector is not mapped, e.g. the
+    /// connector 0, i.e. the Charge Point as a whole.
+    pub fn evse(
+        &self,
+        charge_point_id: &str,
+        request: &StatusNotificationRequest,
+    ) -> Option<(String, Evse)> {
+        let location = self.location(charge_point_id, request.connector_id)?;
+        let last_updated = request.timestamp.unwrap_or_else(Utc::now);
+
+        Some((
+            location.location_id.clone(),
+            Evse {
+                uid: location.evse_uid.clone(),
+                evse_id: location.evse_id.clone(),
+                status: evse_status(request.status),
+                connectors: vec![Connector {
+                    id: location.connector_id.clone(),
+                    standard: location.standard,
+                    format: location.format,
+                    power_type: location.power_type,
+                    max_voltage: location.max_voltage,
+                    max_amperage: location.max_amperage,
+                    last_updated,
+                }],
+                last_updated,
+            },
+        ))
+    }
+}
+
+/// The OCPI status of an EVSE with an OCPP connector status: an occupied
+/// connector is `CHARGING`, even when the EV is not drawing power.
+pub fn evse_status(status: StatusNotificationStatus) -> EvseStatus {
+    match status {
+        StatusNotificationStatus::Available => EvseStatus::Available,
+        StatusNotificationStatus::Preparing
+        | StatusNotificationStatus::Charging
+        | StatusNotificationStatus::SuspendedEVSE
+        | StatusNotificationStatus::SuspendedEV
+        | StatusNotificationStatus::Finishing => EvseStatus::Charging,
+        StatusNotificationStatus::Reserved => EvseStatus::Reserved,
+        StatusNotificationStatus::Unavailable => EvseStatus::Inoperative,
+        StatusNotificationStatus::Faulted => EvseStatus::OutOfOrder,
+    }
+}
+
+/// The energy delivered so far by `transaction`, in Wh, with when it has
+/// been measured: at the stop, or with the last energy register reading.
+fn energy(transaction: &Transaction) -> (i32, DateTime<Utc>) {
+    if let Some(stop) = &transaction.stop {
+        return (
+            stop.meter_stop
+                .saturating_sub(transaction.meter_start)
+                .max(0),
+            stop.stopped_at,
+        );
+    }
+
+    transaction
+        .meter_values
+        .iter()
+        .rev()
+        .find_map(|meter_value| {
+            meter_value
+                .sampled_value
+                .iter()
+                .filter(|sampled_value| {
+                    matches!(
+                        sampled_value.measurand,
+                        None | Some(SampledValueMeasurand::EnergyActiveImportRegister)
+                    )
+                })
+                .find_map(|sampled_value| {
+                    let value = sampled_value.value.parse::<f64>().ok()?;
+
+                    match sampled_value.unit {
+                        None | Some(SampledValueUnit::Wh) => Some(value),
+                        Some(SampledValueUnit::KWh) => Some(value * 1000.),
+                        _ => None,
+                    }
+                })
+                .map(|register| (register, meter_value.timestamp))
+        })
+        .map(|(register, timestamp)| {
+            (
+                (register as i32)
+                    .saturating_sub(transaction.meter_start)
+                    .max(0),
+                timestamp,
+            )
+        })
+        .unwrap_or((0, transaction.started_at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ocppx_server::TransactionStop;
+    use ocppx_types::v1_6::{
+        IdTagInfoStatus, MeterValue, SampledValue, StatusNotificationErrorCode,
+        StopTransactionReason,
+    };
+    use serde_json::json;
+
+    fn mapper() -> Mapper {
+        Mapper::new(
+            Party {
+                country_code: "FR".to_owned(),
+                party_id: "ABC".to_owned(),
+            },
+            "EUR",
+        )
+        .connector(
+            "CP001",
+            1,
+            EvseLocation {
+                location_id: "LOC1".to_owned(),
+                address: "1 rue de la Paix".to_owned(),
+                city: "Paris".to_owned(),
+                country: "FRA".to_owned(),
+                evse_uid: "CP001-1".to_owned(),
+                evse_id: "FR*ABC*E0001".to_owned(),
+                connector_id: "1".to_owned(),
+                standard: ConnectorStandard::Iec62196T2,
+                format: ConnectorFormat::Socket,
+                power_type: PowerType::Ac3Phase,
+                max_voltage: 400,
+                max_amperage: 32,
+            },
+        )
+    }
+
+    #[test]
+    fn test_session_and_cdr() {
+        let started_at = "2024-01-01T10:00:00Z".parse().unwrap();
+        let mut transaction = Transaction {
+            id: 42,
+            charge_point_id: "CP001".to_owned(),
+            connector_id: 1,
+            id_tag: "TAG1".to_owned(),
+            id_tag_status: IdTagInfoStatus::Accepted,
+            reservation_id: None,
+            meter_start: 1000,
+            started_at,
+            meter_values: vec![MeterValue::builder()
+                .timestamp("2024-01-01T10:30:00Z".parse::<DateTime<Utc>>().unwrap())
+                .sampled_value(vec![SampledValue::builder()
+                    .value("3.5")
+                    .unit(SampledValueUnit::KWh)
+                    .build()])
+                .build()],
+            stop: None,
+        };
+
+        let session = mapper().session(&transaction).unwrap();
+        assert_eq!(session.status, SessionStatus::Active);
+        assert_eq!(session.kwh, 2.5);
+        assert_eq!(session.evse_uid, "CP001-1");
+        assert!(mapper().cdr(&transaction, total_cost()).is_none());
+
+        transaction.stop = Some(TransactionStop {
+            id_tag: None,
+            meter_stop: 5000,
+            stopped_at: "2024-01-01T11:30:00Z".parse().unwrap(),
+            reason: StopTransactionReason::Local,
+        });
+
+        let cdr = mapper().cdr(&transaction, total_cost()).unwrap();
+        assert_eq!(cdr.total_energy, 4.);
+        assert_eq!(cdr.total_time, 1.5);
+        assert_eq!(
+            serde_json::to_value(&cdr).unwrap()["cdr_location"]["connector_standard"],
+            json!("IEC_62196_T2")
+        );
+        assert_eq!(
+            serde_json::to_value(mapper().session(&transaction).unwrap()).unwrap()["status"],
+            json!("COMPLETED")
+        );
+
+        transaction.connector_id = 2;
+        assert!(mapper().session(&transaction).is_none());
+    }
+
+    fn total_cost() -> Price {
+        Price {
+            excl_vat: 1.2,
+            incl_vat: None,
+        }
+    }
+
+    #[test]
+    fn test_evse_status() {
+        let (location_id, evse) = mapper()
+            .evse(
+                "CP001",
+                &StatusNotificationRequest::builder()
+                    .connector_id(1)
+                    .error_code(StatusNotificationErrorCode::OtherError)
+                    .status(StatusNotificationStatus::Faulted)
+                    .build(),
+            )
+            .unwrap();
+
+        assert_eq!(location_id, "LOC1");
+        assert_eq!(
+            serde_json::to_value(&evse).unwrap()["status"],
+            json!("OUTOFORDER")
+        );
+        assert_eq!(
+            evse_status(StatusNotificationStatus::SuspendedEV),
+            EvseStatus::Charging
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! The OCPI 2.2.1 objects pushed by a CPO to the eMSPs: [`Session`],
+//! [`Cdr`] and [`Evse`], with the fields that can be filled from OCPP.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Who a CPO is, in the OCPI objects.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Party {
+    /// ISO 3166-1 alpha-2 code, e.g. `FR`.
+    pub country_code: String,
+    /// ISO 15118 ID of the party, e.g. `ABC`.
+    pub party_id: String,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SessionStatus {
+    Active,
+    Completed,
+    Invalid,
+    Pending,
+    Reservation,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AuthMethod {
+    AuthRequest,
+    Command,
+    Whitelist,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TokenType {
+    AdHocUser,
+    AppUser,
+    Other,
+    Rfid,
+}
+
+/// The token used to start a session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CdrToken {
+    pub country_code: String,
+    pub party_id: String,
+    pub uid: String,
+    #[serde(rename = "type")]
+    pub token_type: TokenType,
+    pub contract_id: String,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CdrDimensionType {
+    Energy,
+    MaxCurrent,
+    MinCurrent,
+    Power,
+    Time,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CdrDimension {
+    #[serde(rename = "type")]
+    pub dimension_type: CdrDimensionType,
+    /// In kWh for the energy, in hours for the time.
+    pub volume: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChargingPeriod {
+    pub start_date_time: DateTime<Utc>,
+    pub dimensions: Vec<CdrDimension>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Price {
+    pub excl_vat: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub incl_vat: Option<f64>,
+}
+
+/// A charging session, ongoing or completed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub country_code: String,
+    pub party_id: String,
+    pub id: String,
+    pub start_date_time: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_date_time: Option<DateTime<Utc>>,
+    pub kwh: f64,
+    pub cdr_token: CdrToken,
+    pub auth_method: AuthMethod,
+    pub location_id: String,
+    pub evse_uid: String,
+    pub connector_id: String,
+    pub currency: String,
+    pub charging_periods: Vec<ChargingPeriod>,
+    pub status: SessionStatus,
+    pub last_updated: DateTime<Utc>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConnectorStandard {
+    #[serde(rename = "CHADEMO")]
+    Chademo,
+    #[serde(rename = "DOMESTIC_F")]
+    DomesticF,
+    #[serde(rename = "IEC_62196_T1")]
+    Iec62196T1,
+    #[serde(rename = "IEC_62196_T1_COMBO")]
+    Iec62196T1Combo,
+    #[serde(rename = "IEC_62196_T2")]
+    Iec62196T2,
+    #[serde(rename = "IEC_62196_T2_COMBO")]
+    Iec62196T2Combo,
+    #[serde(rename = "TESLA_S")]
+    TeslaS,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ConnectorFormat {
+    Socket,
+    Cable,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PowerType {
+    #[serde(rename = "AC_1_PHASE")]
+    Ac1Phase,
+    #[serde(rename = "AC_3_PHASE")]
+    Ac3Phase,
+    #[serde(rename = "DC")]
+    Dc,
+}
+
+/// Where a CDR took place.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CdrLocation {
+    pub id: String,
+    pub address: String,
+    pub city: String,
+    pub country: String,
+    pub evse_uid: String,
+    pub evse_id: String,
+    pub connector_id: String,
+    pub connector_standard: ConnectorStandard,
+    pub connector_format: ConnectorFormat,
+    pub connector_power_type: PowerType,
+}
+
+/// A Charge Detail Record: the final account of a completed session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cdr {
+    pub country_code: String,
+    pub party_id: String,
+    pub id: String,
+    pub start_date_time: DateTime<Utc>,
+    pub end_date_time: DateTime<Utc>,
+    pub session_id: String,
+    pub cdr_token: CdrToken,
+    pub auth_method: AuthMethod,
+    pub cdr_location: CdrLocation,
+    pub currency: String,
+    pub charging_periods: Vec<ChargingPeriod>,
+    pub total_cost: Price,
+    /// In kWh.
+    pub total_energy: f64,
+    /// In hours.
+    pub total_time: f64,
+    pub last_updated: DateTime<Utc>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EvseStatus {
+    Available,
+    Blocked,
+    Charging,
+    Inoperative,
+    #[serde(rename = "OUTOFORDER")]
+    OutOfOrder,
+    Planned,
+    Removed,
+    Reserved,
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Connector {
+    pub id: String,
+    pub standard: ConnectorStandard,
+    pub format: ConnectorFormat,
+    pub power_type: PowerType,
+    pub max_voltage: i32,
+    pub max_amperage: i32,
+    pub last_updated: DateTime<Utc>,
+}
+
+/// An EVSE of a location, with its single connector: a connector of an
+/// OCPP 1.6 Charge Point.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Evse {
+    pub uid: String,
+    pub evse_id: String,
+    pub status: EvseStatus,
+    pub connectors: Vec<Connector>,
+    pub last_updated: DateTime<Utc>,
+}
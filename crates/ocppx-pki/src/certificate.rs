@@ -0,0 +1,491 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use ring::signature::{
+    UnparsedPublicKey, VerificationAlgorithm, ECDSA_P256_SHA256_ASN1, ECDSA_P256_SHA384_ASN1,
+    ECDSA_P384_SHA256_ASN1, ECDSA_P384_SHA384_ASN1, ED25519, RSA_PKCS1_2048_8192_SHA256,
+    RSA_PKCS1_2048_8192_SHA384, RSA_PKCS1_2048_8192_SHA512,
+};
+use yasna::{
+    models::{ObjectIdentifier, TaggedDerValue},
+    tags::{TAG_GENERALIZEDTIME, TAG_UTCTIME},
+    ASN1Error, ASN1ErrorKind, ASN1Result, Tag,
+};
+
+/// The `id-pe-authorityInfoAccess` extension.
+const AUTHORITY_INFO_ACCESS: &[u64] = &[1, 3, 6, 1, 5, 5, 7, 1, 1];
+
+/// The `id-ad-ocsp` access method.
+const OCSP_ACCESS: &[u64] = &[1, 3, 6, 1, 5, 5, 7, 48, 1];
+
+/// The `id-ce-extKeyUsage` extension.
+const EXTENDED_KEY_USAGE: &[u64] = &[2, 5, 29, 37];
+
+/// The `id-kp-OCSPSigning` key purpose.
+const OCSP_SIGNING: &[u64] = &[1, 3, 6, 1, 5, 5, 7, 3, 9];
+
+/// The `id-ce-subjectAltName` extension.
+const SUBJECT_ALT_NAME: &[u64] = &[2, 5, 29, 17];
+
+/// The `id-at-commonName` attribute.
+const COMMON_NAME: &[u64] = &[2, 5, 4, 3];
+
+/// The fields of a X.509 certificate used to identify it, and to verify
+/// its signature.
+#[derive(Debug, Clone)]
+pub struct Certificate {
+    pub der: Vec<u8>,
+    /// The DER-encoded `TBSCertificate`, i.e. the signed part.
+    pub tbs_certificate: Vec<u8>,
+    /// At most 20 bytes, per RFC 5280.
+    pub serial_number: Vec<u8>,
+    /// The DER-encoded name of the issuer.
+    pub issuer: Vec<u8>,
+    /// The DER-encoded name of the subject.
+    pub subject: Vec<u8>,
+    /// The common names of the subject.
+    pub common_names: Vec<String>,
+    /// The DNS names of the Subject Alternative Name extension.
+    pub dns_names: Vec<String>,
+    /// The content of the `subjectPublicKey` bit string.
+    pub public_key: Vec<u8>,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+    /// The URL of the OCSP responder, from the Authority Information
+    /// Access extension.
+    pub ocsp_responder_url: Option<String>,
+    /// Whether the Extended Key Usage extension allows signing OCSP
+    /// responses, for a delegated responder.
+    pub ocsp_signing: bool,
+    pub signature_algorithm: ObjectIdentifier,
+    pub signature: Vec<u8>,
+}
+
+/// The fields of a `TBSCertificate`.
+struct TbsCertificate {
+    serial_number: Vec<u8>,
+    issuer: Vec<u8>,
+    validity: (DateTime<Utc>, DateTime<Utc>),
+    subject: Vec<u8>,
+    public_key: Vec<u8>,
+    extensions: Extensions,
+}
+
+/// The extensions of a certificate.
+#[derive(Default)]
+struct Extensions {
+    ocsp_responder_url: Option<String>,
+    ocsp_signing: bool,
+    dns_names: Vec<String>,
+}
+
+impl Certificate {
+    /// Parse a DER certificate. `None` if it is invalid.
+    pub fn from_der(der: Vec<u8>) -> Option<Self> {
+        let (tbs_certificate, signature_algorithm, signature) = yasna::parse_der(&der, |reader| {
+            reader.read_sequence(|reader| {
+                let tbs_certificate = reader.next().read_der()?;
+                let signature_algorithm = reader.next().read_sequence(|reader| {
+                    let algorithm = reader.next().read_oid()?;
+                    // The parameters.
+                    reader.read_optional(|reader| reader.read_der())?;
+
+                    Ok(algorithm)
+                })?;
+                let (signature, _) = reader.next().read_bitvec_bytes()?;
+
+                Ok((tbs_certificate, signature_algorithm, signature))
+            })
+        })
+        .ok()?;
+
+        let TbsCertificate {
+            serial_number,
+            issuer,
+            validity: (not_before, not_after),
+            subject,
+            public_key,
+            extensions,
+        } = yasna::parse_der(&tbs_certificate, |reader| {
+            reader.read_sequence(|reader| {
+                reader.read_optional(|reader| {
+                    reader.read_tagged(Tag::context(0), |reader| reader.read_i64())
+                })?;
+                let (serial_number, _) = reader.next().read_bigint_bytes()?;
+                // The signature algorithm.
+                reader.next().read_der()?;
+                let issuer = reader.next().read_der()?;
+                let validity = reader.next().read_sequence(|reader| {
+                    Ok((
+                        parse_time(reader.next().read_tagged_der()?)?,
+                        parse_time(reader.next().read_tagged_der()?)?,
+                    ))
+                })?;
+                let subject = reader.next().read_der()?;
+                let public_key = reader.next().read_sequence(|reader| {
+                    reader.next().read_der()?;
+
+                    Ok(reader.next().read_bitvec_bytes()?.0)
+                })?;
+
+                // The unique identifiers, and the extensions.
+                let mut extensions = Extensions::default();
+
+                while let Some(field) = reader.read_optional(|reader| reader.read_tagged_der())? {
+                    if field.tag() == Tag::context(3) {
+                        extensions = parse_extensions(field.value())?;
+                    }
+                }
+
+                Ok(TbsCertificate {
+                    serial_number,
+                    issuer,
+                    validity,
+                    subject,
+                    public_key,
+                    extensions,
+                })
+            })
+        })
+        .ok()?;
+
+        // At most 20 bytes, per RFC 5280, so that it fits in the
+        // `serialNumber` of the certificate hash data.
+        if serial_number.iter().skip_while(|byte| **byte == 0).count() > 20 {
+            return None;
+        }
+
+        let common_names = parse_common_names(&subject).ok()?;
+
+        Some(Self {
+            der,
+            tbs_certificate,
+            serial_number,
+            issuer,
+            subject,
+            common_names,
+            dns_names: extensions.dns_names,
+            public_key,
+            not_before,
+            not_after,
+            ocsp_responder_url: extensions.ocsp_responder_url,
+            ocsp_signing: extensions.ocsp_signing,
+            signature_algorithm,
+            signature,
+        })
+    }
+
+    /// Parse the certificates of a PEM document, in order. `None` if there
+    /// is none, or if one of them is invalid.
+    pub fn parse_pem(pem: &str) -> Option<Vec<Self>> {
+        let certificates = pem::parse_many(pem)
+            .ok()?
+            .into_iter()
+            .filter(|pem| pem.tag() == "CERTIFICATE")
+            .map(|pem| Self::from_der(pem.into_contents()))
+            .collect::<Option<Vec<_>>>()?;
+
+        (!certificates.is_empty()).then_some(certificates)
+    }
+
+    pub fn to_pem(&self) -> String {
+        pem::encode(&pem::Pem::new("CERTIFICATE", self.der.clone()))
+    }
+
+    pub fn is_valid_at(&self, now: DateTime<Utc>) -> bool {
+        self.not_before <= now && now <= self.not_after
+    }
+
+    pub fn is_self_signed(&self) -> bool {
+        self.issuer == self.subject
+    }
+
+    /// Whether the certificate has been issued by `issuer`, and signed
+    /// with its key.
+    pub fn is_signed_by(&self, issuer: &Self) -> bool {
+        self.issuer == issuer.subject
+            && verify_signature(
+                &self.signature_algorithm,
+                &issuer.public_key,
+                &self.tbs_certificate,
+                &self.signature,
+            )
+    }
+
+    /// The serial number, in hexadecimal without leading zeros, as
+    /// expected by OCPP.
+    pub fn serial_number_hex(&self) -> String {
+        let serial_number = crate::hex(&self.serial_number);
+        let serial_number = serial_number.trim_start_matches('0');
+
+        if serial_number.is_empty() {
+            "0".to_owned()
+        } else {
+            serial_number.to_owned()
+        }
+    }
+}
+
+/// Find the extensions used by OCPP.
+fn parse_extensions(extensions: &[u8]) -> ASN1Result<Extensions> {
+    let mut parsed = Extensions::default();
+
+    yasna::parse_der(extensions, |reader| {
+        reader.read_sequence_of(|reader| {
+            reader.read_sequence(|reader| {
+                let id = reader.next().read_oid()?;
+                reader.read_optional(|reader| reader.read_bool())?;
+                let value = reader.next().read_bytes()?;
+
+                if id == ObjectIdentifier::from_slice(AUTHORITY_INFO_ACCESS) {
+                    parsed.ocsp_responder_url = parse_authority_info_access(&value)?;
+                } else if id == ObjectIdentifier::from_slice(EXTENDED_KEY_USAGE) {
+                    parsed.ocsp_signing = parse_extended_key_usage(&value)?;
+                } else if id == ObjectIdentifier::from_slice(SUBJECT_ALT_NAME) {
+                    parsed.dns_names = parse_subject_alt_name(&value)?;
+                }
+
+                Ok(())
+            })
+        })
+    })?;
+
+    Ok(parsed)
+}
+
+fn parse_authority_info_access(value: &[u8]) -> ASN1Result<Option<String>> {
+    let mut ocsp_responder_url = None;
+
+    yasna::parse_der(value, |reader| {
+        reader.read_sequence_of(|reader| {
+            reader.read_sequence(|reader| {
+                let method = reader.next().read_oid()?;
+                // A `uniformResourceIdentifier` general name.
+                let location = reader.next().read_tagged_der()?;
+
+                if method == ObjectIdentifier::from_slice(OCSP_ACCESS)
+                    && location.tag() == Tag::context(6)
+                    && ocsp_responder_url.is_none()
+                {
+                    ocsp_responder_url = String::from_utf8(location.value().to_vec()).ok();
+                }
+
+                Ok(())
+            })
+        })
+    })?;
+
+    Ok(ocsp_responder_url)
+}
+
+/// Whether the key purposes include `id-kp-OCSPSigning`.
+fn parse_extended_key_usage(value: &[u8]) -> ASN1Result<bool> {
+    let mut ocsp_signing = false;
+
+    yasna::parse_der(value, |reader| {
+        reader.read_sequence_of(|reader| {
+            ocsp_signing |= reader.read_oid()? == ObjectIdentifier::from_slice(OCSP_SIGNING);
+
+            Ok(())
+        })
+    })?;
+
+    Ok(ocsp_signing)
+}
+
+/// The `dNSName` general names of a Subject Alternative Name extension.
+fn parse_subject_alt_name(value: &[u8]) -> ASN1Result<Vec<String>> {
+    let mut dns_names = Vec::new();
+
+    yasna::parse_der(value, |reader| {
+        reader.read_sequence_of(|reader| {
+            let name = reader.read_tagged_der()?;
+
+            if name.tag() == Tag::context(2) {
+                if let Ok(dns_name) = String::from_utf8(name.value().to_vec()) {
+                    dns_names.push(dns_name);
+                }
+            }
+
+            Ok(())
+        })
+    })?;
+
+    Ok(dns_names)
+}
+
+/// The `commonName` attributes of a DER-encoded name.
+fn parse_common_names(name: &[u8]) -> ASN1Result<Vec<String>> {
+    let mut common_names = Vec::new();
+
+    yasna::parse_der(name, |reader| {
+        reader.read_sequence_of(|reader| {
+            reader.read_set_of(|reader| {
+                reader.read_sequence(|reader| {
+                    let kind = reader.next().read_oid()?;
+                    // A `DirectoryString`, whatever its encoding.
+                    let value = reader.next().read_tagged_der()?;
+
+                    if kind == ObjectIdentifier::from_slice(COMMON_NAME) {
+                        if let Ok(common_name) = String::from_utf8(value.value().to_vec()) {
+                            common_names.push(common_name);
+                        }
+                    }
+
+                    Ok(())
+                })
+            })
+        })
+    })?;
+
+    Ok(common_names)
+}
+
+/// Verify the `signature` of `message` with `public_key`, the content of
+/// a `subjectPublicKey`, given the OID of the signature algorithm.
+///
+/// ECDSA with P-256 or P-384, RSA PKCS #1 v1.5, and Ed25519 are
+/// supported; any other algorithm fails the verification.
+pub fn verify_signature(
+    signature_algorithm: &ObjectIdentifier,
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> bool {
+    let algorithm: &dyn VerificationAlgorithm = match (
+        signature_algorithm.components().as_slice(),
+        public_key.len(),
+    ) {
+        // `ecdsa-with-SHA256` and `ecdsa-with-SHA384`, with an
+        // uncompressed P-256 or P-384 point.
+        ([1, 2, 840, 10045, 4, 3, 2], 65) => &ECDSA_P256_SHA256_ASN1,
+        ([1, 2, 840, 10045, 4, 3, 2], 97) => &ECDSA_P384_SHA256_ASN1,
+        ([1, 2, 840, 10045, 4, 3, 3], 65) => &ECDSA_P256_SHA384_ASN1,
+        ([1, 2, 840, 10045, 4, 3, 3], 97) => &ECDSA_P384_SHA384_ASN1,
+        // `sha256WithRSAEncryption`, `sha384WithRSAEncryption` and
+        // `sha512WithRSAEncryption`.
+        ([1, 2, 840, 113549, 1, 1, 11], _) => &RSA_PKCS1_2048_8192_SHA256,
+        ([1, 2, 840, 113549, 1, 1, 12], _) => &RSA_PKCS1_2048_8192_SHA384,
+        ([1, 2, 840, 113549, 1, 1, 13], _) => &RSA_PKCS1_2048_8192_SHA512,
+        ([1, 3, 101, 112], 32) => &ED25519,
+        _ => return false,
+    };
+
+    UnparsedPublicKey::new(algorithm, public_key)
+        .verify(message, signature)
+        .is_ok()
+}
+
+/// Parse a `UTCTime` or a `GeneralizedTime`, as restricted by RFC 5280.
+pub fn parse_time(time: TaggedDerValue) -> ASN1Result<DateTime<Utc>> {
+    let invalid = || ASN1Error::new(ASN1ErrorKind::Invalid);
+    let value = std::str::from_utf8(time.value()).map_err(|_| invalid())?;
+
+    let value = match time.tag() {
+        TAG_UTCTIME if value.len() == 13 => {
+            let century = if value < "50" { "20" } else { "19" };
+
+            format!("{century}{value}")
+        }
+        TAG_GENERALIZEDTIME => value.to_owned(),
+        _ => return Err(invalid()),
+    };
+
+    NaiveDateTime::parse_from_str(&value, "%Y%m%d%H%M%SZ")
+        .map(|time| time.and_utc())
+        .map_err(|_| invalid())
+}
+
+/// Encode `time` as a `GeneralizedTime`.
+pub fn generalized_time(time: DateTime<Utc>) -> TaggedDerValue {
+    TaggedDerValue::from_tag_and_bytes(
+        TAG_GENERALIZEDTIME,
+        time.format("%Y%m%d%H%M%SZ").to_string().into_bytes(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESPONDER_URL: &str = "http://ocsp.example";
+
+    fn ca(common_name: &str) -> rcgen::CertificateParams {
+        let mut params = rcgen::CertificateParams::default();
+        params
+            .distinguished_name
+            .push(rcgen::DnType::CommonName, common_name);
+        params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+
+        params
+    }
+
+    #[test]
+    fn test_parse_pem() {
+        let root_key = rcgen::KeyPair::generate().unwrap();
+        let root = ca("Root").self_signed(&root_key).unwrap();
+
+        let mut leaf = rcgen::CertificateParams::new(vec!["cp001.example".to_owned()]).unwrap();
+        leaf.distinguished_name
+            .push(rcgen::DnType::CommonName, "CP001");
+        leaf.extended_key_usages = vec![rcgen::ExtendedKeyUsagePurpose::OcspSigning];
+        leaf.custom_extensions = vec![rcgen::CustomExtension::from_oid_content(
+            AUTHORITY_INFO_ACCESS,
+            yasna::construct_der(|writer| {
+                writer.write_sequence(|writer| {
+                    writer.next().write_sequence(|writer| {
+                        writer
+                            .next()
+                            .write_oid(&ObjectIdentifier::from_slice(OCSP_ACCESS));
+                        writer
+                            .next()
+                            .write_tagged_implicit(Tag::context(6), |writer| {
+                                writer.write_ia5_string(RESPONDER_URL)
+                            });
+                    })
+                })
+            }),
+        )];
+        let leaf = leaf
+            .signed_by(&rcgen::KeyPair::generate().unwrap(), &root, &root_key)
+            .unwrap();
+
+        let chain = Certificate::parse_pem(&format!("{}{}", leaf.pem(), root.pem())).unwrap();
+
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[0].issuer, chain[1].subject);
+        assert_eq!(chain[0].ocsp_responder_url.as_deref(), Some(RESPONDER_URL));
+        assert!(chain[0].ocsp_signing);
+        assert_eq!(chain[0].common_names, ["CP001"]);
+        assert_eq!(chain[0].dns_names, ["cp001.example"]);
+        assert_eq!(chain[1].common_names, ["Root"]);
+        assert!(chain[1].dns_names.is_empty());
+        assert_eq!(chain[1].ocsp_responder_url, None);
+        assert!(!chain[1].ocsp_signing);
+        assert!(chain[1].is_self_signed());
+        assert!(chain[0].is_valid_at(Utc::now()));
+        assert!(!chain[0].serial_number_hex().starts_with('0'));
+        assert_eq!(
+            crate::unhex(&chain[0].serial_number_hex()).unwrap(),
+            chain[0]
+                .serial_number
+                .strip_prefix(&[0])
+                .unwrap_or(&chain[0].serial_number)
+        );
+        assert_eq!(
+            Certificate::parse_pem(&chain[0].to_pem()).unwrap()[0].der,
+            chain[0].der
+        );
+
+        // The signatures.
+        assert!(chain[0].is_signed_by(&chain[1]));
+        assert!(chain[1].is_signed_by(&chain[1]));
+        assert!(!chain[1].is_signed_by(&chain[0]));
+
+        let other_key = rcgen::KeyPair::generate().unwrap();
+        let other = Certificate::parse_pem(&ca("Root").self_signed(&other_key).unwrap().pem())
+            .unwrap()
+            .remove(0);
+        assert!(!chain[0].is_signed_by(&other));
+
+        assert!(Certificate::parse_pem("garbage").is_none());
+    }
+}
@@ -0,0 +1,69 @@
+//! The X.509 helpers shared by the crates handling certificates: the
+//! security profile 3 of a Charge Point, the OCSP of ISO 15118, and the
+//! audit log of a Central System.
+//!
+//! A [`Certificate`] holds the fields needed to identify a certificate
+//! and to verify its signature, see [`verify_signature`]. The hashes of
+//! OCPP are in hexadecimal, see [`hash`], [`hex`] and [`unhex`].
+
+mod certificate;
+
+pub use certificate::{generalized_time, parse_time, verify_signature, Certificate};
+use ring::digest::{digest, SHA256, SHA384, SHA512};
+
+/// The hash algorithms of the `CertificateHashData` of OCPP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+/// Hash `data`, in hexadecimal.
+pub fn hash(hash_algorithm: HashAlgorithm, data: &[u8]) -> String {
+    let algorithm = match hash_algorithm {
+        HashAlgorithm::Sha256 => &SHA256,
+        HashAlgorithm::Sha384 => &SHA384,
+        HashAlgorithm::Sha512 => &SHA512,
+    };
+
+    hex(digest(algorithm, data).as_ref())
+}
+
+/// Encode `bytes` in lowercase hexadecimal.
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Decode hexadecimal, with an odd number of digits if the leading zero
+/// is missing.
+pub fn unhex(hex: &str) -> Option<Vec<u8>> {
+    // Serial numbers are sent without their leading zeros.
+    let hex = if hex.len() % 2 == 1 {
+        format!("0{hex}")
+    } else {
+        hex.to_owned()
+    };
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(hex(&[0x00, 0x2a, 0xff]), "002aff");
+        assert_eq!(unhex("2aff").unwrap(), [0x2a, 0xff]);
+        assert_eq!(unhex("aff").unwrap(), [0x0a, 0xff]);
+        assert_eq!(unhex("zz"), None);
+        assert_eq!(
+            hash(HashAlgorithm::Sha256, b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+}
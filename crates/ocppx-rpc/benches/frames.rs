@@ -0,0 +1,66 @@
+//! Throughput of the frame parsing, from a text frame to a typed payload,
+//! through a `Message` or through a `BorrowedMessage`.
+//!
+//! ```sh
+//! cargo bench -p ocppx-rpc --bench frames
+//! ```
+
+use ocppx_rpc::{BorrowedMessage, Message};
+use ocppx_types::v1_6::MeterValuesRequest;
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+const FRAME: &str = r#"[2,"19223201","MeterValues",{"connectorId":1,"transactionId":42,"meterValue":[{"timestamp":"2013-02-01T20:53:32.486Z","sampledValue":[{"value":"12345","context":"Sample.Periodic","measurand":"Energy.Active.Import.Register","unit":"Wh"},{"value":"7.4","context":"Sample.Periodic","measurand":"Power.Active.Import","phase":"L1-N","unit":"kW"},{"value":"16.2","context":"Sample.Periodic","measurand":"Current.Import","phase":"L1","unit":"A"},{"value":"80","context":"Sample.Periodic","measurand":"SoC","unit":"Percent"}]}]}]"#;
+
+const ITERATIONS: u32 = 200_000;
+
+fn bench<F>(name: &str, mut parse: F) -> Duration
+where
+    F: FnMut(&str),
+{
+    // Warm up.
+    for _ in 0..ITERATIONS / 10 {
+        parse(black_box(FRAME));
+    }
+
+    let started = Instant::now();
+
+    for _ in 0..ITERATIONS {
+        parse(black_box(FRAME));
+    }
+
+    let elapsed = started.elapsed();
+    println!(
+        "{name:>16}: {per_frame:?} per frame, {frames:.0} frames/s",
+        per_frame = elapsed / ITERATIONS,
+        frames = f64::from(ITERATIONS) / elapsed.as_secs_f64(),
+    );
+
+    elapsed
+}
+
+fn main() {
+    let owned = bench("Message", |frame| {
+        let Ok(Message::Call(call)) = frame.parse::<Message>() else {
+            unreachable!()
+        };
+
+        black_box(call.payload::<MeterValuesRequest>().unwrap());
+    });
+
+    let borrowed = bench("BorrowedMessage", |frame| {
+        let Ok(BorrowedMessage::Call(call)) = BorrowedMessage::parse(frame) else {
+            unreachable!()
+        };
+
+        black_box(call.payload::<MeterValuesRequest>().unwrap());
+    });
+
+    println!(
+        "{:>16}: {:.2}x",
+        "speedup",
+        owned.as_secs_f64() / borrowed.as_secs_f64()
+    );
+}
@@ -0,0 +1,283 @@
+use crate::{Call, CallError, CallResult, ErrorCode, Message, MessageTypeId, Result};
+use serde::{
+    de::{self, IgnoredAny, SeqAccess, Unexpected, Visitor},
+    Deserialize, Deserializer,
+};
+use serde_json::value::RawValue;
+use std::{borrow::Cow, fmt};
+
+/// An OCPP-J frame parsed without copying, unlike [`Message`].
+///
+/// The strings borrow from the frame, unless they contain escape sequences,
+/// and the payloads are kept as raw JSON until they are deserialized into
+/// typed payloads, without going through a `serde_json::Value`.
+///
+/// ```
+/// # use ocppx_rpc::BorrowedMessage;
+/// let frame = r#"[2,"19223201","Heartbeat",{}]"#;
+///
+/// let BorrowedMessage::Call(call) = BorrowedMessage::parse(frame).unwrap() else {
+///     unreachable!()
+/// };
+///
+/// assert_eq!(call.action, "Heartbeat");
+/// assert_eq!(call.payload.get(), "{}");
+/// ```
+#[derive(Debug, Clone)]
+pub enum BorrowedMessage<'a> {
+    Call(BorrowedCall<'a>),
+    CallResult(BorrowedCallResult<'a>),
+    CallError(BorrowedCallError<'a>),
+}
+
+/// A [`Call`] borrowed from its frame.
+#[derive(Debug, Clone)]
+pub struct BorrowedCall<'a> {
+    pub unique_id: Cow<'a, str>,
+    pub action: Cow<'a, str>,
+    pub payload: &'a RawValue,
+}
+
+/// A [`CallResult`] borrowed from its frame.
+#[derive(Debug, Clone)]
+pub struct BorrowedCallResult<'a> {
+    pub unique_id: Cow<'a, str>,
+    pub payload: &'a RawValue,
+}
+
+/// A [`CallError`] borrowed from its frame.
+#[derive(Debug, Clone)]
+pub struct BorrowedCallError<'a> {
+    pub unique_id: Cow<'a, str>,
+    pub error_code: ErrorCode,
+    pub error_description: Cow<'a, str>,
+    pub error_details: &'a RawValue,
+}
+
+impl<'a> BorrowedMessage<'a> {
+    /// Parse a text frame.
+    pub fn parse(frame: &'a str) -> Result<Self> {
+        Ok(serde_json::from_str(frame)?)
+    }
+
+    /// Parse a frame from its bytes, e.g. a WebSocket payload.
+    pub fn from_slice(frame: &'a [u8]) -> Result<Self> {
+        Ok(serde_json::from_slice(frame)?)
+    }
+
+    pub fn type_id(&self) -> MessageTypeId {
+        match self {
+            Self::Call(_) => MessageTypeId::Call,
+            Self::CallResult(_) => MessageTypeId::CallResult,
+            Self::CallError(_) => MessageTypeId::CallError,
+        }
+    }
+
+    pub fn unique_id(&self) -> &str {
+        match self {
+            Self::Call(BorrowedCall { unique_id, .. })
+            | Self::CallResult(BorrowedCallResult { unique_id, .. })
+            | Self::CallError(BorrowedCallError { unique_id, .. }) => unique_id,
+        }
+    }
+
+    /// Copy the frame into a [`Message`].
+    pub fn to_owned(&self) -> Result<Message> {
+        Ok(match self {
+            Self::Call(call) => Message::Call(Call {
+                unique_id: call.unique_id.clone().into_owned(),
+                action: call.action.clone().into_owned(),
+                payload: serde_json::from_str(call.payload.get())?,
+            }),
+            Self::CallResult(call_result) => Message::CallResult(CallResult {
+                unique_id: call_result.unique_id.clone().into_owned(),
+                payload: serde_json::from_str(call_result.payload.get())?,
+            }),
+            Self::CallError(call_error) => Message::CallError(CallError {
+                unique_id: call_error.unique_id.clone().into_owned(),
+                error_code: call_error.error_code.clone(),
+                error_description: call_error.error_description.clone().into_owned(),
+                error_details: serde_json::from_str(call_error.error_details.get())?,
+            }),
+        })
+    }
+}
+
+impl<'a> BorrowedCall<'a> {
+    /// Deserialize the payload into a typed payload, which can itself
+    /// borrow from the frame.
+    pub fn payload<P>(&self) -> Result<P>
+    where
+        P: Deserialize<'a>,
+    {
+        Ok(serde_json::from_str(self.payload.get())?)
+    }
+}
+
+impl<'a> BorrowedCallResult<'a> {
+    /// Deserialize the payload into a typed payload, which can itself
+    /// borrow from the frame.
+    pub fn payload<P>(&self) -> Result<P>
+    where
+        P: Deserialize<'a>,
+    {
+        Ok(serde_json::from_str(self.payload.get())?)
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for BorrowedMessage<'a> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(BorrowedMessageVisitor)
+    }
+}
+
+struct BorrowedMessageVisitor;
+
+impl<'de> Visitor<'de> for BorrowedMessageVisitor {
+    type Value = BorrowedMessage<'de>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an OCPP-J frame, i.e. an array starting with a message type ID")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut length = 0;
+
+        macro_rules! next {
+            ($seq:ident) => {{
+                length += 1;
+
+                $seq.next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(length - 1, &self))?
+            }};
+        }
+
+        let type_id: u8 = next!(seq);
+        let type_id = MessageTypeId::try_from(type_id).map_err(|type_id| {
+            de::Error::invalid_value(Unexpected::Unsigned(type_id.into()), &"2, 3 or 4")
+        })?;
+        let CowStr(unique_id) = next!(seq);
+
+        let message = match type_id {
+            MessageTypeId::Call => {
+                let CowStr(action) = next!(seq);
+
+                BorrowedMessage::Call(BorrowedCall {
+                    unique_id,
+                    action,
+                    payload: next!(seq),
+                })
+            }
+
+            MessageTypeId::CallResult => BorrowedMessage::CallResult(BorrowedCallResult {
+                unique_id,
+                payload: next!(seq),
+            }),
+
+            MessageTypeId::CallError => {
+                let error_code = next!(seq);
+                let CowStr(error_description) = next!(seq);
+
+                BorrowedMessage::CallError(BorrowedCallError {
+                    unique_id,
+                    error_code,
+                    error_description,
+                    error_details: next!(seq),
+                })
+            }
+        };
+
+        if seq.next_element::<IgnoredAny>()?.is_some() {
+            return Err(de::Error::invalid_length(
+                length + 1,
+                &"no more elements in the frame",
+            ));
+        }
+
+        Ok(message)
+    }
+}
+
+/// A string borrowed from the frame when possible. `Cow<str>` itself is
+/// always deserialized as an owned string.
+struct CowStr<'a>(Cow<'a, str>);
+
+impl<'de> Deserialize<'de> for CowStr<'de> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct CowStrVisitor;
+
+        impl<'de> Visitor<'de> for CowStrVisitor {
+            type Value = CowStr<'de>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a string")
+            }
+
+            fn visit_borrowed_str<E>(self, value: &'de str) -> std::result::Result<Self::Value, E> {
+                Ok(CowStr(Cow::Borrowed(value)))
+            }
+
+            fn visit_str<E>(self, value: &str) -> std::result::Result<Self::Value, E> {
+                Ok(CowStr(Cow::Owned(value.to_owned())))
+            }
+
+            fn visit_string<E>(self, value: String) -> std::result::Result<Self::Value, E> {
+                Ok(CowStr(Cow::Owned(value)))
+            }
+        }
+
+        deserializer.deserialize_str(CowStrVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_borrowed_message() {
+        let frame = r#"[2,"19223201","Authorize",{"idTag":"ABC1"}]"#;
+        let BorrowedMessage::Call(call) = BorrowedMessage::parse(frame).unwrap() else {
+            panic!("not a `Call`");
+        };
+
+        assert!(matches!(call.unique_id, Cow::Borrowed("19223201")));
+        assert!(matches!(call.action, Cow::Borrowed("Authorize")));
+        assert_eq!(
+            call.payload::<ocppx_types::v1_6::AuthorizeRequest>()
+                .unwrap()
+                .id_tag,
+            "ABC1"
+        );
+        assert_eq!(
+            BorrowedMessage::Call(call).to_owned().unwrap(),
+            frame.parse::<Message>().unwrap()
+        );
+
+        let frame = r#"[4,"19\"22","NotImplemented","Unknown action",{"a":1}]"#;
+        let message = BorrowedMessage::from_slice(frame.as_bytes()).unwrap();
+
+        assert_eq!(message.unique_id(), "19\"22");
+        assert_eq!(
+            message.to_owned().unwrap(),
+            Message::CallError(CallError {
+                unique_id: "19\"22".to_owned(),
+                error_code: ErrorCode::NotImplemented,
+                error_description: "Unknown action".to_owned(),
+                error_details: json!({"a": 1}),
+            })
+        );
+
+        assert!(BorrowedMessage::parse(r#"[3,"19223201",{},{}]"#).is_err());
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{Error, ErrorCode, Message, MessageTypeId, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+/// A request, sent by either the Charge Point or the Central System.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Call {
+    pub unique_id: String,
+    pub action: String,
+    pub payload: Value,
+}
+
+impl Call {
+    /// Create a new `Call` from a typed payload, e.g.
+    /// `ocppx_types::v1_6::HeartbeatRequest`.
+    pub fn new<P>(
+        unique_id: impl Into<String>,
+        action: impl Into<String>,
+        payload: &P,
+    ) -> Result<Self>
+    where
+        P: Serialize,
+    {
+        Ok(Self {
+            unique_id: unique_id.into(),
+            action: action.into(),
+            payload: serde_json::to_value(payload)?,
+        })
+    }
+
+    /// Convert the payload into a typed payload.
+    pub fn payload<P>(&self) -> Result<P>
+    where
+        P: DeserializeOwned,
+    {
+        Ok(P::deserialize(&self.payload)?)
+    }
+}
+
+/// A successful response to a [`Call`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallResult {
+    pub unique_id: String,
+    pub payload: Value,
+}
+
+impl CallResult {
+    /// Create a new `CallResult` from a typed payload, e.g.
+    /// `ocppx_types::v1_6::HeartbeatResponse`.
+    pub fn new<P>(unique_id: impl Into<String>, payload: &P) -> Result<Self>
+    where
+        P: Serialize,
+    {
+        Ok(Self {
+            unique_id: unique_id.into(),
+            payload: serde_json::to_value(payload)?,
+        })
+    }
+
+    /// Convert the payload into a typed payload.
+    pub fn payload<P>(&self) -> Result<P>
+    where
+        P: DeserializeOwned,
+    {
+        Ok(P::deserialize(&self.payload)?)
+    }
+}
+
+/// An error response to a [`Call`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallError {
+    pub unique_id: String,
+    pub error_code: ErrorCode,
+    pub error_description: String,
+    pub error_details: Value,
+}
+
+impl CallError {
+    /// Create a new `CallError`. `error_details` is sent as an empty object
+    /// when `None`.
+    pub fn new(
+        unique_id: impl Into<String>,
+        error_code: ErrorCode,
+        error_description: impl Into<String>,
+        error_details: Option<Value>,
+    ) -> Self {
+        Self {
+            unique_id: unique_id.into(),
+            error_code,
+            error_description: error_description.into(),
+            error_details: error_details.unwrap_or_else(|| Value::Object(Default::default())),
+        }
+    }
+}
+
+macro_rules! impl_from_message {
+    ($( $type:ident ),*) => {
+        $(
+            impl From<$type> for Message {
+                fn from(value: $type) -> Self {
+                    Self::$type(value)
+                }
+            }
+
+            impl TryFrom<Message> for $type {
+                type Error = Error;
+
+                fn try_from(message: Message) -> Result<Self> {
+                    match message {
+                        Message::$type(value) => Ok(value),
+                        message => Err(Error::UnexpectedMessageType {
+                            expected: MessageTypeId::$type,
+                            got: message.type_id(),
+                        }),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_message!(Call, CallResult, CallError);
@@ -0,0 +1,306 @@
+use crate::{Direction, Error, Message, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    sync::mpsc::{self, Receiver, SyncSender},
+    thread,
+    time::Duration,
+};
+use tokio::time;
+
+/// A frame captured by a [`Recorder`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapturedFrame {
+    pub timestamp: DateTime<Utc>,
+    pub direction: Direction,
+    pub charge_point_id: String,
+    /// The frame, in its wire format.
+    pub frame: String,
+}
+
+impl CapturedFrame {
+    /// The frame, parsed.
+    pub fn message(&self) -> Result<Message> {
+        self.frame.parse()
+    }
+}
+
+/// How many frames can wait to be written before new ones are dropped.
+const CAPACITY: usize = 1024;
+
+enum Command {
+    Record(CapturedFrame),
+    Flush(mpsc::Sender<io::Result<()>>),
+}
+
+/// Record every frame received or sent in an append-only file, one
+/// [`CapturedFrame`] per line (JSON Lines).
+///
+/// The frames are written by a dedicated thread, so that recording never
+/// blocks the connections. The recorder is cheap to clone: clones append
+/// to the same file.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    commands: SyncSender<Command>,
+}
+
+impl Recorder {
+    /// Append to the capture at `path`, creating it if it does not exist.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let (commands, receiver) = mpsc::sync_channel(CAPACITY);
+
+        thread::Builder::new()
+            .name("ocppx-capture".to_owned())
+            .spawn(move || write(BufWriter::new(file), receiver))?;
+
+        Ok(Self { commands })
+    }
+
+    /// Record `frame`, received or sent now.
+    pub fn record(&self, direction: Direction, charge_point_id: &str, frame: &str) {
+        let captured_frame = CapturedFrame {
+            timestamp: Utc::now(),
+            direction,
+            charge_point_id: charge_point_id.to_owned(),
+            frame: frame.to_owned(),
+        };
+
+        // A capture is a debugging aid: failing to write it, or dropping
+        // frames when the disk is too slow, must not break the connection.
+        let _ = self.commands.try_send(Command::Record(captured_frame));
+    }
+
+    /// Wait until the frames recorded so far are written.
+    ///
+    /// It blocks the current thread.
+    pub fn flush(&self) -> io::Result<()> {
+        let (sender, receiver) = mpsc::channel();
+
+        self.commands
+            .send(Command::Flush(sender))
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+
+        receiver
+            .recv()
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?
+    }
+}
+
+/// Write the frames sent to a [`Recorder`], until all its clones are
+/// dropped. The file is flushed each time no more frames are waiting.
+fn write(mut file: BufWriter<File>, commands: Receiver<Command>) {
+    while let Ok(command) = commands.recv() {
+        let mut flushes = vec![];
+
+        for command in [command].into_iter().chain(commands.try_iter()) {
+            match command {
+                Command::Record(captured_frame) => {
+                    if let Ok(line) = serde_json::to_string(&captured_frame) {
+                        let _ = writeln!(file, "{line}");
+                    }
+                }
+                Command::Flush(flushed) => flushes.push(flushed),
+            }
+        }
+
+        let result = file.flush();
+
+        for flushed in flushes {
+            let _ = flushed.send(
+                result
+                    .as_ref()
+                    .map(|_| ())
+                    .map_err(|error| io::Error::new(error.kind(), error.to_string())),
+            );
+        }
+    }
+}
+
+/// Play back a recorded session, with the delays between the frames.
+#[derive(Debug, Clone)]
+pub struct Replayer {
+    frames: Vec<CapturedFrame>,
+    /// `None` to play without delays.
+    speed: Option<f64>,
+}
+
+impl Replayer {
+    pub fn new(frames: Vec<CapturedFrame>) -> Self {
+        Self {
+            frames,
+            speed: Some(1.0),
+        }
+    }
+
+    /// Read the capture at `path`, written by a [`Recorder`].
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let frames = BufReader::new(File::open(path)?)
+            .lines()
+            .filter(|line| !line.as_ref().is_ok_and(|line| line.trim().is_empty()))
+            .map(|line| {
+                serde_json::from_str(&line?)
+                    .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+            })
+            .collect::<io::Result<_>>()?;
+
+        Ok(Self::new(frames))
+    }
+
+    /// Play the session `speed` times faster than it has been recorded.
+    ///
+    /// Fails with [`Error::InvalidSpeed`] if `speed` is not positive and
+    /// finite, see [`Self::without_delays`] instead.
+    pub fn speed(mut self, speed: f64) -> Result<Self> {
+        if !(speed.is_finite() && speed > 0.0) {
+            return Err(Error::InvalidSpeed(speed));
+        }
+
+        self.speed = Some(speed);
+
+        Ok(self)
+    }
+
+    /// Play the session as fast as possible.
+    pub fn without_delays(mut self) -> Self {
+        self.speed = None;
+
+        self
+    }
+
+    pub fn frames(&self) -> &[CapturedFrame] {
+        &self.frames
+    }
+
+    /// Only keep the frames of `charge_point_id`.
+    pub fn charge_point(mut self, charge_point_id: &str) -> Self {
+        self.frames
+            .retain(|frame| frame.charge_point_id == charge_point_id);
+
+        self
+    }
+
+    /// Play the session back, from its first frame.
+    pub fn play(&self) -> Playback<'_> {
+        Playback {
+            replayer: self,
+            next: 0,
+        }
+    }
+}
+
+/// A recorded session being played back, see [`Replayer::play`].
+#[derive(Debug)]
+pub struct Playback<'a> {
+    replayer: &'a Replayer,
+    next: usize,
+}
+
+impl<'a> Playback<'a> {
+    /// The next frame, once the time that separated it from the previous
+    /// frame in the capture has passed.
+    pub async fn next(&mut self) -> Option<&'a CapturedFrame> {
+        let frames = &self.replayer.frames;
+        let frame = frames.get(self.next)?;
+
+        let previous = self.next.checked_sub(1).map(|previous| &frames[previous]);
+
+        if let (Some(previous), Some(speed)) = (previous, self.replayer.speed) {
+            let delay = (frame.timestamp - previous.timestamp)
+                .to_std()
+                .unwrap_or_default()
+                .as_secs_f64()
+                / speed;
+
+            // A delay too long to be slept is a broken capture: skip it.
+            if let Ok(delay) = Duration::try_from_secs_f64(delay) {
+                time::sleep(delay).await;
+            }
+        }
+
+        self.next += 1;
+
+        Some(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_record_and_replay() {
+        let path = env::temp_dir().join(format!("ocppx-capture-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let recorder = Recorder::open(&path).unwrap();
+        recorder.record(Direction::Incoming, "CP001", r#"[2,"1","Heartbeat",{}]"#);
+        recorder.record(
+            Direction::Outgoing,
+            "CP001",
+            r#"[3,"1",{"currentTime":"2013-02-01T20:53:32.486Z"}]"#,
+        );
+        recorder.record(Direction::Incoming, "CP002", r#"[2,"1","Heartbeat",{}]"#);
+        recorder.flush().unwrap();
+
+        let replayer = Replayer::open(&path)
+            .unwrap()
+            .without_delays()
+            .charge_point("CP001");
+        std::fs::remove_file(&path).unwrap();
+
+        let mut playback = replayer.play();
+        let mut replayed = vec![];
+
+        while let Some(frame) = playback.next().await {
+            replayed.push((
+                frame.direction,
+                frame.message().unwrap().unique_id().to_owned(),
+            ));
+        }
+
+        assert_eq!(
+            replayed,
+            [
+                (Direction::Incoming, "1".to_owned()),
+                (Direction::Outgoing, "1".to_owned())
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_speed() {
+        let frame = |timestamp: &str| CapturedFrame {
+            timestamp: timestamp.parse().unwrap(),
+            direction: Direction::Incoming,
+            charge_point_id: "CP001".to_owned(),
+            frame: r#"[2,"1","Heartbeat",{}]"#.to_owned(),
+        };
+        let replayer = Replayer::new(vec![
+            frame("2013-02-01T20:53:00Z"),
+            frame("2013-02-01T20:53:10Z"),
+        ]);
+
+        for speed in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                replayer.clone().speed(speed),
+                Err(Error::InvalidSpeed(_))
+            ));
+        }
+
+        let replayer = replayer.speed(2.0).unwrap();
+        let mut playback = replayer.play();
+        let start = time::Instant::now();
+
+        playback.next().await.unwrap();
+        playback.next().await.unwrap();
+
+        assert_eq!(start.elapsed(), Duration::from_secs(5));
+        assert!(playback.next().await.is_none());
+    }
+}
@@ -0,0 +1,726 @@
+use crate::deflate::{self, Inflater};
+use std::{
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// The name of the extension, in `Sec-WebSocket-Extensions`.
+const PERMESSAGE_DEFLATE: &str = "permessage-deflate";
+
+/// The largest message decompressed by default, as `tungstenite` accepts.
+const DEFAULT_MAX_MESSAGE_SIZE: usize = 64 << 20;
+
+/// The `permessage-deflate` WebSocket extension (RFC 7692), which
+/// compresses the messages: the OCPP messages are verbose JSON, and
+/// compress well.
+///
+/// The messages are compressed independently of each other, which is
+/// always allowed, and the peer may refer to its previous messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compression {
+    /// The size of the window of the compression, in bits, from 9 to 15. A
+    /// smaller window uses less memory, and compresses less. The peer can
+    /// lower it during the negotiation.
+    pub window_bits: u8,
+    /// Size of the smallest message to compress, in bytes: the smaller
+    /// ones are sent as they are.
+    pub threshold: usize,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self {
+            window_bits: 15,
+            threshold: 256,
+        }
+    }
+}
+
+impl Compression {
+    fn window_bits(&self) -> u8 {
+        self.window_bits.clamp(9, 15)
+    }
+
+    /// The `Sec-WebSocket-Extensions` header of a client handshake.
+    pub fn offer(&self) -> String {
+        format!("{PERMESSAGE_DEFLATE}; client_max_window_bits")
+    }
+
+    /// The `Sec-WebSocket-Extensions` header of the response of a server
+    /// to the `offers` of a client, or `None` if none of them can be
+    /// accepted.
+    pub fn accept(&self, offers: &str) -> Option<String> {
+        offers.split(',').find_map(|offer| {
+            let mut parameters = offer.split(';').map(str::trim);
+
+            if parameters.next() != Some(PERMESSAGE_DEFLATE) {
+                return None;
+            }
+
+            let mut server_no_context_takeover = false;
+            let mut server_max_window_bits = None;
+
+            for parameter in parameters {
+                match parameter
+                    .split_once('=')
+                    .map(|(name, value)| (name.trim(), value.trim().trim_matches('"')))
+                {
+                    None if parameter == "server_no_context_takeover" => {
+                        server_no_context_takeover = true
+                    }
+                    None if parameter == "client_no_context_takeover"
+                        || parameter == "client_max_window_bits" => {}
+                    Some(("server_max_window_bits", value)) => {
+                        server_max_window_bits = Some(window_bits(value)?)
+                    }
+                    Some(("client_max_window_bits", value)) => {
+                        window_bits(value)?;
+                    }
+                    _ => return None,
+                }
+            }
+
+            let mut response = PERMESSAGE_DEFLATE.to_owned();
+
+            // The messages never refer to the previous ones anyway.
+            if server_no_context_takeover {
+                response.push_str("; server_no_context_takeover");
+            }
+
+            let window_bits = server_max_window_bits.unwrap_or(15).min(self.window_bits());
+
+            if server_max_window_bits.is_some() || window_bits < 15 {
+                response.push_str(&format!("; server_max_window_bits={window_bits}"));
+            }
+
+            Some(response)
+        })
+    }
+}
+
+fn window_bits(value: &str) -> Option<u8> {
+    value
+        .parse()
+        .ok()
+        .filter(|window_bits| (8..=15).contains(window_bits))
+}
+
+/// The window to compress with, given the response of the server.
+fn negotiate(compression: &Compression, role: Role, head: &[u8]) -> Option<u8> {
+    let head = String::from_utf8_lossy(head);
+    let parameter = match role {
+        Role::Client => "client_max_window_bits",
+        Role::Server => "server_max_window_bits",
+    };
+
+    head.lines()
+        .skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(name, _)| name.trim().eq_ignore_ascii_case("sec-websocket-extensions"))
+        .flat_map(|(_, value)| value.split(','))
+        .find_map(|extension| {
+            let mut parameters = extension.split(';').map(str::trim);
+
+            if parameters.next() != Some(PERMESSAGE_DEFLATE) {
+                return None;
+            }
+
+            let window_bits = parameters
+                .filter_map(|parameter| parameter.split_once('='))
+                .find(|(name, _)| name.trim() == parameter)
+                .and_then(|(_, value)| window_bits(value.trim().trim_matches('"')))
+                .unwrap_or(15);
+
+            Some(window_bits.min(compression.window_bits()))
+        })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Client,
+    Server,
+}
+
+/// A stream carrying a WebSocket connection, compressing and
+/// decompressing its messages if [`Compression`] is negotiated by its
+/// handshake. It sits below `tungstenite`, which does not support the
+/// extension.
+///
+/// The compressed messages are decompressed before `tungstenite` reads
+/// them, and the messages written by `tungstenite` are compressed when
+/// they are complete, i.e. not fragmented, and at least as large as
+/// [`Compression::threshold`].
+#[derive(Debug)]
+pub struct Compressed<S> {
+    inner: S,
+    state: Option<Box<State>>,
+}
+
+impl<S> Compressed<S> {
+    /// The stream of a client, which offers `compression` in its handshake.
+    /// The stream is left as it is when `None`.
+    pub fn client(inner: S, compression: Option<Compression>) -> Self {
+        Self::new(inner, compression, Role::Client)
+    }
+
+    /// The stream of a server, which accepts `compression` in its
+    /// handshake. The stream is left as it is when `None`.
+    pub fn server(inner: S, compression: Option<Compression>) -> Self {
+        Self::new(inner, compression, Role::Server)
+    }
+
+    fn new(inner: S, compression: Option<Compression>, role: Role) -> Self {
+        Self {
+            inner,
+            state: compression.map(|compression| {
+                Box::new(State {
+                    compression,
+                    role,
+                    max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+                    window_bits: None,
+                    read_head: Some(Vec::new()),
+                    read_input: Vec::new(),
+                    read_output: Vec::new(),
+                    read_position: 0,
+                    fragments: None,
+                    inflater: Inflater::default(),
+                    write_head: Some(Vec::new()),
+                    write_input: Vec::new(),
+                    write_output: Vec::new(),
+                    write_position: 0,
+                })
+            }),
+        }
+    }
+
+    /// Size of the largest message to decompress, in bytes. Defaults to
+    /// 64 MiB.
+    pub fn max_message_size(mut self, max_message_size: usize) -> Self {
+        if let Some(state) = self.state.as_mut() {
+            state.max_message_size = max_message_size;
+        }
+
+        self
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    compression: Compression,
+    role: Role,
+    max_message_size: usize,
+    /// The window to compress with, once the extension is negotiated.
+    window_bits: Option<u8>,
+    /// The HTTP head read so far, until it ends.
+    read_head: Option<Vec<u8>>,
+    read_input: Vec<u8>,
+    read_output: Vec<u8>,
+    read_position: usize,
+    /// The opcode and the payload of a compressed message being received
+    /// in fragments.
+    fragments: Option<(u8, Vec<u8>)>,
+    inflater: Inflater,
+    /// The HTTP head written so far, until it ends.
+    write_head: Option<Vec<u8>>,
+    write_input: Vec<u8>,
+    write_output: Vec<u8>,
+    write_position: usize,
+}
+
+impl State {
+    /// Process bytes read from the peer.
+    fn read(&mut self, mut bytes: &[u8]) -> io::Result<()> {
+        if let Some(head) = self.read_head.as_mut() {
+            match pass_head(head, bytes, &mut self.read_output) {
+                Some(rest) => {
+                    if self.role == Role::Client {
+                        self.window_bits = negotiate(&self.compression, self.role, head);
+                    }
+
+                    self.read_head = None;
+                    bytes = rest;
+                }
+                None => return Ok(()),
+            }
+        }
+
+        self.read_input.extend_from_slice(bytes);
+
+        let mut consumed = 0;
+
+        while let Some((frame, length)) =
+            Frame::parse(&self.read_input[consumed..], self.max_message_size)?
+        {
+            let raw = &self.read_input[consumed..consumed + length];
+            consumed += length;
+
+            let message = match self.fragments.as_mut() {
+                // A control frame, in the middle of a message.
+                _ if frame.opcode >= 8 => None,
+                Some((opcode, payload)) if frame.opcode == 0 => {
+                    payload.extend_from_slice(&frame.payload);
+
+                    if payload.len() > self.max_message_size {
+                        return Err(too_large());
+                    }
+
+                    if !frame.fin {
+                        continue;
+                    }
+
+                    let opcode = *opcode;
+                    let payload = self.fragments.take().map(|(_, payload)| payload);
+
+                    payload.map(|payload| (opcode, payload))
+                }
+                None if frame.rsv1
+                    && self.window_bits.is_some()
+                    && matches!(frame.opcode, 1 | 2) =>
+                {
+                    if !frame.fin {
+                        self.fragments = Some((frame.opcode, frame.payload));
+
+                        continue;
+                    }
+
+                    Some((frame.opcode, frame.payload))
+                }
+                _ => None,
+            };
+
+            match message {
+                Some((opcode, payload)) => {
+                    let payload = self.inflater.inflate(&payload, self.max_message_size)?;
+                    // `tungstenite` expects the frames of a client to be
+                    // masked.
+                    let mask = (self.role == Role::Server).then_some([0; 4]);
+
+                    Frame::write(&mut self.read_output, opcode, false, mask, &payload);
+                }
+                None => self.read_output.extend_from_slice(raw),
+            }
+        }
+
+        self.read_input.drain(..consumed);
+
+        Ok(())
+    }
+
+    /// Process bytes written to the peer.
+    fn write(&mut self, mut bytes: &[u8]) -> io::Result<()> {
+        if let Some(head) = self.write_head.as_mut() {
+            match pass_head(head, bytes, &mut self.write_output) {
+                Some(rest) => {
+                    if self.role == Role::Server {
+                        self.window_bits = negotiate(&self.compression, self.role, head);
+                    }
+
+                    self.write_head = None;
+                    bytes = rest;
+                }
+                None => return Ok(()),
+            }
+        }
+
+        self.write_input.extend_from_slice(bytes);
+
+        let mut consumed = 0;
+
+        while let Some((frame, length)) = Frame::parse(&self.write_input[consumed..], usize::MAX)? {
+            let raw = &self.write_input[consumed..consumed + length];
+            consumed += length;
+
+            let compressed = match self.window_bits {
+                Some(window_bits)
+                    if frame.fin
+                        && !frame.rsv1
+                        && matches!(frame.opcode, 1 | 2)
+                        && frame.payload.len() >= self.compression.threshold =>
+                {
+                    Some(deflate::compress(&frame.payload, window_bits))
+                        .filter(|compressed| compressed.len() < frame.payload.len())
+                }
+                _ => None,
+            };
+
+            match compressed {
+                Some(compressed) => Frame::write(
+                    &mut self.write_output,
+                    frame.opcode,
+                    true,
+                    frame.mask,
+                    &compressed,
+                ),
+                None => self.write_output.extend_from_slice(raw),
+            }
+        }
+
+        self.write_input.drain(..consumed);
+
+        Ok(())
+    }
+}
+
+/// Pass `bytes` to `output` until the end of the HTTP head, which is kept
+/// in `head`. Return the bytes following the head, once it has ended.
+fn pass_head<'a>(head: &mut Vec<u8>, bytes: &'a [u8], output: &mut Vec<u8>) -> Option<&'a [u8]> {
+    let previous_length = head.len();
+    head.extend_from_slice(bytes);
+
+    let start = previous_length.saturating_sub(3);
+
+    match head[start..]
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+    {
+        Some(position) => {
+            let end = start + position + 4;
+            let length = end - previous_length;
+
+            head.truncate(end);
+            output.extend_from_slice(&bytes[..length]);
+
+            Some(&bytes[length..])
+        }
+        None => {
+            output.extend_from_slice(bytes);
+
+            None
+        }
+    }
+}
+
+fn too_large() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "the message is too large")
+}
+
+/// A WebSocket frame, with its payload unmasked.
+struct Frame {
+    fin: bool,
+    rsv1: bool,
+    opcode: u8,
+    mask: Option<[u8; 4]>,
+    payload: Vec<u8>,
+}
+
+impl Frame {
+    /// Parse the frame at the start of `bytes`, and return it with its
+    /// length, or `None` if it is not complete yet.
+    fn parse(bytes: &[u8], max_size: usize) -> io::Result<Option<(Self, usize)>> {
+        let [first, second, ..] = *bytes else {
+            return Ok(None);
+        };
+
+        let (payload_length, mut offset) = match second & 0x7f {
+            126 => match bytes.get(2..4) {
+                Some(length) => (u64::from(u16::from_be_bytes([length[0], length[1]])), 4),
+                None => return Ok(None),
+            },
+            127 => match bytes.get(2..10) {
+                Some(length) => (u64::from_be_bytes(length.try_into().expect("8 bytes")), 10),
+                None => return Ok(None),
+            },
+            length => (u64::from(length), 2),
+        };
+        let payload_length = usize::try_from(payload_length)
+            .ok()
+            .filter(|length| *length <= max_size)
+            .ok_or_else(too_large)?;
+
+        let mask = if second & 0x80 != 0 {
+            let Some(mask) = bytes.get(offset..offset + 4) else {
+                return Ok(None);
+            };
+            offset += 4;
+
+            Some(<[u8; 4]>::try_from(mask).expect("4 bytes"))
+        } else {
+            None
+        };
+
+        let Some(payload) = bytes.get(offset..offset + payload_length) else {
+            return Ok(None);
+        };
+        let mut payload = payload.to_vec();
+
+        if let Some(mask) = mask {
+            apply_mask(&mut payload, mask);
+        }
+
+        Ok(Some((
+            Self {
+                fin: first & 0x80 != 0,
+                rsv1: first & 0x40 != 0,
+                opcode: first & 0x0f,
+                mask,
+                payload,
+            },
+            offset + payload_length,
+        )))
+    }
+
+    /// Write a complete frame, i.e. with `FIN`, to `output`.
+    fn write(output: &mut Vec<u8>, opcode: u8, rsv1: bool, mask: Option<[u8; 4]>, payload: &[u8]) {
+        output.push(0x80 | if rsv1 { 0x40 } else { 0 } | opcode);
+
+        let masked = if mask.is_some() { 0x80 } else { 0 };
+
+        match payload.len() {
+            length @ 0..=125 => output.push(masked | length as u8),
+            length @ 126..=0xffff => {
+                output.push(masked | 126);
+                output.extend_from_slice(&(length as u16).to_be_bytes());
+            }
+            length => {
+                output.push(masked | 127);
+                output.extend_from_slice(&(length as u64).to_be_bytes());
+            }
+        }
+
+        if let Some(mask) = mask {
+            output.extend_from_slice(&mask);
+        }
+
+        let start = output.len();
+        output.extend_from_slice(payload);
+
+        if let Some(mask) = mask {
+            apply_mask(&mut output[start..], mask);
+        }
+    }
+}
+
+fn apply_mask(payload: &mut [u8], mask: [u8; 4]) {
+    for (index, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[index % 4];
+    }
+}
+
+impl<S> Compressed<S>
+where
+    S: AsyncWrite + Unpin,
+{
+    /// Write the processed bytes to the inner stream.
+    fn poll_drain(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let Some(state) = self.state.as_mut() else {
+            return Poll::Ready(Ok(()));
+        };
+
+        while state.write_position < state.write_output.len() {
+            let written = ready!(Pin::new(&mut self.inner)
+                .poll_write(cx, &state.write_output[state.write_position..]))?;
+
+            if written == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+
+            state.write_position += written;
+        }
+
+        state.write_output.clear();
+        state.write_position = 0;
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S> AsyncRead for Compressed<S>
+where
+    S: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        let Some(state) = this.state.as_mut() else {
+            return Pin::new(&mut this.inner).poll_read(cx, buf);
+        };
+
+        loop {
+            if state.read_position < state.read_output.len() {
+                let available = &state.read_output[state.read_position..];
+                let length = available.len().min(buf.remaining());
+
+                buf.put_slice(&available[..length]);
+                state.read_position += length;
+
+                if state.read_position == state.read_output.len() {
+                    state.read_output.clear();
+                    state.read_position = 0;
+                }
+
+                return Poll::Ready(Ok(()));
+            }
+
+            let mut chunk = [0; 8192];
+            let mut chunk = ReadBuf::new(&mut chunk);
+            ready!(Pin::new(&mut this.inner).poll_read(cx, &mut chunk))?;
+
+            // The end of the stream.
+            if chunk.filled().is_empty() {
+                return Poll::Ready(Ok(()));
+            }
+
+            state.read(chunk.filled())?;
+        }
+    }
+}
+
+impl<S> AsyncWrite for Compressed<S>
+where
+    S: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        if this.state.is_none() {
+            return Pin::new(&mut this.inner).poll_write(cx, buf);
+        }
+
+        // Push back until the previous bytes are written.
+        ready!(this.poll_drain(cx))?;
+
+        if let Some(state) = this.state.as_mut() {
+            state.write(buf)?;
+        }
+
+        // The rest is written on the next write, or on flush.
+        if let Poll::Ready(Err(error)) = this.poll_drain(cx) {
+            return Poll::Ready(Err(error));
+        }
+
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_drain(cx))?;
+
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_drain(cx))?;
+
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiation() {
+        let compression = Compression::default();
+
+        assert_eq!(
+            compression.accept("permessage-deflate; client_max_window_bits"),
+            Some("permessage-deflate".to_owned())
+        );
+        assert_eq!(
+            compression.accept(
+                "permessage-deflate; unknown, permessage-deflate; server_no_context_takeover; server_max_window_bits=10"
+            ),
+            Some(
+                "permessage-deflate; server_no_context_takeover; server_max_window_bits=10"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            compression.accept("permessage-deflate; server_max_window_bits=16"),
+            None
+        );
+        assert_eq!(
+            Compression {
+                window_bits: 12,
+                ..Compression::default()
+            }
+            .accept(&compression.offer()),
+            Some("permessage-deflate; server_max_window_bits=12".to_owned())
+        );
+
+        let head = b"HTTP/1.1 101 Switching Protocols\r\n\
+                     sec-websocket-extensions: permessage-deflate; server_max_window_bits=10\r\n\r\n";
+        assert_eq!(negotiate(&compression, Role::Server, head), Some(10));
+        assert_eq!(negotiate(&compression, Role::Client, head), Some(15));
+        assert_eq!(
+            negotiate(
+                &compression,
+                Role::Client,
+                b"HTTP/1.1 101 Switching Protocols\r\n\r\n"
+            ),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compressed() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let compression = Compression {
+            threshold: 16,
+            ..Compression::default()
+        };
+        let (client, mut server) = tokio::io::duplex(1 << 16);
+        let mut client = Compressed::client(client, Some(compression));
+
+        let request = b"GET /CP001 HTTP/1.1\r\n\r\n";
+        client.write_all(request).await.unwrap();
+        let mut head = vec![0; request.len()];
+        server.read_exact(&mut head).await.unwrap();
+
+        let response = b"HTTP/1.1 101 Switching Protocols\r\n\
+                         Sec-WebSocket-Extensions: permessage-deflate\r\n\r\n";
+        server.write_all(response).await.unwrap();
+        let mut head = vec![0; response.len()];
+        client.read_exact(&mut head).await.unwrap();
+        assert_eq!(head, response);
+
+        // A text frame, masked by the client, large enough to be compressed.
+        let message = br#"[2,"1","Heartbeat",{}][2,"1","Heartbeat",{}]"#;
+        let mut frame = Vec::new();
+        Frame::write(&mut frame, 1, false, Some([1, 2, 3, 4]), message);
+        client.write_all(&frame).await.unwrap();
+        client.flush().await.unwrap();
+
+        let mut header = [0; 6];
+        server.read_exact(&mut header).await.unwrap();
+        assert_eq!(header[0], 0xc1);
+        assert_eq!(&header[2..], &[1, 2, 3, 4]);
+        let mut payload = vec![0; usize::from(header[1] & 0x7f)];
+        server.read_exact(&mut payload).await.unwrap();
+        apply_mask(&mut payload, [1, 2, 3, 4]);
+        assert_eq!(
+            Inflater::default().inflate(&payload, 1024).unwrap(),
+            message.to_vec()
+        );
+
+        // A compressed frame of the server is decompressed.
+        let mut frame = Vec::new();
+        Frame::write(&mut frame, 1, true, None, &deflate::compress(message, 15));
+        server.write_all(&frame).await.unwrap();
+        let mut received = vec![0; 2 + message.len()];
+        client.read_exact(&mut received).await.unwrap();
+        assert_eq!(received[..2], [0x81, message.len() as u8]);
+        assert_eq!(&received[2..], message);
+    }
+}
@@ -0,0 +1,558 @@
+//! A raw DEFLATE (RFC 1951) codec, for the `permessage-deflate` extension
+//! of WebSocket.
+//!
+//! The messages are compressed with the fixed Huffman codes, which spares
+//! sending the code tables: the OCPP messages are small, and their
+//! redundancy is mostly in the repeated keys, that LZ77 catches. Any
+//! DEFLATE stream is decompressed.
+
+use std::io;
+
+/// The largest window of DEFLATE, i.e. 15 bits.
+const MAX_WINDOW: usize = 1 << 15;
+
+/// The longest match of LZ77.
+const MAX_MATCH: usize = 258;
+
+/// How many earlier positions with the same hash are tried for a match.
+const MAX_CHAIN: usize = 64;
+
+const LENGTH_BASES: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA_BITS: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASES: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA_BITS: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// The order of the lengths of the code length codes, in a dynamic block.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Compress `data` in a single block, with matches at most `1 <<
+/// window_bits` bytes away, and end it with an empty stored block, without
+/// its `00 00 ff ff` trailer, as `permessage-deflate` expects.
+pub(crate) fn compress(data: &[u8], window_bits: u8) -> Vec<u8> {
+    let window = (1usize << window_bits.clamp(8, 15)).min(MAX_WINDOW);
+    let mut writer = BitWriter::default();
+
+    // Not the final block, with the fixed Huffman codes.
+    writer.write(0, 1);
+    writer.write(1, 2);
+
+    // The last position of each hash of 3 bytes, and the previous position
+    // with the same hash of each position.
+    let mut heads = vec![usize::MAX; MAX_WINDOW];
+    let mut previous = vec![usize::MAX; data.len()];
+    let hash = |position: usize| {
+        let bytes = &data[position..position + 3];
+
+        ((usize::from(bytes[0]) << 10) ^ (usize::from(bytes[1]) << 5) ^ usize::from(bytes[2]))
+            & (MAX_WINDOW - 1)
+    };
+    let insert = |position: usize, heads: &mut [usize], previous: &mut [usize]| {
+        if position + 3 <= data.len() {
+            let hash = hash(position);
+            previous[position] = heads[hash];
+            heads[hash] = position;
+        }
+    };
+
+    let mut position = 0;
+
+    while position < data.len() {
+        let mut best = (0, 0);
+
+        if position + 3 <= data.len() {
+            let mut candidate = heads[hash(position)];
+            let longest = (data.len() - position).min(MAX_MATCH);
+
+            for _ in 0..MAX_CHAIN {
+                if candidate == usize::MAX || position - candidate > window {
+                    break;
+                }
+
+                let length = data[candidate..]
+                    .iter()
+                    .zip(&data[position..position + longest])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+
+                if length > best.0 {
+                    best = (length, position - candidate);
+
+                    if length == longest {
+                        break;
+                    }
+                }
+
+                candidate = previous[candidate];
+            }
+        }
+
+        if best.0 >= 3 {
+            let (length, distance) = best;
+
+            write_length(&mut writer, length);
+            write_distance(&mut writer, distance);
+
+            for position in position..position + length {
+                insert(position, &mut heads, &mut previous);
+            }
+
+            position += length;
+        } else {
+            write_literal(&mut writer, u16::from(data[position]));
+            insert(position, &mut heads, &mut previous);
+            position += 1;
+        }
+    }
+
+    // The end of the block.
+    write_literal(&mut writer, 256);
+
+    // An empty stored block, not final: its header, then the padding to
+    // the next byte. Its `00 00 ff ff` lengths are left out.
+    writer.write(0, 3);
+    writer.finish()
+}
+
+fn write_literal(writer: &mut BitWriter, symbol: u16) {
+    let (code, length) = match symbol {
+        0..=143 => (0x30 + symbol, 8),
+        144..=255 => (0x190 + symbol - 144, 9),
+        256..=279 => (symbol - 256, 7),
+        _ => (0xc0 + symbol - 280, 8),
+    };
+
+    writer.write_code(code, length);
+}
+
+fn write_length(writer: &mut BitWriter, length: usize) {
+    let index = LENGTH_BASES
+        .iter()
+        .rposition(|base| usize::from(*base) <= length)
+        .expect("a match is at least 3 bytes long");
+
+    write_literal(writer, 257 + index as u16);
+    writer.write(
+        (length - usize::from(LENGTH_BASES[index])) as u32,
+        LENGTH_EXTRA_BITS[index],
+    );
+}
+
+fn write_distance(writer: &mut BitWriter, distance: usize) {
+    let index = DISTANCE_BASES
+        .iter()
+        .rposition(|base| usize::from(*base) <= distance)
+        .expect("a distance is at least 1");
+
+    writer.write_code(index as u16, 5);
+    writer.write(
+        (distance - usize::from(DISTANCE_BASES[index])) as u32,
+        DISTANCE_EXTRA_BITS[index],
+    );
+}
+
+/// Write the bits from the least significant one, as DEFLATE does.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    length: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, bits: u32, length: u8) {
+        for index in 0..length {
+            self.buffer |= ((bits >> index) & 1) << self.length;
+            self.length += 1;
+
+            if self.length == 8 {
+                self.bytes.push(self.buffer as u8);
+                self.buffer = 0;
+                self.length = 0;
+            }
+        }
+    }
+
+    /// Write a Huffman code, from its most significant bit.
+    fn write_code(&mut self, code: u16, length: u8) {
+        let reversed = u32::from(code.reverse_bits() >> (16 - length));
+
+        self.write(reversed, length);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.length > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+
+        self.bytes
+    }
+}
+
+/// Decompress the messages of a peer, which can refer to the previous
+/// ones: the last 32 KiB are kept.
+#[derive(Debug, Default)]
+pub(crate) struct Inflater {
+    history: Vec<u8>,
+}
+
+impl Inflater {
+    /// Decompress a message, without its `00 00 ff ff` trailer, into at
+    /// most `max_size` bytes.
+    pub(crate) fn inflate(&mut self, data: &[u8], max_size: usize) -> io::Result<Vec<u8>> {
+        let mut input = data.to_vec();
+        input.extend_from_slice(&[0, 0, 0xff, 0xff]);
+
+        let mut reader = BitReader {
+            bytes: &input,
+            position: 0,
+            bit: 0,
+        };
+        let mut output = std::mem::take(&mut self.history);
+        let start = output.len();
+        // Checked while decoding: a block can expand a thousandfold.
+        let limit = start.saturating_add(max_size);
+
+        while !reader.is_at_end() {
+            let last = reader.bits(1)? == 1;
+
+            match reader.bits(2)? {
+                0 => stored(&mut reader, &mut output, limit)?,
+                1 => codes(
+                    &mut reader,
+                    &mut output,
+                    limit,
+                    &fixed_literals(),
+                    &fixed_distances(),
+                )?,
+                2 => {
+                    let (literals, distances) = dynamic_tables(&mut reader)?;
+
+                    codes(&mut reader, &mut output, limit, &literals, &distances)?
+                }
+                _ => return Err(invalid("invalid DEFLATE block type")),
+            }
+
+            if last {
+                break;
+            }
+        }
+
+        let message = output[start..].to_vec();
+        output.drain(..output.len().saturating_sub(MAX_WINDOW));
+        self.history = output;
+
+        Ok(message)
+    }
+}
+
+fn invalid(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Check that `length` more bytes in `output` stay within `limit`.
+fn check_limit(output: &[u8], length: usize, limit: usize) -> io::Result<()> {
+    if output.len() + length > limit {
+        return Err(invalid("the decompressed message is too large"));
+    }
+
+    Ok(())
+}
+
+/// Read the bits from the least significant one.
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    bit: u8,
+}
+
+impl BitReader<'_> {
+    fn is_at_end(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+
+    fn bits(&mut self, length: u8) -> io::Result<u32> {
+        let mut bits = 0;
+
+        for index in 0..length {
+            let byte = self
+                .bytes
+                .get(self.position)
+                .ok_or_else(|| invalid("truncated DEFLATE stream"))?;
+            bits |= u32::from((byte >> self.bit) & 1) << index;
+
+            self.bit += 1;
+
+            if self.bit == 8 {
+                self.bit = 0;
+                self.position += 1;
+            }
+        }
+
+        Ok(bits)
+    }
+
+    fn align(&mut self) {
+        if self.bit > 0 {
+            self.bit = 0;
+            self.position += 1;
+        }
+    }
+
+    fn bytes(&mut self, length: usize) -> io::Result<&[u8]> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + length)
+            .ok_or_else(|| invalid("truncated DEFLATE stream"))?;
+        self.position += length;
+
+        Ok(bytes)
+    }
+}
+
+/// A canonical Huffman code, decoded a bit at a time.
+struct Huffman {
+    /// The number of codes of each length.
+    counts: [u16; 16],
+    /// The symbols, by code.
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+
+        for length in lengths {
+            counts[usize::from(*length)] += 1;
+        }
+
+        counts[0] = 0;
+
+        let mut symbols = (0..lengths.len() as u16)
+            .filter(|symbol| lengths[usize::from(*symbol)] > 0)
+            .collect::<Vec<_>>();
+        symbols.sort_by_key(|symbol| lengths[usize::from(*symbol)]);
+
+        Self { counts, symbols }
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> io::Result<u16> {
+        let mut code = 0i32;
+        let mut first = 0i32;
+        let mut index = 0i32;
+
+        for count in &self.counts[1..] {
+            code |= reader.bits(1)? as i32;
+            let count = i32::from(*count);
+
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        Err(invalid("invalid Huffman code"))
+    }
+}
+
+fn fixed_literals() -> Huffman {
+    let mut lengths = [8; 288];
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+
+    Huffman::new(&lengths)
+}
+
+fn fixed_distances() -> Huffman {
+    Huffman::new(&[5; 30])
+}
+
+fn dynamic_tables(reader: &mut BitReader) -> io::Result<(Huffman, Huffman)> {
+    let literals = reader.bits(5)? as usize + 257;
+    let distances = reader.bits(5)? as usize + 1;
+    let code_lengths = reader.bits(4)? as usize + 4;
+
+    let mut lengths = [0; 19];
+
+    for index in CODE_LENGTH_ORDER.iter().take(code_lengths) {
+        lengths[*index] = reader.bits(3)? as u8;
+    }
+
+    let code_lengths = Huffman::new(&lengths);
+    let mut lengths = Vec::with_capacity(literals + distances);
+
+    while lengths.len() < literals + distances {
+        let (length, repeat) = match code_lengths.decode(reader)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => (
+                *lengths
+                    .last()
+                    .ok_or_else(|| invalid("no length to repeat"))?,
+                3 + reader.bits(2)?,
+            ),
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+
+        lengths.extend(std::iter::repeat_n(length, repeat as usize));
+    }
+
+    if lengths.len() > literals + distances {
+        return Err(invalid("too many code lengths"));
+    }
+
+    Ok((
+        Huffman::new(&lengths[..literals]),
+        Huffman::new(&lengths[literals..]),
+    ))
+}
+
+fn stored(reader: &mut BitReader, output: &mut Vec<u8>, limit: usize) -> io::Result<()> {
+    reader.align();
+
+    let lengths = reader.bytes(4)?;
+    let length = u16::from_le_bytes([lengths[0], lengths[1]]);
+
+    if length != !u16::from_le_bytes([lengths[2], lengths[3]]) {
+        return Err(invalid("invalid stored block length"));
+    }
+
+    check_limit(output, length.into(), limit)?;
+    output.extend_from_slice(reader.bytes(length.into())?);
+
+    Ok(())
+}
+
+fn codes(
+    reader: &mut BitReader,
+    output: &mut Vec<u8>,
+    limit: usize,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> io::Result<()> {
+    loop {
+        let symbol = literals.decode(reader)?;
+
+        match symbol {
+            0..=255 => {
+                check_limit(output, 1, limit)?;
+                output.push(symbol as u8);
+            }
+            256 => return Ok(()),
+            _ => {
+                let index = usize::from(symbol - 257);
+                let length = usize::from(
+                    *LENGTH_BASES
+                        .get(index)
+                        .ok_or_else(|| invalid("invalid length symbol"))?,
+                ) + reader.bits(LENGTH_EXTRA_BITS[index])? as usize;
+
+                let index = usize::from(distances.decode(reader)?);
+                let distance = usize::from(
+                    *DISTANCE_BASES
+                        .get(index)
+                        .ok_or_else(|| invalid("invalid distance symbol"))?,
+                ) + reader.bits(DISTANCE_EXTRA_BITS[index])? as usize;
+
+                if distance > output.len() {
+                    return Err(invalid("the distance is too far back"));
+                }
+
+                check_limit(output, length, limit)?;
+
+                let start = output.len() - distance;
+
+                for index in start..start + length {
+                    output.push(output[index]);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let message = br#"[2,"19223201","MeterValues",{"connectorId":1,"meterValue":[{"timestamp":"2013-02-01T20:53:32.486Z","sampledValue":[{"value":"1000","measurand":"Energy.Active.Import.Register"},{"value":"1001","measurand":"Energy.Active.Import.Register"}]}]}]"#;
+        let mut inflater = Inflater::default();
+
+        for window_bits in [9, 15] {
+            let compressed = compress(message, window_bits);
+            assert!(compressed.len() < message.len());
+            assert_eq!(
+                inflater.inflate(&compressed, 1 << 20).unwrap(),
+                message.to_vec()
+            );
+        }
+
+        assert_eq!(inflater.inflate(&compress(b"", 15), 1 << 20).unwrap(), b"");
+        assert!(inflater.inflate(&compress(message, 15), 16).is_err());
+
+        // A bomb is stopped at the limit, not at the end of its block.
+        let bomb = compress(&vec![0; 1 << 20], 15);
+        assert!(bomb.len() < 1 << 13);
+        assert!(Inflater::default().inflate(&bomb, 1024).is_err());
+    }
+
+    #[test]
+    fn test_inflate() {
+        let mut inflater = Inflater::default();
+
+        // The example of RFC 7692, section 7.2.3.1, then the same message
+        // referring to the first one, as with a context takeover.
+        assert_eq!(
+            inflater
+                .inflate(&[0xf2, 0x48, 0xcd, 0xc9, 0xc9, 0x07, 0x00], 1024)
+                .unwrap(),
+            b"Hello"
+        );
+        assert_eq!(
+            inflater
+                .inflate(&[0xf2, 0x00, 0x11, 0x00, 0x00], 1024)
+                .unwrap(),
+            b"Hello"
+        );
+
+        // A stored block (RFC 7692, section 7.2.3.3), and a dynamic one.
+        assert_eq!(
+            inflater
+                .inflate(
+                    &[0x00, 0x05, 0x00, 0xfa, 0xff, 0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x00],
+                    1024
+                )
+                .unwrap(),
+            b"Hello"
+        );
+        assert_eq!(
+            inflater.inflate(DYNAMIC, 1024).unwrap(),
+            br#"[2,"19223201","Heartbeat",{}][2,"19223202","Heartbeat",{}][2,"19223203","Heartbeat",{}]"#
+        );
+    }
+
+    /// Compressed by zlib, with Huffman codes only.
+    const DYNAMIC: &[u8] = &[
+        0x04, 0xc1, 0xb1, 0x0d, 0x00, 0x20, 0x0c, 0x03, 0xb0, 0x5f, 0x32, 0x67, 0xa0, 0xe9, 0xc4,
+        0x07, 0xfc, 0x80, 0x18, 0x8a, 0xd4, 0x07, 0x50, 0x37, 0xc4, 0xef, 0xd8, 0x53, 0x84, 0x75,
+        0xc9, 0xd5, 0x0c, 0xc4, 0xc8, 0x38, 0xb5, 0x33, 0x0a, 0xbc, 0x6f, 0x4d, 0x11, 0xd6, 0x25,
+        0x57, 0x13, 0x88, 0x91, 0x71, 0x6a, 0x67, 0x14, 0x78, 0xdf, 0x9a, 0x22, 0xac, 0x4b, 0xae,
+        0xe6, 0x20, 0x46, 0xc6, 0xa9, 0x9d, 0x51, 0xe0, 0x7d, 0xeb, 0x03,
+    ];
+}
@@ -0,0 +1,180 @@
+use crate::CallError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+/// The member of the `errorDetails` holding the [`FieldError`]s.
+const FIELD_ERRORS: &str = "validationErrors";
+
+/// A field of a payload that is not valid, carried in the `errorDetails` of
+/// a [`CallError`], see [`CallError::with_field_errors`].
+///
+/// On the wire, the field errors are a `validationErrors` array of objects
+/// with a `path`, a `constraint`, and optionally the `expected` and `got`
+/// values and a `message`:
+///
+/// ```json
+/// {"validationErrors": [{"path": "/idTag", "constraint": "maxLength", "expected": 20, "got": 24}]}
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldError {
+    /// JSON Pointer to the field, e.g. `/meterValue/0/timestamp`, or an
+    /// empty string for the whole payload.
+    pub path: String,
+    /// The violated constraint, e.g. `required`, `maxLength` or `enum`.
+    pub constraint: String,
+    /// What the constraint expects, e.g. the maximum length.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected: Option<Value>,
+    /// What the field is, e.g. its length.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub got: Option<Value>,
+    /// A human-readable description of the error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl FieldError {
+    pub fn new(path: impl Into<String>, constraint: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            constraint: constraint.into(),
+            expected: None,
+            got: None,
+            message: None,
+        }
+    }
+
+    pub fn expected(mut self, expected: impl Into<Value>) -> Self {
+        self.expected = Some(expected.into());
+
+        self
+    }
+
+    pub fn got(mut self, got: impl Into<Value>) -> Self {
+        self.got = Some(got.into());
+
+        self
+    }
+
+    pub fn message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+
+        self
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "`{}`: violates `{}`", self.path, self.constraint)?;
+
+        match (&self.expected, &self.got) {
+            (Some(expected), Some(got)) => write!(formatter, " (expected {expected}, got {got})")?,
+            (Some(expected), None) => write!(formatter, " (expected {expected})")?,
+            (None, Some(got)) => write!(formatter, " (got {got})")?,
+            (None, None) => {}
+        }
+
+        if let Some(message) = &self.message {
+            write!(formatter, ": {message}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl CallError {
+    /// Attach `field_errors` to the `errorDetails`, next to their other
+    /// members, if any.
+    pub fn with_field_errors<I>(mut self, field_errors: I) -> Self
+    where
+        I: IntoIterator<Item = FieldError>,
+    {
+        let field_errors = field_errors
+            .into_iter()
+            .map(|field_error| {
+                serde_json::to_value(field_error).expect("a field error is always serializable")
+            })
+            .collect();
+
+        if !self.error_details.is_object() {
+            self.error_details = Value::Object(Default::default());
+        }
+
+        if let Value::Object(details) = &mut self.error_details {
+            details.insert(FIELD_ERRORS.to_owned(), Value::Array(field_errors));
+        }
+
+        self
+    }
+
+    /// The field errors of the `errorDetails`, see
+    /// [`CallError::with_field_errors`]. The entries that are not field
+    /// errors, e.g. sent by a peer with another format, are skipped.
+    pub fn field_errors(&self) -> Vec<FieldError> {
+        let Some(Value::Array(field_errors)) = self.error_details.get(FIELD_ERRORS) else {
+            return Vec::new();
+        };
+
+        field_errors
+            .iter()
+            .filter_map(|field_error| FieldError::deserialize(field_error).ok())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrorCode;
+    use serde_json::json;
+
+    #[test]
+    fn test_field_errors() {
+        let call_error = CallError::new(
+            "1",
+            ErrorCode::PropertyConstraintViolation,
+            "",
+            Some(json!({ "vendor": "ocppx" })),
+        )
+        .with_field_errors([
+            FieldError::new("/idTag", "maxLength").expected(20).got(24),
+            FieldError::new("", "required").message("`connectorId` is missing"),
+        ]);
+
+        assert_eq!(
+            call_error.error_details,
+            json!({
+                "vendor": "ocppx",
+                "validationErrors": [
+                    { "path": "/idTag", "constraint": "maxLength", "expected": 20, "got": 24 },
+                    { "path": "", "constraint": "required", "message": "`connectorId` is missing" },
+                ],
+            })
+        );
+
+        let field_errors = call_error.field_errors();
+        assert_eq!(
+            field_errors[0].to_string(),
+            "`/idTag`: violates `maxLength` (expected 20, got 24)"
+        );
+        assert_eq!(
+            field_errors[1].to_string(),
+            "``: violates `required`: `connectorId` is missing"
+        );
+
+        // Details in another format are not field errors.
+        let call_error = CallError::new(
+            "2",
+            ErrorCode::FormationViolation,
+            "",
+            Some(
+                json!({ "validationErrors": [{ "field": "idTag" }, { "path": "/a", "constraint": "type" }] }),
+            ),
+        );
+        assert_eq!(call_error.field_errors(), [FieldError::new("/a", "type")]);
+        assert!(CallError::new("3", ErrorCode::GenericError, "", None)
+            .field_errors()
+            .is_empty());
+    }
+}
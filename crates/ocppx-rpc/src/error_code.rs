@@ -0,0 +1,137 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+
+macro_rules! error_codes {
+    ($( $(#[$meta:meta])* $variant:ident ),* $(,)?) => {
+        /// The error code of a [`CallError`][crate::CallError].
+        ///
+        /// It covers the codes of OCPP-J 1.6 and 2.0.1. Any other code is
+        /// kept verbatim in [`ErrorCode::Other`].
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum ErrorCode {
+            $(
+                $(#[$meta])*
+                $variant,
+            )*
+            /// A code that is not defined by OCPP-J.
+            Other(String),
+        }
+
+        impl ErrorCode {
+            /// The code as sent on the wire.
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $( Self::$variant => stringify!($variant), )*
+                    Self::Other(code) => code,
+                }
+            }
+        }
+
+        impl FromStr for ErrorCode {
+            type Err = std::convert::Infallible;
+
+            fn from_str(code: &str) -> Result<Self, Self::Err> {
+                Ok(match code {
+                    $( stringify!($variant) => Self::$variant, )*
+                    code => Self::Other(code.to_owned()),
+                })
+            }
+        }
+    };
+}
+
+error_codes! {
+    /// The requested action is not known by the receiver.
+    NotImplemented,
+    /// The requested action is recognized but not supported by the
+    /// receiver.
+    NotSupported,
+    /// An internal error occurred and the receiver was not able to process
+    /// the requested action successfully.
+    InternalError,
+    /// The payload for the action is incomplete.
+    ProtocolError,
+    /// During the processing of the action a security issue occurred,
+    /// preventing the receiver from completing the action successfully.
+    SecurityError,
+    /// The payload for the action is syntactically incorrect or does not
+    /// conform to the PDU structure for the action (OCPP 1.6).
+    FormationViolation,
+    /// The payload for the action is syntactically incorrect (OCPP 2.0.1).
+    FormatViolation,
+    /// The payload is syntactically correct but at least one field
+    /// contains an invalid value.
+    PropertyConstraintViolation,
+    /// The payload is syntactically correct but at least one of the fields
+    /// violates occurrence constraints (OCPP 1.6, misspelled by the
+    /// specification).
+    OccurenceConstraintViolation,
+    /// The payload is syntactically correct but at least one of the fields
+    /// violates occurrence constraints (OCPP 2.0.1).
+    OccurrenceConstraintViolation,
+    /// The payload is syntactically correct but at least one of the fields
+    /// violates data type constraints.
+    TypeConstraintViolation,
+    /// A message with a message type ID unknown to the receiver was
+    /// received (OCPP 2.0.1).
+    MessageTypeNotSupported,
+    /// The content of the frame is not a valid RPC frame (OCPP 2.0.1).
+    RpcFrameworkError,
+    /// Any other error not covered by the other codes.
+    GenericError,
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl From<&str> for ErrorCode {
+    fn from(code: &str) -> Self {
+        match code.parse() {
+            Ok(code) => code,
+            Err(infallible) => match infallible {},
+        }
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ErrorCode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let code = String::deserialize(deserializer)?;
+
+        code.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for code in [
+            ErrorCode::NotImplemented,
+            ErrorCode::OccurenceConstraintViolation,
+            ErrorCode::RpcFrameworkError,
+            ErrorCode::Other("VendorSpecific".to_owned()),
+        ] {
+            let json = serde_json::to_string(&code).unwrap();
+
+            assert_eq!(json, format!("\"{code}\""));
+            assert_eq!(serde_json::from_str::<ErrorCode>(&json).unwrap(), code);
+        }
+    }
+}
@@ -0,0 +1,411 @@
+use crate::{Frame, TransportError};
+use futures_util::{Sink, Stream};
+use std::{
+    borrow::Cow,
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio_tungstenite::tungstenite::{
+    error::{CapacityError, ProtocolError},
+    protocol::{frame::coding::CloseCode, CloseFrame},
+};
+
+/// The size of the largest frame to receive, by default: 16 MiB.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 16 << 20;
+
+/// Size of the header of a frame: its opcode, then its length.
+const HEADER_SIZE: usize = 5;
+
+/// The frames are flushed before sending more, past this size.
+const WRITE_BUFFER_SIZE: usize = 64 << 10;
+
+const TEXT: u8 = 0x1;
+const BINARY: u8 = 0x2;
+const CLOSE: u8 = 0x8;
+const PING: u8 = 0x9;
+const PONG: u8 = 0xa;
+
+/// A [`Transport`][crate::Transport] over any byte stream, e.g. a plain TCP
+/// connection for an embedded gateway, or a serial line tunnelled to the
+/// Central System.
+///
+/// Each frame is written as its opcode, on 1 byte, as in WebSocket, then
+/// the length of its payload, on 4 bytes in big endian, then its payload.
+/// The close frames carry their code and their reason as in WebSocket too.
+/// The pings are answered with pongs.
+#[derive(Debug)]
+pub struct FramedTransport<S> {
+    inner: S,
+    max_frame_size: usize,
+    read_buffer: Vec<u8>,
+    write_buffer: Vec<u8>,
+    /// Whether a close frame has been sent: nothing is sent after it.
+    closed: bool,
+}
+
+impl<S> FramedTransport<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            read_buffer: Vec::new(),
+            write_buffer: Vec::new(),
+            closed: false,
+        }
+    }
+
+    /// Size of the largest frame to receive, in bytes. Defaults to
+    /// [`DEFAULT_MAX_FRAME_SIZE`].
+    pub fn max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+
+        self
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// The next frame of the read buffer, if complete.
+    fn decode(&mut self) -> Result<Option<Frame>, InvalidFrame> {
+        let Some(header) = self.read_buffer.get(..HEADER_SIZE) else {
+            return Ok(None);
+        };
+        let opcode = header[0];
+        let size = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+
+        if size > self.max_frame_size {
+            return Err(InvalidFrame::TooLong {
+                size,
+                max_size: self.max_frame_size,
+            });
+        }
+
+        if self.read_buffer.len() < HEADER_SIZE + size {
+            return Ok(None);
+        }
+
+        let payload = self.read_buffer[HEADER_SIZE..HEADER_SIZE + size].to_vec();
+        self.read_buffer.drain(..HEADER_SIZE + size);
+
+        Ok(Some(match opcode {
+            TEXT => Frame::Text(String::from_utf8(payload).map_err(|_| InvalidFrame::Utf8)?),
+            BINARY => Frame::Binary(payload),
+            PING => Frame::Ping(payload),
+            PONG => Frame::Pong(payload),
+            CLOSE => Frame::Close(match payload.get(..2) {
+                Some(code) => Some(CloseFrame {
+                    code: CloseCode::from(u16::from_be_bytes([code[0], code[1]])),
+                    reason: Cow::Owned(
+                        String::from_utf8(payload[2..].to_vec()).map_err(|_| InvalidFrame::Utf8)?,
+                    ),
+                }),
+                None => None,
+            }),
+            opcode => return Err(InvalidFrame::Opcode(opcode)),
+        }))
+    }
+
+    /// Append `frame` to the write buffer.
+    fn encode(&mut self, frame: Frame) -> Result<(), InvalidFrame> {
+        let (opcode, payload) = match frame {
+            Frame::Text(text) => (TEXT, text.into_bytes()),
+            Frame::Binary(payload) => (BINARY, payload),
+            Frame::Ping(payload) => (PING, payload),
+            Frame::Pong(payload) => (PONG, payload),
+            Frame::Close(close) => (
+                CLOSE,
+                close.map_or_else(Vec::new, |close| {
+                    let mut payload = u16::from(close.code).to_be_bytes().to_vec();
+                    payload.extend_from_slice(close.reason.as_bytes());
+
+                    payload
+                }),
+            ),
+            // Raw WebSocket frames have no meaning here.
+            Frame::Frame(_) => return Ok(()),
+        };
+        let size = u32::try_from(payload.len()).map_err(|_| InvalidFrame::TooLong {
+            size: payload.len(),
+            max_size: u32::MAX as usize,
+        })?;
+
+        self.write_buffer.push(opcode);
+        self.write_buffer.extend_from_slice(&size.to_be_bytes());
+        self.write_buffer.extend_from_slice(&payload);
+
+        Ok(())
+    }
+
+    /// Write the whole write buffer to the byte stream.
+    fn poll_write_buffer(&mut self, context: &mut Context<'_>) -> Poll<Result<(), TransportError>> {
+        while !self.write_buffer.is_empty() {
+            let written =
+                ready!(Pin::new(&mut self.inner).poll_write(context, &self.write_buffer))?;
+
+            if written == 0 {
+                return Poll::Ready(Err(io::Error::from(io::ErrorKind::WriteZero).into()));
+            }
+
+            self.write_buffer.drain(..written);
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A frame that cannot be decoded, or encoded.
+#[derive(Debug)]
+enum InvalidFrame {
+    TooLong { size: usize, max_size: usize },
+    Opcode(u8),
+    Utf8,
+}
+
+impl From<InvalidFrame> for TransportError {
+    fn from(error: InvalidFrame) -> Self {
+        match error {
+            InvalidFrame::TooLong { size, max_size } => {
+                Self::Capacity(CapacityError::MessageTooLong { size, max_size })
+            }
+            InvalidFrame::Opcode(opcode) => ProtocolError::InvalidOpcode(opcode).into(),
+            InvalidFrame::Utf8 => Self::Utf8,
+        }
+    }
+}
+
+impl<S> Stream for FramedTransport<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    type Item = Result<Frame, TransportError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            match this.decode() {
+                Ok(Some(Frame::Ping(payload))) => {
+                    if !this.closed {
+                        // The pong is sent with the next frames if the byte
+                        // stream is not ready now.
+                        this.encode(Frame::Pong(payload.clone()))
+                            .expect("a ping is not larger than a pong");
+                        let _ = this.poll_write_buffer(context);
+                    }
+
+                    return Poll::Ready(Some(Ok(Frame::Ping(payload))));
+                }
+                Ok(Some(frame)) => return Poll::Ready(Some(Ok(frame))),
+                Ok(None) => {}
+                Err(error) => return Poll::Ready(Some(Err(error.into()))),
+            }
+
+            let mut bytes = [0; 8 << 10];
+            let mut buffer = ReadBuf::new(&mut bytes);
+
+            if let Err(error) = ready!(Pin::new(&mut this.inner).poll_read(context, &mut buffer)) {
+                return Poll::Ready(Some(Err(error.into())));
+            }
+
+            if buffer.filled().is_empty() {
+                return Poll::Ready(if this.read_buffer.is_empty() {
+                    None
+                } else {
+                    Some(Err(ProtocolError::ResetWithoutClosingHandshake.into()))
+                });
+            }
+
+            this.read_buffer.extend_from_slice(buffer.filled());
+        }
+    }
+}
+
+impl<S> Sink<Frame> for FramedTransport<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    type Error = TransportError;
+
+    fn poll_ready(
+        mut self: Pin<&mut Self>,
+        context: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        if self.closed {
+            return Poll::Ready(Err(TransportError::AlreadyClosed));
+        }
+
+        if self.write_buffer.len() >= WRITE_BUFFER_SIZE {
+            ready!(self.poll_write_buffer(context))?;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, frame: Frame) -> Result<(), Self::Error> {
+        if self.closed {
+            return Err(TransportError::AlreadyClosed);
+        }
+
+        let close = matches!(frame, Frame::Close(_));
+        self.encode(frame)?;
+
+        // Nothing is sent after a close frame.
+        self.closed = close;
+
+        Ok(())
+    }
+
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        context: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        ready!(self.poll_write_buffer(context))?;
+
+        Poll::Ready(ready!(Pin::new(&mut self.inner).poll_flush(context)).map_err(Into::into))
+    }
+
+    fn poll_close(
+        mut self: Pin<&mut Self>,
+        context: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        ready!(self.poll_write_buffer(context))?;
+        self.closed = true;
+
+        Poll::Ready(ready!(Pin::new(&mut self.inner).poll_shutdown(context)).map_err(Into::into))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::{SinkExt, StreamExt};
+    use tokio::io::{duplex, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_framed_transport() {
+        // A small buffer, to split the frames across reads and writes.
+        let (left, right) = duplex(7);
+        let mut left = FramedTransport::new(left);
+        let mut right = FramedTransport::new(right);
+
+        let close = Frame::Close(Some(CloseFrame {
+            code: CloseCode::Normal,
+            reason: Cow::Borrowed("bye"),
+        }));
+        let frames = [
+            Frame::text("[2,\"1\",\"Heartbeat\",{}]"),
+            Frame::Binary(vec![1, 2, 3]),
+            Frame::Pong(vec![]),
+            close.clone(),
+        ];
+
+        let sent = tokio::spawn(async move {
+            for frame in frames {
+                left.feed(frame).await.unwrap();
+            }
+
+            left.flush().await.unwrap();
+
+            left
+        });
+
+        assert_eq!(
+            right.next().await.unwrap().unwrap(),
+            Frame::text("[2,\"1\",\"Heartbeat\",{}]")
+        );
+        assert_eq!(
+            right.next().await.unwrap().unwrap(),
+            Frame::Binary(vec![1, 2, 3])
+        );
+        assert_eq!(right.next().await.unwrap().unwrap(), Frame::Pong(vec![]));
+        assert_eq!(right.next().await.unwrap().unwrap(), close);
+
+        // Nothing is sent after a close frame.
+        let mut left = sent.await.unwrap();
+        assert!(matches!(
+            left.send(Frame::text("[]")).await,
+            Err(TransportError::AlreadyClosed)
+        ));
+
+        // The pings are answered.
+        let (left, right) = duplex(64);
+        let mut left = FramedTransport::new(left);
+        let mut right = FramedTransport::new(right);
+
+        left.send(Frame::Ping(b"ping".to_vec())).await.unwrap();
+        assert_eq!(
+            right.next().await.unwrap().unwrap(),
+            Frame::Ping(b"ping".to_vec())
+        );
+        assert_eq!(
+            left.next().await.unwrap().unwrap(),
+            Frame::Pong(b"ping".to_vec())
+        );
+
+        // The stream ends once the peer is gone, between two frames.
+        drop(right);
+        assert!(left.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_framed_transport_hostile_frames() {
+        let (mut left, right) = duplex(64);
+        let mut right = FramedTransport::new(right).max_frame_size(8);
+
+        left.write_all(&[TEXT, 0, 0, 0, 9]).await.unwrap();
+        assert!(matches!(
+            right.next().await.unwrap(),
+            Err(TransportError::Capacity(CapacityError::MessageTooLong {
+                size: 9,
+                max_size: 8
+            }))
+        ));
+
+        let (mut left, right) = duplex(64);
+        let mut right = FramedTransport::new(right);
+
+        left.write_all(&[0x3, 0, 0, 0, 0]).await.unwrap();
+        assert!(matches!(
+            right.next().await.unwrap(),
+            Err(TransportError::Protocol(ProtocolError::InvalidOpcode(0x3)))
+        ));
+
+        let (mut left, right) = duplex(64);
+        let mut right = FramedTransport::new(right);
+
+        left.write_all(&[TEXT, 0, 0, 0, 2, 0xff, 0xfe])
+            .await
+            .unwrap();
+        assert!(matches!(
+            right.next().await.unwrap(),
+            Err(TransportError::Utf8)
+        ));
+
+        // A truncated frame.
+        let (mut left, right) = duplex(64);
+        let mut right = FramedTransport::new(right);
+
+        left.write_all(&[TEXT, 0, 0, 0, 2, b'[']).await.unwrap();
+        drop(left);
+        assert!(matches!(
+            right.next().await.unwrap(),
+            Err(TransportError::Protocol(
+                ProtocolError::ResetWithoutClosingHandshake
+            ))
+        ));
+    }
+}
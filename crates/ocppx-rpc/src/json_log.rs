@@ -0,0 +1,167 @@
+use crate::Span;
+use log::{
+    kv::{self, VisitSource},
+    LevelFilter, Log, Metadata, Record, SetLoggerError,
+};
+use serde_json::{Map, Value};
+use std::{
+    io::{self, Write},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+/// A logger writing one JSON object per record, with the key-values of the
+/// record as properties.
+///
+/// The records of the ocppx crates carry the `charge_point_id`, the
+/// `unique_id` and the `action` of the messages, so that a single `Call`
+/// can be followed from the connection to its handler, e.g.:
+///
+/// ```json
+/// {"time":1359752012486,"level":"DEBUG","target":"ocppx_server::server","message":"Call received","charge_point_id":"CP001","unique_id":"19223201","action":"Heartbeat"}
+/// ```
+///
+/// The fields of the current [`Span`] are added to the records that do not
+/// carry them. It is opt-in: any other `log` implementation can be installed instead.
+pub struct JsonLogger<W> {
+    writer: Mutex<W>,
+    level: LevelFilter,
+}
+
+impl JsonLogger<io::Stderr> {
+    /// Log the records up to `level` on the standard error.
+    pub fn stderr(level: LevelFilter) -> Self {
+        Self::new(io::stderr(), level)
+    }
+}
+
+impl<W> JsonLogger<W>
+where
+    W: Write + Send + 'static,
+{
+    /// Log the records up to `level` in `writer`.
+    pub fn new(writer: W, level: LevelFilter) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+            level,
+        }
+    }
+
+    /// Install the logger as the global logger.
+    pub fn install(self) -> Result<(), SetLoggerError> {
+        let level = self.level;
+        log::set_logger(Box::leak(Box::new(self)))?;
+        log::set_max_level(level);
+
+        Ok(())
+    }
+
+    /// The JSON object of `record`.
+    fn object(record: &Record<'_>) -> Value {
+        let mut object = Map::new();
+        object.insert(
+            "time".to_owned(),
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_millis() as u64)
+                .into(),
+        );
+        object.insert("level".to_owned(), record.level().as_str().into());
+        object.insert("target".to_owned(), record.target().into());
+        object.insert("message".to_owned(), record.args().to_string().into());
+
+        let _ = record.key_values().visit(&mut Properties(&mut object));
+
+        if let Some(span) = Span::current() {
+            for (name, value) in span.fields() {
+                object.entry(name).or_insert_with(|| value.into());
+            }
+        }
+
+        Value::Object(object)
+    }
+}
+
+struct Properties<'a>(&'a mut Map<String, Value>);
+
+impl<'kvs> VisitSource<'kvs> for Properties<'_> {
+    fn visit_pair(&mut self, key: kv::Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        let value = if let Some(value) = value.to_u64() {
+            value.into()
+        } else if let Some(value) = value.to_i64() {
+            value.into()
+        } else if let Some(value) = value.to_bool() {
+            value.into()
+        } else {
+            value.to_string().into()
+        };
+
+        self.0.insert(key.as_str().to_owned(), value);
+
+        Ok(())
+    }
+}
+
+impl<W> Log for JsonLogger<W>
+where
+    W: Write + Send + 'static,
+{
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let mut writer = self.writer.lock().unwrap();
+        let _ = writeln!(writer, "{}", Self::object(record));
+    }
+
+    fn flush(&self) {
+        let _ = self.writer.lock().unwrap().flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    #[test]
+    fn test_json_record() {
+        let key_values = [("charge_point_id", "CP001"), ("action", "Heartbeat")];
+        let object = JsonLogger::<Vec<u8>>::object(
+            &Record::builder()
+                .level(Level::Debug)
+                .target("ocppx_server")
+                .args(format_args!("Call received"))
+                .key_values(&key_values)
+                .build(),
+        );
+
+        assert_eq!(object["level"], "DEBUG");
+        assert_eq!(object["message"], "Call received");
+        assert_eq!(object["charge_point_id"], "CP001");
+        assert_eq!(object["action"], "Heartbeat");
+    }
+
+    #[tokio::test]
+    async fn test_json_record_in_span() {
+        let object = Span::call("CP001", "1", "Heartbeat")
+            .instrument(async {
+                JsonLogger::<Vec<u8>>::object(
+                    &Record::builder()
+                        .level(Level::Info)
+                        .args(format_args!("handled"))
+                        .build(),
+                )
+            })
+            .await;
+
+        assert_eq!(object["charge_point_id"], "CP001");
+        assert_eq!(object["unique_id"], "1");
+        assert_eq!(object["action"], "Heartbeat");
+    }
+}
@@ -0,0 +1,135 @@
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// WebSocket-level keep-alive: a ping is sent once nothing has been
+/// received for `ping_interval`, and the connection is stale if nothing,
+/// not even the pong, is received within `pong_timeout` after it.
+///
+/// It detects dead peers, e.g. a half-open TCP connection, independently
+/// of the OCPP `Heartbeat`s, which only tell that the application is alive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepAlive {
+    pub ping_interval: Duration,
+    pub pong_timeout: Duration,
+}
+
+impl Default for KeepAlive {
+    fn default() -> Self {
+        Self {
+            ping_interval: Duration::from_secs(30),
+            pong_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Something that happened to the transport of a connection, as opposed to
+/// the OCPP messages carried by it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionEvent {
+    /// Nothing has been received from the peer for `silence`, despite a
+    /// ping: the connection is considered dead, and is closed.
+    Stale {
+        charge_point_id: String,
+        silence: Duration,
+    },
+}
+
+/// What to do when the [`KeepAliveTimer`] deadline is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepAliveAction {
+    /// Send a ping.
+    Ping,
+    /// The pong has not been received in time.
+    Stale,
+}
+
+/// The state of the keep-alive of a connection, driven by its event loop:
+/// it sleeps until [`KeepAliveTimer::deadline`], then calls
+/// [`KeepAliveTimer::poll`], and calls [`KeepAliveTimer::received`] for
+/// every frame received.
+#[derive(Debug)]
+pub struct KeepAliveTimer {
+    keep_alive: KeepAlive,
+    last_received: Instant,
+    ping_sent: Option<Instant>,
+}
+
+impl KeepAliveTimer {
+    pub fn new(keep_alive: KeepAlive, now: Instant) -> Self {
+        Self {
+            keep_alive,
+            last_received: now,
+            ping_sent: None,
+        }
+    }
+
+    /// A frame, of any kind, has been received at `now`.
+    pub fn received(&mut self, now: Instant) {
+        self.last_received = now;
+        self.ping_sent = None;
+    }
+
+    /// When to call [`KeepAliveTimer::poll`].
+    pub fn deadline(&self) -> Instant {
+        match self.ping_sent {
+            Some(ping_sent) => ping_sent + self.keep_alive.pong_timeout,
+            None => self.last_received + self.keep_alive.ping_interval,
+        }
+    }
+
+    /// What to do at `now`, if anything.
+    pub fn poll(&mut self, now: Instant) -> Option<KeepAliveAction> {
+        if now < self.deadline() {
+            return None;
+        }
+
+        if self.ping_sent.is_some() {
+            return Some(KeepAliveAction::Stale);
+        }
+
+        self.ping_sent = Some(now);
+
+        Some(KeepAliveAction::Ping)
+    }
+
+    /// Time elapsed since the last frame was received.
+    pub fn silence(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.last_received)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keep_alive_timer() {
+        let now = Instant::now();
+        let second = Duration::from_secs(1);
+        let mut timer = KeepAliveTimer::new(
+            KeepAlive {
+                ping_interval: 10 * second,
+                pong_timeout: 5 * second,
+            },
+            now,
+        );
+
+        assert_eq!(timer.deadline(), now + 10 * second);
+        assert_eq!(timer.poll(now + 9 * second), None);
+
+        // A frame postpones the ping.
+        timer.received(now + 5 * second);
+        assert_eq!(timer.poll(now + 10 * second), None);
+        assert_eq!(timer.poll(now + 15 * second), Some(KeepAliveAction::Ping));
+
+        // The pong arrives in time.
+        assert_eq!(timer.deadline(), now + 20 * second);
+        timer.received(now + 16 * second);
+        assert_eq!(timer.poll(now + 26 * second), Some(KeepAliveAction::Ping));
+
+        // It does not.
+        assert_eq!(timer.poll(now + 30 * second), None);
+        assert_eq!(timer.poll(now + 31 * second), Some(KeepAliveAction::Stale));
+        assert_eq!(timer.silence(now + 31 * second), 15 * second);
+    }
+}
@@ -0,0 +1,100 @@
+//! OCPP-J RPC framing.
+//!
+//! OCPP-J transports messages as JSON arrays over WebSocket, where the
+//! first element is the message type ID:
+//!
+//! * `[2, "<UniqueId>", "<Action>", {<Payload>}]` for a [`Call`],
+//! * `[3, "<UniqueId>", {<Payload>}]` for a [`CallResult`],
+//! * `[4, "<UniqueId>", "<ErrorCode>", "<ErrorDescription>", {<ErrorDetails>}]`
+//!   for a [`CallError`].
+//!
+//! [`Message`] represents any of these frames, and can be parsed from or
+//! serialized to its wire format. [`FieldError`]s in the `errorDetails` of
+//! a [`CallError`] tell which fields of a payload are not valid.
+//! [`BorrowedMessage`] is parsed without copying the frame, for high
+//! message rates. [`MessageIds`] generates the unique IDs of the `Call`s,
+//! e.g. UUIDs v7, [`PendingCalls`] tracks the `Call`s waiting for a
+//! response, and [`CallWindow`] holds the next `Call`s back until they are
+//! answered. [`KeepAliveTimer`] pings the peers at the WebSocket level to
+//! detect dead connections.
+//!
+//! [`Recorder`] captures the frames of a session in a file, and
+//! [`Replayer`] plays them back.
+//!
+//! The crates log their activity with the `log` facade, with the
+//! `charge_point_id`, the `unique_id` and the `action` of the messages as
+//! key-values. A [`Span`] gives them to the records logged while a
+//! connection or a `Call` is handled, the ones of the handlers included;
+//! [`JsonLogger`] writes these records as JSON lines.
+//!
+//! [`Compressed`] adds the `permessage-deflate` extension, configured by
+//! [`Compression`], to the WebSocket connections.
+//!
+//! The frames are carried by a [`Transport`]: a WebSocket connection, a
+//! [`FramedTransport`] over any byte stream, e.g. a plain TCP connection or
+//! a serial line, or a [`MemoryTransport`] to wire a client and a server
+//! together in tests. A [`Connector`] opens a transport at each connection.
+//!
+//! With the `metrics` feature, [`Metrics`] counts the OCPP traffic of a
+//! server or a client, and renders it in the Prometheus text format.
+
+mod borrowed;
+mod call;
+mod capture;
+mod compression;
+mod deflate;
+mod details;
+mod error_code;
+mod framed;
+mod json_log;
+mod keep_alive;
+mod message;
+mod message_id;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod pending;
+mod span;
+mod transport;
+mod window;
+
+pub use borrowed::{BorrowedCall, BorrowedCallError, BorrowedCallResult, BorrowedMessage};
+pub use call::{Call, CallError, CallResult};
+pub use capture::{CapturedFrame, Playback, Recorder, Replayer};
+pub use compression::{Compressed, Compression};
+pub use details::FieldError;
+pub use error_code::ErrorCode;
+pub use framed::{FramedTransport, DEFAULT_MAX_FRAME_SIZE};
+pub use json_log::JsonLogger;
+pub use keep_alive::{ConnectionEvent, KeepAlive, KeepAliveAction, KeepAliveTimer};
+pub use message::{Direction, Message, MessageTypeId};
+pub use message_id::{
+    Counter, MessageId, MessageIdError, MessageIdGenerator, MessageIds, UuidV7,
+    MAX_MESSAGE_ID_LENGTH,
+};
+#[cfg(feature = "metrics")]
+pub use metrics::Metrics;
+pub use pending::{
+    PendingCall, PendingCallError, PendingCalls, DEFAULT_CALL_TIMEOUT,
+    DEFAULT_MAX_OUTSTANDING_CALLS,
+};
+pub use span::Span;
+use thiserror::Error;
+pub use transport::{ConnectFuture, Connector, Frame, MemoryTransport, Transport, TransportError};
+pub use window::CallWindow;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("cannot serialize or deserialize a frame or a payload")]
+    Json(#[from] serde_json::Error),
+
+    #[error("unexpected message type: expected `{expected:?}`, got `{got:?}`")]
+    UnexpectedMessageType {
+        expected: MessageTypeId,
+        got: MessageTypeId,
+    },
+
+    #[error("invalid replay speed `{0}`: it must be positive and finite")]
+    InvalidSpeed(f64),
+}
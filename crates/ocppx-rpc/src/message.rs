@@ -0,0 +1,341 @@
+use crate::{Call, CallError, CallResult, Error, Result};
+use serde::{
+    de::{self, IgnoredAny, SeqAccess, Unexpected, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{fmt, str::FromStr};
+
+/// The message type ID, i.e. the first element of a frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u8)]
+pub enum MessageTypeId {
+    Call = 2,
+    CallResult = 3,
+    CallError = 4,
+}
+
+impl TryFrom<u8> for MessageTypeId {
+    type Error = u8;
+
+    fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
+        Ok(match value {
+            2 => Self::Call,
+            3 => Self::CallResult,
+            4 => Self::CallError,
+            _ => return Err(value),
+        })
+    }
+}
+
+/// Whether a message is received or sent.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Incoming,
+    Outgoing,
+}
+
+impl Direction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Incoming => "incoming",
+            Self::Outgoing => "outgoing",
+        }
+    }
+}
+
+/// Any OCPP-J frame.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Call(Call),
+    CallResult(CallResult),
+    CallError(CallError),
+}
+
+impl Message {
+    pub fn type_id(&self) -> MessageTypeId {
+        match self {
+            Self::Call(_) => MessageTypeId::Call,
+            Self::CallResult(_) => MessageTypeId::CallResult,
+            Self::CallError(_) => MessageTypeId::CallError,
+        }
+    }
+
+    pub fn unique_id(&self) -> &str {
+        match self {
+            Self::Call(Call { unique_id, .. })
+            | Self::CallResult(CallResult { unique_id, .. })
+            | Self::CallError(CallError { unique_id, .. }) => unique_id,
+        }
+    }
+}
+
+impl Call {
+    /// The unique ID of a frame which cannot be parsed as a [`Message`] but
+    /// starts as a `Call`, i.e. `[2,"<unique ID>",`, so that the error can
+    /// still be responded to with a [`CallError`].
+    pub fn recover_unique_id(frame: &str) -> Option<String> {
+        let frame = frame.trim_start().strip_prefix('[')?.trim_start();
+        let frame = frame.strip_prefix('2')?.trim_start();
+        let frame = frame.strip_prefix(',')?.trim_start();
+
+        // The unique ID is read alone, the rest of the frame can be invalid
+        // JSON.
+        serde_json::Deserializer::from_str(frame)
+            .into_iter::<String>()
+            .next()?
+            .ok()
+    }
+}
+
+impl FromStr for Message {
+    type Err = Error;
+
+    fn from_str(frame: &str) -> Result<Self> {
+        Ok(serde_json::from_str(frame)?)
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let frame = serde_json::to_string(self).map_err(|_| fmt::Error)?;
+
+        formatter.write_str(&frame)
+    }
+}
+
+impl Serialize for Message {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Call(call) => call.serialize(serializer),
+            Self::CallResult(call_result) => call_result.serialize(serializer),
+            Self::CallError(call_error) => call_error.serialize(serializer),
+        }
+    }
+}
+
+impl Serialize for Call {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (
+            MessageTypeId::Call as u8,
+            &self.unique_id,
+            &self.action,
+            &self.payload,
+        )
+            .serialize(serializer)
+    }
+}
+
+impl Serialize for CallResult {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (
+            MessageTypeId::CallResult as u8,
+            &self.unique_id,
+            &self.payload,
+        )
+            .serialize(serializer)
+    }
+}
+
+impl Serialize for CallError {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (
+            MessageTypeId::CallError as u8,
+            &self.unique_id,
+            &self.error_code,
+            &self.error_description,
+            &self.error_details,
+        )
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Message {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(MessageVisitor)
+    }
+}
+
+struct MessageVisitor;
+
+impl<'de> Visitor<'de> for MessageVisitor {
+    type Value = Message;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an OCPP-J frame, i.e. an array starting with a message type ID")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut length = 0;
+
+        macro_rules! next {
+            ($seq:ident) => {{
+                length += 1;
+
+                $seq.next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(length - 1, &self))?
+            }};
+        }
+
+        let type_id: u8 = next!(seq);
+        let type_id = MessageTypeId::try_from(type_id).map_err(|type_id| {
+            de::Error::invalid_value(Unexpected::Unsigned(type_id.into()), &"2, 3 or 4")
+        })?;
+        let unique_id: String = next!(seq);
+
+        let message = match type_id {
+            MessageTypeId::Call => Message::Call(Call {
+                unique_id,
+                action: next!(seq),
+                payload: next!(seq),
+            }),
+
+            MessageTypeId::CallResult => Message::CallResult(CallResult {
+                unique_id,
+                payload: next!(seq),
+            }),
+
+            MessageTypeId::CallError => Message::CallError(CallError {
+                unique_id,
+                error_code: next!(seq),
+                error_description: next!(seq),
+                error_details: next!(seq),
+            }),
+        };
+
+        if seq.next_element::<IgnoredAny>()?.is_some() {
+            return Err(de::Error::invalid_length(
+                length + 1,
+                &"no more elements in the frame",
+            ));
+        }
+
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrorCode;
+    use serde_json::json;
+
+    #[test]
+    fn test_call() {
+        let frame = r#"[2,"19223201","Heartbeat",{}]"#;
+        let message = frame.parse::<Message>().unwrap();
+
+        assert_eq!(
+            message,
+            Message::Call(Call {
+                unique_id: "19223201".to_string(),
+                action: "Heartbeat".to_string(),
+                payload: json!({}),
+            })
+        );
+        assert_eq!(message.to_string(), frame);
+    }
+
+    #[test]
+    fn test_call_result() {
+        let frame = r#"[3,"19223201",{"currentTime":"2013-02-01T20:53:32.486Z"}]"#;
+        let message = frame.parse::<Message>().unwrap();
+
+        assert_eq!(
+            message,
+            Message::CallResult(CallResult {
+                unique_id: "19223201".to_string(),
+                payload: json!({"currentTime": "2013-02-01T20:53:32.486Z"}),
+            })
+        );
+        assert_eq!(message.to_string(), frame);
+    }
+
+    #[test]
+    fn test_call_error() {
+        let frame = r#"[4,"19223201","NotImplemented","Unknown action",{}]"#;
+        let message = frame.parse::<Message>().unwrap();
+
+        assert_eq!(
+            message,
+            Message::CallError(CallError {
+                unique_id: "19223201".to_string(),
+                error_code: ErrorCode::NotImplemented,
+                error_description: "Unknown action".to_string(),
+                error_details: json!({}),
+            })
+        );
+        assert_eq!(message.to_string(), frame);
+    }
+
+    #[test]
+    fn test_invalid_frames() {
+        assert!(r#"[5,"19223201",{}]"#.parse::<Message>().is_err());
+        assert!(r#"[2,"19223201","Heartbeat"]"#.parse::<Message>().is_err());
+        assert!(r#"[3,"19223201",{},{}]"#.parse::<Message>().is_err());
+        assert!(r#"{"id":"19223201"}"#.parse::<Message>().is_err());
+    }
+
+    #[test]
+    fn test_recover_unique_id() {
+        for frame in [
+            r#"[2,"19223201","Heartbeat"]"#,
+            r#"[2,"19223201","Heartbeat",{"#,
+            r#" [ 2 , "19223201" , 42, []]"#,
+        ] {
+            assert_eq!(Call::recover_unique_id(frame).as_deref(), Some("19223201"));
+        }
+
+        assert_eq!(
+            Call::recover_unique_id(r#"[2,"19\"223201","#).as_deref(),
+            Some("19\"223201")
+        );
+
+        for frame in [
+            r#"[3,"19223201",{},{}]"#,
+            r#"[2,19223201,"Heartbeat",{}]"#,
+            r#"[22,"19223201","Heartbeat",{}]"#,
+            r#"[2,"19223201"#,
+            r#"{"id":"19223201"}"#,
+            "",
+        ] {
+            assert_eq!(Call::recover_unique_id(frame), None, "{frame}");
+        }
+    }
+
+    #[test]
+    fn test_typed_payload() {
+        use ocppx_types::v1_6::HeartbeatRequest;
+
+        let call = Call::new(
+            "19223201",
+            "Heartbeat",
+            &HeartbeatRequest::builder().build(),
+        )
+        .unwrap();
+        let _: HeartbeatRequest = call.payload().unwrap();
+
+        assert_eq!(
+            Message::from(call).to_string(),
+            r#"[2,"19223201","Heartbeat",{}]"#
+        );
+    }
+}
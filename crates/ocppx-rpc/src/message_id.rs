@@ -0,0 +1,282 @@
+use std::{
+    fmt,
+    ops::Deref,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::SystemTime,
+};
+use thiserror::Error;
+
+/// The maximum length of a unique ID, in OCPP-J.
+pub const MAX_MESSAGE_ID_LENGTH: usize = 36;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MessageIdError {
+    #[error("a unique ID cannot be empty")]
+    Empty,
+
+    #[error("a unique ID is at most {MAX_MESSAGE_ID_LENGTH} characters long, got {0}")]
+    TooLong(usize),
+
+    #[error("the unique ID `{0}` is already pending")]
+    Pending(String),
+}
+
+/// The unique ID of a `Call`, at most [`MAX_MESSAGE_ID_LENGTH`] characters
+/// long, see [`MessageIds`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MessageId(String);
+
+impl MessageId {
+    pub fn new(unique_id: impl Into<String>) -> Result<Self, MessageIdError> {
+        let unique_id = unique_id.into();
+
+        match unique_id.chars().count() {
+            0 => Err(MessageIdError::Empty),
+            length if length > MAX_MESSAGE_ID_LENGTH => Err(MessageIdError::TooLong(length)),
+            _ => Ok(Self(unique_id)),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl Deref for MessageId {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl fmt::Display for MessageId {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(&self.0)
+    }
+}
+
+impl TryFrom<String> for MessageId {
+    type Error = MessageIdError;
+
+    fn try_from(unique_id: String) -> Result<Self, Self::Error> {
+        Self::new(unique_id)
+    }
+}
+
+impl TryFrom<&str> for MessageId {
+    type Error = MessageIdError;
+
+    fn try_from(unique_id: &str) -> Result<Self, Self::Error> {
+        Self::new(unique_id)
+    }
+}
+
+impl From<MessageId> for String {
+    fn from(unique_id: MessageId) -> Self {
+        unique_id.0
+    }
+}
+
+/// Generate the unique IDs of the `Call`s of a peer.
+///
+/// A closure `Fn() -> MessageId` is a generator too.
+pub trait MessageIdGenerator: Send + Sync + 'static {
+    fn generate(&self) -> MessageId;
+}
+
+impl<F> MessageIdGenerator for F
+where
+    F: Fn() -> MessageId + Send + Sync + 'static,
+{
+    fn generate(&self) -> MessageId {
+        self()
+    }
+}
+
+/// A generator counting up, e.g. `42`, `43`…
+#[derive(Debug)]
+pub struct Counter {
+    next: AtomicU64,
+}
+
+impl Counter {
+    pub fn new(first: u64) -> Self {
+        Self {
+            next: AtomicU64::new(first),
+        }
+    }
+
+    /// Count from the current time, in milliseconds, so that the unique IDs
+    /// differ from the ones of a previous process.
+    pub fn from_now() -> Self {
+        Self::new(
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_millis() as u64),
+        )
+    }
+}
+
+impl MessageIdGenerator for Counter {
+    fn generate(&self) -> MessageId {
+        // 20 digits at most.
+        MessageId(self.next.fetch_add(1, Ordering::Relaxed).to_string())
+    }
+}
+
+/// A generator of UUIDs version 7 (RFC 9562): time-ordered, and random
+/// enough to be unique across peers and processes.
+///
+/// The UUIDs are monotonic: within a millisecond, their 12 bits after the
+/// version are a counter.
+#[derive(Debug, Default)]
+pub struct UuidV7 {
+    /// The timestamp and the counter of the last UUID.
+    last: Mutex<(u64, u16)>,
+}
+
+impl UuidV7 {
+    const MAX_COUNTER: u16 = 0xfff;
+
+    /// The UUID of `timestamp`, in milliseconds, with the random bits
+    /// `random`.
+    fn generate_at(&self, timestamp: u64, random: u64) -> MessageId {
+        let (timestamp, counter) = {
+            let mut last = self.last.lock().unwrap();
+
+            *last = match *last {
+                // The clock has not moved, or has gone back: count.
+                (last_timestamp, counter)
+                    if timestamp <= last_timestamp && counter < Self::MAX_COUNTER =>
+                {
+                    (last_timestamp, counter + 1)
+                }
+                (last_timestamp, _) if timestamp <= last_timestamp => (last_timestamp + 1, 0),
+                // Half of the range is left to count.
+                _ => (timestamp, (random >> 52) as u16 & (Self::MAX_COUNTER >> 1)),
+            };
+
+            *last
+        };
+
+        let uuid = (u128::from(timestamp & 0xffff_ffff_ffff) << 80)
+            | (0x7 << 76)
+            | (u128::from(counter) << 64)
+            | (0b10 << 62)
+            | u128::from(random & 0x3fff_ffff_ffff_ffff);
+
+        MessageId(format!(
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            uuid >> 96,
+            (uuid >> 80) & 0xffff,
+            (uuid >> 64) & 0xffff,
+            (uuid >> 48) & 0xffff,
+            uuid & 0xffff_ffff_ffff,
+        ))
+    }
+}
+
+impl MessageIdGenerator for UuidV7 {
+    fn generate(&self) -> MessageId {
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis() as u64);
+
+        self.generate_at(timestamp, rand::random())
+    }
+}
+
+/// The [`MessageIdGenerator`] of a peer, cheap to clone.
+#[derive(Clone)]
+pub struct MessageIds {
+    generator: Arc<dyn MessageIdGenerator>,
+}
+
+impl fmt::Debug for MessageIds {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.debug_struct("MessageIds").finish_non_exhaustive()
+    }
+}
+
+impl MessageIds {
+    pub fn new<G>(generator: G) -> Self
+    where
+        G: MessageIdGenerator,
+    {
+        Self {
+            generator: Arc::new(generator),
+        }
+    }
+
+    /// Count from `first`, see [`Counter`].
+    pub fn counter(first: u64) -> Self {
+        Self::new(Counter::new(first))
+    }
+
+    /// Count from the current time, see [`Counter::from_now`].
+    pub fn counter_from_now() -> Self {
+        Self::new(Counter::from_now())
+    }
+
+    /// UUIDs version 7, see [`UuidV7`].
+    pub fn uuid_v7() -> Self {
+        Self::new(UuidV7::default())
+    }
+
+    /// The next unique ID.
+    pub fn next(&self) -> MessageId {
+        self.generator.generate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_id() {
+        assert_eq!(MessageId::new(""), Err(MessageIdError::Empty));
+        assert_eq!(
+            MessageId::new("x".repeat(37)),
+            Err(MessageIdError::TooLong(37))
+        );
+        assert_eq!(MessageId::new("x".repeat(36)).unwrap().len(), 36);
+
+        let message_ids = MessageIds::counter(41);
+        assert_eq!(message_ids.next().as_str(), "41");
+        assert_eq!(message_ids.next().as_str(), "42");
+
+        let message_ids = MessageIds::new(|| MessageId::new("constant").unwrap());
+        assert_eq!(message_ids.next().as_str(), "constant");
+    }
+
+    #[test]
+    fn test_uuid_v7() {
+        let uuids = UuidV7::default();
+        let first = uuids.generate_at(0x0190_a1b2_c3d4, u64::MAX);
+
+        assert_eq!(first.len(), 36);
+        assert!(first.starts_with("0190a1b2-c3d4-77ff-bfff-"));
+
+        // Within the same millisecond, or if the clock goes back, the UUIDs
+        // still increase.
+        let second = uuids.generate_at(0x0190_a1b2_c3d4, 0);
+        let third = uuids.generate_at(0x0190_a1b2_c3d0, 0);
+        assert!(first < second && second < third);
+        assert!(third.starts_with("0190a1b2-c3d4-7801-8000-"));
+
+        // Once the counter is exhausted, the timestamp moves on.
+        *uuids.last.lock().unwrap() = (0x0190_a1b2_c3d4, UuidV7::MAX_COUNTER);
+        assert!(uuids
+            .generate_at(0x0190_a1b2_c3d4, 0)
+            .starts_with("0190a1b2-c3d5-7000-"));
+    }
+}
@@ -0,0 +1,316 @@
+use crate::{Direction, ErrorCode};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write as _},
+    io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+/// The upper bounds of the buckets of the call latency histogram, in
+/// seconds.
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        for (bucket, upper_bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if value <= upper_bound {
+                *bucket += 1;
+            }
+        }
+
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    messages: BTreeMap<(Direction, String), u64>,
+    call_duration: BTreeMap<String, Histogram>,
+    call_handling: BTreeMap<String, Histogram>,
+    call_errors: BTreeMap<String, u64>,
+    connected_charge_points: usize,
+    reconnects: u64,
+}
+
+/// Counters and histograms of the OCPP traffic, rendered in the Prometheus
+/// text format.
+///
+/// The metrics are cheap to clone: clones share the same registry, e.g.
+/// one clone is given to the server or the client, and another one serves
+/// the metrics with [`Metrics::serve`].
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    registry: Arc<Mutex<Registry>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a `Call` received or sent.
+    pub fn record_message(&self, direction: Direction, action: &str) {
+        *self
+            .registry
+            .lock()
+            .unwrap()
+            .messages
+            .entry((direction, action.to_owned()))
+            .or_default() += 1;
+    }
+
+    /// Record the time a sent `Call` has waited for its response.
+    pub fn record_call_latency(&self, action: &str, latency: Duration) {
+        self.registry
+            .lock()
+            .unwrap()
+            .call_duration
+            .entry(action.to_owned())
+            .or_default()
+            .observe(latency.as_secs_f64());
+    }
+
+    /// Record the time a received `Call` has taken to be handled.
+    pub fn record_call_handling(&self, action: &str, duration: Duration) {
+        self.registry
+            .lock()
+            .unwrap()
+            .call_handling
+            .entry(action.to_owned())
+            .or_default()
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Count a `CallError` received or sent.
+    pub fn record_call_error(&self, error_code: &ErrorCode) {
+        *self
+            .registry
+            .lock()
+            .unwrap()
+            .call_errors
+            .entry(error_code.as_str().to_owned())
+            .or_default() += 1;
+    }
+
+    pub fn set_connected_charge_points(&self, connected_charge_points: usize) {
+        self.registry.lock().unwrap().connected_charge_points = connected_charge_points;
+    }
+
+    /// Count a successful reconnection.
+    pub fn record_reconnect(&self) {
+        self.registry.lock().unwrap().reconnects += 1;
+    }
+
+    /// The metrics, in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let registry = self.registry.lock().unwrap();
+        let mut output = String::new();
+
+        // Writing in a `String` cannot fail.
+        let _ = render(&registry, &mut output);
+
+        output
+    }
+
+    /// Serve the metrics over HTTP on `listener`, at `/metrics`, forever.
+    pub async fn serve(&self, listener: TcpListener) -> io::Result<()> {
+        loop {
+            let (mut stream, _) = listener.accept().await?;
+            let metrics = self.clone();
+
+            tokio::spawn(async move {
+                // The request line is enough to route the request.
+                let mut buffer = [0; 1024];
+                let Ok(read) = stream.read(&mut buffer).await else {
+                    return;
+                };
+                let request = String::from_utf8_lossy(&buffer[..read]);
+
+                let response = match request.split(' ').take(2).collect::<Vec<_>>()[..] {
+                    ["GET", "/metrics"] => {
+                        let body = metrics.render();
+
+                        format!(
+                            "HTTP/1.1 200 OK\r\n\
+                             Content-Type: text/plain; version=0.0.4\r\n\
+                             Content-Length: {}\r\n\
+                             Connection: close\r\n\r\n{body}",
+                            body.len(),
+                        )
+                    }
+                    _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        .to_owned(),
+                };
+
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            });
+        }
+    }
+}
+
+/// A label value, escaped.
+struct Label<'a>(&'a str);
+
+impl fmt::Display for Label<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for character in self.0.chars() {
+            match character {
+                '\\' => formatter.write_str("\\\\")?,
+                '"' => formatter.write_str("\\\"")?,
+                '\n' => formatter.write_str("\\n")?,
+                character => formatter.write_char(character)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn render(registry: &Registry, output: &mut String) -> fmt::Result {
+    writeln!(
+        output,
+        "# HELP ocppx_messages_total Number of Calls, by direction and action."
+    )?;
+    writeln!(output, "# TYPE ocppx_messages_total counter")?;
+
+    for ((direction, action), count) in &registry.messages {
+        writeln!(
+            output,
+            "ocppx_messages_total{{direction=\"{}\",action=\"{}\"}} {count}",
+            direction.as_str(),
+            Label(action),
+        )?;
+    }
+
+    render_histograms(
+        output,
+        "ocppx_call_duration_seconds",
+        "Time for a sent Call to be answered, by action.",
+        &registry.call_duration,
+    )?;
+    render_histograms(
+        output,
+        "ocppx_call_handling_seconds",
+        "Time for a received Call to be handled, by action.",
+        &registry.call_handling,
+    )?;
+
+    writeln!(
+        output,
+        "# HELP ocppx_call_errors_total Number of CallErrors, by error code."
+    )?;
+    writeln!(output, "# TYPE ocppx_call_errors_total counter")?;
+
+    for (code, count) in &registry.call_errors {
+        writeln!(
+            output,
+            "ocppx_call_errors_total{{code=\"{}\"}} {count}",
+            Label(code),
+        )?;
+    }
+
+    writeln!(
+        output,
+        "# HELP ocppx_connected_charge_points Number of connected Charge Points."
+    )?;
+    writeln!(output, "# TYPE ocppx_connected_charge_points gauge")?;
+    writeln!(
+        output,
+        "ocppx_connected_charge_points {}",
+        registry.connected_charge_points,
+    )?;
+
+    writeln!(
+        output,
+        "# HELP ocppx_reconnects_total Number of successful reconnections."
+    )?;
+    writeln!(output, "# TYPE ocppx_reconnects_total counter")?;
+    writeln!(output, "ocppx_reconnects_total {}", registry.reconnects)
+}
+
+fn render_histograms(
+    output: &mut String,
+    name: &str,
+    help: &str,
+    histograms: &BTreeMap<String, Histogram>,
+) -> fmt::Result {
+    writeln!(output, "# HELP {name} {help}")?;
+    writeln!(output, "# TYPE {name} histogram")?;
+
+    for (action, histogram) in histograms {
+        let action = Label(action);
+
+        for (count, upper_bound) in histogram.buckets.iter().zip(LATENCY_BUCKETS) {
+            writeln!(
+                output,
+                "{name}_bucket{{action=\"{action}\",le=\"{upper_bound}\"}} {count}",
+            )?;
+        }
+
+        writeln!(
+            output,
+            "{name}_bucket{{action=\"{action}\",le=\"+Inf\"}} {}",
+            histogram.count,
+        )?;
+        writeln!(
+            output,
+            "{name}_sum{{action=\"{action}\"}} {}",
+            histogram.sum,
+        )?;
+        writeln!(
+            output,
+            "{name}_count{{action=\"{action}\"}} {}",
+            histogram.count,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::new();
+        metrics.record_message(Direction::Incoming, "Heartbeat");
+        metrics.record_message(Direction::Incoming, "Heartbeat");
+        metrics.record_call_latency("Reset", Duration::from_millis(20));
+        metrics.record_call_handling("Heartbeat", Duration::from_millis(1));
+        metrics.record_call_error(&ErrorCode::NotImplemented);
+        metrics.set_connected_charge_points(2);
+
+        let output = metrics.render();
+
+        assert!(output
+            .contains("ocppx_messages_total{direction=\"incoming\",action=\"Heartbeat\"} 2\n"));
+        assert!(
+            output.contains("ocppx_call_duration_seconds_bucket{action=\"Reset\",le=\"0.01\"} 0\n")
+        );
+        assert!(output
+            .contains("ocppx_call_duration_seconds_bucket{action=\"Reset\",le=\"0.025\"} 1\n"));
+        assert!(output.contains("ocppx_call_duration_seconds_count{action=\"Reset\"} 1\n"));
+        assert!(output
+            .contains("ocppx_call_handling_seconds_bucket{action=\"Heartbeat\",le=\"0.005\"} 1\n"));
+        assert!(output.contains("ocppx_call_errors_total{code=\"NotImplemented\"} 1\n"));
+        assert!(output.contains("ocppx_connected_charge_points 2\n"));
+        assert!(output.contains("ocppx_reconnects_total 0\n"));
+    }
+}
@@ -0,0 +1,339 @@
+use crate::{Message, MessageIdError, MessageIds};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use thiserror::Error;
+use tokio::{
+    sync::{oneshot, OwnedSemaphorePermit, Semaphore},
+    time,
+};
+
+/// The default number of outstanding `Call`s per direction: OCPP-J allows
+/// a single one.
+pub const DEFAULT_MAX_OUTSTANDING_CALLS: usize = 1;
+
+/// The default time to wait for the response to a `Call`.
+pub const DEFAULT_CALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum PendingCallError {
+    #[error("no response received after {0:?}")]
+    Timeout(Duration),
+
+    #[error("the pending call has been cancelled")]
+    Cancelled,
+}
+
+/// The pending `Call`s by unique ID, with the registration they belong to.
+type Calls = Arc<Mutex<HashMap<String, (u64, oneshot::Sender<Message>)>>>;
+
+/// The `Call`s sent to a peer which are waiting for a response.
+///
+/// Registering a `Call` waits until fewer than `max_outstanding` `Call`s are
+/// pending, so that a sender never has more outstanding `Call`s than
+/// allowed.
+#[derive(Debug)]
+pub struct PendingCalls {
+    calls: Calls,
+    /// The number of registrations so far.
+    registrations: AtomicU64,
+    slots: Arc<Semaphore>,
+    timeout: Duration,
+}
+
+impl Default for PendingCalls {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_OUTSTANDING_CALLS, DEFAULT_CALL_TIMEOUT)
+    }
+}
+
+impl PendingCalls {
+    /// Create a registry allowing `max_outstanding` `Call`s at once (at
+    /// least 1), each waiting up to `timeout` for its response.
+    pub fn new(max_outstanding: usize, timeout: Duration) -> Self {
+        Self {
+            calls: Default::default(),
+            registrations: AtomicU64::new(0),
+            slots: Arc::new(Semaphore::new(max_outstanding.max(1))),
+            timeout,
+        }
+    }
+
+    /// Register the `Call` identified by `unique_id`, waiting for a free
+    /// slot first. The `Call` must be sent once registered.
+    ///
+    /// A `Call` with the unique ID of a pending one takes its place: the
+    /// previous one is cancelled, see [`Self::register_unique`] to avoid it.
+    pub async fn register(&self, unique_id: impl Into<String>) -> PendingCall {
+        let permit = self
+            .slots
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("the semaphore is never closed");
+
+        let unique_id = unique_id.into();
+        let registration = self.registrations.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = oneshot::channel();
+        self.calls
+            .lock()
+            .unwrap()
+            .insert(unique_id.clone(), (registration, sender));
+
+        PendingCall {
+            unique_id,
+            registration,
+            receiver,
+            calls: self.calls.clone(),
+            timeout: self.timeout,
+            _permit: permit,
+        }
+    }
+
+    /// Register a `Call` with a unique ID of `message_ids` that is not
+    /// pending yet, waiting for a free slot first. The `Call` must be sent
+    /// with [`PendingCall::unique_id`] once registered.
+    ///
+    /// Fails if `message_ids` keeps generating pending unique IDs.
+    pub async fn register_unique(
+        &self,
+        message_ids: &MessageIds,
+    ) -> Result<PendingCall, MessageIdError> {
+        const ATTEMPTS: usize = 8;
+
+        let permit = self
+            .slots
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("the semaphore is never closed");
+
+        let mut calls = self.calls.lock().unwrap();
+        let mut unique_id = message_ids.next();
+
+        for _ in 1..ATTEMPTS {
+            if !calls.contains_key(unique_id.as_str()) {
+                break;
+            }
+
+            unique_id = message_ids.next();
+        }
+
+        if calls.contains_key(unique_id.as_str()) {
+            return Err(MessageIdError::Pending(unique_id.into_string()));
+        }
+
+        let unique_id = unique_id.into_string();
+        let registration = self.registrations.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = oneshot::channel();
+        calls.insert(unique_id.clone(), (registration, sender));
+
+        Ok(PendingCall {
+            unique_id,
+            registration,
+            receiver,
+            calls: self.calls.clone(),
+            timeout: self.timeout,
+            _permit: permit,
+        })
+    }
+
+    /// Deliver a `CallResult` or a `CallError` to its pending `Call`.
+    ///
+    /// Returns the message back if no `Call` is waiting for it.
+    pub fn resolve(&self, response: Message) -> Result<(), Message> {
+        let pending_call = self.calls.lock().unwrap().remove(response.unique_id());
+
+        match pending_call {
+            Some((_, pending_call)) => {
+                // The caller may have given up in the meantime.
+                let _ = pending_call.send(response);
+
+                Ok(())
+            }
+            None => {
+                log::debug!(unique_id = response.unique_id(); "no pending Call for the response");
+
+                Err(response)
+            }
+        }
+    }
+
+    /// Cancel the pending `Call` identified by `unique_id`, e.g. when it
+    /// cannot be sent. Returns whether it was pending.
+    pub fn cancel(&self, unique_id: &str) -> bool {
+        self.calls.lock().unwrap().remove(unique_id).is_some()
+    }
+
+    /// Cancel all the pending `Call`s, e.g. when the connection is closed.
+    pub fn cancel_all(&self) {
+        self.calls.lock().unwrap().clear();
+    }
+
+    /// The number of `Call`s waiting for a response.
+    pub fn len(&self) -> usize {
+        self.calls.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A registered `Call`, see [`PendingCalls::register`].
+///
+/// Dropping it unregisters the `Call` and frees its slot.
+#[derive(Debug)]
+pub struct PendingCall {
+    unique_id: String,
+    /// Which registration of `unique_id` it is.
+    registration: u64,
+    receiver: oneshot::Receiver<Message>,
+    calls: Calls,
+    timeout: Duration,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl PendingCall {
+    pub fn unique_id(&self) -> &str {
+        &self.unique_id
+    }
+
+    /// Wait for the response, i.e. a `CallResult` or a `CallError`.
+    pub async fn wait(mut self) -> Result<Message, PendingCallError> {
+        match time::timeout(self.timeout, &mut self.receiver).await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(_)) => Err(PendingCallError::Cancelled),
+            Err(_) => {
+                log::debug!(unique_id = self.unique_id.as_str(); "no response to the Call after {:?}", self.timeout);
+
+                Err(PendingCallError::Timeout(self.timeout))
+            }
+        }
+    }
+}
+
+impl Drop for PendingCall {
+    fn drop(&mut self) {
+        let mut calls = self.calls.lock().unwrap();
+
+        // The unique ID may have been registered again since, by another
+        // `Call` that is still pending.
+        if calls
+            .get(&self.unique_id)
+            .is_some_and(|(registration, _)| *registration == self.registration)
+        {
+            calls.remove(&self.unique_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CallResult;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_resolve() {
+        let pending_calls = PendingCalls::default();
+        let pending_call = pending_calls.register("1").await;
+
+        assert!(pending_calls
+            .resolve(CallResult::new("1", &json!({})).unwrap().into())
+            .is_ok());
+        assert!(matches!(
+            pending_call.wait().await,
+            Ok(Message::CallResult(_))
+        ));
+        assert!(pending_calls.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_timeout() {
+        let pending_calls = PendingCalls::new(1, Duration::from_secs(5));
+        let pending_call = pending_calls.register("1").await;
+
+        assert_eq!(
+            pending_call.wait().await.unwrap_err(),
+            PendingCallError::Timeout(Duration::from_secs(5))
+        );
+        assert!(pending_calls.is_empty());
+        assert!(pending_calls
+            .resolve(CallResult::new("1", &json!({})).unwrap().into())
+            .is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_one_outstanding_call() {
+        let pending_calls = PendingCalls::default();
+        let first = pending_calls.register("1").await;
+
+        assert!(
+            time::timeout(Duration::from_secs(1), pending_calls.register("2"))
+                .await
+                .is_err()
+        );
+
+        drop(first);
+
+        let second = pending_calls.register("2").await;
+        assert_eq!(second.unique_id(), "2");
+    }
+
+    #[tokio::test]
+    async fn test_register_twice() {
+        let pending_calls = PendingCalls::new(2, DEFAULT_CALL_TIMEOUT);
+        let first = pending_calls.register("1").await;
+        let second = pending_calls.register("1").await;
+
+        // The first `Call` is cancelled, and its end does not unregister
+        // the second one.
+        assert_eq!(first.wait().await.unwrap_err(), PendingCallError::Cancelled);
+        assert_eq!(pending_calls.len(), 1);
+
+        assert!(pending_calls
+            .resolve(CallResult::new("1", &json!({})).unwrap().into())
+            .is_ok());
+        assert!(matches!(second.wait().await, Ok(Message::CallResult(_))));
+        assert!(pending_calls.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_register_unique() {
+        use crate::{MessageId, MessageIds};
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        let pending_calls = PendingCalls::new(4, DEFAULT_CALL_TIMEOUT);
+        let first = pending_calls.register("1").await;
+
+        // `1` is pending: the next unique ID is taken.
+        let next = AtomicU64::new(1);
+        let message_ids = MessageIds::new(move || {
+            MessageId::new(next.fetch_add(1, Ordering::Relaxed).to_string()).unwrap()
+        });
+        let second = pending_calls.register_unique(&message_ids).await.unwrap();
+        assert_eq!(second.unique_id(), "2");
+
+        let constant = MessageIds::new(|| MessageId::new("1").unwrap());
+        assert_eq!(
+            pending_calls.register_unique(&constant).await.unwrap_err(),
+            MessageIdError::Pending("1".to_owned())
+        );
+
+        drop(first);
+        assert_eq!(
+            pending_calls
+                .register_unique(&constant)
+                .await
+                .unwrap()
+                .unique_id(),
+            "1"
+        );
+    }
+}
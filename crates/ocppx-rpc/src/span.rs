@@ -0,0 +1,117 @@
+use std::future::Future;
+use tokio::task::futures::TaskLocalFuture;
+
+tokio::task_local! {
+    static CURRENT: Span;
+}
+
+/// The context of the records logged while a future runs: the records of a
+/// connection carry its `charge_point_id`, and the ones of a `Call` its
+/// `unique_id` and its `action`, including the records of the handlers.
+/// [`JsonLogger`] adds them to the records.
+///
+/// It is not a `tracing` span: it is kept in a Tokio task-local, read by the
+/// `log` records only, and a task spawned by the future does not inherit it
+/// unless it is instrumented too.
+///
+/// ```rust,ignore
+/// Span::call("CP001", &call.unique_id, &call.action)
+///     .instrument(handler.handle_call("CP001", call))
+///     .await
+/// ```
+///
+/// [`JsonLogger`]: crate::JsonLogger
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Span {
+    pub charge_point_id: Option<String>,
+    pub unique_id: Option<String>,
+    pub action: Option<String>,
+}
+
+impl Span {
+    /// The span of a connection.
+    pub fn charge_point(charge_point_id: &str) -> Self {
+        Self {
+            charge_point_id: Some(charge_point_id.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    /// The span of a `Call`, from when it is sent or received to its
+    /// response.
+    pub fn call(charge_point_id: &str, unique_id: &str, action: &str) -> Self {
+        Self {
+            charge_point_id: Some(charge_point_id.to_owned()),
+            unique_id: Some(unique_id.to_owned()),
+            action: Some(action.to_owned()),
+        }
+    }
+
+    /// The span of the running task, if any.
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// Run `future` in this span. The fields that are not set are inherited
+    /// from the current span.
+    pub fn instrument<F>(self, future: F) -> TaskLocalFuture<Span, F>
+    where
+        F: Future,
+    {
+        let span = match Self::current() {
+            Some(parent) => Self {
+                charge_point_id: self.charge_point_id.or(parent.charge_point_id),
+                unique_id: self.unique_id.or(parent.unique_id),
+                action: self.action.or(parent.action),
+            },
+            None => self,
+        };
+
+        CURRENT.scope(span, future)
+    }
+
+    /// The fields that are set, by name.
+    pub(crate) fn fields(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        [
+            ("charge_point_id", &self.charge_point_id),
+            ("unique_id", &self.unique_id),
+            ("action", &self.action),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value.as_deref()?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_span() {
+        assert_eq!(Span::current(), None);
+
+        let span = Span::charge_point("CP001")
+            .instrument(async {
+                Span {
+                    unique_id: Some("1".to_owned()),
+                    ..Default::default()
+                }
+                .instrument(async { Span::current() })
+                .await
+            })
+            .await;
+
+        assert_eq!(
+            span,
+            Some(Span {
+                charge_point_id: Some("CP001".to_owned()),
+                unique_id: Some("1".to_owned()),
+                action: None,
+            })
+        );
+        assert_eq!(
+            span.unwrap().fields().collect::<Vec<_>>(),
+            [("charge_point_id", "CP001"), ("unique_id", "1")]
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use futures_util::{Sink, Stream};
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::sync::mpsc;
+pub use tokio_tungstenite::tungstenite::{Error as TransportError, Message as Frame};
+
+/// A connection carrying the frames of OCPP-J between a Charge Point and a
+/// Central System.
+///
+/// It is implemented by the WebSocket connections of `tokio-tungstenite`,
+/// and by [`MemoryTransport`], to wire a client and a server together
+/// without a network.
+pub trait Transport:
+    Stream<Item = Result<Frame, TransportError>>
+    + Sink<Frame, Error = TransportError>
+    + Send
+    + Unpin
+    + 'static
+{
+}
+
+impl<T> Transport for T where
+    T: Stream<Item = Result<Frame, TransportError>>
+        + Sink<Frame, Error = TransportError>
+        + Send
+        + Unpin
+        + 'static
+{
+}
+
+/// The future returned by [`Connector::connect`].
+pub type ConnectFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Pin<Box<dyn Transport>>, TransportError>> + Send + 'a>>;
+
+/// Open a [`Transport`] to a Central System, at each connection of a
+/// Charge Point and at each of its reconnections.
+///
+/// It is implemented by the closures returning a future of a transport,
+/// e.g. a [`FramedTransport`][crate::FramedTransport] over a TCP
+/// connection:
+///
+/// ```rust,ignore
+/// let connector = || async {
+///     Ok(FramedTransport::new(TcpStream::connect("10.0.0.1:9000").await?))
+/// };
+/// ```
+pub trait Connector: Send + Sync + 'static {
+    fn connect(&self) -> ConnectFuture<'_>;
+}
+
+impl<F, Fut, T> Connector for F
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<T, TransportError>> + Send + 'static,
+    T: Transport,
+{
+    fn connect(&self) -> ConnectFuture<'_> {
+        let transport = self();
+
+        Box::pin(async move { Ok(Box::pin(transport.await?) as Pin<Box<dyn Transport>>) })
+    }
+}
+
+/// An in-process [`Transport`]: one end of a pair created with
+/// [`MemoryTransport::pair`].
+///
+/// The frames are delivered in order, without any I/O, so that the tests
+/// using it are deterministic, e.g. with the time of Tokio paused. The
+/// pings are answered with pongs, as a WebSocket peer would do.
+#[derive(Debug)]
+pub struct MemoryTransport {
+    /// `None` once closed.
+    sender: Option<mpsc::UnboundedSender<Frame>>,
+    receiver: mpsc::UnboundedReceiver<Frame>,
+}
+
+impl MemoryTransport {
+    /// Two connected ends: the frames sent to one are received by the
+    /// other.
+    pub fn pair() -> (Self, Self) {
+        let (left_sender, right_receiver) = mpsc::unbounded_channel();
+        let (right_sender, left_receiver) = mpsc::unbounded_channel();
+
+        (
+            Self {
+                sender: Some(left_sender),
+                receiver: left_receiver,
+            },
+            Self {
+                sender: Some(right_sender),
+                receiver: right_receiver,
+            },
+        )
+    }
+}
+
+impl Stream for MemoryTransport {
+    type Item = Result<Frame, TransportError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let frame = self.receiver.poll_recv(context);
+
+        if let (Poll::Ready(Some(Frame::Ping(payload))), Some(sender)) = (&frame, &self.sender) {
+            // Nobody listening anymore is fine.
+            let _ = sender.send(Frame::Pong(payload.clone()));
+        }
+
+        frame.map(|frame| frame.map(Ok))
+    }
+}
+
+impl Sink<Frame> for MemoryTransport {
+    type Error = TransportError;
+
+    fn poll_ready(
+        self: Pin<&mut Self>,
+        _context: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(match &self.sender {
+            Some(_) => Ok(()),
+            None => Err(TransportError::AlreadyClosed),
+        })
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, frame: Frame) -> Result<(), Self::Error> {
+        let close = matches!(frame, Frame::Close(_));
+        let sender = self.sender.as_ref().ok_or(TransportError::AlreadyClosed)?;
+
+        sender
+            .send(frame)
+            .map_err(|_| TransportError::ConnectionClosed)?;
+
+        // Nothing is sent after a close frame.
+        if close {
+            self.sender = None;
+        }
+
+        Ok(())
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        _context: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+        mut self: Pin<&mut Self>,
+        _context: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.sender = None;
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::{SinkExt, StreamExt};
+
+    #[tokio::test]
+    async fn test_memory_transport() {
+        let (mut left, mut right) = MemoryTransport::pair();
+
+        left.send(Frame::text("[2,\"1\",\"Heartbeat\",{}]"))
+            .await
+            .unwrap();
+        right.send(Frame::Ping(b"ping".to_vec())).await.unwrap();
+        assert_eq!(
+            right.next().await.unwrap().unwrap(),
+            Frame::text("[2,\"1\",\"Heartbeat\",{}]")
+        );
+
+        // The ping is answered.
+        assert_eq!(
+            left.next().await.unwrap().unwrap(),
+            Frame::Ping(b"ping".to_vec())
+        );
+        assert_eq!(
+            right.next().await.unwrap().unwrap(),
+            Frame::Pong(b"ping".to_vec())
+        );
+
+        // Closed after a close frame.
+        left.send(Frame::Close(None)).await.unwrap();
+        assert!(matches!(
+            left.send(Frame::text("[]")).await,
+            Err(TransportError::AlreadyClosed)
+        ));
+        assert_eq!(right.next().await.unwrap().unwrap(), Frame::Close(None));
+        assert!(right.next().await.is_none());
+
+        drop(left);
+        assert!(matches!(
+            right.send(Frame::text("[]")).await,
+            Err(TransportError::ConnectionClosed)
+        ));
+    }
+}
@@ -0,0 +1,125 @@
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// The `Call`s sent on a connection which have not been answered yet.
+///
+/// OCPP-J forbids to send a `Call` before the response to the previous one
+/// has been received, or the previous one has timed out. [`PendingCalls`]
+/// bounds the callers waiting for a response, but a caller can give up on
+/// a `Call` that is still on its way; the window enforces the ordering
+/// where the frames are sent, and stays closed until the response is
+/// received, or until `timeout`.
+///
+/// More than one outstanding `Call` pipelines them, which only the
+/// tolerant peers accept.
+///
+/// [`PendingCalls`]: crate::PendingCalls
+#[derive(Debug)]
+pub struct CallWindow {
+    max_outstanding: usize,
+    timeout: Duration,
+    /// The unique IDs of the `Call`s sent, and when they time out.
+    outstanding: Vec<(String, Instant)>,
+}
+
+impl CallWindow {
+    /// A window of `max_outstanding` `Call`s (at least 1), each
+    /// outstanding for up to `timeout`.
+    pub fn new(max_outstanding: usize, timeout: Duration) -> Self {
+        Self {
+            max_outstanding: max_outstanding.max(1),
+            timeout,
+            outstanding: Vec::new(),
+        }
+    }
+
+    /// Whether a `Call` can be sent.
+    pub fn is_open(&self) -> bool {
+        self.outstanding.len() < self.max_outstanding
+    }
+
+    /// The `Call` identified by `unique_id` has been sent at `now`.
+    pub fn sent(&mut self, unique_id: impl Into<String>, now: Instant) {
+        self.outstanding
+            .push((unique_id.into(), now + self.timeout));
+    }
+
+    /// A response has been received for the `Call` identified by
+    /// `unique_id`. Returns whether it was outstanding.
+    pub fn received(&mut self, unique_id: &str) -> bool {
+        let length = self.outstanding.len();
+        self.outstanding.retain(|(sent, _)| sent != unique_id);
+
+        self.outstanding.len() != length
+    }
+
+    /// When the window opens again because of a timeout, if it is closed.
+    pub fn deadline(&self) -> Option<Instant> {
+        if self.is_open() {
+            return None;
+        }
+
+        self.outstanding.iter().map(|(_, deadline)| *deadline).min()
+    }
+
+    /// Forget about the `Call`s that have timed out at `now`.
+    pub fn expire(&mut self, now: Instant) {
+        self.outstanding.retain(|(_, deadline)| *deadline > now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_call_window() {
+        let now = Instant::now();
+        let timeout = Duration::from_secs(30);
+        let mut window = CallWindow::new(1, timeout);
+
+        assert!(window.is_open());
+        assert_eq!(window.deadline(), None);
+
+        window.sent("1", now);
+        assert!(!window.is_open());
+        assert_eq!(window.deadline(), Some(now + timeout));
+
+        // A late or unknown response does not open the window.
+        assert!(!window.received("0"));
+        assert!(!window.is_open());
+
+        assert!(window.received("1"));
+        assert!(window.is_open());
+        assert!(!window.received("1"));
+
+        // Without a response, the window opens once the `Call` times out.
+        window.sent("2", now);
+        window.expire(now + timeout / 2);
+        assert!(!window.is_open());
+        window.expire(now + timeout);
+        assert!(window.is_open());
+    }
+
+    #[test]
+    fn test_pipelined_call_window() {
+        let now = Instant::now();
+        let mut window = CallWindow::new(2, Duration::from_secs(30));
+
+        window.sent("1", now);
+        assert!(window.is_open());
+        assert_eq!(window.deadline(), None);
+
+        window.sent("2", now + Duration::from_secs(1));
+        assert!(!window.is_open());
+        assert_eq!(window.deadline(), Some(now + Duration::from_secs(30)));
+
+        // The responses can arrive in any order.
+        assert!(window.received("2"));
+        assert!(window.is_open());
+        window.sent("3", now);
+        assert!(window.received("1"));
+        assert!(window.received("3"));
+        assert!(window.is_open());
+    }
+}
@@ -0,0 +1,132 @@
+//! Memory used by the server per idle connection.
+//!
+//! Open `OCPPX_BENCH_CONNECTIONS` connections (1000 by default) that send
+//! nothing after the handshake, and compare the resident memory of the
+//! process before and after. The Charge Point side of each connection is a
+//! bare TCP stream, so that the memory is mostly the server's.
+//!
+//! ```sh
+//! cargo bench -p ocppx-server --bench idle_connections
+//! ```
+//!
+//! Each connection uses 2 file descriptors, see `ulimit -n`. Linux only.
+
+use ocppx_rpc::{Call, CallError, CallResult, ErrorCode};
+use ocppx_server::{CsmsHandler, Server};
+use std::{env, fs, time::Instant};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    time::{sleep, Duration},
+};
+
+struct Handler;
+
+impl CsmsHandler for Handler {
+    async fn handle_call(
+        &self,
+        _charge_point_id: &str,
+        call: Call,
+    ) -> Result<CallResult, CallError> {
+        Err(CallError::new(
+            call.unique_id,
+            ErrorCode::NotImplemented,
+            "",
+            None,
+        ))
+    }
+}
+
+/// The resident memory of the process, in bytes.
+fn resident_memory() -> Option<u64> {
+    let statm = fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+
+    Some(pages * 4096)
+}
+
+/// Open a WebSocket connection, and leave it idle.
+async fn connect(address: &str, charge_point_id: usize) -> TcpStream {
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    stream
+        .write_all(
+            format!(
+                "GET /ocpp/CP{charge_point_id:06} HTTP/1.1\r\n\
+                 Host: {address}\r\n\
+                 Upgrade: websocket\r\n\
+                 Connection: Upgrade\r\n\
+                 Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+                 Sec-WebSocket-Version: 13\r\n\
+                 Sec-WebSocket-Protocol: ocpp1.6\r\n\r\n"
+            )
+            .as_bytes(),
+        )
+        .await
+        .unwrap();
+
+    let mut response = Vec::new();
+
+    while !response.ends_with(b"\r\n\r\n") {
+        let mut byte = [0; 1];
+        stream.read_exact(&mut byte).await.unwrap();
+        response.push(byte[0]);
+    }
+
+    assert!(
+        response.starts_with(b"HTTP/1.1 101"),
+        "the handshake has failed"
+    );
+
+    stream
+}
+
+fn main() {
+    let connections = env::var("OCPPX_BENCH_CONNECTIONS")
+        .ok()
+        .and_then(|connections| connections.parse().ok())
+        .unwrap_or(1000);
+
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(async move {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap().to_string();
+            let server = Server::new(Handler);
+
+            tokio::spawn({
+                let server = server.clone();
+
+                async move { server.serve(listener).await }
+            });
+
+            // Warm up the runtime and the allocator.
+            drop(connect(&address, connections).await);
+            sleep(Duration::from_millis(100)).await;
+
+            let Some(before) = resident_memory() else {
+                eprintln!("the resident memory can only be measured on Linux");
+
+                return;
+            };
+            let started = Instant::now();
+            let mut streams = Vec::with_capacity(connections);
+
+            for charge_point_id in 0..connections {
+                streams.push(connect(&address, charge_point_id).await);
+            }
+
+            while server.connected_charge_points().len() < connections {
+                sleep(Duration::from_millis(10)).await;
+            }
+
+            let elapsed = started.elapsed();
+            let after = resident_memory().unwrap();
+
+            println!(
+                "{connections} idle connections opened in {elapsed:?}: {per_connection} bytes per connection",
+                per_connection = after.saturating_sub(before) / connections as u64,
+            );
+        });
+}
@@ -0,0 +1,624 @@
+use chrono::{DateTime, Utc};
+use ocppx_pki::hex;
+use ocppx_rpc::Message;
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Seek, SeekFrom, Write},
+    path::Path,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError},
+    thread,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+/// The previous hash of the first entry of a log.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// A security-relevant event, recorded by an [`AuditLog`].
+///
+/// The `status` of the `Call`s sent by the Central System is the `status`
+/// of their response, the error code of their `CallError`, or `None` if
+/// the Charge Point has not responded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AuditEvent {
+    /// A Charge Point has been refused by the
+    /// [`AuthProvider`][crate::AuthProvider], `attempts` times since the
+    /// previous entry, see [`AUTHENTICATION_FAILURE_WINDOW`].
+    #[serde(rename_all = "camelCase")]
+    AuthenticationFailed {
+        charge_point_id: String,
+        attempts: u64,
+    },
+    /// An `InstallCertificate` or a `CertificateSigned` has been sent.
+    #[serde(rename_all = "camelCase")]
+    CertificateInstalled {
+        charge_point_id: String,
+        action: String,
+        certificate_type: Option<String>,
+        status: Option<String>,
+    },
+    /// A `RemoteStartTransaction` has been sent.
+    #[serde(rename_all = "camelCase")]
+    RemoteStartRequested {
+        charge_point_id: String,
+        id_tag: Option<String>,
+        connector_id: Option<u64>,
+        status: Option<String>,
+    },
+    /// A `ChangeConfiguration` has been sent. The value of the
+    /// `AuthorizationKey`, whatever its case, is not recorded.
+    #[serde(rename_all = "camelCase")]
+    ConfigurationChanged {
+        charge_point_id: String,
+        key: Option<String>,
+        value: Option<String>,
+        status: Option<String>,
+    },
+}
+
+impl AuditEvent {
+    /// The event of the `Call` of `action` sent to `charge_point_id` with
+    /// `request`, and answered with `response`, if it is security-relevant.
+    pub(crate) fn of_call(
+        charge_point_id: &str,
+        action: &str,
+        request: &Value,
+        response: Option<&Message>,
+    ) -> Option<Self> {
+        let string = |name: &str| request.get(name).and_then(Value::as_str).map(str::to_owned);
+        let status = response.map(|response| match response {
+            Message::CallResult(call_result) => call_result
+                .payload
+                .get("status")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_owned(),
+            Message::CallError(call_error) => call_error.error_code.to_string(),
+            Message::Call(_) => String::new(),
+        });
+        let charge_point_id = charge_point_id.to_owned();
+
+        Some(match action {
+            "InstallCertificate" | "CertificateSigned" => Self::CertificateInstalled {
+                charge_point_id,
+                action: action.to_owned(),
+                certificate_type: string("certificateType"),
+                status,
+            },
+            "RemoteStartTransaction" => Self::RemoteStartRequested {
+                charge_point_id,
+                id_tag: string("idTag"),
+                connector_id: request.get("connectorId").and_then(Value::as_u64),
+                status,
+            },
+            "ChangeConfiguration" => {
+                let key = string("key");
+                // The configuration keys are case-insensitive.
+                let value = string("value").filter(|_| {
+                    !key.as_deref()
+                        .is_some_and(|key| key.eq_ignore_ascii_case("AuthorizationKey"))
+                });
+
+                Self::ConfigurationChanged {
+                    charge_point_id,
+                    key,
+                    value,
+                    status,
+                }
+            }
+            _ => return None,
+        })
+    }
+}
+
+/// An entry of an [`AuditLog`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    /// The position of the entry in the log, from 0.
+    pub sequence: u64,
+    pub timestamp: DateTime<Utc>,
+    pub event: AuditEvent,
+    /// The hash of the previous entry, zeros for the first one.
+    pub previous_hash: String,
+    /// The HMAC-SHA256 of the entry without its hash, in hexadecimal.
+    pub hash: String,
+}
+
+impl AuditEntry {
+    /// The hash the entry must have with `key`.
+    fn expected_hash(&self, key: &hmac::Key) -> String {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Hashed<'a> {
+            sequence: u64,
+            timestamp: &'a DateTime<Utc>,
+            event: &'a AuditEvent,
+            previous_hash: &'a str,
+        }
+
+        let hashed = serde_json::to_vec(&Hashed {
+            sequence: self.sequence,
+            timestamp: &self.timestamp,
+            event: &self.event,
+            previous_hash: &self.previous_hash,
+        })
+        .expect("an audit entry is always serializable");
+
+        hex(hmac::sign(key, &hashed).as_ref())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum AuditError {
+    #[error("I/O error")]
+    Io(#[from] io::Error),
+
+    #[error("the entry at line {line} is not valid")]
+    Invalid {
+        line: usize,
+        source: serde_json::Error,
+    },
+
+    #[error("the entry at line {line} has been tampered with, or the entries before it")]
+    Tampered { line: usize },
+
+    #[error("the audit log is lagging behind, the event is dropped")]
+    Full,
+
+    #[error("the audit log is closed")]
+    Closed,
+}
+
+/// Append the security-relevant events to a file, one [`AuditEntry`] per
+/// line (JSON Lines), see [`ServerConfig::audit_log`].
+///
+/// Each entry holds the HMAC of the previous one, keyed with a secret kept
+/// apart from the log: modifying, removing or reordering entries breaks
+/// the chain, which [`AuditLog::verify`] detects, and cannot be hidden
+/// without the key. Removing the last entries is only detected by
+/// comparing the number of entries, or the last hash, with a copy kept
+/// elsewhere.
+///
+/// The entries are written by a dedicated thread, so that recording never
+/// blocks the server. The failed authentications of a Charge Point are
+/// aggregated: at most one entry per [`AUTHENTICATION_FAILURE_WINDOW`],
+/// counting the attempts.
+///
+/// The log is cheap to clone: clones append to the same file.
+///
+/// [`ServerConfig::audit_log`]: crate::ServerConfig::audit_log
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    commands: SyncSender<Command>,
+}
+
+/// The period over which the failed authentications of a Charge Point are
+/// aggregated in one entry.
+pub const AUTHENTICATION_FAILURE_WINDOW: Duration = Duration::from_secs(60);
+
+/// The number of events waiting to be written before new ones are
+/// dropped.
+const CAPACITY: usize = 1024;
+
+#[derive(Debug)]
+enum Command {
+    Record(AuditEvent),
+    Flush(mpsc::Sender<io::Result<()>>),
+}
+
+/// The state of the thread writing the entries.
+struct Writer {
+    file: File,
+    key: hmac::Key,
+    next_sequence: u64,
+    last_hash: String,
+    /// The failed authentications of each Charge Point not recorded yet,
+    /// with the end of their window.
+    failures: HashMap<String, (Instant, u64)>,
+}
+
+impl AuditLog {
+    /// Append to the log at `path`, creating it if it does not exist, with
+    /// the HMAC `key`. The existing entries are verified first, and an
+    /// incomplete last line, e.g. after a crash, is truncated.
+    pub fn open(path: impl AsRef<Path>, key: &[u8]) -> Result<Self, AuditError> {
+        let path = path.as_ref();
+        let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+        let (last, length) = if path.exists() {
+            read(path, &key)?
+        } else {
+            (None, 0)
+        };
+
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)?;
+        file.set_len(length)?;
+
+        let mut writer = Writer {
+            file,
+            key,
+            next_sequence: last.as_ref().map_or(0, |last| last.sequence + 1),
+            last_hash: last.map_or_else(|| GENESIS_HASH.to_owned(), |last| last.hash),
+            failures: HashMap::new(),
+        };
+        writer.file.seek(SeekFrom::End(0))?;
+
+        let (commands, receiver) = mpsc::sync_channel(CAPACITY);
+        thread::Builder::new()
+            .name("ocppx-audit".to_owned())
+            .spawn(move || writer.run(receiver))?;
+
+        Ok(Self { commands })
+    }
+
+    /// Check the chain of the entries of the log at `path`, with the HMAC
+    /// `key`. Return its last entry, if any. An incomplete last line is
+    /// ignored.
+    pub fn verify(path: impl AsRef<Path>, key: &[u8]) -> Result<Option<AuditEntry>, AuditError> {
+        Ok(read(path.as_ref(), &hmac::Key::new(hmac::HMAC_SHA256, key))?.0)
+    }
+
+    /// Append `event`, happening now, without waiting for it to be
+    /// written. Fail if too many events are waiting already.
+    pub fn record(&self, event: AuditEvent) -> Result<(), AuditError> {
+        self.commands
+            .try_send(Command::Record(event))
+            .map_err(|error| match error {
+                TrySendError::Full(_) => AuditError::Full,
+                TrySendError::Disconnected(_) => AuditError::Closed,
+            })
+    }
+
+    /// Wait until the events recorded so far are written and synced,
+    /// including the aggregated failed authentications. This blocks the
+    /// current thread.
+    pub fn flush(&self) -> Result<(), AuditError> {
+        let (sender, receiver) = mpsc::channel();
+
+        self.commands
+            .send(Command::Flush(sender))
+            .map_err(|_| AuditError::Closed)?;
+
+        Ok(receiver.recv().map_err(|_| AuditError::Closed)??)
+    }
+}
+
+/// Read and check the entries of the log at `path`. Return its last entry,
+/// if any, and the length of its complete lines.
+fn read(path: &Path, key: &hmac::Key) -> Result<(Option<AuditEntry>, u64), AuditError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut last: Option<AuditEntry> = None;
+    let mut length = 0;
+    let mut line = String::new();
+
+    for number in 1.. {
+        line.clear();
+
+        if reader.read_line(&mut line)? == 0 || !line.ends_with('\n') {
+            break;
+        }
+
+        length += line.len() as u64;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let entry =
+            serde_json::from_str::<AuditEntry>(&line).map_err(|source| AuditError::Invalid {
+                line: number,
+                source,
+            })?;
+        let (sequence, previous_hash) = last
+            .as_ref()
+            .map_or((0, GENESIS_HASH), |last| (last.sequence + 1, &last.hash));
+
+        if entry.sequence != sequence
+            || entry.previous_hash != previous_hash
+            || entry.hash != entry.expected_hash(key)
+        {
+            return Err(AuditError::Tampered { line: number });
+        }
+
+        last = Some(entry);
+    }
+
+    Ok((last, length))
+}
+
+impl Writer {
+    fn run(mut self, commands: Receiver<Command>) {
+        loop {
+            let deadline = self.failures.values().map(|(end, _)| *end).min();
+            let mut command = match deadline {
+                Some(deadline) => {
+                    match commands.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                    {
+                        Ok(command) => Some(command),
+                        Err(RecvTimeoutError::Timeout) => None,
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+                None => match commands.recv() {
+                    Ok(command) => Some(command),
+                    Err(_) => break,
+                },
+            };
+
+            // Write the events waiting, then sync them at once.
+            loop {
+                if let Err(error) = self.handle(command) {
+                    log::error!(error:% = error; "cannot write in the audit log");
+                }
+
+                match commands.try_recv() {
+                    Ok(next) => command = Some(next),
+                    Err(_) => break,
+                }
+            }
+
+            if let Err(error) = self.sync() {
+                log::error!(error:% = error; "cannot sync the audit log");
+            }
+        }
+
+        if let Err(error) = self.flush_failures(None).and_then(|()| self.sync()) {
+            log::error!(error:% = error; "cannot write in the audit log");
+        }
+    }
+
+    /// Handle `command`, or the end of the window of failed
+    /// authentications if `None`.
+    fn handle(&mut self, command: Option<Command>) -> io::Result<()> {
+        match command {
+            Some(Command::Record(event)) => self.record(event),
+            Some(Command::Flush(sender)) => {
+                let _ = sender.send(self.flush_failures(None).and_then(|()| self.sync()));
+
+                Ok(())
+            }
+            None => self.flush_failures(Some(Instant::now())),
+        }
+    }
+
+    fn record(&mut self, event: AuditEvent) -> io::Result<()> {
+        if let AuditEvent::AuthenticationFailed {
+            charge_point_id,
+            attempts,
+        } = &event
+        {
+            if let Some((_, failures)) = self.failures.get_mut(charge_point_id) {
+                *failures += attempts;
+
+                return Ok(());
+            }
+
+            self.failures.insert(
+                charge_point_id.clone(),
+                (Instant::now() + AUTHENTICATION_FAILURE_WINDOW, 0),
+            );
+        }
+
+        self.write(event)
+    }
+
+    /// Write the failed authentications whose window has ended at `now`,
+    /// or all of them.
+    fn flush_failures(&mut self, now: Option<Instant>) -> io::Result<()> {
+        let ended = self
+            .failures
+            .iter()
+            .filter(|(_, (end, _))| now.is_none_or(|now| *end <= now))
+            .map(|(charge_point_id, _)| charge_point_id.clone())
+            .collect::<Vec<_>>();
+
+        for charge_point_id in ended {
+            let (_, attempts) = self.failures.remove(&charge_point_id).unwrap();
+
+            if attempts > 0 {
+                self.write(AuditEvent::AuthenticationFailed {
+                    charge_point_id,
+                    attempts,
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write(&mut self, event: AuditEvent) -> io::Result<()> {
+        let mut entry = AuditEntry {
+            sequence: self.next_sequence,
+            timestamp: Utc::now(),
+            event,
+            previous_hash: self.last_hash.clone(),
+            hash: String::new(),
+        };
+        entry.hash = entry.expected_hash(&self.key);
+
+        let mut line = serde_json::to_string(&entry).map_err(io::Error::from)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+
+        self.next_sequence += 1;
+        self.last_hash = entry.hash;
+
+        Ok(())
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.file.sync_data()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ocppx_rpc::CallResult;
+    use serde_json::json;
+
+    #[test]
+    fn test_of_call() {
+        let response = Message::from(CallResult {
+            unique_id: "1".to_owned(),
+            payload: json!({ "status": "Accepted" }),
+        });
+
+        assert_eq!(
+            AuditEvent::of_call(
+                "CP001",
+                "ChangeConfiguration",
+                &json!({ "key": "AuthorizationKey", "value": "secret" }),
+                Some(&response),
+            ),
+            Some(AuditEvent::ConfigurationChanged {
+                charge_point_id: "CP001".to_owned(),
+                key: Some("AuthorizationKey".to_owned()),
+                value: None,
+                status: Some("Accepted".to_owned()),
+            })
+        );
+        assert_eq!(
+            AuditEvent::of_call(
+                "CP001",
+                "ChangeConfiguration",
+                &json!({ "key": "authorizationKEY", "value": "secret" }),
+                Some(&response),
+            ),
+            Some(AuditEvent::ConfigurationChanged {
+                charge_point_id: "CP001".to_owned(),
+                key: Some("authorizationKEY".to_owned()),
+                value: None,
+                status: Some("Accepted".to_owned()),
+            })
+        );
+        assert_eq!(
+            AuditEvent::of_call(
+                "CP001",
+                "RemoteStartTransaction",
+                &json!({ "idTag": "TAG" }),
+                None
+            ),
+            Some(AuditEvent::RemoteStartRequested {
+                charge_point_id: "CP001".to_owned(),
+                id_tag: Some("TAG".to_owned()),
+                connector_id: None,
+                status: None,
+            })
+        );
+        assert_eq!(
+            AuditEvent::of_call("CP001", "Heartbeat", &json!({}), None),
+            None
+        );
+    }
+
+    #[test]
+    fn test_audit_log() {
+        const KEY: &[u8] = b"secret";
+
+        let path = std::env::temp_dir().join(format!("ocppx-audit-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let event = |charge_point_id: &str| AuditEvent::AuthenticationFailed {
+            charge_point_id: charge_point_id.to_owned(),
+            attempts: 1,
+        };
+
+        let audit_log = AuditLog::open(&path, KEY).unwrap();
+        audit_log.record(event("CP001")).unwrap();
+        audit_log.flush().unwrap();
+
+        // Reopening the log continues the chain.
+        let audit_log = AuditLog::open(&path, KEY).unwrap();
+        audit_log.record(event("CP002")).unwrap();
+        audit_log.record(event("CP003")).unwrap();
+        audit_log.flush().unwrap();
+
+        let last = AuditLog::verify(&path, KEY).unwrap().unwrap();
+        assert_eq!(last.sequence, 2);
+        assert_eq!(last.event, event("CP003"));
+
+        // Without the key, the chain cannot be verified, nor forged.
+        assert!(matches!(
+            AuditLog::verify(&path, b"guess"),
+            Err(AuditError::Tampered { line: 1 })
+        ));
+
+        // Modifying an entry breaks the chain.
+        let log = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, log.replacen("CP002", "CP004", 1)).unwrap();
+        assert!(matches!(
+            AuditLog::verify(&path, KEY),
+            Err(AuditError::Tampered { line: 2 })
+        ));
+
+        // And so does removing one.
+        let lines = log.lines().collect::<Vec<_>>();
+        std::fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+        assert!(matches!(
+            AuditLog::open(&path, KEY),
+            Err(AuditError::Tampered { line: 2 })
+        ));
+
+        // An incomplete last line, e.g. after a crash, is truncated.
+        std::fs::write(&path, format!("{log}{}", &lines[2][..20])).unwrap();
+        assert_eq!(AuditLog::verify(&path, KEY).unwrap().unwrap().sequence, 2);
+
+        let audit_log = AuditLog::open(&path, KEY).unwrap();
+        audit_log.record(event("CP004")).unwrap();
+        audit_log.flush().unwrap();
+        assert_eq!(AuditLog::verify(&path, KEY).unwrap().unwrap().sequence, 3);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_authentication_failures() {
+        const KEY: &[u8] = b"secret";
+
+        let path =
+            std::env::temp_dir().join(format!("ocppx-audit-failures-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let event = |charge_point_id: &str| AuditEvent::AuthenticationFailed {
+            charge_point_id: charge_point_id.to_owned(),
+            attempts: 1,
+        };
+
+        let audit_log = AuditLog::open(&path, KEY).unwrap();
+
+        for _ in 0..100 {
+            audit_log.record(event("CP001")).unwrap();
+        }
+
+        audit_log.record(event("CP002")).unwrap();
+        audit_log.flush().unwrap();
+
+        // The first failure is recorded right away, the next ones at the
+        // end of their window.
+        let entries = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<AuditEntry>(line).unwrap().event)
+            .collect::<Vec<_>>();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0], event("CP001"));
+        assert_eq!(entries[1], event("CP002"));
+        assert_eq!(
+            entries[2],
+            AuditEvent::AuthenticationFailed {
+                charge_point_id: "CP001".to_owned(),
+                attempts: 99,
+            }
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,78 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use std::future::Future;
+
+/// The HTTP Basic Authentication credentials sent by a Charge Point in the
+/// WebSocket handshake, for the security profiles 1 and 2.
+///
+/// The username is expected to be the Charge Point identity, and the
+/// password its `AuthorizationKey`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl Credentials {
+    /// Parse the value of an `Authorization` header, e.g.
+    /// `Basic Q1AwMDE6c2VjcmV0`.
+    pub fn from_authorization_header(value: &str) -> Option<Self> {
+        let (scheme, encoded) = value.trim().split_once(' ')?;
+
+        if !scheme.eq_ignore_ascii_case("basic") {
+            return None;
+        }
+
+        let decoded = String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+        let (username, password) = decoded.split_once(':')?;
+
+        Some(Self {
+            username: username.to_owned(),
+            password: password.to_owned(),
+        })
+    }
+}
+
+/// Verify the credentials of the Charge Points before accepting their
+/// WebSocket connection.
+///
+/// `()` accepts all the Charge Points, with or without credentials.
+pub trait AuthProvider: Send + Sync + 'static {
+    /// Whether the Charge Point `charge_point_id` is allowed to connect.
+    /// `credentials` is `None` if the Charge Point sent none, or sent
+    /// malformed ones.
+    fn authenticate(
+        &self,
+        charge_point_id: &str,
+        credentials: Option<&Credentials>,
+    ) -> impl Future<Output = bool> + Send;
+}
+
+impl AuthProvider for () {
+    async fn authenticate(
+        &self,
+        _charge_point_id: &str,
+        _credentials: Option<&Credentials>,
+    ) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_credentials() {
+        assert_eq!(
+            Credentials::from_authorization_header("Basic Q1AwMDE6c2VjcmV0"),
+            Some(Credentials {
+                username: "CP001".to_owned(),
+                password: "secret".to_owned(),
+            })
+        );
+        assert_eq!(
+            Credentials::from_authorization_header("Bearer Q1AwMDE6c2VjcmV0"),
+            None
+        );
+    }
+}
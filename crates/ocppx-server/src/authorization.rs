@@ -0,0 +1,149 @@
+mod http;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use chrono::Utc;
+pub use http::HttpAuthorization;
+use ocppx_types::{
+    v1_6::{IdTagInfo, IdTagInfoStatus, LocalAuthorizationList},
+    IdTag,
+};
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteAuthorization;
+use std::{collections::HashMap, fs, future::Future, path::Path};
+
+/// Look up the ID tags presented by the users of the Charge Points, for
+/// `Authorize`, `StartTransaction` and `StopTransaction`, see
+/// [`TransactionManager`][crate::TransactionManager].
+///
+/// `()` accepts all the ID tags, and a closure `Fn(&str) -> IdTagInfo` is
+/// a provider too. The ID tags of the messages are normalized, see
+/// [`IdTag`].
+pub trait AuthorizationProvider: Send + Sync + 'static {
+    /// The status of `id_tag`, with its expiry date and parent ID tag if
+    /// any. A provider that cannot tell, e.g. because its backend is down,
+    /// answers `Invalid`.
+    fn authorize(&self, id_tag: &str) -> impl Future<Output = IdTagInfo> + Send;
+}
+
+impl AuthorizationProvider for () {
+    async fn authorize(&self, _id_tag: &str) -> IdTagInfo {
+        id_tag_info(IdTagInfoStatus::Accepted)
+    }
+}
+
+impl<F> AuthorizationProvider for F
+where
+    F: Fn(&str) -> IdTagInfo + Send + Sync + 'static,
+{
+    async fn authorize(&self, id_tag: &str) -> IdTagInfo {
+        self(id_tag)
+    }
+}
+
+/// An [`AuthorizationProvider`] with a fixed list of ID tags, e.g. read
+/// from a file. The unknown ID tags are `Invalid`, and the accepted ID tags
+/// past their expiry date are `Expired`. The ID tags are compared once
+/// normalized, see [`IdTag`].
+#[derive(Debug, Clone, Default)]
+pub struct StaticAuthorization {
+    id_tags: HashMap<IdTag, IdTagInfo>,
+}
+
+impl StaticAuthorization {
+    pub fn new<I>(id_tags: I) -> Self
+    where
+        I: IntoIterator<Item = (IdTag, IdTagInfo)>,
+    {
+        Self {
+            id_tags: id_tags.into_iter().collect(),
+        }
+    }
+
+    /// Read the ID tags from a JSON file, in the format of the local
+    /// authorization lists of `SendLocalList`:
+    ///
+    /// ```json
+    /// [
+    ///     { "idTag": "ABC", "idTagInfo": { "status": "Accepted" } },
+    ///     { "idTag": "DEF", "idTagInfo": { "status": "Blocked" } }
+    /// ]
+    /// ```
+    ///
+    /// An entry without `idTagInfo` is `Invalid`.
+    pub fn from_file(path: impl AsRef<Path>) -> crate::Result<Self> {
+        let entries: Vec<LocalAuthorizationList> = serde_json::from_slice(&fs::read(path)?)?;
+
+        Ok(Self::new(entries.into_iter().map(|entry| {
+            (
+                entry.id_tag,
+                entry
+                    .id_tag_info
+                    .unwrap_or_else(|| id_tag_info(IdTagInfoStatus::Invalid)),
+            )
+        })))
+    }
+}
+
+impl AuthorizationProvider for StaticAuthorization {
+    async fn authorize(&self, id_tag: &str) -> IdTagInfo {
+        let id_tag = IdTag::try_from(id_tag).ok();
+
+        match id_tag.and_then(|id_tag| self.id_tags.get(&id_tag)) {
+            Some(info)
+                if info.status == IdTagInfoStatus::Accepted
+                    && info.expiry_date.is_some_and(|expiry| expiry < Utc::now()) =>
+            {
+                IdTagInfo {
+                    status: IdTagInfoStatus::Expired,
+                    ..info.clone()
+                }
+            }
+            Some(info) => info.clone(),
+            None => id_tag_info(IdTagInfoStatus::Invalid),
+        }
+    }
+}
+
+pub(crate) fn id_tag_info(status: IdTagInfoStatus) -> IdTagInfo {
+    IdTagInfo::builder().status(status).build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_static_authorization() {
+        let provider = StaticAuthorization::new([
+            (
+                IdTag::try_from("ABC").unwrap(),
+                id_tag_info(IdTagInfoStatus::Accepted),
+            ),
+            (
+                IdTag::try_from("OLD").unwrap(),
+                IdTagInfo::builder()
+                    .status(IdTagInfoStatus::Accepted)
+                    .expiry_date(
+                        "2013-02-01T20:53:32.486Z"
+                            .parse::<chrono::DateTime<Utc>>()
+                            .unwrap(),
+                    )
+                    .build(),
+            ),
+        ]);
+
+        assert_eq!(
+            provider.authorize("abc").await.status,
+            IdTagInfoStatus::Accepted
+        );
+        assert_eq!(
+            provider.authorize("OLD").await.status,
+            IdTagInfoStatus::Expired
+        );
+        assert_eq!(
+            provider.authorize("XYZ").await.status,
+            IdTagInfoStatus::Invalid
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use super::{id_tag_info, AuthorizationProvider};
+use crate::{
+    http::{HttpClient, HttpResponse, HttpUrl},
+    Error, Result,
+};
+use ocppx_types::v1_6::{IdTagInfo, IdTagInfoStatus};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+#[cfg(feature = "tls")]
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time;
+
+/// The placeholder of the ID tag in the URL of [`HttpAuthorization`].
+const ID_TAG: &str = "{idTag}";
+
+/// An [`AuthorizationProvider`] asking an HTTP backend, e.g. the user
+/// database of the operator.
+///
+/// For each ID tag, the backend receives a `GET` request at the URL, where
+/// `{idTag}` is replaced by the ID tag, e.g.
+/// `https://users.example.org/id-tags/{idTag}`. It responds with:
+///
+/// - `200 OK` and an `IdTagInfo` in JSON, e.g. `{"status": "Accepted"}`,
+/// - `404 Not Found` for an unknown ID tag, which is `Invalid`.
+///
+/// Any other response, a response larger than
+/// [`Self::max_response_size`], or a backend not responding within the
+/// timeout, is `Invalid` too, and logged. The `https://` URLs need the
+/// `tls` feature, and the root certificates given with [`Self::tls`].
+#[derive(Debug, Clone)]
+pub struct HttpAuthorization {
+    url: HttpUrl,
+    http: HttpClient,
+    timeout: Duration,
+}
+
+impl HttpAuthorization {
+    pub fn new(url: &str) -> Result<Self> {
+        let url = HttpUrl::parse(url)
+            .filter(|parsed| parsed.path.contains(ID_TAG))
+            .ok_or_else(|| Error::InvalidUrl(url.to_owned()))?;
+
+        Ok(Self {
+            url,
+            http: HttpClient::default().max_response_size(64 << 10),
+            timeout: Duration::from_secs(5),
+        })
+    }
+
+    /// The time to wait for the backend. Defaults to 5 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+
+        self
+    }
+
+    /// Connect to an `https://` backend with `config`.
+    #[cfg(feature = "tls")]
+    pub fn tls(mut self, config: Arc<rustls::ClientConfig>) -> Self {
+        self.http = self.http.tls(config);
+
+        self
+    }
+
+    /// The size of the largest response accepted from the backend, head
+    /// included, in bytes. Defaults to 64 KiB.
+    pub fn max_response_size(mut self, max_response_size: usize) -> Self {
+        self.http = self.http.max_response_size(max_response_size);
+
+        self
+    }
+
+    async fn get(&self, id_tag: &str) -> std::io::Result<HttpResponse> {
+        let request = format!(
+            "GET {path} HTTP/1.0\r\nHost: {host}\r\nAccept: application/json\r\n\r\n",
+            path = self
+                .url
+                .path
+                .replace(ID_TAG, &utf8_percent_encode(id_tag, UNRESERVED).to_string()),
+            host = self.url.host_header(),
+        );
+
+        self.http.send(&self.url, request.as_bytes()).await
+    }
+}
+
+impl AuthorizationProvider for HttpAuthorization {
+    async fn authorize(&self, id_tag: &str) -> IdTagInfo {
+        let error = match time::timeout(self.timeout, self.get(id_tag)).await {
+            Ok(Ok(HttpResponse { status: 200, body })) => match serde_json::from_slice(&body) {
+                Ok(info) => return info,
+                Err(error) => error.to_string(),
+            },
+            Ok(Ok(HttpResponse { status: 404, .. })) => {
+                return id_tag_info(IdTagInfoStatus::Invalid)
+            }
+            Ok(Ok(HttpResponse { status, .. })) => format!("unexpected HTTP status {status}"),
+            Ok(Err(error)) => error.to_string(),
+            Err(_) => format!("no response after {:?}", self.timeout),
+        };
+
+        log::warn!(
+            host = self.url.host.as_str(),
+            id_tag,
+            error = error.as_str();
+            "cannot authorize the ID tag"
+        );
+
+        id_tag_info(IdTagInfoStatus::Invalid)
+    }
+}
+
+/// Everything but the unreserved characters of RFC 3986 is percent-encoded.
+const UNRESERVED: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    #[tokio::test]
+    async fn test_http_authorization() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = [0; 1024];
+                let length = stream.read(&mut request).await.unwrap();
+                let request = String::from_utf8_lossy(&request[..length]);
+
+                let response = if request.starts_with("GET /id-tags/A%20B HTTP/1.0") {
+                    "HTTP/1.0 200 OK\r\n\r\n{\"status\":\"Blocked\"}"
+                } else if request.starts_with("GET /id-tags/LARGE HTTP/1.0") {
+                    "HTTP/1.0 200 OK\r\n\r\n{\"status\":\"Accepted\",\"parentIdTag\":\"0123456789\"}"
+                } else {
+                    "HTTP/1.0 404 Not Found\r\n\r\n"
+                };
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let provider =
+            HttpAuthorization::new(&format!("http://127.0.0.1:{port}/id-tags/{{idTag}}"))
+                .unwrap()
+                .max_response_size(64);
+
+        assert_eq!(
+            provider.authorize("A B").await.status,
+            IdTagInfoStatus::Blocked
+        );
+        assert_eq!(
+            provider.authorize("C").await.status,
+            IdTagInfoStatus::Invalid
+        );
+        assert_eq!(
+            provider.authorize("LARGE").await.status,
+            IdTagInfoStatus::Invalid
+        );
+        assert!(HttpAuthorization::new("https://users.example.org/{idTag}").is_ok());
+        assert!(HttpAuthorization::new("ftp://users.example.org/{idTag}").is_err());
+        assert!(HttpAuthorization::new("https://users.example.org/id-tags").is_err());
+    }
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn test_https_authorization() {
+        use crate::rustls::{
+            pki_types::PrivatePkcs8KeyDer, ClientConfig, RootCertStore, ServerConfig,
+        };
+        use rcgen::{CertificateParams, KeyPair};
+        use tokio_rustls::TlsAcceptor;
+
+        let key = KeyPair::generate().unwrap();
+        let certificate = CertificateParams::new(vec!["localhost".to_owned()])
+            .unwrap()
+            .self_signed(&key)
+            .unwrap();
+
+        let mut root_store = RootCertStore::empty();
+        root_store.add(certificate.der().clone()).unwrap();
+
+        let acceptor = TlsAcceptor::from(Arc::new(
+            ServerConfig::builder()
+                .with_no_client_auth()
+                .with_single_cert(
+                    vec![certificate.der().clone()],
+                    PrivatePkcs8KeyDer::from(key.serialize_der()).into(),
+                )
+                .unwrap(),
+        ));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = acceptor.accept(stream).await.unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).await.unwrap();
+
+            stream
+                .write_all(b"HTTP/1.0 200 OK\r\n\r\n{\"status\":\"Blocked\"}")
+                .await
+                .unwrap();
+            stream.shutdown().await.unwrap();
+        });
+
+        let provider =
+            HttpAuthorization::new(&format!("https://localhost:{port}/id-tags/{{idTag}}"))
+                .unwrap()
+                .tls(Arc::new(
+                    ClientConfig::builder()
+                        .with_root_certificates(root_store)
+                        .with_no_client_auth(),
+                ));
+
+        assert_eq!(
+            provider.authorize("ABC").await.status,
+            IdTagInfoStatus::Blocked
+        );
+    }
+}
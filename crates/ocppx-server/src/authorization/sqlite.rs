@@ -0,0 +1,239 @@
+use super::{id_tag_info, AuthorizationProvider, StaticAuthorization};
+use crate::Result;
+use ocppx_sqlite::{Connection, Row, Value};
+use ocppx_types::{
+    v1_6::{IdTagInfo, IdTagInfoStatus},
+    IdTag,
+};
+use serde_json::Map;
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// How long to wait for the back office to commit its changes.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The `id_tag` column normalized as an [`IdTag`]: without the ASCII
+/// whitespaces, and uppercased.
+const NORMALIZED_ID_TAG: &str = "upper(replace(replace(replace(replace(replace(\
+    id_tag, ' ', ''), char(9), ''), char(10), ''), char(12), ''), char(13), ''))";
+
+/// An [`AuthorizationProvider`] reading the ID tags from an SQLite
+/// database, e.g. maintained by the back office of the operator, in the
+/// table:
+///
+/// ```sql
+/// CREATE TABLE id_tags (
+///     id_tag TEXT PRIMARY KEY,
+///     status TEXT NOT NULL,     -- e.g. 'Accepted' or 'Blocked'
+///     expiry_date TEXT,         -- e.g. '2030-01-01T00:00:00Z'
+///     parent_id_tag TEXT
+/// );
+/// ```
+///
+/// The ID tags are answered as by [`StaticAuthorization`]. The database is
+/// opened read-only with SQLite, which sees the committed changes only, and
+/// each ID tag is looked up when it is authorized, out of the async
+/// runtime. The ID tags are compared once normalized, see [`IdTag`]: the
+/// lookup is fast with an index on the normalized `id_tag`,
+///
+/// ```sql
+/// CREATE INDEX id_tags_normalized ON id_tags (upper(replace(replace(replace(
+///     replace(replace(id_tag, ' ', ''), char(9), ''), char(10), ''),
+///     char(12), ''), char(13), '')));
+/// ```
+///
+/// and the rows with the same normalized `id_tag` are ambiguous: the ID tag
+/// is `Invalid`.
+#[derive(Debug, Clone)]
+pub struct SqliteAuthorization {
+    path: PathBuf,
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteAuthorization {
+    /// Open the database at `path`, and check its `id_tags` table.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_owned();
+        let connection = Connection::open_read_only(&path)?;
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+
+        // Fail now rather than on the first `Authorize`.
+        select(&connection, "")?;
+
+        Ok(Self {
+            path,
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    /// The information of `id_tag`, once normalized, in the database.
+    async fn id_tag_info(&self, id_tag: IdTag) -> ocppx_sqlite::Result<Option<IdTagInfo>> {
+        let connection = self.connection.clone();
+
+        // SQLite blocks while the back office holds a lock.
+        let rows = tokio::task::spawn_blocking({
+            let id_tag = id_tag.clone();
+
+            move || select(&connection.lock().unwrap(), &id_tag)
+        })
+        .await
+        .expect("the lookup of the ID tag has panicked")?;
+
+        Ok(match rows.as_slice() {
+            [] => None,
+            [row] => id_tag_info_of(&id_tag, row),
+            _ => {
+                log::warn!(
+                    path:? = self.path,
+                    id_tag:% = id_tag;
+                    "several rows for the ID tag in the database"
+                );
+
+                Some(id_tag_info(IdTagInfoStatus::Invalid))
+            }
+        })
+    }
+}
+
+impl AuthorizationProvider for SqliteAuthorization {
+    async fn authorize(&self, id_tag: &str) -> IdTagInfo {
+        let Ok(normalized_id_tag) = IdTag::try_from(id_tag) else {
+            return id_tag_info(IdTagInfoStatus::Invalid);
+        };
+
+        match self.id_tag_info(normalized_id_tag.clone()).await {
+            Ok(info) => {
+                StaticAuthorization::new(info.map(|info| (normalized_id_tag, info)))
+                    .authorize(id_tag)
+                    .await
+            }
+            Err(error) => {
+                log::warn!(
+                    path:? = self.path,
+                    id_tag,
+                    error:% = error;
+                    "cannot authorize the ID tag"
+                );
+
+                id_tag_info(IdTagInfoStatus::Invalid)
+            }
+        }
+    }
+}
+
+/// Select the rows whose normalized `id_tag` is `id_tag`.
+fn select(connection: &Connection, id_tag: &str) -> ocppx_sqlite::Result<Vec<Row>> {
+    connection.query(
+        &format!(
+            "SELECT id_tag, status, expiry_date, parent_id_tag FROM id_tags \
+             WHERE {NORMALIZED_ID_TAG} = ?1"
+        ),
+        &[id_tag.into()],
+    )
+}
+
+/// The information of `id_tag` in `row`. `None` if it is invalid.
+fn id_tag_info_of(id_tag: &IdTag, row: &Row) -> Option<IdTagInfo> {
+    let mut info = Map::new();
+
+    for (column, field) in [
+        ("status", "status"),
+        ("expiry_date", "expiryDate"),
+        ("parent_id_tag", "parentIdTag"),
+    ] {
+        if let Some(value) = row.get(column).and_then(Value::as_str) {
+            info.insert(field.to_owned(), value.into());
+        }
+    }
+
+    match serde_json::from_value::<IdTagInfo>(info.into()) {
+        Ok(info) => Some(info),
+        Err(error) => {
+            log::warn!(
+                id_tag:% = id_tag,
+                error:% = error;
+                "invalid ID tag in the database"
+            );
+
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sqlite_authorization() {
+        let path =
+            std::env::temp_dir().join(format!("ocppx-id-tags-{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        // The back office.
+        let back_office = Connection::open(&path).unwrap();
+        back_office
+            .execute_batch(
+                "CREATE TABLE id_tags (
+                    id_tag TEXT PRIMARY KEY,
+                    status TEXT NOT NULL,
+                    expiry_date TEXT,
+                    parent_id_tag TEXT
+                );
+                INSERT INTO id_tags VALUES
+                    ('ABC', 'Accepted', NULL, NULL),
+                    ('BLOCKED', 'Blocked', NULL, 'ABC'),
+                    ('OLD', 'Accepted', '2000-01-01T00:00:00Z', NULL),
+                    ('BAD', 'Unknown', NULL, NULL),
+                    ('DUP', 'Accepted', NULL, NULL),
+                    ('d u p', 'Blocked', NULL, NULL);",
+            )
+            .unwrap();
+
+        let provider = SqliteAuthorization::open(&path).unwrap();
+        let authorize = |id_tag: &'static str| {
+            let provider = provider.clone();
+
+            async move { provider.authorize(id_tag).await }
+        };
+
+        assert_eq!(authorize("abc").await.status, IdTagInfoStatus::Accepted);
+        let blocked = authorize("BLOCKED").await;
+        assert_eq!(blocked.status, IdTagInfoStatus::Blocked);
+        assert_eq!(blocked.parent_id_tag, Some(IdTag::try_from("ABC").unwrap()));
+        assert_eq!(authorize("OLD").await.status, IdTagInfoStatus::Expired);
+        // An unknown status, and an unknown ID tag.
+        assert_eq!(authorize("BAD").await.status, IdTagInfoStatus::Invalid);
+        assert_eq!(authorize("XYZ").await.status, IdTagInfoStatus::Invalid);
+        // Two rows for the same normalized ID tag.
+        assert_eq!(authorize("Dup").await.status, IdTagInfoStatus::Invalid);
+
+        // The uncommitted changes are not seen, the committed ones are.
+        back_office.execute_batch("BEGIN").unwrap();
+        back_office
+            .execute(
+                "UPDATE id_tags SET status = ?1 WHERE id_tag = ?2",
+                &["Blocked".into(), "ABC".into()],
+            )
+            .unwrap();
+        assert_eq!(authorize("ABC").await.status, IdTagInfoStatus::Accepted);
+
+        back_office.execute_batch("COMMIT").unwrap();
+        assert_eq!(authorize("ABC").await.status, IdTagInfoStatus::Blocked);
+
+        back_office
+            .execute("DELETE FROM id_tags WHERE id_tag = ?1", &["OLD".into()])
+            .unwrap();
+        assert_eq!(authorize("OLD").await.status, IdTagInfoStatus::Invalid);
+
+        // A database without the table.
+        back_office.execute_batch("DROP TABLE id_tags").unwrap();
+        assert_eq!(authorize("ABC").await.status, IdTagInfoStatus::Invalid);
+        assert!(SqliteAuthorization::open(&path).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
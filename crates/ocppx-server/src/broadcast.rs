@@ -0,0 +1,130 @@
+use crate::CallFailure;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Mutex,
+};
+
+/// The Charge Points a request is sent to, see [`Server::call_many`].
+///
+/// [`Server::call_many`]: crate::Server::call_many
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// All the connected Charge Points.
+    All,
+    /// The Charge Points of a group, e.g. a site, see [`ChargePointGroups`].
+    Group(String),
+    /// These Charge Points.
+    ChargePoints(Vec<String>),
+}
+
+/// The groups of Charge Points, e.g. by site or by model, to send them the
+/// same request with [`Target::Group`].
+///
+/// A Charge Point can be in several groups, and stays in them when it
+/// disconnects.
+#[derive(Debug, Default)]
+pub struct ChargePointGroups {
+    groups: Mutex<BTreeMap<String, BTreeSet<String>>>,
+}
+
+impl ChargePointGroups {
+    /// Add `charge_point_id` to `group`.
+    pub fn add(&self, group: &str, charge_point_id: &str) {
+        self.groups
+            .lock()
+            .unwrap()
+            .entry(group.to_owned())
+            .or_default()
+            .insert(charge_point_id.to_owned());
+    }
+
+    /// Remove `charge_point_id` from `group`. Return whether it was in it.
+    pub fn remove(&self, group: &str, charge_point_id: &str) -> bool {
+        let mut groups = self.groups.lock().unwrap();
+        let Some(members) = groups.get_mut(group) else {
+            return false;
+        };
+        let removed = members.remove(charge_point_id);
+
+        if members.is_empty() {
+            groups.remove(group);
+        }
+
+        removed
+    }
+
+    /// The Charge Points of `group`, connected or not.
+    pub fn members(&self, group: &str) -> Vec<String> {
+        self.groups
+            .lock()
+            .unwrap()
+            .get(group)
+            .map(|members| members.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// The groups `charge_point_id` is in.
+    pub fn groups_of(&self, charge_point_id: &str) -> Vec<String> {
+        self.groups
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, members)| members.contains(charge_point_id))
+            .map(|(group, _)| group.clone())
+            .collect()
+    }
+}
+
+/// The results of a request sent to several Charge Points, by Charge Point
+/// identity, see [`Server::call_many`].
+///
+/// A Charge Point failing does not stop the request from being sent to the
+/// other ones: the results can be a partial failure.
+///
+/// [`Server::call_many`]: crate::Server::call_many
+#[derive(Debug)]
+pub struct BroadcastResults<T> {
+    pub results: BTreeMap<String, Result<T, CallFailure>>,
+}
+
+impl<T> BroadcastResults<T> {
+    /// Whether all the Charge Points have responded.
+    pub fn is_success(&self) -> bool {
+        self.results.values().all(Result::is_ok)
+    }
+
+    /// The responses of the Charge Points which have responded.
+    pub fn succeeded(&self) -> impl Iterator<Item = (&str, &T)> {
+        self.results
+            .iter()
+            .filter_map(|(id, result)| Some((id.as_str(), result.as_ref().ok()?)))
+    }
+
+    /// The failures of the other Charge Points.
+    pub fn failed(&self) -> impl Iterator<Item = (&str, &CallFailure)> {
+        self.results
+            .iter()
+            .filter_map(|(id, result)| Some((id.as_str(), result.as_ref().err()?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_groups() {
+        let groups = ChargePointGroups::default();
+        groups.add("site:paris", "CP001");
+        groups.add("site:paris", "CP002");
+        groups.add("model:x", "CP001");
+
+        assert_eq!(groups.members("site:paris"), ["CP001", "CP002"]);
+        assert_eq!(groups.groups_of("CP001"), ["model:x", "site:paris"]);
+
+        assert!(groups.remove("model:x", "CP001"));
+        assert!(!groups.remove("model:x", "CP001"));
+        assert!(groups.members("model:x").is_empty());
+        assert_eq!(groups.groups_of("CP001"), ["site:paris"]);
+    }
+}
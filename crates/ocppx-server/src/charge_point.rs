@@ -0,0 +1,307 @@
+use crate::{AuthProvider, CsmsHandler, OcppVersion, Server};
+use ocppx_rpc::{CallError, ErrorCode};
+use ocppx_types::OcppRequest;
+use serde_json::Value;
+use std::time::Duration;
+
+/// A Charge Point connected to a [`Server`], to send it typed requests,
+/// see [`Server::charge_point`].
+///
+/// The `Call`s sent through the handles of a Charge Point are sent one at
+/// a time, in order, whatever [`ServerConfig::max_outstanding_calls`]:
+/// concurrent tasks can share a Charge Point without interleaving their
+/// requests.
+///
+/// [`ServerConfig::max_outstanding_calls`]: crate::ServerConfig::max_outstanding_calls
+pub struct ChargePointHandle<H, A = ()> {
+    server: Server<H, A>,
+    id: String,
+}
+
+impl<H, A> Clone for ChargePointHandle<H, A> {
+    fn clone(&self) -> Self {
+        Self {
+            server: self.server.clone(),
+            id: self.id.clone(),
+        }
+    }
+}
+
+impl<H, A> ChargePointHandle<H, A>
+where
+    H: CsmsHandler,
+    A: AuthProvider,
+{
+    pub(crate) fn new(server: Server<H, A>, id: String) -> Self {
+        Self { server, id }
+    }
+
+    /// The identity of the Charge Point.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The OCPP version negotiated by the Charge Point, if it is connected.
+    pub fn version(&self) -> Option<OcppVersion> {
+        self.server.sessions().version(&self.id)
+    }
+
+    /// Whether the Charge Point is connected, now.
+    pub fn is_connected(&self) -> bool {
+        self.server
+            .sessions()
+            .with_session(&self.id, |_| ())
+            .is_some()
+    }
+
+    /// Send `request`, and wait for its typed response.
+    pub async fn call<R>(&self, request: R) -> Result<R::Response, CallFailure>
+    where
+        R: OcppRequest,
+    {
+        let calls = self
+            .server
+            .sessions()
+            .with_session(&self.id, |session| session.calls.clone())
+            .ok_or_else(|| CallFailure::NotConnected(self.id.clone()))?;
+        let _turn = calls.lock().await;
+
+        let response = self
+            .server
+            .call::<_, Value>(&self.id, R::ACTION, &request)
+            .await
+            .map_err(|error| CallFailure::new(R::ACTION, error))?;
+
+        serde_json::from_value(response).map_err(|source| CallFailure::InvalidResponse {
+            action: R::ACTION.to_owned(),
+            source,
+        })
+    }
+}
+
+/// Why a request sent with [`ChargePointHandle::call`] has failed.
+#[derive(thiserror::Error, Debug)]
+pub enum CallFailure {
+    #[error("the charge point `{0}` is not connected")]
+    NotConnected(String),
+
+    #[error("the connection has closed before the response")]
+    ConnectionClosed,
+
+    #[error("the server is shutting down")]
+    Shutdown,
+
+    #[error("the charge point did not respond to `{action}` after {timeout:?}")]
+    Timeout { action: String, timeout: Duration },
+
+    /// The Charge Point responded with `NotImplemented` or
+    /// `NotSupported`.
+    #[error("the charge point does not support `{action}`: {description}")]
+    NotSupported {
+        action: String,
+        error_code: ErrorCode,
+        description: String,
+    },
+
+    /// The Charge Point rejected the payload of the request, e.g. with a
+    /// `FormationViolation` or a `PropertyConstraintViolation`.
+    #[error("the charge point rejected `{action}` with `{}`: {description}", .error_code.as_str())]
+    InvalidRequest {
+        action: String,
+        error_code: ErrorCode,
+        description: String,
+        details: Value,
+    },
+
+    /// The Charge Point responded with another `CallError`, e.g. an
+    /// `InternalError`.
+    #[error("the charge point failed to handle `{action}` with `{}`: {description}", .error_code.as_str())]
+    Failed {
+        action: String,
+        error_code: ErrorCode,
+        description: String,
+        details: Value,
+    },
+
+    /// The response does not match the one of the request.
+    #[error("invalid response to `{action}`")]
+    InvalidResponse {
+        action: String,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    /// Any other error, e.g. the request cannot be serialized.
+    #[error(transparent)]
+    Server(crate::Error),
+}
+
+impl CallFailure {
+    fn new(action: &str, error: crate::Error) -> Self {
+        use crate::Error;
+
+        match error {
+            Error::ChargePointNotConnected(id) => Self::NotConnected(id),
+            Error::ConnectionClosed => Self::ConnectionClosed,
+            Error::Shutdown => Self::Shutdown,
+            Error::Timeout { action, timeout } => Self::Timeout { action, timeout },
+            Error::CallError(call_error) => Self::from_call_error(action, call_error),
+            error => Self::Server(error),
+        }
+    }
+
+    fn from_call_error(action: &str, call_error: CallError) -> Self {
+        let CallError {
+            error_code,
+            error_description: description,
+            error_details: details,
+            ..
+        } = call_error;
+        let action = action.to_owned();
+
+        match error_code {
+            ErrorCode::NotImplemented | ErrorCode::NotSupported => Self::NotSupported {
+                action,
+                error_code,
+                description,
+            },
+            ErrorCode::ProtocolError
+            | ErrorCode::FormationViolation
+            | ErrorCode::FormatViolation
+            | ErrorCode::PropertyConstraintViolation
+            | ErrorCode::OccurenceConstraintViolation
+            | ErrorCode::OccurrenceConstraintViolation
+            | ErrorCode::TypeConstraintViolation => Self::InvalidRequest {
+                action,
+                error_code,
+                description,
+                details,
+            },
+            error_code => Self::Failed {
+                action,
+                error_code,
+                description,
+                details,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ServerConfig;
+    use ocppx_client::ChargePointClient;
+    use ocppx_rpc::{Call, CallResult};
+    use ocppx_types::v1_6::{ClearCacheRequest, ClearCacheStatus, ResetRequest, ResetType};
+    use tokio::net::TcpListener;
+
+    struct Handler;
+
+    impl CsmsHandler for Handler {
+        async fn handle_call(
+            &self,
+            _charge_point_id: &str,
+            call: Call,
+        ) -> std::result::Result<CallResult, CallError> {
+            Err(CallError::new(
+                call.unique_id,
+                ErrorCode::NotImplemented,
+                "",
+                None,
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_charge_point_handle() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::with_config(
+            Handler,
+            ServerConfig {
+                max_outstanding_calls: 2,
+                ..ServerConfig::default()
+            },
+        );
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        assert!(matches!(
+            server
+                .charge_point("CP001")
+                .call(ClearCacheRequest::builder().build())
+                .await,
+            Err(CallFailure::NotConnected(id)) if id == "CP001"
+        ));
+
+        let client = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001")
+            .await
+            .unwrap();
+        let charge_point = server.charge_point("CP001");
+        assert!(charge_point.is_connected());
+
+        let clear_cache = tokio::spawn({
+            let charge_point = charge_point.clone();
+
+            async move {
+                charge_point
+                    .call(ClearCacheRequest::builder().build())
+                    .await
+            }
+        });
+        let call = client.next_call().await.unwrap();
+        assert_eq!(call.action, "ClearCache");
+
+        let reset = tokio::spawn({
+            let charge_point = charge_point.clone();
+
+            async move {
+                charge_point
+                    .call(ResetRequest::builder().r#type(ResetType::Soft).build())
+                    .await
+            }
+        });
+
+        // The `Reset` waits for the response to the `ClearCache`, although
+        // the server allows 2 outstanding `Call`s.
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), client.next_call())
+                .await
+                .is_err()
+        );
+
+        client
+            .respond(
+                CallResult::new(call.unique_id, &serde_json::json!({"status": "Accepted"}))
+                    .unwrap(),
+            )
+            .unwrap();
+        assert_eq!(
+            clear_cache.await.unwrap().unwrap().status,
+            ClearCacheStatus::Accepted
+        );
+
+        let call = client.next_call().await.unwrap();
+        assert_eq!(call.action, "Reset");
+        client
+            .respond(CallError::new(
+                call.unique_id,
+                ErrorCode::PropertyConstraintViolation,
+                "unknown type",
+                None,
+            ))
+            .unwrap();
+        assert!(matches!(
+            reset.await.unwrap(),
+            Err(CallFailure::InvalidRequest {
+                error_code: ErrorCode::PropertyConstraintViolation,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,110 @@
+use crate::OcppVersion;
+use ocppx_rpc::{MessageIds, DEFAULT_CALL_TIMEOUT, DEFAULT_MAX_OUTSTANDING_CALLS};
+use std::time::Duration;
+
+/// Configuration of a [`Server`][crate::Server].
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Time to wait for the response to a `Call` before giving up.
+    pub call_timeout: Duration,
+    /// Number of `Call`s that can wait for a response at once, per Charge
+    /// Point. OCPP-J allows a single one: a `Call` is not sent before the
+    /// previous one is answered, or has timed out. More pipelines the
+    /// `Call`s, for the tolerant Charge Points.
+    pub max_outstanding_calls: usize,
+    /// Generator of the unique IDs of the `Call`s sent to the Charge
+    /// Points, counting from 0 by default. A generated ID still waiting for
+    /// a response from the same Charge Point is skipped.
+    pub message_ids: MessageIds,
+    /// Number of frames waiting to be sent, per Charge Point. [`Server::call`]
+    /// and the responses of the handler wait when the queue is full, e.g.
+    /// because the Charge Point reads slowly.
+    ///
+    /// [`Server::call`]: crate::Server::call
+    pub outgoing_queue_capacity: usize,
+    /// Number of `Call`s from a Charge Point handled at once. When they are
+    /// all in progress, the next ones are answered with a `GenericError`
+    /// `CallError`; the responses, e.g. to [`Server::call`], are still
+    /// received.
+    ///
+    /// [`Server::call`]: crate::Server::call
+    pub max_concurrent_calls: usize,
+    /// Number of `Call`s handled at once by the handler, for all the Charge
+    /// Points together. The other ones wait for their turn.
+    pub max_concurrent_handlers: usize,
+    /// The OCPP versions accepted from the Charge Points: each one gets the
+    /// highest of the versions it offers, see
+    /// [`CsmsHandler::connected_with_version`]. Only OCPP 1.6 by default.
+    ///
+    /// [`CsmsHandler::connected_with_version`]: crate::CsmsHandler::connected_with_version
+    pub versions: Vec<OcppVersion>,
+    /// Size of the largest message accepted from a Charge Point, in bytes.
+    pub max_message_size: usize,
+    /// Time for a Charge Point to open its connection, from the TCP accept
+    /// to the end of its WebSocket handshake request, the TLS handshake
+    /// included. The connection is dropped past it.
+    pub handshake_timeout: Duration,
+    /// TLS configuration. Connections are accepted over plain TCP when
+    /// `None`.
+    #[cfg(feature = "tls")]
+    pub tls: Option<crate::TlsConfig>,
+    /// Where to count the OCPP traffic.
+    #[cfg(feature = "metrics")]
+    pub metrics: Option<ocppx_rpc::Metrics>,
+    /// Where to capture the frames of all the connections.
+    pub recorder: Option<ocppx_rpc::Recorder>,
+    /// Where to record the security-relevant events: the failed
+    /// authentications, and the certificates, remote starts and
+    /// configuration changes sent to the Charge Points.
+    #[cfg(feature = "audit")]
+    pub audit_log: Option<crate::AuditLog>,
+    /// Inspect and rewrite the messages exchanged with the Charge Points,
+    /// or their captures.
+    pub interceptors: crate::Interceptors,
+    /// Limit the rate of the `Call`s of each Charge Point. Unlimited when
+    /// `None`.
+    pub rate_limit: Option<crate::RateLimit>,
+    /// Number of responses remembered per connection, the most recently
+    /// used ones: a `Call` retransmitted by a Charge Point with the same
+    /// unique ID, action and payload gets the same response again, without
+    /// reaching the handler. Disabled when 0.
+    pub replayed_responses: usize,
+    /// Ping the Charge Points at the WebSocket level, and close the
+    /// connections of those that stop answering, see
+    /// [`Server::connection_events`].
+    ///
+    /// [`Server::connection_events`]: crate::Server::connection_events
+    pub keep_alive: Option<ocppx_rpc::KeepAlive>,
+    /// Accept the `permessage-deflate` WebSocket extension, when the
+    /// Charge Points offer it. The messages are sent as they are when
+    /// `None`.
+    pub compression: Option<ocppx_rpc::Compression>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            call_timeout: DEFAULT_CALL_TIMEOUT,
+            max_outstanding_calls: DEFAULT_MAX_OUTSTANDING_CALLS,
+            message_ids: MessageIds::counter(0),
+            outgoing_queue_capacity: 32,
+            max_concurrent_calls: 4,
+            max_concurrent_handlers: 1024,
+            versions: vec![OcppVersion::V1_6],
+            max_message_size: 4 << 20,
+            handshake_timeout: Duration::from_secs(10),
+            #[cfg(feature = "tls")]
+            tls: None,
+            #[cfg(feature = "metrics")]
+            metrics: None,
+            recorder: None,
+            #[cfg(feature = "audit")]
+            audit_log: None,
+            interceptors: crate::Interceptors::default(),
+            rate_limit: None,
+            replayed_responses: 64,
+            keep_alive: None,
+            compression: None,
+        }
+    }
+}
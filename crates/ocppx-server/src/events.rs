@@ -0,0 +1,246 @@
+use crate::OcppVersion;
+use ocppx_rpc::{CallError, CallResult, ErrorCode};
+use ocppx_types::{
+    v1_6::{
+        BootNotificationResponse, BootNotificationStatus, IdTagInfoStatus, StartTransactionRequest,
+        StartTransactionResponse,
+    },
+    IdTag,
+};
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+/// Capacity of the channel of the [`Event`]s: a subscriber lagging behind
+/// by more events misses the oldest ones.
+const EVENTS_CAPACITY: usize = 1024;
+
+/// Something that happened to a Charge Point, see [`EventBus`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// A Charge Point has connected.
+    Connected {
+        charge_point_id: String,
+        session_id: u64,
+        version: OcppVersion,
+    },
+    /// The session of a Charge Point has ended.
+    ///
+    /// A [`DisconnectReason::Superseded`] session ends after the
+    /// [`Event::Connected`] of the session taking it over.
+    Disconnected {
+        charge_point_id: String,
+        session_id: u64,
+        reason: DisconnectReason,
+    },
+    /// The handler has accepted the `BootNotification` of a Charge Point.
+    BootAccepted {
+        charge_point_id: String,
+        /// The heartbeat interval, in seconds.
+        interval: i32,
+    },
+    /// The handler has responded to a `StartTransaction`.
+    TransactionStarted {
+        charge_point_id: String,
+        connector_id: i32,
+        transaction_id: i32,
+        id_tag: IdTag,
+        id_tag_status: IdTagInfoStatus,
+    },
+    /// A Charge Point has responded to a `Call` of the Central System with
+    /// a `CallError`.
+    CallErrorReceived {
+        charge_point_id: String,
+        unique_id: String,
+        error_code: ErrorCode,
+        error_description: String,
+    },
+    /// The profile of a Charge Point has been applied after its boot, see
+    /// [`ChargePointProfiles`][crate::ChargePointProfiles].
+    ProfileApplied {
+        charge_point_id: String,
+        report: crate::ProfileReport,
+    },
+}
+
+/// Why the session of a Charge Point has ended, see
+/// [`Event::Disconnected`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisconnectReason {
+    /// The Charge Point has closed the connection.
+    Closed,
+    /// The connection has failed, e.g. it has been reset.
+    Error,
+    /// The Charge Point has stopped answering the pings, see
+    /// [`ServerConfig::keep_alive`].
+    ///
+    /// [`ServerConfig::keep_alive`]: crate::ServerConfig::keep_alive
+    Stale,
+    /// The Charge Point has reconnected, see [`SessionRegistry`].
+    ///
+    /// [`SessionRegistry`]: crate::SessionRegistry
+    Superseded,
+    /// The Charge Point has exceeded its rate limit, see
+    /// [`ServerConfig::rate_limit`].
+    ///
+    /// [`ServerConfig::rate_limit`]: crate::ServerConfig::rate_limit
+    RateLimited,
+    /// The server is shutting down.
+    Shutdown,
+}
+
+/// The [`Event`]s of the Charge Points of a server, for any number of
+/// subscribers, e.g. the metrics, a UI or a store, see [`Server::events`].
+///
+/// Nothing is buffered for the subscribers to come: they receive the events
+/// from the time they subscribe.
+///
+/// [`Server::events`]: crate::Server::events
+#[derive(Debug)]
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::Sender::new(EVENTS_CAPACITY),
+        }
+    }
+}
+
+impl EventBus {
+    /// Receive the events, from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+
+    /// Number of subscribers.
+    pub fn subscribers(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    pub(crate) fn publish(&self, event: Event) {
+        // Nobody listening is fine.
+        let _ = self.sender.send(event);
+    }
+
+    /// Publish the event of the response of the handler to the `Call`
+    /// `action` of `charge_point_id`, if any. `request` is the payload of
+    /// the `Call`, kept only for the actions it is needed for, see
+    /// [`keeps_request`].
+    pub(crate) fn publish_response(
+        &self,
+        charge_point_id: &str,
+        action: &str,
+        request: Option<Value>,
+        response: &CallResult,
+    ) {
+        let event = match action {
+            "BootNotification" => {
+                let Ok(response) =
+                    serde_json::from_value::<BootNotificationResponse>(response.payload.clone())
+                else {
+                    return;
+                };
+
+                if response.status != BootNotificationStatus::Accepted {
+                    return;
+                }
+
+                Event::BootAccepted {
+                    charge_point_id: charge_point_id.to_owned(),
+                    interval: response.interval,
+                }
+            }
+
+            "StartTransaction" => {
+                let (Some(Ok(request)), Ok(response)) = (
+                    request.map(serde_json::from_value::<StartTransactionRequest>),
+                    serde_json::from_value::<StartTransactionResponse>(response.payload.clone()),
+                ) else {
+                    return;
+                };
+
+                Event::TransactionStarted {
+                    charge_point_id: charge_point_id.to_owned(),
+                    connector_id: request.connector_id,
+                    transaction_id: response.transaction_id,
+                    id_tag: request.id_tag,
+                    id_tag_status: response.id_tag_info.status,
+                }
+            }
+
+            _ => return,
+        };
+
+        self.publish(event);
+    }
+
+    /// Publish the `CallError` responded by `charge_point_id`.
+    pub(crate) fn publish_call_error(&self, charge_point_id: &str, call_error: &CallError) {
+        self.publish(Event::CallErrorReceived {
+            charge_point_id: charge_point_id.to_owned(),
+            unique_id: call_error.unique_id.clone(),
+            error_code: call_error.error_code.clone(),
+            error_description: call_error.error_description.clone(),
+        });
+    }
+}
+
+/// Whether the payload of the `Call` `action` is needed by
+/// [`EventBus::publish_response`], or to apply the profile of a Charge
+/// Point.
+pub(crate) fn keeps_request(action: &str) -> bool {
+    matches!(action, "BootNotification" | "StartTransaction")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_publish_response() {
+        let events = EventBus::default();
+        let mut subscriber = events.subscribe();
+
+        events.publish_response(
+            "CP001",
+            "BootNotification",
+            None,
+            &CallResult::new(
+                "1",
+                &json!({"currentTime": "2024-01-01T00:00:00Z", "interval": 300, "status": "Pending"}),
+            )
+            .unwrap(),
+        );
+        events.publish_response(
+            "CP001",
+            "StartTransaction",
+            Some(json!({
+                "connectorId": 1,
+                "idTag": "TAG001",
+                "meterStart": 0,
+                "timestamp": "2024-01-01T00:00:00Z",
+            })),
+            &CallResult::new(
+                "2",
+                &json!({"idTagInfo": {"status": "Accepted"}, "transactionId": 42}),
+            )
+            .unwrap(),
+        );
+
+        // The pending boot is not an event.
+        assert_eq!(
+            subscriber.try_recv().unwrap(),
+            Event::TransactionStarted {
+                charge_point_id: "CP001".to_owned(),
+                connector_id: 1,
+                transaction_id: 42,
+                id_tag: IdTag::try_from("TAG001").unwrap(),
+                id_tag_status: IdTagInfoStatus::Accepted,
+            }
+        );
+        assert!(subscriber.try_recv().is_err());
+    }
+}
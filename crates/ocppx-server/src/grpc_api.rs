@@ -0,0 +1,569 @@
+use crate::{
+    http::{self, error, respond},
+    protobuf::{self, ProtobufError, Reader, Transcoder, LENGTH_DELIMITED, VARINT},
+    AuthProvider, CsmsHandler, Error, Result, Server, SessionEvent,
+};
+use ocppx_rpc::ConnectionEvent;
+use ocppx_types::v1_6::{PROTO_ACTIONS, PROTO_MESSAGES};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use serde_json::Value;
+use std::io;
+use tokio::{
+    io::{AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::broadcast::error::RecvError,
+};
+
+/// Size of the largest request accepted by the [`GrpcApi`], in bytes.
+const MAX_REQUEST_SIZE: usize = 64 << 10;
+
+/// The path of the methods of the `CentralSystem` service.
+const SERVICE: &str = "/ocppx.central_system.CentralSystem/";
+
+/// The status codes of gRPC.
+mod code {
+    pub(super) const OK: u8 = 0;
+    pub(super) const INVALID_ARGUMENT: u8 = 3;
+    pub(super) const DEADLINE_EXCEEDED: u8 = 4;
+    pub(super) const NOT_FOUND: u8 = 5;
+    pub(super) const UNIMPLEMENTED: u8 = 12;
+    pub(super) const INTERNAL: u8 = 13;
+    pub(super) const UNAUTHENTICATED: u8 = 16;
+}
+
+/// The `CentralSystem` service of `proto/central_system.proto`, for the
+/// backends driving a [`Server`] as a protocol gateway, in any language:
+///
+/// - `Call` sends an OCPP 1.6 `Request` to a Charge Point, and responds
+///   with its `Response`, or its `CallError`,
+/// - `Subscribe` streams the [`SessionEvent`]s and the
+///   [`ConnectionEvent`]s, of all the Charge Points or of the given ones.
+///
+/// The service is served with the [gRPC-Web][grpc-web] protocol, over
+/// HTTP/1.1, in the binary format (`application/grpc-web+proto`): the
+/// browsers call it directly, and the gRPC clients through a gRPC-Web
+/// proxy, e.g. the `grpc_web` filter of Envoy. A Charge Point that is not
+/// connected is `NOT_FOUND`, and no response is `DEADLINE_EXCEEDED`.
+///
+/// The API is not authenticated unless a bearer token is required with
+/// [`Self::bearer_token`], in the `authorization` metadata.
+///
+/// [grpc-web]: https://github.com/grpc/grpc/blob/master/doc/PROTOCOL-WEB.md
+pub struct GrpcApi<H, A = ()> {
+    server: Server<H, A>,
+    bearer_token: Option<String>,
+}
+
+impl<H, A> GrpcApi<H, A>
+where
+    H: CsmsHandler,
+    A: AuthProvider,
+{
+    pub fn new(server: Server<H, A>) -> Self {
+        Self {
+            server,
+            bearer_token: None,
+        }
+    }
+
+    /// Require the requests to carry `authorization: Bearer <token>`.
+    pub fn bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.bearer_token = Some(token.into());
+        self
+    }
+
+    /// Listen on `address` and serve the API forever.
+    pub async fn listen<T>(self, address: T) -> Result<()>
+    where
+        T: ToSocketAddrs,
+    {
+        self.serve(TcpListener::bind(address).await?).await
+    }
+
+    /// Serve the API on the connections accepted by `listener`, forever.
+    /// Each request is served on its own connection.
+    pub async fn serve(self, listener: TcpListener) -> Result<()> {
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let server = self.server.clone();
+            let bearer_token = self.bearer_token.clone();
+
+            tokio::spawn(async move {
+                if let Err(error) = handle(stream, &server, bearer_token.as_deref()).await {
+                    log::debug!(error:% = error; "gRPC API connection failed");
+                }
+            });
+        }
+    }
+}
+
+async fn handle<H, A>(
+    mut stream: TcpStream,
+    server: &Server<H, A>,
+    bearer_token: Option<&str>,
+) -> io::Result<()>
+where
+    H: CsmsHandler,
+    A: AuthProvider,
+{
+    let request = match http::read_request(&mut stream, MAX_REQUEST_SIZE).await {
+        Ok(request) => request,
+        Err(error) => return http::reject(&mut stream, error).await,
+    };
+
+    let Some(method) = request.path.strip_prefix(SERVICE) else {
+        return respond(&mut stream, 404, &error("not found")).await;
+    };
+
+    if request.method != "POST" {
+        return respond(&mut stream, 405, &error("method not allowed")).await;
+    }
+
+    if !matches!(
+        request.header("content-type"),
+        Some("application/grpc-web" | "application/grpc-web+proto")
+    ) {
+        return respond(&mut stream, 415, &error("unsupported media type")).await;
+    }
+
+    stream
+        .write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: application/grpc-web+proto\r\nConnection: close\r\n\r\n",
+        )
+        .await?;
+
+    let status = match bearer_token {
+        Some(token) if !request.has_bearer_token(token) => {
+            Err((code::UNAUTHENTICATED, "unauthenticated".to_owned()))
+        }
+        _ => match (method, message(&request.body)) {
+            (_, Err(status)) => Err(status),
+            ("Call", Ok(message)) => match call(server, message).await {
+                Ok(response) => write_message(&mut stream, &response).await.map(Ok)?,
+                Err(status) => Err(status),
+            },
+            ("Subscribe", Ok(message)) => subscribe(&mut stream, server, message).await?,
+            (method, Ok(_)) => Err((code::UNIMPLEMENTED, format!("unknown method `{method}`"))),
+        },
+    };
+
+    write_trailers(&mut stream, status).await?;
+    stream.shutdown().await
+}
+
+/// The status of an RPC that has failed, with its message.
+type Status = (u8, String);
+
+/// The single message of a unary request.
+fn message(body: &[u8]) -> std::result::Result<&[u8], Status> {
+    let invalid = || (code::INVALID_ARGUMENT, "malformed request".to_owned());
+    let (flags, rest) = body.split_first().ok_or_else(invalid)?;
+
+    if flags & 1 == 1 {
+        return Err((
+            code::UNIMPLEMENTED,
+            "compressed messages are not supported".to_owned(),
+        ));
+    }
+
+    let (length, message) = rest.split_first_chunk::<4>().ok_or_else(invalid)?;
+
+    if *flags != 0 || u32::from_be_bytes(*length) as usize != message.len() {
+        return Err(invalid());
+    }
+
+    Ok(message)
+}
+
+/// Send the `CallRequest` `message`, and return its `CallResponse`.
+async fn call<H, A>(server: &Server<H, A>, message: &[u8]) -> std::result::Result<Vec<u8>, Status>
+where
+    H: CsmsHandler,
+    A: AuthProvider,
+{
+    let transcoder = Transcoder::new(PROTO_MESSAGES, PROTO_ACTIONS);
+    let invalid = |error: ProtobufError| (code::INVALID_ARGUMENT, error.to_string());
+
+    let mut reader = Reader::new(message);
+    let (mut charge_point_id, mut request) = ("", None);
+
+    while !reader.is_empty() {
+        match reader.key().map_err(invalid)? {
+            (1, LENGTH_DELIMITED) => charge_point_id = reader.string().map_err(invalid)?,
+            (2, LENGTH_DELIMITED) => request = Some(reader.bytes().map_err(invalid)?),
+            (_, wire_type) => reader.skip(wire_type).map_err(invalid)?,
+        }
+    }
+
+    let (action, payload) = transcoder
+        .decode_payload("Request", request.unwrap_or_default())
+        .map_err(invalid)?;
+
+    let mut response = Vec::new();
+
+    match server
+        .call::<Value, Value>(charge_point_id, action, &payload)
+        .await
+    {
+        Ok(payload) => protobuf::write_bytes(
+            1,
+            &transcoder
+                .encode_payload("Response", action, &payload)
+                .map_err(|error| (code::INTERNAL, format!("invalid response: {error}")))?,
+            &mut response,
+        ),
+        Err(Error::CallError(call_error)) => {
+            let mut message = Vec::new();
+            protobuf::write_bytes(1, call_error.error_code.as_str().as_bytes(), &mut message);
+            protobuf::write_bytes(2, call_error.error_description.as_bytes(), &mut message);
+            protobuf::write_bytes(
+                3,
+                call_error.error_details.to_string().as_bytes(),
+                &mut message,
+            );
+
+            protobuf::write_bytes(2, &message, &mut response);
+        }
+        Err(error @ Error::ChargePointNotConnected(_)) => {
+            return Err((code::NOT_FOUND, error.to_string()))
+        }
+        Err(error @ Error::Timeout { .. }) => {
+            return Err((code::DEADLINE_EXCEEDED, error.to_string()))
+        }
+        Err(error) => return Err((code::INTERNAL, error.to_string())),
+    }
+
+    Ok(response)
+}
+
+/// Stream the `Event`s of the `SubscribeRequest` `message` until the client
+/// goes away, or sends anything.
+async fn subscribe<H, A>(
+    stream: &mut TcpStream,
+    server: &Server<H, A>,
+    message: &[u8],
+) -> io::Result<std::result::Result<(), Status>>
+where
+    H: CsmsHandler,
+    A: AuthProvider,
+{
+    let mut reader = Reader::new(message);
+    let mut charge_point_ids = Vec::new();
+
+    while !reader.is_empty() {
+        let field = match reader.key() {
+            Ok((1, LENGTH_DELIMITED)) => reader
+                .string()
+                .map(|id| charge_point_ids.push(id.to_owned())),
+            Ok((_, wire_type)) => reader.skip(wire_type),
+            Err(error) => Err(error),
+        };
+
+        if let Err(error) = field {
+            return Ok(Err((code::INVALID_ARGUMENT, error.to_string())));
+        }
+    }
+
+    let mut session_events = server.sessions().subscribe();
+    let mut connection_events = server.connection_events();
+    let (mut reader, mut writer) = stream.split();
+    let mut received = [0; 1];
+
+    loop {
+        let event = tokio::select! {
+            _ = reader.read(&mut received) => return Ok(Ok(())),
+            event = session_events.recv() => event.map(|event| {
+                let (charge_point_id, number, session_id, by) = match event {
+                    SessionEvent::Opened { charge_point_id, session_id } => (charge_point_id, 2, session_id, None),
+                    SessionEvent::Superseded { charge_point_id, session_id, by } => (charge_point_id, 3, session_id, by),
+                    SessionEvent::Closed { charge_point_id, session_id } => (charge_point_id, 4, session_id, None),
+                };
+                let mut message = Vec::new();
+                protobuf::write_key(1, VARINT, &mut message);
+                protobuf::write_varint(session_id, &mut message);
+
+                if let Some(by) = by {
+                    protobuf::write_key(2, VARINT, &mut message);
+                    protobuf::write_varint(by, &mut message);
+                }
+
+                (charge_point_id, number, message)
+            }),
+            event = connection_events.recv() => event.map(|event| match event {
+                ConnectionEvent::Stale { charge_point_id, silence } => {
+                    let mut message = Vec::new();
+                    protobuf::write_double(1, silence.as_secs_f64(), &mut message);
+
+                    (charge_point_id, 5, message)
+                }
+            }),
+        };
+
+        let (charge_point_id, number, message) = match event {
+            Ok(event) => event,
+            Err(RecvError::Lagged(missed)) => {
+                log::warn!(missed = missed; "gRPC API events missed");
+
+                continue;
+            }
+            Err(RecvError::Closed) => return Ok(Ok(())),
+        };
+
+        if !charge_point_ids.is_empty() && !charge_point_ids.contains(&charge_point_id) {
+            continue;
+        }
+
+        let mut event = Vec::new();
+        protobuf::write_bytes(1, charge_point_id.as_bytes(), &mut event);
+        protobuf::write_bytes(number, &message, &mut event);
+
+        write_message(&mut writer, &event).await?;
+    }
+}
+
+/// Write a frame of `message`.
+async fn write_message<S>(stream: &mut S, message: &[u8]) -> io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    let length = u32::try_from(message.len()).map_err(|_| io::ErrorKind::InvalidData)?;
+    let mut frame = Vec::with_capacity(5 + message.len());
+    frame.push(0);
+    frame.extend_from_slice(&length.to_be_bytes());
+    frame.extend_from_slice(message);
+
+    stream.write_all(&frame).await
+}
+
+/// Write the frame of the trailers, with the status of the RPC.
+async fn write_trailers<S>(
+    stream: &mut S,
+    status: std::result::Result<(), Status>,
+) -> io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    let trailers = match status {
+        Ok(()) => format!("grpc-status:{}\r\n", code::OK),
+        Err((code, message)) => format!(
+            "grpc-status:{code}\r\ngrpc-message:{}\r\n",
+            utf8_percent_encode(&message, GRPC_MESSAGE)
+        ),
+    };
+    let mut frame = vec![0x80];
+    frame.extend_from_slice(&(trailers.len() as u32).to_be_bytes());
+    frame.extend_from_slice(trailers.as_bytes());
+
+    stream.write_all(&frame).await
+}
+
+/// The bytes percent-encoded in `grpc-message`: the ones outside of the
+/// printable ASCII, and `%`.
+const GRPC_MESSAGE: &AsciiSet = &CONTROLS.add(b'%');
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ocppx_client::ChargePointClient;
+    use ocppx_rpc::{Call, CallError, CallResult, ErrorCode};
+    use serde_json::json;
+
+    struct Handler;
+
+    impl CsmsHandler for Handler {
+        async fn handle_call(
+            &self,
+            _charge_point_id: &str,
+            call: Call,
+        ) -> std::result::Result<CallResult, CallError> {
+            Err(CallError::new(
+                call.unique_id,
+                ErrorCode::NotImplemented,
+                "",
+                None,
+            ))
+        }
+    }
+
+    /// Call `method` with `message`, and return the frames of the response.
+    async fn request(port: u16, method: &str, token: &str, message: &[u8]) -> (String, Vec<u8>) {
+        let mut body = Vec::new();
+        write_message(&mut body, message).await.unwrap();
+
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        stream
+            .write_all(
+                format!(
+                    "POST {SERVICE}{method} HTTP/1.1\r\nAuthorization: Bearer {token}\r\nContent-Type: application/grpc-web+proto\r\nContent-Length: {}\r\n\r\n",
+                    body.len()
+                )
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+        stream.write_all(&body).await.unwrap();
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+
+        let end = response
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .unwrap()
+            + 4;
+        let body = response.split_off(end);
+
+        (String::from_utf8(response).unwrap(), body)
+    }
+
+    /// The frame of `message`, followed by the frame of the trailers with
+    /// `trailers`.
+    fn frames(message: &[u8], trailers: &str) -> Vec<u8> {
+        let mut frames = Vec::new();
+
+        if !message.is_empty() {
+            frames.push(0);
+            frames.extend_from_slice(&(message.len() as u32).to_be_bytes());
+            frames.extend_from_slice(message);
+        }
+
+        frames.push(0x80);
+        frames.extend_from_slice(&(trailers.len() as u32).to_be_bytes());
+        frames.extend_from_slice(trailers.as_bytes());
+
+        frames
+    }
+
+    #[tokio::test]
+    async fn test_grpc_api() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::new(Handler);
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let api_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = api_listener.local_addr().unwrap().port();
+        tokio::spawn(
+            GrpcApi::new(server.clone())
+                .bearer_token("secret")
+                .serve(api_listener),
+        );
+
+        let client = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001")
+            .await
+            .unwrap();
+
+        let transcoder = Transcoder::new(PROTO_MESSAGES, PROTO_ACTIONS);
+        let call_request = |charge_point_id: &str| {
+            let mut message = Vec::new();
+            protobuf::write_bytes(1, charge_point_id.as_bytes(), &mut message);
+            protobuf::write_bytes(
+                2,
+                &transcoder
+                    .encode_payload("Request", "Reset", &json!({"type": "Soft"}))
+                    .unwrap(),
+                &mut message,
+            );
+
+            message
+        };
+
+        let (head, body) = request(port, "Call", "oops", &call_request("CP001")).await;
+        assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert_eq!(
+            body,
+            frames(b"", "grpc-status:16\r\ngrpc-message:unauthenticated\r\n")
+        );
+
+        let reset = tokio::spawn({
+            let message = call_request("CP001");
+
+            async move { request(port, "Call", "secret", &message).await }
+        });
+        let call = client.next_call().await.unwrap();
+        assert_eq!(call.action, "Reset");
+        assert_eq!(call.payload, json!({"type": "Soft"}));
+        client
+            .respond(CallResult::new(call.unique_id, &json!({"status": "Accepted"})).unwrap())
+            .unwrap();
+
+        let mut call_response = Vec::new();
+        protobuf::write_bytes(
+            1,
+            &transcoder
+                .encode_payload("Response", "Reset", &json!({"status": "Accepted"}))
+                .unwrap(),
+            &mut call_response,
+        );
+        let (head, body) = reset.await.unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(head.contains("Content-Type: application/grpc-web+proto\r\n"));
+        assert_eq!(body, frames(&call_response, "grpc-status:0\r\n"));
+
+        let (_, body) = request(port, "Call", "secret", &call_request("CP002")).await;
+        assert_eq!(
+            body,
+            frames(
+                b"",
+                "grpc-status:5\r\ngrpc-message:the charge point `CP002` is not connected\r\n"
+            )
+        );
+
+        let (_, body) = request(port, "Unknown", "secret", b"").await;
+        assert_eq!(
+            body,
+            frames(
+                b"",
+                "grpc-status:12\r\ngrpc-message:unknown method `Unknown`\r\n"
+            )
+        );
+
+        // Follow the events of `CP002` only.
+        let mut subscribe_request = Vec::new();
+        protobuf::write_bytes(1, b"CP002", &mut subscribe_request);
+        let mut body = Vec::new();
+        write_message(&mut body, &subscribe_request).await.unwrap();
+
+        let mut events = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        events
+            .write_all(
+                format!(
+                    "POST {SERVICE}Subscribe HTTP/1.1\r\nAuthorization: Bearer secret\r\nContent-Type: application/grpc-web\r\nContent-Length: {}\r\n\r\n",
+                    body.len()
+                )
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+        events.write_all(&body).await.unwrap();
+
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            head.push(events.read_u8().await.unwrap());
+        }
+        assert!(head.starts_with(b"HTTP/1.1 200 OK\r\n"));
+
+        drop(client);
+        let _client = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP002")
+            .await
+            .unwrap();
+
+        // `charge_point_id = 1`, and `opened = 2` with its `session_id`.
+        let mut frame = [0; 5];
+        events.read_exact(&mut frame).await.unwrap();
+        assert_eq!(frame[0], 0);
+        let mut event = vec![0; u32::from_be_bytes(frame[1..].try_into().unwrap()) as usize];
+        events.read_exact(&mut event).await.unwrap();
+        assert!(event.starts_with(b"\x0a\x05CP002\x12"));
+
+        // Anything sent by the client ends the stream.
+        events.write_all(b"\0").await.unwrap();
+        let mut trailers = Vec::new();
+        events.read_to_end(&mut trailers).await.unwrap();
+        assert_eq!(trailers, frames(b"", "grpc-status:0\r\n"));
+    }
+}
@@ -0,0 +1,114 @@
+use crate::OcppVersion;
+use ocppx_rpc::{Call, CallError, CallResult, CapturedFrame, Direction, Message, Replayer};
+use std::future::Future;
+
+/// Handle the `Call`s sent by the Charge Points.
+///
+/// The handler is shared by all the connections, and `Call`s are handled
+/// concurrently.
+pub trait CsmsHandler: Send + Sync + 'static {
+    /// Handle a `Call` sent by the Charge Point `charge_point_id`. The
+    /// response must carry the same unique ID as the `Call`.
+    fn handle_call(
+        &self,
+        charge_point_id: &str,
+        call: Call,
+    ) -> impl Future<Output = Result<CallResult, CallError>> + Send;
+
+    /// A Charge Point has connected.
+    fn connected(&self, _charge_point_id: &str) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// A Charge Point has connected with the OCPP `version` it has
+    /// negotiated, see [`ServerConfig::versions`]. By default, this is
+    /// [`CsmsHandler::connected`].
+    ///
+    /// [`ServerConfig::versions`]: crate::ServerConfig::versions
+    fn connected_with_version(
+        &self,
+        charge_point_id: &str,
+        _version: OcppVersion,
+    ) -> impl Future<Output = ()> + Send {
+        self.connected(charge_point_id)
+    }
+
+    /// A Charge Point has disconnected.
+    fn disconnected(&self, _charge_point_id: &str) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+}
+
+/// Feed the `Call`s received in a recorded session to `handler`, at the
+/// pace of the [`Replayer`], e.g. to reproduce the misbehavior of a Charge
+/// Point against a new version of the handler.
+///
+/// The frames sent in the session, and the frames that are not `Call`s,
+/// are skipped. Returns the `Call`s with the responses of `handler`.
+pub async fn replay<H>(handler: &H, replayer: &Replayer) -> Vec<(CapturedFrame, Message)>
+where
+    H: CsmsHandler,
+{
+    let mut playback = replayer.play();
+    let mut responses = vec![];
+
+    while let Some(frame) = playback.next().await {
+        if frame.direction != Direction::Incoming {
+            continue;
+        }
+
+        let Some(call) = frame
+            .message()
+            .ok()
+            .and_then(|message| Call::try_from(message).ok())
+        else {
+            continue;
+        };
+
+        let response = match handler.handle_call(&frame.charge_point_id, call).await {
+            Ok(call_result) => call_result.into(),
+            Err(call_error) => call_error.into(),
+        };
+
+        responses.push((frame.clone(), response));
+    }
+
+    responses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Echo;
+
+    impl CsmsHandler for Echo {
+        async fn handle_call(
+            &self,
+            _charge_point_id: &str,
+            call: Call,
+        ) -> Result<CallResult, CallError> {
+            Ok(CallResult::new(call.unique_id, &call.payload).unwrap())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay() {
+        let frame = |direction, frame: &str| CapturedFrame {
+            timestamp: "2013-02-01T20:53:32.486Z".parse().unwrap(),
+            direction,
+            charge_point_id: "CP001".to_owned(),
+            frame: frame.to_owned(),
+        };
+        let replayer = Replayer::new(vec![
+            frame(Direction::Incoming, r#"[2,"1","Heartbeat",{}]"#),
+            frame(Direction::Outgoing, r#"[3,"1",{}]"#),
+            frame(Direction::Incoming, r#"[2,"2","Authorize",{"idTag":"A"}]"#),
+        ]);
+
+        let responses = replay(&Echo, &replayer).await;
+
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[1].1.to_string(), r#"[3,"2",{"idTag":"A"}]"#);
+    }
+}
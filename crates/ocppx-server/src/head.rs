@@ -0,0 +1,128 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
+
+/// The maximum size of the HTTP request head of the WebSocket handshake.
+const MAX_HEAD_SIZE: usize = 8 * 1024;
+
+/// The parts of the WebSocket handshake request needed before the
+/// upgrade.
+pub(crate) struct RequestHead {
+    pub path: String,
+    pub authorization: Option<String>,
+}
+
+/// Read the HTTP request head from `stream`.
+///
+/// The bytes read so far are returned too, so that they can be replayed to
+/// the WebSocket handshake with [`Prefixed`].
+pub(crate) async fn read_request_head<S>(stream: &mut S) -> io::Result<(RequestHead, Vec<u8>)>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut buffer = Vec::with_capacity(1024);
+    let mut chunk = [0; 1024];
+
+    loop {
+        let read = stream.read(&mut chunk).await?;
+
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        buffer.extend_from_slice(&chunk[..read]);
+
+        let mut headers = [httparse::EMPTY_HEADER; 64];
+        let mut request = httparse::Request::new(&mut headers);
+
+        match request.parse(&buffer) {
+            Ok(httparse::Status::Complete(_)) => {
+                let head = RequestHead {
+                    path: request.path.unwrap_or_default().to_owned(),
+                    authorization: request
+                        .headers
+                        .iter()
+                        .find(|header| header.name.eq_ignore_ascii_case("authorization"))
+                        .and_then(|header| std::str::from_utf8(header.value).ok())
+                        .map(ToOwned::to_owned),
+                };
+
+                return Ok((head, buffer));
+            }
+            Ok(httparse::Status::Partial) if buffer.len() < MAX_HEAD_SIZE => {}
+            Ok(httparse::Status::Partial) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "the request head is too large",
+                ))
+            }
+            Err(error) => return Err(io::Error::new(io::ErrorKind::InvalidData, error)),
+        }
+    }
+}
+
+/// A stream whose first read bytes are `prefix`.
+pub(crate) struct Prefixed<S> {
+    prefix: Vec<u8>,
+    position: usize,
+    stream: S,
+}
+
+impl<S> Prefixed<S> {
+    pub(crate) fn new(prefix: Vec<u8>, stream: S) -> Self {
+        Self {
+            prefix,
+            position: 0,
+            stream,
+        }
+    }
+}
+
+impl<S> AsyncRead for Prefixed<S>
+where
+    S: AsyncRead + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        context: &mut Context<'_>,
+        buffer: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+
+        if this.position < this.prefix.len() {
+            let remaining = &this.prefix[this.position..];
+            let length = remaining.len().min(buffer.remaining());
+
+            buffer.put_slice(&remaining[..length]);
+            this.position += length;
+
+            return Poll::Ready(Ok(()));
+        }
+
+        Pin::new(&mut this.stream).poll_read(context, buffer)
+    }
+}
+
+impl<S> AsyncWrite for Prefixed<S>
+where
+    S: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        context: &mut Context<'_>,
+        buffer: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(context, buffer)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(context)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(context)
+    }
+}
@@ -0,0 +1,571 @@
+//! The HTTP/1.1 layers of the crate:
+//!
+//! - with the `http-api` feature, the one shared by the JSON APIs served
+//!   next to a [`Server`], e.g. `HttpApi`: one request per connection,
+//!   closed after the response, see [`read_request`] and [`respond`],
+//! - the [`HttpClient`] of the HTTP backends, e.g. of
+//!   [`HttpAuthorization`], also used by the other crates of `ocppx`.
+//!
+//! [`Server`]: crate::Server
+//! [`HttpAuthorization`]: crate::HttpAuthorization
+
+#[cfg(feature = "http-api")]
+use percent_encoding::percent_decode_str;
+#[cfg(feature = "http-api")]
+use serde_json::{json, Value};
+#[cfg(feature = "http-api")]
+use std::borrow::Cow;
+use std::io;
+#[cfg(feature = "tls")]
+use std::sync::Arc;
+#[cfg(feature = "http-api")]
+use subtle::ConstantTimeEq;
+#[cfg(feature = "http-api")]
+use tokio::io::AsyncWrite;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+/// A request read by [`read_request`].
+#[cfg(feature = "http-api")]
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    /// The headers, with their names in lowercase.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+#[cfg(feature = "http-api")]
+impl HttpRequest {
+    /// The value of the header `name`, given in lowercase, if any.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The percent-decoded segments of the path, e.g. `["charge-points",
+    /// "CP/1"]` for `/charge-points/CP%2F1`.
+    pub fn segments(&self) -> Vec<Cow<'_, str>> {
+        self.path
+            .trim_matches('/')
+            .split('/')
+            .map(|segment| percent_decode_str(segment).decode_utf8_lossy())
+            .collect()
+    }
+
+    /// Whether the request carries `Authorization: Bearer <token>`. The
+    /// tokens are compared in constant time.
+    pub fn has_bearer_token(&self, token: &str) -> bool {
+        self.header("authorization")
+            .and_then(|authorization| authorization.strip_prefix("Bearer "))
+            .is_some_and(|given| given.as_bytes().ct_eq(token.as_bytes()).into())
+    }
+}
+
+/// Why a request has not been read, with the status to respond.
+#[cfg(feature = "http-api")]
+#[derive(Debug)]
+pub enum RequestError {
+    /// The connection failed, or has been closed: there is no one to
+    /// respond to.
+    Io(io::Error),
+    /// `400 Bad Request`.
+    Malformed,
+    /// `413 Payload Too Large`.
+    TooLarge,
+}
+
+#[cfg(feature = "http-api")]
+impl From<io::Error> for RequestError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Read a request from `stream`, of at most `max_size` bytes, head
+/// included.
+#[cfg(feature = "http-api")]
+pub async fn read_request<S>(stream: &mut S, max_size: usize) -> Result<HttpRequest, RequestError>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 4096];
+
+    let (mut request, content_length) = loop {
+        let length = stream.read(&mut chunk).await?;
+
+        if length == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        if buffer.len() + length > max_size {
+            return Err(RequestError::TooLarge);
+        }
+
+        buffer.extend_from_slice(&chunk[..length]);
+
+        let mut headers = [httparse::EMPTY_HEADER; 32];
+        let mut parsed = httparse::Request::new(&mut headers);
+
+        let body_start = match parsed.parse(&buffer) {
+            Ok(httparse::Status::Complete(body_start)) => body_start,
+            Ok(httparse::Status::Partial) => continue,
+            Err(_) => return Err(RequestError::Malformed),
+        };
+
+        let request = HttpRequest {
+            method: parsed.method.unwrap_or_default().to_owned(),
+            path: parsed.path.unwrap_or_default().to_owned(),
+            headers: parsed
+                .headers
+                .iter()
+                .filter_map(|header| {
+                    Some((
+                        header.name.to_ascii_lowercase(),
+                        std::str::from_utf8(header.value).ok()?.to_owned(),
+                    ))
+                })
+                .collect(),
+            body: buffer[body_start..].to_vec(),
+        };
+        let content_length = match request.header("content-length") {
+            Some(value) => value.parse().map_err(|_| RequestError::Malformed)?,
+            None => 0,
+        };
+        if body_start
+            .checked_add(content_length)
+            .is_none_or(|body_end| body_end > max_size)
+        {
+            return Err(RequestError::TooLarge);
+        }
+
+        break (request, content_length);
+    };
+
+    while request.body.len() < content_length {
+        let length = stream.read(&mut chunk).await?;
+
+        if length == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        request.body.extend_from_slice(&chunk[..length]);
+    }
+
+    request.body.truncate(content_length);
+
+    Ok(request)
+}
+
+/// An error in the body of a response, e.g. `{"error": "not found"}`.
+#[cfg(feature = "http-api")]
+pub fn error(message: &str) -> Value {
+    json!({ "error": message })
+}
+
+/// Respond `body` with `status`, and close the connection.
+#[cfg(feature = "http-api")]
+pub async fn respond<S>(stream: &mut S, status: u16, body: &Value) -> io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        502 => "Bad Gateway",
+        504 => "Gateway Timeout",
+        _ => "Internal Server Error",
+    };
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Respond to a request that could not be read, if there is someone to
+/// respond to.
+#[cfg(feature = "http-api")]
+pub async fn reject<S>(stream: &mut S, error: RequestError) -> io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    match error {
+        RequestError::Io(error) => Err(error),
+        RequestError::Malformed => respond(stream, 400, &self::error("malformed request")).await,
+        RequestError::TooLarge => {
+            respond(stream, 413, &self::error("the request is too large")).await
+        }
+    }
+}
+
+/// An `http://` or `https://` URL, split for an [`HttpClient`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpUrl {
+    pub secure: bool,
+    pub host: String,
+    pub port: u16,
+    /// The path, with the query if any, e.g. `/id-tags?tag=ABC`, or an
+    /// empty string.
+    pub path: String,
+}
+
+impl HttpUrl {
+    pub fn parse(url: &str) -> Option<Self> {
+        let (secure, rest) = match url.split_once("://")? {
+            ("http", rest) => (false, rest),
+            ("https", rest) => (true, rest),
+            _ => return None,
+        };
+        let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        // An IPv6 address is in brackets, e.g. `[::1]:8080`.
+        let (host, port) = match authority.strip_prefix('[') {
+            Some(authority) => {
+                let (host, port) = authority.split_once(']')?;
+
+                match port {
+                    "" => (host, None),
+                    port => (host, Some(port.strip_prefix(':')?)),
+                }
+            }
+            None => match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            },
+        };
+        let port = match port {
+            Some(port) => port.parse().ok()?,
+            None if secure => 443,
+            None => 80,
+        };
+
+        if host.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            secure,
+            host: host.to_owned(),
+            port,
+            path: path.to_owned(),
+        })
+    }
+
+    /// The host for a `Host` header, in brackets if it is an IPv6 address.
+    pub fn host_header(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        }
+    }
+}
+
+/// A response received by an [`HttpClient`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+/// A client sending one HTTP/1.0 request per connection: the body of the
+/// response is neither chunked nor kept alive, it ends with the
+/// connection.
+///
+/// The `https://` URLs need the `tls` feature, and the root certificates
+/// given with [`Self::tls`].
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    #[cfg(feature = "tls")]
+    tls: Option<Arc<rustls::ClientConfig>>,
+    max_response_size: usize,
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        Self {
+            #[cfg(feature = "tls")]
+            tls: None,
+            max_response_size: 1 << 20,
+        }
+    }
+}
+
+impl HttpClient {
+    /// Connect to the `https://` URLs with `config`.
+    #[cfg(feature = "tls")]
+    pub fn tls(mut self, config: Arc<rustls::ClientConfig>) -> Self {
+        self.tls = Some(config);
+
+        self
+    }
+
+    /// The size of the largest response accepted, head included, in bytes.
+    /// Defaults to 1 MiB.
+    pub fn max_response_size(mut self, max_response_size: usize) -> Self {
+        self.max_response_size = max_response_size;
+
+        self
+    }
+
+    /// Send `request`, a complete HTTP/1.0 request, to `url`.
+    pub async fn send(&self, url: &HttpUrl, request: &[u8]) -> io::Result<HttpResponse> {
+        let stream = TcpStream::connect((url.host.as_str(), url.port)).await?;
+
+        if !url.secure {
+            return self.exchange(stream, request).await;
+        }
+
+        #[cfg(feature = "tls")]
+        {
+            use rustls::pki_types::ServerName;
+            use tokio_rustls::TlsConnector;
+
+            let config = self.tls.clone().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "no TLS configuration for the `https://` URLs",
+                )
+            })?;
+            let server_name = ServerName::try_from(url.host.clone())
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+            let stream = TlsConnector::from(config)
+                .connect(server_name, stream)
+                .await?;
+
+            self.exchange(stream, request).await
+        }
+
+        #[cfg(not(feature = "tls"))]
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the `https://` URLs need the `tls` feature",
+        ))
+    }
+
+    async fn exchange<S>(&self, mut stream: S, request: &[u8]) -> io::Result<HttpResponse>
+    where
+        S: AsyncRead + AsyncWriteExt + Unpin,
+    {
+        stream.write_all(request).await?;
+
+        let mut response = Vec::new();
+        let mut chunk = [0; 4096];
+
+        loop {
+            let length = match stream.read(&mut chunk).await {
+                Ok(0) => break,
+                Ok(length) => length,
+                // Many servers close the TLS connections without a
+                // `close_notify`: the `Content-Length` is checked instead.
+                Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(error) => return Err(error),
+            };
+
+            if response.len() + length > self.max_response_size {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "the HTTP response is too large",
+                ));
+            }
+
+            response.extend_from_slice(&chunk[..length]);
+        }
+
+        let malformed = || io::Error::new(io::ErrorKind::InvalidData, "malformed HTTP response");
+        let mut headers = [httparse::EMPTY_HEADER; 32];
+        let mut parsed = httparse::Response::new(&mut headers);
+
+        let Ok(httparse::Status::Complete(body_start)) = parsed.parse(&response) else {
+            return Err(malformed());
+        };
+
+        let mut body = response[body_start..].to_vec();
+        let content_length = parsed
+            .headers
+            .iter()
+            .find(|header| header.name.eq_ignore_ascii_case("content-length"))
+            .map(|header| {
+                std::str::from_utf8(header.value)
+                    .ok()
+                    .and_then(|value| value.trim().parse::<usize>().ok())
+                    .ok_or_else(malformed)
+            })
+            .transpose()?;
+
+        if let Some(content_length) = content_length {
+            if body.len() < content_length {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+            }
+
+            body.truncate(content_length);
+        }
+
+        Ok(HttpResponse {
+            status: parsed.code.unwrap_or_default(),
+            body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "http-api")]
+    #[tokio::test]
+    async fn test_read_request() {
+        let read =
+            |request: &'static str| async move { read_request(&mut request.as_bytes(), 128).await };
+
+        let request = read(
+            "POST /charge-points/CP001/ HTTP/1.1\r\nAuthorization: Bearer secret\r\nContent-Length: 2\r\n\r\n{}",
+        )
+        .await
+        .unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.segments(), ["charge-points", "CP001"]);
+        assert_eq!(request.body, b"{}");
+        assert!(request.has_bearer_token("secret"));
+        assert!(!request.has_bearer_token("secret2"));
+        assert!(!request.has_bearer_token(""));
+
+        assert!(matches!(
+            read("GET / HTTP/1.1\r\nContent-Length: 18446744073709551615\r\n\r\n").await,
+            Err(RequestError::TooLarge)
+        ));
+        assert!(matches!(
+            read("GET / HTTP/1.1\r\nContent-Length: 200\r\n\r\n").await,
+            Err(RequestError::TooLarge)
+        ));
+        assert!(matches!(
+            read("GET / HTTP/1.1\r\nContent-Length: x\r\n\r\n").await,
+            Err(RequestError::Malformed)
+        ));
+        assert!(matches!(
+            read("GET / HTTP/1.1\r\nContent-Length: 2\r\n\r\n{").await,
+            Err(RequestError::Io(_))
+        ));
+        assert!(matches!(
+            read("GET\0/ HTTP/1.1\r\n\r\n").await,
+            Err(RequestError::Malformed)
+        ));
+
+        let request = read("GET /charge-points/CP%2F1/CP%201/CP%FF HTTP/1.1\r\n\r\n")
+            .await
+            .unwrap();
+        assert_eq!(
+            request.segments(),
+            ["charge-points", "CP/1", "CP 1", "CP\u{fffd}"]
+        );
+    }
+
+    #[test]
+    fn test_http_url() {
+        assert_eq!(
+            HttpUrl::parse("https://example.org/id-tags?tag=ABC"),
+            Some(HttpUrl {
+                secure: true,
+                host: "example.org".to_owned(),
+                port: 443,
+                path: "/id-tags?tag=ABC".to_owned(),
+            })
+        );
+        assert_eq!(
+            HttpUrl::parse("http://127.0.0.1:8080"),
+            Some(HttpUrl {
+                secure: false,
+                host: "127.0.0.1".to_owned(),
+                port: 8080,
+                path: String::new(),
+            })
+        );
+        assert_eq!(
+            HttpUrl::parse("http://[::1]:8080/id-tags"),
+            Some(HttpUrl {
+                secure: false,
+                host: "::1".to_owned(),
+                port: 8080,
+                path: "/id-tags".to_owned(),
+            })
+        );
+        assert_eq!(
+            HttpUrl::parse("https://[2001:db8::1]"),
+            Some(HttpUrl {
+                secure: true,
+                host: "2001:db8::1".to_owned(),
+                port: 443,
+                path: String::new(),
+            })
+        );
+        assert_eq!(
+            HttpUrl::parse("http://[::1]:8080/").unwrap().host_header(),
+            "[::1]"
+        );
+        assert_eq!(
+            HttpUrl::parse("http://127.0.0.1:8080/")
+                .unwrap()
+                .host_header(),
+            "127.0.0.1"
+        );
+        assert_eq!(HttpUrl::parse("ftp://example.org/"), None);
+        assert_eq!(HttpUrl::parse("http://[::1/"), None);
+        assert_eq!(HttpUrl::parse("http://[::1]8080/"), None);
+        assert_eq!(HttpUrl::parse("http://:80/"), None);
+        assert_eq!(HttpUrl::parse("http://example.org:x/"), None);
+    }
+
+    #[tokio::test]
+    async fn test_http_client() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = HttpUrl::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+
+        let server = tokio::spawn(async move {
+            for response in [
+                "HTTP/1.0 200 OK\r\nContent-Length: 2\r\n\r\n{}",
+                "HTTP/1.0 200 OK\r\n\r\n01234567890123456789012345678901234567890123456789",
+                "HTTP/1.0 200 OK\r\nContent-Length: 4\r\n\r\n{}",
+            ] {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = [0; 18];
+                stream.read_exact(&mut request).await.unwrap();
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let client = HttpClient::default().max_response_size(64);
+        let request = b"GET / HTTP/1.0\r\n\r\n";
+
+        let response = client.send(&url, request).await.unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"{}");
+
+        let too_large = client.send(&url, request).await.unwrap_err();
+        assert_eq!(too_large.kind(), io::ErrorKind::InvalidData);
+
+        let truncated = client.send(&url, request).await.unwrap_err();
+        assert_eq!(truncated.kind(), io::ErrorKind::UnexpectedEof);
+
+        server.await.unwrap();
+
+        let secure = HttpUrl {
+            secure: true,
+            ..url
+        };
+        assert!(client.send(&secure, request).await.is_err());
+    }
+}
@@ -0,0 +1,473 @@
+use crate::{
+    http::{self, error, respond},
+    AuthProvider, CsmsHandler, Error, OcppVersion, Result, Server, SessionEvent,
+};
+use ocppx_rpc::ConnectionEvent;
+use ocppx_types::{
+    v1_6::{
+        ChangeAvailabilityRequest, RemoteStartTransactionRequest, RemoteStopTransactionRequest,
+        ResetRequest,
+    },
+    OcppRequest,
+};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::broadcast::error::RecvError,
+    time,
+};
+
+/// Size of the largest request accepted by the [`HttpApi`], in bytes.
+const MAX_REQUEST_SIZE: usize = 64 << 10;
+
+/// Interval of the comments sent on an idle event stream, to notice that
+/// its client has gone away.
+const EVENTS_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// A management API over HTTP/1.1, for the operators to drive a [`Server`]
+/// without speaking OCPP. The bodies are in JSON:
+///
+/// - `GET /charge-points`: the connected Charge Points, e.g.
+///   `[{"id": "CP001", "sessionId": 1}]`,
+/// - `POST /charge-points/{id}/remote-start-transaction`,
+///   `POST /charge-points/{id}/remote-stop-transaction`,
+///   `POST /charge-points/{id}/reset`, and
+///   `POST /charge-points/{id}/change-availability`: send the request in
+///   the body to the Charge Point, and respond with its response,
+/// - `GET /events`: a stream of [Server-Sent Events][sse], `opened`,
+///   `superseded`, `closed` for the [`SessionEvent`]s, and `stale` for the
+///   [`ConnectionEvent`]s.
+///
+/// The path segments are percent-decoded, e.g. `CP%2F1` is the Charge
+/// Point `CP/1`. A request to a Charge Point that is not connected is `404
+/// Not Found`, to one speaking another version than OCPP 1.6 is `409
+/// Conflict`, a `CallError` is `502 Bad Gateway`, and no response is `504
+/// Gateway Timeout`.
+///
+/// The API is not authenticated unless a bearer token is required with
+/// [`Self::bearer_token`].
+///
+/// [sse]: https://html.spec.whatwg.org/multipage/server-sent-events.html
+pub struct HttpApi<H, A = ()> {
+    server: Server<H, A>,
+    bearer_token: Option<String>,
+}
+
+impl<H, A> HttpApi<H, A>
+where
+    H: CsmsHandler,
+    A: AuthProvider,
+{
+    pub fn new(server: Server<H, A>) -> Self {
+        Self {
+            server,
+            bearer_token: None,
+        }
+    }
+
+    /// Require the requests to carry `Authorization: Bearer <token>`.
+    pub fn bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.bearer_token = Some(token.into());
+        self
+    }
+
+    /// Listen on `address` and serve the API forever.
+    pub async fn listen<T>(self, address: T) -> Result<()>
+    where
+        T: ToSocketAddrs,
+    {
+        self.serve(TcpListener::bind(address).await?).await
+    }
+
+    /// Serve the API on the connections accepted by `listener`, forever.
+    /// Each request is served on its own connection.
+    pub async fn serve(self, listener: TcpListener) -> Result<()> {
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let server = self.server.clone();
+            let bearer_token = self.bearer_token.clone();
+
+            tokio::spawn(async move {
+                if let Err(error) = handle(stream, &server, bearer_token.as_deref()).await {
+                    log::debug!(error:% = error; "HTTP API connection failed");
+                }
+            });
+        }
+    }
+}
+
+async fn handle<H, A>(
+    mut stream: TcpStream,
+    server: &Server<H, A>,
+    bearer_token: Option<&str>,
+) -> std::io::Result<()>
+where
+    H: CsmsHandler,
+    A: AuthProvider,
+{
+    let request = match http::read_request(&mut stream, MAX_REQUEST_SIZE).await {
+        Ok(request) => request,
+        Err(error) => return http::reject(&mut stream, error).await,
+    };
+
+    if let Some(token) = bearer_token {
+        if !request.has_bearer_token(token) {
+            return respond(&mut stream, 401, &error("unauthorized")).await;
+        }
+    }
+
+    let body = &request.body;
+    let segments = request.segments();
+    let segments = segments.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+
+    let (status, response) = match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["charge-points"]) => (
+            200,
+            Value::Array(
+                server
+                    .connected_charge_points()
+                    .into_iter()
+                    .map(|id| {
+                        let session_id = server.sessions().session_id(&id);
+
+                        json!({ "id": id, "sessionId": session_id })
+                    })
+                    .collect(),
+            ),
+        ),
+        ("GET", ["events"]) => return events(&mut stream, server).await,
+        ("POST", ["charge-points", id, "remote-start-transaction"]) => {
+            send::<H, A, RemoteStartTransactionRequest>(server, id, body).await
+        }
+        ("POST", ["charge-points", id, "remote-stop-transaction"]) => {
+            send::<H, A, RemoteStopTransactionRequest>(server, id, body).await
+        }
+        ("POST", ["charge-points", id, "reset"]) => {
+            send::<H, A, ResetRequest>(server, id, body).await
+        }
+        ("POST", ["charge-points", id, "change-availability"]) => {
+            send::<H, A, ChangeAvailabilityRequest>(server, id, body).await
+        }
+        _ => (404, error("not found")),
+    };
+
+    respond(&mut stream, status, &response).await
+}
+
+/// Send the request `R` in `body` to the Charge Point `charge_point_id`.
+/// The requests are of OCPP 1.6, they are not sent to the Charge Points
+/// speaking another version.
+async fn send<H, A, R>(server: &Server<H, A>, charge_point_id: &str, body: &[u8]) -> (u16, Value)
+where
+    H: CsmsHandler,
+    A: AuthProvider,
+    R: OcppRequest,
+{
+    match server.charge_point(charge_point_id).version() {
+        Some(OcppVersion::V1_6) => (),
+        Some(version) => {
+            return (
+                409,
+                self::error(&format!(
+                    "the Charge Point speaks {version}, the API sends OCPP 1.6 requests"
+                )),
+            )
+        }
+        None => {
+            return (
+                404,
+                self::error(
+                    &Error::ChargePointNotConnected(charge_point_id.to_owned()).to_string(),
+                ),
+            )
+        }
+    }
+
+    let request = match serde_json::from_slice::<R>(body) {
+        Ok(request) => request,
+        Err(error) => return (400, self::error(&error.to_string())),
+    };
+
+    match server
+        .call::<R, Value>(charge_point_id, R::ACTION, &request)
+        .await
+    {
+        Ok(response) => (200, response),
+        Err(error @ Error::ChargePointNotConnected(_)) => (404, self::error(&error.to_string())),
+        Err(Error::CallError(call_error)) => (
+            502,
+            json!({
+                "errorCode": call_error.error_code,
+                "errorDescription": call_error.error_description,
+                "errorDetails": call_error.error_details,
+            }),
+        ),
+        Err(error @ Error::Timeout { .. }) => (504, self::error(&error.to_string())),
+        Err(error) => (500, self::error(&error.to_string())),
+    }
+}
+
+/// Stream the events of `server` until the client goes away, or sends
+/// anything: it is noticed at the latest at the next keep-alive comment.
+async fn events<H, A>(stream: &mut TcpStream, server: &Server<H, A>) -> std::io::Result<()>
+where
+    H: CsmsHandler,
+    A: AuthProvider,
+{
+    let mut session_events = server.sessions().subscribe();
+    let mut connection_events = server.connection_events();
+    let mut keep_alive =
+        time::interval_at(time::Instant::now() + EVENTS_KEEP_ALIVE, EVENTS_KEEP_ALIVE);
+    let (mut reader, mut writer) = stream.split();
+    let mut received = [0; 1];
+
+    writer
+        .write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n",
+        )
+        .await?;
+
+    loop {
+        let event = tokio::select! {
+            _ = reader.read(&mut received) => return Ok(()),
+            _ = keep_alive.tick() => {
+                writer.write_all(b": keep-alive\n\n").await?;
+
+                continue;
+            }
+            event = session_events.recv() => event.map(|event| match event {
+                SessionEvent::Opened { charge_point_id, session_id } => (
+                    "opened",
+                    json!({ "chargePointId": charge_point_id, "sessionId": session_id }),
+                ),
+                SessionEvent::Superseded { charge_point_id, session_id, by } => (
+                    "superseded",
+                    json!({ "chargePointId": charge_point_id, "sessionId": session_id, "by": by }),
+                ),
+                SessionEvent::Closed { charge_point_id, session_id } => (
+                    "closed",
+                    json!({ "chargePointId": charge_point_id, "sessionId": session_id }),
+                ),
+            }),
+            event = connection_events.recv() => event.map(|event| match event {
+                ConnectionEvent::Stale { charge_point_id, silence } => (
+                    "stale",
+                    json!({ "chargePointId": charge_point_id, "silence": silence.as_secs_f64() }),
+                ),
+            }),
+        };
+
+        let frame = match event {
+            Ok((name, data)) => format!("event: {name}\ndata: {data}\n\n"),
+            // A comment, ignored by the clients.
+            Err(RecvError::Lagged(missed)) => format!(": {missed} events missed\n\n"),
+            Err(RecvError::Closed) => return Ok(()),
+        };
+
+        writer.write_all(frame.as_bytes()).await?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ServerConfig;
+    use ocppx_client::{ChargePointClient, ClientConfig};
+    use ocppx_rpc::{Call, CallError, CallResult, ErrorCode};
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    struct Handler;
+
+    impl CsmsHandler for Handler {
+        async fn handle_call(
+            &self,
+            _charge_point_id: &str,
+            call: Call,
+        ) -> std::result::Result<CallResult, CallError> {
+            Err(CallError::new(
+                call.unique_id,
+                ErrorCode::NotImplemented,
+                "",
+                None,
+            ))
+        }
+    }
+
+    async fn request(port: u16, request: &str) -> String {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        response
+    }
+
+    #[tokio::test]
+    async fn test_http_api() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::new(Handler);
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let api_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = api_listener.local_addr().unwrap().port();
+        tokio::spawn(
+            HttpApi::new(server.clone())
+                .bearer_token("secret")
+                .serve(api_listener),
+        );
+
+        // Follow the events before the Charge Point connects.
+        let mut events = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        events
+            .write_all(b"GET /events HTTP/1.1\r\nAuthorization: Bearer secret\r\n\r\n")
+            .await
+            .unwrap();
+        let mut events = BufReader::new(events).lines();
+        assert_eq!(
+            events.next_line().await.unwrap().unwrap(),
+            "HTTP/1.1 200 OK"
+        );
+        while !events.next_line().await.unwrap().unwrap().is_empty() {}
+
+        let client = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001")
+            .await
+            .unwrap();
+        assert_eq!(events.next_line().await.unwrap().unwrap(), "event: opened");
+        assert!(events
+            .next_line()
+            .await
+            .unwrap()
+            .unwrap()
+            .starts_with(r#"data: {"chargePointId":"CP001""#));
+
+        let response = request(port, "GET /charge-points HTTP/1.1\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 401 Unauthorized\r\n"));
+
+        let response = request(
+            port,
+            "GET /charge-points HTTP/1.1\r\nAuthorization: Bearer secret\r\n\r\n",
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains(r#"[{"id":"CP001","sessionId":"#));
+
+        let body = r#"{"type":"Soft"}"#;
+        let reset = format!(
+            "POST /charge-points/CP001/reset HTTP/1.1\r\nAuthorization: Bearer secret\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        let reset = tokio::spawn(async move { request(port, &reset).await });
+        let call = client.next_call().await.unwrap();
+        assert_eq!(call.action, "Reset");
+        client
+            .respond(CallResult::new(call.unique_id, &json!({"status": "Accepted"})).unwrap())
+            .unwrap();
+        let response = reset.await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with(r#"{"status":"Accepted"}"#));
+
+        let response = request(
+            port,
+            "POST /charge-points/CP002/reset HTTP/1.1\r\nAuthorization: Bearer secret\r\nContent-Length: 15\r\n\r\n{\"type\":\"Soft\"}",
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+        let response = request(
+            port,
+            "POST /charge-points/CP001/reset HTTP/1.1\r\nAuthorization: Bearer secret\r\nContent-Length: 2\r\n\r\n{}",
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_http_api_version() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::with_config(
+            Handler,
+            ServerConfig {
+                versions: vec![OcppVersion::V1_6, OcppVersion::V2_0_1],
+                ..ServerConfig::default()
+            },
+        );
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let api_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = api_listener.local_addr().unwrap().port();
+        tokio::spawn(HttpApi::new(server.clone()).serve(api_listener));
+
+        let _client = ChargePointClient::connect_with_config(
+            &format!("ws://{address}/ocpp"),
+            "CP002",
+            ClientConfig {
+                subprotocol: "ocpp2.0.1",
+                heartbeat: false,
+                ..ClientConfig::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        // The OCPP 1.6 request is not sent to an OCPP 2.0.1 Charge Point.
+        let response = request(
+            port,
+            "POST /charge-points/CP%30%302/reset HTTP/1.1\r\nContent-Length: 15\r\n\r\n{\"type\":\"Soft\"}",
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 409 Conflict\r\n"));
+        assert!(response.contains("ocpp2.0.1"));
+    }
+
+    #[tokio::test]
+    async fn test_http_api_percent_encoded_identity() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::new(Handler);
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let api_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = api_listener.local_addr().unwrap().port();
+        tokio::spawn(HttpApi::new(server.clone()).serve(api_listener));
+
+        // The Charge Point connects on `/ocpp/CP%201`.
+        let client = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP%201")
+            .await
+            .unwrap();
+        assert_eq!(server.connected_charge_points(), ["CP 1"]);
+
+        let response = request(port, "GET /charge-points HTTP/1.1\r\n\r\n").await;
+        assert!(response.contains(r#"[{"id":"CP 1","sessionId":"#));
+
+        let reset = tokio::spawn(async move {
+            request(
+                port,
+                "POST /charge-points/CP%201/reset HTTP/1.1\r\nContent-Length: 15\r\n\r\n{\"type\":\"Soft\"}",
+            )
+            .await
+        });
+        let call = client.next_call().await.unwrap();
+        assert_eq!(call.action, "Reset");
+        client
+            .respond(CallResult::new(call.unique_id, &json!({"status": "Accepted"})).unwrap())
+            .unwrap();
+        assert!(reset.await.unwrap().starts_with("HTTP/1.1 200 OK\r\n"));
+    }
+}
@@ -0,0 +1,150 @@
+use ocppx_rpc::{Direction, Message};
+use std::{borrow::Cow, fmt, sync::Arc};
+
+/// Inspect, and possibly rewrite, the messages exchanged with the Charge
+/// Points, see [`ServerConfig::interceptors`].
+///
+/// ```rust,ignore
+/// struct FirmwareMirror;
+///
+/// impl Interceptor for FirmwareMirror {
+///     fn outgoing(&self, _charge_point_id: &str, action: &str, message: &mut Message) {
+///         if let (Message::Call(call), "UpdateFirmware") = (message, action) {
+///             call.payload["location"] = "https://mirror.example.org/firmware.bin".into();
+///         }
+///     }
+/// }
+/// ```
+///
+/// `action` is the action of the `Call`, for the responses too.
+///
+/// [`ServerConfig::interceptors`]: crate::ServerConfig::interceptors
+pub trait Interceptor: Send + Sync + 'static {
+    /// A message received from `charge_point_id`, once parsed: a `Call`
+    /// before it reaches the handler, or a response before it is returned
+    /// by [`Server::call`].
+    ///
+    /// [`Server::call`]: crate::Server::call
+    fn incoming(&self, charge_point_id: &str, action: &str, message: &mut Message) {
+        let _ = (charge_point_id, action, message);
+    }
+
+    /// A message to send to `charge_point_id`, before it is serialized: a
+    /// `Call` of [`Server::call`], or a response of the handler.
+    ///
+    /// [`Server::call`]: crate::Server::call
+    fn outgoing(&self, charge_point_id: &str, action: &str, message: &mut Message) {
+        let _ = (charge_point_id, action, message);
+    }
+
+    /// A message captured by [`ServerConfig::recorder`], e.g. to scrub the
+    /// personal data from the captures. Only the capture is rewritten, not
+    /// the message exchanged with the Charge Point.
+    ///
+    /// [`ServerConfig::recorder`]: crate::ServerConfig::recorder
+    fn captured(&self, charge_point_id: &str, direction: Direction, message: &mut Message) {
+        let _ = (charge_point_id, direction, message);
+    }
+}
+
+/// The [`Interceptor`]s of a server, called in the order they are added.
+#[derive(Clone, Default)]
+pub struct Interceptors {
+    interceptors: Vec<Arc<dyn Interceptor>>,
+}
+
+impl fmt::Debug for Interceptors {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("Interceptors")
+            .field("len", &self.interceptors.len())
+            .finish()
+    }
+}
+
+impl Interceptors {
+    /// Add `interceptor`, after the other ones.
+    pub fn with<I>(mut self, interceptor: I) -> Self
+    where
+        I: Interceptor,
+    {
+        self.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.interceptors.is_empty()
+    }
+
+    pub(crate) fn incoming(&self, charge_point_id: &str, action: &str, message: &mut Message) {
+        for interceptor in &self.interceptors {
+            interceptor.incoming(charge_point_id, action, message);
+        }
+    }
+
+    pub(crate) fn outgoing(&self, charge_point_id: &str, action: &str, message: &mut Message) {
+        for interceptor in &self.interceptors {
+            interceptor.outgoing(charge_point_id, action, message);
+        }
+    }
+
+    /// The `frame` to capture. The frames that are not messages are
+    /// captured as they are.
+    pub(crate) fn captured<'a>(
+        &self,
+        charge_point_id: &str,
+        direction: Direction,
+        frame: &'a str,
+    ) -> Cow<'a, str> {
+        if self.is_empty() {
+            return Cow::Borrowed(frame);
+        }
+
+        let Ok(mut message) = frame.parse::<Message>() else {
+            return Cow::Borrowed(frame);
+        };
+
+        for interceptor in &self.interceptors {
+            interceptor.captured(charge_point_id, direction, &mut message);
+        }
+
+        Cow::Owned(message.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct ScrubIdTags;
+
+    impl Interceptor for ScrubIdTags {
+        fn captured(&self, _charge_point_id: &str, _direction: Direction, message: &mut Message) {
+            if let Message::Call(call) = message {
+                if let Some(id_tag) = call.payload.get_mut("idTag") {
+                    *id_tag = "***".into();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_captured() {
+        let frame = r#"[2,"1","Authorize",{"idTag":"TAG001"}]"#;
+
+        assert_eq!(
+            Interceptors::default().captured("CP001", Direction::Incoming, frame),
+            frame
+        );
+
+        let interceptors = Interceptors::default().with(ScrubIdTags);
+        assert_eq!(
+            interceptors.captured("CP001", Direction::Incoming, frame),
+            r#"[2,"1","Authorize",{"idTag":"***"}]"#
+        );
+        assert_eq!(
+            interceptors.captured("CP001", Direction::Incoming, "not a message"),
+            "not a message"
+        );
+    }
+}
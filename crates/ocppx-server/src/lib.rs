@@ -0,0 +1,209 @@
+//! An OCPP-J 1.6 Central System server.
+//!
+//! [`Server`] accepts WebSocket connections from Charge Points, identified
+//! by the last segment of the URL path (e.g. `ws://csms.example.org/ocpp/CP001`
+//! for `CP001`). The `Call`s sent by the Charge Points are dispatched to a
+//! [`CsmsHandler`], and the Central System can send its own `Call`s with
+//! [`Server::call`], or typed requests with a [`ChargePointHandle`], see
+//! [`Server::charge_point`]. The same request is sent to several Charge
+//! Points, e.g. a site in [`Server::groups`], with [`Server::call_many`].
+//! [`Server::shutdown`] drains the connections before a restart.
+//!
+//! The Charge Points speak OCPP 1.6 by default. The server can accept the
+//! other versions on the same port, see [`ServerConfig::versions`]: each
+//! Charge Point gets the highest version it offers, e.g. to dispatch it to
+//! a handler per version with a [`VersionRouter`].
+//!
+//! Other carriers than WebSocket, e.g. plain TCP or a serial line, are
+//! served with [`Server::serve_transport`].
+//!
+//! A Charge Point reconnecting with the identity of a connected one takes
+//! its session over, see [`SessionRegistry`].
+//! [`ServerConfig::keep_alive`] pings the Charge Points to detect the dead
+//! connections.
+//!
+//! The connections, boots, transactions and `CallError`s of the Charge
+//! Points are published on an [`EventBus`], see [`Server::events`], for the
+//! components following them without being part of the handler.
+//!
+//! With the `tls` feature (enabled by default), connections are accepted
+//! over TLS through [`ServerConfig::tls`], see `TlsConfig` for the security
+//! profiles presets.
+//!
+//! The `Call`s can go through a stack of [`Layer`]s before reaching the
+//! handler, see [`Middleware`], e.g. [`LogLayer`] logs them and
+//! [`RateLimitLayer`] limits their rate. With the `json-schema` feature,
+//! `ValidationLayer` validates their payloads.
+//!
+//! With the `store` feature, `StorageLayer` records the state of the Charge
+//! Points (boot information, connector statuses, transactions and meter
+//! values) in an `ocppx_store::Storage`, and `EventSinkLayer` sends them to
+//! an `EventSink`, e.g. a message queue for the analytics pipelines.
+//!
+//! [`TransactionManager`] tracks the transactions of the Charge Points, for
+//! the handlers. It authorizes the ID tags with an
+//! [`AuthorizationProvider`], e.g. [`StaticAuthorization`] for a list of ID
+//! tags in a file, [`HttpAuthorization`] for an HTTP backend, or, with the
+//! `sqlite` feature, `SqliteAuthorization` for an SQLite database.
+//!
+//! The configuration of the Charge Points, e.g. their heartbeat interval,
+//! can be kept in [`ChargePointProfiles`], and is applied each time they
+//! boot. Until their `BootNotification` is accepted, the Charge Points are
+//! restricted by the [`Registrations`], e.g. held `Pending` while they are
+//! provisioned.
+//!
+//! With the `metrics` feature, the traffic is counted in
+//! `ServerConfig::metrics`, to be scraped by Prometheus, and `MetricsLayer`
+//! measures how long the `Call`s take to be handled.
+//!
+//! The rate of the `Call`s of each Charge Point can be limited with
+//! [`ServerConfig::rate_limit`], e.g. against a Charge Point flooding the
+//! server with `MeterValues`.
+//!
+//! With the `http-api` feature, `HttpApi` exposes the connected Charge
+//! Points, a few requests (`RemoteStartTransaction`, `RemoteStopTransaction`,
+//! `Reset` and `ChangeAvailability`) and the session events over HTTP, for
+//! the operators that do not speak OCPP. Its HTTP layer, in the `http`
+//! module, serves other APIs too. The module also has the client of the
+//! HTTP backends, over TLS too with the `tls` feature.
+//!
+//! With the `grpc` feature, `GrpcApi` serves the `CentralSystem` service of
+//! `proto/central_system.proto` to the backends, over gRPC-Web.
+//!
+//! The frames of the connections can be captured with
+//! [`ServerConfig::recorder`], and the captured `Call`s fed back to a
+//! handler with [`replay()`].
+//!
+//! The messages, and their captures, can be inspected and rewritten by
+//! [`ServerConfig::interceptors`], e.g. to add vendor fields, or to scrub
+//! the personal data from the captures, see [`Interceptor`].
+//!
+//! With the `audit` feature, the security-relevant events, e.g. the failed
+//! authentications or the configuration changes, are appended to a
+//! tamper-evident [`ServerConfig::audit_log`], chained with an HMAC key.
+
+#[cfg(feature = "audit")]
+mod audit;
+mod auth;
+mod authorization;
+mod broadcast;
+mod charge_point;
+mod config;
+mod events;
+#[cfg(feature = "grpc")]
+mod grpc_api;
+mod handler;
+mod head;
+pub mod http;
+#[cfg(feature = "http-api")]
+mod http_api;
+mod interceptor;
+mod middleware;
+mod profile;
+#[cfg(feature = "grpc")]
+mod protobuf;
+mod rate_limit;
+mod registration;
+mod replies;
+mod server;
+mod session;
+#[cfg(feature = "tls")]
+mod tls;
+mod transaction;
+mod version;
+
+#[cfg(feature = "audit")]
+pub use audit::{AuditEntry, AuditError, AuditEvent, AuditLog, AUTHENTICATION_FAILURE_WINDOW};
+pub use auth::{AuthProvider, Credentials};
+#[cfg(feature = "sqlite")]
+pub use authorization::SqliteAuthorization;
+pub use authorization::{AuthorizationProvider, HttpAuthorization, StaticAuthorization};
+pub use broadcast::{BroadcastResults, ChargePointGroups, Target};
+pub use charge_point::{CallFailure, ChargePointHandle};
+pub use config::ServerConfig;
+pub use events::{DisconnectReason, Event, EventBus};
+#[cfg(feature = "grpc")]
+pub use grpc_api::GrpcApi;
+pub use handler::{replay, CsmsHandler};
+#[cfg(feature = "http-api")]
+pub use http_api::HttpApi;
+pub use interceptor::{Interceptor, Interceptors};
+#[cfg(feature = "store")]
+pub use middleware::{EventSink, EventSinkLayer, Sinking, StorageLayer, Storing};
+pub use middleware::{
+    Filter, FilterLayer, HandlerService, Layer, LogLayer, Logging, Middleware, RateLimitLayer,
+    RateLimiting, Service,
+};
+#[cfg(feature = "metrics")]
+pub use middleware::{Measuring, MetricsLayer};
+#[cfg(feature = "json-schema")]
+pub use middleware::{Validation, ValidationLayer};
+pub use profile::{ChargePointProfile, ChargePointProfiles, ProfileReport};
+pub use rate_limit::{Rate, RateLimit};
+pub use registration::Registrations;
+#[cfg(feature = "tls")]
+pub use rustls;
+pub use server::{Server, SUBPROTOCOL};
+pub use session::{SessionEvent, SessionRegistry};
+use std::time::Duration;
+use thiserror::Error;
+#[cfg(feature = "tls")]
+pub use tls::TlsConfig;
+pub use transaction::{Transaction, TransactionError, TransactionManager, TransactionStop};
+pub use version::{OcppVersion, UnknownSubprotocolError, Unsupported, VersionRouter};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("I/O error")]
+    Io(#[from] std::io::Error),
+
+    #[error("WebSocket error")]
+    WebSocket(#[from] Box<tokio_tungstenite::tungstenite::Error>),
+
+    #[cfg(feature = "tls")]
+    #[error("TLS error")]
+    Tls(#[from] rustls::Error),
+
+    #[cfg(feature = "tls")]
+    #[error("cannot build the client certificate verifier")]
+    TlsVerifier(#[from] rustls::server::VerifierBuilderError),
+
+    #[error("RPC error")]
+    Rpc(#[from] ocppx_rpc::Error),
+
+    #[error("JSON error")]
+    Json(#[from] serde_json::Error),
+
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error")]
+    Sqlite(#[from] ocppx_sqlite::Error),
+
+    #[error("invalid unique ID")]
+    MessageId(#[from] ocppx_rpc::MessageIdError),
+
+    #[error("invalid URL `{0}`")]
+    InvalidUrl(String),
+
+    #[error("the charge point `{0}` is not connected")]
+    ChargePointNotConnected(String),
+
+    #[error("the connection is closed")]
+    ConnectionClosed,
+
+    #[error("the server is shutting down")]
+    Shutdown,
+
+    #[error("the charge point did not respond to `{action}` after {timeout:?}")]
+    Timeout { action: String, timeout: Duration },
+
+    #[error("the charge point responded with an error: `{}`", .0.error_code)]
+    CallError(ocppx_rpc::CallError),
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for Error {
+    fn from(error: tokio_tungstenite::tungstenite::Error) -> Self {
+        Self::WebSocket(Box::new(error))
+    }
+}
@@ -0,0 +1,943 @@
+use crate::{
+    rate_limit::{RateLimiter, Verdict},
+    CsmsHandler, OcppVersion, RateLimit,
+};
+use ocppx_rpc::{Call, CallError, CallResult, ErrorCode};
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+use tokio::time::Instant;
+
+tokio::task_local! {
+    /// The OCPP version negotiated by the Charge Point whose `Call` goes
+    /// through the layers of a [`Middleware`].
+    static VERSION: OcppVersion;
+}
+
+/// The OCPP version negotiated by the Charge Point whose `Call` is
+/// processed, if known: the `Call`s given to a [`Middleware`] by a
+/// [`replay()`][crate::replay] have none.
+#[cfg(feature = "json-schema")]
+fn negotiated_version() -> Option<OcppVersion> {
+    VERSION.try_with(|version| *version).ok()
+}
+
+/// A step of the processing of the `Call`s sent by the Charge Points.
+///
+/// The innermost service is the [`CsmsHandler`]; the others are created by
+/// [`Layer`]s, and decide whether, and how, the `Call` reaches the inner
+/// service.
+pub trait Service: Send + Sync + 'static {
+    /// Process a `Call` sent by the Charge Point `charge_point_id`.
+    fn call(
+        &self,
+        charge_point_id: &str,
+        call: Call,
+    ) -> impl Future<Output = Result<CallResult, CallError>> + Send;
+}
+
+/// Wrap a [`Service`] into another one, e.g. to log, validate or reject the
+/// `Call`s.
+pub trait Layer<S> {
+    type Service: Service;
+
+    fn layer(&self, inner: S) -> Self::Service;
+}
+
+/// The innermost service, calling the handler.
+pub struct HandlerService<H> {
+    handler: Arc<H>,
+}
+
+impl<H> Service for HandlerService<H>
+where
+    H: CsmsHandler,
+{
+    fn call(
+        &self,
+        charge_point_id: &str,
+        call: Call,
+    ) -> impl Future<Output = Result<CallResult, CallError>> + Send {
+        self.handler.handle_call(charge_point_id, call)
+    }
+}
+
+/// A handler wrapped in a stack of [`Layer`]s, to be given to a
+/// [`Server`][crate::Server]:
+///
+/// ```rust,ignore
+/// let handler = Middleware::new(handler)
+///     .layer(ValidationLayer)
+///     .layer(FilterLayer::new(|charge_point_id, _call| { … }));
+/// let server = Server::new(handler);
+/// ```
+///
+/// The last layer is the outermost one: it sees the `Call`s first. The
+/// connections and disconnections go straight to the handler, and the
+/// middleware keeps the OCPP version negotiated by each Charge Point, for
+/// the layers, e.g. `ValidationLayer`.
+pub struct Middleware<H, S> {
+    handler: Arc<H>,
+    service: S,
+    versions: Mutex<HashMap<String, OcppVersion>>,
+}
+
+impl<H> Middleware<H, HandlerService<H>>
+where
+    H: CsmsHandler,
+{
+    pub fn new(handler: H) -> Self {
+        let handler = Arc::new(handler);
+
+        Self {
+            service: HandlerService {
+                handler: handler.clone(),
+            },
+            handler,
+            versions: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<H, S> Middleware<H, S>
+where
+    H: CsmsHandler,
+    S: Service,
+{
+    /// Wrap the stack in `layer`.
+    pub fn layer<L>(self, layer: L) -> Middleware<H, L::Service>
+    where
+        L: Layer<S>,
+    {
+        Middleware {
+            handler: self.handler,
+            service: layer.layer(self.service),
+            versions: self.versions,
+        }
+    }
+}
+
+impl<H, S> CsmsHandler for Middleware<H, S>
+where
+    H: CsmsHandler,
+    S: Service,
+{
+    async fn handle_call(
+        &self,
+        charge_point_id: &str,
+        call: Call,
+    ) -> Result<CallResult, CallError> {
+        let version = self.versions.lock().unwrap().get(charge_point_id).copied();
+        let call_result = self.service.call(charge_point_id, call);
+
+        match version {
+            Some(version) => VERSION.scope(version, call_result).await,
+            None => call_result.await,
+        }
+    }
+
+    fn connected(&self, charge_point_id: &str) -> impl Future<Output = ()> + Send {
+        self.handler.connected(charge_point_id)
+    }
+
+    fn connected_with_version(
+        &self,
+        charge_point_id: &str,
+        version: OcppVersion,
+    ) -> impl Future<Output = ()> + Send {
+        self.versions
+            .lock()
+            .unwrap()
+            .insert(charge_point_id.to_owned(), version);
+
+        self.handler
+            .connected_with_version(charge_point_id, version)
+    }
+
+    fn disconnected(&self, charge_point_id: &str) -> impl Future<Output = ()> + Send {
+        self.versions.lock().unwrap().remove(charge_point_id);
+
+        self.handler.disconnected(charge_point_id)
+    }
+}
+
+/// A layer rejecting the `Call`s for which `filter` returns an error, e.g.
+/// to forbid some actions to some Charge Points.
+pub struct FilterLayer<F> {
+    filter: Arc<F>,
+}
+
+impl<F> FilterLayer<F>
+where
+    F: Fn(&str, &Call) -> Result<(), CallError> + Send + Sync + 'static,
+{
+    pub fn new(filter: F) -> Self {
+        Self {
+            filter: Arc::new(filter),
+        }
+    }
+}
+
+impl<F, S> Layer<S> for FilterLayer<F>
+where
+    F: Fn(&str, &Call) -> Result<(), CallError> + Send + Sync + 'static,
+    S: Service,
+{
+    type Service = Filter<F, S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Filter {
+            filter: self.filter.clone(),
+            inner,
+        }
+    }
+}
+
+/// The service created by [`FilterLayer`].
+pub struct Filter<F, S> {
+    filter: Arc<F>,
+    inner: S,
+}
+
+impl<F, S> Service for Filter<F, S>
+where
+    F: Fn(&str, &Call) -> Result<(), CallError> + Send + Sync + 'static,
+    S: Service,
+{
+    async fn call(&self, charge_point_id: &str, call: Call) -> Result<CallResult, CallError> {
+        (self.filter)(charge_point_id, &call)?;
+
+        self.inner.call(charge_point_id, call).await
+    }
+}
+
+/// A layer validating the payloads of the `Call`s against their JSON
+/// schema, and responding with a `FormationViolation` when they do not
+/// match. `Call`s with an unknown action are left to the inner service.
+///
+/// The schemas are the ones of the OCPP version negotiated by the Charge
+/// Point: OCPP 1.6, with the messages of its Security Whitepaper, or OCPP
+/// 2.0.1. The `Call`s of OCPP 2.1 are not validated, and the ones of a
+/// Charge Point whose version is not known are validated as OCPP 1.6.
+#[cfg(feature = "json-schema")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidationLayer;
+
+#[cfg(feature = "json-schema")]
+impl<S> Layer<S> for ValidationLayer
+where
+    S: Service,
+{
+    type Service = Validation<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Validation { inner }
+    }
+}
+
+/// The service created by [`ValidationLayer`].
+#[cfg(feature = "json-schema")]
+pub struct Validation<S> {
+    inner: S,
+}
+
+#[cfg(feature = "json-schema")]
+impl<S> Service for Validation<S>
+where
+    S: Service,
+{
+    async fn call(&self, charge_point_id: &str, call: Call) -> Result<CallResult, CallError> {
+        use ocppx_types::{v1_6, v1_6_security, v2_0_1};
+
+        let validation = match negotiated_version().unwrap_or(OcppVersion::V1_6) {
+            OcppVersion::V1_6 => match call.action.parse::<v1_6::Action>() {
+                Ok(action) => Some(v1_6::validate(action, &call.payload)),
+                Err(_) => call
+                    .action
+                    .parse::<v1_6_security::Action>()
+                    .ok()
+                    .map(|action| v1_6_security::validate(action, &call.payload)),
+            },
+            OcppVersion::V2_0_1 => call
+                .action
+                .parse::<v2_0_1::Action>()
+                .ok()
+                .map(|action| v2_0_1::validate(action, &call.payload)),
+            OcppVersion::V2_1 => None,
+        };
+
+        if let Some(Err(error)) = validation {
+            let field_errors = error
+                .violations
+                .iter()
+                .map(|violation| {
+                    // The violated keyword ends the schema path, e.g.
+                    // `/properties/idTag/maxLength`.
+                    let constraint = violation.schema_path.rsplit('/').next().unwrap_or("");
+
+                    ocppx_rpc::FieldError::new(&violation.instance_path, constraint)
+                        .message(&violation.message)
+                })
+                .collect::<Vec<_>>();
+
+            return Err(CallError::new(
+                call.unique_id,
+                ErrorCode::FormationViolation,
+                error.to_string(),
+                None,
+            )
+            .with_field_errors(field_errors));
+        }
+
+        self.inner.call(charge_point_id, call).await
+    }
+}
+
+/// A layer logging the `Call`s, with how long they took to be handled, and
+/// the error code of their `CallError` if any.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogLayer;
+
+impl<S> Layer<S> for LogLayer
+where
+    S: Service,
+{
+    type Service = Logging<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Logging { inner }
+    }
+}
+
+/// The service created by [`LogLayer`].
+pub struct Logging<S> {
+    inner: S,
+}
+
+impl<S> Service for Logging<S>
+where
+    S: Service,
+{
+    async fn call(&self, charge_point_id: &str, call: Call) -> Result<CallResult, CallError> {
+        let unique_id = call.unique_id.clone();
+        let action = call.action.clone();
+        let started = Instant::now();
+
+        log::debug!(
+            charge_point_id,
+            unique_id = unique_id.as_str(),
+            action = action.as_str();
+            "Call received"
+        );
+
+        let call_result = self.inner.call(charge_point_id, call).await;
+        let duration = started.elapsed();
+
+        match &call_result {
+            Ok(_) => log::info!(
+                charge_point_id,
+                unique_id = unique_id.as_str(),
+                action = action.as_str(),
+                duration:? = duration;
+                "Call handled"
+            ),
+            Err(call_error) => log::warn!(
+                charge_point_id,
+                unique_id = unique_id.as_str(),
+                action = action.as_str(),
+                duration:? = duration,
+                error_code = call_error.error_code.as_str();
+                "Call answered with a CallError"
+            ),
+        }
+
+        call_result
+    }
+}
+
+/// A layer limiting the rate of the `Call`s of each Charge Point, like
+/// [`ServerConfig::rate_limit`][crate::ServerConfig::rate_limit], but for
+/// the handler only, e.g. to limit some Charge Points with a
+/// [`FilterLayer`] in front of it.
+///
+/// A `Call` above the rate is answered with a `SecurityError`. A layer
+/// cannot close the connections: [`RateLimit::close_after`] is ignored.
+pub struct RateLimitLayer {
+    rate_limit: Arc<RateLimit>,
+    limiters: Arc<Mutex<HashMap<String, RateLimiter>>>,
+}
+
+impl RateLimitLayer {
+    pub fn new(rate_limit: RateLimit) -> Self {
+        Self {
+            rate_limit: Arc::new(rate_limit),
+            limiters: Arc::default(),
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer
+where
+    S: Service,
+{
+    type Service = RateLimiting<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimiting {
+            rate_limit: self.rate_limit.clone(),
+            limiters: self.limiters.clone(),
+            inner,
+        }
+    }
+}
+
+/// The service created by [`RateLimitLayer`].
+pub struct RateLimiting<S> {
+    rate_limit: Arc<RateLimit>,
+    limiters: Arc<Mutex<HashMap<String, RateLimiter>>>,
+    inner: S,
+}
+
+impl<S> Service for RateLimiting<S>
+where
+    S: Service,
+{
+    async fn call(&self, charge_point_id: &str, call: Call) -> Result<CallResult, CallError> {
+        let now = Instant::now();
+        let verdict = self
+            .limiters
+            .lock()
+            .unwrap()
+            .entry(charge_point_id.to_owned())
+            .or_insert_with(|| RateLimiter::new(&self.rate_limit, now))
+            .check(&call.action, now);
+
+        if verdict != Verdict::Accept {
+            return Err(CallError::new(
+                call.unique_id,
+                ErrorCode::SecurityError,
+                "Rate limit exceeded",
+                None,
+            ));
+        }
+
+        self.inner.call(charge_point_id, call).await
+    }
+}
+
+/// A layer recording how long the `Call`s take to be handled, by action,
+/// and the `CallError`s they are answered with, in
+/// [`Metrics`][ocppx_rpc::Metrics], e.g. the ones of
+/// [`ServerConfig::metrics`][crate::ServerConfig::metrics].
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone)]
+pub struct MetricsLayer {
+    metrics: ocppx_rpc::Metrics,
+}
+
+#[cfg(feature = "metrics")]
+impl MetricsLayer {
+    pub fn new(metrics: ocppx_rpc::Metrics) -> Self {
+        Self { metrics }
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl<S> Layer<S> for MetricsLayer
+where
+    S: Service,
+{
+    type Service = Measuring<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Measuring {
+            metrics: self.metrics.clone(),
+            inner,
+        }
+    }
+}
+
+/// The service created by [`MetricsLayer`].
+#[cfg(feature = "metrics")]
+pub struct Measuring<S> {
+    metrics: ocppx_rpc::Metrics,
+    inner: S,
+}
+
+#[cfg(feature = "metrics")]
+impl<S> Service for Measuring<S>
+where
+    S: Service,
+{
+    async fn call(&self, charge_point_id: &str, call: Call) -> Result<CallResult, CallError> {
+        let action = call.action.clone();
+        let started = Instant::now();
+        let call_result = self.inner.call(charge_point_id, call).await;
+
+        self.metrics
+            .record_call_handling(&action, started.elapsed());
+
+        if let Err(call_error) = &call_result {
+            self.metrics.record_call_error(&call_error.error_code);
+        }
+
+        call_result
+    }
+}
+
+/// A layer recording the `Call`s that change the state of the Charge
+/// Points in a [`Storage`][ocppx_store::Storage], once the inner service
+/// has accepted them: a `BootNotification` is recorded only if it is
+/// `Accepted`, a `StartTransaction` with the transaction ID of the
+/// response.
+///
+/// A failure of the storage is logged, and does not fail the `Call`.
+#[cfg(feature = "store")]
+pub struct StorageLayer<St> {
+    storage: Arc<St>,
+}
+
+#[cfg(feature = "store")]
+impl<St> StorageLayer<St>
+where
+    St: ocppx_store::Storage,
+{
+    pub fn new(storage: Arc<St>) -> Self {
+        Self { storage }
+    }
+}
+
+#[cfg(feature = "store")]
+impl<St, S> Layer<S> for StorageLayer<St>
+where
+    St: ocppx_store::Storage,
+    S: Service,
+{
+    type Service = Storing<St, S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Storing {
+            storage: self.storage.clone(),
+            inner,
+        }
+    }
+}
+
+/// The service created by [`StorageLayer`].
+#[cfg(feature = "store")]
+pub struct Storing<St, S> {
+    storage: Arc<St>,
+    inner: S,
+}
+
+#[cfg(feature = "store")]
+impl<St, S> Service for Storing<St, S>
+where
+    St: ocppx_store::Storage,
+    S: Service,
+{
+    async fn call(&self, charge_point_id: &str, call: Call) -> Result<CallResult, CallError> {
+        let payload = call.payload.clone();
+        let action = call.action.clone();
+        let call_result = self.inner.call(charge_point_id, call).await?;
+
+        if let Some(event) = event(&action, payload, &call_result) {
+            if let Err(error) = self
+                .storage
+                .record(ocppx_store::Entry::new(charge_point_id, event))
+                .await
+            {
+                log::warn!(
+                    charge_point_id,
+                    action = action.as_str(),
+                    error:% = error;
+                    "cannot record the call in the storage"
+                );
+            }
+        }
+
+        Ok(call_result)
+    }
+}
+
+/// The event of an accepted `Call`, if it changes the state of the Charge
+/// Point.
+#[cfg(feature = "store")]
+fn event(
+    action: &str,
+    payload: serde_json::Value,
+    call_result: &CallResult,
+) -> Option<ocppx_store::Event> {
+    use ocppx_store::Event;
+    use serde_json::from_value;
+
+    match action {
+        "BootNotification" if call_result.payload["status"] == "Accepted" => {
+            from_value(payload).ok().map(Event::Booted)
+        }
+        "StatusNotification" => from_value(payload).ok().map(Event::ConnectorStatus),
+        "StartTransaction" => call_result.payload["transactionId"]
+            .as_i64()
+            .and_then(|transaction_id| i32::try_from(transaction_id).ok())
+            .zip(from_value(payload).ok())
+            .map(|(transaction_id, request)| Event::TransactionStarted {
+                transaction_id,
+                request,
+            }),
+        "StopTransaction" => from_value(payload).ok().map(Event::TransactionStopped),
+        "MeterValues" => from_value(payload).ok().map(Event::MeterValues),
+        _ => None,
+    }
+}
+
+/// Where the events of the Charge Points are sent by [`EventSinkLayer`],
+/// e.g. a message queue feeding an analytics pipeline.
+#[cfg(feature = "store")]
+pub trait EventSink: Send + Sync + 'static {
+    type Error: std::fmt::Display;
+
+    /// Send `entry`, keyed by its `charge_point_id`.
+    fn send(
+        &self,
+        entry: &ocppx_store::Entry,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+}
+
+/// A layer sending the same events as [`StorageLayer`] to an [`EventSink`]:
+/// the accepted boots, the status changes, the transactions and the meter
+/// values.
+///
+/// A failure of the sink is logged, and does not fail the `Call`.
+#[cfg(feature = "store")]
+pub struct EventSinkLayer<K> {
+    sink: Arc<K>,
+}
+
+#[cfg(feature = "store")]
+impl<K> EventSinkLayer<K>
+where
+    K: EventSink,
+{
+    pub fn new(sink: Arc<K>) -> Self {
+        Self { sink }
+    }
+}
+
+#[cfg(feature = "store")]
+impl<K, S> Layer<S> for EventSinkLayer<K>
+where
+    K: EventSink,
+    S: Service,
+{
+    type Service = Sinking<K, S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Sinking {
+            sink: self.sink.clone(),
+            inner,
+        }
+    }
+}
+
+/// The service created by [`EventSinkLayer`].
+#[cfg(feature = "store")]
+pub struct Sinking<K, S> {
+    sink: Arc<K>,
+    inner: S,
+}
+
+#[cfg(feature = "store")]
+impl<K, S> Service for Sinking<K, S>
+where
+    K: EventSink,
+    S: Service,
+{
+    async fn call(&self, charge_point_id: &str, call: Call) -> Result<CallResult, CallError> {
+        let payload = call.payload.clone();
+        let action = call.action.clone();
+        let call_result = self.inner.call(charge_point_id, call).await?;
+
+        if let Some(event) = event(&action, payload, &call_result) {
+            if let Err(error) = self
+                .sink
+                .send(&ocppx_store::Entry::new(charge_point_id, event))
+                .await
+            {
+                log::warn!(
+                    charge_point_id,
+                    action = action.as_str(),
+                    error:% = error;
+                    "cannot send the event to the sink"
+                );
+            }
+        }
+
+        Ok(call_result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ocppx_rpc::ErrorCode;
+    use serde_json::json;
+
+    struct Handler;
+
+    impl CsmsHandler for Handler {
+        async fn handle_call(
+            &self,
+            _charge_point_id: &str,
+            call: Call,
+        ) -> Result<CallResult, CallError> {
+            Ok(CallResult {
+                unique_id: call.unique_id,
+                payload: json!({}),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_layers() {
+        let handler = Middleware::new(Handler).layer(FilterLayer::new(|charge_point_id, call| {
+            if charge_point_id == "CP002" {
+                Err(CallError::new(
+                    call.unique_id.clone(),
+                    ErrorCode::SecurityError,
+                    "",
+                    None,
+                ))
+            } else {
+                Ok(())
+            }
+        }));
+        #[cfg(feature = "json-schema")]
+        let handler = handler.layer(ValidationLayer);
+
+        let call = |payload| Call {
+            unique_id: "1".to_owned(),
+            action: "Heartbeat".to_owned(),
+            payload,
+        };
+
+        assert!(handler.handle_call("CP001", call(json!({}))).await.is_ok());
+        assert_eq!(
+            handler
+                .handle_call("CP002", call(json!({})))
+                .await
+                .unwrap_err()
+                .error_code,
+            ErrorCode::SecurityError
+        );
+
+        #[cfg(feature = "json-schema")]
+        {
+            let call_error = handler
+                .handle_call("CP001", call(json!({ "unexpected": true })))
+                .await
+                .unwrap_err();
+
+            assert_eq!(call_error.error_code, ErrorCode::FormationViolation);
+            assert_eq!(
+                call_error
+                    .field_errors()
+                    .iter()
+                    .map(|field_error| (field_error.path.as_str(), field_error.constraint.as_str()))
+                    .collect::<Vec<_>>(),
+                [("", "additionalProperties")]
+            );
+        }
+    }
+
+    #[cfg(feature = "json-schema")]
+    #[tokio::test]
+    async fn test_validation_layer_version() {
+        let handler = Middleware::new(Handler).layer(ValidationLayer);
+        let boot_notification = || Call {
+            unique_id: "1".to_owned(),
+            action: "BootNotification".to_owned(),
+            payload: json!({
+                "chargePointVendor": "X",
+                "chargePointModel": "Y",
+            }),
+        };
+
+        assert!(handler
+            .handle_call("CP001", boot_notification())
+            .await
+            .is_ok());
+
+        handler
+            .connected_with_version("CP001", OcppVersion::V2_0_1)
+            .await;
+
+        assert_eq!(
+            handler
+                .handle_call("CP001", boot_notification())
+                .await
+                .unwrap_err()
+                .error_code,
+            ErrorCode::FormationViolation
+        );
+        assert!(handler
+            .handle_call("CP002", boot_notification())
+            .await
+            .is_ok());
+
+        handler.disconnected("CP001").await;
+
+        assert!(handler
+            .handle_call("CP001", boot_notification())
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_layer() {
+        use crate::Rate;
+
+        let handler = Middleware::new(Handler)
+            .layer(RateLimitLayer::new(RateLimit {
+                global: Some(Rate::per_minute(2)),
+                ..Default::default()
+            }))
+            .layer(LogLayer);
+        let heartbeat = || Call {
+            unique_id: "1".to_owned(),
+            action: "Heartbeat".to_owned(),
+            payload: json!({}),
+        };
+
+        assert!(handler.handle_call("CP001", heartbeat()).await.is_ok());
+        assert!(handler.handle_call("CP001", heartbeat()).await.is_ok());
+
+        let call_error = handler.handle_call("CP001", heartbeat()).await.unwrap_err();
+        assert_eq!(call_error.error_code, ErrorCode::SecurityError);
+        assert_eq!(call_error.error_description, "Rate limit exceeded");
+
+        // Each Charge Point has its own rate.
+        assert!(handler.handle_call("CP002", heartbeat()).await.is_ok());
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_metrics_layer() {
+        let metrics = ocppx_rpc::Metrics::new();
+        let handler = Middleware::new(Handler)
+            .layer(FilterLayer::new(|charge_point_id, call| {
+                if charge_point_id == "CP002" {
+                    Err(CallError::new(
+                        call.unique_id.clone(),
+                        ErrorCode::SecurityError,
+                        "",
+                        None,
+                    ))
+                } else {
+                    Ok(())
+                }
+            }))
+            .layer(MetricsLayer::new(metrics.clone()));
+
+        for charge_point_id in ["CP001", "CP002"] {
+            let _ = handler
+                .handle_call(
+                    charge_point_id,
+                    Call {
+                        unique_id: "1".to_owned(),
+                        action: "Heartbeat".to_owned(),
+                        payload: json!({}),
+                    },
+                )
+                .await;
+        }
+
+        let output = metrics.render();
+        assert!(output.contains("ocppx_call_handling_seconds_count{action=\"Heartbeat\"} 2\n"));
+        assert!(output.contains("ocppx_call_errors_total{code=\"SecurityError\"} 1\n"));
+    }
+
+    #[cfg(feature = "store")]
+    #[tokio::test]
+    async fn test_storage_layer() {
+        use ocppx_store::{MemoryStorage, Storage};
+
+        let storage = Arc::new(MemoryStorage::new());
+        let handler = Middleware::new(Handler).layer(StorageLayer::new(storage.clone()));
+
+        handler
+            .handle_call(
+                "CP001",
+                Call {
+                    unique_id: "1".to_owned(),
+                    action: "StatusNotification".to_owned(),
+                    payload: json!({
+                        "connectorId": 1,
+                        "errorCode": "NoError",
+                        "status": "Available",
+                    }),
+                },
+            )
+            .await
+            .unwrap();
+
+        let connectors = storage.connectors("CP001").await.unwrap();
+        assert_eq!(connectors.len(), 1);
+        assert_eq!(connectors[0].connector_id, 1);
+    }
+
+    #[cfg(feature = "store")]
+    #[tokio::test]
+    async fn test_event_sink_layer() {
+        use ocppx_store::{Entry, Event};
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        struct Sink(Mutex<Vec<Entry>>);
+
+        impl EventSink for Sink {
+            type Error = std::convert::Infallible;
+
+            async fn send(&self, entry: &Entry) -> Result<(), Self::Error> {
+                self.0.lock().unwrap().push(entry.clone());
+
+                Ok(())
+            }
+        }
+
+        let sink = Arc::new(Sink::default());
+        let handler = Middleware::new(Handler).layer(EventSinkLayer::new(sink.clone()));
+
+        for (action, payload) in [
+            ("Heartbeat", json!({})),
+            (
+                "MeterValues",
+                json!({
+                    "connectorId": 1,
+                    "meterValue": [{
+                        "timestamp": "2013-02-01T20:53:32.486Z",
+                        "sampledValue": [{"value": "1000"}],
+                    }],
+                }),
+            ),
+        ] {
+            handler
+                .handle_call(
+                    "CP001",
+                    Call {
+                        unique_id: "1".to_owned(),
+                        action: action.to_owned(),
+                        payload,
+                    },
+                )
+                .await
+                .unwrap();
+        }
+
+        let entries = sink.0.lock().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].charge_point_id, "CP001");
+        assert!(matches!(entries[0].event, Event::MeterValues(_)));
+    }
+}
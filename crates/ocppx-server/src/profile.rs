@@ -0,0 +1,509 @@
+use crate::{AuthProvider, CsmsHandler, Event, Server};
+use ocppx_rpc::CallResult;
+use ocppx_types::{
+    v1_6::{
+        BootNotificationRequest, BootNotificationResponse, BootNotificationStatus,
+        ChangeConfigurationRequest, ChangeConfigurationStatus, GetConfigurationRequest,
+    },
+    CiString50, CiString500,
+};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Number of times a configuration key is changed before giving up.
+const ATTEMPTS: usize = 3;
+
+/// Time to wait before changing the configuration keys again.
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// The configuration key listing the feature profiles of a Charge Point.
+const SUPPORTED_FEATURE_PROFILES: &str = "SupportedFeatureProfiles";
+
+/// The configuration a Charge Point should have, applied each time it
+/// boots, see [`ChargePointProfiles`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChargePointProfile {
+    /// The `HeartbeatInterval`.
+    pub heartbeat_interval: Option<Duration>,
+    /// The `MeterValueSampleInterval`.
+    pub meter_value_sample_interval: Option<Duration>,
+    /// The feature profiles the Charge Point must support, e.g.
+    /// `SmartCharging`, checked against its `SupportedFeatureProfiles`.
+    pub feature_profiles: Vec<String>,
+    /// The firmware versions the Charge Point may run, checked against its
+    /// `BootNotification`. Any version is allowed when empty.
+    pub firmware_versions: Vec<String>,
+    /// Other configuration keys, e.g. `LocalAuthorizeOffline`, with their
+    /// values.
+    pub configuration: BTreeMap<String, String>,
+}
+
+impl ChargePointProfile {
+    /// All the configuration keys of the profile, with their values.
+    pub fn keys(&self) -> BTreeMap<String, String> {
+        let mut keys = self.configuration.clone();
+
+        if let Some(interval) = self.heartbeat_interval {
+            keys.insert(
+                "HeartbeatInterval".to_owned(),
+                interval.as_secs().to_string(),
+            );
+        }
+
+        if let Some(interval) = self.meter_value_sample_interval {
+            keys.insert(
+                "MeterValueSampleInterval".to_owned(),
+                interval.as_secs().to_string(),
+            );
+        }
+
+        keys
+    }
+}
+
+/// What applying a [`ChargePointProfile`] has done, see
+/// [`Event::ProfileApplied`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileReport {
+    /// The configuration keys that had their value, or have been changed
+    /// and read back with it.
+    pub applied: Vec<String>,
+    /// The configuration keys changed, but only after a reboot.
+    pub reboot_required: Vec<String>,
+    /// The configuration keys that could not be changed, with the reason,
+    /// e.g. `Rejected`, or the failure of the last attempt.
+    pub failed: BTreeMap<String, String>,
+    /// The feature profiles of the profile that the Charge Point does not
+    /// support.
+    pub missing_feature_profiles: Vec<String>,
+    /// The firmware version of the Charge Point, if it is not allowed by
+    /// the profile.
+    pub disallowed_firmware: Option<String>,
+}
+
+impl ProfileReport {
+    /// Whether the Charge Point complies with the profile.
+    pub fn is_compliant(&self) -> bool {
+        self.reboot_required.is_empty()
+            && self.failed.is_empty()
+            && self.missing_feature_profiles.is_empty()
+            && self.disallowed_firmware.is_none()
+    }
+}
+
+/// The [`ChargePointProfile`]s of the Charge Points, see
+/// [`Server::profiles`].
+///
+/// When a Charge Point boots, i.e. once its `BootNotification` is
+/// accepted, its profile, or the default one, is applied: the
+/// configuration keys that differ are changed with `ChangeConfiguration`,
+/// and read back with `GetConfiguration`, a few times if needed. The
+/// result is published as an [`Event::ProfileApplied`].
+#[derive(Debug, Default)]
+pub struct ChargePointProfiles {
+    profiles: Mutex<HashMap<String, Arc<ChargePointProfile>>>,
+    default: Mutex<Option<Arc<ChargePointProfile>>>,
+}
+
+impl ChargePointProfiles {
+    /// Set the profile of `charge_point_id`, replacing the previous one.
+    pub fn set(&self, charge_point_id: &str, profile: ChargePointProfile) {
+        self.profiles
+            .lock()
+            .unwrap()
+            .insert(charge_point_id.to_owned(), Arc::new(profile));
+    }
+
+    /// Remove the profile of `charge_point_id`, which gets the default one
+    /// again.
+    pub fn remove(&self, charge_point_id: &str) -> Option<Arc<ChargePointProfile>> {
+        self.profiles.lock().unwrap().remove(charge_point_id)
+    }
+
+    /// Set the profile of the Charge Points without their own.
+    pub fn set_default(&self, profile: Option<ChargePointProfile>) {
+        *self.default.lock().unwrap() = profile.map(Arc::new);
+    }
+
+    /// The profile of `charge_point_id`, or the default one.
+    pub fn get(&self, charge_point_id: &str) -> Option<Arc<ChargePointProfile>> {
+        self.profiles
+            .lock()
+            .unwrap()
+            .get(charge_point_id)
+            .cloned()
+            .or_else(|| self.default.lock().unwrap().clone())
+    }
+}
+
+/// The `BootNotification` of `request`, if `response` accepts it.
+pub(crate) fn accepted_boot(
+    request: Option<&Value>,
+    response: &CallResult,
+) -> Option<BootNotificationRequest> {
+    let response =
+        serde_json::from_value::<BootNotificationResponse>(response.payload.clone()).ok()?;
+
+    if response.status != BootNotificationStatus::Accepted {
+        return None;
+    }
+
+    serde_json::from_value(request?.clone()).ok()
+}
+
+/// Apply `profile` to `charge_point_id`, which has just sent `boot`.
+pub(crate) async fn apply<H, A>(
+    server: Server<H, A>,
+    charge_point_id: String,
+    profile: Arc<ChargePointProfile>,
+    boot: BootNotificationRequest,
+) where
+    H: CsmsHandler,
+    A: AuthProvider,
+{
+    let charge_point = server.charge_point(&charge_point_id);
+    let mut report = ProfileReport::default();
+
+    if let Some(firmware_version) = boot.firmware_version {
+        let firmware_version = firmware_version.as_str();
+
+        if !profile.firmware_versions.is_empty()
+            && !profile
+                .firmware_versions
+                .iter()
+                .any(|allowed| allowed == firmware_version)
+        {
+            report.disallowed_firmware = Some(firmware_version.to_owned());
+        }
+    }
+
+    let mut pending = profile.keys();
+    let mut check_feature_profiles = !profile.feature_profiles.is_empty();
+
+    for attempt in 0..=ATTEMPTS {
+        // Read the keys back: those already set are not changed again.
+        let mut keys = pending.keys().cloned().collect::<Vec<_>>();
+
+        if check_feature_profiles {
+            keys.push(SUPPORTED_FEATURE_PROFILES.to_owned());
+        }
+
+        // No key would read them all.
+        if keys.is_empty() {
+            break;
+        }
+
+        let request = GetConfigurationRequest::builder()
+            .key(
+                keys.into_iter()
+                    .filter_map(|key| CiString50::try_from(key).ok())
+                    .collect::<Vec<_>>(),
+            )
+            .build();
+
+        match charge_point.call(request).await {
+            Ok(response) => {
+                for configuration_key in response.configuration_key.unwrap_or_default() {
+                    let key = configuration_key.key.as_str();
+                    let value = configuration_key.value.as_ref().map(|value| value.as_str());
+
+                    if key == SUPPORTED_FEATURE_PROFILES && check_feature_profiles {
+                        let supported = value
+                            .unwrap_or_default()
+                            .split(',')
+                            .map(str::trim)
+                            .collect::<Vec<_>>();
+
+                        report.missing_feature_profiles = profile
+                            .feature_profiles
+                            .iter()
+                            .filter(|feature_profile| {
+                                !supported.contains(&feature_profile.as_str())
+                            })
+                            .cloned()
+                            .collect();
+                        check_feature_profiles = false;
+                    } else if pending.get(key).map(String::as_str) == value {
+                        pending.remove(key);
+                        report.applied.push(key.to_owned());
+                    }
+                }
+            }
+
+            Err(error) => {
+                log::warn!(
+                    charge_point_id = charge_point_id.as_str(),
+                    error:% = error;
+                    "cannot read the configuration to apply the profile"
+                );
+            }
+        }
+
+        if pending.is_empty() || attempt == ATTEMPTS {
+            break;
+        }
+
+        if attempt > 0 {
+            tokio::time::sleep(RETRY_DELAY).await;
+        }
+
+        for (key, value) in pending.clone() {
+            let (Ok(ci_key), Ok(ci_value)) = (
+                CiString50::try_from(key.clone()),
+                CiString500::try_from(value),
+            ) else {
+                pending.remove(&key);
+                report.failed.insert(key, "invalid key or value".to_owned());
+
+                continue;
+            };
+
+            let request = ChangeConfigurationRequest::builder()
+                .key(ci_key)
+                .value(ci_value)
+                .build();
+
+            match charge_point.call(request).await {
+                // Read back at the next attempt.
+                Ok(response) if response.status == ChangeConfigurationStatus::Accepted => {}
+
+                Ok(response) if response.status == ChangeConfigurationStatus::RebootRequired => {
+                    pending.remove(&key);
+                    report.reboot_required.push(key);
+                }
+
+                Ok(response) => {
+                    pending.remove(&key);
+                    report.failed.insert(key, format!("{:?}", response.status));
+                }
+
+                Err(error) => {
+                    report.failed.insert(key, error.to_string());
+                }
+            }
+        }
+    }
+
+    // The keys still pending have not been read back with their value.
+    for key in pending.into_keys() {
+        report
+            .failed
+            .entry(key)
+            .or_insert_with(|| "not applied".to_owned());
+    }
+
+    report.applied.sort();
+
+    // A key failing at an attempt may be applied at the next one.
+    for key in &report.applied {
+        report.failed.remove(key);
+    }
+
+    log::info!(
+        charge_point_id = charge_point_id.as_str(),
+        compliant = report.is_compliant();
+        "profile applied"
+    );
+
+    server.events().publish(Event::ProfileApplied {
+        charge_point_id,
+        report,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ocppx_client::{ChargePointClient, ClientConfig};
+    use ocppx_rpc::{Call, CallError};
+    use serde_json::json;
+    use tokio::net::TcpListener;
+
+    struct Handler;
+
+    impl CsmsHandler for Handler {
+        async fn handle_call(
+            &self,
+            _charge_point_id: &str,
+            call: Call,
+        ) -> Result<CallResult, CallError> {
+            Ok(CallResult::new(
+                call.unique_id,
+                &json!({
+                    "status": "Accepted",
+                    "currentTime": "2013-02-01T20:53:32.486Z",
+                    "interval": 300,
+                }),
+            )
+            .unwrap())
+        }
+    }
+
+    #[test]
+    fn test_profiles() {
+        let profiles = ChargePointProfiles::default();
+        let profile = ChargePointProfile {
+            heartbeat_interval: Some(Duration::from_secs(300)),
+            configuration: [("LocalAuthorizeOffline".to_owned(), "true".to_owned())].into(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            profile.keys(),
+            BTreeMap::from([
+                ("HeartbeatInterval".to_owned(), "300".to_owned()),
+                ("LocalAuthorizeOffline".to_owned(), "true".to_owned()),
+            ])
+        );
+
+        assert!(profiles.get("CP001").is_none());
+
+        profiles.set_default(Some(ChargePointProfile::default()));
+        profiles.set("CP001", profile.clone());
+        assert_eq!(profiles.get("CP001").as_deref(), Some(&profile));
+        assert_eq!(
+            profiles.get("CP002").as_deref(),
+            Some(&ChargePointProfile::default())
+        );
+
+        profiles.remove("CP001");
+        assert_eq!(
+            profiles.get("CP001").as_deref(),
+            Some(&ChargePointProfile::default())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::new(Handler);
+        let mut events = server.events().subscribe();
+
+        server.profiles().set(
+            "CP001",
+            ChargePointProfile {
+                heartbeat_interval: Some(Duration::from_secs(300)),
+                feature_profiles: vec!["Core".to_owned(), "SmartCharging".to_owned()],
+                firmware_versions: vec!["1.2.0".to_owned()],
+                configuration: [
+                    ("AllowOfflineTxForUnknownId".to_owned(), "true".to_owned()),
+                    ("LocalAuthorizeOffline".to_owned(), "true".to_owned()),
+                ]
+                .into(),
+                ..Default::default()
+            },
+        );
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let client = ChargePointClient::connect_with_config(
+            &format!("ws://{address}/ocpp"),
+            "CP001",
+            ClientConfig {
+                heartbeat: false,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        client
+            .call::<_, Value>(
+                "BootNotification",
+                &json!({
+                    "chargePointVendor": "ocppx",
+                    "chargePointModel": "test",
+                    "firmwareVersion": "1.0.0",
+                }),
+            )
+            .await
+            .unwrap();
+
+        let respond = |call: Call, payload: Value| {
+            client
+                .respond(CallResult::new(call.unique_id, &payload).unwrap())
+                .unwrap()
+        };
+
+        let call = client.next_call().await.unwrap();
+        assert_eq!(call.action, "GetConfiguration");
+        respond(
+            call,
+            json!({
+                "configurationKey": [
+                    { "key": "AllowOfflineTxForUnknownId", "readonly": false, "value": "false" },
+                    { "key": "HeartbeatInterval", "readonly": false, "value": "60" },
+                    { "key": "LocalAuthorizeOffline", "readonly": false, "value": "true" },
+                    { "key": "SupportedFeatureProfiles", "readonly": true, "value": "Core,FirmwareManagement" },
+                ],
+            }),
+        );
+
+        // Only the keys that differ are changed.
+        let call = client.next_call().await.unwrap();
+        assert_eq!(
+            (call.action.as_str(), &call.payload),
+            (
+                "ChangeConfiguration",
+                &json!({ "key": "AllowOfflineTxForUnknownId", "value": "true" })
+            )
+        );
+        respond(call, json!({ "status": "Rejected" }));
+
+        let call = client.next_call().await.unwrap();
+        assert_eq!(
+            call.payload,
+            json!({ "key": "HeartbeatInterval", "value": "300" })
+        );
+        respond(call, json!({ "status": "Accepted" }));
+
+        // And read back.
+        let call = client.next_call().await.unwrap();
+        assert_eq!(
+            (call.action.as_str(), &call.payload),
+            ("GetConfiguration", &json!({ "key": ["HeartbeatInterval"] }))
+        );
+        respond(
+            call,
+            json!({
+                "configurationKey": [
+                    { "key": "HeartbeatInterval", "readonly": false, "value": "300" },
+                ],
+            }),
+        );
+
+        let report = loop {
+            if let Event::ProfileApplied { report, .. } = events.recv().await.unwrap() {
+                break report;
+            }
+        };
+
+        assert_eq!(
+            report,
+            ProfileReport {
+                applied: vec![
+                    "HeartbeatInterval".to_owned(),
+                    "LocalAuthorizeOffline".to_owned()
+                ],
+                reboot_required: vec![],
+                failed: [(
+                    "AllowOfflineTxForUnknownId".to_owned(),
+                    "Rejected".to_owned()
+                )]
+                .into(),
+                missing_feature_profiles: vec!["SmartCharging".to_owned()],
+                disallowed_firmware: Some("1.0.0".to_owned()),
+            }
+        );
+        assert!(!report.is_compliant());
+    }
+}
@@ -0,0 +1,686 @@
+use chrono::{DateTime, SecondsFormat};
+use ocppx_types::{ProtoField, ProtoMessage, ProtoType};
+use serde_json::{Map, Number, Value};
+use thiserror::Error;
+
+/// Why a payload cannot be transcoded between JSON and protobuf.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ProtobufError {
+    #[error("malformed protobuf message")]
+    Malformed,
+
+    #[error("unknown message `{0}`")]
+    UnknownMessage(String),
+
+    #[error("invalid value for the field `{0}`")]
+    InvalidField(&'static str),
+}
+
+type Result<T> = std::result::Result<T, ProtobufError>;
+
+pub(crate) const VARINT: u8 = 0;
+pub(crate) const FIXED64: u8 = 1;
+pub(crate) const LENGTH_DELIMITED: u8 = 2;
+
+/// The payloads of `messages`, as described by their descriptors, e.g.
+/// `ocppx_types::v1_6::PROTO_MESSAGES`.
+#[derive(Clone, Copy)]
+pub(crate) struct Transcoder {
+    messages: &'static [ProtoMessage],
+    actions: &'static [&'static str],
+}
+
+impl Transcoder {
+    pub(crate) fn new(messages: &'static [ProtoMessage], actions: &'static [&'static str]) -> Self {
+        Self { messages, actions }
+    }
+
+    /// The JSON payload of the protobuf message `name`.
+    pub(crate) fn decode(&self, name: &str, bytes: &[u8]) -> Result<Value> {
+        let message = self.message(name)?;
+
+        self.decode_message(message, bytes).map(Value::Object)
+    }
+
+    /// The protobuf message `name` of the JSON `payload`.
+    pub(crate) fn encode(&self, name: &str, payload: &Value) -> Result<Vec<u8>> {
+        let message = self.message(name)?;
+        let object = payload.as_object().ok_or(ProtobufError::Malformed)?;
+        let mut output = Vec::new();
+
+        self.encode_message(message, object, &mut output)?;
+
+        Ok(output)
+    }
+
+    /// The action and the JSON payload of a `Request` or a `Response`
+    /// message, `kind`: the payload of the action `n` is the field `n + 1`.
+    pub(crate) fn decode_payload(&self, kind: &str, bytes: &[u8]) -> Result<(&'static str, Value)> {
+        let mut reader = Reader::new(bytes);
+        let mut payload = None;
+
+        while !reader.is_empty() {
+            let (number, wire_type) = reader.key()?;
+            let action = usize::try_from(number)
+                .ok()
+                .and_then(|number| number.checked_sub(1))
+                .and_then(|index| self.actions.get(index));
+
+            match (action, wire_type) {
+                (Some(action), LENGTH_DELIMITED) => {
+                    payload = Some((*action, reader.bytes()?));
+                }
+                _ => reader.skip(wire_type)?,
+            }
+        }
+
+        // The last field of a `oneof` wins.
+        let (action, bytes) = payload.ok_or(ProtobufError::Malformed)?;
+
+        Ok((action, self.decode(&format!("{action}{kind}"), bytes)?))
+    }
+
+    /// The `Request` or `Response` message, `kind`, holding the JSON
+    /// `payload` of `action`.
+    pub(crate) fn encode_payload(
+        &self,
+        kind: &str,
+        action: &str,
+        payload: &Value,
+    ) -> Result<Vec<u8>> {
+        let number = self
+            .actions
+            .iter()
+            .position(|known| *known == action)
+            .ok_or_else(|| ProtobufError::UnknownMessage(format!("{action}{kind}")))?
+            + 1;
+        let mut output = Vec::new();
+
+        write_bytes(
+            number as u32,
+            &self.encode(&format!("{action}{kind}"), payload)?,
+            &mut output,
+        );
+
+        Ok(output)
+    }
+
+    fn message(&self, name: &str) -> Result<&'static ProtoMessage> {
+        ProtoMessage::find(self.messages, name)
+            .ok_or_else(|| ProtobufError::UnknownMessage(name.to_owned()))
+    }
+
+    fn encode_message(
+        &self,
+        message: &ProtoMessage,
+        object: &Map<String, Value>,
+        output: &mut Vec<u8>,
+    ) -> Result<()> {
+        for field in message.fields {
+            let value = match object.get(field.json_name) {
+                None | Some(Value::Null) => continue,
+                Some(value) => value,
+            };
+
+            if !field.repeated {
+                self.encode_field(field, value, output)?;
+
+                continue;
+            }
+
+            let items = value
+                .as_array()
+                .ok_or(ProtobufError::InvalidField(field.json_name))?;
+
+            // The repeated scalars are packed.
+            if wire_type(field.ty) == LENGTH_DELIMITED {
+                for item in items {
+                    self.encode_field(field, item, output)?;
+                }
+            } else {
+                let mut packed = Vec::new();
+
+                for item in items {
+                    self.encode_value(field, item, &mut packed)?;
+                }
+
+                write_bytes(field.number, &packed, output);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The key of `field`, followed by `value`.
+    fn encode_field(&self, field: &ProtoField, value: &Value, output: &mut Vec<u8>) -> Result<()> {
+        write_key(field.number, wire_type(field.ty), output);
+
+        self.encode_value(field, value, output)
+    }
+
+    /// `value`, without the key of its field.
+    fn encode_value(&self, field: &ProtoField, value: &Value, output: &mut Vec<u8>) -> Result<()> {
+        let invalid = || ProtobufError::InvalidField(field.json_name);
+
+        match field.ty {
+            ProtoType::String => {
+                write_length_delimited(value.as_str().ok_or_else(invalid)?.as_bytes(), output)
+            }
+            ProtoType::Decimal => {
+                let Value::Number(number) = value else {
+                    return Err(invalid());
+                };
+
+                write_length_delimited(number.to_string().as_bytes(), output);
+            }
+            ProtoType::Bool => write_varint(value.as_bool().ok_or_else(invalid)?.into(), output),
+            ProtoType::Int32 => {
+                let value = value
+                    .as_i64()
+                    .and_then(|value| i32::try_from(value).ok())
+                    .ok_or_else(invalid)?;
+
+                // The negative values are sign-extended to 64 bits.
+                write_varint(i64::from(value) as u64, output);
+            }
+            ProtoType::Int64 => write_varint(value.as_i64().ok_or_else(invalid)? as u64, output),
+            ProtoType::Double => {
+                output.extend_from_slice(&value.as_f64().ok_or_else(invalid)?.to_le_bytes())
+            }
+            ProtoType::Timestamp => {
+                let timestamp = value
+                    .as_str()
+                    .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+                    .ok_or_else(invalid)?;
+                let mut message = Vec::new();
+
+                write_key(1, VARINT, &mut message);
+                write_varint(timestamp.timestamp() as u64, &mut message);
+                write_key(2, VARINT, &mut message);
+                write_varint(timestamp.timestamp_subsec_nanos().into(), &mut message);
+
+                write_length_delimited(&message, output);
+            }
+            ProtoType::Value => {
+                let mut message = Vec::new();
+                encode_any(value, &mut message);
+
+                write_length_delimited(&message, output);
+            }
+            ProtoType::Message(name) => {
+                let mut message = Vec::new();
+                self.encode_message(
+                    self.message(name)?,
+                    value.as_object().ok_or_else(invalid)?,
+                    &mut message,
+                )?;
+
+                write_length_delimited(&message, output);
+            }
+            ProtoType::Enum(variants) => {
+                let variant = value
+                    .as_str()
+                    .and_then(|value| variants.iter().position(|variant| *variant == value))
+                    .ok_or_else(invalid)?;
+
+                write_varint(variant as u64 + 1, output);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn decode_message(&self, message: &ProtoMessage, bytes: &[u8]) -> Result<Map<String, Value>> {
+        let mut object = Map::new();
+        let mut reader = Reader::new(bytes);
+
+        while !reader.is_empty() {
+            let (number, wire_type) = reader.key()?;
+            let Some(field) = message.fields.iter().find(|field| field.number == number) else {
+                reader.skip(wire_type)?;
+
+                continue;
+            };
+
+            if !field.repeated {
+                let value = self.decode_value(field, wire_type, &mut reader)?;
+                object.insert(field.json_name.to_owned(), value);
+
+                continue;
+            }
+
+            let items = object
+                .entry(field.json_name)
+                .or_insert_with(|| Value::Array(Vec::new()))
+                .as_array_mut()
+                .expect("the repeated fields are arrays");
+
+            // The repeated scalars may be packed, or not.
+            if wire_type == LENGTH_DELIMITED && self::wire_type(field.ty) != LENGTH_DELIMITED {
+                let mut packed = Reader::new(reader.bytes()?);
+
+                while !packed.is_empty() {
+                    items.push(self.decode_value(field, self::wire_type(field.ty), &mut packed)?);
+                }
+            } else {
+                items.push(self.decode_value(field, wire_type, &mut reader)?);
+            }
+        }
+
+        // Without presence, the default values are not on the wire.
+        for field in message.fields.iter().filter(|field| field.required) {
+            let default = match field.ty {
+                _ if field.repeated => Value::Array(Vec::new()),
+                ProtoType::String => Value::from(""),
+                ProtoType::Decimal | ProtoType::Int32 | ProtoType::Int64 => Value::from(0),
+                ProtoType::Double => Value::from(0.0),
+                ProtoType::Bool => Value::from(false),
+                _ => continue,
+            };
+
+            object.entry(field.json_name).or_insert(default);
+        }
+
+        Ok(object)
+    }
+
+    fn decode_value(
+        &self,
+        field: &ProtoField,
+        wire_type: u8,
+        reader: &mut Reader<'_>,
+    ) -> Result<Value> {
+        if wire_type != self::wire_type(field.ty) {
+            return Err(ProtobufError::Malformed);
+        }
+
+        let invalid = || ProtobufError::InvalidField(field.json_name);
+
+        Ok(match field.ty {
+            ProtoType::String => Value::from(reader.string()?),
+            ProtoType::Decimal => Value::Number(reader.string()?.parse().map_err(|_| invalid())?),
+            ProtoType::Bool => Value::from(reader.varint()? != 0),
+            // The values are truncated to 32 bits, as by the protobuf
+            // parsers.
+            ProtoType::Int32 => Value::from(reader.varint()? as i32),
+            ProtoType::Int64 => Value::from(reader.varint()? as i64),
+            ProtoType::Double => Number::from_f64(f64::from_bits(reader.fixed64()?))
+                .map(Value::Number)
+                .ok_or_else(invalid)?,
+            ProtoType::Timestamp => {
+                let mut message = Reader::new(reader.bytes()?);
+                let (mut seconds, mut nanos) = (0, 0);
+
+                while !message.is_empty() {
+                    match message.key()? {
+                        (1, VARINT) => seconds = message.varint()? as i64,
+                        (2, VARINT) => nanos = message.varint()? as u32,
+                        (_, wire_type) => message.skip(wire_type)?,
+                    }
+                }
+
+                let timestamp = DateTime::from_timestamp(seconds, nanos).ok_or_else(invalid)?;
+
+                Value::from(timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+            }
+            ProtoType::Value => decode_any(reader.bytes()?)?,
+            ProtoType::Message(name) => {
+                Value::Object(self.decode_message(self.message(name)?, reader.bytes()?)?)
+            }
+            ProtoType::Enum(variants) => {
+                let variant = usize::try_from(reader.varint()?)
+                    .ok()
+                    .and_then(|value| value.checked_sub(1))
+                    .and_then(|index| variants.get(index))
+                    .ok_or_else(invalid)?;
+
+                Value::from(*variant)
+            }
+        })
+    }
+}
+
+/// The wire type of the values of `ty`.
+fn wire_type(ty: ProtoType) -> u8 {
+    match ty {
+        ProtoType::Bool | ProtoType::Int32 | ProtoType::Int64 | ProtoType::Enum(_) => VARINT,
+        ProtoType::Double => FIXED64,
+        ProtoType::String
+        | ProtoType::Decimal
+        | ProtoType::Timestamp
+        | ProtoType::Value
+        | ProtoType::Message(_) => LENGTH_DELIMITED,
+    }
+}
+
+/// `value` as a `google.protobuf.Value`.
+fn encode_any(value: &Value, output: &mut Vec<u8>) {
+    match value {
+        Value::Null => {
+            write_key(1, VARINT, output);
+            write_varint(0, output);
+        }
+        Value::Number(number) => {
+            write_key(2, FIXED64, output);
+            output.extend_from_slice(&number.as_f64().unwrap_or_default().to_le_bytes());
+        }
+        Value::String(string) => write_bytes(3, string.as_bytes(), output),
+        Value::Bool(bool) => {
+            write_key(4, VARINT, output);
+            write_varint((*bool).into(), output);
+        }
+        // A `google.protobuf.Struct`, whose `fields` map is a repeated
+        // entry with a `key` and a `value`.
+        Value::Object(object) => {
+            let mut message = Vec::new();
+
+            for (key, value) in object {
+                let mut entry = Vec::new();
+                write_bytes(1, key.as_bytes(), &mut entry);
+                let mut any = Vec::new();
+                encode_any(value, &mut any);
+                write_bytes(2, &any, &mut entry);
+
+                write_bytes(1, &entry, &mut message);
+            }
+
+            write_bytes(5, &message, output);
+        }
+        // A `google.protobuf.ListValue`.
+        Value::Array(array) => {
+            let mut message = Vec::new();
+
+            for value in array {
+                let mut any = Vec::new();
+                encode_any(value, &mut any);
+
+                write_bytes(1, &any, &mut message);
+            }
+
+            write_bytes(6, &message, output);
+        }
+    }
+}
+
+/// The JSON value of a `google.protobuf.Value`.
+fn decode_any(bytes: &[u8]) -> Result<Value> {
+    let mut reader = Reader::new(bytes);
+    let mut value = Value::Null;
+
+    while !reader.is_empty() {
+        value = match reader.key()? {
+            (1, VARINT) => {
+                reader.varint()?;
+
+                Value::Null
+            }
+            (2, FIXED64) => {
+                let number = f64::from_bits(reader.fixed64()?);
+
+                // The integers are `double`s on the wire.
+                if number.fract() == 0.0 && number.abs() < 2f64.powi(53) {
+                    Value::from(number as i64)
+                } else {
+                    Number::from_f64(number)
+                        .map(Value::Number)
+                        .ok_or(ProtobufError::Malformed)?
+                }
+            }
+            (3, LENGTH_DELIMITED) => Value::from(reader.string()?),
+            (4, VARINT) => Value::from(reader.varint()? != 0),
+            (5, LENGTH_DELIMITED) => {
+                let mut message = Reader::new(reader.bytes()?);
+                let mut object = Map::new();
+
+                while !message.is_empty() {
+                    let (1, LENGTH_DELIMITED) = message.key()? else {
+                        return Err(ProtobufError::Malformed);
+                    };
+                    let mut entry = Reader::new(message.bytes()?);
+                    let (mut key, mut value) = (String::new(), Value::Null);
+
+                    while !entry.is_empty() {
+                        match entry.key()? {
+                            (1, LENGTH_DELIMITED) => key = entry.string()?.to_owned(),
+                            (2, LENGTH_DELIMITED) => value = decode_any(entry.bytes()?)?,
+                            (_, wire_type) => entry.skip(wire_type)?,
+                        }
+                    }
+
+                    object.insert(key, value);
+                }
+
+                Value::Object(object)
+            }
+            (6, LENGTH_DELIMITED) => {
+                let mut message = Reader::new(reader.bytes()?);
+                let mut array = Vec::new();
+
+                while !message.is_empty() {
+                    match message.key()? {
+                        (1, LENGTH_DELIMITED) => array.push(decode_any(message.bytes()?)?),
+                        (_, wire_type) => message.skip(wire_type)?,
+                    }
+                }
+
+                Value::Array(array)
+            }
+            (_, wire_type) => {
+                reader.skip(wire_type)?;
+
+                continue;
+            }
+        };
+    }
+
+    Ok(value)
+}
+
+pub(crate) fn write_varint(mut value: u64, output: &mut Vec<u8>) {
+    while value >= 0x80 {
+        output.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+
+    output.push(value as u8);
+}
+
+pub(crate) fn write_key(number: u32, wire_type: u8, output: &mut Vec<u8>) {
+    write_varint(u64::from(number) << 3 | u64::from(wire_type), output);
+}
+
+fn write_length_delimited(bytes: &[u8], output: &mut Vec<u8>) {
+    write_varint(bytes.len() as u64, output);
+    output.extend_from_slice(bytes);
+}
+
+/// The field `number`, holding `bytes`: a string, or a message.
+pub(crate) fn write_bytes(number: u32, bytes: &[u8], output: &mut Vec<u8>) {
+    write_key(number, LENGTH_DELIMITED, output);
+    write_length_delimited(bytes, output);
+}
+
+/// The field `number`, holding a `double`.
+pub(crate) fn write_double(number: u32, value: f64, output: &mut Vec<u8>) {
+    write_key(number, FIXED64, output);
+    output.extend_from_slice(&value.to_le_bytes());
+}
+
+/// A protobuf message, read field by field.
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// The number and the wire type of the next field.
+    pub(crate) fn key(&mut self) -> Result<(u32, u8)> {
+        let key = self.varint()?;
+        let number = u32::try_from(key >> 3).map_err(|_| ProtobufError::Malformed)?;
+
+        Ok((number, (key & 0b111) as u8))
+    }
+
+    pub(crate) fn varint(&mut self) -> Result<u64> {
+        let mut value = 0;
+
+        for (index, byte) in self.bytes.iter().enumerate().take(10) {
+            value |= u64::from(byte & 0x7f) << (7 * index);
+
+            if byte & 0x80 == 0 {
+                self.bytes = &self.bytes[index + 1..];
+
+                return Ok(value);
+            }
+        }
+
+        Err(ProtobufError::Malformed)
+    }
+
+    fn fixed64(&mut self) -> Result<u64> {
+        let (bytes, rest) = self
+            .bytes
+            .split_first_chunk::<8>()
+            .ok_or(ProtobufError::Malformed)?;
+        self.bytes = rest;
+
+        Ok(u64::from_le_bytes(*bytes))
+    }
+
+    pub(crate) fn bytes(&mut self) -> Result<&'a [u8]> {
+        let length = usize::try_from(self.varint()?).map_err(|_| ProtobufError::Malformed)?;
+
+        if length > self.bytes.len() {
+            return Err(ProtobufError::Malformed);
+        }
+
+        let (bytes, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+
+        Ok(bytes)
+    }
+
+    pub(crate) fn string(&mut self) -> Result<&'a str> {
+        std::str::from_utf8(self.bytes()?).map_err(|_| ProtobufError::Malformed)
+    }
+
+    /// Skip a field of `wire_type`, e.g. of a newer version of the message.
+    pub(crate) fn skip(&mut self, wire_type: u8) -> Result<()> {
+        match wire_type {
+            VARINT => {
+                self.varint()?;
+            }
+            FIXED64 => {
+                self.fixed64()?;
+            }
+            LENGTH_DELIMITED => {
+                self.bytes()?;
+            }
+            // `fixed32`.
+            5 if self.bytes.len() >= 4 => self.bytes = &self.bytes[4..],
+            _ => return Err(ProtobufError::Malformed),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ocppx_types::v1_6::{PROTO_ACTIONS, PROTO_MESSAGES};
+    use serde_json::json;
+
+    #[test]
+    fn test_transcoder() {
+        let transcoder = Transcoder::new(PROTO_MESSAGES, PROTO_ACTIONS);
+
+        // `chargePointModel = 2` and `chargePointVendor = 4`.
+        let request = json!({"chargePointModel": "X", "chargePointVendor": "ACME"});
+        let bytes = transcoder
+            .encode("BootNotificationRequest", &request)
+            .unwrap();
+        assert_eq!(bytes, b"\x12\x01X\x22\x04ACME");
+        assert_eq!(
+            transcoder.decode("BootNotificationRequest", &bytes),
+            Ok(request)
+        );
+
+        // The enums, the timestamps and the defaults without presence.
+        let response = json!({
+            "currentTime": "2013-02-01T20:53:32.486Z",
+            "interval": 0,
+            "status": "Rejected",
+        });
+        let bytes = transcoder
+            .encode("BootNotificationResponse", &response)
+            .unwrap();
+        assert_eq!(
+            transcoder.decode("BootNotificationResponse", &bytes[..bytes.len() - 4]),
+            Ok(json!({"currentTime": "2013-02-01T20:53:32.486Z", "interval": 0}))
+        );
+        assert_eq!(
+            transcoder.decode("BootNotificationResponse", &bytes),
+            Ok(response)
+        );
+
+        // The nested messages, the repeated fields, and the negative
+        // integers.
+        let meter_values = json!({
+            "connectorId": -1,
+            "meterValue": [{
+                "timestamp": "2013-02-01T20:53:32Z",
+                "sampledValue": [{"value": "12.5"}, {"value": "13"}],
+            }],
+        });
+        let bytes = transcoder
+            .encode("MeterValuesRequest", &meter_values)
+            .unwrap();
+        assert_eq!(
+            transcoder.decode("MeterValuesRequest", &bytes),
+            Ok(meter_values)
+        );
+
+        // The payloads, by action.
+        let bytes = transcoder
+            .encode_payload("Request", "Heartbeat", &json!({}))
+            .unwrap();
+        assert_eq!(
+            transcoder.decode_payload("Request", &bytes),
+            Ok(("Heartbeat", json!({})))
+        );
+
+        assert_eq!(
+            transcoder.encode("ResetRequest", &json!({"type": "Warm"})),
+            Err(ProtobufError::InvalidField("type"))
+        );
+        assert_eq!(
+            transcoder.decode("ResetRequest", b"\x08\x09"),
+            Err(ProtobufError::InvalidField("type"))
+        );
+        assert_eq!(
+            transcoder.decode("ResetRequest", b"\x0a\x09"),
+            Err(ProtobufError::Malformed)
+        );
+        assert_eq!(
+            transcoder.decode("Reset", b""),
+            Err(ProtobufError::UnknownMessage("Reset".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_any() {
+        let value = json!({"a": [1, 2.5, "b", true, null], "c": {}});
+        let mut bytes = Vec::new();
+        encode_any(&value, &mut bytes);
+
+        assert_eq!(decode_any(&bytes), Ok(value));
+    }
+}
@@ -0,0 +1,208 @@
+use std::{collections::HashMap, time::Duration};
+use tokio::time::Instant;
+
+/// The rate of a token bucket: `calls` per `period` on average, and up to
+/// `burst` at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate {
+    pub calls: u32,
+    pub period: Duration,
+    pub burst: u32,
+}
+
+impl Rate {
+    /// `calls` per second, with a burst of `calls`.
+    pub fn per_second(calls: u32) -> Self {
+        Self::per(calls, Duration::from_secs(1))
+    }
+
+    /// `calls` per minute, with a burst of `calls`.
+    pub fn per_minute(calls: u32) -> Self {
+        Self::per(calls, Duration::from_secs(60))
+    }
+
+    /// `calls` per `period`, with a burst of `calls`.
+    pub fn per(calls: u32, period: Duration) -> Self {
+        Self {
+            calls,
+            period,
+            burst: calls,
+        }
+    }
+
+    /// Allow up to `burst` calls at once.
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.burst = burst;
+
+        self
+    }
+}
+
+/// Limit the rate of the `Call`s of each Charge Point, see
+/// [`ServerConfig::rate_limit`][crate::ServerConfig::rate_limit].
+///
+/// A `Call` above the rate is answered with a `SecurityError` without
+/// reaching the handler. A Charge Point that keeps calling above the rate
+/// is disconnected.
+///
+/// ```
+/// # use ocppx_server::{Rate, RateLimit};
+/// let rate_limit = RateLimit {
+///     global: Some(Rate::per_second(10)),
+///     close_after: Some(100),
+///     ..Default::default()
+/// }
+/// .action("MeterValues", Rate::per_minute(6).burst(2));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RateLimit {
+    /// The rate of all the `Call`s of a Charge Point.
+    pub global: Option<Rate>,
+    /// The rates of some actions, on top of `global`.
+    pub actions: HashMap<String, Rate>,
+    /// Close the connection once this many `Call`s in a row have been
+    /// rejected.
+    pub close_after: Option<u32>,
+}
+
+impl RateLimit {
+    /// Limit the rate of the `action` `Call`s.
+    pub fn action(mut self, action: impl Into<String>, rate: Rate) -> Self {
+        self.actions.insert(action.into(), rate);
+
+        self
+    }
+}
+
+/// What to do with a `Call`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Verdict {
+    Accept,
+    Reject,
+    /// Reject, and close the connection.
+    Close,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    rate: Rate,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl Bucket {
+    fn new(rate: Rate, now: Instant) -> Self {
+        Self {
+            rate,
+            tokens: rate.burst.into(),
+            refilled_at: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+
+        self.tokens = (self.tokens
+            + elapsed.as_secs_f64() * f64::from(self.rate.calls) / self.rate.period.as_secs_f64())
+        .min(self.rate.burst.into());
+        self.refilled_at = now;
+    }
+}
+
+/// The token buckets of a connection.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    global: Option<Bucket>,
+    actions: HashMap<String, Bucket>,
+    rates: HashMap<String, Rate>,
+    close_after: Option<u32>,
+    rejected_in_a_row: u32,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(rate_limit: &RateLimit, now: Instant) -> Self {
+        Self {
+            global: rate_limit.global.map(|rate| Bucket::new(rate, now)),
+            actions: HashMap::new(),
+            rates: rate_limit.actions.clone(),
+            close_after: rate_limit.close_after,
+            rejected_in_a_row: 0,
+        }
+    }
+
+    /// Take a token for an `action` `Call` received at `now`.
+    pub(crate) fn check(&mut self, action: &str, now: Instant) -> Verdict {
+        let action_bucket = match self.rates.get(action) {
+            Some(rate) => Some(
+                self.actions
+                    .entry(action.to_owned())
+                    .or_insert_with(|| Bucket::new(*rate, now)),
+            ),
+            None => None,
+        };
+
+        let mut accepted = true;
+
+        for bucket in self.global.iter_mut().chain(action_bucket) {
+            bucket.refill(now);
+            accepted &= bucket.tokens >= 1.0;
+        }
+
+        if accepted {
+            for bucket in self.global.iter_mut().chain(self.actions.get_mut(action)) {
+                bucket.tokens -= 1.0;
+            }
+
+            self.rejected_in_a_row = 0;
+
+            return Verdict::Accept;
+        }
+
+        self.rejected_in_a_row += 1;
+
+        match self.close_after {
+            Some(close_after) if self.rejected_in_a_row >= close_after => Verdict::Close,
+            _ => Verdict::Reject,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter() {
+        let now = Instant::now();
+        let mut limiter = RateLimiter::new(
+            &RateLimit {
+                global: Some(Rate::per_second(10)),
+                close_after: Some(3),
+                ..Default::default()
+            }
+            .action("MeterValues", Rate::per_minute(6).burst(2)),
+            now,
+        );
+
+        assert_eq!(limiter.check("MeterValues", now), Verdict::Accept);
+        assert_eq!(limiter.check("MeterValues", now), Verdict::Accept);
+        assert_eq!(limiter.check("MeterValues", now), Verdict::Reject);
+
+        // The other actions are only limited by the global rate, and reset
+        // the rejections in a row.
+        assert_eq!(limiter.check("Heartbeat", now), Verdict::Accept);
+
+        // A `MeterValues` token every 10 seconds.
+        let later = now + Duration::from_secs(10);
+        assert_eq!(limiter.check("MeterValues", later), Verdict::Accept);
+
+        for _ in 0..9 {
+            assert_eq!(limiter.check("Heartbeat", later), Verdict::Accept);
+        }
+
+        // The global bucket is empty.
+        assert_eq!(limiter.check("Heartbeat", later), Verdict::Reject);
+        assert_eq!(limiter.check("Heartbeat", later), Verdict::Reject);
+        assert_eq!(limiter.check("Heartbeat", later), Verdict::Close);
+    }
+}
@@ -0,0 +1,236 @@
+use chrono::Utc;
+use ocppx_rpc::CallResult;
+use ocppx_types::v1_6::{BootNotificationResponse, BootNotificationStatus};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    time::Duration,
+};
+use tokio::time::Instant;
+
+/// The registration of a Charge Point, as told by the last response to its
+/// `BootNotification`.
+#[derive(Debug)]
+struct Registration {
+    status: BootNotificationStatus,
+    /// The `BootNotification`s received before are answered again without
+    /// reaching the handler, when the Charge Point is not accepted.
+    retry_at: Option<Instant>,
+    /// The actions requested with a `TriggerMessage`, that the Charge Point
+    /// may send although it is not accepted, once each.
+    triggered: HashSet<String>,
+}
+
+/// The registration status of the Charge Points, see
+/// [`Server::registrations`].
+///
+/// The status of a Charge Point is the one of the last response of the
+/// handler to its `BootNotification`. Until it is `Accepted`:
+///
+/// * a `BootNotification` sent before the end of the interval of the
+///   previous response, and not requested with a `TriggerMessage`, gets the
+///   same status, with the time left, without reaching the handler,
+/// * the other `Call`s are answered with a `SecurityError`, except the ones
+///   requested with a `TriggerMessage` by the Central System.
+///
+/// A Charge Point [held][Registrations::hold] while it is being provisioned
+/// is answered `Pending` even if the handler accepts it, until it is
+/// [accepted][Registrations::accept]: its next `BootNotification` then
+/// reaches the handler, e.g. requested with a `TriggerMessage`.
+///
+/// The registrations are kept when the Charge Points disconnect.
+///
+/// [`Server::registrations`]: crate::Server::registrations
+#[derive(Debug, Default)]
+pub struct Registrations {
+    registrations: Mutex<HashMap<String, Registration>>,
+    /// The Charge Points being provisioned, with the interval of their
+    /// `Pending` responses.
+    held: Mutex<HashMap<String, Duration>>,
+}
+
+impl Registrations {
+    /// The registration status of `charge_point_id`, if it has booted.
+    pub fn status(&self, charge_point_id: &str) -> Option<BootNotificationStatus> {
+        self.registrations
+            .lock()
+            .unwrap()
+            .get(charge_point_id)
+            .map(|registration| registration.status)
+    }
+
+    /// Keep `charge_point_id` `Pending` while it is being provisioned, and
+    /// tell it to boot again after `retry_interval`.
+    pub fn hold(&self, charge_point_id: &str, retry_interval: Duration) {
+        self.held
+            .lock()
+            .unwrap()
+            .insert(charge_point_id.to_owned(), retry_interval);
+    }
+
+    /// Whether `charge_point_id` is held, see [`Registrations::hold`].
+    pub fn is_held(&self, charge_point_id: &str) -> bool {
+        self.held.lock().unwrap().contains_key(charge_point_id)
+    }
+
+    /// Let `charge_point_id` be accepted, once it is provisioned: its next
+    /// `BootNotification` reaches the handler, whenever it is sent. Return
+    /// whether it was held.
+    pub fn accept(&self, charge_point_id: &str) -> bool {
+        if let Some(registration) = self.registrations.lock().unwrap().get_mut(charge_point_id) {
+            registration.retry_at = None;
+        }
+
+        self.held.lock().unwrap().remove(charge_point_id).is_some()
+    }
+
+    /// The response to the `BootNotification` `unique_id` of
+    /// `charge_point_id`, received at `now`, if it is sent too early.
+    pub(crate) fn throttled_boot(
+        &self,
+        charge_point_id: &str,
+        unique_id: &str,
+        now: Instant,
+    ) -> Option<CallResult> {
+        let mut registrations = self.registrations.lock().unwrap();
+        let registration = registrations.get_mut(charge_point_id)?;
+        let retry_at = registration.retry_at.filter(|retry_at| *retry_at > now)?;
+
+        if registration.triggered.remove("BootNotification") {
+            return None;
+        }
+
+        let response = BootNotificationResponse::builder()
+            .status(registration.status)
+            .current_time(Utc::now())
+            .interval(retry_at.duration_since(now).as_secs_f64().ceil() as i32)
+            .build();
+
+        CallResult::new(unique_id, &response).ok()
+    }
+
+    /// Whether `charge_point_id` may send a `Call` of `action`.
+    pub(crate) fn allows(&self, charge_point_id: &str, action: &str) -> bool {
+        if action == "BootNotification" {
+            return true;
+        }
+
+        match self.registrations.lock().unwrap().get_mut(charge_point_id) {
+            Some(registration) if registration.status != BootNotificationStatus::Accepted => {
+                registration.triggered.remove(action)
+            }
+            _ => true,
+        }
+    }
+
+    /// `charge_point_id` has been sent a `TriggerMessage` for
+    /// `requested_message`.
+    pub(crate) fn triggered(&self, charge_point_id: &str, requested_message: &str) {
+        if let Some(registration) = self.registrations.lock().unwrap().get_mut(charge_point_id) {
+            if registration.status != BootNotificationStatus::Accepted {
+                registration.triggered.insert(requested_message.to_owned());
+            }
+        }
+    }
+
+    /// `response`, the response of the handler to a `BootNotification` of
+    /// `charge_point_id` received at `now`, is about to be sent. It is
+    /// turned to `Pending` if the Charge Point is held.
+    pub(crate) fn booted(&self, charge_point_id: &str, response: &mut CallResult, now: Instant) {
+        let Ok(mut boot) =
+            serde_json::from_value::<BootNotificationResponse>(response.payload.clone())
+        else {
+            return;
+        };
+
+        if let Some(retry_interval) = self.held.lock().unwrap().get(charge_point_id) {
+            if boot.status == BootNotificationStatus::Accepted {
+                boot.status = BootNotificationStatus::Pending;
+                boot.interval = retry_interval.as_secs().try_into().unwrap_or(i32::MAX);
+
+                if let Ok(payload) = serde_json::to_value(&boot) {
+                    response.payload = payload;
+                }
+            }
+        }
+
+        let retry_at = (boot.status != BootNotificationStatus::Accepted)
+            .then(|| now + Duration::from_secs(boot.interval.max(0) as u64));
+
+        self.registrations.lock().unwrap().insert(
+            charge_point_id.to_owned(),
+            Registration {
+                status: boot.status,
+                retry_at,
+                triggered: HashSet::new(),
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_registrations() {
+        let registrations = Registrations::default();
+        let now = Instant::now();
+        let accepted = || {
+            CallResult::new(
+                "1",
+                &json!({
+                    "status": "Accepted",
+                    "currentTime": "2013-02-01T20:53:32.486Z",
+                    "interval": 300,
+                }),
+            )
+            .unwrap()
+        };
+
+        // Before its first boot, a Charge Point is not restricted.
+        assert!(registrations.allows("CP001", "Heartbeat"));
+
+        registrations.hold("CP001", Duration::from_secs(60));
+
+        let mut response = accepted();
+        registrations.booted("CP001", &mut response, now);
+        assert_eq!(response.payload["status"], "Pending");
+        assert_eq!(response.payload["interval"], 60);
+        assert_eq!(
+            registrations.status("CP001"),
+            Some(BootNotificationStatus::Pending)
+        );
+
+        // Booting again too early is answered with the time left.
+        let throttled = registrations
+            .throttled_boot("CP001", "2", now + Duration::from_millis(20_500))
+            .unwrap();
+        assert_eq!(throttled.payload["status"], "Pending");
+        assert_eq!(throttled.payload["interval"], 40);
+        assert!(registrations
+            .throttled_boot("CP001", "3", now + Duration::from_secs(60))
+            .is_none());
+
+        registrations.triggered("CP001", "BootNotification");
+        assert!(registrations.throttled_boot("CP001", "4", now).is_none());
+        assert!(registrations.throttled_boot("CP001", "5", now).is_some());
+
+        // Only the triggered messages are allowed, once.
+        assert!(!registrations.allows("CP001", "StatusNotification"));
+        registrations.triggered("CP001", "StatusNotification");
+        assert!(registrations.allows("CP001", "StatusNotification"));
+        assert!(!registrations.allows("CP001", "StatusNotification"));
+        assert!(registrations.allows("CP001", "BootNotification"));
+
+        // Once provisioned, the next boot is accepted.
+        assert!(registrations.accept("CP001"));
+        assert!(registrations.throttled_boot("CP001", "6", now).is_none());
+
+        let mut response = accepted();
+        registrations.booted("CP001", &mut response, now);
+        assert_eq!(response, accepted());
+        assert!(registrations.allows("CP001", "StatusNotification"));
+    }
+}
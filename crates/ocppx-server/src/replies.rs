@@ -0,0 +1,269 @@
+use ocppx_rpc::Call;
+use ring::digest::{digest, SHA256, SHA256_OUTPUT_LEN};
+use std::collections::VecDeque;
+
+/// What is known about a `Call` received on a connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Reply {
+    /// The handler has not answered yet.
+    InProgress,
+    /// The frame of the response, as it has been sent.
+    Answered(String),
+}
+
+/// What [`ReplyCache::received`] knows about a `Call`.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Lookup<'a> {
+    /// The `Call` is new: it is to be handled, then answered with its
+    /// ticket.
+    New(Ticket),
+    /// The `Call` has been received already.
+    Again(&'a Reply),
+}
+
+/// Identifies a `Call` remembered by a [`ReplyCache`], so that its response
+/// is not remembered for another `Call` reusing its unique ID meanwhile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Ticket(u64);
+
+/// What tells a `Call` apart from another one with the same unique ID:
+/// its action, and the SHA-256 of its payload rather than the payload
+/// itself, which can be large.
+#[derive(PartialEq, Eq)]
+struct Fingerprint {
+    action: String,
+    payload: [u8; SHA256_OUTPUT_LEN],
+}
+
+impl Fingerprint {
+    fn of(call: &Call) -> Self {
+        let payload = digest(&SHA256, call.payload.to_string().as_bytes());
+
+        Self {
+            action: call.action.clone(),
+            payload: payload
+                .as_ref()
+                .try_into()
+                .expect("a SHA-256 digest is 32 bytes long"),
+        }
+    }
+}
+
+/// A `Call` received on a connection, with its reply.
+struct Received {
+    ticket: Ticket,
+    unique_id: String,
+    fingerprint: Fingerprint,
+    reply: Reply,
+}
+
+/// The replies to the last `Call`s of a connection, least recently used
+/// first, see [`ServerConfig::replayed_responses`].
+///
+/// A `Call` retransmitted with the same unique ID, action and payload,
+/// e.g. because the Charge Point has not received the response in time,
+/// gets the same response again instead of being handled twice. A
+/// different `Call` reusing the unique ID is handled.
+///
+/// [`ServerConfig::replayed_responses`]: crate::ServerConfig::replayed_responses
+pub(crate) struct ReplyCache {
+    capacity: usize,
+    replies: VecDeque<Received>,
+    next_ticket: u64,
+}
+
+impl ReplyCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            replies: VecDeque::with_capacity(capacity),
+            next_ticket: 0,
+        }
+    }
+
+    /// The reply to `call` if it has been received already. Otherwise, it
+    /// is remembered as in progress, replacing a different `Call` with the
+    /// same unique ID.
+    pub(crate) fn received(&mut self, call: &Call) -> Lookup<'_> {
+        let ticket = Ticket(self.next_ticket);
+        self.next_ticket += 1;
+
+        if self.capacity == 0 {
+            return Lookup::New(ticket);
+        }
+
+        let fingerprint = Fingerprint::of(call);
+
+        match self.take(|received| received.unique_id == call.unique_id) {
+            Some(received) if received.fingerprint == fingerprint => {
+                self.replies.push_back(received);
+
+                Lookup::Again(&self.replies.back().unwrap().reply)
+            }
+            _ => {
+                self.insert(Received {
+                    ticket,
+                    unique_id: call.unique_id.clone(),
+                    fingerprint,
+                    reply: Reply::InProgress,
+                });
+
+                Lookup::New(ticket)
+            }
+        }
+    }
+
+    /// Remember `frame`, the response to the `Call` of `ticket`, if it is
+    /// still remembered, and has not been replaced by another `Call`
+    /// reusing its unique ID.
+    pub(crate) fn answered(&mut self, ticket: Ticket, frame: String) {
+        if let Some(mut received) = self.take(|received| received.ticket == ticket) {
+            received.reply = Reply::Answered(frame);
+            self.replies.push_back(received);
+        }
+    }
+
+    /// Forget the `Call` of `ticket`, e.g. when it has been rejected before
+    /// reaching the handler: it is handled if it is received again.
+    pub(crate) fn forget(&mut self, ticket: Ticket) {
+        self.take(|received| received.ticket == ticket);
+    }
+
+    fn take(&mut self, predicate: impl Fn(&Received) -> bool) -> Option<Received> {
+        let position = self.replies.iter().position(predicate)?;
+
+        self.replies.remove(position)
+    }
+
+    fn insert(&mut self, received: Received) {
+        if self.replies.len() == self.capacity {
+            self.replies.pop_front();
+        }
+
+        self.replies.push_back(received);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_reply_cache() {
+        let call = |unique_id: &str| Call {
+            unique_id: unique_id.to_owned(),
+            action: "Heartbeat".to_owned(),
+            payload: json!({}),
+        };
+        let new = |lookup: Lookup<'_>| match lookup {
+            Lookup::New(ticket) => ticket,
+            Lookup::Again(reply) => panic!("`{reply:?}` instead of a new `Call`"),
+        };
+        let mut replies = ReplyCache::new(2);
+
+        let ticket = new(replies.received(&call("1")));
+        assert_eq!(
+            replies.received(&call("1")),
+            Lookup::Again(&Reply::InProgress)
+        );
+
+        replies.answered(ticket, "[3,\"1\",{}]".to_owned());
+        assert_eq!(
+            replies.received(&call("1")),
+            Lookup::Again(&Reply::Answered("[3,\"1\",{}]".to_owned()))
+        );
+
+        // `2` is the least recently used, it is evicted first.
+        new(replies.received(&call("2")));
+        assert!(matches!(replies.received(&call("1")), Lookup::Again(_)));
+        let ticket = new(replies.received(&call("3")));
+        new(replies.received(&call("2")));
+        assert_eq!(
+            replies.received(&call("3")),
+            Lookup::Again(&Reply::InProgress)
+        );
+
+        replies.forget(ticket);
+        new(replies.received(&call("3")));
+
+        // Another `Call` reusing the unique ID is not a retransmission.
+        let authorize = Call {
+            unique_id: "3".to_owned(),
+            action: "Authorize".to_owned(),
+            payload: json!({"idTag": "ABC"}),
+        };
+        new(replies.received(&authorize));
+        assert_eq!(
+            replies.received(&authorize),
+            Lookup::Again(&Reply::InProgress)
+        );
+
+        let other_tag = Call {
+            payload: json!({"idTag": "DEF"}),
+            ..authorize
+        };
+        new(replies.received(&other_tag));
+
+        let mut disabled = ReplyCache::new(0);
+        new(disabled.received(&call("1")));
+        new(disabled.received(&call("1")));
+    }
+
+    #[test]
+    fn test_reply_cache_changed_payload() {
+        let call = |payload| Call {
+            unique_id: "1".to_owned(),
+            action: "DataTransfer".to_owned(),
+            payload,
+        };
+        let mut replies = ReplyCache::new(2);
+
+        let Lookup::New(ticket) = replies.received(&call(json!({"vendorId": "A"}))) else {
+            panic!("the `Call` is new");
+        };
+        replies.answered(ticket, "[3,\"1\",{}]".to_owned());
+
+        // Another payload is handled, and does not get the response to the
+        // first one.
+        assert!(matches!(
+            replies.received(&call(json!({"vendorId": "B"}))),
+            Lookup::New(_)
+        ));
+        assert_eq!(
+            replies.received(&call(json!({"vendorId": "B"}))),
+            Lookup::Again(&Reply::InProgress)
+        );
+    }
+
+    #[test]
+    fn test_reply_cache_reused_unique_id() {
+        let call = |id_tag: &str| Call {
+            unique_id: "3".to_owned(),
+            action: "Authorize".to_owned(),
+            payload: json!({ "idTag": id_tag }),
+        };
+        let mut replies = ReplyCache::new(2);
+
+        // `A` is handled, when `B` reuses its unique ID.
+        let Lookup::New(a) = replies.received(&call("A")) else {
+            panic!("`A` is new");
+        };
+        let Lookup::New(b) = replies.received(&call("B")) else {
+            panic!("`B` is new");
+        };
+
+        // The response to `A` is not remembered for `B`.
+        replies.answered(a, "A".to_owned());
+        assert_eq!(
+            replies.received(&call("B")),
+            Lookup::Again(&Reply::InProgress)
+        );
+
+        replies.answered(b, "B".to_owned());
+        assert_eq!(
+            replies.received(&call("B")),
+            Lookup::Again(&Reply::Answered("B".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,2773 @@
+use crate::{
+    events::{self, DisconnectReason, Event, EventBus},
+    head::{read_request_head, Prefixed},
+    profile,
+    rate_limit::{RateLimiter, Verdict},
+    replies::{Lookup, Reply, ReplyCache},
+    session::Outgoing,
+    AuthProvider, BroadcastResults, ChargePointGroups, ChargePointHandle, ChargePointProfiles,
+    Credentials, CsmsHandler, Error, OcppVersion, Registrations, Result, ServerConfig,
+    SessionRegistry, Target,
+};
+use futures_util::{future::join_all, SinkExt, StreamExt};
+use ocppx_rpc::{
+    Call, CallError, CallWindow, Compressed, Compression, ConnectionEvent, ErrorCode,
+    KeepAliveAction, KeepAliveTimer, Message, PendingCallError, PendingCalls, Span, Transport,
+};
+use ocppx_types::OcppRequest;
+use percent_encoding::percent_decode_str;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{borrow::Cow, collections::VecDeque, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt},
+    net::{TcpListener, ToSocketAddrs},
+    sync::{broadcast, mpsc, watch, Semaphore},
+    time::{sleep_until, timeout_at, Instant},
+};
+use tokio_tungstenite::{
+    accept_hdr_async_with_config,
+    tungstenite::{
+        handshake::server::{Callback, ErrorResponse, Request, Response},
+        http::{
+            header::{SEC_WEBSOCKET_EXTENSIONS, SEC_WEBSOCKET_PROTOCOL},
+            HeaderValue, StatusCode,
+        },
+        protocol::{frame::coding::CloseCode, CloseFrame, WebSocketConfig},
+        Message as Frame,
+    },
+};
+
+/// The WebSocket subprotocol of OCPP 1.6, the version negotiated with the
+/// Charge Points by default, see [`ServerConfig::versions`].
+pub const SUBPROTOCOL: &str = "ocpp1.6";
+
+/// Capacity of the channel of the [`ConnectionEvent`]s.
+const EVENTS_CAPACITY: usize = 256;
+
+/// Time to wait before accepting again after an error, doubled at each
+/// consecutive error up to [`MAX_ACCEPT_BACKOFF`].
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(10);
+
+/// Longest time to wait before accepting again after an error.
+const MAX_ACCEPT_BACKOFF: Duration = Duration::from_secs(1);
+
+struct Inner<H, A> {
+    handler: H,
+    auth_provider: A,
+    config: ServerConfig,
+    sessions: SessionRegistry,
+    groups: ChargePointGroups,
+    profiles: ChargePointProfiles,
+    registrations: Registrations,
+    events: EventBus,
+    connection_events: broadcast::Sender<ConnectionEvent>,
+    /// Permits to run the handler, shared by all the connections.
+    handlers: Arc<Semaphore>,
+    /// The deadline of the shutdown, once [`Server::shutdown`] is called.
+    shutdown: watch::Sender<Option<Instant>>,
+    /// Number of running connections.
+    running_connections: watch::Sender<usize>,
+}
+
+impl<H, A> Inner<H, A> {
+    /// Capture `frame` with the recorder, if any, through the interceptors.
+    fn capture(&self, direction: ocppx_rpc::Direction, charge_point_id: &str, frame: &str) {
+        if let Some(recorder) = &self.config.recorder {
+            let frame = self
+                .config
+                .interceptors
+                .captured(charge_point_id, direction, frame);
+            recorder.record(direction, charge_point_id, &frame);
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl<H, A> Inner<H, A> {
+    /// Record something in the metrics, if they are enabled.
+    fn record<F>(&self, record: F)
+    where
+        F: FnOnce(&ocppx_rpc::Metrics),
+    {
+        if let Some(metrics) = &self.config.metrics {
+            record(metrics);
+        }
+    }
+}
+
+#[cfg(feature = "audit")]
+impl<H, A> Inner<H, A> {
+    /// Record `event` in the audit log, if any.
+    fn audit(&self, event: crate::AuditEvent) {
+        if let Some(audit_log) = &self.config.audit_log {
+            if let Err(error) = audit_log.record(event) {
+                log::error!(error:% = error; "cannot record an event in the audit log");
+            }
+        }
+    }
+}
+
+/// A Central System, accepting connections from Charge Points.
+///
+/// The server is cheap to clone: clones share the same connections and
+/// handler.
+///
+/// The Charge Points are authenticated by an [`AuthProvider`] before their
+/// connection is upgraded to WebSocket; by default, all of them are
+/// accepted.
+///
+/// Each connection is a task of the Tokio runtime, idle until a frame is
+/// received or has to be sent, so that a server holds tens of thousands of
+/// connections. The queues between the connections and the handler are
+/// bounded, see [`ServerConfig`]: a slow handler, or a slow Charge Point,
+/// pushes back instead of buffering without limit.
+pub struct Server<H, A = ()> {
+    inner: Arc<Inner<H, A>>,
+}
+
+impl<H, A> Clone for Server<H, A> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<H> Server<H>
+where
+    H: CsmsHandler,
+{
+    pub fn new(handler: H) -> Self {
+        Self::with_config(handler, ServerConfig::default())
+    }
+
+    pub fn with_config(handler: H, config: ServerConfig) -> Self {
+        Self::with_auth_provider(handler, config, ())
+    }
+}
+
+impl<H, A> Server<H, A>
+where
+    H: CsmsHandler,
+    A: AuthProvider,
+{
+    /// Create a server authenticating the Charge Points with
+    /// `auth_provider`.
+    pub fn with_auth_provider(handler: H, config: ServerConfig, auth_provider: A) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                handler,
+                auth_provider,
+                handlers: Arc::new(Semaphore::new(config.max_concurrent_handlers.max(1))),
+                shutdown: watch::Sender::new(None),
+                running_connections: watch::Sender::new(0),
+                config,
+                sessions: SessionRegistry::new(),
+                groups: ChargePointGroups::default(),
+                profiles: ChargePointProfiles::default(),
+                registrations: Registrations::default(),
+                events: EventBus::default(),
+                connection_events: broadcast::Sender::new(EVENTS_CAPACITY),
+            }),
+        }
+    }
+
+    /// Listen on `address` and accept connections forever.
+    pub async fn listen<T>(&self, address: T) -> Result<()>
+    where
+        T: ToSocketAddrs,
+    {
+        self.serve(TcpListener::bind(address).await?).await
+    }
+
+    /// Accept connections on `listener`, until the server is shut down.
+    ///
+    /// The errors of a single connection, or the lack of file descriptors,
+    /// do not stop the server: it accepts again, a bit later for the
+    /// latter. It fails only when `listener` itself is not usable anymore.
+    pub async fn serve(&self, listener: TcpListener) -> Result<()> {
+        let mut shutdown = self.inner.shutdown.subscribe();
+        let mut backoff = ACCEPT_BACKOFF;
+
+        loop {
+            let accepted = tokio::select! {
+                accepted = listener.accept() => accepted,
+                _ = shutdown.wait_for(Option::is_some) => return Ok(()),
+            };
+            let stream = match accepted {
+                Ok((stream, _)) => {
+                    backoff = ACCEPT_BACKOFF;
+
+                    stream
+                }
+
+                // The connection is gone before being accepted.
+                Err(error) if is_connection_error(&error) => {
+                    log::debug!(error:% = error; "connection lost before being accepted");
+
+                    continue;
+                }
+
+                Err(error) if error.kind() == std::io::ErrorKind::InvalidInput => {
+                    return Err(error.into())
+                }
+
+                // E.g. too many open files: the connections in progress
+                // have to end first.
+                Err(error) => {
+                    log::warn!(error:% = error, backoff:? = backoff; "cannot accept a connection");
+
+                    tokio::select! {
+                        _ = sleep_until(Instant::now() + backoff) => {}
+                        _ = shutdown.wait_for(Option::is_some) => return Ok(()),
+                    }
+
+                    backoff = (backoff * 2).min(MAX_ACCEPT_BACKOFF);
+
+                    continue;
+                }
+            };
+            // A client which never finishes its handshake does not hold the
+            // connection forever.
+            let handshake_deadline = Instant::now() + self.inner.config.handshake_timeout;
+
+            #[cfg(feature = "tls")]
+            if let Some(tls) = &self.inner.config.tls {
+                let acceptor = tokio_rustls::TlsAcceptor::from(tls.config.clone());
+                let inner = self.inner.clone();
+
+                tokio::spawn(async move {
+                    // Failed TLS handshakes only concern this connection.
+                    if let Ok(Ok(stream)) =
+                        timeout_at(handshake_deadline, acceptor.accept(stream)).await
+                    {
+                        let client_identities = crate::tls::client_identities(stream.get_ref().1);
+
+                        run_connection(inner, stream, handshake_deadline, client_identities).await;
+                    }
+                });
+
+                continue;
+            }
+
+            tokio::spawn(run_connection(
+                self.inner.clone(),
+                stream,
+                handshake_deadline,
+                None,
+            ));
+        }
+    }
+
+    /// Serve the Charge Point `charge_point_id` over a [`Transport`]
+    /// opened by the caller, e.g. one end of a
+    /// [`MemoryTransport`][ocppx_rpc::MemoryTransport] whose other end is
+    /// given to the Charge Point. Returns once the connection is closed.
+    ///
+    /// The Charge Point is not authenticated: the caller vouches for its
+    /// identity. Nothing is negotiated either: it speaks the highest of
+    /// [`ServerConfig::versions`].
+    pub async fn serve_transport<T>(&self, charge_point_id: &str, transport: T)
+    where
+        T: Transport,
+    {
+        let version = self
+            .inner
+            .config
+            .versions
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or(OcppVersion::V1_6);
+
+        run_session(
+            self.inner.clone(),
+            charge_point_id.to_owned(),
+            version,
+            transport,
+        )
+        .await;
+    }
+
+    /// Shut the server down, e.g. before a restart:
+    ///
+    /// 1. the new connections are not accepted anymore, and
+    ///    [`Server::serve`] returns,
+    /// 2. the `Call`s in progress are waited for, up to `timeout`, and the
+    ///    new ones are answered with an `InternalError`,
+    /// 3. the connections are closed with the `1001` (going away) code,
+    /// 4. the `Call`s sent with [`Server::call`] still waiting for their
+    ///    response fail with [`Error::Shutdown`].
+    ///
+    /// It returns once all the connections are closed.
+    pub async fn shutdown(&self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        self.inner.shutdown.send_if_modified(|shutdown| {
+            // A second shutdown does not extend the deadline.
+            shutdown.get_or_insert(deadline);
+
+            true
+        });
+        log::info!("shutting down");
+
+        let _ = self
+            .inner
+            .running_connections
+            .subscribe()
+            .wait_for(|running_connections| *running_connections == 0)
+            .await;
+    }
+
+    /// The identities of the currently connected Charge Points.
+    pub fn connected_charge_points(&self) -> Vec<String> {
+        self.inner.sessions.charge_point_ids()
+    }
+
+    /// The sessions of the connected Charge Points, to follow or to close
+    /// them.
+    pub fn sessions(&self) -> &SessionRegistry {
+        &self.inner.sessions
+    }
+
+    /// The events of the Charge Points, e.g. their connections or their
+    /// transactions, for any number of subscribers.
+    pub fn events(&self) -> &EventBus {
+        &self.inner.events
+    }
+
+    /// The events of the transports, e.g. [`ConnectionEvent::Stale`] when a
+    /// Charge Point stops answering the pings of
+    /// [`ServerConfig::keep_alive`], from now on.
+    pub fn connection_events(&self) -> broadcast::Receiver<ConnectionEvent> {
+        self.inner.connection_events.subscribe()
+    }
+
+    /// A handle on the Charge Point `charge_point_id`, to send it typed
+    /// requests, connected or not yet.
+    pub fn charge_point(&self, charge_point_id: &str) -> ChargePointHandle<H, A> {
+        ChargePointHandle::new(self.clone(), charge_point_id.to_owned())
+    }
+
+    /// The groups of Charge Points, e.g. by site, for [`Target::Group`].
+    pub fn groups(&self) -> &ChargePointGroups {
+        &self.inner.groups
+    }
+
+    /// The configuration profiles of the Charge Points, applied when they
+    /// boot.
+    pub fn profiles(&self) -> &ChargePointProfiles {
+        &self.inner.profiles
+    }
+
+    /// The registration status of the Charge Points, set by the responses
+    /// to their `BootNotification`s.
+    pub fn registrations(&self) -> &Registrations {
+        &self.inner.registrations
+    }
+
+    /// Send `request` to all the connected Charge Points, see
+    /// [`Server::call_many`].
+    pub async fn broadcast<R>(&self, request: R) -> BroadcastResults<R::Response>
+    where
+        R: OcppRequest + Clone,
+    {
+        self.call_many(Target::All, request).await
+    }
+
+    /// Send `request` to the Charge Points of `target`, concurrently, and
+    /// wait for all their typed responses, or failures.
+    ///
+    /// The members of a group which are not connected fail with
+    /// [`CallFailure::NotConnected`].
+    ///
+    /// [`CallFailure::NotConnected`]: crate::CallFailure::NotConnected
+    pub async fn call_many<R>(&self, target: Target, request: R) -> BroadcastResults<R::Response>
+    where
+        R: OcppRequest + Clone,
+    {
+        let charge_point_ids = match target {
+            Target::All => self.connected_charge_points(),
+            Target::Group(group) => self.inner.groups.members(&group),
+            Target::ChargePoints(charge_point_ids) => charge_point_ids,
+        };
+
+        let calls = charge_point_ids.into_iter().map(|charge_point_id| {
+            let charge_point = self.charge_point(&charge_point_id);
+            let request = request.clone();
+
+            async move { (charge_point_id, charge_point.call(request).await) }
+        });
+
+        BroadcastResults {
+            results: join_all(calls).await.into_iter().collect(),
+        }
+    }
+
+    /// Send a typed request to the Charge Point `charge_point_id`, and
+    /// wait for its typed response.
+    pub async fn send<R>(&self, charge_point_id: &str, request: R) -> Result<R::Response>
+    where
+        R: OcppRequest,
+    {
+        self.call(charge_point_id, R::ACTION, &request).await
+    }
+
+    /// Send a `Call` with a typed payload to the Charge Point
+    /// `charge_point_id`, and wait for its typed response.
+    ///
+    /// The `Call` is sent once the previous outstanding `Call`s to this
+    /// Charge Point, if any, have been answered or have timed out.
+    pub async fn call<P, R>(&self, charge_point_id: &str, action: &str, payload: &P) -> Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        if self.inner.shutdown.borrow().is_some() {
+            return Err(Error::Shutdown);
+        }
+
+        let (outgoing, pending_calls) = self
+            .inner
+            .sessions
+            .with_session(charge_point_id, |session| {
+                (session.outgoing.clone(), session.pending_calls.clone())
+            })
+            .ok_or_else(|| Error::ChargePointNotConnected(charge_point_id.to_owned()))?;
+
+        let pending_call = pending_calls
+            .register_unique(&self.inner.config.message_ids)
+            .await?;
+        let unique_id = pending_call.unique_id().to_owned();
+        let call = Call::new(unique_id.clone(), action, payload)?;
+
+        // The Charge Point may send the message requested, even if it is
+        // not accepted.
+        if action == "TriggerMessage" {
+            if let Some(requested_message) = call
+                .payload
+                .get("requestedMessage")
+                .and_then(serde_json::Value::as_str)
+            {
+                self.inner
+                    .registrations
+                    .triggered(charge_point_id, requested_message);
+            }
+        }
+
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        #[cfg(feature = "metrics")]
+        self.inner
+            .record(|metrics| metrics.record_message(ocppx_rpc::Direction::Outgoing, action));
+
+        #[cfg(feature = "audit")]
+        let audited_request = self
+            .inner
+            .config
+            .audit_log
+            .is_some()
+            .then(|| call.payload.clone());
+
+        let mut message = Message::from(call);
+        self.inner
+            .config
+            .interceptors
+            .outgoing(charge_point_id, action, &mut message);
+
+        outgoing
+            .send(Outgoing {
+                frame: Frame::Text(message.to_string()),
+                call: Some(unique_id.clone()),
+                answers: None,
+            })
+            .await
+            .map_err(|_| Error::ConnectionClosed)?;
+        log::debug!(
+            charge_point_id = charge_point_id,
+            unique_id = unique_id.as_str(),
+            action = action;
+            "Call sent"
+        );
+
+        let response = Span::call(charge_point_id, &unique_id, action)
+            .instrument(pending_call.wait())
+            .await;
+
+        #[cfg(feature = "audit")]
+        if let Some(request) = audited_request {
+            if let Some(event) = crate::AuditEvent::of_call(
+                charge_point_id,
+                action,
+                &request,
+                response.as_ref().ok(),
+            ) {
+                self.inner.audit(event);
+            }
+        }
+
+        let mut response = response.map_err(|error| match error {
+            PendingCallError::Timeout(timeout) => Error::Timeout {
+                action: action.to_owned(),
+                timeout,
+            },
+            PendingCallError::Cancelled if self.inner.shutdown.borrow().is_some() => {
+                Error::Shutdown
+            }
+            PendingCallError::Cancelled => Error::ConnectionClosed,
+        })?;
+        self.inner
+            .config
+            .interceptors
+            .incoming(charge_point_id, action, &mut response);
+
+        #[cfg(feature = "metrics")]
+        self.inner.record(|metrics| {
+            metrics.record_call_latency(action, started.elapsed());
+
+            if let Message::CallError(call_error) = &response {
+                metrics.record_call_error(&call_error.error_code);
+            }
+        });
+
+        log::debug!(
+            charge_point_id = charge_point_id,
+            unique_id = unique_id.as_str(),
+            action = action;
+            "response received"
+        );
+
+        match response {
+            Message::CallResult(call_result) => Ok(call_result.payload()?),
+            Message::CallError(call_error) => Err(Error::CallError(call_error)),
+            Message::Call(_) => unreachable!("only responses are registered as pending calls"),
+        }
+    }
+}
+
+/// Extract the Charge Point identity from the URL path, and negotiate the
+/// subprotocol.
+struct Handshake<'a> {
+    charge_point_id: &'a mut Option<String>,
+    version: &'a mut Option<OcppVersion>,
+    versions: &'a [OcppVersion],
+    compression: Option<&'a Compression>,
+}
+
+impl Callback for Handshake<'_> {
+    fn on_request(
+        self,
+        request: &Request,
+        mut response: Response,
+    ) -> std::result::Result<Response, ErrorResponse> {
+        match charge_point_id_from_path(request.uri().path()) {
+            Some(charge_point_id) => {
+                *self.charge_point_id = Some(charge_point_id);
+            }
+            None => {
+                let mut response = ErrorResponse::new(Some("Missing charge point identity".into()));
+                *response.status_mut() = StatusCode::NOT_FOUND;
+
+                return Err(response);
+            }
+        }
+
+        let version = OcppVersion::negotiate(
+            request
+                .headers()
+                .get_all(SEC_WEBSOCKET_PROTOCOL)
+                .iter()
+                .filter_map(|value| value.to_str().ok()),
+            self.versions,
+        );
+
+        // If no supported subprotocol is offered, the handshake must
+        // complete without a `Sec-WebSocket-Protocol` header, and the
+        // connection must be closed immediately.
+        if let Some(version) = version {
+            response.headers_mut().insert(
+                SEC_WEBSOCKET_PROTOCOL,
+                HeaderValue::from_static(version.subprotocol()),
+            );
+            *self.version = Some(version);
+        }
+
+        if let Some(compression) = self.compression {
+            let offers = request
+                .headers()
+                .get_all(SEC_WEBSOCKET_EXTENSIONS)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .collect::<Vec<_>>()
+                .join(",");
+
+            if let Some(extension) = compression
+                .accept(&offers)
+                .and_then(|extension| HeaderValue::from_str(&extension).ok())
+            {
+                response
+                    .headers_mut()
+                    .insert(SEC_WEBSOCKET_EXTENSIONS, extension);
+            }
+        }
+
+        Ok(response)
+    }
+}
+
+/// The Charge Point identity is the last segment of the URL path,
+/// percent-decoded, e.g. `CP 1` for `/ocpp/CP%201`. `None` if it is empty,
+/// or if it is not UTF-8 once decoded.
+fn charge_point_id_from_path(path: &str) -> Option<String> {
+    let path = path.split('?').next().unwrap_or_default();
+    let segment = path
+        .rsplit('/')
+        .next()
+        .filter(|segment| !segment.is_empty())?;
+
+    percent_decode_str(segment)
+        .decode_utf8()
+        .ok()
+        .map(Cow::into_owned)
+}
+
+/// Run a connection, before the WebSocket handshake. The handshake request
+/// must be read by `handshake_deadline`. `client_identities` are the
+/// identities vouched for by the client certificate, if any: the Charge
+/// Point must connect with one of them.
+async fn run_connection<H, A, S>(
+    inner: Arc<Inner<H, A>>,
+    mut stream: S,
+    handshake_deadline: Instant,
+    client_identities: Option<Vec<String>>,
+) where
+    H: CsmsHandler,
+    A: AuthProvider,
+    S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    // The credentials are verified before the upgrade, and the handshake
+    // is then replayed to `tungstenite`.
+    let Ok(Ok((head, head_bytes))) =
+        timeout_at(handshake_deadline, read_request_head(&mut stream)).await
+    else {
+        return;
+    };
+
+    if let Some(charge_point_id) = charge_point_id_from_path(&head.path).as_deref() {
+        if let Some(client_identities) = &client_identities {
+            if !client_identities
+                .iter()
+                .any(|identity| identity == charge_point_id)
+            {
+                log::warn!(
+                    charge_point_id = charge_point_id;
+                    "the client certificate is not issued to the Charge Point"
+                );
+                #[cfg(feature = "audit")]
+                inner.audit(crate::AuditEvent::AuthenticationFailed {
+                    charge_point_id: charge_point_id.to_owned(),
+                    attempts: 1,
+                });
+
+                let _ = stream
+                    .write_all(
+                        b"HTTP/1.1 403 Forbidden\r\n\
+                          Content-Length: 0\r\n\
+                          Connection: close\r\n\r\n",
+                    )
+                    .await;
+                let _ = stream.shutdown().await;
+
+                return;
+            }
+        }
+
+        let credentials = head
+            .authorization
+            .as_deref()
+            .and_then(Credentials::from_authorization_header);
+
+        if !inner
+            .auth_provider
+            .authenticate(charge_point_id, credentials.as_ref())
+            .await
+        {
+            log::warn!(charge_point_id = charge_point_id; "Charge Point not authenticated");
+            #[cfg(feature = "audit")]
+            inner.audit(crate::AuditEvent::AuthenticationFailed {
+                charge_point_id: charge_point_id.to_owned(),
+                attempts: 1,
+            });
+
+            let _ = stream
+                .write_all(
+                    b"HTTP/1.1 401 Unauthorized\r\n\
+                      WWW-Authenticate: Basic realm=\"OCPP\"\r\n\
+                      Content-Length: 0\r\n\
+                      Connection: close\r\n\r\n",
+                )
+                .await;
+            let _ = stream.shutdown().await;
+
+            return;
+        }
+    }
+
+    let stream = Compressed::server(Prefixed::new(head_bytes, stream), inner.config.compression)
+        .max_message_size(inner.config.max_message_size);
+
+    let mut charge_point_id = None;
+    let mut version = None;
+
+    let handshake = Handshake {
+        charge_point_id: &mut charge_point_id,
+        version: &mut version,
+        versions: &inner.config.versions,
+        compression: inner.config.compression.as_ref(),
+    };
+
+    let websocket_config = WebSocketConfig {
+        max_message_size: Some(inner.config.max_message_size),
+        max_frame_size: Some(inner.config.max_message_size),
+        ..WebSocketConfig::default()
+    };
+
+    let Ok(mut stream) =
+        accept_hdr_async_with_config(stream, handshake, Some(websocket_config)).await
+    else {
+        return;
+    };
+
+    let (Some(charge_point_id), Some(version)) = (charge_point_id, version) else {
+        let _ = stream.close(None).await;
+
+        return;
+    };
+
+    Span::charge_point(&charge_point_id)
+        .instrument(run_session(inner, charge_point_id, version, stream))
+        .await;
+}
+
+/// Run the session of the Charge Point `charge_point_id`, once connected
+/// with the OCPP `version`.
+async fn run_session<H, A, T>(
+    inner: Arc<Inner<H, A>>,
+    charge_point_id: String,
+    version: OcppVersion,
+    stream: T,
+) where
+    H: CsmsHandler,
+    A: AuthProvider,
+    T: Transport,
+{
+    inner
+        .running_connections
+        .send_modify(|running_connections| *running_connections += 1);
+    let _running = RunningConnection(&inner.running_connections);
+
+    let (outgoing_sender, mut outgoing) =
+        mpsc::channel(inner.config.outgoing_queue_capacity.max(1));
+    let calls = Arc::new(Semaphore::new(inner.config.max_concurrent_calls.max(1)));
+    let mut rate_limiter = inner
+        .config
+        .rate_limit
+        .as_ref()
+        .map(|rate_limit| RateLimiter::new(rate_limit, Instant::now()));
+    let pending_calls = Arc::new(PendingCalls::new(
+        inner.config.max_outstanding_calls,
+        inner.config.call_timeout,
+    ));
+
+    // A Charge Point reconnecting with the same identity takes the previous
+    // session over.
+    let (session_id, superseded) = inner.sessions.open(
+        &charge_point_id,
+        version,
+        outgoing_sender.clone(),
+        pending_calls.clone(),
+    );
+
+    #[cfg(feature = "metrics")]
+    inner.record(|metrics| metrics.set_connected_charge_points(inner.sessions.len()));
+    log::info!(
+        charge_point_id = charge_point_id.as_str(),
+        session_id = session_id,
+        version:% = version;
+        "Charge Point connected"
+    );
+    inner.events.publish(Event::Connected {
+        charge_point_id: charge_point_id.clone(),
+        session_id,
+        version,
+    });
+    inner
+        .handler
+        .connected_with_version(&charge_point_id, version)
+        .await;
+
+    let (mut sink, mut stream) = stream.split();
+    let mut shutdown = inner.shutdown.subscribe();
+    let mut shutdown_deadline = *shutdown.borrow_and_update();
+    let mut keep_alive = inner
+        .config
+        .keep_alive
+        .map(|keep_alive| KeepAliveTimer::new(keep_alive, Instant::now()));
+
+    // The `Call`s wait for the responses to the previous ones, even if
+    // their callers have given up; the responses do not wait.
+    let mut window = CallWindow::new(
+        inner.config.max_outstanding_calls,
+        inner.config.call_timeout,
+    );
+    let mut held_calls = VecDeque::new();
+    let mut replies = ReplyCache::new(inner.config.replayed_responses);
+
+    let reason = loop {
+        if window.is_open() {
+            if let Some((unique_id, frame)) = held_calls.pop_front() {
+                window.sent(unique_id, Instant::now());
+
+                if let Frame::Text(text) = &frame {
+                    inner.capture(ocppx_rpc::Direction::Outgoing, &charge_point_id, text);
+                }
+
+                if sink.send(frame).await.is_err() {
+                    break DisconnectReason::Error;
+                }
+
+                continue;
+            }
+        }
+
+        // Once shut down, the connection is closed when nothing is in
+        // progress anymore.
+        if shutdown_deadline.is_some() {
+            let calls_in_progress =
+                inner.config.max_concurrent_calls.max(1) - calls.available_permits();
+
+            if calls_in_progress == 0 && pending_calls.is_empty() {
+                let _ = sink.send(going_away()).await;
+
+                break DisconnectReason::Shutdown;
+            }
+        }
+
+        tokio::select! {
+            _ = shutdown.changed(), if shutdown_deadline.is_none() => {
+                shutdown_deadline = *shutdown.borrow_and_update();
+            }
+
+            _ = sleep_until(shutdown_deadline.unwrap_or_else(Instant::now)), if shutdown_deadline.is_some() => {
+                let _ = sink.send(going_away()).await;
+
+                break DisconnectReason::Shutdown;
+            }
+
+            _ = sleep_until(keep_alive.as_ref().map_or_else(Instant::now, KeepAliveTimer::deadline)), if keep_alive.is_some() => {
+                let now = Instant::now();
+
+                match keep_alive.as_mut().and_then(|keep_alive| keep_alive.poll(now)) {
+                    Some(KeepAliveAction::Ping) => {
+                        let sent = sink.send(Frame::Ping(Vec::new())).await;
+
+                        if sent.is_err() {
+                            break DisconnectReason::Error;
+                        }
+                    }
+
+                    Some(KeepAliveAction::Stale) => {
+                        let silence = keep_alive.as_ref().map_or(Duration::ZERO, |keep_alive| keep_alive.silence(now));
+                        log::warn!(
+                            charge_point_id = charge_point_id.as_str(),
+                            silence:? = silence;
+                            "connection stale"
+                        );
+
+                        // Nobody listening is fine.
+                        let _ = inner.connection_events.send(ConnectionEvent::Stale {
+                            charge_point_id: charge_point_id.clone(),
+                            silence,
+                        });
+
+                        break DisconnectReason::Stale;
+                    }
+
+                    None => {}
+                }
+            }
+
+            _ = superseded.notified() => {
+                let _ = sink
+                    .send(Frame::Close(Some(CloseFrame {
+                        code: CloseCode::Policy,
+                        reason: "Superseded by a new connection".into(),
+                    })))
+                    .await;
+
+                break DisconnectReason::Superseded;
+            }
+
+            _ = sleep_until(window.deadline().unwrap_or_else(Instant::now)), if window.deadline().is_some() => {
+                window.expire(Instant::now());
+            }
+
+            outgoing = outgoing.recv() => {
+                let Some(Outgoing { frame, call, answers }) = outgoing else {
+                    break DisconnectReason::Error;
+                };
+
+                if let (Some(ticket), Frame::Text(text)) = (answers, &frame) {
+                    replies.answered(ticket, text.to_string());
+                }
+
+                if let Some(unique_id) = call {
+                    if !window.is_open() {
+                        held_calls.push_back((unique_id, frame));
+
+                        continue;
+                    }
+
+                    window.sent(unique_id, Instant::now());
+                }
+
+                if let Frame::Text(text) = &frame {
+                    inner.capture(ocppx_rpc::Direction::Outgoing, &charge_point_id, text);
+                }
+
+                if sink.send(frame).await.is_err() {
+                    break DisconnectReason::Error;
+                }
+            }
+
+            frame = stream.next() => {
+                // Any frame, not only a pong, proves that the Charge Point
+                // is alive.
+                if let (Some(keep_alive), Some(Ok(_))) = (keep_alive.as_mut(), &frame) {
+                    keep_alive.received(Instant::now());
+                }
+
+                match frame {
+                    Some(Ok(Frame::Text(frame))) => {
+                        inner.capture(ocppx_rpc::Direction::Incoming, &charge_point_id, &frame);
+
+                        // A frame which cannot be parsed is responded to if
+                        // it starts as a `Call`, else it has no unique ID to
+                        // respond to and is ignored.
+                        let mut message = match frame.parse::<Message>() {
+                            Ok(message) => message,
+                            Err(error) => {
+                                let Some(unique_id) = Call::recover_unique_id(&frame) else {
+                                    log::warn!(
+                                        charge_point_id = charge_point_id.as_str(),
+                                        error:% = error;
+                                        "invalid frame ignored"
+                                    );
+
+                                    continue;
+                                };
+
+                                log::warn!(
+                                    charge_point_id = charge_point_id.as_str(),
+                                    unique_id = unique_id.as_str(),
+                                    error:% = error;
+                                    "invalid Call rejected"
+                                );
+
+                                let call_error = CallError::new(
+                                    unique_id,
+                                    ErrorCode::FormationViolation,
+                                    "Invalid frame",
+                                    None,
+                                );
+                                let frame = Message::from(call_error).to_string();
+
+                                inner.capture(ocppx_rpc::Direction::Outgoing, &charge_point_id, &frame);
+
+                                if sink.send(Frame::Text(frame)).await.is_err() {
+                                    break DisconnectReason::Error;
+                                }
+
+                                continue;
+                            }
+                        };
+
+                        if let (Message::Call(call), false) = (&message, inner.config.interceptors.is_empty()) {
+                            let action = call.action.clone();
+                            inner.config.interceptors.incoming(&charge_point_id, &action, &mut message);
+                        }
+
+                        match message {
+                            Message::Call(call) => {
+                                log::debug!(
+                                    charge_point_id = charge_point_id.as_str(),
+                                    unique_id = call.unique_id.as_str(),
+                                    action = call.action.as_str();
+                                    "Call received"
+                                );
+
+                                // A `Call` received again gets the same
+                                // response, without being handled twice.
+                                let ticket = match replies.received(&call) {
+                                    Lookup::Again(Reply::Answered(response)) => {
+                                        log::debug!(
+                                            charge_point_id = charge_point_id.as_str(),
+                                            unique_id = call.unique_id.as_str();
+                                            "Call received again, response sent again"
+                                        );
+
+                                        let response = response.clone();
+                                        inner.capture(ocppx_rpc::Direction::Outgoing, &charge_point_id, &response);
+
+                                        if sink.send(Frame::Text(response)).await.is_err() {
+                                            break DisconnectReason::Error;
+                                        }
+
+                                        continue;
+                                    }
+                                    Lookup::Again(Reply::InProgress) => {
+                                        log::debug!(
+                                            charge_point_id = charge_point_id.as_str(),
+                                            unique_id = call.unique_id.as_str();
+                                            "Call received again while it is handled, ignored"
+                                        );
+
+                                        continue;
+                                    }
+                                    Lookup::New(ticket) => ticket,
+                                };
+
+                                #[cfg(feature = "metrics")]
+                                inner.record(|metrics| {
+                                    metrics.record_message(ocppx_rpc::Direction::Incoming, &call.action)
+                                });
+
+                                // A Charge Point that is not accepted boots
+                                // again too early.
+                                if let Some(response) = (call.action == "BootNotification")
+                                    .then(|| inner.registrations.throttled_boot(&charge_point_id, &call.unique_id, Instant::now()))
+                                    .flatten()
+                                {
+                                    log::debug!(
+                                        charge_point_id = charge_point_id.as_str(),
+                                        unique_id = call.unique_id.as_str();
+                                        "BootNotification received before the end of the interval"
+                                    );
+
+                                    replies.forget(ticket);
+
+                                    let frame = Message::from(response).to_string();
+                                    inner.capture(ocppx_rpc::Direction::Outgoing, &charge_point_id, &frame);
+
+                                    if sink.send(Frame::Text(frame)).await.is_err() {
+                                        break DisconnectReason::Error;
+                                    }
+
+                                    continue;
+                                }
+
+                                let verdict = rate_limiter
+                                    .as_mut()
+                                    .map_or(Verdict::Accept, |limiter| limiter.check(&call.action, Instant::now()));
+                                let mut call_permit = None;
+                                let rejection = if shutdown_deadline.is_some() {
+                                    Some((ErrorCode::InternalError, "The Central System is shutting down"))
+                                } else if verdict != Verdict::Accept {
+                                    log::warn!(
+                                        charge_point_id = charge_point_id.as_str(),
+                                        unique_id = call.unique_id.as_str(),
+                                        action = call.action.as_str();
+                                        "Call rejected by the rate limit"
+                                    );
+
+                                    Some((ErrorCode::SecurityError, "Rate limit exceeded"))
+                                } else if !inner.registrations.allows(&charge_point_id, &call.action) {
+                                    log::warn!(
+                                        charge_point_id = charge_point_id.as_str(),
+                                        unique_id = call.unique_id.as_str(),
+                                        action = call.action.as_str();
+                                        "Call rejected, the Charge Point is not accepted"
+                                    );
+
+                                    Some((ErrorCode::SecurityError, "The Charge Point is not accepted"))
+                                } else if let Ok(permit) = calls.clone().try_acquire_owned() {
+                                    call_permit = Some(permit);
+
+                                    None
+                                } else {
+                                    log::warn!(
+                                        charge_point_id = charge_point_id.as_str(),
+                                        unique_id = call.unique_id.as_str(),
+                                        action = call.action.as_str();
+                                        "Call rejected, too many Calls in progress"
+                                    );
+
+                                    Some((ErrorCode::GenericError, "Too many Calls in progress"))
+                                };
+
+                                if let Some((error_code, error_description)) = rejection {
+                                    replies.forget(ticket);
+
+                                    let call_error = CallError::new(
+                                        call.unique_id,
+                                        error_code,
+                                        error_description,
+                                        None,
+                                    );
+                                    let frame = Message::from(call_error).to_string();
+
+                                    inner.capture(ocppx_rpc::Direction::Outgoing, &charge_point_id, &frame);
+
+                                    if sink.send(Frame::Text(frame)).await.is_err() {
+                                        break DisconnectReason::Error;
+                                    }
+
+                                    if verdict == Verdict::Close && shutdown_deadline.is_none() {
+                                        let _ = sink
+                                            .send(Frame::Close(Some(CloseFrame {
+                                                code: CloseCode::Policy,
+                                                reason: "Rate limit exceeded".into(),
+                                            })))
+                                            .await;
+
+                                        break DisconnectReason::RateLimited;
+                                    }
+
+                                    continue;
+                                }
+
+                                let inner = inner.clone();
+                                let charge_point_id = charge_point_id.clone();
+                                let outgoing = outgoing_sender.clone();
+                                let action = call.action.clone();
+                                let unique_id = call.unique_id.clone();
+                                let request = events::keeps_request(&action).then(|| call.payload.clone());
+
+                                let span = Span::call(&charge_point_id, &unique_id, &action);
+
+                                tokio::spawn(span.instrument(async move {
+                                    let Ok(_handler_permit) = inner.handlers.acquire().await else {
+                                        return;
+                                    };
+
+                                    let mut boot = None;
+                                    let mut response: Message =
+                                        match inner.handler.handle_call(&charge_point_id, call).await {
+                                            Ok(mut call_result) => {
+                                                if action == "BootNotification" {
+                                                    inner.registrations.booted(&charge_point_id, &mut call_result, Instant::now());
+                                                    boot = profile::accepted_boot(request.as_ref(), &call_result);
+                                                }
+
+                                                inner.events.publish_response(&charge_point_id, &action, request, &call_result);
+
+                                                call_result.into()
+                                            }
+                                            Err(call_error) => {
+                                                #[cfg(feature = "metrics")]
+                                                inner.record(|metrics| {
+                                                    metrics.record_call_error(&call_error.error_code)
+                                                });
+
+                                                call_error.into()
+                                            }
+                                        };
+
+                                    inner.config.interceptors.outgoing(&charge_point_id, &action, &mut response);
+
+                                    log::debug!(
+                                        charge_point_id = charge_point_id.as_str(),
+                                        unique_id = response.unique_id();
+                                        "response sent"
+                                    );
+
+                                    // The permit is released first, so that the
+                                    // connection knows that the `Call` is done
+                                    // when it receives the response.
+                                    drop(call_permit);
+                                    let _ = outgoing
+                                        .send(Outgoing {
+                                            frame: Frame::Text(response.to_string()),
+                                            call: None,
+                                            answers: Some(ticket),
+                                        })
+                                        .await;
+
+                                    // The profile is applied once the Charge
+                                    // Point knows that it is accepted.
+                                    if let Some(boot) = boot {
+                                        if let Some(profile) = inner.profiles.get(&charge_point_id) {
+                                            tokio::spawn(profile::apply(
+                                                Server { inner: inner.clone() },
+                                                charge_point_id,
+                                                profile,
+                                                boot,
+                                            ));
+                                        }
+                                    }
+                                }));
+                            }
+
+                            // Late responses, e.g. after a timeout, are
+                            // dropped.
+                            response => {
+                                if let Message::CallError(call_error) = &response {
+                                    inner.events.publish_call_error(&charge_point_id, call_error);
+                                }
+
+                                window.received(response.unique_id());
+                                let _ = pending_calls.resolve(response);
+                            }
+                        }
+                    }
+
+                    // Pongs are queued by `tungstenite` when a ping is
+                    // received; flush to send them immediately.
+                    Some(Ok(Frame::Ping(_))) => {
+                        if sink.flush().await.is_err() {
+                            break DisconnectReason::Error;
+                        }
+                    }
+
+                    Some(Ok(Frame::Close(_))) | None => break DisconnectReason::Closed,
+
+                    Some(Err(_)) => break DisconnectReason::Error,
+
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    };
+
+    let current = inner.sessions.close(&charge_point_id, session_id);
+
+    #[cfg(feature = "metrics")]
+    inner.record(|metrics| metrics.set_connected_charge_points(inner.sessions.len()));
+
+    // Cancelling the pending calls wakes up their callers with a
+    // `ConnectionClosed` error.
+    pending_calls.cancel_all();
+
+    // A session closed by `SessionRegistry::supersede` ends with its own
+    // reason.
+    let reason = if current {
+        reason
+    } else {
+        DisconnectReason::Superseded
+    };
+    inner.events.publish(Event::Disconnected {
+        charge_point_id: charge_point_id.clone(),
+        session_id,
+        reason,
+    });
+
+    // A superseded session is not a disconnection: the Charge Point is
+    // connected through the new session.
+    if current {
+        log::info!(
+            charge_point_id = charge_point_id.as_str(),
+            session_id = session_id;
+            "Charge Point disconnected"
+        );
+        inner.handler.disconnected(&charge_point_id).await;
+    } else {
+        log::info!(
+            charge_point_id = charge_point_id.as_str(),
+            session_id = session_id;
+            "session superseded"
+        );
+    }
+}
+
+/// Decrement the number of running connections when a connection ends.
+struct RunningConnection<'a>(&'a watch::Sender<usize>);
+
+impl Drop for RunningConnection<'_> {
+    fn drop(&mut self) {
+        self.0
+            .send_modify(|running_connections| *running_connections -= 1);
+    }
+}
+
+/// Whether an error of `accept` concerns only the connection being accepted.
+fn is_connection_error(error: &std::io::Error) -> bool {
+    matches!(
+        error.kind(),
+        std::io::ErrorKind::ConnectionRefused
+            | std::io::ErrorKind::ConnectionAborted
+            | std::io::ErrorKind::ConnectionReset
+    )
+}
+
+/// The close frame sent when the server shuts down.
+fn going_away() -> Frame {
+    Frame::Close(Some(CloseFrame {
+        code: CloseCode::Away,
+        reason: "The Central System is shutting down".into(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CallFailure;
+    use ocppx_client::ChargePointClient;
+    use ocppx_rpc::{CallError, CallResult, ErrorCode};
+    use ocppx_types::v1_6::{HeartbeatRequest, HeartbeatResponse};
+    use std::{sync::atomic::Ordering, time::Duration};
+
+    struct Handler;
+
+    impl CsmsHandler for Handler {
+        async fn handle_call(
+            &self,
+            _charge_point_id: &str,
+            call: Call,
+        ) -> std::result::Result<CallResult, CallError> {
+            match call.action.as_str() {
+                "Heartbeat" => Ok(CallResult::new(
+                    call.unique_id,
+                    &HeartbeatResponse::builder()
+                        .current_time(
+                            "2013-02-01T20:53:32.486Z"
+                                .parse::<chrono::DateTime<chrono::Utc>>()
+                                .unwrap(),
+                        )
+                        .build(),
+                )
+                .unwrap()),
+                _ => Err(CallError::new(
+                    call.unique_id,
+                    ErrorCode::NotImplemented,
+                    "",
+                    None,
+                )),
+            }
+        }
+    }
+
+    #[test]
+    fn test_charge_point_id_from_path() {
+        assert_eq!(
+            charge_point_id_from_path("/ocpp/CP001?x=1").as_deref(),
+            Some("CP001")
+        );
+        assert_eq!(
+            charge_point_id_from_path("/ocpp/CP%2F1").as_deref(),
+            Some("CP/1")
+        );
+        assert_eq!(charge_point_id_from_path("/ocpp/"), None);
+        assert_eq!(charge_point_id_from_path("/ocpp/CP%FF"), None);
+    }
+
+    #[test]
+    fn test_is_connection_error() {
+        use std::io::{Error, ErrorKind};
+
+        assert!(is_connection_error(&Error::from(
+            ErrorKind::ConnectionAborted
+        )));
+        // Too many open files.
+        assert!(!is_connection_error(&Error::from_raw_os_error(24)));
+    }
+
+    #[tokio::test]
+    async fn test_call_from_charge_point() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::new(Handler);
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let client = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001")
+            .await
+            .unwrap();
+
+        let response = client
+            .send_heartbeat(HeartbeatRequest::builder().build())
+            .await
+            .unwrap();
+        assert_eq!(
+            response.current_time.to_rfc3339(),
+            "2013-02-01T20:53:32.486+00:00"
+        );
+        assert_eq!(server.connected_charge_points(), ["CP001"]);
+
+        let error = client
+            .call::<_, serde_json::Value>("Unknown", &serde_json::json!({}))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(error, ocppx_client::Error::CallError(CallError { error_code, .. }) if error_code == ErrorCode::NotImplemented)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_serve_transport() {
+        let server = Server::new(Handler);
+        let (charge_point, central_system) = ocppx_rpc::MemoryTransport::pair();
+        let client = ChargePointClient::with_transport(
+            "CP001",
+            charge_point,
+            ocppx_client::ClientConfig::default(),
+        );
+        let session = tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve_transport("CP001", central_system).await }
+        });
+
+        let response = client
+            .send_heartbeat(HeartbeatRequest::builder().build())
+            .await
+            .unwrap();
+        assert_eq!(
+            response.current_time.to_rfc3339(),
+            "2013-02-01T20:53:32.486+00:00"
+        );
+        assert_eq!(server.connected_charge_points(), ["CP001"]);
+
+        // And the other way around.
+        let payload = serde_json::json!({"type": "Soft"});
+        let (response, ()) = tokio::join!(
+            server.call::<_, serde_json::Value>("CP001", "Reset", &payload),
+            async {
+                let call = client.next_call().await.unwrap();
+                assert_eq!(call.action, "Reset");
+                client
+                    .respond(
+                        CallResult::new(call.unique_id, &serde_json::json!({"status": "Accepted"}))
+                            .unwrap(),
+                    )
+                    .unwrap();
+            }
+        );
+        assert_eq!(response.unwrap(), serde_json::json!({"status": "Accepted"}));
+
+        client.close().await.unwrap();
+        session.await.unwrap();
+        assert!(server.connected_charge_points().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_invalid_frames() {
+        let server = Server::new(Handler);
+        let (mut charge_point, central_system) = ocppx_rpc::MemoryTransport::pair();
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve_transport("CP001", central_system).await }
+        });
+
+        // A frame without a unique ID is ignored, a `Call` which cannot be
+        // parsed is responded to.
+        for frame in [
+            r#"{"id":"19223201"}"#,
+            r#"[2,"19223201","Heartbeat"]"#,
+            r#"[2,"19223202","Heartbeat",{"#,
+        ] {
+            charge_point.send(Frame::Text(frame.into())).await.unwrap();
+        }
+
+        for unique_id in ["19223201", "19223202"] {
+            let Some(Ok(Frame::Text(frame))) = charge_point.next().await else {
+                panic!("expected a text frame");
+            };
+            let call_error = CallError::try_from(frame.parse::<Message>().unwrap()).unwrap();
+
+            assert_eq!(call_error.unique_id, unique_id);
+            assert_eq!(call_error.error_code, ErrorCode::FormationViolation);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_serve_plain_tcp() {
+        use ocppx_rpc::FramedTransport;
+        use tokio::net::TcpStream;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::new(Handler);
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move {
+                // An identity per connection: `CP001`, then `CP002`.
+                for charge_point_id in ["CP001", "CP002"] {
+                    let (stream, _) = listener.accept().await.unwrap();
+                    let server = server.clone();
+
+                    tokio::spawn(async move {
+                        server
+                            .serve_transport(charge_point_id, FramedTransport::new(stream))
+                            .await
+                    });
+                }
+            }
+        });
+
+        let connector =
+            move || async move { Ok(FramedTransport::new(TcpStream::connect(address).await?)) };
+
+        for charge_point_id in ["CP001", "CP002"] {
+            let client = ChargePointClient::connect_with_connector(
+                charge_point_id,
+                connector,
+                ocppx_client::ClientConfig::default(),
+            )
+            .await
+            .unwrap();
+
+            let response = client
+                .send_heartbeat(HeartbeatRequest::builder().build())
+                .await
+                .unwrap();
+            assert_eq!(
+                response.current_time.to_rfc3339(),
+                "2013-02-01T20:53:32.486+00:00"
+            );
+            assert_eq!(server.connected_charge_points(), [charge_point_id]);
+
+            client.close().await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handlers_are_bounded() {
+        use std::sync::atomic::AtomicUsize;
+
+        /// Count the `Call`s handled at once.
+        #[derive(Default)]
+        struct Slow {
+            current: AtomicUsize,
+            max: AtomicUsize,
+        }
+
+        impl CsmsHandler for Arc<Slow> {
+            async fn handle_call(
+                &self,
+                charge_point_id: &str,
+                call: Call,
+            ) -> std::result::Result<CallResult, CallError> {
+                let current = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max.fetch_max(current, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                self.current.fetch_sub(1, Ordering::SeqCst);
+
+                Handler.handle_call(charge_point_id, call).await
+            }
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let handler = Arc::new(Slow::default());
+        let server = Server::with_config(
+            handler.clone(),
+            ServerConfig {
+                max_concurrent_handlers: 2,
+                ..Default::default()
+            },
+        );
+
+        tokio::spawn(async move { server.serve(listener).await });
+
+        let mut clients = Vec::new();
+
+        for charge_point_id in ["CP001", "CP002", "CP003", "CP004"] {
+            clients.push(
+                ChargePointClient::connect(&format!("ws://{address}/ocpp"), charge_point_id)
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let heartbeats = clients
+            .into_iter()
+            .map(|client| {
+                tokio::spawn(async move {
+                    client
+                        .send_heartbeat(HeartbeatRequest::builder().build())
+                        .await
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for heartbeat in heartbeats {
+            assert!(heartbeat.await.unwrap().is_ok());
+        }
+
+        assert_eq!(handler.max.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_call_back_at_the_limit() {
+        use std::sync::OnceLock;
+
+        /// Ask the Charge Point something before answering its `Call`.
+        #[derive(Default)]
+        struct CallBack(OnceLock<Server<Arc<CallBack>>>);
+
+        impl CsmsHandler for Arc<CallBack> {
+            async fn handle_call(
+                &self,
+                charge_point_id: &str,
+                call: Call,
+            ) -> std::result::Result<CallResult, CallError> {
+                let server = self.0.get().unwrap();
+                let response = server
+                    .call::<_, serde_json::Value>(
+                        charge_point_id,
+                        "GetConfiguration",
+                        &serde_json::json!({}),
+                    )
+                    .await
+                    .unwrap();
+
+                Ok(CallResult::new(call.unique_id, &response).unwrap())
+            }
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let handler = Arc::new(CallBack::default());
+        let server = Server::with_config(
+            handler.clone(),
+            ServerConfig {
+                max_concurrent_calls: 1,
+                ..Default::default()
+            },
+        );
+        let _ = handler.0.set(server.clone());
+
+        tokio::spawn(async move { server.serve(listener).await });
+
+        let client = Arc::new(
+            ChargePointClient::connect_with_config(
+                &format!("ws://{address}/ocpp"),
+                "CP001",
+                ocppx_client::ClientConfig {
+                    max_outstanding_calls: 2,
+                    heartbeat: false,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap(),
+        );
+        let data_transfer = tokio::spawn({
+            let client = client.clone();
+
+            async move {
+                client
+                    .call::<_, serde_json::Value>("DataTransfer", &serde_json::json!({}))
+                    .await
+            }
+        });
+
+        // The `DataTransfer` takes the only slot until its handler gets the
+        // response of the Charge Point: another `Call` is rejected
+        // meanwhile.
+        let call = client.next_call().await.unwrap();
+        assert_eq!(call.action, "GetConfiguration");
+        assert!(matches!(
+            client.send_heartbeat(HeartbeatRequest::builder().build()).await,
+            Err(ocppx_client::Error::CallError(CallError { error_code, .. })) if error_code == ErrorCode::GenericError
+        ));
+
+        client
+            .respond(
+                CallResult::new(
+                    call.unique_id,
+                    &serde_json::json!({ "configurationKey": [] }),
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        assert_eq!(
+            data_transfer.await.unwrap().unwrap(),
+            serde_json::json!({ "configurationKey": [] })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::with_config(
+            Handler,
+            ServerConfig {
+                rate_limit: Some(
+                    crate::RateLimit::default().action("Heartbeat", crate::Rate::per_minute(1)),
+                ),
+                ..Default::default()
+            },
+        );
+
+        tokio::spawn(async move { server.serve(listener).await });
+
+        let client = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001")
+            .await
+            .unwrap();
+
+        assert!(client
+            .send_heartbeat(HeartbeatRequest::builder().build())
+            .await
+            .is_ok());
+        assert!(matches!(
+            client.send_heartbeat(HeartbeatRequest::builder().build()).await,
+            Err(ocppx_client::Error::CallError(CallError { error_code, .. })) if error_code == ErrorCode::SecurityError
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_replayed_responses() {
+        use std::sync::atomic::AtomicUsize;
+
+        /// Count the `Call`s handled.
+        struct Counting(AtomicUsize);
+
+        impl CsmsHandler for Arc<Counting> {
+            async fn handle_call(
+                &self,
+                _charge_point_id: &str,
+                call: Call,
+            ) -> std::result::Result<CallResult, CallError> {
+                let calls = self.0.fetch_add(1, Ordering::SeqCst);
+
+                Ok(
+                    CallResult::new(call.unique_id, &serde_json::json!({ "calls": calls }))
+                        .unwrap(),
+                )
+            }
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let handler = Arc::new(Counting(Default::default()));
+        let server = Server::new(handler.clone());
+
+        tokio::spawn(async move { server.serve(listener).await });
+
+        // The Charge Point sends its `Call`s again with the same unique ID.
+        let client = ChargePointClient::connect_with_config(
+            &format!("ws://{address}/ocpp"),
+            "CP001",
+            ocppx_client::ClientConfig {
+                message_ids: ocppx_rpc::MessageIds::new(|| {
+                    ocppx_rpc::MessageId::new("retransmitted").unwrap()
+                }),
+                heartbeat: false,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        for _ in 0..3 {
+            let response = client
+                .call::<_, serde_json::Value>("DataTransfer", &serde_json::json!({}))
+                .await
+                .unwrap();
+            assert_eq!(response, serde_json::json!({ "calls": 0 }));
+        }
+
+        assert_eq!(handler.0.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::new(Handler);
+
+        let serving = tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let client = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001")
+            .await
+            .unwrap();
+
+        // A `Call` that the Charge Point never answers.
+        let response = tokio::spawn({
+            let server = server.clone();
+
+            async move {
+                server
+                    .call::<_, serde_json::Value>("CP001", "ClearCache", &serde_json::json!({}))
+                    .await
+            }
+        });
+        client.next_call().await.unwrap();
+
+        server.shutdown(Duration::from_millis(50)).await;
+
+        assert!(serving.await.unwrap().is_ok());
+        assert!(matches!(response.await.unwrap(), Err(Error::Shutdown)));
+        assert!(server.connected_charge_points().is_empty());
+        assert!(matches!(
+            server
+                .call::<_, serde_json::Value>("CP001", "ClearCache", &serde_json::json!({}))
+                .await,
+            Err(Error::Shutdown)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_session_takeover() {
+        use crate::SessionEvent;
+        use std::sync::atomic::AtomicUsize;
+
+        /// Count the disconnections.
+        #[derive(Default)]
+        struct Disconnections(AtomicUsize);
+
+        impl CsmsHandler for Arc<Disconnections> {
+            async fn handle_call(
+                &self,
+                charge_point_id: &str,
+                call: Call,
+            ) -> std::result::Result<CallResult, CallError> {
+                Handler.handle_call(charge_point_id, call).await
+            }
+
+            async fn disconnected(&self, _charge_point_id: &str) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let disconnections = Arc::new(Disconnections::default());
+        let server = Server::new(disconnections.clone());
+        let mut events = server.sessions().subscribe();
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let first = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001")
+            .await
+            .unwrap();
+
+        // A `Call` to the first session, never answered.
+        let response = tokio::spawn({
+            let server = server.clone();
+
+            async move {
+                server
+                    .call::<_, serde_json::Value>("CP001", "ClearCache", &serde_json::json!({}))
+                    .await
+            }
+        });
+        first.next_call().await.unwrap();
+
+        let second = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001")
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            response.await.unwrap(),
+            Err(Error::ConnectionClosed)
+        ));
+        assert!(first
+            .send_heartbeat(HeartbeatRequest::builder().build())
+            .await
+            .is_err());
+        assert!(second
+            .send_heartbeat(HeartbeatRequest::builder().build())
+            .await
+            .is_ok());
+        assert_eq!(server.connected_charge_points(), ["CP001"]);
+        assert_eq!(disconnections.0.load(Ordering::SeqCst), 0);
+
+        assert!(matches!(
+            events.recv().await,
+            Ok(SessionEvent::Opened { session_id: 0, .. })
+        ));
+        assert!(matches!(
+            events.recv().await,
+            Ok(SessionEvent::Superseded {
+                session_id: 0,
+                by: Some(1),
+                ..
+            })
+        ));
+        assert!(matches!(
+            events.recv().await,
+            Ok(SessionEvent::Opened { session_id: 1, .. })
+        ));
+
+        // Behind a load balancer, the Charge Point has reconnected to
+        // another node.
+        assert!(server.sessions().supersede("CP001"));
+        assert!(second
+            .send_heartbeat(HeartbeatRequest::builder().build())
+            .await
+            .is_err());
+        assert!(server.connected_charge_points().is_empty());
+        assert_eq!(disconnections.0.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_keep_alive() {
+        use tokio::{io::AsyncReadExt, net::TcpStream};
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::with_config(
+            Handler,
+            ServerConfig {
+                keep_alive: Some(ocppx_rpc::KeepAlive {
+                    ping_interval: Duration::from_millis(50),
+                    pong_timeout: Duration::from_millis(50),
+                }),
+                ..Default::default()
+            },
+        );
+        let mut events = server.connection_events();
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        // The client answers the pings.
+        let _client = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001")
+            .await
+            .unwrap();
+
+        // A bare TCP stream, never read after the handshake, does not.
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream
+            .write_all(
+                format!(
+                    "GET /ocpp/CP002 HTTP/1.1\r\n\
+                     Host: {address}\r\n\
+                     Upgrade: websocket\r\n\
+                     Connection: Upgrade\r\n\
+                     Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+                     Sec-WebSocket-Version: 13\r\n\
+                     Sec-WebSocket-Protocol: ocpp1.6\r\n\r\n"
+                )
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+        let mut response = [0; 12];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(&response, b"HTTP/1.1 101");
+
+        assert!(matches!(
+            events.recv().await,
+            Ok(ConnectionEvent::Stale { charge_point_id, .. }) if charge_point_id == "CP002"
+        ));
+
+        while server.connected_charge_points().len() > 1 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        assert_eq!(server.connected_charge_points(), ["CP001"]);
+    }
+
+    #[tokio::test]
+    async fn test_handshake_timeout() {
+        use tokio::{io::AsyncReadExt, net::TcpStream};
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::with_config(
+            Handler,
+            ServerConfig {
+                handshake_timeout: Duration::from_millis(100),
+                ..Default::default()
+            },
+        );
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        // A client which stalls, before or in the middle of its request, is
+        // dropped.
+        let mut silent = TcpStream::connect(address).await.unwrap();
+        let mut stalled = TcpStream::connect(address).await.unwrap();
+        stalled
+            .write_all(b"GET /ocpp/CP001 HTTP/1.1\r\nUpgrade: websocket\r\n")
+            .await
+            .unwrap();
+
+        for stream in [&mut silent, &mut stalled] {
+            let mut buffer = [0; 1];
+            let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buffer))
+                .await
+                .expect("the stalled connection is still open");
+            assert!(matches!(read, Ok(0) | Err(_)));
+        }
+
+        // The connections finishing their handshake in time are served.
+        let client = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP002")
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(client
+            .send_heartbeat(HeartbeatRequest::builder().build())
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_call_from_central_system() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::new(Handler);
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let client = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001")
+            .await
+            .unwrap();
+
+        let response = tokio::spawn({
+            let server = server.clone();
+
+            async move {
+                server
+                    .call::<_, serde_json::Value>("CP001", "ClearCache", &serde_json::json!({}))
+                    .await
+            }
+        });
+
+        let call = client.next_call().await.unwrap();
+        assert_eq!(call.action, "ClearCache");
+        client
+            .respond(
+                CallResult::new(call.unique_id, &serde_json::json!({"status": "Accepted"}))
+                    .unwrap(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            response.await.unwrap().unwrap(),
+            serde_json::json!({"status": "Accepted"})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_call_many() {
+        use ocppx_types::{
+            v1_6::{ChangeConfigurationRequest, ChangeConfigurationStatus},
+            CiString50, CiString500,
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::new(Handler);
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let mut clients = Vec::new();
+
+        for charge_point_id in ["CP001", "CP002"] {
+            let client = Arc::new(
+                ChargePointClient::connect(&format!("ws://{address}/ocpp"), charge_point_id)
+                    .await
+                    .unwrap(),
+            );
+
+            // `CP001` accepts the new configuration, `CP002` does not
+            // support it.
+            tokio::spawn({
+                let client = client.clone();
+
+                async move {
+                    while let Some(call) = client.next_call().await {
+                        let _ = if charge_point_id == "CP001" {
+                            client.respond(
+                                CallResult::new(
+                                    call.unique_id,
+                                    &serde_json::json!({"status": "Accepted"}),
+                                )
+                                .unwrap(),
+                            )
+                        } else {
+                            client.respond(CallError::new(
+                                call.unique_id,
+                                ErrorCode::NotSupported,
+                                "",
+                                None,
+                            ))
+                        };
+                    }
+                }
+            });
+            clients.push(client);
+        }
+
+        let request = ChangeConfigurationRequest::builder()
+            .key(CiString50::try_from("HeartbeatInterval").unwrap())
+            .value(CiString500::try_from("60").unwrap())
+            .build();
+
+        let results = server.broadcast(request.clone()).await;
+        assert!(!results.is_success());
+        assert_eq!(
+            results
+                .succeeded()
+                .map(|(id, response)| (id, response.status))
+                .collect::<Vec<_>>(),
+            [("CP001", ChangeConfigurationStatus::Accepted)]
+        );
+        assert!(matches!(
+            results.failed().collect::<Vec<_>>()[..],
+            [("CP002", CallFailure::NotSupported { .. })]
+        ));
+
+        // The members of a group which are not connected fail too.
+        server.groups().add("site:paris", "CP001");
+        server.groups().add("site:paris", "CP003");
+
+        let results = server
+            .call_many(Target::Group("site:paris".to_owned()), request)
+            .await;
+        assert_eq!(results.results.len(), 2);
+        assert!(results.results["CP001"].is_ok());
+        assert!(matches!(
+            results.results["CP003"],
+            Err(CallFailure::NotConnected(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_events() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::new(Handler);
+        let mut events = server.events().subscribe();
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let client = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001")
+            .await
+            .unwrap();
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            Event::Connected { charge_point_id, .. } if charge_point_id == "CP001"
+        ));
+
+        let response = tokio::spawn({
+            let server = server.clone();
+
+            async move {
+                server
+                    .send("CP001", HeartbeatRequest::builder().build())
+                    .await
+            }
+        });
+        let call = client.next_call().await.unwrap();
+        client
+            .respond(CallError::new(
+                call.unique_id.clone(),
+                ErrorCode::NotImplemented,
+                "",
+                None,
+            ))
+            .unwrap();
+        assert!(response.await.unwrap().is_err());
+        assert_eq!(
+            events.recv().await.unwrap(),
+            Event::CallErrorReceived {
+                charge_point_id: "CP001".to_owned(),
+                unique_id: call.unique_id,
+                error_code: ErrorCode::NotImplemented,
+                error_description: String::new(),
+            }
+        );
+
+        client.close().await.unwrap();
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            Event::Disconnected {
+                charge_point_id,
+                reason: DisconnectReason::Closed,
+                ..
+            } if charge_point_id == "CP001"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_interceptors() {
+        use crate::{Interceptor, Interceptors};
+
+        /// Rewrite the location of the firmwares, and the status of the
+        /// responses to `ClearCache`.
+        struct Rewrite;
+
+        impl Interceptor for Rewrite {
+            fn incoming(&self, _charge_point_id: &str, action: &str, message: &mut Message) {
+                if let (Message::CallResult(call_result), "ClearCache") = (message, action) {
+                    call_result.payload["status"] = "Rejected".into();
+                }
+            }
+
+            fn outgoing(&self, _charge_point_id: &str, action: &str, message: &mut Message) {
+                if let (Message::Call(call), "UpdateFirmware") = (message, action) {
+                    call.payload["location"] = "https://mirror.example.org/firmware.bin".into();
+                }
+            }
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::with_config(
+            Handler,
+            ServerConfig {
+                interceptors: Interceptors::default().with(Rewrite),
+                ..ServerConfig::default()
+            },
+        );
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let client = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001")
+            .await
+            .unwrap();
+
+        let response = tokio::spawn({
+            let server = server.clone();
+
+            async move {
+                server
+                    .call::<_, serde_json::Value>(
+                        "CP001",
+                        "UpdateFirmware",
+                        &serde_json::json!({
+                            "location": "https://firmware.example.org/firmware.bin",
+                            "retrieveDate": "2024-01-01T00:00:00Z",
+                        }),
+                    )
+                    .await
+            }
+        });
+        let call = client.next_call().await.unwrap();
+        assert_eq!(
+            call.payload["location"],
+            "https://mirror.example.org/firmware.bin"
+        );
+        client
+            .respond(CallResult::new(call.unique_id, &serde_json::json!({})).unwrap())
+            .unwrap();
+        response.await.unwrap().unwrap();
+
+        let response = tokio::spawn({
+            let server = server.clone();
+
+            async move {
+                server
+                    .call::<_, serde_json::Value>("CP001", "ClearCache", &serde_json::json!({}))
+                    .await
+            }
+        });
+        let call = client.next_call().await.unwrap();
+        client
+            .respond(
+                CallResult::new(call.unique_id, &serde_json::json!({"status": "Accepted"}))
+                    .unwrap(),
+            )
+            .unwrap();
+        assert_eq!(
+            response.await.unwrap().unwrap(),
+            serde_json::json!({"status": "Rejected"})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_version_negotiation() {
+        use crate::{Unsupported, VersionRouter};
+        use ocppx_client::ClientConfig;
+
+        /// Respond to the `Call`s with `version`.
+        struct Versioned(&'static str);
+
+        impl CsmsHandler for Versioned {
+            async fn handle_call(
+                &self,
+                _charge_point_id: &str,
+                call: Call,
+            ) -> std::result::Result<CallResult, CallError> {
+                Ok(
+                    CallResult::new(call.unique_id, &serde_json::json!({"version": self.0}))
+                        .unwrap(),
+                )
+            }
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::with_config(
+            VersionRouter::new(Versioned("1.6"), Versioned("2.0.1"), Unsupported),
+            ServerConfig {
+                versions: vec![OcppVersion::V1_6, OcppVersion::V2_0_1],
+                ..ServerConfig::default()
+            },
+        );
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        for (charge_point_id, subprotocol, version) in [
+            ("CP001", "ocpp1.6", OcppVersion::V1_6),
+            ("CP002", "ocpp2.0.1", OcppVersion::V2_0_1),
+        ] {
+            let client = ChargePointClient::connect_with_config(
+                &format!("ws://{address}/ocpp"),
+                charge_point_id,
+                ClientConfig {
+                    subprotocol,
+                    heartbeat: false,
+                    ..ClientConfig::default()
+                },
+            )
+            .await
+            .unwrap();
+            assert_eq!(server.sessions().version(charge_point_id), Some(version));
+
+            let response = client
+                .call::<_, serde_json::Value>("Heartbeat", &serde_json::json!({}))
+                .await
+                .unwrap();
+            assert_eq!(response["version"], &subprotocol["ocpp".len()..]);
+        }
+
+        // OCPP 2.1 is not accepted.
+        assert!(ChargePointClient::connect_with_config(
+            &format!("ws://{address}/ocpp"),
+            "CP003",
+            ClientConfig {
+                subprotocol: "ocpp2.1",
+                reconnect: None,
+                ..ClientConfig::default()
+            },
+        )
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_call_ordering() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::new(Handler);
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let client = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001")
+            .await
+            .unwrap();
+        let call = |action: &'static str| {
+            let server = server.clone();
+
+            tokio::spawn(async move {
+                server
+                    .call::<_, serde_json::Value>("CP001", action, &serde_json::json!({}))
+                    .await
+            })
+        };
+
+        // The caller of the first `Call` gives up before its response.
+        let first = call("ClearCache");
+        let first_call = client.next_call().await.unwrap();
+        first.abort();
+
+        // The second `Call` waits for the response to the first one, but
+        // the responses to the Charge Point do not.
+        let second = call("ClearCache");
+        client
+            .send_heartbeat(HeartbeatRequest::builder().build())
+            .await
+            .unwrap();
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), client.next_call())
+                .await
+                .is_err()
+        );
+
+        client
+            .respond(CallResult::new(first_call.unique_id, &serde_json::json!({})).unwrap())
+            .unwrap();
+        let second_call = client.next_call().await.unwrap();
+        client
+            .respond(CallResult::new(second_call.unique_id, &serde_json::json!({})).unwrap())
+            .unwrap();
+        second.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pipelined_calls() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::with_config(
+            Handler,
+            ServerConfig {
+                max_outstanding_calls: 2,
+                ..Default::default()
+            },
+        );
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let client = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001")
+            .await
+            .unwrap();
+        let responses = ["ClearCache", "GetConfiguration"].map(|action| {
+            let server = server.clone();
+
+            tokio::spawn(async move {
+                server
+                    .call::<_, serde_json::Value>("CP001", action, &serde_json::json!({}))
+                    .await
+            })
+        });
+
+        // Both `Call`s are received before any response, and are answered
+        // in the reverse order.
+        let first_call = client.next_call().await.unwrap();
+        let second_call = client.next_call().await.unwrap();
+
+        for call in [second_call, first_call] {
+            client
+                .respond(CallResult::new(call.unique_id, &serde_json::json!({})).unwrap())
+                .unwrap();
+        }
+
+        for response in responses {
+            response.await.unwrap().unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compression() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let compression = ocppx_rpc::Compression {
+            window_bits: 10,
+            threshold: 0,
+        };
+        let server = Server::with_config(
+            Handler,
+            ServerConfig {
+                compression: Some(compression),
+                ..ServerConfig::default()
+            },
+        );
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let client = ChargePointClient::connect_with_config(
+            &format!("ws://{address}/ocpp"),
+            "CP001",
+            ocppx_client::ClientConfig {
+                compression: Some(compression),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        client
+            .send_heartbeat(HeartbeatRequest::builder().build())
+            .await
+            .unwrap();
+
+        // Larger than a window, in both directions.
+        let data = "0123456789abcdef".repeat(256);
+        let response = tokio::spawn({
+            let server = server.clone();
+            let data = data.clone();
+
+            async move {
+                server
+                    .call::<_, serde_json::Value>(
+                        "CP001",
+                        "DataTransfer",
+                        &serde_json::json!({"vendorId": "ocppx", "data": data}),
+                    )
+                    .await
+            }
+        });
+
+        let call = client.next_call().await.unwrap();
+        assert_eq!(call.payload["data"], data);
+        client
+            .respond(CallResult::new(call.unique_id, &call.payload).unwrap())
+            .unwrap();
+
+        assert_eq!(response.await.unwrap().unwrap()["data"], data);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_metrics() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let metrics = ocppx_rpc::Metrics::new();
+        let server = Server::with_config(
+            Handler,
+            ServerConfig {
+                metrics: Some(metrics.clone()),
+                ..Default::default()
+            },
+        );
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let client = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001")
+            .await
+            .unwrap();
+        client
+            .send_heartbeat(HeartbeatRequest::builder().build())
+            .await
+            .unwrap();
+        let _ = client
+            .call::<_, serde_json::Value>("Unknown", &serde_json::json!({}))
+            .await;
+
+        let output = metrics.render();
+        assert!(output
+            .contains("ocppx_messages_total{direction=\"incoming\",action=\"Heartbeat\"} 1\n"));
+        assert!(output.contains("ocppx_call_errors_total{code=\"NotImplemented\"} 1\n"));
+        assert!(output.contains("ocppx_connected_charge_points 1\n"));
+    }
+
+    #[tokio::test]
+    async fn test_call_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::with_config(
+            Handler,
+            ServerConfig {
+                call_timeout: Duration::from_millis(50),
+                ..Default::default()
+            },
+        );
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let _client = ChargePointClient::connect(&format!("ws://{address}/ocpp"), "CP001")
+            .await
+            .unwrap();
+
+        let error = server
+            .call::<_, serde_json::Value>("CP001", "ClearCache", &serde_json::json!({}))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::Timeout { action, .. } if action == "ClearCache"));
+    }
+
+    #[tokio::test]
+    async fn test_basic_auth() {
+        struct Passwords;
+
+        impl AuthProvider for Passwords {
+            async fn authenticate(
+                &self,
+                charge_point_id: &str,
+                credentials: Option<&Credentials>,
+            ) -> bool {
+                matches!(
+                    credentials,
+                    Some(Credentials { username, password })
+                        if username == charge_point_id && password == "secret"
+                )
+            }
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::with_auth_provider(Handler, ServerConfig::default(), Passwords);
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let csms_url = format!("ws://{address}/ocpp");
+        let connect = |password: Option<&str>| {
+            ChargePointClient::connect_with_config(
+                &csms_url,
+                "CP001",
+                ocppx_client::ClientConfig {
+                    basic_auth_password: password.map(ToOwned::to_owned),
+                    ..Default::default()
+                },
+            )
+        };
+
+        assert!(matches!(
+            connect(None).await,
+            Err(ocppx_client::Error::Unauthorized)
+        ));
+        assert!(matches!(
+            connect(Some("wrong")).await,
+            Err(ocppx_client::Error::Unauthorized)
+        ));
+
+        let client = connect(Some("secret")).await.unwrap();
+        assert!(client
+            .send_heartbeat(HeartbeatRequest::builder().build())
+            .await
+            .is_ok());
+    }
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn test_security_profile_3() {
+        use crate::{
+            rustls::{
+                pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer},
+                RootCertStore,
+            },
+            TlsConfig,
+        };
+        use ocppx_client::ClientConfig;
+        use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair};
+
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::new(Vec::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+
+        let mut root_store = RootCertStore::empty();
+        root_store.add(ca.der().clone()).unwrap();
+
+        let certificate = |name: &str| -> (Vec<CertificateDer<'static>>, PrivateKeyDer<'static>) {
+            let key = KeyPair::generate().unwrap();
+            let certificate = CertificateParams::new(vec![name.to_owned()])
+                .unwrap()
+                .signed_by(&key, &ca, &ca_key)
+                .unwrap();
+
+            (
+                vec![certificate.der().clone()],
+                PrivatePkcs8KeyDer::from(key.serialize_der()).into(),
+            )
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (server_chain, server_key) = certificate("localhost");
+        let server = Server::with_config(
+            Handler,
+            ServerConfig {
+                tls: Some(
+                    TlsConfig::security_profile_3(server_chain, server_key, root_store.clone())
+                        .unwrap(),
+                ),
+                ..Default::default()
+            },
+        );
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let csms_url = format!("wss://localhost:{port}/ocpp");
+
+        // Without a client certificate, the TLS handshake fails.
+        assert!(ChargePointClient::connect_with_config(
+            &csms_url,
+            "CP001",
+            ClientConfig {
+                tls: Some(ocppx_client::TlsConfig::security_profile_2(
+                    root_store.clone()
+                )),
+                ..Default::default()
+            },
+        )
+        .await
+        .is_err());
+
+        let (client_chain, client_key) = certificate("CP001");
+        let client_config = || ClientConfig {
+            tls: Some(
+                ocppx_client::TlsConfig::security_profile_3(
+                    root_store.clone(),
+                    client_chain.clone(),
+                    client_key.clone_key(),
+                )
+                .unwrap(),
+            ),
+            ..Default::default()
+        };
+
+        // The certificate of CP001 does not vouch for CP002.
+        assert!(
+            ChargePointClient::connect_with_config(&csms_url, "CP002", client_config())
+                .await
+                .is_err()
+        );
+        assert!(server.connected_charge_points().is_empty());
+
+        let client = ChargePointClient::connect_with_config(&csms_url, "CP001", client_config())
+            .await
+            .unwrap();
+
+        assert!(client
+            .send_heartbeat(HeartbeatRequest::builder().build())
+            .await
+            .is_ok());
+    }
+}
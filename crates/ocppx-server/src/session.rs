@@ -0,0 +1,286 @@
+use crate::{replies::Ticket, OcppVersion};
+use ocppx_rpc::PendingCalls;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+use tokio::sync::{broadcast, mpsc, Notify};
+use tokio_tungstenite::tungstenite::Message as Frame;
+
+/// Capacity of the channel of the [`SessionEvent`]s: a subscriber lagging
+/// behind by more events misses the oldest ones.
+const EVENTS_CAPACITY: usize = 256;
+
+/// Something that happened to a session, see [`SessionRegistry::subscribe`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionEvent {
+    /// A Charge Point has connected.
+    Opened {
+        charge_point_id: String,
+        session_id: u64,
+    },
+    /// A session has been taken over, and its connection is being closed.
+    ///
+    /// `by` is the session taking over, or `None` if the Charge Point has
+    /// reconnected to another node, see [`SessionRegistry::supersede`].
+    Superseded {
+        charge_point_id: String,
+        session_id: u64,
+        by: Option<u64>,
+    },
+    /// A Charge Point has disconnected.
+    Closed {
+        charge_point_id: String,
+        session_id: u64,
+    },
+}
+
+/// A frame to send to a Charge Point, with the unique ID of the `Call` it
+/// carries, or the ticket of the `Call` it answers, if any.
+pub(crate) struct Outgoing {
+    pub(crate) frame: Frame,
+    pub(crate) call: Option<String>,
+    pub(crate) answers: Option<Ticket>,
+}
+
+/// The connection of a Charge Point.
+pub(crate) struct Session {
+    pub(crate) id: u64,
+    pub(crate) version: OcppVersion,
+    pub(crate) outgoing: mpsc::Sender<Outgoing>,
+    pub(crate) pending_calls: Arc<PendingCalls>,
+    /// Held while a `Call` of a [`ChargePointHandle`] is outstanding.
+    ///
+    /// [`ChargePointHandle`]: crate::ChargePointHandle
+    pub(crate) calls: Arc<tokio::sync::Mutex<()>>,
+    superseded: Arc<Notify>,
+}
+
+/// The sessions of the Charge Points connected to a server, at most one per
+/// Charge Point identity.
+///
+/// A Charge Point reconnecting while its previous connection is still open,
+/// e.g. half-open after a network failure, takes it over: the previous
+/// connection is closed, its outstanding `Call`s fail with
+/// `Error::ConnectionClosed`, and the handler is not told that the Charge
+/// Point has disconnected.
+///
+/// Behind a load balancer, a Charge Point can reconnect to another node:
+/// [`SessionRegistry::supersede`] closes the session on this node, e.g.
+/// when the other nodes broadcast the [`SessionEvent::Opened`] events.
+pub struct SessionRegistry {
+    sessions: Mutex<HashMap<String, Session>>,
+    next_session_id: AtomicU64,
+    events: broadcast::Sender<SessionEvent>,
+}
+
+impl SessionRegistry {
+    pub(crate) fn new() -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+            next_session_id: AtomicU64::new(0),
+            events: broadcast::Sender::new(EVENTS_CAPACITY),
+        }
+    }
+
+    /// Receive the events of the sessions, from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<SessionEvent> {
+        self.events.subscribe()
+    }
+
+    /// The identities of the connected Charge Points.
+    pub fn charge_point_ids(&self) -> Vec<String> {
+        self.sessions.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// The ID of the session of `charge_point_id`, if it is connected.
+    pub fn session_id(&self, charge_point_id: &str) -> Option<u64> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(charge_point_id)
+            .map(|session| session.id)
+    }
+
+    /// The OCPP version negotiated by `charge_point_id`, if it is
+    /// connected.
+    pub fn version(&self, charge_point_id: &str) -> Option<OcppVersion> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(charge_point_id)
+            .map(|session| session.version)
+    }
+
+    /// Number of connected Charge Points.
+    pub fn len(&self) -> usize {
+        self.sessions.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Close the session of `charge_point_id`, because the Charge Point
+    /// has connected to another node. Return whether it had a session.
+    pub fn supersede(&self, charge_point_id: &str) -> bool {
+        let Some(session) = self.sessions.lock().unwrap().remove(charge_point_id) else {
+            return false;
+        };
+
+        session.superseded.notify_one();
+        self.emit(SessionEvent::Superseded {
+            charge_point_id: charge_point_id.to_owned(),
+            session_id: session.id,
+            by: None,
+        });
+
+        true
+    }
+
+    /// Open a session for `charge_point_id`, taking over its previous one.
+    /// The connection must be closed once the returned `Notify` is
+    /// notified.
+    pub(crate) fn open(
+        &self,
+        charge_point_id: &str,
+        version: OcppVersion,
+        outgoing: mpsc::Sender<Outgoing>,
+        pending_calls: Arc<PendingCalls>,
+    ) -> (u64, Arc<Notify>) {
+        let session_id = self.next_session_id.fetch_add(1, Ordering::Relaxed);
+        let superseded = Arc::new(Notify::new());
+
+        let previous = self.sessions.lock().unwrap().insert(
+            charge_point_id.to_owned(),
+            Session {
+                id: session_id,
+                version,
+                outgoing,
+                pending_calls,
+                calls: Arc::default(),
+                superseded: superseded.clone(),
+            },
+        );
+
+        if let Some(previous) = previous {
+            previous.superseded.notify_one();
+            self.emit(SessionEvent::Superseded {
+                charge_point_id: charge_point_id.to_owned(),
+                session_id: previous.id,
+                by: Some(session_id),
+            });
+        }
+
+        self.emit(SessionEvent::Opened {
+            charge_point_id: charge_point_id.to_owned(),
+            session_id,
+        });
+
+        (session_id, superseded)
+    }
+
+    /// Close the session `session_id` of `charge_point_id`. Return `false`
+    /// if it has been superseded in the meantime.
+    pub(crate) fn close(&self, charge_point_id: &str, session_id: u64) -> bool {
+        {
+            let mut sessions = self.sessions.lock().unwrap();
+
+            if !matches!(sessions.get(charge_point_id), Some(session) if session.id == session_id) {
+                return false;
+            }
+
+            sessions.remove(charge_point_id);
+        }
+
+        self.emit(SessionEvent::Closed {
+            charge_point_id: charge_point_id.to_owned(),
+            session_id,
+        });
+
+        true
+    }
+
+    /// Run `f` with the session of `charge_point_id`, if it is connected.
+    pub(crate) fn with_session<F, T>(&self, charge_point_id: &str, f: F) -> Option<T>
+    where
+        F: FnOnce(&Session) -> T,
+    {
+        self.sessions.lock().unwrap().get(charge_point_id).map(f)
+    }
+
+    fn emit(&self, event: SessionEvent) {
+        // Nobody listening is fine.
+        let _ = self.events.send(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_takeover() {
+        let registry = SessionRegistry::new();
+        let mut events = registry.subscribe();
+        let open = || {
+            let (outgoing, _) = mpsc::channel(1);
+
+            registry.open(
+                "CP001",
+                OcppVersion::V1_6,
+                outgoing,
+                Arc::new(PendingCalls::new(1, Duration::from_secs(1))),
+            )
+        };
+
+        let (first, first_superseded) = open();
+        let (second, _) = open();
+
+        // The previous session is told to close, even if it was not waiting
+        // yet.
+        first_superseded.notified().await;
+        assert_eq!(registry.session_id("CP001"), Some(second));
+
+        // Closing the superseded session keeps the new one.
+        assert!(!registry.close("CP001", first));
+        assert_eq!(registry.charge_point_ids(), ["CP001"]);
+        assert!(registry.close("CP001", second));
+        assert!(registry.is_empty());
+        assert!(!registry.supersede("CP001"));
+
+        let charge_point_id = || "CP001".to_owned();
+        let mut received = Vec::new();
+
+        while let Ok(event) = events.try_recv() {
+            received.push(event);
+        }
+
+        assert_eq!(
+            received,
+            [
+                SessionEvent::Opened {
+                    charge_point_id: charge_point_id(),
+                    session_id: first
+                },
+                SessionEvent::Superseded {
+                    charge_point_id: charge_point_id(),
+                    session_id: first,
+                    by: Some(second)
+                },
+                SessionEvent::Opened {
+                    charge_point_id: charge_point_id(),
+                    session_id: second
+                },
+                SessionEvent::Closed {
+                    charge_point_id: charge_point_id(),
+                    session_id: second
+                },
+            ]
+        );
+    }
+}
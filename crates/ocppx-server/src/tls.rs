@@ -0,0 +1,87 @@
+use crate::Result;
+use ocppx_pki::Certificate;
+use rustls::{
+    pki_types::{CertificateDer, PrivateKeyDer},
+    server::{danger::ClientCertVerifier, WebPkiClientVerifier},
+    RootCertStore, ServerConfig,
+};
+use std::sync::Arc;
+
+/// TLS configuration of a [`Server`][crate::Server].
+///
+/// [`Self::security_profile_2`] and [`Self::security_profile_3`] are presets
+/// for the OCPP security profiles; any other setup, e.g. selecting the
+/// certificate from the SNI, can be built with [`Self::from_rustls`].
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    pub(crate) config: Arc<ServerConfig>,
+}
+
+impl TlsConfig {
+    /// Security profile 2: TLS with Basic Authentication. The Central
+    /// System presents `certificate_chain`; the Charge Points authenticate
+    /// with their Basic Authentication credentials.
+    pub fn security_profile_2(
+        certificate_chain: Vec<CertificateDer<'static>>,
+        private_key: PrivateKeyDer<'static>,
+    ) -> Result<Self> {
+        Ok(Self::from_rustls(Arc::new(
+            ServerConfig::builder()
+                .with_no_client_auth()
+                .with_single_cert(certificate_chain, private_key)?,
+        )))
+    }
+
+    /// Security profile 3: TLS with client side certificates. The Central
+    /// System presents `certificate_chain`; the Charge Points must present
+    /// a certificate issued by one of `client_root_store`, whose common name
+    /// or one of whose DNS names is their identity.
+    pub fn security_profile_3(
+        certificate_chain: Vec<CertificateDer<'static>>,
+        private_key: PrivateKeyDer<'static>,
+        client_root_store: RootCertStore,
+    ) -> Result<Self> {
+        let verifier = WebPkiClientVerifier::builder(Arc::new(client_root_store)).build()?;
+
+        Self::with_client_certificate_verifier(certificate_chain, private_key, verifier)
+    }
+
+    /// Like [`Self::security_profile_3`], but the certificate chains of the
+    /// Charge Points are validated by `verifier`.
+    pub fn with_client_certificate_verifier(
+        certificate_chain: Vec<CertificateDer<'static>>,
+        private_key: PrivateKeyDer<'static>,
+        verifier: Arc<dyn ClientCertVerifier>,
+    ) -> Result<Self> {
+        Ok(Self::from_rustls(Arc::new(
+            ServerConfig::builder()
+                .with_client_cert_verifier(verifier)
+                .with_single_cert(certificate_chain, private_key)?,
+        )))
+    }
+
+    /// Use a custom `rustls` configuration.
+    pub fn from_rustls(config: Arc<ServerConfig>) -> Self {
+        Self { config }
+    }
+}
+
+/// The identities the client certificate of `connection` vouches for: the
+/// common names of its subject, and its DNS names. `None` if the client has
+/// not presented a certificate.
+pub(crate) fn client_identities(connection: &rustls::ServerConnection) -> Option<Vec<String>> {
+    let certificate = connection.peer_certificates()?.first()?;
+
+    // A certificate which cannot be parsed vouches for no identity.
+    let Some(certificate) = Certificate::from_der(certificate.to_vec()) else {
+        return Some(Vec::new());
+    };
+
+    Some(
+        certificate
+            .common_names
+            .into_iter()
+            .chain(certificate.dns_names)
+            .collect(),
+    )
+}
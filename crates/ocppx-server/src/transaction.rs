@@ -0,0 +1,432 @@
+use crate::{authorization::id_tag_info, AuthorizationProvider};
+use chrono::{DateTime, Utc};
+use ocppx_types::v1_6::{
+    AuthorizeRequest, AuthorizeResponse, IdTagInfo, IdTagInfoStatus, MeterValue,
+    MeterValuesRequest, StartTransactionRequest, StartTransactionResponse, StopTransactionReason,
+    StopTransactionRequest, StopTransactionResponse,
+};
+use std::{collections::BTreeMap, sync::Mutex};
+use thiserror::Error;
+
+/// A transaction rule of the specification has been broken.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum TransactionError {
+    #[error("the transaction `{0}` is unknown, or already stopped")]
+    UnknownTransaction(i32),
+
+    #[error("the transaction `{transaction_id}` belongs to another charge point or connector")]
+    Mismatch { transaction_id: i32 },
+}
+
+/// A transaction, ongoing or stopped.
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    pub id: i32,
+    pub charge_point_id: String,
+    pub connector_id: i32,
+    pub id_tag: String,
+    /// The status of the ID tag when the transaction started. The Charge
+    /// Point is expected to stop the transaction if it is not `Accepted`.
+    pub id_tag_status: IdTagInfoStatus,
+    pub reservation_id: Option<i32>,
+    /// Meter value at the start of the transaction, in Wh.
+    pub meter_start: i32,
+    pub started_at: DateTime<Utc>,
+    /// The meter values sent during the transaction, with `MeterValues`
+    /// or in the `transactionData` of `StopTransaction`.
+    pub meter_values: Vec<MeterValue>,
+    pub stop: Option<TransactionStop>,
+}
+
+/// How a transaction has stopped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionStop {
+    /// The ID tag that stopped the transaction, if any.
+    pub id_tag: Option<String>,
+    /// Meter value at the end of the transaction, in Wh.
+    pub meter_stop: i32,
+    pub stopped_at: DateTime<Utc>,
+    /// `Local` when omitted by the Charge Point.
+    pub reason: StopTransactionReason,
+}
+
+impl Transaction {
+    pub fn is_active(&self) -> bool {
+        self.stop.is_none()
+    }
+
+    /// The energy delivered during the transaction, in Wh, once it has
+    /// stopped.
+    pub fn energy(&self) -> Option<i32> {
+        self.stop
+            .as_ref()
+            .map(|stop| stop.meter_stop.saturating_sub(self.meter_start))
+    }
+}
+
+struct State {
+    next_transaction_id: i32,
+    transactions: BTreeMap<i32, Transaction>,
+}
+
+/// Track the transactions of the Charge Points, from their
+/// `StartTransaction`, `MeterValues` and `StopTransaction` requests.
+///
+/// The manager assigns the transaction IDs, authorizes the ID tags with an
+/// [`AuthorizationProvider`], and rejects the requests about transactions
+/// that have not started. It is meant to be called by a
+/// [`CsmsHandler`][crate::CsmsHandler], which turns its results into
+/// responses.
+pub struct TransactionManager<A = ()> {
+    authorization_provider: A,
+    state: Mutex<State>,
+}
+
+impl Default for TransactionManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TransactionManager {
+    /// Create a manager accepting all the ID tags.
+    pub fn new() -> Self {
+        Self::with_authorization_provider(())
+    }
+
+    /// Create a manager authorizing the ID tags with `authorizer`.
+    pub fn with_authorizer<F>(authorizer: F) -> TransactionManager<F>
+    where
+        F: Fn(&str) -> IdTagInfo + Send + Sync + 'static,
+    {
+        TransactionManager::with_authorization_provider(authorizer)
+    }
+}
+
+impl<A> TransactionManager<A>
+where
+    A: AuthorizationProvider,
+{
+    /// Create a manager authorizing the ID tags with
+    /// `authorization_provider`.
+    pub fn with_authorization_provider(authorization_provider: A) -> Self {
+        Self {
+            authorization_provider,
+            state: Mutex::new(State {
+                next_transaction_id: 1,
+                transactions: BTreeMap::new(),
+            }),
+        }
+    }
+
+    /// Handle an `Authorize`.
+    pub async fn authorize(&self, request: &AuthorizeRequest) -> AuthorizeResponse {
+        AuthorizeResponse::builder()
+            .id_tag_info(self.authorization_provider.authorize(&request.id_tag).await)
+            .build()
+    }
+
+    /// Handle a `StartTransaction`.
+    ///
+    /// A transaction ID is always assigned, even when the ID tag is not
+    /// accepted, as the Charge Point may have started charging already.
+    /// An ongoing transaction on the same connector is considered stopped,
+    /// since the Charge Point has obviously lost track of it.
+    pub async fn start(
+        &self,
+        charge_point_id: &str,
+        request: &StartTransactionRequest,
+    ) -> StartTransactionResponse {
+        let id_tag_info = self.authorization_provider.authorize(&request.id_tag).await;
+        let mut state = self.state.lock().unwrap();
+
+        for transaction in state.transactions.values_mut() {
+            if transaction.is_active()
+                && transaction.charge_point_id == charge_point_id
+                && transaction.connector_id == request.connector_id
+            {
+                transaction.stop = Some(TransactionStop {
+                    id_tag: None,
+                    meter_stop: request.meter_start,
+                    stopped_at: request.timestamp,
+                    reason: StopTransactionReason::Other,
+                });
+            }
+        }
+
+        let transaction_id = state.next_transaction_id;
+        state.next_transaction_id += 1;
+
+        state.transactions.insert(
+            transaction_id,
+            Transaction {
+                id: transaction_id,
+                charge_point_id: charge_point_id.to_owned(),
+                connector_id: request.connector_id,
+                id_tag: request.id_tag.as_str().to_owned(),
+                id_tag_status: id_tag_info.status,
+                reservation_id: request.reservation_id,
+                meter_start: request.meter_start,
+                started_at: request.timestamp,
+                meter_values: Vec::new(),
+                stop: None,
+            },
+        );
+
+        StartTransactionResponse::builder()
+            .id_tag_info(id_tag_info)
+            .transaction_id(transaction_id)
+            .build()
+    }
+
+    /// Handle a `MeterValues`. The meter values are recorded in the
+    /// transaction, if any.
+    pub fn meter_values(
+        &self,
+        charge_point_id: &str,
+        request: &MeterValuesRequest,
+    ) -> Result<(), TransactionError> {
+        let Some(transaction_id) = request.transaction_id else {
+            return Ok(());
+        };
+
+        let mut state = self.state.lock().unwrap();
+        let transaction = active_transaction(&mut state, charge_point_id, transaction_id)?;
+
+        if transaction.connector_id != request.connector_id {
+            return Err(TransactionError::Mismatch { transaction_id });
+        }
+
+        transaction
+            .meter_values
+            .extend(request.meter_value.iter().cloned());
+
+        Ok(())
+    }
+
+    /// Handle a `StopTransaction`. The ID tag, if any, is authorized again
+    /// when it differs from the one that started the transaction.
+    pub async fn stop(
+        &self,
+        charge_point_id: &str,
+        request: &StopTransactionRequest,
+    ) -> Result<StopTransactionResponse, TransactionError> {
+        let start_id_tag = {
+            let mut state = self.state.lock().unwrap();
+
+            active_transaction(&mut state, charge_point_id, request.transaction_id)?
+                .id_tag
+                .clone()
+        };
+
+        let id_tag_info = match request.id_tag.as_deref() {
+            Some(id_tag) if id_tag == start_id_tag => Some(id_tag_info(IdTagInfoStatus::Accepted)),
+            Some(id_tag) => Some(self.authorization_provider.authorize(id_tag).await),
+            None => None,
+        };
+
+        let mut state = self.state.lock().unwrap();
+        let transaction = active_transaction(&mut state, charge_point_id, request.transaction_id)?;
+
+        transaction
+            .meter_values
+            .extend(request.transaction_data.iter().flatten().map(|data| {
+                MeterValue::builder()
+                    .sampled_value(data.sampled_value.clone())
+                    .timestamp(data.timestamp)
+                    .build()
+            }));
+        transaction.stop = Some(TransactionStop {
+            id_tag: request.id_tag.as_deref().map(str::to_owned),
+            meter_stop: request.meter_stop,
+            stopped_at: request.timestamp,
+            reason: request.reason.unwrap_or(StopTransactionReason::Local),
+        });
+
+        Ok(StopTransactionResponse::builder()
+            .id_tag_info_opt(id_tag_info)
+            .build())
+    }
+
+    /// A transaction, ongoing or stopped.
+    pub fn transaction(&self, transaction_id: i32) -> Option<Transaction> {
+        self.state
+            .lock()
+            .unwrap()
+            .transactions
+            .get(&transaction_id)
+            .cloned()
+    }
+
+    /// The ongoing transactions, of all the Charge Points.
+    pub fn active_transactions(&self) -> Vec<Transaction> {
+        self.transactions(|transaction| transaction.is_active())
+    }
+
+    /// The ongoing transactions of `charge_point_id`.
+    pub fn active_transactions_of(&self, charge_point_id: &str) -> Vec<Transaction> {
+        self.transactions(|transaction| {
+            transaction.is_active() && transaction.charge_point_id == charge_point_id
+        })
+    }
+
+    /// The stopped transactions, of all the Charge Points.
+    pub fn history(&self) -> Vec<Transaction> {
+        self.transactions(|transaction| !transaction.is_active())
+    }
+
+    /// The transactions matching `filter`, ordered by ID.
+    pub fn transactions<F>(&self, filter: F) -> Vec<Transaction>
+    where
+        F: Fn(&Transaction) -> bool,
+    {
+        self.state
+            .lock()
+            .unwrap()
+            .transactions
+            .values()
+            .filter(|transaction| filter(transaction))
+            .cloned()
+            .collect()
+    }
+}
+
+fn active_transaction<'a>(
+    state: &'a mut State,
+    charge_point_id: &str,
+    transaction_id: i32,
+) -> Result<&'a mut Transaction, TransactionError> {
+    match state.transactions.get_mut(&transaction_id) {
+        Some(transaction) if transaction.is_active() => {
+            if transaction.charge_point_id != charge_point_id {
+                return Err(TransactionError::Mismatch { transaction_id });
+            }
+
+            Ok(transaction)
+        }
+        _ => Err(TransactionError::UnknownTransaction(transaction_id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ocppx_types::{v1_6::SampledValue, IdTag};
+
+    #[tokio::test]
+    async fn test_transaction_lifecycle() {
+        let manager = TransactionManager::with_authorizer(|id_tag| {
+            IdTagInfo::builder()
+                .status(if id_tag == "BLOCKED" {
+                    IdTagInfoStatus::Blocked
+                } else {
+                    IdTagInfoStatus::Accepted
+                })
+                .build()
+        });
+        let timestamp: DateTime<Utc> = "2013-02-01T20:53:32.486Z".parse().unwrap();
+
+        let response = manager
+            .start(
+                "CP001",
+                &StartTransactionRequest::builder()
+                    .connector_id(1)
+                    .id_tag(IdTag::try_from("TAG").unwrap())
+                    .meter_start(100)
+                    .timestamp(timestamp)
+                    .build(),
+            )
+            .await;
+        assert_eq!(response.id_tag_info.status, IdTagInfoStatus::Accepted);
+        let transaction_id = response.transaction_id;
+
+        manager
+            .meter_values(
+                "CP001",
+                &MeterValuesRequest::builder()
+                    .connector_id(1)
+                    .transaction_id(transaction_id)
+                    .meter_value(vec![MeterValue::builder()
+                        .timestamp(timestamp)
+                        .sampled_value(vec![SampledValue::builder().value("150").build()])
+                        .build()])
+                    .build(),
+            )
+            .unwrap();
+        assert_eq!(manager.active_transactions_of("CP001").len(), 1);
+        assert!(manager.active_transactions_of("CP002").is_empty());
+
+        let stop = StopTransactionRequest::builder()
+            .transaction_id(transaction_id)
+            .id_tag(IdTag::try_from("BLOCKED").unwrap())
+            .meter_stop(1100)
+            .timestamp(timestamp)
+            .build();
+
+        assert_eq!(
+            manager.stop("CP002", &stop).await.unwrap_err(),
+            TransactionError::Mismatch { transaction_id }
+        );
+        assert_eq!(
+            manager
+                .stop("CP001", &stop)
+                .await
+                .unwrap()
+                .id_tag_info
+                .unwrap()
+                .status,
+            IdTagInfoStatus::Blocked
+        );
+
+        // No stop without a start.
+        assert_eq!(
+            manager.stop("CP001", &stop).await.unwrap_err(),
+            TransactionError::UnknownTransaction(transaction_id)
+        );
+
+        let transaction = manager.transaction(transaction_id).unwrap();
+        assert_eq!(transaction.energy(), Some(1000));
+        assert_eq!(transaction.meter_values.len(), 1);
+        assert_eq!(
+            transaction.stop.unwrap().reason,
+            StopTransactionReason::Local
+        );
+        assert!(manager.active_transactions().is_empty());
+        assert_eq!(manager.history().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_meter_values_do_not_overflow() {
+        let manager = TransactionManager::new();
+        let timestamp: DateTime<Utc> = "2013-02-01T20:53:32.486Z".parse().unwrap();
+
+        // A hostile Charge Point goes from the highest meter value to the
+        // lowest one.
+        let transaction_id = manager
+            .start(
+                "CP001",
+                &StartTransactionRequest::builder()
+                    .connector_id(1)
+                    .id_tag(IdTag::try_from("TAG").unwrap())
+                    .meter_start(i32::MAX)
+                    .timestamp(timestamp)
+                    .build(),
+            )
+            .await
+            .transaction_id;
+        manager
+            .stop(
+                "CP001",
+                &StopTransactionRequest::builder()
+                    .transaction_id(transaction_id)
+                    .meter_stop(i32::MIN)
+                    .timestamp(timestamp)
+                    .build(),
+            )
+            .await
+            .unwrap();
+
+        let transaction = manager.transaction(transaction_id).unwrap();
+        assert_eq!(transaction.energy(), Some(i32::MIN));
+    }
+}
@@ -0,0 +1,210 @@
+use crate::CsmsHandler;
+use ocppx_rpc::{Call, CallError, CallResult, ErrorCode};
+use std::{collections::HashMap, fmt, str::FromStr, sync::Mutex};
+
+/// A version of OCPP-J, negotiated with the Charge Points through the
+/// WebSocket subprotocol, see [`ServerConfig::versions`].
+///
+/// The versions are ordered from the oldest to the newest.
+///
+/// [`ServerConfig::versions`]: crate::ServerConfig::versions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OcppVersion {
+    V1_6,
+    V2_0_1,
+    V2_1,
+}
+
+impl OcppVersion {
+    /// The WebSocket subprotocol of the version, e.g. `ocpp1.6`.
+    pub fn subprotocol(self) -> &'static str {
+        match self {
+            Self::V1_6 => "ocpp1.6",
+            Self::V2_0_1 => "ocpp2.0.1",
+            Self::V2_1 => "ocpp2.1",
+        }
+    }
+
+    /// The highest version of `supported` among the subprotocols `offered`
+    /// by a Charge Point, e.g. the values of its `Sec-WebSocket-Protocol`
+    /// headers.
+    pub fn negotiate<'a, I>(offered: I, supported: &[Self]) -> Option<Self>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        offered
+            .into_iter()
+            .flat_map(|value| value.split(','))
+            .filter_map(|subprotocol| subprotocol.trim().parse().ok())
+            .filter(|version| supported.contains(version))
+            .max()
+    }
+}
+
+impl fmt::Display for OcppVersion {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(self.subprotocol())
+    }
+}
+
+/// The subprotocol is not one of an [`OcppVersion`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownSubprotocolError(pub String);
+
+impl fmt::Display for UnknownSubprotocolError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "unknown subprotocol `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownSubprotocolError {}
+
+impl FromStr for OcppVersion {
+    type Err = UnknownSubprotocolError;
+
+    fn from_str(subprotocol: &str) -> Result<Self, Self::Err> {
+        Ok(match subprotocol {
+            "ocpp1.6" => Self::V1_6,
+            "ocpp2.0.1" => Self::V2_0_1,
+            "ocpp2.1" => Self::V2_1,
+            _ => return Err(UnknownSubprotocolError(subprotocol.to_owned())),
+        })
+    }
+}
+
+/// A handler dispatching the Charge Points to a handler per
+/// [`OcppVersion`], the one they have negotiated.
+///
+/// [`Unsupported`] stands for the versions without a handler, which are not
+/// to be in [`ServerConfig::versions`].
+///
+/// The `Call`s of the Charge Points whose version is not known, e.g. in a
+/// [`replay()`][crate::replay], are dispatched to the OCPP 1.6 handler.
+///
+/// [`ServerConfig::versions`]: crate::ServerConfig::versions
+pub struct VersionRouter<H16, H201 = Unsupported, H21 = Unsupported> {
+    v1_6: H16,
+    v2_0_1: H201,
+    v2_1: H21,
+    versions: Mutex<HashMap<String, OcppVersion>>,
+}
+
+impl<H16, H201, H21> VersionRouter<H16, H201, H21>
+where
+    H16: CsmsHandler,
+    H201: CsmsHandler,
+    H21: CsmsHandler,
+{
+    pub fn new(v1_6: H16, v2_0_1: H201, v2_1: H21) -> Self {
+        Self {
+            v1_6,
+            v2_0_1,
+            v2_1,
+            versions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The version negotiated by the connected Charge Point
+    /// `charge_point_id`.
+    pub fn version(&self, charge_point_id: &str) -> Option<OcppVersion> {
+        self.versions.lock().unwrap().get(charge_point_id).copied()
+    }
+}
+
+impl<H16, H201, H21> CsmsHandler for VersionRouter<H16, H201, H21>
+where
+    H16: CsmsHandler,
+    H201: CsmsHandler,
+    H21: CsmsHandler,
+{
+    async fn handle_call(
+        &self,
+        charge_point_id: &str,
+        call: Call,
+    ) -> Result<CallResult, CallError> {
+        match self.version(charge_point_id).unwrap_or(OcppVersion::V1_6) {
+            OcppVersion::V1_6 => self.v1_6.handle_call(charge_point_id, call).await,
+            OcppVersion::V2_0_1 => self.v2_0_1.handle_call(charge_point_id, call).await,
+            OcppVersion::V2_1 => self.v2_1.handle_call(charge_point_id, call).await,
+        }
+    }
+
+    async fn connected_with_version(&self, charge_point_id: &str, version: OcppVersion) {
+        self.versions
+            .lock()
+            .unwrap()
+            .insert(charge_point_id.to_owned(), version);
+
+        match version {
+            OcppVersion::V1_6 => {
+                self.v1_6
+                    .connected_with_version(charge_point_id, version)
+                    .await
+            }
+            OcppVersion::V2_0_1 => {
+                self.v2_0_1
+                    .connected_with_version(charge_point_id, version)
+                    .await
+            }
+            OcppVersion::V2_1 => {
+                self.v2_1
+                    .connected_with_version(charge_point_id, version)
+                    .await
+            }
+        }
+    }
+
+    async fn disconnected(&self, charge_point_id: &str) {
+        let version = self.versions.lock().unwrap().remove(charge_point_id);
+
+        match version {
+            Some(OcppVersion::V1_6) => self.v1_6.disconnected(charge_point_id).await,
+            Some(OcppVersion::V2_0_1) => self.v2_0_1.disconnected(charge_point_id).await,
+            Some(OcppVersion::V2_1) => self.v2_1.disconnected(charge_point_id).await,
+            None => {}
+        }
+    }
+}
+
+/// The handler of a version that is not supported by a [`VersionRouter`]:
+/// the `Call`s are answered with a `NotSupported` `CallError`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Unsupported;
+
+impl CsmsHandler for Unsupported {
+    async fn handle_call(
+        &self,
+        _charge_point_id: &str,
+        call: Call,
+    ) -> Result<CallResult, CallError> {
+        Err(CallError::new(
+            call.unique_id,
+            ErrorCode::NotSupported,
+            "the OCPP version is not supported",
+            None,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        let supported = [OcppVersion::V1_6, OcppVersion::V2_0_1];
+
+        assert_eq!(
+            OcppVersion::negotiate(["ocpp1.6, ocpp2.0.1", "ocpp2.1"], &supported),
+            Some(OcppVersion::V2_0_1)
+        );
+        assert_eq!(
+            OcppVersion::negotiate(["ocpp1.6"], &supported),
+            Some(OcppVersion::V1_6)
+        );
+        assert_eq!(
+            OcppVersion::negotiate(["ocpp2.1", "ocpp1.5"], &supported),
+            None
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{DiagnosticsUploader, EvConfig, NetworkUploader};
+use ocppx_client::{ClockSource, SystemClock};
+use std::{sync::Arc, time::Duration};
+
+/// Configuration of a [`Simulator`][crate::Simulator].
+#[derive(Debug, Clone)]
+pub struct SimulatorConfig {
+    /// URL of the Central System, without the Charge Point identity.
+    pub csms_url: String,
+    /// Identity of the Charge Point.
+    pub charge_point_id: String,
+    pub vendor: String,
+    pub model: String,
+    pub firmware_version: Option<String>,
+    /// Number of connectors, numbered from 1.
+    pub connectors: i32,
+    /// Maximum power delivered by a charging connector, in W.
+    pub charging_power: u32,
+    /// Number of phases wired to the Charge Point, 1 to 3.
+    pub phases: i32,
+    /// The EV plugged in the connectors.
+    pub ev: EvConfig,
+    /// Interval between two `MeterValues` of a charging connector.
+    pub meter_values_interval: Duration,
+    /// How long a connector must stay in a new state before it is notified
+    /// with a `StatusNotification`, the initial `MinimumStatusDuration`.
+    pub minimum_status_duration: Duration,
+    /// Behaviour of the firmware updates.
+    pub firmware: FirmwareConfig,
+    /// Uploads the diagnostics requested with `GetDiagnostics`.
+    pub diagnostics_uploader: Arc<dyn DiagnosticsUploader>,
+    /// Interval between two upload attempts, when the `GetDiagnostics`
+    /// does not provide one.
+    pub diagnostics_retry_interval: Duration,
+    /// The clock of the Charge Point, e.g. a
+    /// [`SkewedClock`][ocppx_client::SkewedClock] to simulate a wrong
+    /// clock. The timestamps sent are still adjusted to the clock of the
+    /// Central System.
+    pub clock: Arc<dyn ClockSource>,
+}
+
+impl SimulatorConfig {
+    pub fn new(csms_url: impl Into<String>, charge_point_id: impl Into<String>) -> Self {
+        Self {
+            csms_url: csms_url.into(),
+            charge_point_id: charge_point_id.into(),
+            vendor: "ocppx".to_owned(),
+            model: "Simulator".to_owned(),
+            firmware_version: None,
+            connectors: 1,
+            charging_power: 11_000,
+            phases: 3,
+            ev: EvConfig::default(),
+            meter_values_interval: Duration::from_secs(60),
+            minimum_status_duration: Duration::ZERO,
+            firmware: FirmwareConfig::default(),
+            diagnostics_uploader: Arc::new(NetworkUploader::default()),
+            diagnostics_retry_interval: Duration::from_secs(30),
+            clock: Arc::new(SystemClock),
+        }
+    }
+}
+
+/// How a firmware update, requested with `UpdateFirmware`, behaves.
+///
+/// Failures can be injected to exercise how the Central System handles
+/// them.
+#[derive(Debug, Clone)]
+pub struct FirmwareConfig {
+    /// Time to download the firmware.
+    pub download_duration: Duration,
+    /// Time to install the firmware.
+    pub install_duration: Duration,
+    /// Number of download attempts that fail before one succeeds. The
+    /// download is retried as many times as the `UpdateFirmware` allows.
+    pub download_failures: u32,
+    /// Whether the installation fails.
+    pub install_fails: bool,
+    /// Interval between two download attempts, when the `UpdateFirmware`
+    /// does not provide one.
+    pub retry_interval: Duration,
+}
+
+impl Default for FirmwareConfig {
+    fn default() -> Self {
+        Self {
+            download_duration: Duration::from_secs(10),
+            install_duration: Duration::from_secs(10),
+            download_failures: 0,
+            install_fails: false,
+            retry_interval: Duration::from_secs(30),
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use crate::Ev;
+use chrono::{DateTime, Utc};
+use ocppx_types::{
+    v1_6::{StatusNotificationErrorCode, StatusNotificationStatus},
+    CiString255, CiString50, IdTag,
+};
+use serde::{Deserialize, Serialize};
+
+/// The status of a connector, as sent in a `StatusNotification`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConnectorStatus {
+    Available,
+    Preparing,
+    Charging,
+    SuspendedEVSE,
+    SuspendedEV,
+    Finishing,
+    Reserved,
+    Unavailable,
+    Faulted,
+}
+
+impl From<ConnectorStatus> for StatusNotificationStatus {
+    fn from(status: ConnectorStatus) -> Self {
+        match status {
+            ConnectorStatus::Available => Self::Available,
+            ConnectorStatus::Preparing => Self::Preparing,
+            ConnectorStatus::Charging => Self::Charging,
+            ConnectorStatus::SuspendedEVSE => Self::SuspendedEVSE,
+            ConnectorStatus::SuspendedEV => Self::SuspendedEV,
+            ConnectorStatus::Finishing => Self::Finishing,
+            ConnectorStatus::Reserved => Self::Reserved,
+            ConnectorStatus::Unavailable => Self::Unavailable,
+            ConnectorStatus::Faulted => Self::Faulted,
+        }
+    }
+}
+
+/// The error of a connector, as sent in a `StatusNotification`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChargePointErrorCode {
+    ConnectorLockFailure,
+    EVCommunicationError,
+    GroundFailure,
+    HighTemperature,
+    InternalError,
+    LocalListConflict,
+    NoError,
+    OtherError,
+    OverCurrentFailure,
+    PowerMeterFailure,
+    PowerSwitchFailure,
+    ReaderFailure,
+    ResetFailure,
+    UnderVoltage,
+    OverVoltage,
+    WeakSignal,
+}
+
+impl From<ChargePointErrorCode> for StatusNotificationErrorCode {
+    fn from(error_code: ChargePointErrorCode) -> Self {
+        match error_code {
+            ChargePointErrorCode::ConnectorLockFailure => Self::ConnectorLockFailure,
+            ChargePointErrorCode::EVCommunicationError => Self::EVCommunicationError,
+            ChargePointErrorCode::GroundFailure => Self::GroundFailure,
+            ChargePointErrorCode::HighTemperature => Self::HighTemperature,
+            ChargePointErrorCode::InternalError => Self::InternalError,
+            ChargePointErrorCode::LocalListConflict => Self::LocalListConflict,
+            ChargePointErrorCode::NoError => Self::NoError,
+            ChargePointErrorCode::OtherError => Self::OtherError,
+            ChargePointErrorCode::OverCurrentFailure => Self::OverCurrentFailure,
+            ChargePointErrorCode::PowerMeterFailure => Self::PowerMeterFailure,
+            ChargePointErrorCode::PowerSwitchFailure => Self::PowerSwitchFailure,
+            ChargePointErrorCode::ReaderFailure => Self::ReaderFailure,
+            ChargePointErrorCode::ResetFailure => Self::ResetFailure,
+            ChargePointErrorCode::UnderVoltage => Self::UnderVoltage,
+            ChargePointErrorCode::OverVoltage => Self::OverVoltage,
+            ChargePointErrorCode::WeakSignal => Self::WeakSignal,
+        }
+    }
+}
+
+/// An error reported by a connector, with the details of its vendor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectorError {
+    pub error_code: ChargePointErrorCode,
+    /// Free-form information about the error.
+    pub info: Option<CiString50>,
+    pub vendor_id: Option<CiString255>,
+    /// The error code of the vendor, see `vendor_id`.
+    pub vendor_error_code: Option<CiString50>,
+}
+
+impl ConnectorError {
+    pub fn new(error_code: ChargePointErrorCode) -> Self {
+        Self {
+            error_code,
+            info: None,
+            vendor_id: None,
+            vendor_error_code: None,
+        }
+    }
+
+    pub fn info(mut self, info: CiString50) -> Self {
+        self.info = Some(info);
+
+        self
+    }
+
+    pub fn vendor_error(mut self, vendor_id: CiString255, vendor_error_code: CiString50) -> Self {
+        self.vendor_id = Some(vendor_id);
+        self.vendor_error_code = Some(vendor_error_code);
+
+        self
+    }
+}
+
+/// What a `StatusNotification` tells about a connector: its status, and
+/// its error, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectorState {
+    pub status: ConnectorStatus,
+    pub error: Option<ConnectorError>,
+}
+
+impl From<ConnectorStatus> for ConnectorState {
+    fn from(status: ConnectorStatus) -> Self {
+        Self {
+            status,
+            error: None,
+        }
+    }
+}
+
+/// What happens to a connector.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConnectorEvent {
+    /// A cable is plugged in.
+    PlugIn,
+    /// A transaction has started.
+    StartCharging,
+    /// The transaction has stopped.
+    StopCharging,
+    /// The cable is unplugged.
+    Unplug,
+    /// The connector is reserved.
+    Reserve,
+    /// The reservation has been used, cancelled, or has expired.
+    ReservationEnded,
+    /// The connector is made unavailable, with a `ChangeAvailability` to
+    /// `Inoperative`.
+    MakeInoperative,
+    /// The connector is made available again, with a `ChangeAvailability`
+    /// to `Operative`.
+    MakeOperative,
+    /// The connector has an error that makes it unusable.
+    Fault,
+    /// The error of the connector has been fixed.
+    FaultCleared,
+}
+
+impl ConnectorStatus {
+    /// The status after `event`, or `None` if the transition is not
+    /// allowed.
+    pub fn next(self, event: ConnectorEvent) -> Option<Self> {
+        use ConnectorEvent::*;
+        use ConnectorStatus::*;
+
+        Some(match (self, event) {
+            (Available | Reserved, PlugIn) => Preparing,
+            (Available | Preparing | Reserved, StartCharging) => Charging,
+            (Charging | SuspendedEV | SuspendedEVSE, StopCharging) => Finishing,
+            (Preparing | Finishing, Unplug) => Available,
+            (Available, Reserve) => Reserved,
+            (Reserved, ReservationEnded) => Available,
+            (Available | Preparing | Finishing | Reserved, MakeInoperative) => Unavailable,
+            (Unavailable, Unplug) => Unavailable,
+            (Unavailable, MakeOperative) => Available,
+            (Available | Preparing | Finishing | Reserved, Fault) => Faulted,
+            (Faulted, Unplug) => Faulted,
+            (Faulted, FaultCleared) => Available,
+            _ => return None,
+        })
+    }
+}
+
+/// An ongoing transaction.
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    pub id: i32,
+    pub id_tag: IdTag,
+    /// Meter value at the start of the transaction, in Wh.
+    pub meter_start: i32,
+    pub started_at: DateTime<Utc>,
+}
+
+/// A connector of the simulated Charge Point.
+#[derive(Debug, Clone)]
+pub struct Connector {
+    pub id: i32,
+    pub status: ConnectorStatus,
+    /// Energy meter, in Wh.
+    pub meter: i32,
+    /// Maximum power delivered while charging, in W: the one of the
+    /// configuration when `None`.
+    pub charging_power: Option<u32>,
+    /// The EV plugged in, if any.
+    pub ev: Option<Ev>,
+    pub transaction: Option<Transaction>,
+    /// The event deferred until the end of the ongoing transaction, e.g. a
+    /// `ChangeAvailability` to `Inoperative` answered `Scheduled`.
+    pub deferred: Option<ConnectorEvent>,
+    /// The error of a `Faulted` connector.
+    pub error: Option<ConnectorError>,
+}
+
+impl Connector {
+    pub(crate) fn new(id: i32) -> Self {
+        Self {
+            id,
+            status: ConnectorStatus::Available,
+            meter: 0,
+            charging_power: None,
+            ev: None,
+            transaction: None,
+            deferred: None,
+            error: None,
+        }
+    }
+
+    /// The state notified in a `StatusNotification`.
+    pub fn state(&self) -> ConnectorState {
+        ConnectorState {
+            status: self.status,
+            error: self.error.clone(),
+        }
+    }
+}
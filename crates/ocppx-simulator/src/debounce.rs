@@ -0,0 +1,111 @@
+use crate::ConnectorState;
+use std::{collections::HashMap, time::Duration};
+use tokio::time::Instant;
+
+/// What to do with the state held back for a connector, see
+/// [`StatusDebouncer::release`].
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Release {
+    /// Notify the state, which has lasted long enough.
+    Now(ConnectorState),
+    /// Ask again at this instant.
+    Later(Instant),
+    /// No state is held back.
+    Nothing,
+}
+
+/// Condense the `StatusNotification`s of the connectors whose state flaps:
+/// a new state is only notified once it has lasted the
+/// `MinimumStatusDuration`, and not at all if the connector is back to the
+/// state notified last.
+#[derive(Debug, Default)]
+pub(crate) struct StatusDebouncer {
+    /// The state notified last of each connector.
+    sent: HashMap<i32, ConnectorState>,
+    /// The state held back of each connector, with when it has been
+    /// reached.
+    held: HashMap<i32, (ConnectorState, Instant)>,
+}
+
+impl StatusDebouncer {
+    /// `state` of `connector_id` is notified right away.
+    pub(crate) fn sent(&mut self, connector_id: i32, state: ConnectorState) {
+        self.held.remove(&connector_id);
+        self.sent.insert(connector_id, state);
+    }
+
+    /// `connector_id` has reached `state` at `now`: hold it back. Return
+    /// whether nothing was held back for the connector, i.e. whether its
+    /// release must be scheduled.
+    pub(crate) fn hold(&mut self, connector_id: i32, state: ConnectorState, now: Instant) -> bool {
+        if self.sent.get(&connector_id) == Some(&state) {
+            self.held.remove(&connector_id);
+
+            return false;
+        }
+
+        self.held.insert(connector_id, (state, now)).is_none()
+    }
+
+    /// The state held back for `connector_id`, if it has lasted `minimum`
+    /// at `now`.
+    pub(crate) fn release(
+        &mut self,
+        connector_id: i32,
+        now: Instant,
+        minimum: Duration,
+    ) -> Release {
+        let Some((_, reached_at)) = self.held.get(&connector_id) else {
+            return Release::Nothing;
+        };
+
+        if *reached_at + minimum > now {
+            return Release::Later(*reached_at + minimum);
+        }
+
+        let (state, _) = self.held.remove(&connector_id).unwrap();
+        self.sent.insert(connector_id, state.clone());
+
+        Release::Now(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChargePointErrorCode, ConnectorError, ConnectorStatus};
+
+    #[test]
+    fn test_status_debouncer() {
+        let mut debouncer = StatusDebouncer::default();
+        let now = Instant::now();
+        let minimum = Duration::from_secs(5);
+        let at = |seconds| now + Duration::from_secs(seconds);
+
+        debouncer.sent(1, ConnectorStatus::Available.into());
+        assert_eq!(debouncer.release(1, now, minimum), Release::Nothing);
+
+        // A flap back to the state notified last is not notified.
+        assert!(debouncer.hold(1, ConnectorStatus::Preparing.into(), now));
+        assert!(!debouncer.hold(1, ConnectorStatus::Available.into(), at(1)));
+        assert_eq!(debouncer.release(1, at(5), minimum), Release::Nothing);
+
+        // Only the last state of a storm is notified, once it has lasted.
+        assert!(debouncer.hold(1, ConnectorStatus::Preparing.into(), at(10)));
+        let faulted = ConnectorState {
+            status: ConnectorStatus::Faulted,
+            error: Some(ConnectorError::new(ChargePointErrorCode::GroundFailure)),
+        };
+        assert!(!debouncer.hold(1, faulted.clone(), at(12)));
+        assert_eq!(
+            debouncer.release(1, at(15), minimum),
+            Release::Later(at(17))
+        );
+        assert_eq!(
+            debouncer.release(1, at(17), minimum),
+            Release::Now(faulted.clone())
+        );
+        assert_eq!(debouncer.release(1, at(17), minimum), Release::Nothing);
+        assert!(!debouncer.hold(1, faulted, at(20)));
+    }
+}
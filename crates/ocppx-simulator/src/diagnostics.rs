@@ -0,0 +1,672 @@
+#[cfg(feature = "tls")]
+use std::sync::Arc;
+use std::{fmt, future::Future, io, pin::Pin};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+use url::Url;
+
+/// The future returned by [`DiagnosticsUploader::upload`].
+pub type UploadFuture<'a> = Pin<Box<dyn Future<Output = io::Result<()>> + Send + 'a>>;
+
+/// Upload a diagnostics archive, requested with `GetDiagnostics`.
+pub trait DiagnosticsUploader: Send + Sync {
+    /// Upload `archive` as `file_name` in the directory `location`.
+    fn upload<'a>(
+        &'a self,
+        location: &'a Url,
+        file_name: &'a str,
+        archive: Vec<u8>,
+    ) -> UploadFuture<'a>;
+}
+
+/// The default [`DiagnosticsUploader`]: it uploads to `ftp://` URLs, in
+/// passive mode, and to `http://` URLs, with a `PUT` request.
+///
+/// With the `tls` feature, it uploads to `ftps://` URLs too, with explicit
+/// FTPS (`AUTH TLS`, on the port 21 by default), and to `https://` URLs,
+/// given the root certificates with [`Self::tls`]. Otherwise, they fail
+/// with [`io::ErrorKind::Unsupported`].
+#[derive(Debug, Clone, Default)]
+pub struct NetworkUploader {
+    #[cfg(feature = "tls")]
+    tls: Option<Arc<ocppx_client::rustls::ClientConfig>>,
+}
+
+impl NetworkUploader {
+    /// Upload to the `ftps://` and `https://` URLs with `config`.
+    #[cfg(feature = "tls")]
+    pub fn tls(mut self, config: Arc<ocppx_client::rustls::ClientConfig>) -> Self {
+        self.tls = Some(config);
+
+        self
+    }
+
+    /// The TLS connector of the `ftps://` and `https://` URLs.
+    #[cfg(feature = "tls")]
+    fn connector(&self, target: &Url) -> io::Result<Tls> {
+        use ocppx_client::rustls::pki_types::ServerName;
+
+        let config = self.tls.clone().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "no TLS configuration for the `{}` locations",
+                    target.scheme()
+                ),
+            )
+        })?;
+        let (host, _) = address(target)?;
+        let server_name = ServerName::try_from(host.to_owned())
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+
+        Ok(Tls {
+            connector: tokio_rustls::TlsConnector::from(config),
+            server_name,
+        })
+    }
+
+    #[cfg(not(feature = "tls"))]
+    fn connector(&self, target: &Url) -> io::Result<Tls> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("`{}` locations need the `tls` feature", target.scheme()),
+        ))
+    }
+}
+
+impl DiagnosticsUploader for NetworkUploader {
+    fn upload<'a>(
+        &'a self,
+        location: &'a Url,
+        file_name: &'a str,
+        archive: Vec<u8>,
+    ) -> UploadFuture<'a> {
+        Box::pin(async move {
+            let target = target(location, file_name)?;
+
+            match location.scheme() {
+                "ftp" => upload_ftp(&target, &archive, None).await,
+                "ftps" => upload_ftp(&target, &archive, Some(self.connector(&target)?)).await,
+                "http" => upload_http(&target, &archive, None).await,
+                "https" => upload_http(&target, &archive, Some(self.connector(&target)?)).await,
+                scheme => Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("`{scheme}` locations are not supported"),
+                )),
+            }
+        })
+    }
+}
+
+impl fmt::Debug for dyn DiagnosticsUploader {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("DiagnosticsUploader")
+    }
+}
+
+/// A connection, over TLS or not.
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<S> Stream for S where S: AsyncRead + AsyncWrite + Unpin + Send {}
+
+type BoxStream = Box<dyn Stream>;
+
+/// Secure the connections to a server.
+#[cfg(feature = "tls")]
+struct Tls {
+    connector: tokio_rustls::TlsConnector,
+    server_name: ocppx_client::rustls::pki_types::ServerName<'static>,
+}
+
+/// Without the `tls` feature, no connection can be secured.
+#[cfg(not(feature = "tls"))]
+enum Tls {}
+
+impl Tls {
+    #[cfg(feature = "tls")]
+    async fn connect(&self, stream: BoxStream) -> io::Result<BoxStream> {
+        Ok(Box::new(
+            self.connector
+                .connect(self.server_name.clone(), stream)
+                .await?,
+        ))
+    }
+
+    #[cfg(not(feature = "tls"))]
+    async fn connect(&self, _stream: BoxStream) -> io::Result<BoxStream> {
+        match *self {}
+    }
+}
+
+/// The URL of `file_name` in the directory `location`.
+fn target(location: &Url, file_name: &str) -> io::Result<Url> {
+    let mut directory = location.clone();
+
+    if !directory.path().ends_with('/') {
+        directory.set_path(&format!("{}/", directory.path()));
+    }
+
+    directory
+        .join(file_name)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))
+}
+
+fn address(target: &Url) -> io::Result<(&str, u16)> {
+    let host = target
+        .host_str()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "the location has no host"))?;
+    let port = target
+        .port_or_known_default()
+        .unwrap_or(if target.scheme().starts_with("ftp") {
+            21
+        } else {
+            80
+        });
+
+    Ok((host, port))
+}
+
+async fn upload_http(target: &Url, archive: &[u8], tls: Option<Tls>) -> io::Result<()> {
+    let (host, port) = address(target)?;
+    let mut stream: BoxStream = Box::new(TcpStream::connect((host, port)).await?);
+
+    if let Some(tls) = &tls {
+        stream = tls.connect(stream).await?;
+    }
+
+    stream
+        .write_all(
+            format!(
+                "PUT {path} HTTP/1.1\r\n\
+                 Host: {host}:{port}\r\n\
+                 Content-Type: application/x-tar\r\n\
+                 Content-Length: {length}\r\n\
+                 Connection: close\r\n\r\n",
+                path = &target[url::Position::BeforePath..url::Position::AfterQuery],
+                length = archive.len(),
+            )
+            .as_bytes(),
+        )
+        .await?;
+    stream.write_all(archive).await?;
+    stream.flush().await?;
+
+    let mut status_line = String::new();
+    BufReader::new(stream).read_line(&mut status_line).await?;
+
+    match status_line.split(' ').nth(1) {
+        Some(status) if status.starts_with('2') => Ok(()),
+        _ => Err(io::Error::other(format!(
+            "the upload has been refused: `{}`",
+            status_line.trim_end()
+        ))),
+    }
+}
+
+/// A connection to an FTP server.
+struct Ftp {
+    control: BufReader<BoxStream>,
+}
+
+impl Ftp {
+    /// Read a reply, and return its code.
+    async fn reply(&mut self) -> io::Result<(u16, String)> {
+        let mut line = String::new();
+
+        loop {
+            line.clear();
+
+            if self.control.read_line(&mut line).await? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+
+            // Multi-line replies end with a line starting with the code and
+            // a space.
+            if let Some(code) = line
+                .get(..3)
+                .filter(|_| line.as_bytes().get(3) == Some(&b' '))
+                .and_then(|code| code.parse().ok())
+            {
+                return Ok((code, line.trim_end().to_owned()));
+            }
+        }
+    }
+
+    /// Send `command`, and expect a reply with one of the `expected` codes.
+    async fn command(&mut self, command: &str, expected: &[u16]) -> io::Result<(u16, String)> {
+        let control = self.control.get_mut();
+        control
+            .write_all(format!("{command}\r\n").as_bytes())
+            .await?;
+        control.flush().await?;
+
+        self.expect(expected).await
+    }
+
+    async fn expect(&mut self, expected: &[u16]) -> io::Result<(u16, String)> {
+        let (code, reply) = self.reply().await?;
+
+        if expected.contains(&code) {
+            Ok((code, reply))
+        } else {
+            Err(io::Error::other(format!("unexpected FTP reply `{reply}`")))
+        }
+    }
+}
+
+/// Upload `archive` to `target` over FTP, or over explicit FTPS with
+/// `tls`: the control and the data connections are both secured.
+async fn upload_ftp(target: &Url, archive: &[u8], tls: Option<Tls>) -> io::Result<()> {
+    let (host, port) = address(target)?;
+    let mut ftp = Ftp {
+        control: BufReader::new(Box::new(TcpStream::connect((host, port)).await?)),
+    };
+
+    ftp.expect(&[220]).await?;
+
+    if let Some(tls) = &tls {
+        ftp.command("AUTH TLS", &[234]).await?;
+        ftp.control = BufReader::new(tls.connect(ftp.control.into_inner()).await?);
+    }
+
+    let user = match target.username() {
+        "" => "anonymous",
+        user => user,
+    };
+
+    if ftp.command(&format!("USER {user}"), &[230, 331]).await?.0 == 331 {
+        ftp.command(
+            &format!("PASS {}", target.password().unwrap_or_default()),
+            &[230],
+        )
+        .await?;
+    }
+
+    if tls.is_some() {
+        ftp.command("PBSZ 0", &[200]).await?;
+        ftp.command("PROT P", &[200]).await?;
+    }
+
+    ftp.command("TYPE I", &[200]).await?;
+
+    // `227 Entering Passive Mode (h1,h2,h3,h4,p1,p2)`.
+    let (_, reply) = ftp.command("PASV", &[227]).await?;
+    let numbers = reply
+        .split(['(', ')'])
+        .nth(1)
+        .map(|numbers| {
+            numbers
+                .split(',')
+                .map(|number| number.trim().parse::<u8>())
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .and_then(Result::ok)
+        .filter(|numbers| numbers.len() == 6)
+        .ok_or_else(|| io::Error::other(format!("invalid FTP passive reply `{reply}`")))?;
+
+    let mut data: BoxStream = Box::new(
+        TcpStream::connect((
+            std::net::Ipv4Addr::new(numbers[0], numbers[1], numbers[2], numbers[3]),
+            u16::from(numbers[4]) << 8 | u16::from(numbers[5]),
+        ))
+        .await?,
+    );
+
+    ftp.command(&format!("STOR {}", target.path()), &[125, 150])
+        .await?;
+
+    if let Some(tls) = &tls {
+        data = tls.connect(data).await?;
+    }
+
+    data.write_all(archive).await?;
+    data.shutdown().await?;
+    drop(data);
+
+    ftp.expect(&[226, 250]).await?;
+    ftp.command("QUIT", &[221]).await?;
+
+    Ok(())
+}
+
+/// Bundle `files` in a tar archive.
+pub(crate) fn archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut archive = vec![];
+
+    for (name, content) in files {
+        let mut header = [0u8; 512];
+        header[..name.len().min(100)].copy_from_slice(&name.as_bytes()[..name.len().min(100)]);
+        header[100..107].copy_from_slice(b"0000644");
+        header[108..115].copy_from_slice(b"0000000");
+        header[116..123].copy_from_slice(b"0000000");
+        header[124..135].copy_from_slice(format!("{:011o}", content.len()).as_bytes());
+        header[136..147].copy_from_slice(b"00000000000");
+        header[156] = b'0';
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+
+        // The checksum is computed with its own field filled with spaces.
+        header[148..156].fill(b' ');
+        let checksum = header.iter().map(|byte| u32::from(*byte)).sum::<u32>();
+        header[148..155].copy_from_slice(format!("{checksum:06o}\0").as_bytes());
+
+        archive.extend_from_slice(&header);
+        archive.extend_from_slice(content);
+        archive.resize(archive.len().next_multiple_of(512), 0);
+    }
+
+    // The archive ends with two empty blocks.
+    archive.resize(archive.len() + 1024, 0);
+
+    archive
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{io::AsyncReadExt, net::TcpListener};
+
+    /// Read a whole HTTP request.
+    async fn read_request<S>(stream: &mut S) -> io::Result<Vec<u8>>
+    where
+        S: AsyncRead + Unpin,
+    {
+        let mut request = vec![];
+        let mut buffer = [0; 1024];
+
+        loop {
+            let read = stream.read(&mut buffer).await?;
+            request.extend_from_slice(&buffer[..read]);
+
+            let Some(end) = request.windows(4).position(|window| window == b"\r\n\r\n") else {
+                continue;
+            };
+            let length = String::from_utf8_lossy(&request[..end])
+                .lines()
+                .find_map(|line| line.strip_prefix("Content-Length: "))
+                .and_then(|length| length.parse::<usize>().ok())
+                .unwrap_or(0);
+
+            if request.len() >= end + 4 + length || read == 0 {
+                return Ok(request);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_upload() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let request = read_request(&mut stream).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 201 Created\r\nContent-Length: 0\r\n\r\n")
+                .await
+                .unwrap();
+
+            request
+        });
+
+        let archive = archive(&[("status.json", b"{}")]);
+        assert_eq!(archive.len(), 2 * 512 + 1024);
+
+        NetworkUploader::default()
+            .upload(
+                &format!("http://{address}/diagnostics").parse().unwrap(),
+                "CP001.tar",
+                archive.clone(),
+            )
+            .await
+            .unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with(b"PUT /diagnostics/CP001.tar HTTP/1.1\r\n"));
+        assert!(request.ends_with(&archive));
+
+        assert_eq!(
+            NetworkUploader::default()
+                .upload(
+                    &"https://example.org/".parse().unwrap(),
+                    "CP001.tar",
+                    vec![]
+                )
+                .await
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::Unsupported
+        );
+    }
+    /// An FTP server accepting one upload, over explicit FTPS with
+    /// `acceptor`. It returns the commands received, and the file uploaded.
+    async fn serve_ftp(
+        listener: TcpListener,
+        #[cfg(feature = "tls")] acceptor: Option<tokio_rustls::TlsAcceptor>,
+    ) -> (Vec<String>, Vec<u8>) {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut control = BufReader::new(Box::new(stream) as BoxStream);
+        let data_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let data_port = data_listener.local_addr().unwrap().port();
+        let mut commands = vec![];
+        let mut file = vec![];
+
+        control.get_mut().write_all(b"220 Ready\r\n").await.unwrap();
+
+        loop {
+            let mut line = String::new();
+            control.read_line(&mut line).await.unwrap();
+            commands.push(line.trim_end().to_owned());
+
+            let reply = match line.split([' ', '\r']).next().unwrap() {
+                "USER" => "331 Password required".to_owned(),
+                "PASS" => "230 Logged in".to_owned(),
+                "PBSZ" | "PROT" | "TYPE" => "200 OK".to_owned(),
+                "PASV" => format!(
+                    "227 Entering Passive Mode (127,0,0,1,{},{})",
+                    data_port >> 8,
+                    data_port & 0xff
+                ),
+                #[cfg(feature = "tls")]
+                "AUTH" => {
+                    control
+                        .get_mut()
+                        .write_all(b"234 AUTH TLS OK\r\n")
+                        .await
+                        .unwrap();
+                    let stream = acceptor
+                        .as_ref()
+                        .unwrap()
+                        .accept(control.into_inner())
+                        .await
+                        .unwrap();
+                    control = BufReader::new(Box::new(stream));
+
+                    continue;
+                }
+                "STOR" => {
+                    control.get_mut().write_all(b"150 Ready\r\n").await.unwrap();
+                    let (stream, _) = data_listener.accept().await.unwrap();
+                    let mut data: BoxStream = Box::new(stream);
+
+                    #[cfg(feature = "tls")]
+                    if let Some(acceptor) = &acceptor {
+                        data = Box::new(acceptor.accept(data).await.unwrap());
+                    }
+
+                    data.read_to_end(&mut file).await.unwrap();
+
+                    "226 Transfer complete".to_owned()
+                }
+                "QUIT" => {
+                    control.get_mut().write_all(b"221 Bye\r\n").await.unwrap();
+
+                    return (commands, file);
+                }
+                _ => "502 Not implemented".to_owned(),
+            };
+
+            let control = control.get_mut();
+            control
+                .write_all(format!("{reply}\r\n").as_bytes())
+                .await
+                .unwrap();
+            control.flush().await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ftp_upload() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tokio::spawn(serve_ftp(
+            listener,
+            #[cfg(feature = "tls")]
+            None,
+        ));
+
+        let archive = archive(&[("status.json", b"{}")]);
+
+        NetworkUploader::default()
+            .upload(
+                &format!("ftp://operator:secret@{address}/diagnostics")
+                    .parse()
+                    .unwrap(),
+                "CP001.tar",
+                archive.clone(),
+            )
+            .await
+            .unwrap();
+
+        let (commands, file) = server.await.unwrap();
+        assert_eq!(
+            commands,
+            [
+                "USER operator",
+                "PASS secret",
+                "TYPE I",
+                "PASV",
+                "STOR /diagnostics/CP001.tar",
+                "QUIT",
+            ]
+        );
+        assert_eq!(file, archive);
+    }
+
+    /// A TLS acceptor for `localhost`, and the client configuration
+    /// trusting it.
+    #[cfg(feature = "tls")]
+    fn tls() -> (
+        tokio_rustls::TlsAcceptor,
+        Arc<ocppx_client::rustls::ClientConfig>,
+    ) {
+        use ocppx_client::rustls::{
+            pki_types::PrivatePkcs8KeyDer, ClientConfig, RootCertStore, ServerConfig,
+        };
+        use rcgen::{CertificateParams, KeyPair};
+
+        let key = KeyPair::generate().unwrap();
+        let certificate = CertificateParams::new(vec!["localhost".to_owned()])
+            .unwrap()
+            .self_signed(&key)
+            .unwrap();
+
+        let mut root_store = RootCertStore::empty();
+        root_store.add(certificate.der().clone()).unwrap();
+
+        let server_config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![certificate.der().clone()],
+                PrivatePkcs8KeyDer::from(key.serialize_der()).into(),
+            )
+            .unwrap();
+        let client_config = ClientConfig::builder()
+            .with_root_certificates(root_store)
+            .with_no_client_auth();
+
+        (
+            tokio_rustls::TlsAcceptor::from(Arc::new(server_config)),
+            Arc::new(client_config),
+        )
+    }
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn test_ftps_upload() {
+        let (acceptor, client_config) = tls();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(serve_ftp(listener, Some(acceptor)));
+
+        let archive = archive(&[("status.json", b"{}")]);
+
+        NetworkUploader::default()
+            .tls(client_config)
+            .upload(
+                &format!("ftps://localhost:{port}/").parse().unwrap(),
+                "CP001.tar",
+                archive.clone(),
+            )
+            .await
+            .unwrap();
+
+        let (commands, file) = server.await.unwrap();
+        assert_eq!(
+            commands,
+            [
+                "AUTH TLS",
+                "USER anonymous",
+                "PASS",
+                "PBSZ 0",
+                "PROT P",
+                "TYPE I",
+                "PASV",
+                "STOR /CP001.tar",
+                "QUIT",
+            ]
+        );
+        assert_eq!(file, archive);
+    }
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn test_https_upload() {
+        let (acceptor, client_config) = tls();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = acceptor.accept(stream).await.unwrap();
+            let request = read_request(&mut stream).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+                .await
+                .unwrap();
+            stream.shutdown().await.unwrap();
+
+            request
+        });
+
+        let archive = archive(&[("status.json", b"{}")]);
+
+        NetworkUploader::default()
+            .tls(client_config)
+            .upload(
+                &format!("https://localhost:{port}/diagnostics/")
+                    .parse()
+                    .unwrap(),
+                "CP001.tar",
+                archive.clone(),
+            )
+            .await
+            .unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with(b"PUT /diagnostics/CP001.tar HTTP/1.1\r\n"));
+        assert!(request.ends_with(&archive));
+    }
+}
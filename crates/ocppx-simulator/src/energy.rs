@@ -0,0 +1,167 @@
+use ocppx_smartcharging::{Limit, NOMINAL_VOLTAGE};
+use std::time::Duration;
+
+/// The EV plugged in a connector of a [`Simulator`][crate::Simulator],
+/// see [`Ev`].
+#[derive(Debug, Clone)]
+pub struct EvConfig {
+    /// Capacity of the battery, in Wh.
+    pub battery_capacity: u32,
+    /// State of charge when plugged in, in %.
+    pub initial_soc: f64,
+    /// Maximum power accepted by the EV, in W.
+    pub max_power: u32,
+    /// Number of phases of the on-board charger, 1 to 3.
+    pub phases: i32,
+    /// State of charge, in %, from which the power accepted by the EV
+    /// decreases linearly, down to nothing when the battery is full.
+    pub taper_soc: f64,
+}
+
+impl Default for EvConfig {
+    fn default() -> Self {
+        Self {
+            battery_capacity: 60_000,
+            initial_soc: 20.0,
+            max_power: 22_000,
+            phases: 3,
+            taper_soc: 80.0,
+        }
+    }
+}
+
+impl EvConfig {
+    /// The power accepted by the EV at `soc`, in W.
+    fn accepted_power(&self, soc: f64) -> f64 {
+        let max_power = f64::from(self.max_power);
+
+        if soc < self.taper_soc {
+            max_power
+        } else {
+            max_power * ((100.0 - soc) / (100.0 - self.taper_soc).max(f64::EPSILON)).max(0.0)
+        }
+    }
+}
+
+/// The EV plugged in a connector, charging its battery.
+///
+/// The power is the lowest of the limit of the charging profiles, of the
+/// maximum power of the connector, and of the power accepted by the EV.
+/// It is sampled at each `MeterValues` interval.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ev {
+    /// State of charge of the battery, in %.
+    pub soc: f64,
+    /// The power delivered at the last sample, in W.
+    pub power: f64,
+    /// The current per phase at the last sample, in A.
+    pub current: f64,
+    /// The number of phases charging at the last sample.
+    pub phases: i32,
+    /// The energy delivered that the meter, in whole Wh, has not counted
+    /// yet.
+    uncounted_energy: f64,
+}
+
+impl Ev {
+    pub(crate) fn new(config: &EvConfig) -> Self {
+        Self {
+            soc: config.initial_soc.clamp(0.0, 100.0),
+            power: 0.0,
+            current: 0.0,
+            phases: 0,
+            uncounted_energy: 0.0,
+        }
+    }
+
+    /// Charge for `duration`, within `limit` and the `max_power` of the
+    /// connector, in W, on at most `phases`. Return the energy to add to
+    /// the meter, in Wh.
+    pub(crate) fn charge(
+        &mut self,
+        config: &EvConfig,
+        limit: Limit,
+        max_power: u32,
+        phases: i32,
+        duration: Duration,
+    ) -> i32 {
+        let phases = config.phases.min(phases).min(limit.number_phases).max(1);
+        let power = (limit.current * NOMINAL_VOLTAGE * f64::from(phases))
+            .min(f64::from(max_power))
+            .min(config.accepted_power(self.soc))
+            .max(0.0);
+        let energy = power * duration.as_secs_f64() / 3600.0;
+
+        self.soc =
+            (self.soc + energy * 100.0 / f64::from(config.battery_capacity.max(1))).min(100.0);
+        self.power = power;
+        self.current = power / (NOMINAL_VOLTAGE * f64::from(phases));
+        self.phases = phases;
+
+        self.uncounted_energy += energy;
+        let counted = self.uncounted_energy.floor();
+        self.uncounted_energy -= counted;
+
+        counted as i32
+    }
+
+    /// Stop charging, e.g. when the transaction has stopped.
+    pub(crate) fn idle(&mut self) {
+        self.power = 0.0;
+        self.current = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_charge() {
+        let config = EvConfig {
+            battery_capacity: 50_000,
+            initial_soc: 70.0,
+            max_power: 11_000,
+            phases: 3,
+            taper_soc: 80.0,
+        };
+        let limit = Limit {
+            current: 32.0,
+            number_phases: 3,
+        };
+        let mut ev = Ev::new(&config);
+
+        // The EV accepts less than the 22 kW of the connector.
+        assert_eq!(
+            ev.charge(&config, limit, 22_000, 3, Duration::from_secs(60)),
+            183
+        );
+        assert_eq!(ev.power, 11_000.0);
+        assert!((ev.current - 15.94).abs() < 0.01);
+
+        // A charging profile limits the current, on a single phase.
+        let limit = Limit {
+            current: 10.0,
+            number_phases: 1,
+        };
+        assert_eq!(
+            ev.charge(&config, limit, 22_000, 3, Duration::from_secs(3600)),
+            2300
+        );
+        assert_eq!((ev.power, ev.current, ev.phases), (2300.0, 10.0, 1));
+
+        // The accepted power decreases past 80 %, down to nothing.
+        ev.soc = 90.0;
+        ev.charge(&config, limit, 22_000, 3, Duration::ZERO);
+        assert_eq!(ev.power, 2300.0);
+        ev.soc = 99.0;
+        ev.charge(&config, limit, 22_000, 3, Duration::ZERO);
+        assert!((ev.power - 550.0).abs() < 0.01);
+        ev.soc = 100.0;
+        assert_eq!(
+            ev.charge(&config, limit, 22_000, 3, Duration::from_secs(60)),
+            0
+        );
+        assert_eq!(ev.soc, 100.0);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{simulator::Inner, Result};
+use serde_json::json;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// A fault to inject, see [`FaultInjector::inject`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// A `Heartbeat` `Call` whose JSON is truncated.
+    MalformedJson,
+    /// A `Heartbeat` `Call` with this message type ID instead of `2`.
+    WrongMessageTypeId(i64),
+    /// Two `Heartbeat` `Call`s with the same unique ID.
+    DuplicateUniqueId,
+    /// A `StatusNotification` whose `status` is not one of the enum.
+    InvalidEnumValue,
+    /// The connection drops, without a close frame. The simulator then
+    /// reconnects, if its client does.
+    SocketReset,
+}
+
+/// Injects faults in the messages of a [`Simulator`][crate::Simulator],
+/// to check how a Central System handles a misbehaving Charge Point. See
+/// [`Simulator::faults`][crate::Simulator::faults].
+///
+/// The `Call`s with faults are not waited for: their responses, if any,
+/// are dropped.
+#[derive(Clone)]
+pub struct FaultInjector {
+    inner: Arc<Inner>,
+}
+
+/// The unique IDs of the `Call`s with faults, which do not collide with the
+/// ones of the client.
+static NEXT_UNIQUE_ID: AtomicU64 = AtomicU64::new(1);
+
+fn next_unique_id() -> String {
+    format!("fault-{}", NEXT_UNIQUE_ID.fetch_add(1, Ordering::Relaxed))
+}
+
+impl FaultInjector {
+    pub(crate) fn new(inner: Arc<Inner>) -> Self {
+        Self { inner }
+    }
+
+    /// Inject `fault`, now.
+    pub fn inject(&self, fault: Fault) -> Result<()> {
+        let unique_id = next_unique_id();
+
+        match fault {
+            Fault::MalformedJson => self.send_raw(format!(r#"[2,"{unique_id}","Heartbeat",{{"#)),
+            Fault::WrongMessageTypeId(message_type_id) => {
+                self.send_raw(json!([message_type_id, unique_id, "Heartbeat", {}]).to_string())
+            }
+            Fault::DuplicateUniqueId => {
+                let call = json!([2, unique_id, "Heartbeat", {}]).to_string();
+                self.send_raw(call.clone())?;
+                self.send_raw(call)
+            }
+            Fault::InvalidEnumValue => self.send_raw(
+                json!([
+                    2,
+                    unique_id,
+                    "StatusNotification",
+                    {"connectorId": 0, "errorCode": "NoError", "status": "OnFire"},
+                ])
+                .to_string(),
+            ),
+            Fault::SocketReset => {
+                self.inner.client.reset();
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Send a text frame as is, for the faults that [`Fault`] does not
+    /// cover.
+    pub fn send_raw(&self, text: impl Into<String>) -> Result<()> {
+        Ok(self.inner.client.send_raw(text)?)
+    }
+
+    /// Hold back the responses to the `Call`s of the Central System for
+    /// `delay`, e.g. beyond its timeout. `None` responds immediately again.
+    pub fn delay_responses(&self, delay: Option<Duration>) {
+        self.inner.state.lock().unwrap().response_delay = delay;
+    }
+}
@@ -0,0 +1,89 @@
+//! A virtual OCPP 1.6 Charge Point.
+//!
+//! [`Simulator`] connects to a Central System, boots, and then models its
+//! connectors: their status follows a state machine (see
+//! [`ConnectorStatus`]), and it automatically emits `Heartbeat`,
+//! `StatusNotification` and `MeterValues` messages. The EVs plugged in
+//! charge their battery, see [`Ev`]: the power follows the charging
+//! profiles, and the state of charge. Transaction flows can
+//! be driven step by step, scripted with [`Step`]s, or laid out on a
+//! timeline with a [`Scenario`], e.g. loaded from JSON.
+//!
+//! The simulator keeps a Local Authorization List and an authorization
+//! cache, see [`Simulator::authorize`], applies the charging profiles
+//! sent by the Central System, and answers `GetConfiguration` and
+//! `ChangeConfiguration` from its configuration keys. An `UpdateFirmware`
+//! goes through the download and the installation of the firmware, which
+//! can be made slow or failing with a [`FirmwareConfig`]. A
+//! `GetDiagnostics` uploads a diagnostics archive with a
+//! [`DiagnosticsUploader`]. Connectors can be reserved with `ReserveNow`:
+//! a reserved connector only starts transactions for the ID tag of its
+//! reservation. A `TriggerMessage` makes the simulator send the requested
+//! message again. A `ChangeAvailability` to `Inoperative` of a connector
+//! with an ongoing transaction is `Scheduled`: the connector becomes
+//! `Unavailable` when the transaction ends. The connectors can be
+//! [faulted][Simulator::fault] with a [`ConnectorError`]. The
+//! `StatusNotification`s of the connectors whose state flaps are condensed:
+//! a new state is only notified once it has lasted the
+//! `MinimumStatusDuration`. A [`FaultInjector`] makes the simulator
+//! misbehave, to harden the Central Systems.
+//!
+//! A session captured by an `ocppx_rpc::Recorder` is sent again by
+//! [`Simulator::replay`].
+
+mod config;
+mod connector;
+mod debounce;
+mod diagnostics;
+mod energy;
+mod fault;
+mod scenario;
+mod script;
+mod simulator;
+
+pub use config::{FirmwareConfig, SimulatorConfig};
+pub use connector::{
+    ChargePointErrorCode, Connector, ConnectorError, ConnectorEvent, ConnectorState,
+    ConnectorStatus, Transaction,
+};
+pub use diagnostics::{DiagnosticsUploader, NetworkUploader, UploadFuture};
+pub use energy::{Ev, EvConfig};
+pub use fault::{Fault, FaultInjector};
+pub use scenario::{Scenario, ScenarioProgress, ScenarioStep};
+pub use script::Step;
+pub use simulator::Simulator;
+use std::time::Duration;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("client error")]
+    Client(#[from] ocppx_client::Error),
+
+    #[error("unknown connector `{0}`")]
+    UnknownConnector(i32),
+
+    #[error("connector `{connector_id}` cannot handle `{event:?}` while `{status:?}`")]
+    InvalidTransition {
+        connector_id: i32,
+        status: ConnectorStatus,
+        event: ConnectorEvent,
+    },
+
+    #[error("the ID tag `{0}` is not authorized")]
+    NotAuthorized(String),
+
+    #[error("connector `{0}` is reserved for another ID tag")]
+    Reserved(i32),
+
+    #[error("connector `{0}` has no ongoing transaction")]
+    NoTransaction(i32),
+
+    #[error("invalid {0}")]
+    InvalidString(&'static str, #[source] ocppx_types::CiStringError),
+
+    #[error("invalid duration `{0:?}`")]
+    InvalidDuration(Duration),
+}
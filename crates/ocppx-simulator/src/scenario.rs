@@ -0,0 +1,162 @@
+use crate::Step;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::time::Duration;
+
+/// A scenario: the actions of a Charge Point on a timeline, see
+/// [`Simulator::run_scenario`][crate::Simulator::run_scenario].
+///
+/// It is meant to be written by hand, e.g. in JSON:
+///
+/// ```json
+/// {
+///     "name": "A 10 kWh charge",
+///     "steps": [
+///         { "at": 0, "action": "heartbeat", "every": "30s" },
+///         { "at": "1m", "action": "plugIn", "connectorId": 1 },
+///         { "at": "1m", "action": "charge", "connectorId": 1, "idTag": "04E91C5A", "energy": 10000, "duration": "20m" },
+///         { "at": "22m", "action": "unplug", "connectorId": 1 }
+///     ]
+/// }
+/// ```
+///
+/// The durations are numbers of seconds, or strings with a unit: `500ms`,
+/// `30s`, `20m` or `1h`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Scenario {
+    #[serde(default)]
+    pub name: String,
+    pub steps: Vec<ScenarioStep>,
+}
+
+/// A step of a [`Scenario`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScenarioStep {
+    /// When the step runs, from the start of the scenario. A step runs
+    /// after the previous one, even if it is late.
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub at: Duration,
+    #[serde(flatten)]
+    pub step: Step,
+}
+
+/// The progress of a running [`Scenario`], with the index of its steps.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ScenarioProgress {
+    StepStarted {
+        step: usize,
+    },
+    StepDone {
+        step: usize,
+    },
+    /// A periodic `Heartbeat` has been sent.
+    Heartbeat,
+}
+
+pub(crate) fn deserialize_duration<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Seconds(f64),
+        WithUnit(String),
+    }
+
+    match Raw::deserialize(deserializer)? {
+        Raw::Seconds(seconds) => Duration::try_from_secs_f64(seconds).map_err(de::Error::custom),
+        Raw::WithUnit(value) => parse_duration(&value).ok_or_else(|| {
+            de::Error::invalid_value(de::Unexpected::Str(&value), &"a duration, e.g. `30s`")
+        }),
+    }
+}
+
+/// A duration between two repetitions, which cannot be zero.
+pub(crate) fn deserialize_optional_duration<'de, D>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match deserialize_duration(deserializer)? {
+        duration if duration.is_zero() => Err(de::Error::invalid_value(
+            de::Unexpected::Other("a zero duration"),
+            &"a positive duration",
+        )),
+        duration => Ok(Some(duration)),
+    }
+}
+
+/// Parse `500ms`, `30s`, `20m` or `1h`.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let split = value
+        .find(|character: char| !(character.is_ascii_digit() || character == '.'))
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number = number.parse::<f64>().ok()?;
+    let seconds = match unit.trim() {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => return None,
+    };
+
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_scenario() {
+        let scenario = serde_json::from_str::<Scenario>(
+            r#"{
+                "name": "A 10 kWh charge",
+                "steps": [
+                    { "at": 0, "action": "heartbeat", "every": "30s" },
+                    { "at": 60, "action": "plugIn", "connectorId": 1 },
+                    { "at": "1m", "action": "charge", "connectorId": 1, "idTag": "04E91C5A", "energy": 10000, "duration": "20m" },
+                    { "at": "1.5h", "action": "unplug", "connectorId": 1 }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(scenario.name, "A 10 kWh charge");
+        assert_eq!(
+            scenario
+                .steps
+                .iter()
+                .map(|step| step.at.as_secs())
+                .collect::<Vec<_>>(),
+            [0, 60, 60, 5400]
+        );
+        assert!(matches!(
+            scenario.steps[0].step,
+            Step::Heartbeat { every: Some(every) } if every == Duration::from_secs(30)
+        ));
+        assert!(matches!(
+            &scenario.steps[2].step,
+            Step::Charge { connector_id: 1, id_tag, energy: 10_000, duration }
+                if id_tag == "04E91C5A" && *duration == Duration::from_secs(1200)
+        ));
+
+        assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_duration("2 d"), None);
+        assert!(serde_json::from_str::<ScenarioStep>(
+            r#"{ "at": "soon", "action": "unplug", "connectorId": 1 }"#
+        )
+        .is_err());
+
+        for every in ["0", r#""0s""#, r#""0ms""#] {
+            assert!(serde_json::from_str::<ScenarioStep>(&format!(
+                r#"{{ "at": 0, "action": "heartbeat", "every": {every} }}"#
+            ))
+            .is_err());
+        }
+    }
+}
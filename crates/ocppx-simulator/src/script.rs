@@ -0,0 +1,51 @@
+use crate::scenario::{deserialize_duration, deserialize_optional_duration};
+use serde::Deserialize;
+use std::time::Duration;
+
+/// A step of a scripted flow, see [`Simulator::run`][crate::Simulator::run],
+/// or of a [`Scenario`][crate::Scenario].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(
+    tag = "action",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum Step {
+    PlugIn {
+        connector_id: i32,
+    },
+    StartTransaction {
+        connector_id: i32,
+        id_tag: String,
+    },
+    StopTransaction {
+        connector_id: i32,
+    },
+    Unplug {
+        connector_id: i32,
+    },
+    /// Wait for the given duration. The steps of a [`Scenario`] are laid
+    /// out with their `at` instead.
+    ///
+    /// [`Scenario`]: crate::Scenario
+    #[serde(skip)]
+    Wait(Duration),
+    /// Send a `Heartbeat`, then one `every` interval until the end of the
+    /// flow, if any.
+    Heartbeat {
+        #[serde(default, deserialize_with = "deserialize_optional_duration")]
+        every: Option<Duration>,
+    },
+    /// Start a transaction, deliver `energy` Wh over `duration`, and stop
+    /// the transaction. The energy is metered at each `MeterValues`
+    /// interval, it is then rounded to it. It is less if the power it takes
+    /// is above the one of the Charge Point, of the charging profiles, or
+    /// of the EV, see [`Ev`][crate::Ev].
+    Charge {
+        connector_id: i32,
+        id_tag: String,
+        energy: u32,
+        #[serde(deserialize_with = "deserialize_duration")]
+        duration: Duration,
+    },
+}
@@ -0,0 +1,2170 @@
+use crate::{
+    debounce::{Release, StatusDebouncer},
+    diagnostics, ChargePointErrorCode, Connector, ConnectorError, ConnectorEvent, ConnectorState,
+    ConnectorStatus, Error, Ev, FaultInjector, Result, Scenario, ScenarioProgress, ScenarioStep,
+    SimulatorConfig, Step, Transaction,
+};
+use ocppx_client::{
+    authorize_offline, AuthorizationCache, ChargePointClient, ClientConfig, ConfigurationStore,
+    LocalAuthList, Reservation, ReservationManager,
+};
+use ocppx_rpc::{
+    Call, CallError, CallResult, CapturedFrame, Direction, ErrorCode, Message, Replayer,
+};
+use ocppx_smartcharging::{ChargingProfileStore, NOMINAL_VOLTAGE};
+use ocppx_types::v1_6::{
+    AuthorizeRequest, BootNotificationRequest, BootNotificationStatus, CancelReservationRequest,
+    CancelReservationResponse, CancelReservationStatus, ChangeAvailabilityRequest,
+    ChangeAvailabilityResponse, ChangeAvailabilityStatus, ChangeAvailabilityType,
+    ChangeConfigurationRequest, ClearCacheResponse, ClearCacheStatus, ClearChargingProfileRequest,
+    ClearChargingProfileResponse, DiagnosticsStatusNotificationRequest,
+    DiagnosticsStatusNotificationStatus, FirmwareStatusNotificationRequest,
+    FirmwareStatusNotificationStatus, GetCompositeScheduleRequest, GetConfigurationRequest,
+    GetDiagnosticsRequest, GetDiagnosticsResponse, GetLocalListVersionResponse, HeartbeatRequest,
+    IdTagInfo, IdTagInfoStatus, MeterValue, MeterValuesRequest, ReserveNowRequest,
+    ReserveNowResponse, ReserveNowStatus, SampledValueContext, SampledValueUnit,
+    SendLocalListRequest, SendLocalListResponse, SetChargingProfileRequest,
+    SetChargingProfileResponse, StartTransactionRequest, StatusNotificationRequest,
+    StopTransactionReason, StopTransactionRequest, TriggerMessageRequest,
+    TriggerMessageRequestedMessage, TriggerMessageResponse, TriggerMessageStatus,
+    UpdateFirmwareRequest, UpdateFirmwareResponse,
+};
+use ocppx_types::{CiString20, CiString255, CiStringError, IdTag};
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{task::JoinHandle, time};
+
+/// Interval before retrying a `BootNotification` that has not been
+/// accepted, when the Central System does not provide one.
+const DEFAULT_BOOT_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+pub(crate) struct State {
+    connectors: BTreeMap<i32, Connector>,
+    local_auth_list: LocalAuthList,
+    authorization_cache: AuthorizationCache,
+    charging_profiles: ChargingProfileStore,
+    configuration: ConfigurationStore,
+    reservations: ReservationManager,
+    firmware_status: FirmwareStatusNotificationStatus,
+    firmware_update: Option<JoinHandle<()>>,
+    diagnostics_status: DiagnosticsStatusNotificationStatus,
+    diagnostics_upload: Option<JoinHandle<()>>,
+    /// How long the responses to the Central System are held back, see
+    /// [`FaultInjector::delay_responses`].
+    pub(crate) response_delay: Option<Duration>,
+    status_debouncer: StatusDebouncer,
+}
+
+pub(crate) struct Inner {
+    config: SimulatorConfig,
+    pub(crate) client: ChargePointClient,
+    pub(crate) state: Mutex<State>,
+}
+
+/// A simulated Charge Point, connected to a Central System.
+pub struct Simulator {
+    inner: Arc<Inner>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Simulator {
+    /// Connect to the Central System, and boot: the `BootNotification` is
+    /// sent until it is accepted, then the status of every connector is
+    /// notified.
+    pub async fn start(config: SimulatorConfig) -> Result<Self> {
+        let client = ChargePointClient::connect_with_config(
+            &config.csms_url,
+            &config.charge_point_id,
+            ClientConfig {
+                clock: config.clock.clone(),
+                ..ClientConfig::default()
+            },
+        )
+        .await?;
+
+        loop {
+            let response = client.send(boot_notification_request(&config)?).await?;
+            let interval = Duration::from_secs(response.interval.max(0) as u64);
+
+            match response.status {
+                BootNotificationStatus::Accepted => break,
+                BootNotificationStatus::Pending | BootNotificationStatus::Rejected => {
+                    time::sleep(if interval.is_zero() {
+                        DEFAULT_BOOT_RETRY_INTERVAL
+                    } else {
+                        interval
+                    })
+                    .await;
+                }
+            }
+        }
+
+        let mut configuration = ConfigurationStore::new();
+        configuration.set("NumberOfConnectors", config.connectors.to_string());
+        configuration.set(
+            "MeterValueSampleInterval",
+            config.meter_values_interval.as_secs().to_string(),
+        );
+        configuration.set(
+            "MinimumStatusDuration",
+            config.minimum_status_duration.as_secs().to_string(),
+        );
+
+        let inner = Arc::new(Inner {
+            state: Mutex::new(State {
+                connectors: (1..=config.connectors)
+                    .map(|connector_id| (connector_id, Connector::new(connector_id)))
+                    .collect(),
+                local_auth_list: LocalAuthList::new(),
+                authorization_cache: AuthorizationCache::new(),
+                charging_profiles: ChargingProfileStore::new(
+                    f64::from(config.charging_power)
+                        / (NOMINAL_VOLTAGE * f64::from(config.phases.clamp(1, 3))),
+                ),
+                configuration,
+                reservations: ReservationManager::new(),
+                firmware_status: FirmwareStatusNotificationStatus::Idle,
+                firmware_update: None,
+                diagnostics_status: DiagnosticsStatusNotificationStatus::Idle,
+                diagnostics_upload: None,
+                response_delay: None,
+                status_debouncer: StatusDebouncer::default(),
+            }),
+            config,
+            client,
+        });
+
+        // Connector 0 is the Charge Point itself. The states at boot are
+        // notified right away.
+        for connector_id in 0..=inner.config.connectors {
+            inner
+                .send_connector_state(connector_id, ConnectorStatus::Available.into())
+                .await?;
+        }
+
+        let tasks = vec![
+            tokio::spawn(send_meter_values(inner.clone())),
+            tokio::spawn(handle_calls(inner.clone())),
+            tokio::spawn(expire_reservations(inner.clone())),
+        ];
+
+        Ok(Self { inner, tasks })
+    }
+
+    /// Inject faults in the messages sent to the Central System.
+    pub fn faults(&self) -> FaultInjector {
+        FaultInjector::new(self.inner.clone())
+    }
+
+    /// A snapshot of the connectors.
+    pub fn connectors(&self) -> Vec<Connector> {
+        self.inner
+            .state
+            .lock()
+            .unwrap()
+            .connectors
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// The status of the last firmware update, as last sent in a
+    /// `FirmwareStatusNotification`.
+    pub fn firmware_status(&self) -> FirmwareStatusNotificationStatus {
+        self.inner.state.lock().unwrap().firmware_status
+    }
+
+    /// The status of the last diagnostics upload, as last sent in a
+    /// `DiagnosticsStatusNotification`.
+    pub fn diagnostics_status(&self) -> DiagnosticsStatusNotificationStatus {
+        self.inner.state.lock().unwrap().diagnostics_status
+    }
+
+    /// Authorize `id_tag` with an `Authorize` request. When the Central
+    /// System cannot be reached, the Local Authorization List and the
+    /// authorization cache are consulted instead.
+    pub async fn authorize(&self, id_tag: &str) -> Result<IdTagInfo> {
+        let id_tag = ci_string::<IdTag>(id_tag, "ID tag")?;
+        let id_tag_info = match self
+            .inner
+            .client
+            .send_authorize(AuthorizeRequest::builder().id_tag(id_tag.clone()).build())
+            .await
+        {
+            Ok(response) => {
+                self.inner
+                    .state
+                    .lock()
+                    .unwrap()
+                    .authorization_cache
+                    .update(&id_tag, &response.id_tag_info);
+
+                response.id_tag_info
+            }
+
+            Err(ocppx_client::Error::ConnectionClosed | ocppx_client::Error::Timeout { .. }) => {
+                let state = self.inner.state.lock().unwrap();
+
+                authorize_offline(
+                    &state.local_auth_list,
+                    &state.authorization_cache,
+                    &id_tag,
+                    self.inner.client.now(),
+                )
+                .ok_or_else(|| Error::NotAuthorized(id_tag.to_string()))?
+            }
+
+            Err(error) => return Err(error.into()),
+        };
+
+        match id_tag_info.status {
+            IdTagInfoStatus::Accepted => Ok(id_tag_info),
+            _ => Err(Error::NotAuthorized(id_tag.into())),
+        }
+    }
+
+    /// Plug a cable in `connector_id`.
+    pub async fn plug_in(&self, connector_id: i32) -> Result<()> {
+        let status = self
+            .inner
+            .transition(connector_id, ConnectorEvent::PlugIn, |connector| {
+                connector.ev = Some(Ev::new(&self.inner.config.ev));
+            })?;
+
+        self.inner
+            .send_status_notification(connector_id, status)
+            .await
+    }
+
+    /// Start a transaction on `connector_id` for `id_tag`, and return the
+    /// transaction ID assigned by the Central System.
+    pub async fn start_transaction(&self, connector_id: i32, id_tag: &str) -> Result<i32> {
+        let id_tag = ci_string::<IdTag>(id_tag, "ID tag")?;
+        let meter_start = self
+            .inner
+            .check_transition(connector_id, ConnectorEvent::StartCharging)?;
+        let timestamp = self.inner.client.now();
+
+        // A reserved connector only starts transactions for the ID tag of
+        // its reservation, which then ends.
+        let reservation = {
+            let mut state = self.inner.state.lock().unwrap();
+
+            if !state.reservations.check(connector_id, &id_tag, None) {
+                return Err(Error::Reserved(connector_id));
+            }
+
+            state.reservations.consume(connector_id, &id_tag, None)
+        };
+
+        let mut request = StartTransactionRequest::builder()
+            .connector_id(connector_id)
+            .id_tag(id_tag.clone())
+            .meter_start(meter_start)
+            .timestamp(timestamp)
+            .build();
+        request.reservation_id = reservation.map(|reservation| reservation.id);
+
+        let response = self.inner.client.send(request).await?;
+        let transaction_id = response.transaction_id;
+
+        self.inner
+            .state
+            .lock()
+            .unwrap()
+            .authorization_cache
+            .update(&id_tag, &response.id_tag_info);
+
+        if response.id_tag_info.status != IdTagInfoStatus::Accepted {
+            self.inner
+                .send_stop_transaction(
+                    StopTransactionRequest::builder()
+                        .transaction_id(transaction_id)
+                        .meter_stop(meter_start)
+                        .timestamp(self.inner.client.now())
+                        .reason(StopTransactionReason::DeAuthorized)
+                        .build(),
+                )
+                .await?;
+
+            return Err(Error::NotAuthorized(id_tag.into()));
+        }
+
+        let status =
+            self.inner
+                .transition(connector_id, ConnectorEvent::StartCharging, |connector| {
+                    connector.transaction = Some(Transaction {
+                        id: transaction_id,
+                        id_tag,
+                        meter_start,
+                        started_at: timestamp,
+                    });
+                })?;
+        self.inner
+            .state
+            .lock()
+            .unwrap()
+            .charging_profiles
+            .start_transaction(connector_id, transaction_id, timestamp);
+
+        self.inner
+            .send_status_notification(connector_id, status)
+            .await?;
+
+        Ok(transaction_id)
+    }
+
+    /// Stop the ongoing transaction on `connector_id`.
+    pub async fn stop_transaction(&self, connector_id: i32) -> Result<()> {
+        self.inner
+            .check_transition(connector_id, ConnectorEvent::StopCharging)?;
+
+        let (transaction, meter_stop) = {
+            let state = self.inner.state.lock().unwrap();
+            let connector = &state.connectors[&connector_id];
+
+            (
+                connector
+                    .transaction
+                    .clone()
+                    .ok_or(Error::NoTransaction(connector_id))?,
+                connector.meter,
+            )
+        };
+
+        self.inner
+            .send_stop_transaction(
+                StopTransactionRequest::builder()
+                    .transaction_id(transaction.id)
+                    .id_tag(transaction.id_tag)
+                    .meter_stop(meter_stop)
+                    .timestamp(self.inner.client.now())
+                    .reason(StopTransactionReason::Local)
+                    .build(),
+            )
+            .await?;
+
+        self.inner.end_transaction(connector_id).await
+    }
+
+    /// Report `error` on `connector_id`, which becomes `Faulted`.
+    pub async fn fault(&self, connector_id: i32, error: ConnectorError) -> Result<()> {
+        let status = self
+            .inner
+            .transition(connector_id, ConnectorEvent::Fault, |connector| {
+                connector.error = Some(error.clone());
+            })?;
+
+        self.inner
+            .send_status_notification(
+                connector_id,
+                ConnectorState {
+                    status,
+                    error: Some(error),
+                },
+            )
+            .await
+    }
+
+    /// Clear the error of the `Faulted` `connector_id`, which becomes
+    /// `Available` again.
+    pub async fn clear_fault(&self, connector_id: i32) -> Result<()> {
+        let status =
+            self.inner
+                .transition(connector_id, ConnectorEvent::FaultCleared, |connector| {
+                    connector.error = None;
+                })?;
+
+        self.inner
+            .send_status_notification(connector_id, status)
+            .await
+    }
+
+    /// Unplug the cable from `connector_id`.
+    pub async fn unplug(&self, connector_id: i32) -> Result<()> {
+        let status = self
+            .inner
+            .transition(connector_id, ConnectorEvent::Unplug, |connector| {
+                connector.ev = None;
+            })?;
+
+        self.inner
+            .send_status_notification(connector_id, status)
+            .await
+    }
+
+    /// Send a `Heartbeat`, besides the ones sent at the interval of the
+    /// Central System.
+    pub async fn heartbeat(&self) -> Result<()> {
+        self.inner
+            .client
+            .send(HeartbeatRequest::builder().build())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Set the maximum power delivered by `connector_id` while charging, in
+    /// W: the one of the configuration when `None`. The charging profiles
+    /// and the EV may limit it further.
+    pub fn set_charging_power(&self, connector_id: i32, power: Option<u32>) -> Result<()> {
+        self.inner
+            .state
+            .lock()
+            .unwrap()
+            .connectors
+            .get_mut(&connector_id)
+            .ok_or(Error::UnknownConnector(connector_id))?
+            .charging_power = power;
+
+        Ok(())
+    }
+
+    /// Run a scripted flow, step by step.
+    pub async fn run<S>(&self, steps: S) -> Result<()>
+    where
+        S: IntoIterator<Item = Step>,
+    {
+        let mut heartbeats = None;
+
+        for step in steps {
+            self.step(&step, &mut heartbeats, &mut |_| {}).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Send the `Call`s of a recorded session again, at the pace of the
+    /// [`Replayer`], e.g. to reproduce the misbehavior of a Charge Point
+    /// against a Central System. The `Call`s of the Charge Point are the
+    /// frames of `direction`: [`Direction::Outgoing`] in a capture of the
+    /// client, [`Direction::Incoming`] in a capture of the server.
+    ///
+    /// The other frames are skipped, and the connectors are left as they
+    /// are. Returns the `Call`s with the responses of the Central System.
+    pub async fn replay(
+        &self,
+        replayer: &Replayer,
+        direction: Direction,
+    ) -> Vec<(CapturedFrame, Result<serde_json::Value>)> {
+        let mut playback = replayer.play();
+        let mut responses = vec![];
+
+        while let Some(frame) = playback.next().await {
+            if frame.direction != direction {
+                continue;
+            }
+
+            let Some(call) = frame
+                .message()
+                .ok()
+                .and_then(|message| Call::try_from(message).ok())
+            else {
+                continue;
+            };
+
+            let response = self
+                .inner
+                .client
+                .call(&call.action, &call.payload)
+                .await
+                .map_err(Error::from);
+
+            responses.push((frame.clone(), response));
+        }
+
+        responses
+    }
+
+    /// Run a [`Scenario`], from now, reporting its progress to
+    /// `on_progress`. It stops at the first failing step.
+    pub async fn run_scenario<F>(&self, scenario: &Scenario, mut on_progress: F) -> Result<()>
+    where
+        F: FnMut(ScenarioProgress),
+    {
+        let start = time::Instant::now();
+        let mut heartbeats = None;
+
+        for (index, ScenarioStep { at, step }) in scenario.steps.iter().enumerate() {
+            let at = start.checked_add(*at).ok_or(Error::InvalidDuration(*at))?;
+
+            self.wait_until(at, &mut heartbeats, &mut on_progress)
+                .await?;
+            on_progress(ScenarioProgress::StepStarted { step: index });
+            self.step(step, &mut heartbeats, &mut on_progress).await?;
+            on_progress(ScenarioProgress::StepDone { step: index });
+        }
+
+        Ok(())
+    }
+
+    /// Run `step`. The periodic `Heartbeat`s, if any, are sent while it
+    /// waits.
+    async fn step<F>(
+        &self,
+        step: &Step,
+        heartbeats: &mut Option<time::Interval>,
+        on_progress: &mut F,
+    ) -> Result<()>
+    where
+        F: FnMut(ScenarioProgress),
+    {
+        match step {
+            Step::PlugIn { connector_id } => self.plug_in(*connector_id).await?,
+            Step::StartTransaction {
+                connector_id,
+                id_tag,
+            } => {
+                self.start_transaction(*connector_id, id_tag).await?;
+            }
+            Step::StopTransaction { connector_id } => self.stop_transaction(*connector_id).await?,
+            Step::Unplug { connector_id } => self.unplug(*connector_id).await?,
+            Step::Wait(duration) => {
+                self.wait_until(deadline(*duration)?, heartbeats, on_progress)
+                    .await?
+            }
+            Step::Heartbeat { every } => {
+                self.heartbeat().await?;
+                *heartbeats = match *every {
+                    Some(every) if every.is_zero() => return Err(Error::InvalidDuration(every)),
+                    Some(every) => {
+                        let mut heartbeats = time::interval_at(deadline(every)?, every);
+                        heartbeats.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+
+                        Some(heartbeats)
+                    }
+                    None => None,
+                };
+            }
+            Step::Charge {
+                connector_id,
+                id_tag,
+                energy,
+                duration,
+            } => {
+                let power = u64::from(*energy) * 3_600_000 / duration.as_millis().max(1) as u64;
+                self.set_charging_power(*connector_id, Some(power.min(u32::MAX.into()) as u32))?;
+
+                let result = async {
+                    self.start_transaction(*connector_id, id_tag).await?;
+                    self.wait_until(deadline(*duration)?, heartbeats, on_progress)
+                        .await?;
+                    self.stop_transaction(*connector_id).await
+                }
+                .await;
+
+                self.set_charging_power(*connector_id, None)?;
+                result?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Wait until `deadline`, sending the periodic `Heartbeat`s meanwhile.
+    async fn wait_until<F>(
+        &self,
+        deadline: time::Instant,
+        heartbeats: &mut Option<time::Interval>,
+        on_progress: &mut F,
+    ) -> Result<()>
+    where
+        F: FnMut(ScenarioProgress),
+    {
+        let Some(heartbeats) = heartbeats else {
+            time::sleep_until(deadline).await;
+
+            return Ok(());
+        };
+
+        loop {
+            tokio::select! {
+                _ = time::sleep_until(deadline) => return Ok(()),
+                _ = heartbeats.tick() => {
+                    self.heartbeat().await?;
+                    on_progress(ScenarioProgress::Heartbeat);
+                }
+            }
+        }
+    }
+
+    /// Stop the simulation, and close the connection.
+    pub async fn stop(self) -> Result<()> {
+        let (firmware_update, diagnostics_upload) = {
+            let mut state = self.inner.state.lock().unwrap();
+
+            (
+                state.firmware_update.take(),
+                state.diagnostics_upload.take(),
+            )
+        };
+
+        for task in self
+            .tasks
+            .into_iter()
+            .chain(firmware_update)
+            .chain(diagnostics_upload)
+        {
+            task.abort();
+            let _ = task.await;
+        }
+
+        match Arc::try_unwrap(self.inner) {
+            Ok(inner) => Ok(inner.client.close().await?),
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+impl Inner {
+    /// Check that `event` is allowed on `connector_id`, and return the
+    /// current meter value.
+    fn check_transition(&self, connector_id: i32, event: ConnectorEvent) -> Result<i32> {
+        let state = self.state.lock().unwrap();
+        let connector = state
+            .connectors
+            .get(&connector_id)
+            .ok_or(Error::UnknownConnector(connector_id))?;
+
+        match connector.status.next(event) {
+            Some(_) => Ok(connector.meter),
+            None => Err(Error::InvalidTransition {
+                connector_id,
+                status: connector.status,
+                event,
+            }),
+        }
+    }
+
+    /// Apply `event` on `connector_id`, update the connector with `update`,
+    /// and return the new status.
+    fn transition<F>(
+        &self,
+        connector_id: i32,
+        event: ConnectorEvent,
+        update: F,
+    ) -> Result<ConnectorStatus>
+    where
+        F: FnOnce(&mut Connector),
+    {
+        let mut state = self.state.lock().unwrap();
+        let connector = state
+            .connectors
+            .get_mut(&connector_id)
+            .ok_or(Error::UnknownConnector(connector_id))?;
+
+        let status = connector
+            .status
+            .next(event)
+            .ok_or(Error::InvalidTransition {
+                connector_id,
+                status: connector.status,
+                event,
+            })?;
+
+        connector.status = status;
+        update(connector);
+
+        Ok(status)
+    }
+
+    /// End the transaction of `connector_id`, once the `StopTransaction`
+    /// has been sent. Every transaction ends here, so that the event
+    /// deferred until then is applied.
+    async fn end_transaction(self: &Arc<Self>, connector_id: i32) -> Result<()> {
+        let status = self.transition(connector_id, ConnectorEvent::StopCharging, |connector| {
+            connector.transaction = None;
+        })?;
+        self.state
+            .lock()
+            .unwrap()
+            .charging_profiles
+            .stop_transaction(connector_id);
+
+        self.send_status_notification(connector_id, status).await?;
+
+        self.apply_deferred(connector_id).await
+    }
+
+    /// Apply the event deferred until the end of the transaction on
+    /// `connector_id`, if any, and notify the new status.
+    async fn apply_deferred(self: &Arc<Self>, connector_id: i32) -> Result<()> {
+        let status = {
+            let mut state = self.state.lock().unwrap();
+            let Some(connector) = state.connectors.get_mut(&connector_id) else {
+                return Ok(());
+            };
+            let Some(status) = connector
+                .deferred
+                .take()
+                .and_then(|event| connector.status.next(event))
+            else {
+                return Ok(());
+            };
+
+            connector.status = status;
+
+            status
+        };
+
+        self.send_status_notification(connector_id, status).await
+    }
+
+    /// A sample of the energy meter of `connector`, in Wh, with the power,
+    /// the current, the voltage and the state of charge of its EV, if any.
+    fn meter_value(&self, connector: &Connector, context: SampledValueContext) -> MeterValue {
+        let sampler = MeterValue::sampler(self.client.now())
+            .context(context)
+            .energy_active_import_register(connector.meter, SampledValueUnit::Wh);
+
+        match &connector.ev {
+            Some(ev) => sampler
+                .power_active_import(ev.power.round(), SampledValueUnit::W)
+                .current_import((ev.current * 10.0).round() / 10.0, SampledValueUnit::A)
+                .voltage(NOMINAL_VOLTAGE, SampledValueUnit::V)
+                .soc(ev.soc.floor())
+                .build(),
+            None => sampler.build(),
+        }
+    }
+
+    async fn send_stop_transaction(&self, request: StopTransactionRequest) -> Result<()> {
+        self.client.send(request).await?;
+
+        Ok(())
+    }
+
+    async fn send_firmware_status(&self, status: FirmwareStatusNotificationStatus) -> Result<()> {
+        self.client
+            .send(
+                FirmwareStatusNotificationRequest::builder()
+                    .status(status)
+                    .build(),
+            )
+            .await?;
+        self.state.lock().unwrap().firmware_status = status;
+
+        Ok(())
+    }
+
+    async fn send_diagnostics_status(
+        &self,
+        status: DiagnosticsStatusNotificationStatus,
+    ) -> Result<()> {
+        self.client
+            .send(
+                DiagnosticsStatusNotificationRequest::builder()
+                    .status(status)
+                    .build(),
+            )
+            .await?;
+        self.state.lock().unwrap().diagnostics_status = status;
+
+        Ok(())
+    }
+
+    /// Notify `state`, the new state of `connector_id`, once it has lasted
+    /// the `MinimumStatusDuration`, see [`StatusDebouncer`].
+    async fn send_status_notification(
+        self: &Arc<Self>,
+        connector_id: i32,
+        state: impl Into<ConnectorState>,
+    ) -> Result<()> {
+        let state = state.into();
+        let minimum_status_duration = self.minimum_status_duration();
+
+        if minimum_status_duration.is_zero() {
+            return self.send_connector_state(connector_id, state).await;
+        }
+
+        let schedule = self.state.lock().unwrap().status_debouncer.hold(
+            connector_id,
+            state,
+            time::Instant::now(),
+        );
+
+        if schedule {
+            tokio::spawn(release_connector_state(self.clone(), connector_id));
+        }
+
+        Ok(())
+    }
+
+    /// Notify `state` of `connector_id` right away.
+    async fn send_connector_state(&self, connector_id: i32, state: ConnectorState) -> Result<()> {
+        self.state
+            .lock()
+            .unwrap()
+            .status_debouncer
+            .sent(connector_id, state.clone());
+
+        let error = state
+            .error
+            .unwrap_or_else(|| ConnectorError::new(ChargePointErrorCode::NoError));
+
+        self.client
+            .send(
+                StatusNotificationRequest::builder()
+                    .connector_id(connector_id)
+                    .error_code(error.error_code)
+                    .info_opt(error.info)
+                    .status(state.status)
+                    .timestamp(self.client.now())
+                    .vendor_error_code_opt(error.vendor_error_code)
+                    .vendor_id_opt(error.vendor_id)
+                    .build(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// The `MinimumStatusDuration` of the configuration.
+    fn minimum_status_duration(&self) -> Duration {
+        Duration::from_secs(
+            self.state
+                .lock()
+                .unwrap()
+                .configuration
+                .get_integer("MinimumStatusDuration")
+                .unwrap_or(0)
+                .into(),
+        )
+    }
+}
+
+/// The instant `duration` from now.
+fn deadline(duration: Duration) -> Result<time::Instant> {
+    time::Instant::now()
+        .checked_add(duration)
+        .ok_or(Error::InvalidDuration(duration))
+}
+
+fn boot_notification_request(config: &SimulatorConfig) -> Result<BootNotificationRequest> {
+    let mut request = BootNotificationRequest::builder()
+        .charge_point_vendor(ci_string::<CiString20>(&config.vendor, "vendor")?)
+        .charge_point_model(ci_string::<CiString20>(&config.model, "model")?)
+        .build();
+    request.firmware_version = config
+        .firmware_version
+        .as_deref()
+        .map(|firmware_version| ci_string(firmware_version, "firmware version"))
+        .transpose()?;
+
+    Ok(request)
+}
+
+/// `value` as a `CiString` of the requests, named `name` in the error.
+fn ci_string<'a, T>(value: &'a str, name: &'static str) -> Result<T>
+where
+    T: TryFrom<&'a str, Error = CiStringError>,
+{
+    T::try_from(value).map_err(|error| Error::InvalidString(name, error))
+}
+
+async fn send_meter_values(inner: Arc<Inner>) {
+    let interval = inner.config.meter_values_interval;
+
+    loop {
+        time::sleep(interval).await;
+
+        let now = inner.client.now();
+        let charging = {
+            let mut state = inner.state.lock().unwrap();
+            let State {
+                connectors,
+                charging_profiles,
+                ..
+            } = &mut *state;
+
+            connectors
+                .values_mut()
+                .filter_map(|connector| {
+                    if connector.status != ConnectorStatus::Charging {
+                        if let Some(ev) = &mut connector.ev {
+                            ev.idle();
+                        }
+
+                        return None;
+                    }
+
+                    // An EV is plugged in even if the transaction started
+                    // without it.
+                    let ev = connector
+                        .ev
+                        .get_or_insert_with(|| Ev::new(&inner.config.ev));
+                    connector.meter += ev.charge(
+                        &inner.config.ev,
+                        charging_profiles.limit(connector.id, now),
+                        connector
+                            .charging_power
+                            .unwrap_or(inner.config.charging_power),
+                        inner.config.phases,
+                        interval,
+                    );
+
+                    Some(connector.clone())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for connector in charging {
+            let request = MeterValuesRequest::builder()
+                .connector_id(connector.id)
+                .transaction_id_opt(
+                    connector
+                        .transaction
+                        .as_ref()
+                        .map(|transaction| transaction.id),
+                )
+                .meter_value(vec![
+                    inner.meter_value(&connector, SampledValueContext::SamplePeriodic)
+                ])
+                .build();
+
+            if inner.client.send_meter_values(request).await.is_err() && inner.client.is_closed() {
+                return;
+            }
+        }
+    }
+}
+
+/// Notify the state held back for `connector_id` once it has lasted the
+/// `MinimumStatusDuration`, unless the connector is back to the state
+/// notified last.
+async fn release_connector_state(inner: Arc<Inner>, connector_id: i32) {
+    loop {
+        let minimum_status_duration = inner.minimum_status_duration();
+        let release = inner.state.lock().unwrap().status_debouncer.release(
+            connector_id,
+            time::Instant::now(),
+            minimum_status_duration,
+        );
+
+        match release {
+            Release::Now(state) => {
+                let _ = inner.send_connector_state(connector_id, state).await;
+
+                break;
+            }
+            Release::Later(instant) => time::sleep_until(instant).await,
+            Release::Nothing => break,
+        }
+    }
+}
+
+/// Interval between two checks of the expiry of the reservations.
+const RESERVATIONS_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+async fn expire_reservations(inner: Arc<Inner>) {
+    loop {
+        time::sleep(RESERVATIONS_CHECK_INTERVAL).await;
+
+        let mut state = inner.state.lock().unwrap();
+
+        for reservation in state.reservations.expire(inner.client.now()) {
+            end_reservation(&inner, &mut state, &reservation);
+        }
+    }
+}
+
+/// Make the connector of `reservation` available again, if it is still
+/// reserved.
+fn end_reservation(inner: &Arc<Inner>, state: &mut State, reservation: &Reservation) {
+    let Some(connector) = state.connectors.get_mut(&reservation.connector_id) else {
+        return;
+    };
+
+    if let Some(status) = connector.status.next(ConnectorEvent::ReservationEnded) {
+        connector.status = status;
+
+        tokio::spawn({
+            let inner = inner.clone();
+            let connector_id = connector.id;
+
+            async move {
+                let _ = inner.send_status_notification(connector_id, status).await;
+            }
+        });
+    }
+}
+
+/// Apply a `ReserveNow`, reserving the connector if it is available.
+fn reserve_now(
+    inner: &Arc<Inner>,
+    state: &mut State,
+    request: &ReserveNowRequest,
+) -> ReserveNowStatus {
+    let connector_status = match request.connector_id {
+        0 => None,
+        connector_id => match state.connectors.get(&connector_id) {
+            Some(connector) => Some(connector.status),
+            None => return ReserveNowStatus::Rejected,
+        },
+    };
+
+    match connector_status {
+        None | Some(ConnectorStatus::Available | ConnectorStatus::Reserved) => {}
+        Some(ConnectorStatus::Faulted) => return ReserveNowStatus::Faulted,
+        Some(ConnectorStatus::Unavailable) => return ReserveNowStatus::Unavailable,
+        Some(_) => return ReserveNowStatus::Occupied,
+    }
+
+    let status = state.reservations.reserve(request, inner.client.now());
+
+    if status == ReserveNowStatus::Accepted && connector_status == Some(ConnectorStatus::Available)
+    {
+        let connector = state.connectors.get_mut(&request.connector_id).unwrap();
+        connector.status = ConnectorStatus::Reserved;
+
+        tokio::spawn({
+            let inner = inner.clone();
+            let connector_id = connector.id;
+
+            async move {
+                let _ = inner
+                    .send_status_notification(connector_id, ConnectorStatus::Reserved)
+                    .await;
+            }
+        });
+    }
+
+    status
+}
+
+/// Apply a `ChangeAvailability` on its connector, or on the charge point
+/// itself and every connector for the connector 0. Making a connector with
+/// an ongoing transaction `Inoperative` is `Scheduled`: it happens when the
+/// transaction ends.
+fn change_availability(
+    inner: &Arc<Inner>,
+    state: &mut State,
+    request: &ChangeAvailabilityRequest,
+) -> ChangeAvailabilityStatus {
+    let event = match request.r#type {
+        ChangeAvailabilityType::Inoperative => ConnectorEvent::MakeInoperative,
+        ChangeAvailabilityType::Operative => ConnectorEvent::MakeOperative,
+    };
+    let connector_ids = match request.connector_id {
+        0 => state.connectors.keys().copied().collect(),
+        connector_id if state.connectors.contains_key(&connector_id) => vec![connector_id],
+        _ => return ChangeAvailabilityStatus::Rejected,
+    };
+    let mut status = ChangeAvailabilityStatus::Accepted;
+
+    // The connector 0 is the charge point itself, it has no state.
+    if request.connector_id == 0 {
+        let connector_status = match request.r#type {
+            ChangeAvailabilityType::Inoperative => ConnectorStatus::Unavailable,
+            ChangeAvailabilityType::Operative => ConnectorStatus::Available,
+        };
+
+        tokio::spawn({
+            let inner = inner.clone();
+
+            async move {
+                let _ = inner.send_status_notification(0, connector_status).await;
+            }
+        });
+    }
+
+    for connector_id in connector_ids {
+        let connector = state.connectors.get_mut(&connector_id).unwrap();
+
+        // The last change wins over the scheduled one.
+        connector.deferred = None;
+
+        if connector.transaction.is_some() && event == ConnectorEvent::MakeInoperative {
+            connector.deferred = Some(event);
+            status = ChangeAvailabilityStatus::Scheduled;
+
+            continue;
+        }
+
+        // Already in the requested availability otherwise.
+        if let Some(connector_status) = connector.status.next(event) {
+            connector.status = connector_status;
+
+            tokio::spawn({
+                let inner = inner.clone();
+
+                async move {
+                    let _ = inner
+                        .send_status_notification(connector_id, connector_status)
+                        .await;
+                }
+            });
+        }
+    }
+
+    status
+}
+
+/// Send the message requested by a `TriggerMessage`. Without a connector,
+/// `MeterValues` and `StatusNotification` are sent for every connector.
+async fn trigger_message(inner: Arc<Inner>, request: TriggerMessageRequest) -> Result<()> {
+    let (connectors, firmware_status, diagnostics_status) = {
+        let state = inner.state.lock().unwrap();
+
+        (
+            state
+                .connectors
+                .values()
+                .filter(|connector| {
+                    request
+                        .connector_id
+                        .is_none_or(|connector_id| connector.id == connector_id)
+                })
+                .cloned()
+                .collect::<Vec<_>>(),
+            state.firmware_status,
+            state.diagnostics_status,
+        )
+    };
+
+    match request.requested_message {
+        TriggerMessageRequestedMessage::BootNotification => {
+            inner
+                .client
+                .send(boot_notification_request(&inner.config)?)
+                .await?;
+        }
+        TriggerMessageRequestedMessage::Heartbeat => {
+            inner
+                .client
+                .send(HeartbeatRequest::builder().build())
+                .await?;
+        }
+        TriggerMessageRequestedMessage::FirmwareStatusNotification => {
+            inner.send_firmware_status(firmware_status).await?;
+        }
+        TriggerMessageRequestedMessage::DiagnosticsStatusNotification => {
+            inner.send_diagnostics_status(diagnostics_status).await?;
+        }
+        TriggerMessageRequestedMessage::MeterValues => {
+            for connector in connectors {
+                inner
+                    .client
+                    .send(
+                        MeterValuesRequest::builder()
+                            .connector_id(connector.id)
+                            .transaction_id_opt(
+                                connector
+                                    .transaction
+                                    .as_ref()
+                                    .map(|transaction| transaction.id),
+                            )
+                            .meter_value(vec![
+                                inner.meter_value(&connector, SampledValueContext::Trigger)
+                            ])
+                            .build(),
+                    )
+                    .await?;
+            }
+        }
+        TriggerMessageRequestedMessage::StatusNotification => {
+            // The connector 0 is the Charge Point itself.
+            if request
+                .connector_id
+                .is_none_or(|connector_id| connector_id == 0)
+            {
+                inner
+                    .send_connector_state(0, ConnectorStatus::Available.into())
+                    .await?;
+            }
+
+            for connector in connectors {
+                inner
+                    .send_connector_state(connector.id, connector.state())
+                    .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_calls(inner: Arc<Inner>) {
+    while let Some(call) = inner.client.next_call().await {
+        let response = handle_call(&inner, call);
+        let response_delay = inner.state.lock().unwrap().response_delay;
+
+        match response_delay {
+            // The next `Call`s are not held back.
+            Some(response_delay) => {
+                tokio::spawn({
+                    let inner = inner.clone();
+
+                    async move {
+                        time::sleep(response_delay).await;
+                        let _ = inner.client.respond(response);
+                    }
+                });
+            }
+            None => {
+                if inner.client.respond(response).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Download and install a firmware, notifying the Central System of each
+/// step with a `FirmwareStatusNotification`.
+async fn update_firmware(inner: Arc<Inner>, request: UpdateFirmwareRequest) {
+    let firmware = &inner.config.firmware;
+    let retry_interval = request
+        .retry_interval
+        .map(|retry_interval| Duration::from_secs(retry_interval.max(0) as u64))
+        .unwrap_or(firmware.retry_interval);
+
+    time::sleep(
+        (request.retrieve_date - inner.client.now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+    .await;
+
+    let mut attempt = 0;
+
+    loop {
+        if inner
+            .send_firmware_status(FirmwareStatusNotificationStatus::Downloading)
+            .await
+            .is_err()
+        {
+            return;
+        }
+
+        time::sleep(firmware.download_duration).await;
+
+        if attempt >= firmware.download_failures {
+            break;
+        }
+
+        if inner
+            .send_firmware_status(FirmwareStatusNotificationStatus::DownloadFailed)
+            .await
+            .is_err()
+            || attempt >= request.retries.unwrap_or(0).max(0) as u32
+        {
+            return;
+        }
+
+        attempt += 1;
+        time::sleep(retry_interval).await;
+    }
+
+    for status in [
+        FirmwareStatusNotificationStatus::Downloaded,
+        FirmwareStatusNotificationStatus::Installing,
+    ] {
+        if inner.send_firmware_status(status).await.is_err() {
+            return;
+        }
+    }
+
+    time::sleep(firmware.install_duration).await;
+
+    let _ = inner
+        .send_firmware_status(if firmware.install_fails {
+            FirmwareStatusNotificationStatus::InstallationFailed
+        } else {
+            FirmwareStatusNotificationStatus::Installed
+        })
+        .await;
+}
+
+/// Upload a diagnostics archive, notifying the Central System of each step
+/// with a `DiagnosticsStatusNotification`.
+async fn upload_diagnostics(
+    inner: Arc<Inner>,
+    request: GetDiagnosticsRequest,
+    file_name: String,
+    archive: Vec<u8>,
+) {
+    let retry_interval = request
+        .retry_interval
+        .map(|retry_interval| Duration::from_secs(retry_interval.max(0) as u64))
+        .unwrap_or(inner.config.diagnostics_retry_interval);
+    let mut attempts = request.retries.unwrap_or(0).max(0) + 1;
+
+    let status = loop {
+        if inner
+            .send_diagnostics_status(DiagnosticsStatusNotificationStatus::Uploading)
+            .await
+            .is_err()
+        {
+            return;
+        }
+
+        match inner
+            .config
+            .diagnostics_uploader
+            .upload(&request.location, &file_name, archive.clone())
+            .await
+        {
+            Ok(()) => break DiagnosticsStatusNotificationStatus::Uploaded,
+            Err(_) if attempts > 1 => {
+                attempts -= 1;
+                time::sleep(retry_interval).await;
+            }
+            Err(_) => break DiagnosticsStatusNotificationStatus::UploadFailed,
+        }
+    };
+
+    let _ = inner.send_diagnostics_status(status).await;
+}
+
+/// The diagnostics archive: the state of the Charge Point, and its
+/// configuration.
+fn diagnostics_archive(inner: &Inner, state: &State, request: &GetDiagnosticsRequest) -> Vec<u8> {
+    let status = json!({
+        "chargePointId": inner.config.charge_point_id,
+        "vendor": inner.config.vendor,
+        "model": inner.config.model,
+        "firmwareVersion": inner.config.firmware_version,
+        "firmwareStatus": state.firmware_status,
+        "startTime": request.start_time,
+        "stopTime": request.stop_time,
+        "connectors": state
+            .connectors
+            .values()
+            .map(|connector| json!({
+                "connectorId": connector.id,
+                "status": connector.status,
+                "meter": connector.meter,
+                "transactionId": connector.transaction.as_ref().map(|transaction| transaction.id),
+            }))
+            .collect::<Vec<_>>(),
+    });
+    let configuration = json!(state
+        .configuration
+        .get_configuration(&GetConfigurationRequest::builder().build()));
+
+    diagnostics::archive(&[
+        ("status.json", status.to_string().as_bytes()),
+        ("configuration.json", configuration.to_string().as_bytes()),
+    ])
+}
+
+fn handle_call(inner: &Arc<Inner>, call: Call) -> Message {
+    let mut state = inner.state.lock().unwrap();
+
+    let payload = match call.action.as_str() {
+        "SendLocalList" => call.payload::<SendLocalListRequest>().map(|request| {
+            json!(SendLocalListResponse::builder()
+                .status(state.local_auth_list.apply(&request))
+                .build())
+        }),
+        "GetLocalListVersion" => Ok(json!(GetLocalListVersionResponse::builder()
+            .list_version(state.local_auth_list.version())
+            .build())),
+        "GetConfiguration" => call
+            .payload::<GetConfigurationRequest>()
+            .map(|request| json!(state.configuration.get_configuration(&request))),
+        "ChangeConfiguration" => call
+            .payload::<ChangeConfigurationRequest>()
+            .map(|request| json!(state.configuration.change_configuration(&request))),
+        "SetChargingProfile" => call.payload::<SetChargingProfileRequest>().map(|request| {
+            json!(SetChargingProfileResponse::builder()
+                .status(state.charging_profiles.set_charging_profile(&request))
+                .build())
+        }),
+        "ClearChargingProfile" => call
+            .payload::<ClearChargingProfileRequest>()
+            .map(|request| {
+                json!(ClearChargingProfileResponse::builder()
+                    .status(state.charging_profiles.clear_charging_profile(&request))
+                    .build())
+            }),
+        "GetCompositeSchedule" => call
+            .payload::<GetCompositeScheduleRequest>()
+            .map(|request| {
+                json!(state
+                    .charging_profiles
+                    .get_composite_schedule(&request, inner.client.now()))
+            }),
+        "UpdateFirmware" => call.payload::<UpdateFirmwareRequest>().map(|request| {
+            // A new update replaces the ongoing one.
+            if let Some(firmware_update) = state
+                .firmware_update
+                .replace(tokio::spawn(update_firmware(inner.clone(), request)))
+            {
+                firmware_update.abort();
+            }
+
+            json!(UpdateFirmwareResponse::builder().build())
+        }),
+        "GetDiagnostics" => call.payload::<GetDiagnosticsRequest>().map(|request| {
+            let file_name = format!(
+                "{}-{}.tar",
+                inner.config.charge_point_id,
+                inner.client.now().format("%Y%m%dT%H%M%SZ"),
+            );
+            let archive = diagnostics_archive(inner, &state, &request);
+
+            // A new upload replaces the ongoing one.
+            if let Some(diagnostics_upload) =
+                state
+                    .diagnostics_upload
+                    .replace(tokio::spawn(upload_diagnostics(
+                        inner.clone(),
+                        request,
+                        file_name.clone(),
+                        archive,
+                    )))
+            {
+                diagnostics_upload.abort();
+            }
+
+            json!(GetDiagnosticsResponse::builder()
+                .file_name_opt(CiString255::try_from(file_name).ok())
+                .build())
+        }),
+        "ReserveNow" => call.payload::<ReserveNowRequest>().map(|request| {
+            json!(ReserveNowResponse::builder()
+                .status(reserve_now(inner, &mut state, &request))
+                .build())
+        }),
+        "CancelReservation" => call.payload::<CancelReservationRequest>().map(|request| {
+            let status = match state.reservations.cancel(request.reservation_id) {
+                Some(reservation) => {
+                    end_reservation(inner, &mut state, &reservation);
+
+                    CancelReservationStatus::Accepted
+                }
+                None => CancelReservationStatus::Rejected,
+            };
+
+            json!(CancelReservationResponse::builder().status(status).build())
+        }),
+        "ChangeAvailability" => call.payload::<ChangeAvailabilityRequest>().map(|request| {
+            json!(ChangeAvailabilityResponse::builder()
+                .status(change_availability(inner, &mut state, &request))
+                .build())
+        }),
+        "TriggerMessage" => match call.payload::<TriggerMessageRequest>() {
+            Ok(request) => {
+                let status = match request.connector_id {
+                    Some(connector_id)
+                        if connector_id != 0 && !state.connectors.contains_key(&connector_id) =>
+                    {
+                        TriggerMessageStatus::Rejected
+                    }
+                    _ => {
+                        // The message is sent after the response.
+                        tokio::spawn({
+                            let inner = inner.clone();
+
+                            async move {
+                                let _ = trigger_message(inner, request).await;
+                            }
+                        });
+
+                        TriggerMessageStatus::Accepted
+                    }
+                };
+
+                Ok(json!(TriggerMessageResponse::builder()
+                    .status(status)
+                    .build()))
+            }
+            // A message that the simulator does not know.
+            Err(_) if call.payload["requestedMessage"].is_string() => {
+                Ok(json!(TriggerMessageResponse::builder()
+                    .status(TriggerMessageStatus::NotImplemented)
+                    .build()))
+            }
+            Err(error) => Err(error),
+        },
+        "ClearCache" => {
+            state.authorization_cache.clear();
+
+            Ok(json!(ClearCacheResponse::builder()
+                .status(ClearCacheStatus::Accepted)
+                .build()))
+        }
+        _ => {
+            return CallError::new(
+                call.unique_id,
+                ErrorCode::NotImplemented,
+                format!("`{}` is not supported by the simulator", call.action),
+                None,
+            )
+            .into()
+        }
+    };
+
+    match payload {
+        Ok(payload) => CallResult {
+            unique_id: call.unique_id,
+            payload,
+        }
+        .into(),
+        Err(_) => CallError::new(
+            call.unique_id,
+            ErrorCode::FormationViolation,
+            format!("invalid `{}` payload", call.action),
+            None,
+        )
+        .into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Fault, FirmwareConfig};
+    use ocppx_server::{CsmsHandler, Server};
+    use ocppx_types::v1_6::SendLocalListStatus;
+    use tokio::net::TcpListener;
+
+    #[derive(Clone, Default)]
+    struct Csms {
+        actions: Arc<Mutex<Vec<String>>>,
+        firmware_statuses: Arc<Mutex<Vec<String>>>,
+        diagnostics_statuses: Arc<Mutex<Vec<String>>>,
+        status_notifications: Arc<Mutex<Vec<serde_json::Value>>>,
+    }
+
+    impl CsmsHandler for Csms {
+        async fn handle_call(
+            &self,
+            _charge_point_id: &str,
+            call: Call,
+        ) -> std::result::Result<CallResult, CallError> {
+            self.actions.lock().unwrap().push(call.action.clone());
+
+            if call.action == "StatusNotification" {
+                self.status_notifications
+                    .lock()
+                    .unwrap()
+                    .push(call.payload.clone());
+            }
+
+            if call.action == "FirmwareStatusNotification" {
+                self.firmware_statuses
+                    .lock()
+                    .unwrap()
+                    .push(call.payload["status"].as_str().unwrap().to_owned());
+            }
+
+            if call.action == "DiagnosticsStatusNotification" {
+                self.diagnostics_statuses
+                    .lock()
+                    .unwrap()
+                    .push(call.payload["status"].as_str().unwrap().to_owned());
+            }
+
+            let payload = match call.action.as_str() {
+                "BootNotification" => json!({
+                    "status": "Accepted",
+                    "currentTime": "2013-02-01T20:53:32.486Z",
+                    "interval": 300,
+                }),
+                "StartTransaction" => json!({
+                    "idTagInfo": {"status": "Accepted"},
+                    "transactionId": 42,
+                }),
+                "Heartbeat" => json!({"currentTime": "2013-02-01T20:53:32.486Z"}),
+                _ => json!({}),
+            };
+
+            Ok(CallResult {
+                unique_id: call.unique_id,
+                payload,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_faults() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let csms = Csms::default();
+        let server = Server::new(csms.clone());
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let simulator = Simulator::start(SimulatorConfig::new(
+            format!("ws://{address}/ocpp"),
+            "CP001",
+        ))
+        .await
+        .unwrap();
+        let faults = simulator.faults();
+
+        faults.delay_responses(Some(Duration::from_millis(200)));
+        let started = time::Instant::now();
+        let response: ClearCacheResponse = server
+            .call("CP001", "ClearCache", &json!({}))
+            .await
+            .unwrap();
+        assert_eq!(response.status, ClearCacheStatus::Accepted);
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        faults.delay_responses(None);
+
+        for fault in [
+            Fault::MalformedJson,
+            Fault::WrongMessageTypeId(7),
+            Fault::DuplicateUniqueId,
+            Fault::InvalidEnumValue,
+        ] {
+            faults.inject(fault).unwrap();
+        }
+
+        // The Central System has survived.
+        simulator.heartbeat().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_scenario() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let csms = Csms::default();
+
+        let server = Server::new(csms.clone());
+
+        tokio::spawn(async move { server.serve(listener).await });
+
+        let mut config = SimulatorConfig::new(format!("ws://{address}/ocpp"), "CP001");
+        config.meter_values_interval = Duration::from_millis(100);
+        config.charging_power = 50_000;
+        config.ev.max_power = 50_000;
+        let simulator = Simulator::start(config).await.unwrap();
+
+        let scenario = serde_json::from_value::<Scenario>(json!({
+            "steps": [
+                { "at": 0, "action": "heartbeat", "every": "200ms" },
+                { "at": 0, "action": "plugIn", "connectorId": 1 },
+                { "at": "100ms", "action": "charge", "connectorId": 1, "idTag": "TAG", "energy": 10, "duration": 1 },
+                { "at": "1.1s", "action": "unplug", "connectorId": 1 },
+            ],
+        }))
+        .unwrap();
+        let mut progress = Vec::new();
+
+        simulator
+            .run_scenario(&scenario, |event| progress.push(event))
+            .await
+            .unwrap();
+
+        // 1 Wh at each of the 9 or 10 `MeterValues` of the charge.
+        let connector = &simulator.connectors()[0];
+        assert_eq!(connector.status, ConnectorStatus::Available);
+        assert_eq!(connector.charging_power, None);
+        assert!((9..=10).contains(&connector.meter), "{}", connector.meter);
+
+        let heartbeats = progress
+            .iter()
+            .filter(|event| **event == ScenarioProgress::Heartbeat)
+            .count();
+        assert!(heartbeats >= 4, "{heartbeats}");
+        assert_eq!(
+            progress
+                .into_iter()
+                .filter(|event| *event != ScenarioProgress::Heartbeat)
+                .collect::<Vec<_>>(),
+            (0..4)
+                .flat_map(|step| [
+                    ScenarioProgress::StepStarted { step },
+                    ScenarioProgress::StepDone { step }
+                ])
+                .collect::<Vec<_>>()
+        );
+        assert!(csms
+            .actions
+            .lock()
+            .unwrap()
+            .contains(&"StopTransaction".to_owned()));
+
+        let scenario = Scenario {
+            name: "Too late".to_owned(),
+            steps: vec![ScenarioStep {
+                at: Duration::MAX,
+                step: Step::Unplug { connector_id: 1 },
+            }],
+        };
+        assert!(matches!(
+            simulator.run_scenario(&scenario, |_| {}).await,
+            Err(Error::InvalidDuration(Duration::MAX))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_replay() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let csms = Csms::default();
+        let server = Server::new(csms.clone());
+
+        tokio::spawn(async move { server.serve(listener).await });
+
+        let simulator = Simulator::start(SimulatorConfig::new(
+            format!("ws://{address}/ocpp"),
+            "CP001",
+        ))
+        .await
+        .unwrap();
+
+        let frame = |direction, frame: &str| CapturedFrame {
+            timestamp: "2013-02-01T20:53:32.486Z".parse().unwrap(),
+            direction,
+            charge_point_id: "CP001".to_owned(),
+            frame: frame.to_owned(),
+        };
+        let replayer = Replayer::new(vec![
+            frame(Direction::Outgoing, r#"[2,"1","Heartbeat",{}]"#),
+            frame(Direction::Incoming, r#"[3,"1",{}]"#),
+            frame(Direction::Incoming, r#"[2,"2","ClearCache",{}]"#),
+            frame(
+                Direction::Outgoing,
+                r#"[2,"3","DataTransfer",{"vendorId":"ocppx"}]"#,
+            ),
+        ]);
+
+        let responses = simulator.replay(&replayer, Direction::Outgoing).await;
+
+        assert_eq!(responses.len(), 2);
+        assert_eq!(
+            responses[0].1.as_ref().unwrap()["currentTime"],
+            "2013-02-01T20:53:32.486Z"
+        );
+
+        let actions = csms.actions.lock().unwrap().clone();
+        assert!(actions.ends_with(&["Heartbeat".to_owned(), "DataTransfer".to_owned()]));
+        assert!(!actions.contains(&"ClearCache".to_owned()));
+    }
+
+    #[tokio::test]
+    async fn test_transaction_flow() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let csms = Csms::default();
+        let server = Server::new(csms.clone());
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let simulator = Simulator::start(SimulatorConfig::new(
+            format!("ws://{address}/ocpp"),
+            "CP001",
+        ))
+        .await
+        .unwrap();
+
+        simulator
+            .run([
+                Step::PlugIn { connector_id: 1 },
+                Step::StartTransaction {
+                    connector_id: 1,
+                    id_tag: "TAG".to_owned(),
+                },
+            ])
+            .await
+            .unwrap();
+
+        let connectors = simulator.connectors();
+        assert_eq!(connectors[0].status, ConnectorStatus::Charging);
+        assert_eq!(connectors[0].transaction.as_ref().unwrap().id, 42);
+
+        assert!(matches!(
+            simulator.plug_in(1).await,
+            Err(Error::InvalidTransition { .. })
+        ));
+
+        simulator
+            .run([
+                Step::StopTransaction { connector_id: 1 },
+                Step::Unplug { connector_id: 1 },
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(simulator.connectors()[0].status, ConnectorStatus::Available);
+        assert_eq!(
+            *csms.actions.lock().unwrap(),
+            [
+                "BootNotification",
+                "StatusNotification",
+                "StatusNotification",
+                "StatusNotification",
+                "StartTransaction",
+                "StatusNotification",
+                "StopTransaction",
+                "StatusNotification",
+                "StatusNotification",
+            ]
+        );
+
+        let response: SendLocalListResponse = server
+            .call(
+                "CP001",
+                "SendLocalList",
+                &json!({
+                    "listVersion": 2,
+                    "updateType": "Full",
+                    "localAuthorizationList": [{"idTag": "TAG", "idTagInfo": {"status": "Accepted"}}],
+                }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status, SendLocalListStatus::Accepted);
+
+        let response: GetLocalListVersionResponse = server
+            .call("CP001", "GetLocalListVersion", &json!({}))
+            .await
+            .unwrap();
+        assert_eq!(response.list_version, 2);
+
+        let response: ReserveNowResponse = server
+            .call(
+                "CP001",
+                "ReserveNow",
+                &json!({
+                    "connectorId": 1,
+                    "expiryDate": "2100-01-01T00:00:00Z",
+                    "idTag": "OTHER",
+                    "reservationId": 7,
+                }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status, ReserveNowStatus::Accepted);
+        assert_eq!(simulator.connectors()[0].status, ConnectorStatus::Reserved);
+        assert!(matches!(
+            simulator.start_transaction(1, "TAG").await,
+            Err(Error::Reserved(1))
+        ));
+
+        let response: CancelReservationResponse = server
+            .call("CP001", "CancelReservation", &json!({"reservationId": 7}))
+            .await
+            .unwrap();
+        assert_eq!(response.status, CancelReservationStatus::Accepted);
+        assert_eq!(simulator.connectors()[0].status, ConnectorStatus::Available);
+
+        let response: TriggerMessageResponse = server
+            .call(
+                "CP001",
+                "TriggerMessage",
+                &json!({"requestedMessage": "Heartbeat"}),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status, TriggerMessageStatus::Accepted);
+
+        time::timeout(Duration::from_secs(5), async {
+            while csms.actions.lock().unwrap().last().unwrap() != "Heartbeat" {
+                time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        let response: TriggerMessageResponse = server
+            .call(
+                "CP001",
+                "TriggerMessage",
+                &json!({"requestedMessage": "SignChargePointCertificate"}),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status, TriggerMessageStatus::NotImplemented);
+
+        simulator.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_change_availability() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let csms = Csms::default();
+        let server = Server::new(csms.clone());
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let mut config = SimulatorConfig::new(format!("ws://{address}/ocpp"), "CP001");
+        config.connectors = 2;
+        let simulator = Simulator::start(config).await.unwrap();
+        let change_availability = |connector_id: i32, r#type: &str| {
+            let server = server.clone();
+            let request = json!({ "connectorId": connector_id, "type": r#type });
+
+            async move {
+                server
+                    .call::<_, ChangeAvailabilityResponse>("CP001", "ChangeAvailability", &request)
+                    .await
+                    .unwrap()
+                    .status
+            }
+        };
+
+        simulator.plug_in(1).await.unwrap();
+        simulator.start_transaction(1, "TAG").await.unwrap();
+
+        // The connector with a transaction is made unavailable once it ends.
+        assert_eq!(
+            change_availability(0, "Inoperative").await,
+            ChangeAvailabilityStatus::Scheduled
+        );
+        let connectors = simulator.connectors();
+        assert_eq!(connectors[0].status, ConnectorStatus::Charging);
+        assert_eq!(connectors[1].status, ConnectorStatus::Unavailable);
+
+        simulator.stop_transaction(1).await.unwrap();
+        assert_eq!(
+            simulator.connectors()[0].status,
+            ConnectorStatus::Unavailable
+        );
+
+        // The charge point itself is notified as unavailable.
+        time::timeout(Duration::from_secs(5), async {
+            while !csms
+                .status_notifications
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|payload| payload["connectorId"] == 0 && payload["status"] == "Unavailable")
+            {
+                time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        simulator.unplug(1).await.unwrap();
+        assert!(matches!(
+            simulator.plug_in(1).await,
+            Err(Error::InvalidTransition { .. })
+        ));
+
+        assert_eq!(
+            change_availability(1, "Operative").await,
+            ChangeAvailabilityStatus::Accepted
+        );
+        assert_eq!(simulator.connectors()[0].status, ConnectorStatus::Available);
+        assert_eq!(
+            change_availability(3, "Operative").await,
+            ChangeAvailabilityStatus::Rejected
+        );
+
+        simulator.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_status_debouncing() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let csms = Csms::default();
+        let server = Server::new(csms.clone());
+
+        tokio::spawn(async move { server.serve(listener).await });
+
+        let mut config = SimulatorConfig::new(format!("ws://{address}/ocpp"), "CP001");
+        config.minimum_status_duration = Duration::from_secs(1);
+        let simulator = Simulator::start(config).await.unwrap();
+        csms.status_notifications.lock().unwrap().clear();
+
+        // A flap back to the state notified last is not notified.
+        simulator.plug_in(1).await.unwrap();
+        simulator.unplug(1).await.unwrap();
+
+        // Only the last state of a storm is notified.
+        simulator.plug_in(1).await.unwrap();
+        simulator
+            .fault(
+                1,
+                ConnectorError::new(ChargePointErrorCode::GroundFailure)
+                    .info("Leakage".try_into().unwrap())
+                    .vendor_error("ocppx".try_into().unwrap(), "E42".try_into().unwrap()),
+            )
+            .await
+            .unwrap();
+
+        time::sleep(Duration::from_millis(1_500)).await;
+        assert_eq!(
+            csms.status_notifications
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|payload| {
+                    let mut payload = payload.clone();
+                    payload.as_object_mut().unwrap().remove("timestamp");
+
+                    payload
+                })
+                .collect::<Vec<_>>(),
+            [json!({
+                "connectorId": 1,
+                "status": "Faulted",
+                "errorCode": "GroundFailure",
+                "info": "Leakage",
+                "vendorId": "ocppx",
+                "vendorErrorCode": "E42",
+            })]
+        );
+
+        simulator.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_firmware_update() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let csms = Csms::default();
+        let server = Server::new(csms.clone());
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        let mut config = SimulatorConfig::new(format!("ws://{address}/ocpp"), "CP001");
+        config.firmware = FirmwareConfig {
+            download_duration: Duration::ZERO,
+            install_duration: Duration::ZERO,
+            download_failures: 1,
+            install_fails: false,
+            retry_interval: Duration::ZERO,
+        };
+        let simulator = Simulator::start(config).await.unwrap();
+
+        let _: UpdateFirmwareResponse = server
+            .call(
+                "CP001",
+                "UpdateFirmware",
+                &json!({
+                    "location": "https://example.org/firmware.bin",
+                    "retrieveDate": "2013-02-01T20:53:32.486Z",
+                    "retries": 1,
+                }),
+            )
+            .await
+            .unwrap();
+
+        time::timeout(Duration::from_secs(5), async {
+            while simulator.firmware_status() != FirmwareStatusNotificationStatus::Installed {
+                time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            *csms.firmware_statuses.lock().unwrap(),
+            [
+                "Downloading",
+                "DownloadFailed",
+                "Downloading",
+                "Downloaded",
+                "Installing",
+                "Installed",
+            ]
+        );
+
+        simulator.stop().await.unwrap();
+    }
+    #[tokio::test]
+    async fn test_get_diagnostics() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let csms = Csms::default();
+        let server = Server::new(csms.clone());
+
+        tokio::spawn({
+            let server = server.clone();
+
+            async move { server.serve(listener).await }
+        });
+
+        // The HTTP server of the diagnostics refuses the first upload.
+        let uploads_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let uploads_address = uploads_listener.local_addr().unwrap();
+        let uploads = tokio::spawn(async move {
+            let mut requests = vec![];
+
+            for response in [
+                "HTTP/1.1 503 Service Unavailable\r\n\r\n",
+                "HTTP/1.1 201 Created\r\n\r\n",
+            ] {
+                let (mut stream, _) = uploads_listener.accept().await.unwrap();
+                let mut request = vec![];
+
+                // The archive ends with two empty blocks.
+                while !request.ends_with(&[0; 1024]) {
+                    let mut buffer = [0; 4096];
+                    let length = stream.read(&mut buffer).await.unwrap();
+                    assert_ne!(length, 0);
+                    request.extend_from_slice(&buffer[..length]);
+                }
+
+                stream.write_all(response.as_bytes()).await.unwrap();
+
+                requests.push(String::from_utf8_lossy(&request).into_owned());
+            }
+
+            requests
+        });
+
+        let simulator = Simulator::start(SimulatorConfig::new(
+            format!("ws://{address}/ocpp"),
+            "CP001",
+        ))
+        .await
+        .unwrap();
+
+        let response: GetDiagnosticsResponse = server
+            .call(
+                "CP001",
+                "GetDiagnostics",
+                &json!({
+                    "location": format!("http://{uploads_address}/diagnostics"),
+                    "retries": 1,
+                    "retryInterval": 0,
+                }),
+            )
+            .await
+            .unwrap();
+        let file_name = response.file_name.unwrap().to_string();
+        assert!(file_name.starts_with("CP001-") && file_name.ends_with(".tar"));
+
+        time::timeout(Duration::from_secs(5), async {
+            while simulator.diagnostics_status() != DiagnosticsStatusNotificationStatus::Uploaded {
+                time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            *csms.diagnostics_statuses.lock().unwrap(),
+            ["Uploading", "Uploading", "Uploaded"]
+        );
+
+        let requests = uploads.await.unwrap();
+        assert!(requests[1].starts_with(&format!("PUT /diagnostics/{file_name} HTTP/1.1\r\n")));
+
+        simulator.stop().await.unwrap();
+    }
+}
@@ -0,0 +1,24 @@
+//! The OCPP 1.6 Smart Charging profile, on the Charge Point side.
+//!
+//! [`ChargingProfileStore`] keeps the charging profiles installed by the
+//! Central System with `SetChargingProfile`, clears them with
+//! `ClearChargingProfile`, and resolves the limit that applies to a
+//! connector at any time, following the stacking rules of the
+//! specification:
+//!
+//! * within a purpose, the valid profile with the highest stack level
+//!   wins, falling back to lower stack levels where its schedule does not
+//!   define a limit,
+//! * a `TxProfile` overrides the `TxDefaultProfile`s, and a
+//!   `TxDefaultProfile` set on a connector overrides the one set on the
+//!   connector 0,
+//! * the `ChargePointMaxProfile` caps the result.
+//!
+//! The composite schedule, as answered to `GetCompositeSchedule`, is the
+//! resolved limit over a period of time.
+
+mod schedule;
+mod store;
+
+pub use schedule::{Limit, NOMINAL_VOLTAGE};
+pub use store::ChargingProfileStore;
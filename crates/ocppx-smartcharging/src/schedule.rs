@@ -0,0 +1,305 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use ocppx_types::{
+    number_to_f64,
+    v1_6::{
+        ChargingProfileKind, ChargingProfileRecurrencyKind, ChargingScheduleChargingRateUnit,
+        CsChargingProfiles,
+    },
+};
+
+/// The voltage between a phase and the neutral, in V, used to convert
+/// limits in W to limits in A and back.
+pub const NOMINAL_VOLTAGE: f64 = 230.0;
+
+/// The number of phases when a period does not tell.
+const DEFAULT_NUMBER_PHASES: i32 = 3;
+
+/// A charging limit, normalized to a current per phase.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Limit {
+    /// The current per phase, in A.
+    pub current: f64,
+    pub number_phases: i32,
+}
+
+impl Limit {
+    pub(crate) fn new(
+        limit: f64,
+        unit: ChargingScheduleChargingRateUnit,
+        number_phases: Option<i32>,
+    ) -> Self {
+        let number_phases = number_phases.unwrap_or(DEFAULT_NUMBER_PHASES).max(1);
+
+        Self {
+            current: match unit {
+                ChargingScheduleChargingRateUnit::A => limit,
+                ChargingScheduleChargingRateUnit::W => {
+                    limit / (NOMINAL_VOLTAGE * f64::from(number_phases))
+                }
+            },
+            number_phases,
+        }
+    }
+
+    /// The limit expressed in `unit`.
+    pub fn value(&self, unit: ChargingScheduleChargingRateUnit) -> f64 {
+        match unit {
+            ChargingScheduleChargingRateUnit::A => self.current,
+            ChargingScheduleChargingRateUnit::W => {
+                self.current * NOMINAL_VOLTAGE * f64::from(self.number_phases)
+            }
+        }
+    }
+
+    /// The lowest of two limits.
+    pub(crate) fn min(self, other: Self) -> Self {
+        if other.current < self.current {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+/// The `startSchedule` of `profile`, or when it becomes valid.
+fn start_schedule(profile: &CsChargingProfiles) -> Option<DateTime<Utc>> {
+    profile
+        .charging_schedule
+        .start_schedule
+        .or(profile.valid_from)
+}
+
+/// The start of the schedule of `profile` that is in force at `at`.
+///
+/// Relative profiles start at `relative_start`, i.e. with the transaction,
+/// or when the schedule is evaluated from without a transaction. Recurring
+/// profiles restart every day or week from their `startSchedule`, and are
+/// not started before it.
+pub(crate) fn schedule_start(
+    profile: &CsChargingProfiles,
+    relative_start: DateTime<Utc>,
+    at: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    match profile.charging_profile_kind {
+        ChargingProfileKind::Absolute => start_schedule(profile),
+        ChargingProfileKind::Relative => Some(relative_start),
+        ChargingProfileKind::Recurring => {
+            let start_schedule = start_schedule(profile).filter(|start| *start <= at)?;
+            let recurrence = recurrence(profile);
+            let elapsed = (at - start_schedule).num_seconds();
+            let recurrences = elapsed / recurrence.num_seconds();
+
+            Some(start_schedule + recurrence * recurrences as i32)
+        }
+    }
+}
+
+/// The time between two recurrences of a recurring profile.
+fn recurrence(profile: &CsChargingProfiles) -> TimeDelta {
+    match profile
+        .recurrency_kind
+        .unwrap_or(ChargingProfileRecurrencyKind::Daily)
+    {
+        ChargingProfileRecurrencyKind::Daily => TimeDelta::days(1),
+        ChargingProfileRecurrencyKind::Weekly => TimeDelta::weeks(1),
+    }
+}
+
+/// The limit defined by `profile` at `at`, if it is valid and its
+/// schedule covers `at`. See [`schedule_start`] for `relative_start`.
+pub(crate) fn limit_at(
+    profile: &CsChargingProfiles,
+    relative_start: DateTime<Utc>,
+    at: DateTime<Utc>,
+) -> Option<Limit> {
+    if profile.valid_from.is_some_and(|valid_from| at < valid_from)
+        || profile.valid_to.is_some_and(|valid_to| at >= valid_to)
+    {
+        return None;
+    }
+
+    let schedule = &profile.charging_schedule;
+    let offset = (at - schedule_start(profile, relative_start, at)?).num_seconds();
+
+    if offset < 0
+        || schedule
+            .duration
+            .is_some_and(|duration| offset >= i64::from(duration))
+    {
+        return None;
+    }
+
+    schedule
+        .charging_schedule_period
+        .iter()
+        .filter(|period| i64::from(period.start_period) <= offset)
+        .max_by_key(|period| period.start_period)
+        .map(|period| {
+            Limit::new(
+                number_to_f64(period.limit),
+                schedule.charging_rate_unit,
+                period.number_phases,
+            )
+        })
+}
+
+/// The instants, within `[from, to)`, at which the limit defined by
+/// `profile` may change. See [`schedule_start`] for `relative_start`.
+pub(crate) fn breakpoints(
+    profile: &CsChargingProfiles,
+    relative_start: DateTime<Utc>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<DateTime<Utc>> {
+    let schedule = &profile.charging_schedule;
+    let mut breakpoints = vec![];
+    breakpoints.extend(profile.valid_from);
+    breakpoints.extend(profile.valid_to);
+
+    // Each recurrence of a recurring profile is a new schedule, the first
+    // one being at its `startSchedule`.
+    let mut at = match profile.charging_profile_kind {
+        ChargingProfileKind::Recurring => {
+            start_schedule(profile).map_or(from, |start| start.max(from))
+        }
+        _ => from,
+    };
+
+    while let Some(start) = schedule_start(profile, relative_start, at) {
+        breakpoints.push(start);
+        breakpoints.extend(
+            schedule
+                .charging_schedule_period
+                .iter()
+                .map(|period| start + TimeDelta::seconds(period.start_period.into())),
+        );
+        breakpoints.extend(
+            schedule
+                .duration
+                .map(|duration| start + TimeDelta::seconds(duration.into())),
+        );
+
+        let next = match profile.charging_profile_kind {
+            ChargingProfileKind::Recurring => start + recurrence(profile),
+            _ => break,
+        };
+
+        if next >= to {
+            break;
+        }
+
+        at = next;
+    }
+
+    breakpoints.retain(|breakpoint| (from..to).contains(breakpoint));
+    breakpoints.sort();
+    breakpoints.dedup();
+
+    breakpoints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ocppx_types::{
+        number_from_f64,
+        v1_6::{ChargingProfilePurpose, ChargingSchedule, ChargingSchedulePeriod},
+    };
+
+    #[test]
+    fn test_recurring_limit() {
+        let start: DateTime<Utc> = "2024-01-01T00:00:00Z".parse().unwrap();
+        let profile = CsChargingProfiles::builder()
+            .charging_profile_id(1)
+            .charging_profile_kind(ChargingProfileKind::Recurring)
+            .recurrency_kind(ChargingProfileRecurrencyKind::Daily)
+            .charging_profile_purpose(ChargingProfilePurpose::TxDefaultProfile)
+            .stack_level(0)
+            .charging_schedule(
+                ChargingSchedule::builder()
+                    .charging_rate_unit(ChargingScheduleChargingRateUnit::W)
+                    .start_schedule(start)
+                    .duration(8 * 3600)
+                    .charging_schedule_period(vec![ChargingSchedulePeriod::builder()
+                        .start_period(0)
+                        .limit(number_from_f64(6900.0))
+                        .build()])
+                    .build(),
+            )
+            .build();
+
+        // From midnight to 8 AM, every day.
+        let limit = limit_at(
+            &profile,
+            start,
+            start + TimeDelta::days(3) + TimeDelta::hours(1),
+        );
+        assert_eq!(
+            limit,
+            Some(Limit {
+                current: 10.0,
+                number_phases: 3
+            })
+        );
+        assert_eq!(
+            limit_at(
+                &profile,
+                start,
+                start + TimeDelta::days(3) + TimeDelta::hours(9)
+            ),
+            None
+        );
+        assert_eq!(
+            breakpoints(&profile, start, start, start + TimeDelta::days(2)),
+            [
+                start,
+                start + TimeDelta::hours(8),
+                start + TimeDelta::days(1),
+                start + TimeDelta::days(1) + TimeDelta::hours(8),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recurring_limit_before_start() {
+        let start: DateTime<Utc> = "2024-01-02T06:00:00Z".parse().unwrap();
+        let profile = CsChargingProfiles::builder()
+            .charging_profile_id(1)
+            .charging_profile_kind(ChargingProfileKind::Recurring)
+            .recurrency_kind(ChargingProfileRecurrencyKind::Daily)
+            .charging_profile_purpose(ChargingProfilePurpose::TxDefaultProfile)
+            .stack_level(0)
+            .charging_schedule(
+                ChargingSchedule::builder()
+                    .charging_rate_unit(ChargingScheduleChargingRateUnit::A)
+                    .start_schedule(start)
+                    .charging_schedule_period(vec![
+                        ChargingSchedulePeriod::builder()
+                            .start_period(0)
+                            .limit(number_from_f64(16.0))
+                            .build(),
+                        ChargingSchedulePeriod::builder()
+                            .start_period(12 * 3600)
+                            .limit(number_from_f64(8.0))
+                            .build(),
+                    ])
+                    .build(),
+            )
+            .build();
+
+        // The evening of the day before is not a recurrence.
+        let before = start - TimeDelta::hours(4);
+        assert_eq!(limit_at(&profile, before, before), None);
+        assert_eq!(
+            limit_at(&profile, before, start + TimeDelta::hours(13)),
+            Some(Limit {
+                current: 8.0,
+                number_phases: 3
+            })
+        );
+        assert_eq!(
+            breakpoints(&profile, before, before, start + TimeDelta::days(1)),
+            [start, start + TimeDelta::hours(12)]
+        );
+    }
+}
@@ -0,0 +1,620 @@
+use crate::{
+    schedule::{breakpoints, limit_at},
+    Limit,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use ocppx_types::{
+    number_from_f64,
+    v1_6::{
+        ChargingProfileKind, ChargingProfilePurpose, ChargingSchedule,
+        ChargingScheduleChargingRateUnit, ChargingSchedulePeriod, ClearChargingProfileRequest,
+        ClearChargingProfileStatus, CsChargingProfiles, GetCompositeScheduleRequest,
+        GetCompositeScheduleResponse, GetCompositeScheduleStatus, SetChargingProfileRequest,
+        SetChargingProfileStatus,
+    },
+};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone)]
+struct InstalledProfile {
+    connector_id: i32,
+    profile: CsChargingProfiles,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ActiveTransaction {
+    transaction_id: i32,
+    started_at: DateTime<Utc>,
+}
+
+/// The charging profiles installed on a Charge Point.
+#[derive(Debug, Clone)]
+pub struct ChargingProfileStore {
+    max_current: f64,
+    profiles: Vec<InstalledProfile>,
+    transactions: BTreeMap<i32, ActiveTransaction>,
+}
+
+impl ChargingProfileStore {
+    /// Create an empty store. `max_current` is the current per phase, in A,
+    /// that the Charge Point delivers when no profile limits it.
+    pub fn new(max_current: f64) -> Self {
+        Self {
+            max_current,
+            profiles: Vec::new(),
+            transactions: BTreeMap::new(),
+        }
+    }
+
+    /// The installed profiles, with the connector they are installed on.
+    pub fn profiles(&self) -> impl Iterator<Item = (i32, &CsChargingProfiles)> {
+        self.profiles
+            .iter()
+            .map(|installed| (installed.connector_id, &installed.profile))
+    }
+
+    /// A transaction has started on `connector_id`: `TxProfile`s can be
+    /// installed, and the relative profiles start.
+    pub fn start_transaction(
+        &mut self,
+        connector_id: i32,
+        transaction_id: i32,
+        started_at: DateTime<Utc>,
+    ) {
+        self.transactions.insert(
+            connector_id,
+            ActiveTransaction {
+                transaction_id,
+                started_at,
+            },
+        );
+    }
+
+    /// The transaction on `connector_id` has stopped: its `TxProfile`s are
+    /// removed.
+    pub fn stop_transaction(&mut self, connector_id: i32) {
+        self.transactions.remove(&connector_id);
+        self.profiles.retain(|installed| {
+            installed.connector_id != connector_id
+                || installed.profile.charging_profile_purpose != ChargingProfilePurpose::TxProfile
+        });
+    }
+
+    /// Handle a `SetChargingProfile`.
+    ///
+    /// A `ChargePointMaxProfile` must be set on the connector 0, and a
+    /// `TxProfile` on a connector with an ongoing transaction. The new
+    /// profile replaces the one with the same ID, and the one with the
+    /// same purpose and stack level on the same connector.
+    pub fn set_charging_profile(
+        &mut self,
+        request: &SetChargingProfileRequest,
+    ) -> SetChargingProfileStatus {
+        let connector_id = request.connector_id;
+        let profile = &request.cs_charging_profiles;
+
+        let valid = connector_id >= 0
+            && match profile.charging_profile_purpose {
+                ChargingProfilePurpose::ChargePointMaxProfile => connector_id == 0,
+                ChargingProfilePurpose::TxDefaultProfile => true,
+                ChargingProfilePurpose::TxProfile => {
+                    self.transactions.get(&connector_id).is_some_and(|active| {
+                        profile
+                            .transaction_id
+                            .is_none_or(|transaction_id| transaction_id == active.transaction_id)
+                    })
+                }
+            }
+            && (profile.charging_profile_kind != ChargingProfileKind::Recurring
+                || profile.charging_schedule.start_schedule.is_some()
+                || profile.valid_from.is_some());
+
+        if !valid {
+            return SetChargingProfileStatus::Rejected;
+        }
+
+        self.profiles.retain(|installed| {
+            installed.profile.charging_profile_id != profile.charging_profile_id
+                && (installed.connector_id != connector_id
+                    || installed.profile.charging_profile_purpose
+                        != profile.charging_profile_purpose
+                    || installed.profile.stack_level != profile.stack_level)
+        });
+        self.profiles.push(InstalledProfile {
+            connector_id,
+            profile: profile.clone(),
+        });
+
+        SetChargingProfileStatus::Accepted
+    }
+
+    /// Handle a `ClearChargingProfile`: the profile with the given ID, or
+    /// the profiles matching all the given criteria, are removed.
+    pub fn clear_charging_profile(
+        &mut self,
+        request: &ClearChargingProfileRequest,
+    ) -> ClearChargingProfileStatus {
+        let count = self.profiles.len();
+
+        self.profiles.retain(|installed| {
+            let profile = &installed.profile;
+            let matches = match request.id {
+                Some(id) => profile.charging_profile_id == id,
+                None => {
+                    request
+                        .connector_id
+                        .is_none_or(|connector_id| installed.connector_id == connector_id)
+                        && request
+                            .charging_profile_purpose
+                            .is_none_or(|purpose| profile.charging_profile_purpose == purpose)
+                        && request
+                            .stack_level
+                            .is_none_or(|stack_level| profile.stack_level == stack_level)
+                }
+            };
+
+            !matches
+        });
+
+        if self.profiles.len() < count {
+            ClearChargingProfileStatus::Accepted
+        } else {
+            ClearChargingProfileStatus::Unknown
+        }
+    }
+
+    /// The limit applying to `connector_id` at `at`. The connector 0 is
+    /// the whole Charge Point. Without a transaction, the relative profiles
+    /// start at `at`.
+    pub fn limit(&self, connector_id: i32, at: DateTime<Utc>) -> Limit {
+        self.resolved_limit(connector_id, at, self.relative_start(connector_id, at))
+    }
+
+    /// The start of the relative profiles of `connector_id`: the start of
+    /// its transaction, or `from` without a transaction.
+    fn relative_start(&self, connector_id: i32, from: DateTime<Utc>) -> DateTime<Utc> {
+        self.transactions
+            .get(&connector_id)
+            .map_or(from, |active| active.started_at)
+    }
+
+    /// The limit applying to `connector_id` at `at`, the relative profiles
+    /// starting at `relative_start`.
+    fn resolved_limit(
+        &self,
+        connector_id: i32,
+        at: DateTime<Utc>,
+        relative_start: DateTime<Utc>,
+    ) -> Limit {
+        // The valid profile with the highest stack level that defines a
+        // limit at `at`.
+        let best = |purpose, connector_id| {
+            let mut profiles = self
+                .profiles
+                .iter()
+                .filter(|installed| {
+                    installed.connector_id == connector_id
+                        && installed.profile.charging_profile_purpose == purpose
+                })
+                .map(|installed| &installed.profile)
+                .collect::<Vec<_>>();
+            profiles.sort_by_key(|profile| std::cmp::Reverse(profile.stack_level));
+
+            profiles
+                .into_iter()
+                .find_map(|profile| limit_at(profile, relative_start, at))
+        };
+
+        let transaction_limit = if connector_id == 0 {
+            None
+        } else {
+            best(ChargingProfilePurpose::TxProfile, connector_id)
+                .or_else(|| best(ChargingProfilePurpose::TxDefaultProfile, connector_id))
+                .or_else(|| best(ChargingProfilePurpose::TxDefaultProfile, 0))
+        };
+
+        [
+            transaction_limit,
+            best(ChargingProfilePurpose::ChargePointMaxProfile, 0),
+        ]
+        .into_iter()
+        .flatten()
+        .fold(
+            Limit::new(self.max_current, ChargingScheduleChargingRateUnit::A, None),
+            Limit::min,
+        )
+    }
+
+    /// The limits applying to `connector_id` during `duration` from
+    /// `start`, as a schedule in `unit`.
+    pub fn composite_schedule(
+        &self,
+        connector_id: i32,
+        start: DateTime<Utc>,
+        duration: TimeDelta,
+        unit: ChargingScheduleChargingRateUnit,
+    ) -> ChargingSchedule {
+        let end = start + duration;
+        // One start for the relative profiles, so that their periods
+        // follow each other over the schedule.
+        let relative_start = self.relative_start(connector_id, start);
+
+        let mut instants = vec![start];
+        instants.extend(
+            self.profiles
+                .iter()
+                .filter(|installed| {
+                    installed.connector_id == 0 || installed.connector_id == connector_id
+                })
+                .flat_map(|installed| breakpoints(&installed.profile, relative_start, start, end)),
+        );
+        instants.sort();
+        instants.dedup();
+
+        let mut periods: Vec<ChargingSchedulePeriod> = Vec::new();
+
+        for instant in instants {
+            let limit = self.resolved_limit(connector_id, instant, relative_start);
+            let value = number_from_f64(limit.value(unit));
+
+            if periods.last().is_some_and(|last| {
+                last.limit == value && last.number_phases == Some(limit.number_phases)
+            }) {
+                continue;
+            }
+
+            periods.push(
+                ChargingSchedulePeriod::builder()
+                    .start_period((instant - start).num_seconds() as i32)
+                    .limit(value)
+                    .number_phases(limit.number_phases)
+                    .build(),
+            );
+        }
+
+        ChargingSchedule::builder()
+            .charging_rate_unit(unit)
+            .charging_schedule_period(periods)
+            .duration(duration.num_seconds() as i32)
+            .start_schedule(start)
+            .build()
+    }
+
+    /// Handle a `GetCompositeSchedule` received at `now`. The schedule is
+    /// in A when the request does not tell.
+    pub fn get_composite_schedule(
+        &self,
+        request: &GetCompositeScheduleRequest,
+        now: DateTime<Utc>,
+    ) -> GetCompositeScheduleResponse {
+        if request.connector_id < 0 || request.duration <= 0 {
+            return GetCompositeScheduleResponse::builder()
+                .status(GetCompositeScheduleStatus::Rejected)
+                .build();
+        }
+
+        GetCompositeScheduleResponse::builder()
+            .charging_schedule(
+                self.composite_schedule(
+                    request.connector_id,
+                    now,
+                    TimeDelta::seconds(request.duration.into()),
+                    request
+                        .charging_rate_unit
+                        .unwrap_or(ChargingScheduleChargingRateUnit::A),
+                ),
+            )
+            .connector_id(request.connector_id)
+            .schedule_start(now)
+            .status(GetCompositeScheduleStatus::Accepted)
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ocppx_types::number_to_f64;
+
+    fn profile(
+        id: i32,
+        purpose: ChargingProfilePurpose,
+        stack_level: i32,
+        periods: &[(i32, f64)],
+    ) -> CsChargingProfiles {
+        CsChargingProfiles::builder()
+            .charging_profile_id(id)
+            .charging_profile_kind(ChargingProfileKind::Relative)
+            .charging_profile_purpose(purpose)
+            .stack_level(stack_level)
+            .charging_schedule(
+                ChargingSchedule::builder()
+                    .charging_rate_unit(ChargingScheduleChargingRateUnit::A)
+                    .duration(3600)
+                    .charging_schedule_period(
+                        periods
+                            .iter()
+                            .map(|&(start_period, limit)| {
+                                ChargingSchedulePeriod::builder()
+                                    .start_period(start_period)
+                                    .limit(number_from_f64(limit))
+                                    .build()
+                            })
+                            .collect::<Vec<_>>(),
+                    )
+                    .build(),
+            )
+            .build()
+    }
+
+    fn set(
+        store: &mut ChargingProfileStore,
+        connector_id: i32,
+        profile: CsChargingProfiles,
+    ) -> SetChargingProfileStatus {
+        store.set_charging_profile(
+            &SetChargingProfileRequest::builder()
+                .connector_id(connector_id)
+                .cs_charging_profiles(profile)
+                .build(),
+        )
+    }
+
+    fn periods(
+        store: &ChargingProfileStore,
+        connector_id: i32,
+        now: DateTime<Utc>,
+        unit: ChargingScheduleChargingRateUnit,
+    ) -> Vec<(i32, f64)> {
+        store
+            .composite_schedule(connector_id, now, TimeDelta::hours(2), unit)
+            .charging_schedule_period
+            .iter()
+            .map(|period| (period.start_period, number_to_f64(period.limit)))
+            .collect()
+    }
+
+    #[test]
+    fn test_composite_schedule() {
+        let now: DateTime<Utc> = "2024-01-01T00:00:00Z".parse().unwrap();
+        let mut store = ChargingProfileStore::new(32.0);
+
+        // No `TxProfile` without a transaction, and no
+        // `ChargePointMaxProfile` on a connector.
+        let tx_profile = profile(3, ChargingProfilePurpose::TxProfile, 0, &[(0, 10.0)]);
+        assert_eq!(
+            set(&mut store, 1, tx_profile.clone()),
+            SetChargingProfileStatus::Rejected
+        );
+        assert_eq!(
+            set(
+                &mut store,
+                1,
+                profile(1, ChargingProfilePurpose::ChargePointMaxProfile, 0, &[])
+            ),
+            SetChargingProfileStatus::Rejected
+        );
+
+        store.start_transaction(1, 42, now);
+
+        for (connector_id, profile) in [
+            (
+                0,
+                profile(
+                    1,
+                    ChargingProfilePurpose::ChargePointMaxProfile,
+                    0,
+                    &[(0, 20.0)],
+                ),
+            ),
+            (
+                0,
+                profile(2, ChargingProfilePurpose::TxDefaultProfile, 0, &[(0, 16.0)]),
+            ),
+            (1, tx_profile),
+        ] {
+            assert_eq!(
+                set(&mut store, connector_id, profile),
+                SetChargingProfileStatus::Accepted
+            );
+        }
+
+        // A `TxProfile` with a higher stack level, for the first 10
+        // minutes.
+        let mut short_tx_profile = profile(
+            4,
+            ChargingProfilePurpose::TxProfile,
+            1,
+            &[(0, 6.0), (300, 25.0)],
+        );
+        short_tx_profile.charging_schedule.duration = Some(600);
+        assert_eq!(
+            set(&mut store, 1, short_tx_profile),
+            SetChargingProfileStatus::Accepted
+        );
+
+        let schedule = store.composite_schedule(
+            1,
+            now,
+            TimeDelta::hours(2),
+            ChargingScheduleChargingRateUnit::A,
+        );
+        let periods = schedule
+            .charging_schedule_period
+            .iter()
+            .map(|period| (period.start_period, number_to_f64(period.limit)))
+            .collect::<Vec<_>>();
+
+        // The higher stack level, capped by the `ChargePointMaxProfile`,
+        // then the lower stack level, then the hardware limit once the
+        // relative profiles are over.
+        assert_eq!(periods, [(0, 6.0), (300, 20.0), (600, 10.0), (3600, 32.0)]);
+
+        // The `TxProfile`s go with the transaction.
+        store.stop_transaction(1);
+        assert_eq!(store.limit(1, now).current, 16.0);
+        assert_eq!(
+            store.clear_charging_profile(
+                &ClearChargingProfileRequest::builder()
+                    .charging_profile_purpose(ChargingProfilePurpose::TxDefaultProfile)
+                    .build()
+            ),
+            ClearChargingProfileStatus::Accepted
+        );
+        assert_eq!(store.limit(1, now).current, 20.0);
+    }
+
+    #[test]
+    fn test_relative_profile_without_transaction() {
+        let now: DateTime<Utc> = "2024-01-01T00:00:00Z".parse().unwrap();
+        let mut store = ChargingProfileStore::new(32.0);
+
+        assert_eq!(
+            set(
+                &mut store,
+                1,
+                profile(
+                    1,
+                    ChargingProfilePurpose::TxDefaultProfile,
+                    0,
+                    &[(0, 20.0), (1800, 10.0)]
+                )
+            ),
+            SetChargingProfileStatus::Accepted
+        );
+
+        // The relative profile starts with the schedule.
+        assert_eq!(
+            periods(&store, 1, now, ChargingScheduleChargingRateUnit::A),
+            [(0, 20.0), (1800, 10.0), (3600, 32.0)]
+        );
+        assert_eq!(
+            periods(
+                &store,
+                1,
+                now + TimeDelta::hours(1),
+                ChargingScheduleChargingRateUnit::A
+            ),
+            [(0, 20.0), (1800, 10.0), (3600, 32.0)]
+        );
+
+        // With a transaction, it starts with the transaction.
+        store.start_transaction(1, 42, now - TimeDelta::minutes(40));
+        assert_eq!(
+            periods(&store, 1, now, ChargingScheduleChargingRateUnit::A),
+            [(0, 10.0), (1200, 32.0)]
+        );
+    }
+
+    #[test]
+    fn test_stack_levels() {
+        let now: DateTime<Utc> = "2024-01-01T00:00:00Z".parse().unwrap();
+        let mut store = ChargingProfileStore::new(32.0);
+        store.start_transaction(1, 42, now);
+
+        // A higher stack level in the middle of a lower one.
+        let mut high = profile(
+            2,
+            ChargingProfilePurpose::TxDefaultProfile,
+            2,
+            &[(0, 24.0), (600, 8.0)],
+        );
+        high.charging_schedule.duration = Some(1200);
+
+        for (connector_id, profile) in [
+            (
+                0,
+                profile(1, ChargingProfilePurpose::TxDefaultProfile, 5, &[(0, 6.0)]),
+            ),
+            (
+                1,
+                profile(3, ChargingProfilePurpose::TxDefaultProfile, 0, &[(0, 16.0)]),
+            ),
+            (1, high),
+        ] {
+            assert_eq!(
+                set(&mut store, connector_id, profile),
+                SetChargingProfileStatus::Accepted
+            );
+        }
+
+        // The profiles of the connector override the one of the connector
+        // 0, whatever its stack level, until they are over.
+        assert_eq!(
+            periods(&store, 1, now, ChargingScheduleChargingRateUnit::A),
+            [(0, 24.0), (600, 8.0), (1200, 16.0), (3600, 32.0)]
+        );
+
+        // A profile with the same purpose and stack level replaces the
+        // previous one.
+        assert_eq!(
+            set(
+                &mut store,
+                1,
+                profile(4, ChargingProfilePurpose::TxDefaultProfile, 2, &[(0, 12.0)])
+            ),
+            SetChargingProfileStatus::Accepted
+        );
+        assert_eq!(
+            periods(&store, 1, now, ChargingScheduleChargingRateUnit::A),
+            [(0, 12.0), (3600, 32.0)]
+        );
+
+        // The connector 0 is the whole Charge Point: no transaction limit.
+        assert_eq!(
+            periods(&store, 0, now, ChargingScheduleChargingRateUnit::A),
+            [(0, 32.0)]
+        );
+    }
+
+    #[test]
+    fn test_charge_point_max_profile() {
+        let now: DateTime<Utc> = "2024-01-01T00:00:00Z".parse().unwrap();
+        let mut store = ChargingProfileStore::new(32.0);
+        store.start_transaction(1, 42, now);
+
+        // 9 660 W on 3 phases is 14 A.
+        let mut max_profile = profile(
+            1,
+            ChargingProfilePurpose::ChargePointMaxProfile,
+            0,
+            &[(0, 9660.0), (1800, 4600.0)],
+        );
+        max_profile.charging_schedule.charging_rate_unit = ChargingScheduleChargingRateUnit::W;
+        max_profile.charging_schedule.charging_schedule_period[1].number_phases = Some(1);
+
+        for (connector_id, profile) in [
+            (0, max_profile),
+            (
+                1,
+                profile(
+                    2,
+                    ChargingProfilePurpose::TxDefaultProfile,
+                    0,
+                    &[(0, 16.0), (900, 10.0)],
+                ),
+            ),
+        ] {
+            assert_eq!(
+                set(&mut store, connector_id, profile),
+                SetChargingProfileStatus::Accepted
+            );
+        }
+
+        // The lowest of the two limits, whatever their unit: 4 600 W on 1
+        // phase is 20 A, above the 10 A of the transaction.
+        assert_eq!(
+            periods(&store, 1, now, ChargingScheduleChargingRateUnit::A),
+            [(0, 14.0), (900, 10.0), (3600, 32.0)]
+        );
+        assert_eq!(
+            periods(&store, 1, now, ChargingScheduleChargingRateUnit::W),
+            [(0, 9660.0), (900, 6900.0), (3600, 22080.0)]
+        );
+        // The connector 0 is only capped by the `ChargePointMaxProfile`.
+        assert_eq!(
+            periods(&store, 0, now, ChargingScheduleChargingRateUnit::A),
+            [(0, 14.0), (1800, 20.0), (3600, 32.0)]
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{http, Body, Envelope, Error, Header, Result, Service, ANONYMOUS_ADDRESS};
+use ocppx_types::{v1_6::Action, OcppRequest};
+use serde_json::Value;
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::SystemTime,
+};
+use tokio::net::TcpStream;
+use url::Url;
+
+/// A client sending requests to a SOAP endpoint.
+///
+/// A Charge Point sends its requests to the Central System service; the
+/// Central System sends its own requests to the Charge Point service, at
+/// the address given by the Charge Point in `wsa:From`.
+#[derive(Debug)]
+pub struct SoapClient {
+    endpoint: Url,
+    service: Service,
+    charge_box_identity: String,
+    from: Option<String>,
+    next_message_id: AtomicU64,
+}
+
+impl SoapClient {
+    /// Create a client for the `service` at `endpoint`, e.g.
+    /// `http://csms.example.org/ocpp`. `charge_box_identity` is the
+    /// identity of the Charge Point, sending or receiving the requests.
+    pub fn new(endpoint: &str, service: Service, charge_box_identity: &str) -> Result<Self> {
+        let endpoint = Url::parse(endpoint)?;
+
+        if endpoint.scheme() != "http" {
+            return Err(Error::UnsupportedScheme(endpoint.scheme().to_owned()));
+        }
+
+        // The message IDs start from the current time, so that they differ
+        // from the ones of a previous process.
+        let first_message_id = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis() as u64);
+
+        Ok(Self {
+            endpoint,
+            service,
+            charge_box_identity: charge_box_identity.to_owned(),
+            from: None,
+            next_message_id: AtomicU64::new(first_message_id),
+        })
+    }
+
+    /// Set the endpoint of the Charge Point, sent in `wsa:From` so that the
+    /// Central System can send its own requests.
+    pub fn with_from(mut self, address: impl Into<String>) -> Self {
+        self.from = Some(address.into());
+
+        self
+    }
+
+    /// Send a request, and wait for its response.
+    pub async fn send<R>(&self, request: R) -> Result<R::Response>
+    where
+        R: OcppRequest,
+    {
+        let action = R::ACTION.parse::<Action>()?;
+        let payload = self.call(action, serde_json::to_value(request)?).await?;
+
+        Ok(serde_json::from_value(payload)?)
+    }
+
+    /// Send the payload of an `action` request, and wait for the payload of
+    /// its response.
+    pub async fn call(&self, action: Action, payload: Value) -> Result<Value> {
+        let message_id = format!(
+            "urn:ocppx:{}",
+            self.next_message_id.fetch_add(1, Ordering::Relaxed)
+        );
+        let request = Envelope {
+            service: self.service,
+            header: Header {
+                charge_box_identity: self.charge_box_identity.clone(),
+                message_id: Some(message_id.clone()),
+                from: self.from.clone(),
+                reply_to: Some(ANONYMOUS_ADDRESS.to_owned()),
+                to: Some(self.endpoint.to_string()),
+                ..Default::default()
+            },
+            body: Body::Request { action, payload },
+        };
+
+        let host = self.endpoint.host_str().unwrap_or_default();
+        let mut stream =
+            TcpStream::connect((host, self.endpoint.port_or_known_default().unwrap_or(80))).await?;
+
+        let path = match self.endpoint.query() {
+            Some(query) => format!("{}?{query}", self.endpoint.path()),
+            None => self.endpoint.path().to_owned(),
+        };
+        let soap_action = format!("{}/{action}", self.service.namespace());
+
+        http::write_message(
+            &mut stream,
+            &format!("POST {path} HTTP/1.1"),
+            &[("Host", host), ("SOAPAction", &soap_action)],
+            &request.to_xml(),
+        )
+        .await?;
+
+        let response = http::read_response(&mut stream).await?;
+        let response = match Envelope::from_xml(&response.body) {
+            Ok(envelope) => envelope,
+            // Faults come with a 4xx or 5xx status, anything else without an
+            // envelope is an HTTP error.
+            Err(_) if response.start != "200" => {
+                return Err(Error::Http(format!(
+                    "unexpected status code {}",
+                    response.start
+                )))
+            }
+            Err(error) => return Err(error),
+        };
+
+        match response.body {
+            Body::Response {
+                action: response_action,
+                payload,
+            } if response_action == action
+                && response
+                    .header
+                    .relates_to
+                    .as_ref()
+                    .is_none_or(|relates_to| *relates_to == message_id) =>
+            {
+                Ok(payload)
+            }
+            Body::Fault(fault) => Err(Error::Fault(fault)),
+            _ => Err(Error::InvalidEnvelope(format!(
+                "the response does not match the `{action}` request"
+            ))),
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use crate::{
+    xml::{self, Element, Node, Schema},
+    Error, Result,
+};
+use ocppx_rpc::{CallError, ErrorCode};
+use ocppx_types::v1_6::Action;
+use quick_xml::escape::escape;
+use serde_json::Value;
+use std::fmt::Write as _;
+
+/// The namespace of SOAP 1.2 envelopes.
+pub const SOAP_NAMESPACE: &str = "http://www.w3.org/2003/05/soap-envelope";
+
+/// The namespace of the WS-Addressing headers.
+pub const ADDRESSING_NAMESPACE: &str = "http://www.w3.org/2005/08/addressing";
+
+/// The WS-Addressing address meaning “respond in the HTTP response”.
+pub const ANONYMOUS_ADDRESS: &str = "http://www.w3.org/2005/08/addressing/anonymous";
+
+/// The WS-Addressing action of faults.
+const FAULT_ACTION: &str = "http://www.w3.org/2005/08/addressing/soap/fault";
+
+/// The service a message is sent to, as defined by the WSDLs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Service {
+    /// The messages sent by the Charge Points.
+    CentralSystem,
+    /// The messages sent by the Central System.
+    ChargePoint,
+}
+
+impl Service {
+    /// The namespace of the messages of the service.
+    pub fn namespace(&self) -> &'static str {
+        match self {
+            Self::CentralSystem => "urn://Ocpp/Cs/2015/10/",
+            Self::ChargePoint => "urn://Ocpp/Cp/2015/10/",
+        }
+    }
+
+    fn from_namespace(namespace: &str) -> Option<Self> {
+        [Self::CentralSystem, Self::ChargePoint]
+            .into_iter()
+            .find(|service| service.namespace() == namespace)
+    }
+}
+
+/// The headers of a message: the identity of the Charge Point, and the
+/// WS-Addressing headers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Header {
+    /// The identity of the Charge Point, sent or targeted.
+    pub charge_box_identity: String,
+    /// `wsa:MessageID`, the ID of a request.
+    pub message_id: Option<String>,
+    /// `wsa:RelatesTo`, the ID of the request a response is for.
+    pub relates_to: Option<String>,
+    /// `wsa:From`, the endpoint of the Charge Point, where the Central
+    /// System sends its own requests.
+    pub from: Option<String>,
+    /// `wsa:ReplyTo`, where to send the response.
+    pub reply_to: Option<String>,
+    /// `wsa:To`, the endpoint the message is sent to.
+    pub to: Option<String>,
+}
+
+/// The body of a message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Body {
+    Request { action: Action, payload: Value },
+    Response { action: Action, payload: Value },
+    Fault(Fault),
+}
+
+/// A SOAP 1.2 fault, i.e. the SOAP counterpart of a `CallError`.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("SOAP fault `{code}`: {reason}")]
+pub struct Fault {
+    /// `Sender` if the request is wrong, `Receiver` if it cannot be
+    /// processed.
+    pub code: String,
+    /// A more precise code, e.g. `NotImplemented`.
+    pub subcode: Option<String>,
+    pub reason: String,
+}
+
+impl From<CallError> for Fault {
+    fn from(error: CallError) -> Self {
+        let code = match error.error_code {
+            ErrorCode::InternalError | ErrorCode::GenericError => "Receiver",
+            _ => "Sender",
+        };
+
+        Self {
+            code: code.to_owned(),
+            subcode: Some(error.error_code.to_string()),
+            reason: error.error_description,
+        }
+    }
+}
+
+/// A SOAP envelope.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Envelope {
+    pub service: Service,
+    pub header: Header,
+    pub body: Body,
+}
+
+impl Envelope {
+    /// Serialize the envelope. The payload is written in the order of the
+    /// properties of its JSON schema.
+    pub fn to_xml(&self) -> String {
+        let mut output = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+             <soap:Envelope xmlns:soap=\"{SOAP_NAMESPACE}\" xmlns:wsa=\"{ADDRESSING_NAMESPACE}\" xmlns:ocpp=\"{namespace}\">\
+             <soap:Header>",
+            namespace = self.service.namespace(),
+        );
+
+        let header = &self.header;
+        let action = match &self.body {
+            Body::Request { action, .. } => format!("/{action}"),
+            Body::Response { action, .. } => format!("/{action}Response"),
+            Body::Fault(_) => FAULT_ACTION.to_owned(),
+        };
+
+        let _ = write!(
+            output,
+            "<ocpp:chargeBoxIdentity>{}</ocpp:chargeBoxIdentity><wsa:Action>{}</wsa:Action>",
+            escape(&header.charge_box_identity),
+            escape(&action),
+        );
+
+        for (name, value) in [
+            ("MessageID", &header.message_id),
+            ("RelatesTo", &header.relates_to),
+            ("To", &header.to),
+        ] {
+            if let Some(value) = value {
+                let _ = write!(output, "<wsa:{name}>{}</wsa:{name}>", escape(value));
+            }
+        }
+
+        for (name, address) in [("From", &header.from), ("ReplyTo", &header.reply_to)] {
+            if let Some(address) = address {
+                let _ = write!(
+                    output,
+                    "<wsa:{name}><wsa:Address>{}</wsa:Address></wsa:{name}>",
+                    escape(address)
+                );
+            }
+        }
+
+        output.push_str("</soap:Header><soap:Body>");
+
+        match &self.body {
+            Body::Request { action, payload } | Body::Response { action, payload } => {
+                let (suffix, schema) = match &self.body {
+                    Body::Request { .. } => ("Request", action.request_schema()),
+                    _ => ("Response", action.response_schema()),
+                };
+                let schema = serde_json::from_str::<Node>(schema).unwrap_or_default();
+
+                xml::write_value(
+                    &mut output,
+                    "ocpp",
+                    &format!("{}{suffix}", lower_first(action.as_str())),
+                    // Empty payloads are still written as an element.
+                    &match payload {
+                        Value::Null => Value::Object(Default::default()),
+                        payload => payload.clone(),
+                    },
+                    Schema::new(&schema),
+                );
+            }
+
+            Body::Fault(fault) => {
+                let _ = write!(
+                    output,
+                    "<soap:Fault><soap:Code><soap:Value>soap:{}</soap:Value>",
+                    escape(&fault.code)
+                );
+
+                if let Some(subcode) = &fault.subcode {
+                    let _ = write!(
+                        output,
+                        "<soap:Subcode><soap:Value>ocpp:{}</soap:Value></soap:Subcode>",
+                        escape(subcode)
+                    );
+                }
+
+                let _ = write!(
+                    output,
+                    "</soap:Code><soap:Reason><soap:Text xml:lang=\"en\">{}</soap:Text></soap:Reason></soap:Fault>",
+                    escape(&fault.reason)
+                );
+            }
+        }
+
+        output.push_str("</soap:Body></soap:Envelope>");
+
+        output
+    }
+
+    /// Parse an envelope. The payload is converted to JSON with the help of
+    /// its JSON schema, since XML has no types.
+    pub fn from_xml(xml: &str) -> Result<Self> {
+        let invalid = |reason: &str| Error::InvalidEnvelope(reason.to_owned());
+
+        let envelope = Element::parse(xml)?;
+
+        if envelope.name != "Envelope" || envelope.namespace.as_deref() != Some(SOAP_NAMESPACE) {
+            return Err(invalid("not a SOAP 1.2 envelope"));
+        }
+
+        let text = |element: Option<&Element>| element.map(|element| element.text.clone());
+        let address = |element: Option<&Element>| text(element.and_then(|e| e.child("Address")));
+
+        let header = envelope.child("Header");
+        let header_child = |name| header.and_then(|header| header.child(name));
+        let header = Header {
+            charge_box_identity: text(header_child("chargeBoxIdentity")).unwrap_or_default(),
+            message_id: text(header_child("MessageID")),
+            relates_to: text(header_child("RelatesTo")),
+            from: address(header_child("From")),
+            reply_to: address(header_child("ReplyTo")),
+            to: text(header_child("To")),
+        };
+
+        let body = envelope
+            .child("Body")
+            .and_then(Element::first_child)
+            .ok_or_else(|| invalid("the body is empty"))?;
+
+        if body.name == "Fault" {
+            // Codes are qualified names, e.g. `soap:Sender`.
+            let unqualify = |code: String| match code.split_once(':') {
+                Some((_, code)) => code.to_owned(),
+                None => code,
+            };
+            let code = body.child("Code");
+
+            return Ok(Self {
+                service: Service::CentralSystem,
+                header,
+                body: Body::Fault(Fault {
+                    code: text(code.and_then(|code| code.child("Value")))
+                        .map(unqualify)
+                        .unwrap_or_default(),
+                    subcode: text(
+                        code.and_then(|code| code.child("Subcode"))
+                            .and_then(|subcode| subcode.child("Value")),
+                    )
+                    .map(unqualify),
+                    reason: text(body.child("Reason").and_then(|reason| reason.child("Text")))
+                        .unwrap_or_default(),
+                }),
+            });
+        }
+
+        let service = body
+            .namespace
+            .as_deref()
+            .and_then(Service::from_namespace)
+            .ok_or_else(|| invalid("the body is not an OCPP message"))?;
+
+        let (action, is_request) = match (
+            body.name.strip_suffix("Request"),
+            body.name.strip_suffix("Response"),
+        ) {
+            (Some(action), _) => (action, true),
+            (_, Some(action)) => (action, false),
+            _ => return Err(invalid("the body is neither a request nor a response")),
+        };
+        let action = upper_first(action).parse::<Action>()?;
+
+        let schema = if is_request {
+            action.request_schema()
+        } else {
+            action.response_schema()
+        };
+        let schema = serde_json::from_str::<Node>(schema)?;
+        let payload = xml::to_value(body, Schema::new(&schema));
+
+        Ok(Self {
+            service,
+            header,
+            body: if is_request {
+                Body::Request { action, payload }
+            } else {
+                Body::Response { action, payload }
+            },
+        })
+    }
+}
+
+fn lower_first(string: &str) -> String {
+    let mut chars = string.chars();
+
+    chars
+        .next()
+        .map(|first| first.to_lowercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+fn upper_first(string: &str) -> String {
+    let mut chars = string.chars();
+
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_round_trip() {
+        let envelope = Envelope {
+            service: Service::CentralSystem,
+            header: Header {
+                charge_box_identity: "CP001".to_owned(),
+                message_id: Some("urn:uuid:1".to_owned()),
+                from: Some("http://10.0.0.1:8080/".to_owned()),
+                ..Default::default()
+            },
+            body: Body::Request {
+                action: Action::MeterValues,
+                payload: json!({
+                    "connectorId": 1,
+                    "meterValue": [{
+                        "timestamp": "2013-02-01T20:53:32.486Z",
+                        "sampledValue": [{ "value": "12.34", "unit": "kWh" }],
+                    }],
+                }),
+            },
+        };
+
+        let xml = envelope.to_xml();
+
+        // The elements are ordered as in the schema, and arrays are
+        // repeated elements.
+        assert!(xml.contains(
+            "<ocpp:meterValuesRequest><ocpp:connectorId>1</ocpp:connectorId><ocpp:meterValue>\
+             <ocpp:timestamp>2013-02-01T20:53:32.486Z</ocpp:timestamp>"
+        ));
+        assert_eq!(Envelope::from_xml(&xml).unwrap(), envelope);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{Error, Result};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// The maximum size of an HTTP message head.
+const MAX_HEAD_SIZE: usize = 8 * 1024;
+
+/// The maximum size of an HTTP message body.
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// The content type of SOAP 1.2 messages.
+pub(crate) const CONTENT_TYPE: &str = "application/soap+xml; charset=utf-8";
+
+/// An HTTP request or response, with only what SOAP needs.
+pub(crate) struct Message {
+    /// The path of a request, or the status code of a response.
+    pub start: String,
+    pub body: String,
+}
+
+/// Read an HTTP request.
+pub(crate) async fn read_request<S>(stream: &mut S) -> Result<Message>
+where
+    S: AsyncRead + Unpin,
+{
+    read_message(stream, |buffer| {
+        let mut headers = [httparse::EMPTY_HEADER; 64];
+        let mut request = httparse::Request::new(&mut headers);
+
+        Ok(match request.parse(buffer).map_err(invalid_head)? {
+            httparse::Status::Complete(head_size) => Some((
+                head_size,
+                content_length(request.headers),
+                request.path.unwrap_or("/").to_owned(),
+            )),
+            httparse::Status::Partial => None,
+        })
+    })
+    .await
+}
+
+/// Read an HTTP response.
+pub(crate) async fn read_response<S>(stream: &mut S) -> Result<Message>
+where
+    S: AsyncRead + Unpin,
+{
+    read_message(stream, |buffer| {
+        let mut headers = [httparse::EMPTY_HEADER; 64];
+        let mut response = httparse::Response::new(&mut headers);
+
+        Ok(match response.parse(buffer).map_err(invalid_head)? {
+            httparse::Status::Complete(head_size) => Some((
+                head_size,
+                content_length(response.headers),
+                response.code.unwrap_or_default().to_string(),
+            )),
+            httparse::Status::Partial => None,
+        })
+    })
+    .await
+}
+
+/// Write an HTTP message with a SOAP body. `start_line` is, e.g.,
+/// `POST /ocpp HTTP/1.1`.
+pub(crate) async fn write_message<S>(
+    stream: &mut S,
+    start_line: &str,
+    headers: &[(&str, &str)],
+    body: &str,
+) -> Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    let mut message = format!(
+        "{start_line}\r\nContent-Type: {CONTENT_TYPE}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        body.len()
+    );
+
+    for (name, value) in headers {
+        message.push_str(&format!("{name}: {value}\r\n"));
+    }
+
+    message.push_str("\r\n");
+    message.push_str(body);
+
+    stream.write_all(message.as_bytes()).await?;
+    stream.flush().await?;
+
+    Ok(())
+}
+
+fn invalid_head(error: httparse::Error) -> Error {
+    Error::Http(format!("invalid message head: {error}"))
+}
+
+fn content_length(headers: &[httparse::Header]) -> Option<usize> {
+    headers
+        .iter()
+        .find(|header| header.name.eq_ignore_ascii_case("content-length"))
+        .and_then(|header| std::str::from_utf8(header.value).ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Read a message whose head is parsed by `parse_head`, which returns the
+/// size of the head, the length of the body, and the start of the
+/// message. Without a length, the body ends with the connection.
+async fn read_message<S, F>(stream: &mut S, parse_head: F) -> Result<Message>
+where
+    S: AsyncRead + Unpin,
+    F: Fn(&[u8]) -> Result<Option<(usize, Option<usize>, String)>>,
+{
+    let mut buffer = Vec::with_capacity(1024);
+    let mut chunk = [0; 4096];
+
+    let (head_size, content_length, start) = loop {
+        let read = stream.read(&mut chunk).await?;
+
+        if read == 0 {
+            return Err(Error::Http("unexpected end of the message head".to_owned()));
+        }
+
+        buffer.extend_from_slice(&chunk[..read]);
+
+        if let Some(head) = parse_head(&buffer)? {
+            break head;
+        }
+
+        if buffer.len() > MAX_HEAD_SIZE {
+            return Err(Error::Http("the message head is too large".to_owned()));
+        }
+    };
+
+    let mut body = buffer.split_off(head_size);
+
+    if content_length.is_some_and(|content_length| content_length > MAX_BODY_SIZE) {
+        return Err(Error::Http("the message body is too large".to_owned()));
+    }
+
+    loop {
+        if content_length.is_some_and(|content_length| body.len() >= content_length) {
+            break;
+        }
+
+        let read = stream.read(&mut chunk).await?;
+
+        if read == 0 {
+            if content_length.is_some() {
+                return Err(Error::Http("unexpected end of the message body".to_owned()));
+            }
+
+            break;
+        }
+
+        body.extend_from_slice(&chunk[..read]);
+
+        if body.len() > MAX_BODY_SIZE {
+            return Err(Error::Http("the message body is too large".to_owned()));
+        }
+    }
+
+    if let Some(content_length) = content_length {
+        body.truncate(content_length);
+    }
+
+    Ok(Message {
+        start,
+        body: String::from_utf8(body)
+            .map_err(|_| Error::Http("the message body is not UTF-8".to_owned()))?,
+    })
+}
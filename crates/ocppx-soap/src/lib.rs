@@ -0,0 +1,53 @@
+//! OCPP 1.6 over SOAP, a.k.a. OCPP 1.6-S, for the Charge Points that do
+//! not speak OCPP-J.
+//!
+//! The payloads are the generated types of `ocppx_types::v1_6`, sent in
+//! SOAP 1.2 [`Envelope`]s as defined by the OCPP 1.6 WSDLs, with the
+//! WS-Addressing headers. [`SoapClient`] sends requests to a Charge Point
+//! or to a Central System, and [`SoapServer`] is the HTTP endpoint of the
+//! Central System, dispatching the requests to a `CsmsHandler`.
+
+mod client;
+mod envelope;
+mod http;
+mod server;
+mod xml;
+
+pub use client::SoapClient;
+pub use envelope::{
+    Body, Envelope, Fault, Header, Service, ADDRESSING_NAMESPACE, ANONYMOUS_ADDRESS, SOAP_NAMESPACE,
+};
+pub use server::SoapServer;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("I/O error")]
+    Io(#[from] std::io::Error),
+
+    #[error("XML error")]
+    Xml(#[from] quick_xml::Error),
+
+    #[error("JSON error")]
+    Json(#[from] serde_json::Error),
+
+    #[error("invalid URL")]
+    Url(#[from] url::ParseError),
+
+    #[error("unsupported URL scheme `{0}`, only `http` is supported")]
+    UnsupportedScheme(String),
+
+    #[error("HTTP error: {0}")]
+    Http(String),
+
+    #[error("invalid SOAP envelope: {0}")]
+    InvalidEnvelope(String),
+
+    #[error(transparent)]
+    UnknownAction(#[from] ocppx_types::UnknownActionError),
+
+    #[error("the peer responded with a fault")]
+    Fault(Fault),
+}
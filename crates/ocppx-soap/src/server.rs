@@ -0,0 +1,215 @@
+use crate::{http, Body, Envelope, Fault, Header, Result, Service};
+use ocppx_rpc::Call;
+use ocppx_server::CsmsHandler;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+/// The HTTP endpoint of the Central System service.
+///
+/// Requests from the Charge Points are dispatched to the same
+/// [`CsmsHandler`] as the ones received over OCPP-J, with the
+/// `chargeBoxIdentity` header as the Charge Point identity and the
+/// `wsa:MessageID` as the unique ID of the `Call`. As SOAP has no
+/// connection, `CsmsHandler::connected` and `CsmsHandler::disconnected`
+/// are never called.
+pub struct SoapServer<H> {
+    handler: Arc<H>,
+}
+
+impl<H> SoapServer<H>
+where
+    H: CsmsHandler,
+{
+    pub fn new(handler: H) -> Self {
+        Self {
+            handler: Arc::new(handler),
+        }
+    }
+
+    /// Listen on `address`, and serve the requests.
+    pub async fn listen<T>(self, address: T) -> Result<()>
+    where
+        T: ToSocketAddrs,
+    {
+        self.serve(TcpListener::bind(address).await?).await
+    }
+
+    /// Serve the requests received on `listener`.
+    pub async fn serve(self, listener: TcpListener) -> Result<()> {
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let handler = self.handler.clone();
+
+            tokio::spawn(async move {
+                let _ = handle_request(handler.as_ref(), stream).await;
+            });
+        }
+    }
+}
+
+async fn handle_request<H>(handler: &H, mut stream: TcpStream) -> Result<()>
+where
+    H: CsmsHandler,
+{
+    let request = http::read_request(&mut stream).await?;
+
+    let (status, response) = match Envelope::from_xml(&request.body) {
+        Ok(Envelope {
+            header,
+            body: Body::Request { action, payload },
+            ..
+        }) => {
+            let unique_id = header.message_id.clone().unwrap_or_default();
+            let header = Header {
+                charge_box_identity: header.charge_box_identity.clone(),
+                relates_to: header.message_id,
+                ..Default::default()
+            };
+
+            let body = match handler
+                .handle_call(
+                    &header.charge_box_identity,
+                    Call {
+                        unique_id,
+                        action: action.to_string(),
+                        payload,
+                    },
+                )
+                .await
+            {
+                Ok(call_result) => Body::Response {
+                    action,
+                    payload: call_result.payload,
+                },
+                Err(call_error) => Body::Fault(call_error.into()),
+            };
+
+            (
+                status(&body),
+                Envelope {
+                    service: Service::CentralSystem,
+                    header,
+                    body,
+                },
+            )
+        }
+
+        Ok(_) => fault_response(Fault {
+            code: "Sender".to_owned(),
+            subcode: None,
+            reason: "expected a request".to_owned(),
+        }),
+
+        Err(error) => fault_response(Fault {
+            code: "Sender".to_owned(),
+            subcode: None,
+            reason: error.to_string(),
+        }),
+    };
+
+    http::write_message(
+        &mut stream,
+        &format!("HTTP/1.1 {status}"),
+        &[],
+        &response.to_xml(),
+    )
+    .await
+}
+
+fn fault_response(fault: Fault) -> (&'static str, Envelope) {
+    let body = Body::Fault(fault);
+
+    (
+        status(&body),
+        Envelope {
+            service: Service::CentralSystem,
+            header: Header::default(),
+            body,
+        },
+    )
+}
+
+/// The HTTP status of a response, as defined by the SOAP 1.2 HTTP binding.
+fn status(body: &Body) -> &'static str {
+    match body {
+        Body::Fault(fault) if fault.code == "Sender" => "400 Bad Request",
+        Body::Fault(_) => "500 Internal Server Error",
+        _ => "200 OK",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Error, SoapClient};
+    use ocppx_rpc::{CallError, CallResult, ErrorCode};
+    use ocppx_types::{
+        v1_6::{BootNotificationRequest, BootNotificationStatus, HeartbeatRequest},
+        CiString20,
+    };
+    use serde_json::json;
+
+    struct Csms;
+
+    impl CsmsHandler for Csms {
+        async fn handle_call(
+            &self,
+            charge_point_id: &str,
+            call: Call,
+        ) -> std::result::Result<CallResult, CallError> {
+            assert_eq!(charge_point_id, "CP001");
+
+            match call.action.as_str() {
+                "BootNotification" => Ok(CallResult {
+                    unique_id: call.unique_id,
+                    payload: json!({
+                        "status": "Accepted",
+                        "currentTime": "2013-02-01T20:53:32.486Z",
+                        "interval": 300,
+                    }),
+                }),
+                _ => Err(CallError::new(
+                    call.unique_id,
+                    ErrorCode::NotImplemented,
+                    "not implemented",
+                    None,
+                )),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_soap_call() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        tokio::spawn(SoapServer::new(Csms).serve(listener));
+
+        let client = SoapClient::new(
+            &format!("http://{address}/ocpp"),
+            Service::CentralSystem,
+            "CP001",
+        )
+        .unwrap();
+
+        let response = client
+            .send(
+                BootNotificationRequest::builder()
+                    .charge_point_vendor(CiString20::try_from("VendorX").unwrap())
+                    .charge_point_model(CiString20::try_from("SingleSocketCharger").unwrap())
+                    .build(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status, BootNotificationStatus::Accepted);
+        assert_eq!(response.interval, 300);
+
+        match client.send(HeartbeatRequest::builder().build()).await {
+            Err(Error::Fault(fault)) => {
+                assert_eq!(fault.code, "Sender");
+                assert_eq!(fault.subcode.as_deref(), Some("NotImplemented"));
+            }
+            response => panic!("unexpected response: {response:?}"),
+        }
+    }
+}
@@ -0,0 +1,356 @@
+use crate::{Error, Result};
+use quick_xml::{
+    escape::escape,
+    events::Event,
+    name::{Namespace, ResolveResult},
+    NsReader,
+};
+use serde::{
+    de::{IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use serde_json::{Map, Number, Value};
+use std::fmt::{self, Write as _};
+
+/// An XML element.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Element {
+    pub namespace: Option<String>,
+    pub name: String,
+    pub children: Vec<Element>,
+    pub text: String,
+}
+
+impl Element {
+    /// Parse the root element of a document.
+    pub(crate) fn parse(xml: &str) -> Result<Self> {
+        let mut reader = NsReader::from_str(xml);
+        reader.config_mut().trim_text(true);
+
+        let mut stack = Vec::<Element>::new();
+
+        loop {
+            let (namespace, event) = reader.read_resolved_event()?;
+            let namespace = match namespace {
+                ResolveResult::Bound(Namespace(namespace)) => {
+                    Some(String::from_utf8_lossy(namespace).into_owned())
+                }
+                _ => None,
+            };
+
+            let element = match event {
+                Event::Start(start) => {
+                    stack.push(Element {
+                        namespace,
+                        name: String::from_utf8_lossy(start.local_name().as_ref()).into_owned(),
+                        ..Default::default()
+                    });
+
+                    continue;
+                }
+                Event::Empty(start) => Element {
+                    namespace,
+                    name: String::from_utf8_lossy(start.local_name().as_ref()).into_owned(),
+                    ..Default::default()
+                },
+                Event::End(_) => stack
+                    .pop()
+                    .ok_or_else(|| Error::InvalidEnvelope("unexpected end tag".to_owned()))?,
+                Event::Text(text) => {
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&text.unescape()?);
+                    }
+
+                    continue;
+                }
+                Event::CData(data) => {
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&String::from_utf8_lossy(&data));
+                    }
+
+                    continue;
+                }
+                Event::Eof => {
+                    return Err(Error::InvalidEnvelope(
+                        "unexpected end of document".to_owned(),
+                    ))
+                }
+                _ => continue,
+            };
+
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(element),
+                None => return Ok(element),
+            }
+        }
+    }
+
+    /// The first child named `name`.
+    pub(crate) fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// The first child element, e.g. the payload of a SOAP body.
+    pub(crate) fn first_child(&self) -> Option<&Element> {
+        self.children.first()
+    }
+}
+
+/// A node of a JSON schema. Unlike `serde_json::Value`, the keys of the
+/// objects keep their order, which is the order of the elements in the
+/// WSDL. Only what is needed to map XML to JSON is kept.
+#[derive(Debug, Default)]
+pub(crate) enum Node {
+    Object(Vec<(String, Node)>),
+    String(String),
+    #[default]
+    Other,
+}
+
+static NULL: Node = Node::Other;
+
+impl Node {
+    fn get(&self, key: &str) -> Option<&Node> {
+        match self {
+            Self::Object(entries) => entries
+                .iter()
+                .find_map(|(name, node)| (name == key).then_some(node)),
+            _ => None,
+        }
+    }
+
+    fn keys(&self) -> impl Iterator<Item = &str> {
+        let entries = match self {
+            Self::Object(entries) => entries.as_slice(),
+            _ => &[],
+        };
+
+        entries.iter().map(|(name, _)| name.as_str())
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(string) => Some(string),
+            _ => None,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct NodeVisitor;
+
+        impl<'de> Visitor<'de> for NodeVisitor {
+            type Value = Node;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a JSON schema")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> std::result::Result<Node, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut entries = Vec::new();
+
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+
+                Ok(Node::Object(entries))
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Node, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                while seq.next_element::<IgnoredAny>()?.is_some() {}
+
+                Ok(Node::Other)
+            }
+
+            fn visit_str<E>(self, string: &str) -> std::result::Result<Node, E> {
+                Ok(Node::String(string.to_owned()))
+            }
+
+            fn visit_bool<E>(self, _: bool) -> std::result::Result<Node, E> {
+                Ok(Node::Other)
+            }
+
+            fn visit_i64<E>(self, _: i64) -> std::result::Result<Node, E> {
+                Ok(Node::Other)
+            }
+
+            fn visit_u64<E>(self, _: u64) -> std::result::Result<Node, E> {
+                Ok(Node::Other)
+            }
+
+            fn visit_f64<E>(self, _: f64) -> std::result::Result<Node, E> {
+                Ok(Node::Other)
+            }
+
+            fn visit_unit<E>(self) -> std::result::Result<Node, E> {
+                Ok(Node::Other)
+            }
+        }
+
+        deserializer.deserialize_any(NodeVisitor)
+    }
+}
+
+/// A JSON schema, with the definitions its references point to.
+#[derive(Clone, Copy)]
+pub(crate) struct Schema<'a> {
+    pub node: &'a Node,
+    pub definitions: &'a Node,
+}
+
+impl<'a> Schema<'a> {
+    pub(crate) fn new(root: &'a Node) -> Self {
+        Self {
+            node: root,
+            definitions: root.get("definitions").unwrap_or(&NULL),
+        }
+    }
+
+    /// Follow the `$ref` of the node, if any.
+    fn resolve(self) -> Self {
+        let node = self
+            .node
+            .get("$ref")
+            .and_then(Node::as_str)
+            .and_then(|reference| reference.strip_prefix("#/definitions/"))
+            .and_then(|name| self.definitions.get(name))
+            .unwrap_or(self.node);
+
+        Self { node, ..self }
+    }
+
+    fn ty(&self) -> Option<&'a str> {
+        self.node.get("type").and_then(Node::as_str)
+    }
+
+    fn property(self, name: &str) -> Self {
+        Self {
+            node: self
+                .node
+                .get("properties")
+                .and_then(|properties| properties.get(name))
+                .unwrap_or(&NULL),
+            ..self
+        }
+        .resolve()
+    }
+
+    fn items(self) -> Self {
+        Self {
+            node: self.node.get("items").unwrap_or(&NULL),
+            ..self
+        }
+        .resolve()
+    }
+}
+
+/// Convert an element into a JSON value. XML has no types, so the schema
+/// tells which elements are numbers, booleans, or arrays.
+pub(crate) fn to_value(element: &Element, schema: Schema) -> Value {
+    let schema = schema.resolve();
+    let text = || Value::String(element.text.clone());
+
+    match schema.ty() {
+        Some("integer") => element
+            .text
+            .parse::<i64>()
+            .map_or_else(|_| text(), Value::from),
+        Some("number") => element
+            .text
+            .parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map_or_else(text, Value::Number),
+        Some("boolean") => match element.text.as_str() {
+            "true" | "1" => Value::Bool(true),
+            "false" | "0" => Value::Bool(false),
+            _ => text(),
+        },
+        Some("object") => to_object(element, schema),
+        None if !element.children.is_empty() => to_object(element, schema),
+        _ => text(),
+    }
+}
+
+fn to_object(element: &Element, schema: Schema) -> Value {
+    let mut object = Map::new();
+
+    for child in &element.children {
+        let property = schema.property(&child.name);
+
+        // An array is a repeated element.
+        if property.ty() == Some("array") {
+            if let Value::Array(items) = object
+                .entry(child.name.clone())
+                .or_insert_with(|| Value::Array(Vec::new()))
+            {
+                items.push(to_value(child, property.items()));
+            }
+        } else {
+            object.insert(child.name.clone(), to_value(child, property));
+        }
+    }
+
+    Value::Object(object)
+}
+
+/// Write a JSON value as the `prefix:name` element. The properties of
+/// an object are written in the order of the schema, as the WSDL expects.
+pub(crate) fn write_value(
+    output: &mut String,
+    prefix: &str,
+    name: &str,
+    value: &Value,
+    schema: Schema,
+) {
+    let schema = schema.resolve();
+
+    match value {
+        Value::Null => {}
+        Value::Array(items) => {
+            for item in items {
+                write_value(output, prefix, name, item, schema.items());
+            }
+        }
+        Value::Object(object) => {
+            let _ = write!(output, "<{prefix}:{name}>");
+
+            let ordered = schema
+                .node
+                .get("properties")
+                .into_iter()
+                .flat_map(Node::keys)
+                .filter(|key| object.contains_key(*key));
+            let others = object
+                .keys()
+                .map(String::as_str)
+                .filter(|key| !matches!(schema.property(key).node, Node::Object(_)));
+
+            for key in ordered.chain(others) {
+                write_value(output, prefix, key, &object[key], schema.property(key));
+            }
+
+            let _ = write!(output, "</{prefix}:{name}>");
+        }
+        Value::String(string) => {
+            let _ = write!(
+                output,
+                "<{prefix}:{name}>{}</{prefix}:{name}>",
+                escape(string)
+            );
+        }
+        Value::Bool(_) | Value::Number(_) => {
+            let _ = write!(output, "<{prefix}:{name}>{value}</{prefix}:{name}>");
+        }
+    }
+}
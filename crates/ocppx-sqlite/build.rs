@@ -0,0 +1,4 @@
+fn main() {
+    // The SQLite library of the system, e.g. `libsqlite3-dev` on Debian.
+    println!("cargo:rustc-link-lib=sqlite3");
+}
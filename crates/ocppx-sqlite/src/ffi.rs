@@ -0,0 +1,97 @@
+//! The declarations of `sqlite3.h` used by the binding, see
+//! <https://www.sqlite.org/c3ref/intro.html>.
+
+#![allow(non_camel_case_types)]
+
+use std::ffi::{c_char, c_double, c_int, c_uchar, c_void};
+
+pub(crate) enum sqlite3 {}
+pub(crate) enum sqlite3_stmt {}
+
+pub(crate) const SQLITE_OK: c_int = 0;
+pub(crate) const SQLITE_MISUSE: c_int = 21;
+pub(crate) const SQLITE_ROW: c_int = 100;
+pub(crate) const SQLITE_DONE: c_int = 101;
+
+pub(crate) const SQLITE_OPEN_READONLY: c_int = 0x0000_0001;
+pub(crate) const SQLITE_OPEN_READWRITE: c_int = 0x0000_0002;
+pub(crate) const SQLITE_OPEN_CREATE: c_int = 0x0000_0004;
+pub(crate) const SQLITE_OPEN_FULLMUTEX: c_int = 0x0001_0000;
+
+pub(crate) const SQLITE_INTEGER: c_int = 1;
+pub(crate) const SQLITE_FLOAT: c_int = 2;
+pub(crate) const SQLITE_TEXT: c_int = 3;
+pub(crate) const SQLITE_BLOB: c_int = 4;
+
+/// The destructor telling SQLite to copy the bound text or blob, i.e.
+/// `(sqlite3_destructor_type) -1`.
+pub(crate) const SQLITE_TRANSIENT: isize = -1;
+
+type ExecCallback =
+    unsafe extern "C" fn(*mut c_void, c_int, *mut *mut c_char, *mut *mut c_char) -> c_int;
+
+extern "C" {
+    pub(crate) fn sqlite3_open_v2(
+        filename: *const c_char,
+        db: *mut *mut sqlite3,
+        flags: c_int,
+        vfs: *const c_char,
+    ) -> c_int;
+    pub(crate) fn sqlite3_close_v2(db: *mut sqlite3) -> c_int;
+    pub(crate) fn sqlite3_errmsg(db: *mut sqlite3) -> *const c_char;
+    pub(crate) fn sqlite3_busy_timeout(db: *mut sqlite3, milliseconds: c_int) -> c_int;
+    pub(crate) fn sqlite3_changes(db: *mut sqlite3) -> c_int;
+    pub(crate) fn sqlite3_exec(
+        db: *mut sqlite3,
+        sql: *const c_char,
+        callback: Option<ExecCallback>,
+        argument: *mut c_void,
+        error_message: *mut *mut c_char,
+    ) -> c_int;
+
+    pub(crate) fn sqlite3_prepare_v2(
+        db: *mut sqlite3,
+        sql: *const c_char,
+        length: c_int,
+        statement: *mut *mut sqlite3_stmt,
+        tail: *mut *const c_char,
+    ) -> c_int;
+    pub(crate) fn sqlite3_step(statement: *mut sqlite3_stmt) -> c_int;
+    pub(crate) fn sqlite3_finalize(statement: *mut sqlite3_stmt) -> c_int;
+
+    pub(crate) fn sqlite3_bind_null(statement: *mut sqlite3_stmt, index: c_int) -> c_int;
+    pub(crate) fn sqlite3_bind_int64(
+        statement: *mut sqlite3_stmt,
+        index: c_int,
+        value: i64,
+    ) -> c_int;
+    pub(crate) fn sqlite3_bind_double(
+        statement: *mut sqlite3_stmt,
+        index: c_int,
+        value: c_double,
+    ) -> c_int;
+    pub(crate) fn sqlite3_bind_text(
+        statement: *mut sqlite3_stmt,
+        index: c_int,
+        value: *const c_char,
+        length: c_int,
+        destructor: isize,
+    ) -> c_int;
+    pub(crate) fn sqlite3_bind_blob(
+        statement: *mut sqlite3_stmt,
+        index: c_int,
+        value: *const c_void,
+        length: c_int,
+        destructor: isize,
+    ) -> c_int;
+
+    pub(crate) fn sqlite3_column_count(statement: *mut sqlite3_stmt) -> c_int;
+    pub(crate) fn sqlite3_column_name(statement: *mut sqlite3_stmt, index: c_int) -> *const c_char;
+    pub(crate) fn sqlite3_column_type(statement: *mut sqlite3_stmt, index: c_int) -> c_int;
+    pub(crate) fn sqlite3_column_int64(statement: *mut sqlite3_stmt, index: c_int) -> i64;
+    pub(crate) fn sqlite3_column_double(statement: *mut sqlite3_stmt, index: c_int) -> c_double;
+    pub(crate) fn sqlite3_column_text(statement: *mut sqlite3_stmt, index: c_int)
+        -> *const c_uchar;
+    pub(crate) fn sqlite3_column_blob(statement: *mut sqlite3_stmt, index: c_int) -> *const c_void;
+    pub(crate) fn sqlite3_column_bytes(statement: *mut sqlite3_stmt, index: c_int) -> c_int;
+}
@@ -0,0 +1,559 @@
+//! A small binding to the SQLite library of the system, for the crates
+//! keeping their data in SQLite databases: `SqliteAuthorization` of
+//! `ocppx-server`, and `SqliteStorage` of `ocppx-store`.
+//!
+//! A [`Connection`] runs the SQL statements with [`Value`]s as parameters,
+//! and returns their [`Row`]s. SQLite takes the locks and replays the
+//! journals, so that the databases can be written by other processes
+//! meanwhile.
+
+mod ffi;
+
+use std::{
+    ffi::{c_int, CStr, CString},
+    path::Path,
+    ptr::{self, NonNull},
+    sync::Arc,
+    time::Duration,
+};
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("SQLite error {code}: {message}")]
+    Sqlite { code: i32, message: String },
+
+    #[error("the path of the database is not in UTF-8")]
+    InvalidPath,
+
+    #[error("the SQL statement, or a parameter, contains a NUL byte")]
+    Nul(#[from] std::ffi::NulError),
+
+    #[error("a parameter is too large for SQLite")]
+    TooLarge,
+}
+
+/// A connection to an SQLite database.
+#[derive(Debug)]
+pub struct Connection {
+    raw: NonNull<ffi::sqlite3>,
+}
+
+// SAFETY: the connections are opened in the serialized threading mode
+// (`SQLITE_OPEN_FULLMUTEX`), so they can be used from any thread.
+unsafe impl Send for Connection {}
+
+impl Connection {
+    /// Open the database at `path` to read and write it, creating it if it
+    /// does not exist.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::open_with_flags(
+            path.as_ref(),
+            ffi::SQLITE_OPEN_READWRITE | ffi::SQLITE_OPEN_CREATE,
+        )
+    }
+
+    /// Open the existing database at `path` to read it only.
+    pub fn open_read_only(path: impl AsRef<Path>) -> Result<Self> {
+        Self::open_with_flags(path.as_ref(), ffi::SQLITE_OPEN_READONLY)
+    }
+
+    fn open_with_flags(path: &Path, flags: c_int) -> Result<Self> {
+        let path = CString::new(path.to_str().ok_or(Error::InvalidPath)?)?;
+        let mut raw = ptr::null_mut();
+
+        // SAFETY: `path` is a valid C string, and `raw` receives the
+        // connection, allocated even when the opening fails.
+        let code = unsafe {
+            ffi::sqlite3_open_v2(
+                path.as_ptr(),
+                &mut raw,
+                flags | ffi::SQLITE_OPEN_FULLMUTEX,
+                ptr::null(),
+            )
+        };
+
+        let Some(raw) = NonNull::new(raw) else {
+            return Err(Error::Sqlite {
+                code,
+                message: "cannot allocate the connection".to_owned(),
+            });
+        };
+        let connection = Self { raw };
+
+        if code != ffi::SQLITE_OK {
+            return Err(connection.error(code));
+        }
+
+        Ok(connection)
+    }
+
+    /// Wait up to `timeout` for the locks held by the other connections,
+    /// instead of failing with `SQLITE_BUSY` at once.
+    pub fn busy_timeout(&self, timeout: Duration) -> Result<()> {
+        let milliseconds = c_int::try_from(timeout.as_millis()).unwrap_or(c_int::MAX);
+
+        // SAFETY: the connection is open.
+        let code = unsafe { ffi::sqlite3_busy_timeout(self.raw.as_ptr(), milliseconds) };
+
+        self.check(code)
+    }
+
+    /// Run the SQL statements of `sql`, separated by semicolons, without
+    /// parameters, e.g. to create the tables.
+    pub fn execute_batch(&self, sql: &str) -> Result<()> {
+        let sql = CString::new(sql)?;
+
+        // SAFETY: the connection is open, and `sql` is a valid C string.
+        let code = unsafe {
+            ffi::sqlite3_exec(
+                self.raw.as_ptr(),
+                sql.as_ptr(),
+                None,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+
+        self.check(code)
+    }
+
+    /// Run the SQL statement `sql` with its `parameters`, bound to `?1`,
+    /// `?2` and so on, and return the number of rows it has changed.
+    pub fn execute(&self, sql: &str, parameters: &[Value]) -> Result<usize> {
+        self.statement(sql, parameters)?.run(|_| ())?;
+
+        // SAFETY: the connection is open.
+        let changes = unsafe { ffi::sqlite3_changes(self.raw.as_ptr()) };
+
+        Ok(usize::try_from(changes).unwrap_or(0))
+    }
+
+    /// Run the SQL query `sql` with its `parameters`, and return its rows.
+    pub fn query(&self, sql: &str, parameters: &[Value]) -> Result<Vec<Row>> {
+        let mut rows = Vec::new();
+        self.statement(sql, parameters)?.run(|row| rows.push(row))?;
+
+        Ok(rows)
+    }
+
+    /// Run `f` in a transaction, committed if it succeeds, rolled back
+    /// otherwise.
+    pub fn transaction<T, E>(
+        &self,
+        f: impl FnOnce(&Self) -> std::result::Result<T, E>,
+    ) -> std::result::Result<T, E>
+    where
+        E: From<Error>,
+    {
+        // Take the write lock at once, not to fail halfway through.
+        self.execute_batch("BEGIN IMMEDIATE")?;
+
+        match f(self) {
+            Ok(value) => {
+                self.execute_batch("COMMIT")?;
+
+                Ok(value)
+            }
+            Err(error) => {
+                let _ = self.execute_batch("ROLLBACK");
+
+                Err(error)
+            }
+        }
+    }
+
+    fn statement(&self, sql: &str, parameters: &[Value]) -> Result<Statement<'_>> {
+        let sql = CString::new(sql)?;
+        let mut raw = ptr::null_mut();
+
+        // SAFETY: the connection is open, `sql` is a valid C string, and
+        // `raw` receives the statement.
+        let code = unsafe {
+            ffi::sqlite3_prepare_v2(
+                self.raw.as_ptr(),
+                sql.as_ptr(),
+                -1,
+                &mut raw,
+                ptr::null_mut(),
+            )
+        };
+        self.check(code)?;
+
+        let statement = Statement {
+            connection: self,
+            raw: NonNull::new(raw).ok_or_else(|| Error::Sqlite {
+                code: ffi::SQLITE_MISUSE,
+                message: "the SQL statement is empty".to_owned(),
+            })?,
+        };
+
+        for (index, parameter) in parameters.iter().enumerate() {
+            statement.bind(
+                c_int::try_from(index + 1).map_err(|_| Error::TooLarge)?,
+                parameter,
+            )?;
+        }
+
+        Ok(statement)
+    }
+
+    fn check(&self, code: c_int) -> Result<()> {
+        if code == ffi::SQLITE_OK {
+            Ok(())
+        } else {
+            Err(self.error(code))
+        }
+    }
+
+    fn error(&self, code: c_int) -> Error {
+        // SAFETY: the connection is open, and the message is a valid C
+        // string until the next call on it.
+        let message = unsafe { CStr::from_ptr(ffi::sqlite3_errmsg(self.raw.as_ptr())) };
+
+        Error::Sqlite {
+            code,
+            message: message.to_string_lossy().into_owned(),
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        // SAFETY: the statements borrow the connection, so they have all
+        // been finalized.
+        unsafe {
+            ffi::sqlite3_close_v2(self.raw.as_ptr());
+        }
+    }
+}
+
+struct Statement<'c> {
+    connection: &'c Connection,
+    raw: NonNull<ffi::sqlite3_stmt>,
+}
+
+impl Statement<'_> {
+    fn bind(&self, index: c_int, value: &Value) -> Result<()> {
+        let raw = self.raw.as_ptr();
+
+        // SAFETY: the statement is prepared, and SQLite copies the text and
+        // the blobs (`SQLITE_TRANSIENT`).
+        let code = unsafe {
+            match value {
+                Value::Null => ffi::sqlite3_bind_null(raw, index),
+                Value::Integer(integer) => ffi::sqlite3_bind_int64(raw, index, *integer),
+                Value::Real(real) => ffi::sqlite3_bind_double(raw, index, *real),
+                Value::Text(text) => ffi::sqlite3_bind_text(
+                    raw,
+                    index,
+                    text.as_ptr().cast(),
+                    c_int::try_from(text.len()).map_err(|_| Error::TooLarge)?,
+                    ffi::SQLITE_TRANSIENT,
+                ),
+                Value::Blob(blob) => ffi::sqlite3_bind_blob(
+                    raw,
+                    index,
+                    blob.as_ptr().cast(),
+                    c_int::try_from(blob.len()).map_err(|_| Error::TooLarge)?,
+                    ffi::SQLITE_TRANSIENT,
+                ),
+            }
+        };
+
+        self.connection.check(code)
+    }
+
+    /// Step through the statement, giving each row to `on_row`.
+    fn run(self, mut on_row: impl FnMut(Row)) -> Result<()> {
+        let raw = self.raw.as_ptr();
+        let mut columns = None;
+
+        loop {
+            // SAFETY: the statement is prepared.
+            match unsafe { ffi::sqlite3_step(raw) } {
+                ffi::SQLITE_ROW => {
+                    let columns = columns.get_or_insert_with(|| self.columns()).clone();
+                    let values = (0..columns.len() as c_int)
+                        .map(|index| self.value(index))
+                        .collect();
+
+                    on_row(Row { columns, values });
+                }
+                ffi::SQLITE_DONE => return Ok(()),
+                code => return Err(self.connection.error(code)),
+            }
+        }
+    }
+
+    fn columns(&self) -> Arc<[String]> {
+        let raw = self.raw.as_ptr();
+
+        // SAFETY: the statement is prepared, and the names are valid C
+        // strings until it is finalized.
+        unsafe {
+            (0..ffi::sqlite3_column_count(raw))
+                .map(|index| {
+                    let name = ffi::sqlite3_column_name(raw, index);
+
+                    if name.is_null() {
+                        String::new()
+                    } else {
+                        CStr::from_ptr(name).to_string_lossy().into_owned()
+                    }
+                })
+                .collect()
+        }
+    }
+
+    fn value(&self, index: c_int) -> Value {
+        let raw = self.raw.as_ptr();
+
+        // SAFETY: the statement has a row, and the text and the blobs are
+        // valid for their number of bytes until the next step.
+        unsafe {
+            match ffi::sqlite3_column_type(raw, index) {
+                ffi::SQLITE_INTEGER => Value::Integer(ffi::sqlite3_column_int64(raw, index)),
+                ffi::SQLITE_FLOAT => Value::Real(ffi::sqlite3_column_double(raw, index)),
+                ffi::SQLITE_TEXT => {
+                    let text = ffi::sqlite3_column_text(raw, index);
+                    let length = ffi::sqlite3_column_bytes(raw, index) as usize;
+
+                    if text.is_null() {
+                        Value::Text(String::new())
+                    } else {
+                        Value::Text(
+                            String::from_utf8_lossy(std::slice::from_raw_parts(text, length))
+                                .into_owned(),
+                        )
+                    }
+                }
+                ffi::SQLITE_BLOB => {
+                    let blob = ffi::sqlite3_column_blob(raw, index);
+                    let length = ffi::sqlite3_column_bytes(raw, index) as usize;
+
+                    if blob.is_null() {
+                        Value::Blob(Vec::new())
+                    } else {
+                        Value::Blob(std::slice::from_raw_parts(blob.cast(), length).to_vec())
+                    }
+                }
+                _ => Value::Null,
+            }
+        }
+    }
+}
+
+impl Drop for Statement<'_> {
+    fn drop(&mut self) {
+        // SAFETY: the statement is prepared, and finalized only here.
+        unsafe {
+            ffi::sqlite3_finalize(self.raw.as_ptr());
+        }
+    }
+}
+
+/// A value of SQLite: a parameter of a statement, or a column of a row.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl Value {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Integer(integer) => Some(*integer),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Integer(integer) => Some(*integer as f64),
+            Self::Real(real) => Some(*real),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+impl From<i32> for Value {
+    fn from(integer: i32) -> Self {
+        Self::Integer(integer.into())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(integer: i64) -> Self {
+        Self::Integer(integer)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(real: f64) -> Self {
+        Self::Real(real)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl<T> From<Option<T>> for Value
+where
+    T: Into<Value>,
+{
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::Null, Into::into)
+    }
+}
+
+/// A row returned by [`Connection::query`].
+#[derive(Debug, Clone)]
+pub struct Row {
+    columns: Arc<[String]>,
+    values: Vec<Value>,
+}
+
+impl Row {
+    /// The value of `column`, by its name.
+    pub fn get(&self, column: &str) -> Option<&Value> {
+        self.columns
+            .iter()
+            .position(|name| name == column)
+            .map(|index| &self.values[index])
+    }
+
+    /// The values, in the order of the columns.
+    pub fn values(&self) -> &[Value] {
+        &self.values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn database(name: &str) -> std::path::PathBuf {
+        let path =
+            env::temp_dir().join(format!("ocppx-sqlite-{name}-{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        path
+    }
+
+    #[test]
+    fn test_query() {
+        let path = database("query");
+        let connection = Connection::open(&path).unwrap();
+
+        connection
+            .execute_batch("CREATE TABLE t (i INTEGER, r REAL, s TEXT, b BLOB)")
+            .unwrap();
+        assert_eq!(
+            connection
+                .execute(
+                    "INSERT INTO t VALUES (?1, ?2, ?3, ?4), (NULL, NULL, NULL, NULL)",
+                    &[
+                        42.into(),
+                        1.5.into(),
+                        "ünïcödé".into(),
+                        Value::Blob(vec![0, 1, 2]),
+                    ],
+                )
+                .unwrap(),
+            2
+        );
+
+        let rows = connection
+            .query(
+                "SELECT * FROM t WHERE i = ?1 OR i IS NULL ORDER BY i",
+                &[42.into()],
+            )
+            .unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].values().iter().all(Value::is_null));
+        assert_eq!(rows[1].get("i"), Some(&Value::Integer(42)));
+        assert_eq!(rows[1].get("r").and_then(Value::as_f64), Some(1.5));
+        assert_eq!(rows[1].get("s").and_then(Value::as_str), Some("ünïcödé"));
+        assert_eq!(rows[1].get("b"), Some(&Value::Blob(vec![0, 1, 2])));
+        assert_eq!(rows[1].get("unknown"), None);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_transaction() {
+        let path = database("transaction");
+        let connection = Connection::open(&path).unwrap();
+        connection
+            .execute_batch("CREATE TABLE t (i INTEGER)")
+            .unwrap();
+
+        assert!(connection
+            .transaction(|connection| {
+                connection.execute("INSERT INTO t VALUES (1)", &[])?;
+                connection.execute("INSERT INTO unknown VALUES (1)", &[])
+            })
+            .is_err());
+        connection
+            .transaction(|connection| connection.execute("INSERT INTO t VALUES (2)", &[]))
+            .unwrap();
+
+        // Another connection sees the committed rows only.
+        let reader = Connection::open_read_only(&path).unwrap();
+        let rows = reader.query("SELECT i FROM t", &[]).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get("i"), Some(&Value::Integer(2)));
+        assert!(reader.execute("INSERT INTO t VALUES (3)", &[]).is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            Connection::open_read_only(database("missing")),
+            Err(Error::Sqlite { .. })
+        ));
+
+        let path = database("errors");
+        let connection = Connection::open(&path).unwrap();
+        assert!(matches!(
+            connection.query("SELECT * FROM unknown", &[]),
+            Err(Error::Sqlite { message, .. }) if message.contains("no such table")
+        ));
+        assert!(matches!(
+            connection.query("SELECT '\0'", &[]),
+            Err(Error::Nul(_))
+        ));
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use ocppx_types::v1_6::{
+    BootNotificationRequest, MeterValuesRequest, StartTransactionRequest,
+    StatusNotificationRequest, StopTransactionRequest,
+};
+use serde::{Deserialize, Serialize};
+
+/// Something that happened to a Charge Point, to be recorded in a
+/// [`Storage`][crate::Storage].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    /// When the entry has been recorded.
+    pub timestamp: DateTime<Utc>,
+    pub charge_point_id: String,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+impl Entry {
+    /// An entry for `event`, happening now.
+    pub fn new(charge_point_id: impl Into<String>, event: Event) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            charge_point_id: charge_point_id.into(),
+            event,
+        }
+    }
+}
+
+/// The requests of the Charge Points that change their state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", content = "request", rename_all = "camelCase")]
+pub enum Event {
+    /// The Charge Point has booted, and has been accepted.
+    Booted(BootNotificationRequest),
+    ConnectorStatus(StatusNotificationRequest),
+    /// A transaction has started, with the ID assigned by the Central
+    /// System.
+    TransactionStarted {
+        transaction_id: i32,
+        #[serde(flatten)]
+        request: StartTransactionRequest,
+    },
+    TransactionStopped(StopTransactionRequest),
+    MeterValues(MeterValuesRequest),
+}
@@ -0,0 +1,265 @@
+use crate::{
+    ChargePointRecord, ConnectorRecord, Entry, Error, MemoryStorage, Result, Storage,
+    TransactionRecord,
+};
+use ocppx_types::v1_6::MeterValue;
+use std::{
+    fs::{self, OpenOptions},
+    io::ErrorKind,
+    path::Path,
+};
+use tokio::{fs::File, io::AsyncWriteExt, sync::Mutex};
+
+/// A [`Storage`] appending the entries to a journal file, one JSON object
+/// per line, and keeping the state in memory.
+///
+/// The journal is replayed when it is opened, so that the state survives
+/// a restart.
+#[derive(Debug)]
+pub struct FileStorage {
+    journal: Mutex<File>,
+    memory: MemoryStorage,
+}
+
+impl FileStorage {
+    /// Open the journal at `path`, creating it if it does not exist, and
+    /// replay it.
+    ///
+    /// A last line without its line feed has been torn by a crash while
+    /// being written: it is truncated.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let memory = MemoryStorage::new();
+        let journal = match fs::read(path) {
+            Ok(journal) => journal,
+            Err(error) if error.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(error) => return Err(error.into()),
+        };
+
+        // The length of the complete lines.
+        let length = journal
+            .iter()
+            .rposition(|byte| *byte == b'\n')
+            .map_or(0, |position| position + 1);
+
+        for (index, line) in journal[..length].split(|byte| *byte == b'\n').enumerate() {
+            if line.trim_ascii().is_empty() {
+                continue;
+            }
+
+            let entry = serde_json::from_slice(line).map_err(|error| Error::InvalidJournal {
+                line: index + 1,
+                error,
+            })?;
+            memory.apply(&entry);
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        if length < journal.len() {
+            file.set_len(length as u64)?;
+        }
+
+        Ok(Self {
+            journal: Mutex::new(File::from_std(file)),
+            memory,
+        })
+    }
+}
+
+impl Storage for FileStorage {
+    async fn record(&self, entry: Entry) -> Result<()> {
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+
+        // Keep the lock while applying the entry, for the memory to follow
+        // the order of the journal.
+        let mut journal = self.journal.lock().await;
+        journal.write_all(&line).await?;
+        journal.flush().await?;
+
+        self.memory.record(entry).await
+    }
+
+    async fn charge_points(&self) -> Result<Vec<ChargePointRecord>> {
+        self.memory.charge_points().await
+    }
+
+    async fn charge_point(&self, charge_point_id: &str) -> Result<Option<ChargePointRecord>> {
+        self.memory.charge_point(charge_point_id).await
+    }
+
+    async fn connectors(&self, charge_point_id: &str) -> Result<Vec<ConnectorRecord>> {
+        self.memory.connectors(charge_point_id).await
+    }
+
+    async fn transaction(&self, transaction_id: i32) -> Result<Option<TransactionRecord>> {
+        self.memory.transaction(transaction_id).await
+    }
+
+    async fn transactions(&self, charge_point_id: &str) -> Result<Vec<TransactionRecord>> {
+        self.memory.transactions(charge_point_id).await
+    }
+
+    async fn meter_values(
+        &self,
+        charge_point_id: &str,
+        connector_id: i32,
+    ) -> Result<Vec<MeterValue>> {
+        self.memory
+            .meter_values(charge_point_id, connector_id)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        storage::tests::{state, test_storage},
+        Event,
+    };
+    use ocppx_types::v1_6::{
+        BootNotificationRequest, MeterValuesRequest, StartTransactionRequest,
+        StatusNotificationRequest, StopTransactionRequest,
+    };
+    use serde_json::{from_value, json};
+    use std::env;
+
+    #[tokio::test]
+    async fn test_journal_is_replayed() {
+        let path = env::temp_dir().join(format!("ocppx-store-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let storage = FileStorage::open(&path).unwrap();
+        let events = [
+            Event::Booted(
+                from_value::<BootNotificationRequest>(json!({
+                    "chargePointVendor": "VendorX",
+                    "chargePointModel": "SingleSocketCharger",
+                }))
+                .unwrap(),
+            ),
+            Event::ConnectorStatus(
+                from_value::<StatusNotificationRequest>(json!({
+                    "connectorId": 1,
+                    "errorCode": "NoError",
+                    "status": "Charging",
+                }))
+                .unwrap(),
+            ),
+            Event::TransactionStarted {
+                transaction_id: 42,
+                request: from_value::<StartTransactionRequest>(json!({
+                    "connectorId": 1,
+                    "idTag": "ABC",
+                    "meterStart": 100,
+                    "timestamp": "2013-02-01T20:53:32.486Z",
+                }))
+                .unwrap(),
+            },
+            Event::MeterValues(
+                from_value::<MeterValuesRequest>(json!({
+                    "connectorId": 1,
+                    "transactionId": 42,
+                    "meterValue": [{
+                        "timestamp": "2013-02-01T21:00:00Z",
+                        "sampledValue": [{ "value": "1100" }],
+                    }],
+                }))
+                .unwrap(),
+            ),
+            Event::TransactionStopped(
+                from_value::<StopTransactionRequest>(json!({
+                    "transactionId": 42,
+                    "meterStop": 2100,
+                    "timestamp": "2013-02-01T22:00:00Z",
+                }))
+                .unwrap(),
+            ),
+        ];
+
+        for event in events {
+            storage.record(Entry::new("CP001", event)).await.unwrap();
+        }
+        drop(storage);
+
+        // A crash while writing an entry.
+        let mut journal = std::fs::read(&path).unwrap();
+        journal.extend_from_slice(br#"{"chargePointId":"CP001","#);
+        std::fs::write(&path, &journal).unwrap();
+
+        let storage = FileStorage::open(&path).unwrap();
+        storage
+            .record(Entry::new(
+                "CP002",
+                Event::Booted(
+                    from_value::<BootNotificationRequest>(json!({
+                        "chargePointVendor": "VendorY",
+                        "chargePointModel": "DoubleSocketCharger",
+                    }))
+                    .unwrap(),
+                ),
+            ))
+            .await
+            .unwrap();
+        drop(storage);
+
+        let storage = FileStorage::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let charge_point = storage.charge_point("CP002").await.unwrap().unwrap();
+        assert_eq!(charge_point.boot.unwrap().charge_point_vendor, "VendorY");
+
+        let charge_point = storage.charge_point("CP001").await.unwrap().unwrap();
+        assert_eq!(charge_point.boot.unwrap().charge_point_vendor, "VendorX");
+
+        let connectors = storage.connectors("CP001").await.unwrap();
+        assert_eq!(connectors.len(), 1);
+        assert_eq!(connectors[0].connector_id, 1);
+
+        let transaction = storage.transaction(42).await.unwrap().unwrap();
+        assert_eq!(transaction.meter_values.len(), 1);
+        assert_eq!(transaction.stop.unwrap().meter_stop, 2100);
+        assert!(storage.transactions("CP002").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_file_storage() {
+        let path = env::temp_dir().join(format!("ocppx-store-{}-state.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let storage = FileStorage::open(&path).unwrap();
+        test_storage(&storage).await;
+        let before = state(&storage).await;
+        drop(storage);
+
+        // The state survives a restart.
+        let storage = FileStorage::open(&path).unwrap();
+        assert_eq!(state(&storage).await, before);
+
+        drop(storage);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_torn_journal() {
+        let path = env::temp_dir().join(format!("ocppx-store-{}-torn.jsonl", std::process::id()));
+
+        // Nothing but a torn line.
+        std::fs::write(&path, br#"{"chargePointId":"CP001","#).unwrap();
+        let storage = FileStorage::open(&path).unwrap();
+        assert!(storage.charge_points().await.unwrap().is_empty());
+        assert!(std::fs::read(&path).unwrap().is_empty());
+        drop(storage);
+
+        // A complete line which is not an entry is not a tear.
+        std::fs::write(&path, b"\n{}\n").unwrap();
+        assert!(matches!(
+            FileStorage::open(&path),
+            Err(Error::InvalidJournal { line: 2, .. })
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
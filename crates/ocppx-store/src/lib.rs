@@ -0,0 +1,56 @@
+//! Persist the state of the Charge Points: their boot information, the
+//! statuses of their connectors, their transactions and meter values.
+//!
+//! The state is built from [`Entry`]s, i.e. the requests of the Charge
+//! Points worth keeping, recorded in a [`Storage`]:
+//!
+//! - [`MemoryStorage`] keeps the state in memory, e.g. for the tests,
+//! - [`FileStorage`] appends the entries to a journal file, one JSON object
+//!   per line (JSON Lines), and replays it when it is opened again,
+//! - with the `sqlite` feature, `SqliteStorage` keeps the state in the
+//!   tables of an SQLite database.
+//!
+//! With its `store` feature, `ocppx-server` records the `Call`s of the
+//! Charge Points with `StorageLayer`.
+
+mod entry;
+mod file;
+mod memory;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod state;
+mod storage;
+
+pub use entry::{Entry, Event};
+pub use file::FileStorage;
+pub use memory::MemoryStorage;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStorage;
+pub use state::{ChargePointRecord, ConnectorRecord, TransactionRecord, TransactionStopRecord};
+pub use storage::Storage;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("I/O error")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON error")]
+    Json(#[from] serde_json::Error),
+
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error")]
+    Sqlite(#[from] ocppx_sqlite::Error),
+
+    #[error("invalid `{column}` column in the database")]
+    InvalidColumn { column: &'static str },
+
+    #[error("invalid entry at line {line} of the journal")]
+    InvalidJournal {
+        line: usize,
+        #[source]
+        error: serde_json::Error,
+    },
+}
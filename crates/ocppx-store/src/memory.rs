@@ -0,0 +1,111 @@
+use crate::{
+    state::State, ChargePointRecord, ConnectorRecord, Entry, Result, Storage, TransactionRecord,
+};
+use ocppx_types::v1_6::MeterValue;
+use std::sync::Mutex;
+
+/// A [`Storage`] keeping the state in memory: it is lost when the storage
+/// is dropped.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    state: Mutex<State>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn apply(&self, entry: &Entry) {
+        self.state.lock().unwrap().apply(entry);
+    }
+}
+
+impl Storage for MemoryStorage {
+    async fn record(&self, entry: Entry) -> Result<()> {
+        self.apply(&entry);
+
+        Ok(())
+    }
+
+    async fn charge_points(&self) -> Result<Vec<ChargePointRecord>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .charge_points
+            .values()
+            .cloned()
+            .collect())
+    }
+
+    async fn charge_point(&self, charge_point_id: &str) -> Result<Option<ChargePointRecord>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .charge_points
+            .get(charge_point_id)
+            .cloned())
+    }
+
+    async fn connectors(&self, charge_point_id: &str) -> Result<Vec<ConnectorRecord>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .connectors
+            .values()
+            .filter(|connector| connector.charge_point_id == charge_point_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn transaction(&self, transaction_id: i32) -> Result<Option<TransactionRecord>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .transactions
+            .get(&transaction_id)
+            .cloned())
+    }
+
+    async fn transactions(&self, charge_point_id: &str) -> Result<Vec<TransactionRecord>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .transactions
+            .values()
+            .filter(|transaction| transaction.charge_point_id == charge_point_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn meter_values(
+        &self,
+        charge_point_id: &str,
+        connector_id: i32,
+    ) -> Result<Vec<MeterValue>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .meter_values
+            .get(&(charge_point_id.to_owned(), connector_id))
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::test_storage;
+
+    #[tokio::test]
+    async fn test_memory_storage() {
+        test_storage(&MemoryStorage::new()).await;
+    }
+}
@@ -0,0 +1,558 @@
+use crate::{
+    ChargePointRecord, ConnectorRecord, Entry, Error, Event, Result, Storage, TransactionRecord,
+    TransactionStopRecord,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use ocppx_sqlite::{Connection, Row, Value};
+use ocppx_types::v1_6::MeterValue;
+use std::{path::Path, str::FromStr, sync::Mutex, time::Duration};
+
+/// The tables of [`SqliteStorage`]. The requests and the meter values are
+/// kept in JSON.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS charge_points (
+    id TEXT PRIMARY KEY,
+    first_seen TEXT NOT NULL,
+    last_seen TEXT NOT NULL,
+    boot TEXT,
+    booted_at TEXT
+);
+
+CREATE TABLE IF NOT EXISTS connectors (
+    charge_point_id TEXT NOT NULL,
+    connector_id INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    error_code TEXT NOT NULL,
+    info TEXT,
+    vendor_error_code TEXT,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (charge_point_id, connector_id)
+);
+
+CREATE TABLE IF NOT EXISTS transactions (
+    id INTEGER PRIMARY KEY,
+    charge_point_id TEXT NOT NULL,
+    connector_id INTEGER NOT NULL,
+    id_tag TEXT NOT NULL,
+    reservation_id INTEGER,
+    meter_start INTEGER NOT NULL,
+    started_at TEXT NOT NULL,
+    stop_id_tag TEXT,
+    meter_stop INTEGER,
+    stopped_at TEXT,
+    stop_reason TEXT
+);
+CREATE INDEX IF NOT EXISTS transactions_by_charge_point ON transactions (charge_point_id);
+
+CREATE TABLE IF NOT EXISTS meter_values (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    charge_point_id TEXT NOT NULL,
+    connector_id INTEGER NOT NULL,
+    transaction_id INTEGER,
+    meter_value TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS meter_values_by_transaction ON meter_values (transaction_id);
+CREATE INDEX IF NOT EXISTS meter_values_by_connector
+    ON meter_values (charge_point_id, connector_id);
+";
+
+/// How long to wait for the other connections to the database, e.g. of a
+/// reporting tool, to release their locks.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A [`Storage`] keeping the state in an SQLite database, one table for
+/// the Charge Points, their connectors, their transactions and their meter
+/// values, so that it can be queried by other tools too.
+///
+/// The entries are recorded as [`MemoryStorage`][crate::MemoryStorage]
+/// applies them, each one in a transaction. The database is opened with
+/// the SQLite library of the system, and its tables are created if they do
+/// not exist.
+#[derive(Debug)]
+pub struct SqliteStorage {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    /// Open the database at `path`, creating it if it does not exist.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        connection.execute_batch(SCHEMA)?;
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn apply(connection: &Connection, entry: &Entry) -> Result<()> {
+        let charge_point_id = entry.charge_point_id.as_str();
+
+        connection.execute(
+            "INSERT INTO charge_points (id, first_seen, last_seen) VALUES (?1, ?2, ?2)
+            ON CONFLICT (id) DO UPDATE SET last_seen = excluded.last_seen",
+            &[charge_point_id.into(), timestamp(entry.timestamp)],
+        )?;
+
+        match &entry.event {
+            Event::Booted(request) => {
+                connection.execute(
+                    "UPDATE charge_points SET boot = ?2, booted_at = ?3 WHERE id = ?1",
+                    &[
+                        charge_point_id.into(),
+                        serde_json::to_string(request)?.into(),
+                        timestamp(entry.timestamp),
+                    ],
+                )?;
+            }
+
+            Event::ConnectorStatus(request) => {
+                connection.execute(
+                    "INSERT OR REPLACE INTO connectors VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    &[
+                        charge_point_id.into(),
+                        request.connector_id.into(),
+                        request.status.to_string().into(),
+                        request.error_code.to_string().into(),
+                        request.info.as_deref().into(),
+                        request.vendor_error_code.as_deref().into(),
+                        timestamp(request.timestamp.unwrap_or(entry.timestamp)),
+                    ],
+                )?;
+            }
+
+            Event::TransactionStarted {
+                transaction_id,
+                request,
+            } => {
+                // A transaction started again starts afresh.
+                connection.execute(
+                    "DELETE FROM meter_values WHERE transaction_id = ?1",
+                    &[(*transaction_id).into()],
+                )?;
+                connection.execute(
+                    "INSERT OR REPLACE INTO transactions
+                        (id, charge_point_id, connector_id, id_tag, reservation_id, meter_start, started_at)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    &[
+                        (*transaction_id).into(),
+                        charge_point_id.into(),
+                        request.connector_id.into(),
+                        request.id_tag.as_str().into(),
+                        request.reservation_id.into(),
+                        request.meter_start.into(),
+                        timestamp(request.timestamp),
+                    ],
+                )?;
+            }
+
+            Event::TransactionStopped(request) => {
+                let stopped = connection.execute(
+                    "UPDATE transactions
+                    SET stop_id_tag = ?2, meter_stop = ?3, stopped_at = ?4, stop_reason = ?5
+                    WHERE id = ?1",
+                    &[
+                        request.transaction_id.into(),
+                        request.id_tag.as_deref().into(),
+                        request.meter_stop.into(),
+                        timestamp(request.timestamp),
+                        request
+                            .reason
+                            .unwrap_or(ocppx_types::v1_6::StopTransactionReason::Local)
+                            .to_string()
+                            .into(),
+                    ],
+                )?;
+
+                if stopped > 0 {
+                    for data in request.transaction_data.iter().flatten() {
+                        let meter_value = MeterValue::builder()
+                            .sampled_value(data.sampled_value.clone())
+                            .timestamp(data.timestamp)
+                            .build();
+
+                        connection.execute(
+                            "INSERT INTO meter_values (charge_point_id, connector_id, transaction_id, meter_value)
+                            SELECT charge_point_id, connector_id, id, ?2 FROM transactions WHERE id = ?1",
+                            &[
+                                request.transaction_id.into(),
+                                serde_json::to_string(&meter_value)?.into(),
+                            ],
+                        )?;
+                    }
+                }
+            }
+
+            Event::MeterValues(request) => {
+                // The meter values of an unknown transaction are kept with
+                // the ones sent outside of a transaction.
+                let transaction_id = match request.transaction_id {
+                    Some(transaction_id) => connection
+                        .query(
+                            "SELECT id FROM transactions WHERE id = ?1",
+                            &[transaction_id.into()],
+                        )?
+                        .first()
+                        .map(|_| transaction_id),
+                    None => None,
+                };
+
+                for meter_value in &request.meter_value {
+                    connection.execute(
+                        "INSERT INTO meter_values (charge_point_id, connector_id, transaction_id, meter_value)
+                        VALUES (?1, ?2, ?3, ?4)",
+                        &[
+                            charge_point_id.into(),
+                            request.connector_id.into(),
+                            transaction_id.into(),
+                            serde_json::to_string(meter_value)?.into(),
+                        ],
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn query<T>(
+        &self,
+        sql: &str,
+        parameters: &[Value],
+        record: impl Fn(&Connection, &Row) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        let connection = self.connection.lock().unwrap();
+
+        connection
+            .query(sql, parameters)?
+            .iter()
+            .map(|row| record(&connection, row))
+            .collect()
+    }
+}
+
+impl Storage for SqliteStorage {
+    async fn record(&self, entry: Entry) -> Result<()> {
+        self.connection
+            .lock()
+            .unwrap()
+            .transaction(|connection| Self::apply(connection, &entry))
+    }
+
+    async fn charge_points(&self) -> Result<Vec<ChargePointRecord>> {
+        self.query("SELECT * FROM charge_points ORDER BY id", &[], charge_point)
+    }
+
+    async fn charge_point(&self, charge_point_id: &str) -> Result<Option<ChargePointRecord>> {
+        Ok(self
+            .query(
+                "SELECT * FROM charge_points WHERE id = ?1",
+                &[charge_point_id.into()],
+                charge_point,
+            )?
+            .pop())
+    }
+
+    async fn connectors(&self, charge_point_id: &str) -> Result<Vec<ConnectorRecord>> {
+        self.query(
+            "SELECT * FROM connectors WHERE charge_point_id = ?1 ORDER BY connector_id",
+            &[charge_point_id.into()],
+            |_, row| {
+                Ok(ConnectorRecord {
+                    charge_point_id: text(row, "charge_point_id")?,
+                    connector_id: integer(row, "connector_id")?,
+                    status: parse(row, "status")?,
+                    error_code: parse(row, "error_code")?,
+                    info: optional_text(row, "info"),
+                    vendor_error_code: optional_text(row, "vendor_error_code"),
+                    updated_at: parse(row, "updated_at")?,
+                })
+            },
+        )
+    }
+
+    async fn transaction(&self, transaction_id: i32) -> Result<Option<TransactionRecord>> {
+        Ok(self
+            .query(
+                "SELECT * FROM transactions WHERE id = ?1",
+                &[transaction_id.into()],
+                transaction,
+            )?
+            .pop())
+    }
+
+    async fn transactions(&self, charge_point_id: &str) -> Result<Vec<TransactionRecord>> {
+        self.query(
+            "SELECT * FROM transactions WHERE charge_point_id = ?1 ORDER BY id",
+            &[charge_point_id.into()],
+            transaction,
+        )
+    }
+
+    async fn meter_values(
+        &self,
+        charge_point_id: &str,
+        connector_id: i32,
+    ) -> Result<Vec<MeterValue>> {
+        self.query(
+            "SELECT meter_value FROM meter_values
+            WHERE charge_point_id = ?1 AND connector_id = ?2 AND transaction_id IS NULL
+            ORDER BY id",
+            &[charge_point_id.into(), connector_id.into()],
+            |_, row| Ok(serde_json::from_str(&text(row, "meter_value")?)?),
+        )
+    }
+}
+
+fn charge_point(_: &Connection, row: &Row) -> Result<ChargePointRecord> {
+    Ok(ChargePointRecord {
+        id: text(row, "id")?,
+        first_seen: parse(row, "first_seen")?,
+        last_seen: parse(row, "last_seen")?,
+        boot: optional_text(row, "boot")
+            .map(|boot| serde_json::from_str(&boot))
+            .transpose()?,
+        booted_at: optional_parse(row, "booted_at")?,
+    })
+}
+
+fn transaction(connection: &Connection, row: &Row) -> Result<TransactionRecord> {
+    let id = integer(row, "id")?;
+    let meter_values = connection
+        .query(
+            "SELECT meter_value FROM meter_values WHERE transaction_id = ?1 ORDER BY id",
+            &[id.into()],
+        )?
+        .iter()
+        .map(|row| Ok(serde_json::from_str(&text(row, "meter_value")?)?))
+        .collect::<Result<Vec<_>>>()?;
+    let stop = match optional_parse(row, "stopped_at")? {
+        Some(stopped_at) => Some(TransactionStopRecord {
+            id_tag: optional_text(row, "stop_id_tag"),
+            meter_stop: integer(row, "meter_stop")?,
+            stopped_at,
+            reason: parse(row, "stop_reason")?,
+        }),
+        None => None,
+    };
+
+    Ok(TransactionRecord {
+        id,
+        charge_point_id: text(row, "charge_point_id")?,
+        connector_id: integer(row, "connector_id")?,
+        id_tag: text(row, "id_tag")?,
+        reservation_id: row
+            .get("reservation_id")
+            .and_then(Value::as_i64)
+            .and_then(|reservation_id| i32::try_from(reservation_id).ok()),
+        meter_start: integer(row, "meter_start")?,
+        started_at: parse(row, "started_at")?,
+        meter_values,
+        stop,
+    })
+}
+
+/// A timestamp as stored in the database, in RFC 3339, e.g.
+/// `2013-02-01T20:53:32.486Z`.
+fn timestamp(timestamp: DateTime<Utc>) -> Value {
+    timestamp
+        .to_rfc3339_opts(SecondsFormat::AutoSi, true)
+        .into()
+}
+
+fn optional_text(row: &Row, column: &'static str) -> Option<String> {
+    row.get(column).and_then(Value::as_str).map(str::to_owned)
+}
+
+fn text(row: &Row, column: &'static str) -> Result<String> {
+    optional_text(row, column).ok_or(Error::InvalidColumn { column })
+}
+
+fn integer(row: &Row, column: &'static str) -> Result<i32> {
+    row.get(column)
+        .and_then(Value::as_i64)
+        .and_then(|integer| i32::try_from(integer).ok())
+        .ok_or(Error::InvalidColumn { column })
+}
+
+fn optional_parse<T>(row: &Row, column: &'static str) -> Result<Option<T>>
+where
+    T: FromStr,
+{
+    optional_text(row, column)
+        .map(|text| text.parse().map_err(|_| Error::InvalidColumn { column }))
+        .transpose()
+}
+
+fn parse<T>(row: &Row, column: &'static str) -> Result<T>
+where
+    T: FromStr,
+{
+    optional_parse(row, column)?.ok_or(Error::InvalidColumn { column })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        storage::tests::{state, test_storage},
+        MemoryStorage,
+    };
+    use ocppx_types::v1_6::{
+        BootNotificationRequest, MeterValuesRequest, StartTransactionRequest,
+        StatusNotificationRequest, StatusNotificationStatus, StopTransactionReason,
+        StopTransactionRequest,
+    };
+    use serde_json::{from_value, json};
+    use std::env;
+
+    #[tokio::test]
+    async fn test_sqlite_storage() {
+        let path = env::temp_dir().join(format!("ocppx-store-{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let storage = SqliteStorage::open(&path).unwrap();
+        let events = [
+            Event::Booted(
+                from_value::<BootNotificationRequest>(json!({
+                    "chargePointVendor": "VendorX",
+                    "chargePointModel": "SingleSocketCharger",
+                }))
+                .unwrap(),
+            ),
+            Event::ConnectorStatus(
+                from_value::<StatusNotificationRequest>(json!({
+                    "connectorId": 1,
+                    "errorCode": "NoError",
+                    "status": "Charging",
+                    "timestamp": "2013-02-01T20:53:00Z",
+                }))
+                .unwrap(),
+            ),
+            Event::TransactionStarted {
+                transaction_id: 42,
+                request: from_value::<StartTransactionRequest>(json!({
+                    "connectorId": 1,
+                    "idTag": "ABC",
+                    "meterStart": 100,
+                    "timestamp": "2013-02-01T20:53:32.486Z",
+                }))
+                .unwrap(),
+            },
+            Event::MeterValues(
+                from_value::<MeterValuesRequest>(json!({
+                    "connectorId": 1,
+                    "transactionId": 42,
+                    "meterValue": [{
+                        "timestamp": "2013-02-01T21:00:00Z",
+                        "sampledValue": [{ "value": "1100" }],
+                    }],
+                }))
+                .unwrap(),
+            ),
+            // Outside of a transaction.
+            Event::MeterValues(
+                from_value::<MeterValuesRequest>(json!({
+                    "connectorId": 2,
+                    "meterValue": [{
+                        "timestamp": "2013-02-01T21:00:00Z",
+                        "sampledValue": [{ "value": "7" }],
+                    }],
+                }))
+                .unwrap(),
+            ),
+            Event::TransactionStopped(
+                from_value::<StopTransactionRequest>(json!({
+                    "transactionId": 42,
+                    "meterStop": 2100,
+                    "timestamp": "2013-02-01T22:00:00Z",
+                    "transactionData": [{
+                        "timestamp": "2013-02-01T22:00:00Z",
+                        "sampledValue": [{ "value": "2100" }],
+                    }],
+                }))
+                .unwrap(),
+            ),
+        ];
+
+        for event in events {
+            storage.record(Entry::new("CP001", event)).await.unwrap();
+        }
+        drop(storage);
+
+        // The state survives a restart.
+        let storage = SqliteStorage::open(&path).unwrap();
+
+        let charge_points = storage.charge_points().await.unwrap();
+        assert_eq!(charge_points.len(), 1);
+        assert_eq!(
+            charge_points[0].boot.as_ref().unwrap().charge_point_vendor,
+            "VendorX"
+        );
+        assert!(charge_points[0].booted_at.is_some());
+        assert!(storage.charge_point("CP002").await.unwrap().is_none());
+
+        let connectors = storage.connectors("CP001").await.unwrap();
+        assert_eq!(
+            connectors,
+            [ConnectorRecord {
+                charge_point_id: "CP001".to_owned(),
+                connector_id: 1,
+                status: StatusNotificationStatus::Charging,
+                error_code: "NoError".parse().unwrap(),
+                info: None,
+                vendor_error_code: None,
+                updated_at: "2013-02-01T20:53:00Z".parse().unwrap(),
+            }]
+        );
+
+        let transaction = storage.transaction(42).await.unwrap().unwrap();
+        assert_eq!(transaction.id_tag, "ABC");
+        assert_eq!(transaction.meter_start, 100);
+        assert_eq!(
+            transaction.started_at,
+            "2013-02-01T20:53:32.486Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(transaction.meter_values.len(), 2);
+        assert_eq!(
+            transaction.stop,
+            Some(TransactionStopRecord {
+                id_tag: None,
+                meter_stop: 2100,
+                stopped_at: "2013-02-01T22:00:00Z".parse().unwrap(),
+                reason: StopTransactionReason::Local,
+            })
+        );
+        assert_eq!(storage.transactions("CP001").await.unwrap().len(), 1);
+        assert!(storage.transactions("CP002").await.unwrap().is_empty());
+
+        assert_eq!(storage.meter_values("CP001", 2).await.unwrap().len(), 1);
+        assert!(storage.meter_values("CP001", 1).await.unwrap().is_empty());
+
+        drop(storage);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_storage_state() {
+        let path = env::temp_dir().join(format!("ocppx-store-{}-state.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let storage = SqliteStorage::open(&path).unwrap();
+        test_storage(&storage).await;
+        let before = state(&storage).await;
+        drop(storage);
+
+        // The state survives a restart, and is the one of the other
+        // storages.
+        let storage = SqliteStorage::open(&path).unwrap();
+        assert_eq!(state(&storage).await, before);
+
+        let memory = MemoryStorage::new();
+        test_storage(&memory).await;
+        assert_eq!(state(&memory).await, before);
+
+        drop(storage);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
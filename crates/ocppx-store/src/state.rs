@@ -0,0 +1,181 @@
+use crate::{Entry, Event};
+use chrono::{DateTime, Utc};
+use ocppx_types::v1_6::{
+    BootNotificationRequest, MeterValue, StatusNotificationErrorCode, StatusNotificationStatus,
+    StopTransactionReason,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A Charge Point, as known from its entries.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChargePointRecord {
+    pub id: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    /// The last accepted `BootNotification`, if any.
+    pub boot: Option<BootNotificationRequest>,
+    pub booted_at: Option<DateTime<Utc>>,
+}
+
+/// The last status reported for a connector. The connector 0 is the Charge
+/// Point itself.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectorRecord {
+    pub charge_point_id: String,
+    pub connector_id: i32,
+    pub status: StatusNotificationStatus,
+    pub error_code: StatusNotificationErrorCode,
+    pub info: Option<String>,
+    pub vendor_error_code: Option<String>,
+    /// The time of the status, as reported by the Charge Point, or else
+    /// the time it has been recorded.
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A transaction, ongoing or stopped.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionRecord {
+    pub id: i32,
+    pub charge_point_id: String,
+    pub connector_id: i32,
+    pub id_tag: String,
+    pub reservation_id: Option<i32>,
+    /// Meter value at the start of the transaction, in Wh.
+    pub meter_start: i32,
+    pub started_at: DateTime<Utc>,
+    /// The meter values sent during the transaction, with `MeterValues`
+    /// or in the `transactionData` of `StopTransaction`.
+    pub meter_values: Vec<MeterValue>,
+    pub stop: Option<TransactionStopRecord>,
+}
+
+impl TransactionRecord {
+    pub fn is_active(&self) -> bool {
+        self.stop.is_none()
+    }
+}
+
+/// How a transaction has stopped.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionStopRecord {
+    pub id_tag: Option<String>,
+    /// Meter value at the end of the transaction, in Wh.
+    pub meter_stop: i32,
+    pub stopped_at: DateTime<Utc>,
+    /// `Local` when omitted by the Charge Point.
+    pub reason: StopTransactionReason,
+}
+
+/// The state built from the entries, shared by the storages.
+#[derive(Debug, Default)]
+pub(crate) struct State {
+    pub(crate) charge_points: BTreeMap<String, ChargePointRecord>,
+    pub(crate) connectors: BTreeMap<(String, i32), ConnectorRecord>,
+    pub(crate) transactions: BTreeMap<i32, TransactionRecord>,
+    /// The meter values sent outside of a transaction, by Charge Point and
+    /// connector.
+    pub(crate) meter_values: BTreeMap<(String, i32), Vec<MeterValue>>,
+}
+
+impl State {
+    pub(crate) fn apply(&mut self, entry: &Entry) {
+        let charge_point_id = &entry.charge_point_id;
+        let charge_point = self
+            .charge_points
+            .entry(charge_point_id.clone())
+            .or_insert_with(|| ChargePointRecord {
+                id: charge_point_id.clone(),
+                first_seen: entry.timestamp,
+                last_seen: entry.timestamp,
+                boot: None,
+                booted_at: None,
+            });
+        charge_point.last_seen = entry.timestamp;
+
+        match &entry.event {
+            Event::Booted(request) => {
+                charge_point.boot = Some(request.clone());
+                charge_point.booted_at = Some(entry.timestamp);
+            }
+
+            Event::ConnectorStatus(request) => {
+                self.connectors.insert(
+                    (charge_point_id.clone(), request.connector_id),
+                    ConnectorRecord {
+                        charge_point_id: charge_point_id.clone(),
+                        connector_id: request.connector_id,
+                        status: request.status,
+                        error_code: request.error_code,
+                        info: request.info.as_deref().map(str::to_owned),
+                        vendor_error_code: request.vendor_error_code.as_deref().map(str::to_owned),
+                        updated_at: request.timestamp.unwrap_or(entry.timestamp),
+                    },
+                );
+            }
+
+            Event::TransactionStarted {
+                transaction_id,
+                request,
+            } => {
+                self.transactions.insert(
+                    *transaction_id,
+                    TransactionRecord {
+                        id: *transaction_id,
+                        charge_point_id: charge_point_id.clone(),
+                        connector_id: request.connector_id,
+                        id_tag: request.id_tag.as_str().to_owned(),
+                        reservation_id: request.reservation_id,
+                        meter_start: request.meter_start,
+                        started_at: request.timestamp,
+                        meter_values: Vec::new(),
+                        stop: None,
+                    },
+                );
+            }
+
+            Event::TransactionStopped(request) => {
+                if let Some(transaction) = self.transactions.get_mut(&request.transaction_id) {
+                    if let Some(transaction_data) = &request.transaction_data {
+                        transaction
+                            .meter_values
+                            .extend(transaction_data.iter().map(|data| {
+                                MeterValue::builder()
+                                    .sampled_value(data.sampled_value.clone())
+                                    .timestamp(data.timestamp)
+                                    .build()
+                            }));
+                    }
+
+                    transaction.stop = Some(TransactionStopRecord {
+                        id_tag: request.id_tag.as_deref().map(str::to_owned),
+                        meter_stop: request.meter_stop,
+                        stopped_at: request.timestamp,
+                        reason: request.reason.unwrap_or(StopTransactionReason::Local),
+                    });
+                }
+            }
+
+            Event::MeterValues(request) => {
+                let transaction = request
+                    .transaction_id
+                    .and_then(|transaction_id| self.transactions.get_mut(&transaction_id));
+
+                match transaction {
+                    Some(transaction) => transaction
+                        .meter_values
+                        .extend(request.meter_value.iter().cloned()),
+                    None => self
+                        .meter_values
+                        .entry((charge_point_id.clone(), request.connector_id))
+                        .or_default()
+                        .extend(request.meter_value.iter().cloned()),
+                }
+            }
+        }
+    }
+}
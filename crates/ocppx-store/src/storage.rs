@@ -0,0 +1,242 @@
+use crate::{ChargePointRecord, ConnectorRecord, Entry, Result, TransactionRecord};
+use ocppx_types::v1_6::MeterValue;
+use std::future::Future;
+
+/// Where the [`Entry`]s are recorded, and the state of the Charge Points
+/// is read from.
+pub trait Storage: Send + Sync + 'static {
+    /// Record `entry`, and update the state accordingly.
+    fn record(&self, entry: Entry) -> impl Future<Output = Result<()>> + Send;
+
+    /// All the Charge Points, sorted by ID.
+    fn charge_points(&self) -> impl Future<Output = Result<Vec<ChargePointRecord>>> + Send;
+
+    fn charge_point(
+        &self,
+        charge_point_id: &str,
+    ) -> impl Future<Output = Result<Option<ChargePointRecord>>> + Send;
+
+    /// The connectors of `charge_point_id`, sorted by ID.
+    fn connectors(
+        &self,
+        charge_point_id: &str,
+    ) -> impl Future<Output = Result<Vec<ConnectorRecord>>> + Send;
+
+    fn transaction(
+        &self,
+        transaction_id: i32,
+    ) -> impl Future<Output = Result<Option<TransactionRecord>>> + Send;
+
+    /// The transactions of `charge_point_id`, ongoing or stopped, sorted by
+    /// ID.
+    fn transactions(
+        &self,
+        charge_point_id: &str,
+    ) -> impl Future<Output = Result<Vec<TransactionRecord>>> + Send;
+
+    /// The meter values sent by `charge_point_id` for `connector_id`
+    /// outside of a transaction. The meter values of a transaction are in
+    /// its [`TransactionRecord`].
+    fn meter_values(
+        &self,
+        charge_point_id: &str,
+        connector_id: i32,
+    ) -> impl Future<Output = Result<Vec<MeterValue>>> + Send;
+}
+
+/// The behaviour shared by all the [`Storage`]s, checked on each of them.
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::Event;
+    use chrono::{DateTime, Utc};
+    use ocppx_types::v1_6::{
+        BootNotificationRequest, MeterValuesRequest, StartTransactionRequest,
+        StatusNotificationRequest, StatusNotificationStatus, StopTransactionRequest,
+    };
+    use serde_json::{from_value, json, Value};
+
+    fn entry(charge_point_id: &str, timestamp: &str, event: Event) -> Entry {
+        Entry {
+            timestamp: timestamp.parse().unwrap(),
+            charge_point_id: charge_point_id.to_owned(),
+            event,
+        }
+    }
+
+    fn booted(vendor: &str) -> Event {
+        Event::Booted(
+            from_value::<BootNotificationRequest>(json!({
+                "chargePointVendor": vendor,
+                "chargePointModel": "SingleSocketCharger",
+            }))
+            .unwrap(),
+        )
+    }
+
+    fn connector_status(connector_id: i32, status: &str) -> Event {
+        Event::ConnectorStatus(
+            from_value::<StatusNotificationRequest>(json!({
+                "connectorId": connector_id,
+                "errorCode": "NoError",
+                "status": status,
+            }))
+            .unwrap(),
+        )
+    }
+
+    fn transaction_started(transaction_id: i32, connector_id: i32) -> Event {
+        Event::TransactionStarted {
+            transaction_id,
+            request: from_value::<StartTransactionRequest>(json!({
+                "connectorId": connector_id,
+                "idTag": "ABC",
+                "meterStart": 100,
+                "timestamp": "2013-02-01T20:53:32.486Z",
+            }))
+            .unwrap(),
+        }
+    }
+
+    fn meter_values(connector_id: i32, transaction_id: Option<i32>, value: &str) -> Event {
+        Event::MeterValues(
+            from_value::<MeterValuesRequest>(json!({
+                "connectorId": connector_id,
+                "transactionId": transaction_id,
+                "meterValue": [{
+                    "timestamp": "2013-02-01T21:00:00Z",
+                    "sampledValue": [{ "value": value }],
+                }],
+            }))
+            .unwrap(),
+        )
+    }
+
+    fn transaction_stopped(transaction_id: i32) -> Event {
+        Event::TransactionStopped(
+            from_value::<StopTransactionRequest>(json!({
+                "transactionId": transaction_id,
+                "meterStop": 2100,
+                "timestamp": "2013-02-01T22:00:00Z",
+            }))
+            .unwrap(),
+        )
+    }
+
+    /// Record the entries of two Charge Points in the empty `storage`, and
+    /// check how they are updated, read and listed.
+    pub(crate) async fn test_storage<S>(storage: &S)
+    where
+        S: Storage,
+    {
+        for entry in [
+            entry("CP002", "2024-01-01T00:00:00Z", booted("VendorY")),
+            entry("CP001", "2024-01-01T00:01:00Z", booted("VendorX")),
+            entry(
+                "CP001",
+                "2024-01-01T00:02:00Z",
+                connector_status(2, "Available"),
+            ),
+            entry(
+                "CP001",
+                "2024-01-01T00:03:00Z",
+                connector_status(1, "Available"),
+            ),
+            // The status of the connector 1 is updated.
+            entry(
+                "CP001",
+                "2024-01-01T00:04:00Z",
+                connector_status(1, "Charging"),
+            ),
+            entry("CP001", "2024-01-01T00:05:00Z", transaction_started(42, 1)),
+            entry("CP001", "2024-01-01T00:06:00Z", transaction_started(41, 2)),
+            entry(
+                "CP001",
+                "2024-01-01T00:07:00Z",
+                meter_values(1, Some(42), "1100"),
+            ),
+            entry("CP001", "2024-01-01T00:08:00Z", meter_values(3, None, "7")),
+            entry("CP001", "2024-01-01T00:09:00Z", transaction_stopped(42)),
+            // An unknown transaction is ignored.
+            entry("CP001", "2024-01-01T00:10:00Z", transaction_stopped(7)),
+            // The boot information is updated.
+            entry("CP001", "2024-01-01T00:11:00Z", booted("VendorZ")),
+        ] {
+            storage.record(entry).await.unwrap();
+        }
+
+        let charge_points = storage.charge_points().await.unwrap();
+        assert_eq!(
+            charge_points
+                .iter()
+                .map(|charge_point| charge_point.id.as_str())
+                .collect::<Vec<_>>(),
+            ["CP001", "CP002"]
+        );
+
+        let charge_point = storage.charge_point("CP001").await.unwrap().unwrap();
+        assert_eq!(charge_point.boot.unwrap().charge_point_vendor, "VendorZ");
+        assert_eq!(
+            charge_point.first_seen,
+            "2024-01-01T00:01:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(
+            charge_point.last_seen,
+            "2024-01-01T00:11:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(charge_point.booted_at, Some(charge_point.last_seen));
+        assert!(storage.charge_point("CP003").await.unwrap().is_none());
+
+        let connectors = storage.connectors("CP001").await.unwrap();
+        assert_eq!(
+            connectors
+                .iter()
+                .map(|connector| (connector.connector_id, connector.status))
+                .collect::<Vec<_>>(),
+            [
+                (1, StatusNotificationStatus::Charging),
+                (2, StatusNotificationStatus::Available)
+            ]
+        );
+        assert!(storage.connectors("CP002").await.unwrap().is_empty());
+
+        let transactions = storage.transactions("CP001").await.unwrap();
+        assert_eq!(
+            transactions
+                .iter()
+                .map(|transaction| (transaction.id, transaction.is_active()))
+                .collect::<Vec<_>>(),
+            [(41, true), (42, false)]
+        );
+        let transaction = storage.transaction(42).await.unwrap().unwrap();
+        assert_eq!(transaction.meter_values.len(), 1);
+        assert_eq!(transaction.stop.unwrap().meter_stop, 2100);
+        assert!(storage.transaction(7).await.unwrap().is_none());
+        assert!(storage.transactions("CP002").await.unwrap().is_empty());
+
+        assert_eq!(storage.meter_values("CP001", 3).await.unwrap().len(), 1);
+        assert!(storage.meter_values("CP001", 1).await.unwrap().is_empty());
+    }
+
+    /// The whole state of `storage`, to compare it before and after a
+    /// restart.
+    pub(crate) async fn state<S>(storage: &S) -> Value
+    where
+        S: Storage,
+    {
+        let mut state = Vec::new();
+
+        for charge_point in storage.charge_points().await.unwrap() {
+            let id = charge_point.id.clone();
+
+            state.push(json!({
+                "chargePoint": charge_point,
+                "connectors": storage.connectors(&id).await.unwrap(),
+                "transactions": storage.transactions(&id).await.unwrap(),
+                "meterValues": storage.meter_values(&id, 3).await.unwrap(),
+            }));
+        }
+
+        Value::Array(state)
+    }
+}
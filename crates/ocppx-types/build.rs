@@ -2,8 +2,7 @@ use case::CaseExt;
 use lazy_static::lazy_static;
 use serde::Deserialize;
 use std::{
-    borrow::Cow,
-    collections::HashMap,
+    collections::BTreeMap,
     env, fs, io,
     io::Write as _,
     path::{Path, PathBuf},
@@ -12,6 +11,8 @@ use thiserror::Error;
 
 fn main() -> Result<()> {
     generate_schemas_for_version(Version::V1_6)?;
+    generate_schemas_for_version(Version::V1_6Security)?;
+    generate_schemas_for_version(Version::V2_0_1)?;
 
     Ok(())
 }
@@ -58,34 +59,623 @@ enum Error {
         schema_path: PathBuf,
     },
 
-    #[error("other unknown error")]
-    Other,
+    #[error("cannot apply an overlay: no `{after}` in an array at `{pointer}` in `{schema_path}`")]
+    SchemaOverlayNotApplicable {
+        pointer: &'static str,
+        after: &'static str,
+        schema_path: PathBuf,
+    },
+
+    #[error("no response schema for the `{action}` action")]
+    ResponseSchemaNotFound { action: String },
+
+    #[error("enum `{name}` has different variants in `{schema_path}`")]
+    ConflictingEnums { name: String, schema_path: PathBuf },
+
+    #[error("schema reference not found: `{reference}` in `{schema_path}`")]
+    SchemaReferenceNotFound {
+        reference: String,
+        schema_path: PathBuf,
+    },
+}
+
+/// Options of the code generator, set by the features of this crate.
+struct Options {
+    /// Represent the `number`s as `rust_decimal::Decimal` instead of
+    /// `f64`. Enabled by the `decimal` feature.
+    decimal: bool,
+    /// Keep the properties not in the schemas, e.g. vendor fields, in an
+    /// `extra` field of the structs. Enabled by the `extra-fields` feature.
+    extra_fields: bool,
+    /// Generate the protobuf definitions of the types too. Enabled by the
+    /// `protobuf` feature.
+    protobuf: bool,
+    /// Generate the TypeScript definitions of the types too. Enabled by the
+    /// `typescript` feature.
+    typescript: bool,
+    /// Represent the [`BoundedString`][bounded_string_capacity]s as
+    /// `arrayvec::ArrayString`s, which enforce their maximum length
+    /// already. Enabled by the `inline-strings` feature.
+    inline_strings: bool,
+}
+
+impl Options {
+    fn from_features() -> Self {
+        Self {
+            decimal: env::var_os("CARGO_FEATURE_DECIMAL").is_some(),
+            extra_fields: env::var_os("CARGO_FEATURE_EXTRA_FIELDS").is_some(),
+            protobuf: env::var_os("CARGO_FEATURE_PROTOBUF").is_some(),
+            typescript: env::var_os("CARGO_FEATURE_TYPESCRIPT").is_some(),
+            inline_strings: env::var_os("CARGO_FEATURE_INLINE_STRINGS").is_some(),
+        }
+    }
+}
+
+lazy_static! {
+    static ref OPTIONS: Options = Options::from_features();
+}
+
+/// The longest strings represented as `BoundedString`s, e.g. `idTag` (20)
+/// or `chargePointVendor` (20), but not `data` (unbounded). The longer ones
+/// stay `String`s, not to bloat the structs with [`Options::inline_strings`].
+const BOUNDED_STRING_MAX_LENGTH: u32 = 50;
+
+/// A local change to a vendored schema. The vendored schemas stay
+/// byte-identical to the published ones: the overlays are applied when they
+/// are read, and the changed schemas are written in `OUT_DIR` to be embedded
+/// for the validation.
+struct SchemaOverlay {
+    /// The path of the schema, relative to the crate.
+    path: &'static str,
+    /// The JSON pointer of an array in the schema.
+    pointer: &'static str,
+    /// The value of the array after which `value` is inserted.
+    after: &'static str,
+    /// The value inserted in the array.
+    value: &'static str,
+}
+
+const SCHEMA_OVERLAYS: &[SchemaOverlay] = &[
+    // The units of StopTransaction only have the misspelled `Celcius`, the
+    // ones of MeterValues also have `Celsius` right after it: insert it the
+    // same way, so that both share the same enum.
+    SchemaOverlay {
+        path: "schemas/v1.6/StopTransaction.json",
+        pointer:
+            "/properties/transactionData/items/properties/sampledValue/items/properties/unit/enum",
+        after: "Celcius",
+        value: "Celsius",
+    },
+];
+
+/// The overlay of a schema, if any.
+fn schema_overlay(schema_path: &Path) -> Option<&'static SchemaOverlay> {
+    SCHEMA_OVERLAYS
+        .iter()
+        .find(|overlay| schema_path.ends_with(overlay.path))
+}
+
+/// The expression embedding a schema, from `OUT_DIR` if it has an overlay.
+fn include_schema(schema_path: &str) -> String {
+    let directory = if schema_overlay(Path::new(schema_path)).is_some() {
+        "OUT_DIR"
+    } else {
+        "CARGO_MANIFEST_DIR"
+    };
+
+    format!("include_str!(concat!(env!(\"{directory}\"), \"/{schema_path}\"))")
 }
 
+/// The OCPP 1.6 feature profiles, each one being a Cargo feature of this
+/// crate, with their actions.
+const V1_6_PROFILES: &[(&str, &[&str])] = &[
+    (
+        "core",
+        &[
+            "Authorize",
+            "BootNotification",
+            "ChangeAvailability",
+            "ChangeConfiguration",
+            "ClearCache",
+            "DataTransfer",
+            "GetConfiguration",
+            "Heartbeat",
+            "MeterValues",
+            "RemoteStartTransaction",
+            "RemoteStopTransaction",
+            "Reset",
+            "StartTransaction",
+            "StatusNotification",
+            "StopTransaction",
+            "UnlockConnector",
+        ],
+    ),
+    (
+        "firmware-management",
+        &[
+            "DiagnosticsStatusNotification",
+            "FirmwareStatusNotification",
+            "GetDiagnostics",
+            "UpdateFirmware",
+        ],
+    ),
+    (
+        "local-auth-list-management",
+        &["GetLocalListVersion", "SendLocalList"],
+    ),
+    ("reservation", &["CancelReservation", "ReserveNow"]),
+    (
+        "smart-charging",
+        &[
+            "ClearChargingProfile",
+            "GetCompositeSchedule",
+            "SetChargingProfile",
+        ],
+    ),
+    ("remote-trigger", &["TriggerMessage"]),
+];
+
 enum Version {
     V1_6,
+    /// The messages added to OCPP 1.6 by the Security Whitepaper.
+    V1_6Security,
+    V2_0_1,
 }
 
 impl Version {
     fn to_str(&self) -> &'static str {
         match self {
             Self::V1_6 => "v1.6",
+            Self::V1_6Security => "v1.6-security",
+            Self::V2_0_1 => "v2.0.1",
         }
     }
 
     fn to_name(&self) -> &'static str {
         match self {
             Self::V1_6 => "v1_6",
+            Self::V1_6Security => "v1_6_security",
+            Self::V2_0_1 => "v2_0_1",
         }
     }
+
+    /// Whether the messages of `action` must be generated, i.e. whether
+    /// the Cargo feature of their profile is enabled. Only OCPP 1.6 has
+    /// profiles.
+    fn is_enabled(&self, action: &str) -> bool {
+        let profiles = match self {
+            Self::V1_6 => V1_6_PROFILES,
+            Self::V1_6Security | Self::V2_0_1 => return true,
+        };
+
+        profiles
+            .iter()
+            .find(|(_, actions)| actions.contains(&action))
+            .is_none_or(|(feature, _)| {
+                env::var_os(format!(
+                    "CARGO_FEATURE_{feature}",
+                    feature = feature.to_uppercase().replace('-', "_")
+                ))
+                .is_some()
+            })
+    }
+}
+
+/// The Rust items compiled from the schemas of a version.
+#[derive(Default)]
+struct CompiledSchemas {
+    /// Whether the bounded strings are the `CiString`s of OCPP 1.6, see
+    /// [`ci_string_type`].
+    ci_strings: bool,
+    /// The structs, by name.
+    structs: BTreeMap<String, String>,
+    /// The enums, by name. They are compiled once all the schemas are
+    /// read, so that identical enums are deduplicated.
+    enums: BTreeMap<String, CompiledEnum>,
+    /// The regular expressions of the `pattern`s, by name of their static.
+    patterns: BTreeMap<String, String>,
+    /// The fields of the structs, by name, for the protobuf messages and
+    /// the TypeScript interfaces.
+    messages: BTreeMap<String, Vec<MessageField>>,
+}
+
+struct CompiledEnum {
+    description: Option<String>,
+    variants: Vec<String>,
+}
+
+/// A field of a struct, with its Rust type.
+struct MessageField {
+    name: String,
+    /// The name of the property, on the wire.
+    raw_name: String,
+    description: Option<String>,
+    ty: String,
+    required: bool,
+}
+
+impl CompiledSchemas {
+    fn into_items(self) -> Vec<String> {
+        // Enums with the same variants, in the same order, share a single
+        // type: the first one by name, and the others are aliases.
+        let mut shared_enums = BTreeMap::<&[String], Vec<(&str, &CompiledEnum)>>::new();
+
+        for (name, compiled_enum) in &self.enums {
+            shared_enums
+                .entry(&compiled_enum.variants)
+                .or_default()
+                .push((name, compiled_enum));
+        }
+
+        let arbitrary_structs = self
+            .messages
+            .iter()
+            .map(|(name, fields)| compile_arbitrary_struct(name, fields))
+            .collect::<Vec<_>>();
+
+        self.patterns
+            .iter()
+            .map(|(name, pattern)| {
+                format!(
+                    "#[cfg(feature = \"std\")]\nstatic {name}: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| regex::Regex::new({pattern:?}).unwrap());"
+                )
+            })
+            .chain(self.structs.values().cloned())
+            .chain(arbitrary_structs)
+            .chain(shared_enums.values().map(|enums| {
+                let (name, compiled_enum) = enums[0];
+                let aliases = enums[1..]
+                    .iter()
+                    .map(|(alias, _)| *alias)
+                    .collect::<Vec<_>>();
+
+                compile_enum_items(name, compiled_enum, &aliases)
+            }))
+            .collect()
+    }
+
+    /// Compile the structs and the enums into protobuf messages and enums,
+    /// with the `Request` and `Response` messages holding any payload.
+    ///
+    /// The fields are numbered in the alphabetical order of their names,
+    /// like the properties of the schemas, so that the numbers only change
+    /// with the schemas.
+    fn to_proto(&self, version: &Version, actions: &[(&str, &str, &str)]) -> String {
+        let mut output = format!(
+            "// Generated by `ocppx-types` from the OCPP {version} JSON schemas.\n\nsyntax = \"proto3\";\n\npackage ocppx.{package};\n\nimport \"google/protobuf/struct.proto\";\nimport \"google/protobuf/timestamp.proto\";\n",
+            version = version.to_str().trim_start_matches('v'),
+            package = version.to_name(),
+        );
+
+        for (name, fields) in &self.messages {
+            let fields = fields
+                .iter()
+                .enumerate()
+                .map(|(index, field)| {
+                    let (repeated, ty) = match field.ty.strip_prefix("Vec<") {
+                        Some(item_ty) => (true, proto_type(item_ty.trim_end_matches('>'))),
+                        None => (false, proto_type(&field.ty)),
+                    };
+                    let label = match (repeated, field.required) {
+                        (true, _) => "repeated ",
+                        (false, true) => "",
+                        (false, false) => "optional ",
+                    };
+
+                    format!(
+                        "  {label}{ty} {name} = {number};\n",
+                        name = field.name,
+                        number = index + 1
+                    )
+                })
+                .collect::<String>();
+
+            output.push_str(&format!("\nmessage {name} {{\n{fields}}}\n"));
+        }
+
+        lazy_static! {
+            static ref NOT_ID: regex::Regex = regex::Regex::new("[^A-Za-z0-9]").unwrap();
+        }
+
+        // The values of the enums are in the scope of the package, hence
+        // prefixed by the name of their enum, and the first one must be 0.
+        for (name, compiled_enum) in &self.enums {
+            let prefix = screaming_snake(name);
+            let values = compiled_enum
+                .variants
+                .iter()
+                .enumerate()
+                .map(|(index, variant)| {
+                    format!(
+                        "  {prefix}_{value} = {number}; // `{variant}` on the wire.\n",
+                        value = screaming_snake(&NOT_ID.replace_all(&variant.to_camel(), "")),
+                        number = index + 1,
+                    )
+                })
+                .collect::<String>();
+
+            output.push_str(&format!(
+                "\nenum {name} {{\n  {prefix}_UNSPECIFIED = 0;\n{values}}}\n"
+            ));
+        }
+
+        for kind in ["Request", "Response"] {
+            let payloads = actions
+                .iter()
+                .enumerate()
+                .map(|(index, (action, _, _))| {
+                    format!(
+                        "    {action}{kind} {name} = {number};\n",
+                        name = action.to_snake(),
+                        number = index + 1
+                    )
+                })
+                .collect::<String>();
+
+            output.push_str(&format!(
+                "\n// Any {kind_lowercase} payload, tagged by its action.\nmessage {kind} {{\n  oneof payload {{\n{payloads}  }}\n}}\n",
+                kind_lowercase = kind.to_lowercase(),
+            ));
+        }
+
+        output
+    }
+
+    /// Compile the messages of [`Self::to_proto`] into the
+    /// `PROTO_MESSAGES` and `PROTO_ACTIONS` statics, to transcode the
+    /// payloads at runtime.
+    fn to_proto_descriptors(&self, actions: &[(&str, &str, &str)]) -> String {
+        let messages = self
+            .messages
+            .iter()
+            .map(|(name, fields)| {
+                let fields = fields
+                    .iter()
+                    .enumerate()
+                    .map(|(index, field)| {
+                        let (repeated, ty) = match field.ty.strip_prefix("Vec<") {
+                            Some(item_ty) => (true, item_ty.trim_end_matches('>')),
+                            None => (false, field.ty.as_str()),
+                        };
+
+                        format!(
+                            "crate::ProtoField {{ number: {number}, json_name: {json_name:?}, ty: {ty}, repeated: {repeated}, required: {required} }}",
+                            number = index + 1,
+                            json_name = field.raw_name,
+                            ty = self.proto_descriptor_type(ty),
+                            required = field.required,
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                format!("crate::ProtoMessage {{ name: {name:?}, fields: &[{fields}] }}")
+            })
+            .collect::<Vec<_>>()
+            .join(",\n    ");
+        let actions = actions
+            .iter()
+            .map(|(action, _, _)| format!("{action:?}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!(
+            "/// The messages of [`PROTO`], to transcode the payloads between JSON\n/// and protobuf.\npub static PROTO_MESSAGES: &[crate::ProtoMessage] = &[\n    {messages}\n];\n\n/// The actions of the `Request` and `Response` messages of [`PROTO`]:\n/// the payload of the action `n` is the field `n + 1`.\npub static PROTO_ACTIONS: &[&str] = &[{actions}];\n"
+        )
+    }
+
+    /// The `crate::ProtoType` of a Rust type generated from a schema, see
+    /// [`proto_type`].
+    fn proto_descriptor_type(&self, ty: &str) -> String {
+        match ty {
+            "String" | "crate::Url" | "crate::IdTag" => "crate::ProtoType::String".to_owned(),
+            ty if ty.starts_with("crate::BoundedString<") || ty.starts_with("crate::CiString") => {
+                "crate::ProtoType::String".to_owned()
+            }
+            "bool" => "crate::ProtoType::Bool".to_owned(),
+            "i32" => "crate::ProtoType::Int32".to_owned(),
+            "i64" => "crate::ProtoType::Int64".to_owned(),
+            "f64" => "crate::ProtoType::Double".to_owned(),
+            "rust_decimal::Decimal" => "crate::ProtoType::Decimal".to_owned(),
+            "crate::DateTime" => "crate::ProtoType::Timestamp".to_owned(),
+            "serde_json::Value" => "crate::ProtoType::Value".to_owned(),
+            ty if self.messages.contains_key(ty) => format!("crate::ProtoType::Message({ty:?})"),
+            ty => match self.enums.get(ty) {
+                Some(compiled_enum) => {
+                    format!("crate::ProtoType::Enum(&{:?})", compiled_enum.variants)
+                }
+                None => panic!("no protobuf type for `{ty}`"),
+            },
+        }
+    }
+
+    /// Compile the structs and the enums into TypeScript interfaces and
+    /// string unions, describing the payloads as they are on the wire, e.g.
+    /// for a Tauri frontend. The `Requests` and `Responses` interfaces map
+    /// the actions to their payloads.
+    fn to_typescript(&self, version: &Version, actions: &[(&str, &str, &str)]) -> String {
+        let mut output = format!(
+            "// Generated by `ocppx-types` from the OCPP {version} JSON schemas.\n",
+            version = version.to_str().trim_start_matches('v'),
+        );
+
+        for (name, fields) in &self.messages {
+            let mut properties = fields
+                .iter()
+                .map(|field| {
+                    format!(
+                        "{doc}  {raw_name}{question_mark}: {ty};\n",
+                        doc = typescript_doc_comment(field.description.as_deref(), "  "),
+                        raw_name = field.raw_name,
+                        question_mark = if field.required { "" } else { "?" },
+                        ty = typescript_type(&field.ty),
+                    )
+                })
+                .collect::<String>();
+
+            if OPTIONS.extra_fields {
+                properties.push_str("  [property: string]: unknown;\n");
+            }
+
+            output.push_str(&format!("\nexport interface {name} {{\n{properties}}}\n"));
+        }
+
+        for (name, compiled_enum) in &self.enums {
+            let variants = compiled_enum
+                .variants
+                .iter()
+                .map(|variant| format!("{variant:?}"))
+                .collect::<Vec<_>>()
+                .join(" | ");
+
+            output.push_str(&format!(
+                "\n{doc}export type {name} = {variants};\n",
+                doc = typescript_doc_comment(compiled_enum.description.as_deref(), ""),
+            ));
+        }
+
+        output.push_str(&format!(
+            "\n/** The action of a `Call`. */\nexport type Action = {actions};\n",
+            actions = actions
+                .iter()
+                .map(|(action, _, _)| format!("{action:?}"))
+                .collect::<Vec<_>>()
+                .join(" | "),
+        ));
+
+        for kind in ["Request", "Response"] {
+            let payloads = actions
+                .iter()
+                .map(|(action, _, _)| format!("  {action}: {action}{kind};\n"))
+                .collect::<String>();
+
+            output.push_str(&format!(
+                "\n/** The {kind_lowercase} payload of each action. */\nexport interface {kind}s {{\n{payloads}}}\n",
+                kind_lowercase = kind.to_lowercase(),
+            ));
+        }
+
+        output
+    }
+}
+
+/// The TypeScript type of a Rust type generated from a schema, as
+/// serialized by `serde_json`.
+fn typescript_type(ty: &str) -> String {
+    if let Some(item_ty) = ty.strip_prefix("Vec<") {
+        return format!("{}[]", typescript_type(item_ty.trim_end_matches('>')));
+    }
+
+    match ty {
+        "String" | "crate::Url" | "crate::IdTag" | "crate::DateTime" => "string",
+        ty if ty.starts_with("crate::BoundedString<") || ty.starts_with("crate::CiString") => {
+            "string"
+        }
+        "bool" => "boolean",
+        // The decimals are serialized as floats.
+        "i32" | "i64" | "f64" | "rust_decimal::Decimal" => "number",
+        "serde_json::Value" => "unknown",
+        // An interface, or an enum.
+        ty => ty,
+    }
+    .to_owned()
+}
+
+/// The JSDoc comment of a TypeScript item, indented by `indentation`,
+/// like [`compile_doc_comment`].
+fn typescript_doc_comment(description: Option<&str>, indentation: &str) -> String {
+    let lines = description
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<_>>();
+    let lines = match lines.iter().position(|line| line.starts_with("urn:")) {
+        Some(urn) => &lines[urn + 1..],
+        None => &lines[..],
+    };
+    let lines = lines
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.replace("*/", "*\\/"))
+        .collect::<Vec<_>>();
+
+    match lines.as_slice() {
+        [] => String::new(),
+        [line] => format!("{indentation}/** {line} */\n"),
+        lines => format!(
+            "{indentation}/**\n{lines}{indentation} */\n",
+            lines = lines
+                .iter()
+                .map(|line| format!("{indentation} * {line}\n"))
+                .collect::<String>(),
+        ),
+    }
+}
+
+/// `EVCommunicationError` becomes `EV_COMMUNICATION_ERROR`: the acronyms
+/// are kept together, as opposed to `to_snake`.
+fn screaming_snake(ident: &str) -> String {
+    let chars = ident.chars().collect::<Vec<_>>();
+    let mut output = String::new();
+
+    for (index, char) in chars.iter().enumerate() {
+        let previous = index.checked_sub(1).map(|index| chars[index]);
+        let next = chars.get(index + 1);
+
+        if char.is_uppercase()
+            && previous.is_some_and(|previous| {
+                !previous.is_uppercase() || next.is_some_and(|next| next.is_lowercase())
+            })
+        {
+            output.push('_');
+        }
+
+        output.push(char.to_ascii_uppercase());
+    }
+
+    output
+}
+
+/// The protobuf type of a Rust type generated from a schema.
+fn proto_type(ty: &str) -> &str {
+    match ty {
+        "String" | "crate::Url" | "crate::IdTag" => "string",
+        ty if ty.starts_with("crate::BoundedString<") || ty.starts_with("crate::CiString") => {
+            "string"
+        }
+        "bool" => "bool",
+        "i32" => "int32",
+        "i64" => "int64",
+        "f64" => "double",
+        // Decimals would lose their precision as `double`s.
+        "rust_decimal::Decimal" => "string",
+        "crate::DateTime" => "google.protobuf.Timestamp",
+        "serde_json::Value" => "google.protobuf.Value",
+        // A message, or an enum.
+        ty => ty,
+    }
+}
+
+/// Strip the `Request` or `Response` suffix of a schema name, e.g.
+/// `BootNotificationResponse` becomes `BootNotification`.
+fn strip_message_kind(name: &str) -> &str {
+    name.strip_suffix("Request")
+        .or_else(|| name.strip_suffix("Response"))
+        .unwrap_or(name)
 }
 
 fn generate_schemas_for_version(version: Version) -> Result<()> {
     let root = Path::new(env!("CARGO_MANIFEST_DIR"));
 
-    let mut compiled_schemas = HashMap::<String, String>::new();
+    let mut compiled_schemas = CompiledSchemas {
+        ci_strings: matches!(version, Version::V1_6),
+        ..CompiledSchemas::default()
+    };
+    let mut schema_paths = BTreeMap::new();
 
-    for schema in fs::read_dir(root.join("schemas").join(version.to_str()))
+    // The schemas are read in order, so that the output is the same from
+    // one build to another.
+    let mut schemas = fs::read_dir(root.join("schemas").join(version.to_str()))
         .map_err(Error::SchemasNotFound)?
         .filter_map(|entry| match entry {
             Ok(entry) if entry.file_type().expect("Cannot read file type").is_file() => {
@@ -101,8 +691,83 @@ fn generate_schemas_for_version(version: Version) -> Result<()> {
             }
             _ => None,
         })
-    {
-        generate_schema(schema, &mut compiled_schemas)?;
+        .collect::<Vec<_>>();
+    schemas.sort();
+
+    for schema in schemas {
+        if let Some(name) = generate_schema(&version, schema.clone(), &mut compiled_schemas)? {
+            // The path is relative to the crate, so that the output does
+            // not depend on where the crate is.
+            let relative_path = schema
+                .strip_prefix(root)
+                .unwrap_or(&schema)
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            schema_paths.insert(name, relative_path);
+        }
+    }
+
+    let actions = schema_paths
+        .iter()
+        .filter_map(|(name, request_path)| Some((name.strip_suffix("Request")?, request_path)))
+        .map(|(action, request_path)| {
+            if let Some(response_path) = schema_paths.get(&format!("{action}Response")) {
+                Ok((action, request_path.as_str(), response_path.as_str()))
+            } else {
+                Err(Error::ResponseSchemaNotFound {
+                    action: action.to_owned(),
+                })
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if OPTIONS.protobuf {
+        let mut proto_path = PathBuf::from(env::var("OUT_DIR").unwrap());
+        proto_path.push(format!("{version}.proto", version = version.to_name()));
+
+        fs::write(&proto_path, compiled_schemas.to_proto(&version, &actions))
+            .map_err(Error::CompiledSchemaCannotBeSaved)?;
+
+        println!(
+            "cargo:rustc-env=OCPPX_TYPES_PROTO_{suffix}={value}",
+            suffix = version.to_name().to_camel(),
+            value = proto_path.as_path().display(),
+        );
+
+        let mut descriptors_path = PathBuf::from(env::var("OUT_DIR").unwrap());
+        descriptors_path.push(format!("{version}_proto.rs", version = version.to_name()));
+
+        fs::write(
+            &descriptors_path,
+            compiled_schemas.to_proto_descriptors(&actions),
+        )
+        .map_err(Error::CompiledSchemaCannotBeSaved)?;
+
+        println!(
+            "cargo:rustc-env=OCPPX_TYPES_PROTO_DESCRIPTORS_{suffix}={value}",
+            suffix = version.to_name().to_camel(),
+            value = descriptors_path.as_path().display(),
+        );
+    }
+
+    if OPTIONS.typescript {
+        let mut typescript_path = PathBuf::from(env::var("OUT_DIR").unwrap());
+        typescript_path.push(format!("{version}.d.ts", version = version.to_name()));
+
+        fs::write(
+            &typescript_path,
+            compiled_schemas.to_typescript(&version, &actions),
+        )
+        .map_err(Error::CompiledSchemaCannotBeSaved)?;
+
+        println!(
+            "cargo:rustc-env=OCPPX_TYPES_TYPESCRIPT_{suffix}={value}",
+            suffix = version.to_name().to_camel(),
+            value = typescript_path.as_path().display(),
+        );
     }
 
     let mut into_file_path = PathBuf::from(env::var("OUT_DIR").unwrap());
@@ -116,14 +781,13 @@ fn generate_schemas_for_version(version: Version) -> Result<()> {
         .open(into_file_path.clone())
         .map_err(Error::CompiledSchemaCannotBeSaved)?;
 
+    let round_trips = compile_round_trips(&compiled_schemas);
+
     file.write_all(
         format!(
-            "use serde::{{Serialize, Deserialize}};\n\n{schemas}",
-            schemas = compiled_schemas
-                .values()
-                .map(Clone::clone)
-                .collect::<Vec<_>>()
-                .join("\n\n"),
+            "use serde::{{Serialize, Deserialize}};\n#[allow(unused_imports)]\nuse alloc::{{borrow::ToOwned, string::String, vec::Vec}};\n#[cfg(feature = \"std\")]\n#[allow(unused_imports)]\nuse validator::Validate as _;\n\n{schemas}\n\n{actions}\n\n{round_trips}",
+            schemas = compiled_schemas.into_items().join("\n\n"),
+            actions = compile_actions(&actions),
         )
         .as_bytes(),
     )
@@ -138,27 +802,77 @@ fn generate_schemas_for_version(version: Version) -> Result<()> {
     Ok(())
 }
 
+#[allow(dead_code)]
 #[derive(Deserialize, Debug)]
 struct Schema {
+    #[serde(alias = "$id")]
     id: String,
-    title: String,
+    title: Option<String>,
+    description: Option<String>,
     #[serde(rename = "type")]
     ty: SchemaPropertyType,
     properties: SchemaProperties,
     required: Option<Vec<String>>,
+    #[serde(default)]
+    definitions: SchemaProperties,
 }
 
-type SchemaProperties = HashMap<String, SchemaProperty>;
+impl Schema {
+    /// The schema name, i.e. its title if any, or the last segment of
+    /// its ID (e.g. `urn:OCPP:Cp:2:2020:3:BootNotificationRequest`).
+    fn name(&self) -> &str {
+        match &self.title {
+            Some(title) => title,
+            None => self.id.rsplit(':').next().unwrap_or(&self.id),
+        }
+    }
+
+    /// The schema description if any, or a description made from its
+    /// name, e.g. “Payload of the `BootNotification` request.”.
+    fn description(&self) -> Option<String> {
+        if self.description.is_some() {
+            return self.description.clone();
+        }
+
+        let name = self.name();
+
+        [("Request", "request"), ("Response", "response")]
+            .into_iter()
+            .find_map(|(suffix, kind)| {
+                let action = name.strip_suffix(suffix)?;
+
+                Some(format!("Payload of the `{action}` {kind}."))
+            })
+    }
+}
+
+type SchemaProperties = BTreeMap<String, SchemaProperty>;
 
 // Source: https://json-schema.org/draft/2020-12/json-schema-validation.html
+#[allow(dead_code)]
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 struct SchemaProperty {
     // Validation for Any Instance Type.
     #[serde(rename = "type")]
-    ty: SchemaPropertyType,
+    ty: Option<SchemaPropertyType>,
     r#enum: Option<Vec<String>>,
 
+    // Meta-Data Annotations.
+    description: Option<String>,
+
+    // Schema Re-Use With "$defs", see
+    // https://json-schema.org/draft/2020-12/json-schema-core.html#name-schema-re-use-with-defs.
+    #[serde(rename = "$ref")]
+    r#ref: Option<String>,
+
+    // Validation for Numeric Instances.
+    multiple_of: Option<f64>,
+    maximum: Option<f64>,
+    exclusive_maximum: Option<f64>,
+    minimum: Option<f64>,
+    exclusive_minimum: Option<f64>,
+
     // Validation for Strings.
     min_length: Option<u32>,
     max_length: Option<u32>,
@@ -181,6 +895,10 @@ struct SchemaProperty {
 
     // Vocabularies for Semantic Content
     format: Option<String>,
+
+    // Non-standard annotations, used by the OCPP 2.0.1 schemas to name
+    // the definitions.
+    java_type: Option<String>,
 }
 
 #[derive(Deserialize, Copy, Clone, Debug)]
@@ -195,246 +913,895 @@ enum SchemaPropertyType {
     Integer,
 }
 
+/// Generate the types of a schema, and return its name, or `None` if its
+/// action is disabled.
 fn generate_schema(
+    version: &Version,
     schema_path: PathBuf,
-    compiled_schemas: &mut HashMap<String, String>,
-) -> Result<()> {
+    compiled_schemas: &mut CompiledSchemas,
+) -> Result<Option<String>> {
     let schema = fs::read_to_string(&schema_path).map_err(|error| Error::SchemaNotFound {
         error,
         schema_path: schema_path.clone(),
     })?;
-    let schema: Schema =
+    let mut schema: serde_json::Value =
         serde_json::from_str(schema.as_str()).map_err(|error| Error::InvalidSchema {
             error,
             schema_path: schema_path.clone(),
         })?;
 
+    if let Some(overlay) = schema_overlay(&schema_path) {
+        let array = schema
+            .pointer_mut(overlay.pointer)
+            .and_then(serde_json::Value::as_array_mut);
+        let position = array.as_ref().and_then(|array| {
+            array
+                .iter()
+                .position(|value| value.as_str() == Some(overlay.after))
+        });
+
+        let (Some(array), Some(position)) = (array, position) else {
+            return Err(Error::SchemaOverlayNotApplicable {
+                pointer: overlay.pointer,
+                after: overlay.after,
+                schema_path,
+            });
+        };
+
+        array.insert(position + 1, overlay.value.into());
+
+        let mut overlaid_path = PathBuf::from(env::var("OUT_DIR").unwrap());
+        overlaid_path.push(overlay.path);
+
+        fs::create_dir_all(overlaid_path.parent().unwrap())
+            .and_then(|()| fs::write(&overlaid_path, schema.to_string()))
+            .map_err(Error::CompiledSchemaCannotBeSaved)?;
+    }
+
+    let schema: Schema = serde_json::from_value(schema).map_err(|error| Error::InvalidSchema {
+        error,
+        schema_path: schema_path.clone(),
+    })?;
+
+    if !version.is_enabled(strip_message_kind(schema.name())) {
+        return Ok(None);
+    }
+
     use SchemaPropertyType::*;
 
     match schema.ty {
         Object => compile_object(
-            &schema.title,
+            schema.name(),
+            schema.description().as_deref(),
             &schema.properties,
             if let Some(required) = &schema.required {
                 required
             } else {
                 &[]
             },
+            &schema.definitions,
             &schema_path,
             compiled_schemas,
         )?,
         ty => return Err(Error::SchemaTypeNotSupported { ty, schema_path }),
     }
 
-    Ok(())
+    Ok(Some(schema.name().to_camel()))
+}
+
+fn compile_actions(actions: &[(&str, &str, &str)]) -> String {
+    let variants = |f: &dyn Fn(&str) -> String| {
+        actions
+            .iter()
+            .map(|(action, _, _)| f(action))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let mut output = format!(
+        "/// The actions, i.e. the names of the request/response pairs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {{
+    {variants}
+}}
+
+impl Action {{
+    /// All the actions, in alphabetical order.
+    pub const VARIANTS: &'static [Self] = &[{variants_list}];
+
+    /// Iterate over all the actions, in alphabetical order.
+    pub fn iter() -> impl Iterator<Item = Self> {{
+        Self::VARIANTS.iter().copied()
+    }}
+
+    pub fn as_str(&self) -> &'static str {{
+        match *self {{
+            {as_str}
+        }}
+    }}
+
+    /// The JSON schema of the request payload.
+    pub fn request_schema(&self) -> &'static str {{
+        match *self {{
+            {request_schema}
+        }}
+    }}
+
+    /// The JSON schema of the response payload.
+    pub fn response_schema(&self) -> &'static str {{
+        match *self {{
+            {response_schema}
+        }}
+    }}
+}}
+
+/// Validate a request payload against the JSON schema of its action.
+#[cfg(feature = \"json-schema\")]
+pub fn validate(action: Action, payload: &serde_json::Value) -> Result<(), crate::ValidationError> {{
+    static VALIDATORS: crate::validation::Validators = crate::validation::Validators::new();
+
+    VALIDATORS.validate(action.as_str(), action.request_schema(), payload)
+}}
+
+/// Validate a response payload against the JSON schema of its action.
+#[cfg(feature = \"json-schema\")]
+pub fn validate_response(action: Action, payload: &serde_json::Value) -> Result<(), crate::ValidationError> {{
+    static VALIDATORS: crate::validation::Validators = crate::validation::Validators::new();
+
+    VALIDATORS.validate(action.as_str(), action.response_schema(), payload)
+}}
+
+impl core::str::FromStr for Action {{
+    type Err = crate::UnknownActionError;
+
+    // All the match arms diverge when every action is disabled.
+    #[allow(unreachable_code, clippy::match_single_binding)]
+    fn from_str(action: &str) -> Result<Self, Self::Err> {{
+        Ok(match action {{
+            {from_str}
+            _ => return Err(crate::UnknownActionError(action.to_owned())),
+        }})
+    }}
+}}
+
+impl core::fmt::Display for Action {{
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {{
+        formatter.write_str(self.as_str())
+    }}
+}}",
+        variants = variants(&|action| format!("{action},")),
+        variants_list = variants(&|action| format!("Self::{action},")),
+        as_str = variants(&|action| format!("Self::{action} => \"{action}\",")),
+        from_str = variants(&|action| format!("\"{action}\" => Self::{action},")),
+        request_schema = actions
+            .iter()
+            .map(|(action, request_path, _)| format!("Self::{action} => {},", include_schema(request_path)))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        response_schema = actions
+            .iter()
+            .map(|(action, _, response_path)| format!("Self::{action} => {},", include_schema(response_path)))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    );
+
+    for (action, _, _) in actions {
+        output.push_str(&format!(
+            "
+
+impl crate::OcppRequest for {action}Request {{
+    type Response = {action}Response;
+
+    const ACTION: &'static str = \"{action}\";
+}}"
+        ));
+    }
+
+    for kind in ["Request", "Response"] {
+        output.push_str(&format!(
+            "
+
+/// Any {kind_lowercase} payload, tagged by its action.
+#[derive(Debug, Clone)]
+pub enum {kind} {{
+    {variants}
+}}
+
+impl {kind} {{
+    pub fn action(&self) -> Action {{
+        match *self {{
+            {action}
+        }}
+    }}
+
+    /// Deserialize the {kind_lowercase} payload of a particular action.
+    #[allow(unreachable_code, unused_variables)]
+    pub fn from_payload(action: Action, payload: serde_json::Value) -> Result<Self, serde_json::Error> {{
+        Ok(match action {{
+            {from_payload}
+        }})
+    }}
+
+    /// Serialize the {kind_lowercase} payload.
+    pub fn to_payload(&self) -> Result<serde_json::Value, serde_json::Error> {{
+        match *self {{
+            {to_payload}
+        }}
+    }}
+}}",
+            kind_lowercase = kind.to_lowercase(),
+            variants = variants(&|action| format!("{action}({action}{kind}),")),
+            action = variants(&|action| format!("Self::{action}(_) => Action::{action},")),
+            from_payload = variants(&|action| format!(
+                "Action::{action} => Self::{action}(serde_json::from_value(payload)?),"
+            )),
+            to_payload = variants(&|action| format!(
+                "Self::{action}(ref payload) => serde_json::to_value(payload),"
+            )),
+        ));
+    }
+
+    output
+}
+
+/// Compile a description, followed by `extra_lines`, into `///` doc
+/// comments.
+///
+/// The descriptions of the OCPP 2.0.1 schemas start with the name of the
+/// element in the data dictionary, and its URN, e.g.
+/// `Charge_ Point\r\nurn:x-oca:ocpp:uid:2:233122\r\nThe physical system…`.
+/// Only what follows the URN is kept.
+fn compile_doc_comment<'a>(
+    description: Option<&str>,
+    extra_lines: impl IntoIterator<Item = &'a str>,
+) -> String {
+    let lines = description
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<_>>();
+    let lines = match lines.iter().position(|line| line.starts_with("urn:")) {
+        Some(urn) => &lines[urn + 1..],
+        None => &lines[..],
+    };
+
+    // Each line is a paragraph. Brackets, e.g. `[RFC5646]`, are not
+    // intra-doc links.
+    lines
+        .iter()
+        .map(|line| line.replace('[', "\\[").replace(']', "\\]"))
+        .chain(extra_lines.into_iter().map(ToOwned::to_owned))
+        .filter(|paragraph| !paragraph.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+        .lines()
+        .map(|line| {
+            format!(
+                "///{space}{line}\n",
+                space = if line.is_empty() { "" } else { " " }
+            )
+        })
+        .collect()
+}
+
+/// Describe the constraints of a property that are not expressed by its
+/// Rust type.
+fn compile_constraints(property: &SchemaProperty) -> Vec<String> {
+    let mut constraints = Vec::new();
+
+    match (&property.min_length, &property.max_length) {
+        (Some(min), Some(max)) => {
+            constraints.push(format!("Between {min} and {max} characters long."))
+        }
+        (None, Some(max)) => constraints.push(format!("At most {max} characters long.")),
+        (Some(min), None) => constraints.push(format!("At least {min} characters long.")),
+        (None, None) => {}
+    }
+
+    if let Some(pattern) = &property.pattern {
+        constraints.push(format!("Must match the pattern `{pattern}`."));
+    }
+
+    constraints
 }
 
 fn compile_object(
     raw_name: &str,
+    description: Option<&str>,
     properties: &SchemaProperties,
     required: &[String],
+    definitions: &SchemaProperties,
     schema_path: &PathBuf,
-    compiled_schemas: &mut HashMap<String, String>,
+    compiled_schemas: &mut CompiledSchemas,
 ) -> Result<()> {
     let struct_name = raw_name.to_camel();
-    let fields = properties
+    let (fields, message_fields): (Vec<_>, Vec<_>) = properties
         .iter()
         .map(|(raw_name, property)| {
-            let (annotations, name, ty) = compile_property(
-                struct_name.as_str(),
+            let (mut annotations, name, ty) = compile_property(
+                strip_message_kind(&struct_name),
                 raw_name.as_str(),
                 property,
+                definitions,
                 schema_path,
                 compiled_schemas,
             )?;
 
-            if required.contains(raw_name) {
-                Ok(format!("{annotations}pub r#{name}: {ty},"))
+            let constraints = compile_constraints(property);
+            annotations.insert_str(
+                0,
+                &compile_doc_comment(
+                    property.description.as_deref(),
+                    constraints.iter().map(String::as_str),
+                ),
+            );
+
+            // Keep the original property name on the wire.
+            if &name != raw_name {
+                annotations.push_str(&format!("#[serde(rename = \"{raw_name}\")] "));
+            }
+
+            let message_field = MessageField {
+                name: name.clone(),
+                raw_name: raw_name.clone(),
+                description: property.description.clone(),
+                ty: ty.clone(),
+                required: required.contains(raw_name),
+            };
+
+            if message_field.required {
+                Ok((
+                    format!("{annotations}#[builder(setter(into))] pub r#{name}: {ty},"),
+                    message_field,
+                ))
             } else {
-                Ok(format!("{annotations}pub r#{name}: Option<{ty}>,"))
+                // `field(value)`, or `field_opt(Option<value>)`.
+                annotations.push_str(
+                    "#[builder(default, setter(into, strip_option(fallback_suffix = \"_opt\")))] ",
+                );
+
+                // The `None`s are skipped, unless serialized `with_nulls`.
+                annotations.push_str("#[serde(skip_serializing_if = \"crate::skip_none\")] ");
+
+                Ok((
+                    format!("{annotations}pub r#{name}: Option<{ty}>,"),
+                    message_field,
+                ))
             }
         })
         .collect::<Result<Vec<_>>>()?
-        .join("\n");
+        .into_iter()
+        .unzip();
+    let mut fields = fields.join("\n");
+
+    if OPTIONS.extra_fields {
+        fields.push_str(
+            "\n/// The properties not in the schema, e.g. vendor fields, sent back as is.\n#[serde(flatten)] #[builder(default)] pub extra: serde_json::Map<String, serde_json::Value>,",
+        );
+    }
 
-    compiled_schemas.insert(
+    compiled_schemas.structs.insert(
         struct_name.clone(),
-        format!("#[derive(Debug, Clone, Serialize, Deserialize, validator::Validate)]\npub struct {struct_name} {{\n    {fields}\n}}",),
+        format!(
+            "{doc}#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]\n#[cfg_attr(feature = \"std\", derive(validator::Validate))]\npub struct {struct_name} {{\n    {fields}\n}}",
+            doc = compile_doc_comment(description, []),
+        ),
     );
+    compiled_schemas
+        .messages
+        .insert(struct_name.clone(), message_fields);
 
     Ok(())
 }
 
+/// Register an enum, compiled later by [`compile_enum_items`].
 fn compile_enum(
     enum_name: &str,
+    description: Option<&str>,
     variants: &[String],
-    compiled_schemas: &mut HashMap<String, String>,
+    schema_path: &Path,
+    compiled_schemas: &mut CompiledSchemas,
 ) -> Result<()> {
+    match compiled_schemas.enums.get(enum_name) {
+        Some(compiled_enum) if compiled_enum.variants != variants => Err(Error::ConflictingEnums {
+            name: enum_name.to_owned(),
+            schema_path: schema_path.to_owned(),
+        }),
+        Some(_) => Ok(()),
+        None => {
+            compiled_schemas.enums.insert(
+                enum_name.to_owned(),
+                CompiledEnum {
+                    description: description.map(ToOwned::to_owned),
+                    variants: variants.to_vec(),
+                },
+            );
+
+            Ok(())
+        }
+    }
+}
+
+/// Compile an enum, and its aliases, i.e. the enums with the same
+/// variants.
+fn compile_enum_items(enum_name: &str, compiled_enum: &CompiledEnum, aliases: &[&str]) -> String {
     lazy_static! {
         static ref NOT_ID: regex::Regex = regex::Regex::new("[^A-Za-z0-9]").unwrap();
     }
 
-    compiled_schemas.insert(
-        enum_name.to_string(),
+    // Pairs of (Rust identifier, wire string).
+    let variants = compiled_enum
+        .variants
+        .iter()
+        .map(|variant| {
+            (
+                NOT_ID.replace_all(&variant.to_camel(), "").into_owned(),
+                variant.as_str(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let for_each_variant = |f: &dyn Fn(&str, &str) -> String, separator: &str| {
+        variants
+            .iter()
+            .map(|(ident, variant)| f(ident, variant))
+            .collect::<Vec<_>>()
+            .join(separator)
+    };
+    let also_used_as = (!aliases.is_empty()).then(|| {
         format!(
-            "#[derive(Debug, Copy, Clone, Serialize, Deserialize)]\npub enum {enum_name} {{\n    {variants}\n}}",
-            variants = variants
+            "Also used as {aliases}.",
+            aliases = aliases
                 .iter()
-                .map(|variant| {
-                    let v = variant.to_camel();
-                    let mut v = NOT_ID.replace_all(&v, "");
-
-                    if &v != variant {
-                        v = Cow::Owned(format!("#[serde(rename = \"{variant}\")] {v}"));
-                    }
-
-                    v.to_mut().push(',');
-
-                    v.into_owned()
-                })
+                .map(|alias| format!("[`{alias}`]"))
                 .collect::<Vec<_>>()
-                .join("\n    ")
+                .join(", ")
+        )
+    });
+
+    let mut output = format!(
+        "{doc}#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum {enum_name} {{
+    {variants}
+}}
+
+impl {enum_name} {{
+    /// All the variants, in the order of the schema.
+    pub const VARIANTS: &'static [Self] = &[{variants_list}];
+
+    /// Iterate over all the variants, in the order of the schema.
+    pub fn iter() -> impl Iterator<Item = Self> {{
+        Self::VARIANTS.iter().copied()
+    }}
+
+    /// The variant as sent on the wire.
+    pub fn as_str(&self) -> &'static str {{
+        match *self {{
+            {as_str}
+        }}
+    }}
+}}
+
+impl core::fmt::Display for {enum_name} {{
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {{
+        formatter.write_str(self.as_str())
+    }}
+}}
+
+impl core::str::FromStr for {enum_name} {{
+    type Err = crate::UnknownVariantError;
+
+    fn from_str(variant: &str) -> Result<Self, Self::Err> {{
+        Ok(match variant {{
+            {from_str}
+            _ => return Err(crate::UnknownVariantError {{
+                enum_name: \"{enum_name}\",
+                variant: variant.to_owned(),
+            }}),
+        }})
+    }}
+}}
+
+impl TryFrom<&str> for {enum_name} {{
+    type Error = crate::UnknownVariantError;
+
+    fn try_from(variant: &str) -> Result<Self, Self::Error> {{
+        variant.parse()
+    }}
+}}",
+        doc = compile_doc_comment(
+            compiled_enum.description.as_deref(),
+            also_used_as.as_deref()
+        ),
+        variants = for_each_variant(
+            &|ident, variant| {
+                let rename = if ident != variant {
+                    format!("#[serde(rename = \"{variant}\")] ")
+                } else {
+                    String::new()
+                };
+
+                format!("/// `{variant}` on the wire.\n    {rename}{ident},")
+            },
+            "\n    "
+        ),
+        variants_list = for_each_variant(&|ident, _| format!("Self::{ident}"), ", "),
+        as_str = for_each_variant(
+            &|ident, variant| format!("Self::{ident} => \"{variant}\","),
+            "\n            "
+        ),
+        from_str = for_each_variant(
+            &|ident, variant| format!("\"{variant}\" => Self::{ident},"),
+            "\n            "
         ),
     );
 
+    output.push_str(&format!(
+        "\n\n{ARBITRARY_CFG}
+impl crate::test_utils::Arbitrary for {enum_name} {{
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {{
+        Self::VARIANTS[rng.random_range(0..Self::VARIANTS.len())]
+    }}
+}}"
+    ));
+
+    for alias in aliases {
+        output.push_str(&format!(
+            "\n\n/// Same as [`{enum_name}`].\npub type {alias} = {enum_name};"
+        ));
+    }
+
+    output
+}
+
+/// The `crate::test_utils` items are compiled for the tests of this crate,
+/// when the `date-time` and `uri` properties have their own types, and
+/// with the `test-utils` feature.
+const ARBITRARY_CFG: &str =
+    "#[cfg(any(all(test, feature = \"chrono\", feature = \"url\"), feature = \"test-utils\"))]";
+
+/// Compile the `crate::test_utils::Arbitrary` implementation of a struct,
+/// made of arbitrary fields.
+fn compile_arbitrary_struct(struct_name: &str, fields: &[MessageField]) -> String {
+    let mut fields = fields
+        .iter()
+        .map(|field| {
+            format!(
+                "r#{name}: crate::test_utils::Arbitrary::arbitrary(rng),",
+                name = field.name
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // Decided before the extra properties, which do not use `rng`.
+    let rng = if fields.is_empty() { "_rng" } else { "rng" };
+
+    // The extra properties could clash with the fields.
+    if OPTIONS.extra_fields {
+        fields.push("extra: Default::default(),".to_owned());
+    }
+
+    format!(
+        "{ARBITRARY_CFG}
+impl crate::test_utils::Arbitrary for {struct_name} {{
+    fn arbitrary<R: rand::Rng + ?Sized>({rng}: &mut R) -> Self {{
+        Self {{
+            {fields}
+        }}
+    }}
+}}",
+        fields = fields.join("\n            "),
+    )
+}
+
+/// Compile the `check_round_trips` function, checking every struct and
+/// every enum of a version.
+fn compile_round_trips(compiled_schemas: &CompiledSchemas) -> String {
+    let checks = compiled_schemas
+        .structs
+        .keys()
+        .chain(compiled_schemas.enums.keys())
+        .map(|name| format!("crate::test_utils::check_round_trip::<{name}, _>(rng)?;"))
+        .collect::<Vec<_>>();
+    let rng = if checks.is_empty() { "_rng" } else { "rng" };
+    let checks = checks.join("\n    ");
+
+    format!(
+        "/// Check that an arbitrary value of every struct and every enum is the
+/// same once serialized and deserialized back, see
+/// [`check_round_trip`][crate::test_utils::check_round_trip].
+{ARBITRARY_CFG}
+pub fn check_round_trips<R: rand::Rng + ?Sized>({rng}: &mut R) -> Result<(), crate::test_utils::RoundTripError> {{
+    {checks}
+
     Ok(())
+}}"
+    )
+}
+
+fn compile_reference(
+    reference: &str,
+    definitions: &SchemaProperties,
+    schema_path: &PathBuf,
+    compiled_schemas: &mut CompiledSchemas,
+) -> Result<String> {
+    let definition = reference
+        .strip_prefix("#/definitions/")
+        .and_then(|name| definitions.get_key_value(name))
+        .ok_or_else(|| Error::SchemaReferenceNotFound {
+            reference: reference.to_owned(),
+            schema_path: schema_path.clone(),
+        })?;
+    let (name, definition) = definition;
+    let type_name = definition.java_type.as_ref().unwrap_or(name);
+
+    let (_, _, ty) = compile_property(
+        "",
+        type_name,
+        definition,
+        definitions,
+        schema_path,
+        compiled_schemas,
+    )?;
+
+    Ok(ty)
 }
 
+/// Compile a property. Its enum type, if any, is named after the property,
+/// prefixed by `type_prefix`, e.g. `status` in `BootNotificationResponse`
+/// is a `BootNotificationStatus`.
 fn compile_property(
-    struct_name: &str,
+    type_prefix: &str,
     raw_name: &str,
     property: &SchemaProperty,
+    definitions: &SchemaProperties,
     schema_path: &PathBuf,
-    compiled_schemas: &mut HashMap<String, String>,
+    compiled_schemas: &mut CompiledSchemas,
 ) -> Result<(String, String, String)> {
     use SchemaPropertyType::*;
 
-    Ok((
-        {
-            let mut v = [match (&property.min_length, &property.max_length) {
-                (None, Some(max)) => Some(format!("#[validate(length(min = 1, max = {max}))]")),
-                (Some(min), Some(max)) => {
-                    Some(format!("#[validate(length(min = {min}, max = {max}))]"))
-                }
-                (Some(min), None) => Some(format!("#[validate(length(min = {min})]")),
-                (None, None) => None,
-            }]
-            .into_iter()
-            .flatten()
-            .collect::<Vec<_>>()
-            .join("\n");
-
-            if v.is_empty() {
-                "".to_string()
-            } else {
-                v.push(' ');
-
-                v
-            }
-        },
-        raw_name.to_snake(),
-        match &property.ty {
-            Boolean => "bool".to_string(),
-
-            String => {
-                if let Some(format) = &property.format {
-                    match format.as_str() {
-                        "date-time" => "chrono::DateTime<chrono::offset::Utc>",
-                        "uri" => "url::Url",
-                        _ => {
-                            return Err(Error::SchemaPropertyFormatNotSupported {
-                                name: raw_name.to_owned(),
-                                format: format.to_string(),
-                                schema_path: schema_path.clone(),
-                            })
-                        }
-                    }
-                    .to_string()
-                } else if let Some(variants) = &property.r#enum {
-                    let enum_name = raw_name.to_camel();
-
-                    compile_enum(enum_name.as_str(), variants, compiled_schemas)?;
+    let ty = match (&property.ty, &property.r#ref) {
+        (Some(ty), _) => ty,
+        (None, Some(reference)) => {
+            let ty = compile_reference(reference, definitions, schema_path, compiled_schemas)?;
 
-                    enum_name
-                } else {
-                    "String".to_string()
-                }
-            }
-
-            Number | Integer => "i32".to_string(),
-
-            Array => {
-                let items = &property.items;
-
-                match items.as_deref() {
-                    Some(&SchemaProperty {
-                        ty: Object,
-                        properties: Some(ref properties),
-                        ref required,
-                        ..
-                    }) => {
-                        let struct_name = raw_name.to_camel();
-
-                        compile_object(
-                            struct_name.as_str(),
-                            properties,
-                            if let Some(required) = required {
-                                required
-                            } else {
-                                &[]
-                            },
-                            schema_path,
-                            compiled_schemas,
-                        )?;
-
-                        format!("Vec<{struct_name}>")
-                    }
+            return Ok((
+                compile_validations(type_prefix, raw_name, property, &ty, compiled_schemas),
+                raw_name.to_snake(),
+                ty,
+            ));
+        }
+        // No type means any JSON value is accepted.
+        (None, None) => {
+            return Ok((
+                "".to_string(),
+                raw_name.to_snake(),
+                "serde_json::Value".to_string(),
+            ))
+        }
+    };
 
-                    Some(&SchemaProperty { ty: String, .. }) => "Vec<String>".to_string(),
+    let ty = match ty {
+        Boolean => "bool".to_string(),
 
+        String => {
+            if let Some(format) = &property.format {
+                match format.as_str() {
+                    "date-time" => "crate::DateTime",
+                    "uri" => "crate::Url",
                     _ => {
-                        return Err(Error::SchemaPropertyTypeNotSupported {
+                        return Err(Error::SchemaPropertyFormatNotSupported {
                             name: raw_name.to_owned(),
-                            ty: Array,
+                            format: format.to_string(),
                             schema_path: schema_path.clone(),
-                        });
+                        })
                     }
                 }
+                .to_string()
+            } else if let Some(variants) = &property.r#enum {
+                let mut enum_name = raw_name.to_camel();
+
+                // E.g. `chargingProfileKind` in `ChargingProfile` is not
+                // prefixed again.
+                if !enum_name.starts_with(type_prefix) {
+                    enum_name.insert_str(0, type_prefix);
+                }
+
+                compile_enum(
+                    enum_name.as_str(),
+                    property.description.as_deref(),
+                    variants,
+                    schema_path,
+                    compiled_schemas,
+                )?;
+
+                enum_name
+            } else if let Some(ci_string) = ci_string_type(raw_name, property, compiled_schemas) {
+                ci_string.to_string()
+            } else if let Some(capacity) = bounded_string_capacity(property) {
+                format!("crate::BoundedString<{capacity}>")
+            } else {
+                "String".to_string()
             }
+        }
 
-            Object => {
-                if let Some(properties) = &property.properties {
-                    let struct_name = raw_name.to_camel();
-
-                    compile_object(
-                        struct_name.as_str(),
-                        properties,
-                        if let Some(required) = &property.required {
-                            required
-                        } else {
-                            &[]
-                        },
-                        schema_path,
-                        compiled_schemas,
-                    )?;
-
-                    struct_name
-                } else {
-                    return Err(Error::SchemaPropertyTypeNotSupported {
-                        name: raw_name.to_owned(),
-                        ty: Object,
-                        schema_path: schema_path.clone(),
-                    });
-                }
+        Number if OPTIONS.decimal => "rust_decimal::Decimal".to_string(),
+        Number => "f64".to_string(),
+
+        // OCPP integers are 32 bits, unless their bounds say otherwise.
+        Integer => {
+            let fits_in_i32 = |bound: Option<f64>| {
+                bound.is_none_or(|bound| {
+                    (f64::from(i32::MIN)..=f64::from(i32::MAX)).contains(&bound)
+                })
+            };
+
+            if fits_in_i32(property.minimum) && fits_in_i32(property.maximum) {
+                "i32".to_string()
+            } else {
+                "i64".to_string()
             }
+        }
 
-            ty => {
+        Array => {
+            if let Some(items) = &property.items {
+                let (_, _, ty) = compile_property(
+                    type_prefix,
+                    raw_name,
+                    items,
+                    definitions,
+                    schema_path,
+                    compiled_schemas,
+                )?;
+
+                format!("Vec<{ty}>")
+            } else {
                 return Err(Error::SchemaPropertyTypeNotSupported {
                     name: raw_name.to_owned(),
-                    ty: *ty,
+                    ty: Array,
                     schema_path: schema_path.clone(),
-                })
+                });
             }
-        },
+        }
+
+        Object => {
+            if let Some(properties) = &property.properties {
+                let struct_name = raw_name.to_camel();
+
+                compile_object(
+                    struct_name.as_str(),
+                    property.description.as_deref(),
+                    properties,
+                    if let Some(required) = &property.required {
+                        required
+                    } else {
+                        &[]
+                    },
+                    definitions,
+                    schema_path,
+                    compiled_schemas,
+                )?;
+
+                struct_name
+            } else {
+                return Err(Error::SchemaPropertyTypeNotSupported {
+                    name: raw_name.to_owned(),
+                    ty: Object,
+                    schema_path: schema_path.clone(),
+                });
+            }
+        }
+
+        ty => {
+            return Err(Error::SchemaPropertyTypeNotSupported {
+                name: raw_name.to_owned(),
+                ty: *ty,
+                schema_path: schema_path.clone(),
+            })
+        }
+    };
+
+    Ok((
+        compile_validations(type_prefix, raw_name, property, &ty, compiled_schemas),
+        raw_name.to_snake(),
+        ty,
     ))
 }
+
+/// The `CiString` type representing a string property of OCPP 1.6, e.g.
+/// `crate::CiString20`, if its maximum length is the one of a `CiString`
+/// and it has no other constraint. The type checks the length itself.
+///
+/// The ID tags, `idTag` and `parentIdTag`, are normalized `crate::IdTag`s.
+fn ci_string_type(
+    raw_name: &str,
+    property: &SchemaProperty,
+    compiled_schemas: &CompiledSchemas,
+) -> Option<&'static str> {
+    if !compiled_schemas.ci_strings || property.min_length.is_some() || property.pattern.is_some() {
+        return None;
+    }
+
+    Some(match property.max_length? {
+        20 if matches!(raw_name, "idTag" | "parentIdTag") => "crate::IdTag",
+        20 => "crate::CiString20",
+        25 => "crate::CiString25",
+        50 => "crate::CiString50",
+        255 => "crate::CiString255",
+        500 => "crate::CiString500",
+        _ => return None,
+    })
+}
+
+/// The capacity of the `crate::BoundedString` representing a string
+/// property, if any: its maximum length, when it has no other constraint,
+/// which would need a `String` to be validated. OCPP bounds the length of
+/// ASCII strings, so the characters are bytes.
+fn bounded_string_capacity(property: &SchemaProperty) -> Option<u32> {
+    match (property.min_length, property.max_length, &property.pattern) {
+        (None, Some(max_length), None) if max_length <= BOUNDED_STRING_MAX_LENGTH => {
+            Some(max_length)
+        }
+        _ => None,
+    }
+}
+
+/// Compile the `validator::Validate` annotations of a property of type
+/// `ty`: the length of the strings and of the arrays, the pattern of the
+/// strings, and the validation of the nested structs.
+fn compile_validations(
+    type_prefix: &str,
+    raw_name: &str,
+    property: &SchemaProperty,
+    ty: &str,
+    compiled_schemas: &mut CompiledSchemas,
+) -> String {
+    let item_ty = ty
+        .strip_prefix("Vec<")
+        .and_then(|item_ty| item_ty.strip_suffix('>'));
+    let (min, max) = match item_ty {
+        Some(_) => (property.min_items, property.max_items),
+        None if ty == "String" => (property.min_length, property.max_length),
+        // An `ArrayString` cannot be longer than its capacity.
+        None if ty.starts_with("crate::BoundedString<") && !OPTIONS.inline_strings => {
+            (property.min_length, property.max_length)
+        }
+        None => (None, None),
+    };
+
+    let mut validations = Vec::new();
+
+    match (min, max) {
+        (Some(min), Some(max)) => validations.push(format!("length(min = {min}, max = {max})")),
+        (Some(min), None) => validations.push(format!("length(min = {min})")),
+        (None, Some(max)) => validations.push(format!("length(max = {max})")),
+        (None, None) => {}
+    }
+
+    if let (Some(pattern), "String") = (&property.pattern, ty) {
+        let pattern_name = format!(
+            "{}_{}_PATTERN",
+            type_prefix.to_snake().to_uppercase(),
+            raw_name.to_snake().to_uppercase()
+        );
+
+        validations.push(format!("regex = \"{pattern_name}\""));
+        compiled_schemas
+            .patterns
+            .insert(pattern_name, pattern.clone());
+    }
+
+    let mut annotations = String::new();
+
+    if !validations.is_empty() {
+        annotations.push_str(&format!(
+            "#[cfg_attr(feature = \"std\", validate({}))] ",
+            validations.join(", ")
+        ));
+    }
+
+    if compiled_schemas.structs.contains_key(item_ty.unwrap_or(ty)) {
+        annotations.push_str("#[cfg_attr(feature = \"std\", validate)] ");
+    }
+
+    annotations
+}
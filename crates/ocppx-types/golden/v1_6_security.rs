@@ -0,0 +1,1782 @@
+use serde::{Serialize, Deserialize};
+#[allow(unused_imports)]
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+#[cfg(feature = "std")]
+#[allow(unused_imports)]
+use validator::Validate as _;
+
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct CertificateHashData {
+    #[serde(rename = "hashAlgorithm")] #[builder(setter(into))] pub r#hash_algorithm: CertificateHashDataHashAlgorithm,
+/// At most 128 characters long.
+#[cfg_attr(feature = "std", validate(length(max = 128)))] #[serde(rename = "issuerKeyHash")] #[builder(setter(into))] pub r#issuer_key_hash: String,
+/// At most 128 characters long.
+#[cfg_attr(feature = "std", validate(length(max = 128)))] #[serde(rename = "issuerNameHash")] #[builder(setter(into))] pub r#issuer_name_hash: String,
+/// At most 40 characters long.
+#[cfg_attr(feature = "std", validate(length(max = 40)))] #[serde(rename = "serialNumber")] #[builder(setter(into))] pub r#serial_number: crate::BoundedString<40>,
+}
+
+/// Payload of the `CertificateSigned` request.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct CertificateSignedRequest {
+    /// At most 10000 characters long.
+#[cfg_attr(feature = "std", validate(length(max = 10000)))] #[serde(rename = "certificateChain")] #[builder(setter(into))] pub r#certificate_chain: String,
+}
+
+/// Payload of the `CertificateSigned` response.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct CertificateSignedResponse {
+    #[builder(setter(into))] pub r#status: CertificateSignedStatus,
+}
+
+/// Payload of the `DeleteCertificate` request.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct DeleteCertificateRequest {
+    #[cfg_attr(feature = "std", validate)] #[serde(rename = "certificateHashData")] #[builder(setter(into))] pub r#certificate_hash_data: CertificateHashData,
+}
+
+/// Payload of the `DeleteCertificate` response.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct DeleteCertificateResponse {
+    #[builder(setter(into))] pub r#status: DeleteCertificateStatus,
+}
+
+/// Payload of the `ExtendedTriggerMessage` request.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct ExtendedTriggerMessageRequest {
+    #[serde(rename = "connectorId")] #[builder(default, setter(into, strip_option(fallback_suffix = "_opt")))] #[serde(skip_serializing_if = "crate::skip_none")] pub r#connector_id: Option<i32>,
+#[serde(rename = "requestedMessage")] #[builder(setter(into))] pub r#requested_message: ExtendedTriggerMessageRequestedMessage,
+}
+
+/// Payload of the `ExtendedTriggerMessage` response.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct ExtendedTriggerMessageResponse {
+    #[builder(setter(into))] pub r#status: ExtendedTriggerMessageStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct Firmware {
+    #[serde(rename = "installDateTime")] #[builder(default, setter(into, strip_option(fallback_suffix = "_opt")))] #[serde(skip_serializing_if = "crate::skip_none")] pub r#install_date_time: Option<crate::DateTime>,
+/// At most 512 characters long.
+#[cfg_attr(feature = "std", validate(length(max = 512)))] #[builder(setter(into))] pub r#location: String,
+#[serde(rename = "retrieveDateTime")] #[builder(setter(into))] pub r#retrieve_date_time: crate::DateTime,
+/// At most 800 characters long.
+#[cfg_attr(feature = "std", validate(length(max = 800)))] #[builder(setter(into))] pub r#signature: String,
+/// At most 5500 characters long.
+#[cfg_attr(feature = "std", validate(length(max = 5500)))] #[serde(rename = "signingCertificate")] #[builder(setter(into))] pub r#signing_certificate: String,
+}
+
+/// Payload of the `GetInstalledCertificateIds` request.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct GetInstalledCertificateIdsRequest {
+    #[serde(rename = "certificateType")] #[builder(setter(into))] pub r#certificate_type: GetInstalledCertificateIdsCertificateType,
+}
+
+/// Payload of the `GetInstalledCertificateIds` response.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct GetInstalledCertificateIdsResponse {
+    #[cfg_attr(feature = "std", validate(length(min = 1)))] #[cfg_attr(feature = "std", validate)] #[serde(rename = "certificateHashData")] #[builder(default, setter(into, strip_option(fallback_suffix = "_opt")))] #[serde(skip_serializing_if = "crate::skip_none")] pub r#certificate_hash_data: Option<Vec<CertificateHashData>>,
+#[builder(setter(into))] pub r#status: GetInstalledCertificateIdsStatus,
+}
+
+/// Payload of the `GetLog` request.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct GetLogRequest {
+    #[cfg_attr(feature = "std", validate)] #[builder(setter(into))] pub r#log: LogParameters,
+#[serde(rename = "logType")] #[builder(setter(into))] pub r#log_type: GetLogLogType,
+#[serde(rename = "requestId")] #[builder(setter(into))] pub r#request_id: i32,
+#[builder(default, setter(into, strip_option(fallback_suffix = "_opt")))] #[serde(skip_serializing_if = "crate::skip_none")] pub r#retries: Option<i32>,
+#[serde(rename = "retryInterval")] #[builder(default, setter(into, strip_option(fallback_suffix = "_opt")))] #[serde(skip_serializing_if = "crate::skip_none")] pub r#retry_interval: Option<i32>,
+}
+
+/// Payload of the `GetLog` response.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct GetLogResponse {
+    /// At most 255 characters long.
+#[cfg_attr(feature = "std", validate(length(max = 255)))] #[builder(default, setter(into, strip_option(fallback_suffix = "_opt")))] #[serde(skip_serializing_if = "crate::skip_none")] pub r#filename: Option<String>,
+#[builder(setter(into))] pub r#status: GetLogStatus,
+}
+
+/// Payload of the `InstallCertificate` request.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct InstallCertificateRequest {
+    /// At most 5500 characters long.
+#[cfg_attr(feature = "std", validate(length(max = 5500)))] #[builder(setter(into))] pub r#certificate: String,
+#[serde(rename = "certificateType")] #[builder(setter(into))] pub r#certificate_type: InstallCertificateCertificateType,
+}
+
+/// Payload of the `InstallCertificate` response.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct InstallCertificateResponse {
+    #[builder(setter(into))] pub r#status: InstallCertificateStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct LogParameters {
+    #[serde(rename = "latestTimestamp")] #[builder(default, setter(into, strip_option(fallback_suffix = "_opt")))] #[serde(skip_serializing_if = "crate::skip_none")] pub r#latest_timestamp: Option<crate::DateTime>,
+#[serde(rename = "oldestTimestamp")] #[builder(default, setter(into, strip_option(fallback_suffix = "_opt")))] #[serde(skip_serializing_if = "crate::skip_none")] pub r#oldest_timestamp: Option<crate::DateTime>,
+/// At most 512 characters long.
+#[cfg_attr(feature = "std", validate(length(max = 512)))] #[serde(rename = "remoteLocation")] #[builder(setter(into))] pub r#remote_location: String,
+}
+
+/// Payload of the `LogStatusNotification` request.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct LogStatusNotificationRequest {
+    #[serde(rename = "requestId")] #[builder(default, setter(into, strip_option(fallback_suffix = "_opt")))] #[serde(skip_serializing_if = "crate::skip_none")] pub r#request_id: Option<i32>,
+#[builder(setter(into))] pub r#status: LogStatusNotificationStatus,
+}
+
+/// Payload of the `LogStatusNotification` response.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct LogStatusNotificationResponse {
+    
+}
+
+/// Payload of the `SecurityEventNotification` request.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct SecurityEventNotificationRequest {
+    /// At most 255 characters long.
+#[cfg_attr(feature = "std", validate(length(max = 255)))] #[serde(rename = "techInfo")] #[builder(default, setter(into, strip_option(fallback_suffix = "_opt")))] #[serde(skip_serializing_if = "crate::skip_none")] pub r#tech_info: Option<String>,
+#[builder(setter(into))] pub r#timestamp: crate::DateTime,
+/// At most 50 characters long.
+#[cfg_attr(feature = "std", validate(length(max = 50)))] #[builder(setter(into))] pub r#type: crate::BoundedString<50>,
+}
+
+/// Payload of the `SecurityEventNotification` response.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct SecurityEventNotificationResponse {
+    
+}
+
+/// Payload of the `SignCertificate` request.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct SignCertificateRequest {
+    /// At most 5500 characters long.
+#[cfg_attr(feature = "std", validate(length(max = 5500)))] #[builder(setter(into))] pub r#csr: String,
+}
+
+/// Payload of the `SignCertificate` response.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct SignCertificateResponse {
+    #[builder(setter(into))] pub r#status: SignCertificateStatus,
+}
+
+/// Payload of the `SignedFirmwareStatusNotification` request.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct SignedFirmwareStatusNotificationRequest {
+    #[serde(rename = "requestId")] #[builder(default, setter(into, strip_option(fallback_suffix = "_opt")))] #[serde(skip_serializing_if = "crate::skip_none")] pub r#request_id: Option<i32>,
+#[builder(setter(into))] pub r#status: SignedFirmwareStatusNotificationStatus,
+}
+
+/// Payload of the `SignedFirmwareStatusNotification` response.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct SignedFirmwareStatusNotificationResponse {
+    
+}
+
+/// Payload of the `SignedUpdateFirmware` request.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct SignedUpdateFirmwareRequest {
+    #[cfg_attr(feature = "std", validate)] #[builder(setter(into))] pub r#firmware: Firmware,
+#[serde(rename = "requestId")] #[builder(setter(into))] pub r#request_id: i32,
+#[builder(default, setter(into, strip_option(fallback_suffix = "_opt")))] #[serde(skip_serializing_if = "crate::skip_none")] pub r#retries: Option<i32>,
+#[serde(rename = "retryInterval")] #[builder(default, setter(into, strip_option(fallback_suffix = "_opt")))] #[serde(skip_serializing_if = "crate::skip_none")] pub r#retry_interval: Option<i32>,
+}
+
+/// Payload of the `SignedUpdateFirmware` response.
+#[derive(Debug, Clone, Serialize, Deserialize, typed_builder::TypedBuilder)]
+#[cfg_attr(feature = "std", derive(validator::Validate))]
+pub struct SignedUpdateFirmwareResponse {
+    #[builder(setter(into))] pub r#status: SignedUpdateFirmwareStatus,
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for CertificateHashData {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#hash_algorithm: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#issuer_key_hash: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#issuer_name_hash: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#serial_number: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for CertificateSignedRequest {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#certificate_chain: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for CertificateSignedResponse {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#status: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for DeleteCertificateRequest {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#certificate_hash_data: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for DeleteCertificateResponse {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#status: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for ExtendedTriggerMessageRequest {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#connector_id: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#requested_message: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for ExtendedTriggerMessageResponse {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#status: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for Firmware {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#install_date_time: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#location: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#retrieve_date_time: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#signature: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#signing_certificate: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for GetInstalledCertificateIdsRequest {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#certificate_type: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for GetInstalledCertificateIdsResponse {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#certificate_hash_data: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#status: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for GetLogRequest {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#log: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#log_type: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#request_id: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#retries: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#retry_interval: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for GetLogResponse {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#filename: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#status: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for InstallCertificateRequest {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#certificate: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#certificate_type: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for InstallCertificateResponse {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#status: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for LogParameters {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#latest_timestamp: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#oldest_timestamp: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#remote_location: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for LogStatusNotificationRequest {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#request_id: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#status: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for LogStatusNotificationResponse {
+    fn arbitrary<R: rand::Rng + ?Sized>(_rng: &mut R) -> Self {
+        Self {
+            
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for SecurityEventNotificationRequest {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#tech_info: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#timestamp: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#type: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for SecurityEventNotificationResponse {
+    fn arbitrary<R: rand::Rng + ?Sized>(_rng: &mut R) -> Self {
+        Self {
+            
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for SignCertificateRequest {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#csr: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for SignCertificateResponse {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#status: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for SignedFirmwareStatusNotificationRequest {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#request_id: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#status: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for SignedFirmwareStatusNotificationResponse {
+    fn arbitrary<R: rand::Rng + ?Sized>(_rng: &mut R) -> Self {
+        Self {
+            
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for SignedUpdateFirmwareRequest {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#firmware: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#request_id: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#retries: crate::test_utils::Arbitrary::arbitrary(rng),
+            r#retry_interval: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for SignedUpdateFirmwareResponse {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            r#status: crate::test_utils::Arbitrary::arbitrary(rng),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DeleteCertificateStatus {
+    /// `Accepted` on the wire.
+    Accepted,
+    /// `Failed` on the wire.
+    Failed,
+    /// `NotFound` on the wire.
+    NotFound,
+}
+
+impl DeleteCertificateStatus {
+    /// All the variants, in the order of the schema.
+    pub const VARIANTS: &'static [Self] = &[Self::Accepted, Self::Failed, Self::NotFound];
+
+    /// Iterate over all the variants, in the order of the schema.
+    pub fn iter() -> impl Iterator<Item = Self> {
+        Self::VARIANTS.iter().copied()
+    }
+
+    /// The variant as sent on the wire.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::Accepted => "Accepted",
+            Self::Failed => "Failed",
+            Self::NotFound => "NotFound",
+        }
+    }
+}
+
+impl core::fmt::Display for DeleteCertificateStatus {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl core::str::FromStr for DeleteCertificateStatus {
+    type Err = crate::UnknownVariantError;
+
+    fn from_str(variant: &str) -> Result<Self, Self::Err> {
+        Ok(match variant {
+            "Accepted" => Self::Accepted,
+            "Failed" => Self::Failed,
+            "NotFound" => Self::NotFound,
+            _ => return Err(crate::UnknownVariantError {
+                enum_name: "DeleteCertificateStatus",
+                variant: variant.to_owned(),
+            }),
+        })
+    }
+}
+
+impl TryFrom<&str> for DeleteCertificateStatus {
+    type Error = crate::UnknownVariantError;
+
+    fn try_from(variant: &str) -> Result<Self, Self::Error> {
+        variant.parse()
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for DeleteCertificateStatus {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self::VARIANTS[rng.random_range(0..Self::VARIANTS.len())]
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InstallCertificateStatus {
+    /// `Accepted` on the wire.
+    Accepted,
+    /// `Failed` on the wire.
+    Failed,
+    /// `Rejected` on the wire.
+    Rejected,
+}
+
+impl InstallCertificateStatus {
+    /// All the variants, in the order of the schema.
+    pub const VARIANTS: &'static [Self] = &[Self::Accepted, Self::Failed, Self::Rejected];
+
+    /// Iterate over all the variants, in the order of the schema.
+    pub fn iter() -> impl Iterator<Item = Self> {
+        Self::VARIANTS.iter().copied()
+    }
+
+    /// The variant as sent on the wire.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::Accepted => "Accepted",
+            Self::Failed => "Failed",
+            Self::Rejected => "Rejected",
+        }
+    }
+}
+
+impl core::fmt::Display for InstallCertificateStatus {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl core::str::FromStr for InstallCertificateStatus {
+    type Err = crate::UnknownVariantError;
+
+    fn from_str(variant: &str) -> Result<Self, Self::Err> {
+        Ok(match variant {
+            "Accepted" => Self::Accepted,
+            "Failed" => Self::Failed,
+            "Rejected" => Self::Rejected,
+            _ => return Err(crate::UnknownVariantError {
+                enum_name: "InstallCertificateStatus",
+                variant: variant.to_owned(),
+            }),
+        })
+    }
+}
+
+impl TryFrom<&str> for InstallCertificateStatus {
+    type Error = crate::UnknownVariantError;
+
+    fn try_from(variant: &str) -> Result<Self, Self::Error> {
+        variant.parse()
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for InstallCertificateStatus {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self::VARIANTS[rng.random_range(0..Self::VARIANTS.len())]
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GetInstalledCertificateIdsStatus {
+    /// `Accepted` on the wire.
+    Accepted,
+    /// `NotFound` on the wire.
+    NotFound,
+}
+
+impl GetInstalledCertificateIdsStatus {
+    /// All the variants, in the order of the schema.
+    pub const VARIANTS: &'static [Self] = &[Self::Accepted, Self::NotFound];
+
+    /// Iterate over all the variants, in the order of the schema.
+    pub fn iter() -> impl Iterator<Item = Self> {
+        Self::VARIANTS.iter().copied()
+    }
+
+    /// The variant as sent on the wire.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::Accepted => "Accepted",
+            Self::NotFound => "NotFound",
+        }
+    }
+}
+
+impl core::fmt::Display for GetInstalledCertificateIdsStatus {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl core::str::FromStr for GetInstalledCertificateIdsStatus {
+    type Err = crate::UnknownVariantError;
+
+    fn from_str(variant: &str) -> Result<Self, Self::Err> {
+        Ok(match variant {
+            "Accepted" => Self::Accepted,
+            "NotFound" => Self::NotFound,
+            _ => return Err(crate::UnknownVariantError {
+                enum_name: "GetInstalledCertificateIdsStatus",
+                variant: variant.to_owned(),
+            }),
+        })
+    }
+}
+
+impl TryFrom<&str> for GetInstalledCertificateIdsStatus {
+    type Error = crate::UnknownVariantError;
+
+    fn try_from(variant: &str) -> Result<Self, Self::Error> {
+        variant.parse()
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for GetInstalledCertificateIdsStatus {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self::VARIANTS[rng.random_range(0..Self::VARIANTS.len())]
+    }
+}
+
+/// Also used as [`SignCertificateStatus`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CertificateSignedStatus {
+    /// `Accepted` on the wire.
+    Accepted,
+    /// `Rejected` on the wire.
+    Rejected,
+}
+
+impl CertificateSignedStatus {
+    /// All the variants, in the order of the schema.
+    pub const VARIANTS: &'static [Self] = &[Self::Accepted, Self::Rejected];
+
+    /// Iterate over all the variants, in the order of the schema.
+    pub fn iter() -> impl Iterator<Item = Self> {
+        Self::VARIANTS.iter().copied()
+    }
+
+    /// The variant as sent on the wire.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::Accepted => "Accepted",
+            Self::Rejected => "Rejected",
+        }
+    }
+}
+
+impl core::fmt::Display for CertificateSignedStatus {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl core::str::FromStr for CertificateSignedStatus {
+    type Err = crate::UnknownVariantError;
+
+    fn from_str(variant: &str) -> Result<Self, Self::Err> {
+        Ok(match variant {
+            "Accepted" => Self::Accepted,
+            "Rejected" => Self::Rejected,
+            _ => return Err(crate::UnknownVariantError {
+                enum_name: "CertificateSignedStatus",
+                variant: variant.to_owned(),
+            }),
+        })
+    }
+}
+
+impl TryFrom<&str> for CertificateSignedStatus {
+    type Error = crate::UnknownVariantError;
+
+    fn try_from(variant: &str) -> Result<Self, Self::Error> {
+        variant.parse()
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for CertificateSignedStatus {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self::VARIANTS[rng.random_range(0..Self::VARIANTS.len())]
+    }
+}
+
+/// Same as [`CertificateSignedStatus`].
+pub type SignCertificateStatus = CertificateSignedStatus;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GetLogStatus {
+    /// `Accepted` on the wire.
+    Accepted,
+    /// `Rejected` on the wire.
+    Rejected,
+    /// `AcceptedCanceled` on the wire.
+    AcceptedCanceled,
+}
+
+impl GetLogStatus {
+    /// All the variants, in the order of the schema.
+    pub const VARIANTS: &'static [Self] = &[Self::Accepted, Self::Rejected, Self::AcceptedCanceled];
+
+    /// Iterate over all the variants, in the order of the schema.
+    pub fn iter() -> impl Iterator<Item = Self> {
+        Self::VARIANTS.iter().copied()
+    }
+
+    /// The variant as sent on the wire.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::Accepted => "Accepted",
+            Self::Rejected => "Rejected",
+            Self::AcceptedCanceled => "AcceptedCanceled",
+        }
+    }
+}
+
+impl core::fmt::Display for GetLogStatus {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl core::str::FromStr for GetLogStatus {
+    type Err = crate::UnknownVariantError;
+
+    fn from_str(variant: &str) -> Result<Self, Self::Err> {
+        Ok(match variant {
+            "Accepted" => Self::Accepted,
+            "Rejected" => Self::Rejected,
+            "AcceptedCanceled" => Self::AcceptedCanceled,
+            _ => return Err(crate::UnknownVariantError {
+                enum_name: "GetLogStatus",
+                variant: variant.to_owned(),
+            }),
+        })
+    }
+}
+
+impl TryFrom<&str> for GetLogStatus {
+    type Error = crate::UnknownVariantError;
+
+    fn try_from(variant: &str) -> Result<Self, Self::Error> {
+        variant.parse()
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for GetLogStatus {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self::VARIANTS[rng.random_range(0..Self::VARIANTS.len())]
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SignedUpdateFirmwareStatus {
+    /// `Accepted` on the wire.
+    Accepted,
+    /// `Rejected` on the wire.
+    Rejected,
+    /// `AcceptedCanceled` on the wire.
+    AcceptedCanceled,
+    /// `InvalidCertificate` on the wire.
+    InvalidCertificate,
+    /// `RevokedCertificate` on the wire.
+    RevokedCertificate,
+}
+
+impl SignedUpdateFirmwareStatus {
+    /// All the variants, in the order of the schema.
+    pub const VARIANTS: &'static [Self] = &[Self::Accepted, Self::Rejected, Self::AcceptedCanceled, Self::InvalidCertificate, Self::RevokedCertificate];
+
+    /// Iterate over all the variants, in the order of the schema.
+    pub fn iter() -> impl Iterator<Item = Self> {
+        Self::VARIANTS.iter().copied()
+    }
+
+    /// The variant as sent on the wire.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::Accepted => "Accepted",
+            Self::Rejected => "Rejected",
+            Self::AcceptedCanceled => "AcceptedCanceled",
+            Self::InvalidCertificate => "InvalidCertificate",
+            Self::RevokedCertificate => "RevokedCertificate",
+        }
+    }
+}
+
+impl core::fmt::Display for SignedUpdateFirmwareStatus {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl core::str::FromStr for SignedUpdateFirmwareStatus {
+    type Err = crate::UnknownVariantError;
+
+    fn from_str(variant: &str) -> Result<Self, Self::Err> {
+        Ok(match variant {
+            "Accepted" => Self::Accepted,
+            "Rejected" => Self::Rejected,
+            "AcceptedCanceled" => Self::AcceptedCanceled,
+            "InvalidCertificate" => Self::InvalidCertificate,
+            "RevokedCertificate" => Self::RevokedCertificate,
+            _ => return Err(crate::UnknownVariantError {
+                enum_name: "SignedUpdateFirmwareStatus",
+                variant: variant.to_owned(),
+            }),
+        })
+    }
+}
+
+impl TryFrom<&str> for SignedUpdateFirmwareStatus {
+    type Error = crate::UnknownVariantError;
+
+    fn try_from(variant: &str) -> Result<Self, Self::Error> {
+        variant.parse()
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for SignedUpdateFirmwareStatus {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self::VARIANTS[rng.random_range(0..Self::VARIANTS.len())]
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExtendedTriggerMessageStatus {
+    /// `Accepted` on the wire.
+    Accepted,
+    /// `Rejected` on the wire.
+    Rejected,
+    /// `NotImplemented` on the wire.
+    NotImplemented,
+}
+
+impl ExtendedTriggerMessageStatus {
+    /// All the variants, in the order of the schema.
+    pub const VARIANTS: &'static [Self] = &[Self::Accepted, Self::Rejected, Self::NotImplemented];
+
+    /// Iterate over all the variants, in the order of the schema.
+    pub fn iter() -> impl Iterator<Item = Self> {
+        Self::VARIANTS.iter().copied()
+    }
+
+    /// The variant as sent on the wire.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::Accepted => "Accepted",
+            Self::Rejected => "Rejected",
+            Self::NotImplemented => "NotImplemented",
+        }
+    }
+}
+
+impl core::fmt::Display for ExtendedTriggerMessageStatus {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl core::str::FromStr for ExtendedTriggerMessageStatus {
+    type Err = crate::UnknownVariantError;
+
+    fn from_str(variant: &str) -> Result<Self, Self::Err> {
+        Ok(match variant {
+            "Accepted" => Self::Accepted,
+            "Rejected" => Self::Rejected,
+            "NotImplemented" => Self::NotImplemented,
+            _ => return Err(crate::UnknownVariantError {
+                enum_name: "ExtendedTriggerMessageStatus",
+                variant: variant.to_owned(),
+            }),
+        })
+    }
+}
+
+impl TryFrom<&str> for ExtendedTriggerMessageStatus {
+    type Error = crate::UnknownVariantError;
+
+    fn try_from(variant: &str) -> Result<Self, Self::Error> {
+        variant.parse()
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for ExtendedTriggerMessageStatus {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self::VARIANTS[rng.random_range(0..Self::VARIANTS.len())]
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LogStatusNotificationStatus {
+    /// `BadMessage` on the wire.
+    BadMessage,
+    /// `Idle` on the wire.
+    Idle,
+    /// `NotSupportedOperation` on the wire.
+    NotSupportedOperation,
+    /// `PermissionDenied` on the wire.
+    PermissionDenied,
+    /// `Uploaded` on the wire.
+    Uploaded,
+    /// `UploadFailure` on the wire.
+    UploadFailure,
+    /// `Uploading` on the wire.
+    Uploading,
+}
+
+impl LogStatusNotificationStatus {
+    /// All the variants, in the order of the schema.
+    pub const VARIANTS: &'static [Self] = &[Self::BadMessage, Self::Idle, Self::NotSupportedOperation, Self::PermissionDenied, Self::Uploaded, Self::UploadFailure, Self::Uploading];
+
+    /// Iterate over all the variants, in the order of the schema.
+    pub fn iter() -> impl Iterator<Item = Self> {
+        Self::VARIANTS.iter().copied()
+    }
+
+    /// The variant as sent on the wire.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::BadMessage => "BadMessage",
+            Self::Idle => "Idle",
+            Self::NotSupportedOperation => "NotSupportedOperation",
+            Self::PermissionDenied => "PermissionDenied",
+            Self::Uploaded => "Uploaded",
+            Self::UploadFailure => "UploadFailure",
+            Self::Uploading => "Uploading",
+        }
+    }
+}
+
+impl core::fmt::Display for LogStatusNotificationStatus {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl core::str::FromStr for LogStatusNotificationStatus {
+    type Err = crate::UnknownVariantError;
+
+    fn from_str(variant: &str) -> Result<Self, Self::Err> {
+        Ok(match variant {
+            "BadMessage" => Self::BadMessage,
+            "Idle" => Self::Idle,
+            "NotSupportedOperation" => Self::NotSupportedOperation,
+            "PermissionDenied" => Self::PermissionDenied,
+            "Uploaded" => Self::Uploaded,
+            "UploadFailure" => Self::UploadFailure,
+            "Uploading" => Self::Uploading,
+            _ => return Err(crate::UnknownVariantError {
+                enum_name: "LogStatusNotificationStatus",
+                variant: variant.to_owned(),
+            }),
+        })
+    }
+}
+
+impl TryFrom<&str> for LogStatusNotificationStatus {
+    type Error = crate::UnknownVariantError;
+
+    fn try_from(variant: &str) -> Result<Self, Self::Error> {
+        variant.parse()
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for LogStatusNotificationStatus {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self::VARIANTS[rng.random_range(0..Self::VARIANTS.len())]
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExtendedTriggerMessageRequestedMessage {
+    /// `BootNotification` on the wire.
+    BootNotification,
+    /// `LogStatusNotification` on the wire.
+    LogStatusNotification,
+    /// `FirmwareStatusNotification` on the wire.
+    FirmwareStatusNotification,
+    /// `Heartbeat` on the wire.
+    Heartbeat,
+    /// `MeterValues` on the wire.
+    MeterValues,
+    /// `SignChargePointCertificate` on the wire.
+    SignChargePointCertificate,
+    /// `StatusNotification` on the wire.
+    StatusNotification,
+}
+
+impl ExtendedTriggerMessageRequestedMessage {
+    /// All the variants, in the order of the schema.
+    pub const VARIANTS: &'static [Self] = &[Self::BootNotification, Self::LogStatusNotification, Self::FirmwareStatusNotification, Self::Heartbeat, Self::MeterValues, Self::SignChargePointCertificate, Self::StatusNotification];
+
+    /// Iterate over all the variants, in the order of the schema.
+    pub fn iter() -> impl Iterator<Item = Self> {
+        Self::VARIANTS.iter().copied()
+    }
+
+    /// The variant as sent on the wire.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::BootNotification => "BootNotification",
+            Self::LogStatusNotification => "LogStatusNotification",
+            Self::FirmwareStatusNotification => "FirmwareStatusNotification",
+            Self::Heartbeat => "Heartbeat",
+            Self::MeterValues => "MeterValues",
+            Self::SignChargePointCertificate => "SignChargePointCertificate",
+            Self::StatusNotification => "StatusNotification",
+        }
+    }
+}
+
+impl core::fmt::Display for ExtendedTriggerMessageRequestedMessage {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl core::str::FromStr for ExtendedTriggerMessageRequestedMessage {
+    type Err = crate::UnknownVariantError;
+
+    fn from_str(variant: &str) -> Result<Self, Self::Err> {
+        Ok(match variant {
+            "BootNotification" => Self::BootNotification,
+            "LogStatusNotification" => Self::LogStatusNotification,
+            "FirmwareStatusNotification" => Self::FirmwareStatusNotification,
+            "Heartbeat" => Self::Heartbeat,
+            "MeterValues" => Self::MeterValues,
+            "SignChargePointCertificate" => Self::SignChargePointCertificate,
+            "StatusNotification" => Self::StatusNotification,
+            _ => return Err(crate::UnknownVariantError {
+                enum_name: "ExtendedTriggerMessageRequestedMessage",
+                variant: variant.to_owned(),
+            }),
+        })
+    }
+}
+
+impl TryFrom<&str> for ExtendedTriggerMessageRequestedMessage {
+    type Error = crate::UnknownVariantError;
+
+    fn try_from(variant: &str) -> Result<Self, Self::Error> {
+        variant.parse()
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for ExtendedTriggerMessageRequestedMessage {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self::VARIANTS[rng.random_range(0..Self::VARIANTS.len())]
+    }
+}
+
+/// Also used as [`InstallCertificateCertificateType`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GetInstalledCertificateIdsCertificateType {
+    /// `CentralSystemRootCertificate` on the wire.
+    CentralSystemRootCertificate,
+    /// `ManufacturerRootCertificate` on the wire.
+    ManufacturerRootCertificate,
+}
+
+impl GetInstalledCertificateIdsCertificateType {
+    /// All the variants, in the order of the schema.
+    pub const VARIANTS: &'static [Self] = &[Self::CentralSystemRootCertificate, Self::ManufacturerRootCertificate];
+
+    /// Iterate over all the variants, in the order of the schema.
+    pub fn iter() -> impl Iterator<Item = Self> {
+        Self::VARIANTS.iter().copied()
+    }
+
+    /// The variant as sent on the wire.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::CentralSystemRootCertificate => "CentralSystemRootCertificate",
+            Self::ManufacturerRootCertificate => "ManufacturerRootCertificate",
+        }
+    }
+}
+
+impl core::fmt::Display for GetInstalledCertificateIdsCertificateType {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl core::str::FromStr for GetInstalledCertificateIdsCertificateType {
+    type Err = crate::UnknownVariantError;
+
+    fn from_str(variant: &str) -> Result<Self, Self::Err> {
+        Ok(match variant {
+            "CentralSystemRootCertificate" => Self::CentralSystemRootCertificate,
+            "ManufacturerRootCertificate" => Self::ManufacturerRootCertificate,
+            _ => return Err(crate::UnknownVariantError {
+                enum_name: "GetInstalledCertificateIdsCertificateType",
+                variant: variant.to_owned(),
+            }),
+        })
+    }
+}
+
+impl TryFrom<&str> for GetInstalledCertificateIdsCertificateType {
+    type Error = crate::UnknownVariantError;
+
+    fn try_from(variant: &str) -> Result<Self, Self::Error> {
+        variant.parse()
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for GetInstalledCertificateIdsCertificateType {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self::VARIANTS[rng.random_range(0..Self::VARIANTS.len())]
+    }
+}
+
+/// Same as [`GetInstalledCertificateIdsCertificateType`].
+pub type InstallCertificateCertificateType = GetInstalledCertificateIdsCertificateType;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GetLogLogType {
+    /// `DiagnosticsLog` on the wire.
+    DiagnosticsLog,
+    /// `SecurityLog` on the wire.
+    SecurityLog,
+}
+
+impl GetLogLogType {
+    /// All the variants, in the order of the schema.
+    pub const VARIANTS: &'static [Self] = &[Self::DiagnosticsLog, Self::SecurityLog];
+
+    /// Iterate over all the variants, in the order of the schema.
+    pub fn iter() -> impl Iterator<Item = Self> {
+        Self::VARIANTS.iter().copied()
+    }
+
+    /// The variant as sent on the wire.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::DiagnosticsLog => "DiagnosticsLog",
+            Self::SecurityLog => "SecurityLog",
+        }
+    }
+}
+
+impl core::fmt::Display for GetLogLogType {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl core::str::FromStr for GetLogLogType {
+    type Err = crate::UnknownVariantError;
+
+    fn from_str(variant: &str) -> Result<Self, Self::Err> {
+        Ok(match variant {
+            "DiagnosticsLog" => Self::DiagnosticsLog,
+            "SecurityLog" => Self::SecurityLog,
+            _ => return Err(crate::UnknownVariantError {
+                enum_name: "GetLogLogType",
+                variant: variant.to_owned(),
+            }),
+        })
+    }
+}
+
+impl TryFrom<&str> for GetLogLogType {
+    type Error = crate::UnknownVariantError;
+
+    fn try_from(variant: &str) -> Result<Self, Self::Error> {
+        variant.parse()
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for GetLogLogType {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self::VARIANTS[rng.random_range(0..Self::VARIANTS.len())]
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SignedFirmwareStatusNotificationStatus {
+    /// `Downloaded` on the wire.
+    Downloaded,
+    /// `DownloadFailed` on the wire.
+    DownloadFailed,
+    /// `Downloading` on the wire.
+    Downloading,
+    /// `DownloadScheduled` on the wire.
+    DownloadScheduled,
+    /// `DownloadPaused` on the wire.
+    DownloadPaused,
+    /// `Idle` on the wire.
+    Idle,
+    /// `InstallationFailed` on the wire.
+    InstallationFailed,
+    /// `Installing` on the wire.
+    Installing,
+    /// `Installed` on the wire.
+    Installed,
+    /// `InstallRebooting` on the wire.
+    InstallRebooting,
+    /// `InstallScheduled` on the wire.
+    InstallScheduled,
+    /// `InstallVerificationFailed` on the wire.
+    InstallVerificationFailed,
+    /// `InvalidSignature` on the wire.
+    InvalidSignature,
+    /// `SignatureVerified` on the wire.
+    SignatureVerified,
+}
+
+impl SignedFirmwareStatusNotificationStatus {
+    /// All the variants, in the order of the schema.
+    pub const VARIANTS: &'static [Self] = &[Self::Downloaded, Self::DownloadFailed, Self::Downloading, Self::DownloadScheduled, Self::DownloadPaused, Self::Idle, Self::InstallationFailed, Self::Installing, Self::Installed, Self::InstallRebooting, Self::InstallScheduled, Self::InstallVerificationFailed, Self::InvalidSignature, Self::SignatureVerified];
+
+    /// Iterate over all the variants, in the order of the schema.
+    pub fn iter() -> impl Iterator<Item = Self> {
+        Self::VARIANTS.iter().copied()
+    }
+
+    /// The variant as sent on the wire.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::Downloaded => "Downloaded",
+            Self::DownloadFailed => "DownloadFailed",
+            Self::Downloading => "Downloading",
+            Self::DownloadScheduled => "DownloadScheduled",
+            Self::DownloadPaused => "DownloadPaused",
+            Self::Idle => "Idle",
+            Self::InstallationFailed => "InstallationFailed",
+            Self::Installing => "Installing",
+            Self::Installed => "Installed",
+            Self::InstallRebooting => "InstallRebooting",
+            Self::InstallScheduled => "InstallScheduled",
+            Self::InstallVerificationFailed => "InstallVerificationFailed",
+            Self::InvalidSignature => "InvalidSignature",
+            Self::SignatureVerified => "SignatureVerified",
+        }
+    }
+}
+
+impl core::fmt::Display for SignedFirmwareStatusNotificationStatus {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl core::str::FromStr for SignedFirmwareStatusNotificationStatus {
+    type Err = crate::UnknownVariantError;
+
+    fn from_str(variant: &str) -> Result<Self, Self::Err> {
+        Ok(match variant {
+            "Downloaded" => Self::Downloaded,
+            "DownloadFailed" => Self::DownloadFailed,
+            "Downloading" => Self::Downloading,
+            "DownloadScheduled" => Self::DownloadScheduled,
+            "DownloadPaused" => Self::DownloadPaused,
+            "Idle" => Self::Idle,
+            "InstallationFailed" => Self::InstallationFailed,
+            "Installing" => Self::Installing,
+            "Installed" => Self::Installed,
+            "InstallRebooting" => Self::InstallRebooting,
+            "InstallScheduled" => Self::InstallScheduled,
+            "InstallVerificationFailed" => Self::InstallVerificationFailed,
+            "InvalidSignature" => Self::InvalidSignature,
+            "SignatureVerified" => Self::SignatureVerified,
+            _ => return Err(crate::UnknownVariantError {
+                enum_name: "SignedFirmwareStatusNotificationStatus",
+                variant: variant.to_owned(),
+            }),
+        })
+    }
+}
+
+impl TryFrom<&str> for SignedFirmwareStatusNotificationStatus {
+    type Error = crate::UnknownVariantError;
+
+    fn try_from(variant: &str) -> Result<Self, Self::Error> {
+        variant.parse()
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for SignedFirmwareStatusNotificationStatus {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self::VARIANTS[rng.random_range(0..Self::VARIANTS.len())]
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CertificateHashDataHashAlgorithm {
+    /// `SHA256` on the wire.
+    SHA256,
+    /// `SHA384` on the wire.
+    SHA384,
+    /// `SHA512` on the wire.
+    SHA512,
+}
+
+impl CertificateHashDataHashAlgorithm {
+    /// All the variants, in the order of the schema.
+    pub const VARIANTS: &'static [Self] = &[Self::SHA256, Self::SHA384, Self::SHA512];
+
+    /// Iterate over all the variants, in the order of the schema.
+    pub fn iter() -> impl Iterator<Item = Self> {
+        Self::VARIANTS.iter().copied()
+    }
+
+    /// The variant as sent on the wire.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::SHA256 => "SHA256",
+            Self::SHA384 => "SHA384",
+            Self::SHA512 => "SHA512",
+        }
+    }
+}
+
+impl core::fmt::Display for CertificateHashDataHashAlgorithm {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl core::str::FromStr for CertificateHashDataHashAlgorithm {
+    type Err = crate::UnknownVariantError;
+
+    fn from_str(variant: &str) -> Result<Self, Self::Err> {
+        Ok(match variant {
+            "SHA256" => Self::SHA256,
+            "SHA384" => Self::SHA384,
+            "SHA512" => Self::SHA512,
+            _ => return Err(crate::UnknownVariantError {
+                enum_name: "CertificateHashDataHashAlgorithm",
+                variant: variant.to_owned(),
+            }),
+        })
+    }
+}
+
+impl TryFrom<&str> for CertificateHashDataHashAlgorithm {
+    type Error = crate::UnknownVariantError;
+
+    fn try_from(variant: &str) -> Result<Self, Self::Error> {
+        variant.parse()
+    }
+}
+
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+impl crate::test_utils::Arbitrary for CertificateHashDataHashAlgorithm {
+    fn arbitrary<R: rand::Rng + ?Sized>(rng: &mut R) -> Self {
+        Self::VARIANTS[rng.random_range(0..Self::VARIANTS.len())]
+    }
+}
+
+/// The actions, i.e. the names of the request/response pairs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    CertificateSigned,
+DeleteCertificate,
+ExtendedTriggerMessage,
+GetInstalledCertificateIds,
+GetLog,
+InstallCertificate,
+LogStatusNotification,
+SecurityEventNotification,
+SignCertificate,
+SignedFirmwareStatusNotification,
+SignedUpdateFirmware,
+}
+
+impl Action {
+    /// All the actions, in alphabetical order.
+    pub const VARIANTS: &'static [Self] = &[Self::CertificateSigned,
+Self::DeleteCertificate,
+Self::ExtendedTriggerMessage,
+Self::GetInstalledCertificateIds,
+Self::GetLog,
+Self::InstallCertificate,
+Self::LogStatusNotification,
+Self::SecurityEventNotification,
+Self::SignCertificate,
+Self::SignedFirmwareStatusNotification,
+Self::SignedUpdateFirmware,];
+
+    /// Iterate over all the actions, in alphabetical order.
+    pub fn iter() -> impl Iterator<Item = Self> {
+        Self::VARIANTS.iter().copied()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::CertificateSigned => "CertificateSigned",
+Self::DeleteCertificate => "DeleteCertificate",
+Self::ExtendedTriggerMessage => "ExtendedTriggerMessage",
+Self::GetInstalledCertificateIds => "GetInstalledCertificateIds",
+Self::GetLog => "GetLog",
+Self::InstallCertificate => "InstallCertificate",
+Self::LogStatusNotification => "LogStatusNotification",
+Self::SecurityEventNotification => "SecurityEventNotification",
+Self::SignCertificate => "SignCertificate",
+Self::SignedFirmwareStatusNotification => "SignedFirmwareStatusNotification",
+Self::SignedUpdateFirmware => "SignedUpdateFirmware",
+        }
+    }
+
+    /// The JSON schema of the request payload.
+    pub fn request_schema(&self) -> &'static str {
+        match *self {
+            Self::CertificateSigned => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/CertificateSigned.json")),
+Self::DeleteCertificate => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/DeleteCertificate.json")),
+Self::ExtendedTriggerMessage => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/ExtendedTriggerMessage.json")),
+Self::GetInstalledCertificateIds => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/GetInstalledCertificateIds.json")),
+Self::GetLog => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/GetLog.json")),
+Self::InstallCertificate => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/InstallCertificate.json")),
+Self::LogStatusNotification => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/LogStatusNotification.json")),
+Self::SecurityEventNotification => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/SecurityEventNotification.json")),
+Self::SignCertificate => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/SignCertificate.json")),
+Self::SignedFirmwareStatusNotification => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/SignedFirmwareStatusNotification.json")),
+Self::SignedUpdateFirmware => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/SignedUpdateFirmware.json")),
+        }
+    }
+
+    /// The JSON schema of the response payload.
+    pub fn response_schema(&self) -> &'static str {
+        match *self {
+            Self::CertificateSigned => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/CertificateSignedResponse.json")),
+Self::DeleteCertificate => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/DeleteCertificateResponse.json")),
+Self::ExtendedTriggerMessage => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/ExtendedTriggerMessageResponse.json")),
+Self::GetInstalledCertificateIds => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/GetInstalledCertificateIdsResponse.json")),
+Self::GetLog => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/GetLogResponse.json")),
+Self::InstallCertificate => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/InstallCertificateResponse.json")),
+Self::LogStatusNotification => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/LogStatusNotificationResponse.json")),
+Self::SecurityEventNotification => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/SecurityEventNotificationResponse.json")),
+Self::SignCertificate => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/SignCertificateResponse.json")),
+Self::SignedFirmwareStatusNotification => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/SignedFirmwareStatusNotificationResponse.json")),
+Self::SignedUpdateFirmware => include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/v1.6-security/SignedUpdateFirmwareResponse.json")),
+        }
+    }
+}
+
+/// Validate a request payload against the JSON schema of its action.
+#[cfg(feature = "json-schema")]
+pub fn validate(action: Action, payload: &serde_json::Value) -> Result<(), crate::ValidationError> {
+    static VALIDATORS: crate::validation::Validators = crate::validation::Validators::new();
+
+    VALIDATORS.validate(action.as_str(), action.request_schema(), payload)
+}
+
+/// Validate a response payload against the JSON schema of its action.
+#[cfg(feature = "json-schema")]
+pub fn validate_response(action: Action, payload: &serde_json::Value) -> Result<(), crate::ValidationError> {
+    static VALIDATORS: crate::validation::Validators = crate::validation::Validators::new();
+
+    VALIDATORS.validate(action.as_str(), action.response_schema(), payload)
+}
+
+impl core::str::FromStr for Action {
+    type Err = crate::UnknownActionError;
+
+    // All the match arms diverge when every action is disabled.
+    #[allow(unreachable_code, clippy::match_single_binding)]
+    fn from_str(action: &str) -> Result<Self, Self::Err> {
+        Ok(match action {
+            "CertificateSigned" => Self::CertificateSigned,
+"DeleteCertificate" => Self::DeleteCertificate,
+"ExtendedTriggerMessage" => Self::ExtendedTriggerMessage,
+"GetInstalledCertificateIds" => Self::GetInstalledCertificateIds,
+"GetLog" => Self::GetLog,
+"InstallCertificate" => Self::InstallCertificate,
+"LogStatusNotification" => Self::LogStatusNotification,
+"SecurityEventNotification" => Self::SecurityEventNotification,
+"SignCertificate" => Self::SignCertificate,
+"SignedFirmwareStatusNotification" => Self::SignedFirmwareStatusNotification,
+"SignedUpdateFirmware" => Self::SignedUpdateFirmware,
+            _ => return Err(crate::UnknownActionError(action.to_owned())),
+        })
+    }
+}
+
+impl core::fmt::Display for Action {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl crate::OcppRequest for CertificateSignedRequest {
+    type Response = CertificateSignedResponse;
+
+    const ACTION: &'static str = "CertificateSigned";
+}
+
+impl crate::OcppRequest for DeleteCertificateRequest {
+    type Response = DeleteCertificateResponse;
+
+    const ACTION: &'static str = "DeleteCertificate";
+}
+
+impl crate::OcppRequest for ExtendedTriggerMessageRequest {
+    type Response = ExtendedTriggerMessageResponse;
+
+    const ACTION: &'static str = "ExtendedTriggerMessage";
+}
+
+impl crate::OcppRequest for GetInstalledCertificateIdsRequest {
+    type Response = GetInstalledCertificateIdsResponse;
+
+    const ACTION: &'static str = "GetInstalledCertificateIds";
+}
+
+impl crate::OcppRequest for GetLogRequest {
+    type Response = GetLogResponse;
+
+    const ACTION: &'static str = "GetLog";
+}
+
+impl crate::OcppRequest for InstallCertificateRequest {
+    type Response = InstallCertificateResponse;
+
+    const ACTION: &'static str = "InstallCertificate";
+}
+
+impl crate::OcppRequest for LogStatusNotificationRequest {
+    type Response = LogStatusNotificationResponse;
+
+    const ACTION: &'static str = "LogStatusNotification";
+}
+
+impl crate::OcppRequest for SecurityEventNotificationRequest {
+    type Response = SecurityEventNotificationResponse;
+
+    const ACTION: &'static str = "SecurityEventNotification";
+}
+
+impl crate::OcppRequest for SignCertificateRequest {
+    type Response = SignCertificateResponse;
+
+    const ACTION: &'static str = "SignCertificate";
+}
+
+impl crate::OcppRequest for SignedFirmwareStatusNotificationRequest {
+    type Response = SignedFirmwareStatusNotificationResponse;
+
+    const ACTION: &'static str = "SignedFirmwareStatusNotification";
+}
+
+impl crate::OcppRequest for SignedUpdateFirmwareRequest {
+    type Response = SignedUpdateFirmwareResponse;
+
+    const ACTION: &'static str = "SignedUpdateFirmware";
+}
+
+/// Any request payload, tagged by its action.
+#[derive(Debug, Clone)]
+pub enum Request {
+    CertificateSigned(CertificateSignedRequest),
+DeleteCertificate(DeleteCertificateRequest),
+ExtendedTriggerMessage(ExtendedTriggerMessageRequest),
+GetInstalledCertificateIds(GetInstalledCertificateIdsRequest),
+GetLog(GetLogRequest),
+InstallCertificate(InstallCertificateRequest),
+LogStatusNotification(LogStatusNotificationRequest),
+SecurityEventNotification(SecurityEventNotificationRequest),
+SignCertificate(SignCertificateRequest),
+SignedFirmwareStatusNotification(SignedFirmwareStatusNotificationRequest),
+SignedUpdateFirmware(SignedUpdateFirmwareRequest),
+}
+
+impl Request {
+    pub fn action(&self) -> Action {
+        match *self {
+            Self::CertificateSigned(_) => Action::CertificateSigned,
+Self::DeleteCertificate(_) => Action::DeleteCertificate,
+Self::ExtendedTriggerMessage(_) => Action::ExtendedTriggerMessage,
+Self::GetInstalledCertificateIds(_) => Action::GetInstalledCertificateIds,
+Self::GetLog(_) => Action::GetLog,
+Self::InstallCertificate(_) => Action::InstallCertificate,
+Self::LogStatusNotification(_) => Action::LogStatusNotification,
+Self::SecurityEventNotification(_) => Action::SecurityEventNotification,
+Self::SignCertificate(_) => Action::SignCertificate,
+Self::SignedFirmwareStatusNotification(_) => Action::SignedFirmwareStatusNotification,
+Self::SignedUpdateFirmware(_) => Action::SignedUpdateFirmware,
+        }
+    }
+
+    /// Deserialize the request payload of a particular action.
+    #[allow(unreachable_code, unused_variables)]
+    pub fn from_payload(action: Action, payload: serde_json::Value) -> Result<Self, serde_json::Error> {
+        Ok(match action {
+            Action::CertificateSigned => Self::CertificateSigned(serde_json::from_value(payload)?),
+Action::DeleteCertificate => Self::DeleteCertificate(serde_json::from_value(payload)?),
+Action::ExtendedTriggerMessage => Self::ExtendedTriggerMessage(serde_json::from_value(payload)?),
+Action::GetInstalledCertificateIds => Self::GetInstalledCertificateIds(serde_json::from_value(payload)?),
+Action::GetLog => Self::GetLog(serde_json::from_value(payload)?),
+Action::InstallCertificate => Self::InstallCertificate(serde_json::from_value(payload)?),
+Action::LogStatusNotification => Self::LogStatusNotification(serde_json::from_value(payload)?),
+Action::SecurityEventNotification => Self::SecurityEventNotification(serde_json::from_value(payload)?),
+Action::SignCertificate => Self::SignCertificate(serde_json::from_value(payload)?),
+Action::SignedFirmwareStatusNotification => Self::SignedFirmwareStatusNotification(serde_json::from_value(payload)?),
+Action::SignedUpdateFirmware => Self::SignedUpdateFirmware(serde_json::from_value(payload)?),
+        })
+    }
+
+    /// Serialize the request payload.
+    pub fn to_payload(&self) -> Result<serde_json::Value, serde_json::Error> {
+        match *self {
+            Self::CertificateSigned(ref payload) => serde_json::to_value(payload),
+Self::DeleteCertificate(ref payload) => serde_json::to_value(payload),
+Self::ExtendedTriggerMessage(ref payload) => serde_json::to_value(payload),
+Self::GetInstalledCertificateIds(ref payload) => serde_json::to_value(payload),
+Self::GetLog(ref payload) => serde_json::to_value(payload),
+Self::InstallCertificate(ref payload) => serde_json::to_value(payload),
+Self::LogStatusNotification(ref payload) => serde_json::to_value(payload),
+Self::SecurityEventNotification(ref payload) => serde_json::to_value(payload),
+Self::SignCertificate(ref payload) => serde_json::to_value(payload),
+Self::SignedFirmwareStatusNotification(ref payload) => serde_json::to_value(payload),
+Self::SignedUpdateFirmware(ref payload) => serde_json::to_value(payload),
+        }
+    }
+}
+
+/// Any response payload, tagged by its action.
+#[derive(Debug, Clone)]
+pub enum Response {
+    CertificateSigned(CertificateSignedResponse),
+DeleteCertificate(DeleteCertificateResponse),
+ExtendedTriggerMessage(ExtendedTriggerMessageResponse),
+GetInstalledCertificateIds(GetInstalledCertificateIdsResponse),
+GetLog(GetLogResponse),
+InstallCertificate(InstallCertificateResponse),
+LogStatusNotification(LogStatusNotificationResponse),
+SecurityEventNotification(SecurityEventNotificationResponse),
+SignCertificate(SignCertificateResponse),
+SignedFirmwareStatusNotification(SignedFirmwareStatusNotificationResponse),
+SignedUpdateFirmware(SignedUpdateFirmwareResponse),
+}
+
+impl Response {
+    pub fn action(&self) -> Action {
+        match *self {
+            Self::CertificateSigned(_) => Action::CertificateSigned,
+Self::DeleteCertificate(_) => Action::DeleteCertificate,
+Self::ExtendedTriggerMessage(_) => Action::ExtendedTriggerMessage,
+Self::GetInstalledCertificateIds(_) => Action::GetInstalledCertificateIds,
+Self::GetLog(_) => Action::GetLog,
+Self::InstallCertificate(_) => Action::InstallCertificate,
+Self::LogStatusNotification(_) => Action::LogStatusNotification,
+Self::SecurityEventNotification(_) => Action::SecurityEventNotification,
+Self::SignCertificate(_) => Action::SignCertificate,
+Self::SignedFirmwareStatusNotification(_) => Action::SignedFirmwareStatusNotification,
+Self::SignedUpdateFirmware(_) => Action::SignedUpdateFirmware,
+        }
+    }
+
+    /// Deserialize the response payload of a particular action.
+    #[allow(unreachable_code, unused_variables)]
+    pub fn from_payload(action: Action, payload: serde_json::Value) -> Result<Self, serde_json::Error> {
+        Ok(match action {
+            Action::CertificateSigned => Self::CertificateSigned(serde_json::from_value(payload)?),
+Action::DeleteCertificate => Self::DeleteCertificate(serde_json::from_value(payload)?),
+Action::ExtendedTriggerMessage => Self::ExtendedTriggerMessage(serde_json::from_value(payload)?),
+Action::GetInstalledCertificateIds => Self::GetInstalledCertificateIds(serde_json::from_value(payload)?),
+Action::GetLog => Self::GetLog(serde_json::from_value(payload)?),
+Action::InstallCertificate => Self::InstallCertificate(serde_json::from_value(payload)?),
+Action::LogStatusNotification => Self::LogStatusNotification(serde_json::from_value(payload)?),
+Action::SecurityEventNotification => Self::SecurityEventNotification(serde_json::from_value(payload)?),
+Action::SignCertificate => Self::SignCertificate(serde_json::from_value(payload)?),
+Action::SignedFirmwareStatusNotification => Self::SignedFirmwareStatusNotification(serde_json::from_value(payload)?),
+Action::SignedUpdateFirmware => Self::SignedUpdateFirmware(serde_json::from_value(payload)?),
+        })
+    }
+
+    /// Serialize the response payload.
+    pub fn to_payload(&self) -> Result<serde_json::Value, serde_json::Error> {
+        match *self {
+            Self::CertificateSigned(ref payload) => serde_json::to_value(payload),
+Self::DeleteCertificate(ref payload) => serde_json::to_value(payload),
+Self::ExtendedTriggerMessage(ref payload) => serde_json::to_value(payload),
+Self::GetInstalledCertificateIds(ref payload) => serde_json::to_value(payload),
+Self::GetLog(ref payload) => serde_json::to_value(payload),
+Self::InstallCertificate(ref payload) => serde_json::to_value(payload),
+Self::LogStatusNotification(ref payload) => serde_json::to_value(payload),
+Self::SecurityEventNotification(ref payload) => serde_json::to_value(payload),
+Self::SignCertificate(ref payload) => serde_json::to_value(payload),
+Self::SignedFirmwareStatusNotification(ref payload) => serde_json::to_value(payload),
+Self::SignedUpdateFirmware(ref payload) => serde_json::to_value(payload),
+        }
+    }
+}
+
+/// Check that an arbitrary value of every struct and every enum is the
+/// same once serialized and deserialized back, see
+/// [`check_round_trip`][crate::test_utils::check_round_trip].
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+pub fn check_round_trips<R: rand::Rng + ?Sized>(rng: &mut R) -> Result<(), crate::test_utils::RoundTripError> {
+    crate::test_utils::check_round_trip::<CertificateHashData, _>(rng)?;
+    crate::test_utils::check_round_trip::<CertificateSignedRequest, _>(rng)?;
+    crate::test_utils::check_round_trip::<CertificateSignedResponse, _>(rng)?;
+    crate::test_utils::check_round_trip::<DeleteCertificateRequest, _>(rng)?;
+    crate::test_utils::check_round_trip::<DeleteCertificateResponse, _>(rng)?;
+    crate::test_utils::check_round_trip::<ExtendedTriggerMessageRequest, _>(rng)?;
+    crate::test_utils::check_round_trip::<ExtendedTriggerMessageResponse, _>(rng)?;
+    crate::test_utils::check_round_trip::<Firmware, _>(rng)?;
+    crate::test_utils::check_round_trip::<GetInstalledCertificateIdsRequest, _>(rng)?;
+    crate::test_utils::check_round_trip::<GetInstalledCertificateIdsResponse, _>(rng)?;
+    crate::test_utils::check_round_trip::<GetLogRequest, _>(rng)?;
+    crate::test_utils::check_round_trip::<GetLogResponse, _>(rng)?;
+    crate::test_utils::check_round_trip::<InstallCertificateRequest, _>(rng)?;
+    crate::test_utils::check_round_trip::<InstallCertificateResponse, _>(rng)?;
+    crate::test_utils::check_round_trip::<LogParameters, _>(rng)?;
+    crate::test_utils::check_round_trip::<LogStatusNotificationRequest, _>(rng)?;
+    crate::test_utils::check_round_trip::<LogStatusNotificationResponse, _>(rng)?;
+    crate::test_utils::check_round_trip::<SecurityEventNotificationRequest, _>(rng)?;
+    crate::test_utils::check_round_trip::<SecurityEventNotificationResponse, _>(rng)?;
+    crate::test_utils::check_round_trip::<SignCertificateRequest, _>(rng)?;
+    crate::test_utils::check_round_trip::<SignCertificateResponse, _>(rng)?;
+    crate::test_utils::check_round_trip::<SignedFirmwareStatusNotificationRequest, _>(rng)?;
+    crate::test_utils::check_round_trip::<SignedFirmwareStatusNotificationResponse, _>(rng)?;
+    crate::test_utils::check_round_trip::<SignedUpdateFirmwareRequest, _>(rng)?;
+    crate::test_utils::check_round_trip::<SignedUpdateFirmwareResponse, _>(rng)?;
+    crate::test_utils::check_round_trip::<CertificateHashDataHashAlgorithm, _>(rng)?;
+    crate::test_utils::check_round_trip::<CertificateSignedStatus, _>(rng)?;
+    crate::test_utils::check_round_trip::<DeleteCertificateStatus, _>(rng)?;
+    crate::test_utils::check_round_trip::<ExtendedTriggerMessageRequestedMessage, _>(rng)?;
+    crate::test_utils::check_round_trip::<ExtendedTriggerMessageStatus, _>(rng)?;
+    crate::test_utils::check_round_trip::<GetInstalledCertificateIdsCertificateType, _>(rng)?;
+    crate::test_utils::check_round_trip::<GetInstalledCertificateIdsStatus, _>(rng)?;
+    crate::test_utils::check_round_trip::<GetLogLogType, _>(rng)?;
+    crate::test_utils::check_round_trip::<GetLogStatus, _>(rng)?;
+    crate::test_utils::check_round_trip::<InstallCertificateCertificateType, _>(rng)?;
+    crate::test_utils::check_round_trip::<InstallCertificateStatus, _>(rng)?;
+    crate::test_utils::check_round_trip::<LogStatusNotificationStatus, _>(rng)?;
+    crate::test_utils::check_round_trip::<SignCertificateStatus, _>(rng)?;
+    crate::test_utils::check_round_trip::<SignedFirmwareStatusNotificationStatus, _>(rng)?;
+    crate::test_utils::check_round_trip::<SignedUpdateFirmwareStatus, _>(rng)?;
+
+    Ok(())
+}
\ No newline at end of file
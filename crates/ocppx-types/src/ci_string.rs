@@ -0,0 +1,275 @@
+//! The case-insensitive strings of OCPP 1.6, e.g. [`CiString20`], used by
+//! the generated structs of [`v1_6`][crate::v1_6].
+
+use crate::BoundedString;
+use alloc::{borrow::ToOwned, string::String};
+use core::{borrow::Borrow, fmt, ops::Deref, str::FromStr};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// The string is not a valid `CiString`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CiStringError {
+    /// Longer than `max_length` characters.
+    TooLong { max_length: usize },
+    /// Not only made of printable ASCII characters.
+    NotPrintableAscii,
+}
+
+impl fmt::Display for CiStringError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLong { max_length } => {
+                write!(formatter, "longer than {max_length} characters")
+            }
+            Self::NotPrintableAscii => write!(formatter, "not printable ASCII"),
+        }
+    }
+}
+
+impl core::error::Error for CiStringError {}
+
+/// Check that `value` fits in a `CiString` of `max_length` characters. The
+/// characters are bytes, since they are ASCII.
+fn check(value: &str, max_length: usize) -> Result<(), CiStringError> {
+    if !value
+        .bytes()
+        .all(|byte| byte == b' ' || byte.is_ascii_graphic())
+    {
+        Err(CiStringError::NotPrintableAscii)
+    } else if value.len() > max_length {
+        Err(CiStringError::TooLong { max_length })
+    } else {
+        Ok(())
+    }
+}
+
+/// How the characters of a `CiString` are stored, once checked.
+trait Storage: Deref<Target = str> + Sized {
+    fn from_checked_str(value: &str) -> Self;
+
+    fn from_checked_string(value: String) -> Self;
+
+    fn into_string(self) -> String;
+}
+
+impl Storage for String {
+    fn from_checked_str(value: &str) -> Self {
+        value.to_owned()
+    }
+
+    fn from_checked_string(value: String) -> Self {
+        value
+    }
+
+    fn into_string(self) -> String {
+        self
+    }
+}
+
+#[cfg(feature = "inline-strings")]
+impl<const N: usize> Storage for arrayvec::ArrayString<N> {
+    fn from_checked_str(value: &str) -> Self {
+        Self::from(value).expect("the length is checked")
+    }
+
+    fn from_checked_string(value: String) -> Self {
+        Self::from_checked_str(&value)
+    }
+
+    fn into_string(self) -> String {
+        self.as_str().to_owned()
+    }
+}
+
+macro_rules! ci_string {
+    ($(#[$meta:meta])* $name:ident, $max_length:literal, $storage:ty) => {
+        $(#[$meta])*
+        ///
+        /// Only printable ASCII characters are allowed: it cannot be
+        /// constructed from another string, see [`CiStringError`]. The
+        /// comparisons are case-sensitive, use [`str::eq_ignore_ascii_case`]
+        /// to compare as OCPP does.
+        #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name($storage);
+
+        impl $name {
+            /// The maximum length, in characters.
+            pub const MAX_LENGTH: usize = $max_length;
+
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl TryFrom<&str> for $name {
+            type Error = CiStringError;
+
+            fn try_from(value: &str) -> Result<Self, Self::Error> {
+                check(value, $max_length)?;
+
+                Ok(Self(Storage::from_checked_str(value)))
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = CiStringError;
+
+            fn try_from(value: String) -> Result<Self, Self::Error> {
+                check(&value, $max_length)?;
+
+                Ok(Self(Storage::from_checked_string(value)))
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = CiStringError;
+
+            fn from_str(value: &str) -> Result<Self, Self::Err> {
+                Self::try_from(value)
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                value.0.into_string()
+            }
+        }
+
+        impl Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.as_str() == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.as_str() == *other
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str(&self.0)
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.0)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct Visitor;
+
+                impl de::Visitor<'_> for Visitor {
+                    type Value = $name;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        write!(
+                            formatter,
+                            "a printable ASCII string of at most {} characters",
+                            $max_length
+                        )
+                    }
+
+                    fn visit_str<E: de::Error>(self, value: &str) -> Result<$name, E> {
+                        $name::try_from(value).map_err(E::custom)
+                    }
+
+                    fn visit_string<E: de::Error>(self, value: String) -> Result<$name, E> {
+                        $name::try_from(value).map_err(E::custom)
+                    }
+                }
+
+                deserializer.deserialize_str(Visitor)
+            }
+        }
+    };
+}
+
+ci_string!(
+    /// A `CiString20Type` of OCPP 1.6, e.g. an `idTag`.
+    CiString20,
+    20,
+    BoundedString<20>
+);
+ci_string!(
+    /// A `CiString25Type` of OCPP 1.6, e.g. a `chargePointSerialNumber`.
+    CiString25,
+    25,
+    BoundedString<25>
+);
+ci_string!(
+    /// A `CiString50Type` of OCPP 1.6, e.g. a configuration key.
+    CiString50,
+    50,
+    BoundedString<50>
+);
+ci_string!(
+    /// A `CiString255Type` of OCPP 1.6, e.g. `info` of a
+    /// `StatusNotification`.
+    CiString255,
+    255,
+    String
+);
+ci_string!(
+    /// A `CiString500Type` of OCPP 1.6, e.g. a configuration value.
+    CiString500,
+    500,
+    String
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ci_strings() {
+        let id_tag = CiString20::try_from("ABCDEF0123456789").unwrap();
+
+        assert_eq!(id_tag, "ABCDEF0123456789");
+        assert!(id_tag.eq_ignore_ascii_case("abcdef0123456789"));
+        assert_eq!(String::from(id_tag), "ABCDEF0123456789");
+
+        assert_eq!(
+            CiString20::try_from("AN-ID-TAG-LONGER-THAN-20-CHARACTERS"),
+            Err(CiStringError::TooLong { max_length: 20 })
+        );
+        assert_eq!(
+            "ÉTIQUETTE".parse::<CiString50>(),
+            Err(CiStringError::NotPrintableAscii)
+        );
+        assert_eq!(
+            CiString500::try_from("a\nb".to_owned()),
+            Err(CiStringError::NotPrintableAscii)
+        );
+
+        assert_eq!(
+            serde_json::from_str::<CiString20>(r#""A \"quoted\" tag""#).unwrap(),
+            "A \"quoted\" tag"
+        );
+        assert!(serde_json::from_str::<CiString20>(r#""AN-ID-TAG-LONGER-THAN-20""#).is_err());
+    }
+}
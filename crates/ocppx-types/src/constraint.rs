@@ -0,0 +1,155 @@
+use serde_json::Value;
+use std::{collections::BTreeMap, fmt};
+use thiserror::Error;
+use validator::{Validate, ValidationErrors, ValidationErrorsKind};
+
+/// Constraints of the schema violated by a typed payload, see
+/// [`validate_all`].
+#[derive(Error, Debug, Clone, PartialEq)]
+pub struct ConstraintError {
+    /// The violations, sorted by path, each reported once.
+    pub violations: Vec<ConstraintViolation>,
+}
+
+impl fmt::Display for ConstraintError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("the payload violates the constraints of its schema")?;
+
+        for violation in &self.violations {
+            write!(formatter, "\n  {violation}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A constraint violated by a field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstraintViolation {
+    /// JSON Pointer to the field, with the property names of the schema,
+    /// e.g. `/meterValue/0/sampledValue/0/value`.
+    pub path: String,
+    /// The violated constraint, e.g. `length`.
+    pub code: String,
+    /// The parameters of the constraint, e.g. `max`, and the invalid
+    /// `value`.
+    pub params: BTreeMap<String, Value>,
+}
+
+impl fmt::Display for ConstraintViolation {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "`{}`: violates `{}`", self.path, self.code)?;
+
+        let bounds = self
+            .params
+            .iter()
+            .filter(|(name, _)| *name != "value")
+            .map(|(name, value)| format!("{name} = {value}"))
+            .collect::<Vec<_>>();
+
+        if !bounds.is_empty() {
+            write!(formatter, " ({})", bounds.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Check all the constraints of a typed payload, e.g. the length of its
+/// strings, including in its nested structs, and report every violation
+/// at once.
+///
+/// Unlike `validate` (with the `json-schema` feature), which checks a JSON
+/// payload against its schema, it checks a payload built in Rust before it
+/// is sent.
+pub fn validate_all<T>(payload: &T) -> Result<(), ConstraintError>
+where
+    T: Validate,
+{
+    let Err(errors) = payload.validate() else {
+        return Ok(());
+    };
+
+    let mut violations = Vec::new();
+    collect_violations(&errors, "", &mut violations);
+
+    violations.sort_by(|a, b| (&a.path, &a.code).cmp(&(&b.path, &b.code)));
+    violations.dedup_by(|a, b| a.path == b.path && a.code == b.code);
+
+    Err(ConstraintError { violations })
+}
+
+fn collect_violations(
+    errors: &ValidationErrors,
+    path: &str,
+    violations: &mut Vec<ConstraintViolation>,
+) {
+    for (field, kind) in errors.errors() {
+        // Fields without a `serde(rename)` are reported with their Rust
+        // name, which is the property name but for keywords.
+        let path = format!("{path}/{}", field.trim_start_matches("r#"));
+
+        match kind {
+            ValidationErrorsKind::Field(errors) => violations.extend(errors.iter().map(|error| {
+                ConstraintViolation {
+                    path: path.clone(),
+                    code: error.code.to_string(),
+                    params: error
+                        .params
+                        .iter()
+                        .map(|(name, value)| (name.to_string(), value.clone()))
+                        .collect(),
+                }
+            })),
+            ValidationErrorsKind::Struct(errors) => collect_violations(errors, &path, violations),
+            ValidationErrorsKind::List(errors) => {
+                for (index, errors) in errors {
+                    collect_violations(errors, &format!("{path}/{index}"), violations);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2_0_1::{DataTransferRequest, SetVariablesRequest};
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_all() {
+        let request: DataTransferRequest = serde_json::from_value(json!({
+            "vendorId": "org.example.".repeat(25),
+        }))
+        .unwrap();
+        let error = validate_all(&request).unwrap_err();
+
+        assert_eq!(error.violations.len(), 1);
+        assert_eq!(
+            error.violations[0].to_string(),
+            "`/vendorId`: violates `length` (max = 255)"
+        );
+
+        let request: SetVariablesRequest = serde_json::from_value(json!({
+            "setVariableData": [
+                {
+                    "attributeValue": "60",
+                    "component": { "name": "OCPPCommCtrlr" },
+                    "variable": { "name": "HeartbeatInterval" },
+                },
+                {
+                    "attributeValue": "0".repeat(1001),
+                    "component": { "name": "OCPPCommCtrlr" },
+                    "variable": { "name": "HeartbeatInterval" },
+                },
+            ],
+        }))
+        .unwrap();
+
+        assert_eq!(
+            validate_all(&request).unwrap_err().violations[0].path,
+            "/setVariableData/1/attributeValue"
+        );
+    }
+}
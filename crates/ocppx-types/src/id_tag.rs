@@ -0,0 +1,172 @@
+//! The ID tags of OCPP 1.6, see [`IdTag`].
+
+use crate::{CiString20, CiStringError};
+use alloc::string::String;
+use core::{borrow::Borrow, fmt, ops::Deref, str::FromStr};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// An `idTag` of OCPP 1.6, e.g. the UID of an RFID card, `04E91C5A`: a
+/// [`CiString20`] in a normalized form.
+///
+/// The RFID readers do not agree on how to print a UID: `04e91c5a`,
+/// `04 E9 1C 5A`, or `04E91C5A\r\n` are the same card. The whitespaces are
+/// removed and the letters are uppercased, before the length is checked, so
+/// that the same card is the same `IdTag` whatever the reader, e.g. in the
+/// local authorization list. The ID tags received from the other side are
+/// normalized too.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IdTag(CiString20);
+
+impl IdTag {
+    /// The maximum length, in characters, once normalized.
+    pub const MAX_LENGTH: usize = CiString20::MAX_LENGTH;
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// `value` without its whitespaces, and uppercased.
+fn normalize(value: &str) -> String {
+    value
+        .chars()
+        .filter(|character| !character.is_ascii_whitespace())
+        .map(|character| character.to_ascii_uppercase())
+        .collect()
+}
+
+impl TryFrom<&str> for IdTag {
+    type Error = CiStringError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        CiString20::try_from(normalize(value)).map(Self)
+    }
+}
+
+impl TryFrom<String> for IdTag {
+    type Error = CiStringError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_str())
+    }
+}
+
+impl FromStr for IdTag {
+    type Err = CiStringError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::try_from(value)
+    }
+}
+
+impl From<IdTag> for CiString20 {
+    fn from(value: IdTag) -> Self {
+        value.0
+    }
+}
+
+impl From<IdTag> for String {
+    fn from(value: IdTag) -> Self {
+        value.0.into()
+    }
+}
+
+impl Deref for IdTag {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for IdTag {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for IdTag {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for IdTag {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for IdTag {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl fmt::Display for IdTag {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(&self.0)
+    }
+}
+
+impl Serialize for IdTag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for IdTag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl de::Visitor<'_> for Visitor {
+            type Value = IdTag;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(
+                    formatter,
+                    "an ID tag of at most {} characters",
+                    IdTag::MAX_LENGTH
+                )
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<IdTag, E> {
+                IdTag::try_from(value).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_str(Visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_id_tag() {
+        let id_tag = IdTag::try_from("04E91C5A").unwrap();
+
+        for read in ["04e91c5a", "04 E9 1C 5A", " 04e9 1c5A\r\n"] {
+            assert_eq!(IdTag::try_from(read).unwrap(), id_tag);
+        }
+        assert_eq!(id_tag, "04E91C5A");
+
+        // The length is the one of the normalized ID tag.
+        assert!(IdTag::try_from("04 E9 1C 5A 04 E9 1C 5A 04").is_ok());
+        assert_eq!(
+            IdTag::try_from("04E91C5A04E91C5A04E91C5A"),
+            Err(CiStringError::TooLong { max_length: 20 })
+        );
+        assert_eq!(
+            IdTag::try_from("BADGE-É"),
+            Err(CiStringError::NotPrintableAscii)
+        );
+
+        assert_eq!(
+            serde_json::from_str::<IdTag>(r#""04 e9 1c 5a""#).unwrap(),
+            id_tag
+        );
+        assert_eq!(serde_json::to_string(&id_tag).unwrap(), r#""04E91C5A""#);
+    }
+}
@@ -1,3 +1,499 @@
+//! The OCPP payloads, generated from their JSON schemas, e.g.
+//! [`v1_6::BootNotificationRequest`].
+//!
+//! Without the `std` feature (enabled by default), the crate is `no_std`
+//! and only needs `alloc`, e.g. for a charge controller: the constraints of
+//! the schemas are not checked, and the modules needing `std` are left out.
+//! Without the `chrono` and `url` features (enabled by default), the
+//! `date-time` and `uri` properties are [`String`]s, see [`DateTime`] and
+//! [`Url`]. With the `inline-strings` feature, the short strings are stored
+//! inline instead of allocated, see [`BoundedString`]. With the `decimal`
+//! feature, the `number` properties are decimals, see [`Number`].
+//!
+//! The strings of OCPP 1.6 are [`CiString20`]s and the like, and its ID
+//! tags are normalized [`IdTag`]s.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use alloc::string::String;
+use core::fmt;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// A `date-time` property of the schemas.
+#[cfg(feature = "chrono")]
+pub type DateTime = chrono::DateTime<chrono::Utc>;
+
+/// A `date-time` property of the schemas, in the RFC 3339 format, e.g.
+/// `2013-02-01T20:53:32.486Z`, without the `chrono` feature.
+#[cfg(not(feature = "chrono"))]
+pub type DateTime = String;
+
+/// A `uri` property of the schemas.
+#[cfg(feature = "url")]
+pub type Url = url::Url;
+
+/// A `uri` property of the schemas, without the `url` feature.
+#[cfg(not(feature = "url"))]
+pub type Url = String;
+
+/// A `number` property of the schemas, e.g. the limit of a charging
+/// schedule period, with the `decimal` feature.
+#[cfg(feature = "decimal")]
+pub type Number = rust_decimal::Decimal;
+
+/// A `number` property of the schemas, without the `decimal` feature.
+#[cfg(not(feature = "decimal"))]
+pub type Number = f64;
+
+/// The [`Number`] closest to `value`, zero if there is none, e.g. for
+/// `NaN` with the `decimal` feature.
+pub fn number_from_f64(value: f64) -> Number {
+    #[cfg(feature = "decimal")]
+    return rust_decimal::prelude::FromPrimitive::from_f64(value).unwrap_or_default();
+
+    #[cfg(not(feature = "decimal"))]
+    value
+}
+
+/// The `f64` closest to `number`.
+pub fn number_to_f64(number: Number) -> f64 {
+    #[cfg(feature = "decimal")]
+    return rust_decimal::prelude::ToPrimitive::to_f64(&number).unwrap_or_default();
+
+    #[cfg(not(feature = "decimal"))]
+    number
+}
+
+/// A string property of at most `N` characters, e.g. `idTag`, stored
+/// inline with the `inline-strings` feature.
+#[cfg(feature = "inline-strings")]
+pub type BoundedString<const N: usize> = arrayvec::ArrayString<N>;
+
+/// A string property of at most `N` characters, e.g. `idTag`, without the
+/// `inline-strings` feature: its length is checked by the validation.
+#[cfg(not(feature = "inline-strings"))]
+pub type BoundedString<const N: usize> = String;
+
+/// A [`BoundedString`] from `value`, `None` if `value` is too long to be
+/// stored inline.
+pub fn bounded_string<const N: usize>(value: &str) -> Option<BoundedString<N>> {
+    #[cfg(feature = "inline-strings")]
+    return arrayvec::ArrayString::from(value).ok();
+
+    #[cfg(not(feature = "inline-strings"))]
+    Some(value.into())
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    /// Whether [`with_nulls`] is running on this thread.
+    static WITH_NULLS: core::cell::Cell<bool> = const { core::cell::Cell::new(false) };
+}
+
+/// Run `serialize` with the non-required fields that are `None` serialized
+/// as `null`s, instead of being skipped, e.g. for a peer rejecting the
+/// missing fields. The other serializations are not changed.
+///
+/// ```
+/// use ocppx_types::v1_6::AuthorizeResponse;
+/// use serde_json::json;
+///
+/// let response: AuthorizeResponse =
+///     serde_json::from_value(json!({ "idTagInfo": { "status": "Accepted" } })).unwrap();
+///
+/// assert_eq!(
+///     ocppx_types::with_nulls(|| serde_json::to_value(&response)).unwrap(),
+///     json!({ "idTagInfo": { "status": "Accepted", "expiryDate": null, "parentIdTag": null } }),
+/// );
+/// ```
+#[cfg(feature = "std")]
+pub fn with_nulls<R>(serialize: impl FnOnce() -> R) -> R {
+    /// Restore the previous mode, even if `serialize` panics.
+    struct Restore(bool);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            WITH_NULLS.set(self.0);
+        }
+    }
+
+    let _restore = Restore(WITH_NULLS.replace(true));
+
+    serialize()
+}
+
+/// Whether a non-required field is skipped when serialized, see
+/// [`with_nulls`].
+fn skip_none<T>(value: &Option<T>) -> bool {
+    #[cfg(feature = "std")]
+    return value.is_none() && !WITH_NULLS.get();
+
+    #[cfg(not(feature = "std"))]
+    value.is_none()
+}
+
+/// A request payload, paired with its response payload.
+///
+/// It is implemented for all the generated request payloads, e.g.
+/// `v1_6::BootNotificationRequest` has `v1_6::BootNotificationResponse` as
+/// response, and `BootNotification` as action.
+pub trait OcppRequest: Serialize + DeserializeOwned {
+    type Response: Serialize + DeserializeOwned;
+
+    /// The action name, as sent in a `Call`.
+    const ACTION: &'static str;
+}
+
+/// The action name is not part of the OCPP version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownActionError(pub String);
+
+impl fmt::Display for UnknownActionError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "unknown action `{}`", self.0)
+    }
+}
+
+impl core::error::Error for UnknownActionError {}
+
+/// The string is not a variant of a generated enum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownVariantError {
+    pub enum_name: &'static str,
+    pub variant: String,
+}
+
+impl fmt::Display for UnknownVariantError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            formatter,
+            "unknown variant `{}` for `{}`",
+            self.variant, self.enum_name
+        )
+    }
+}
+
+impl core::error::Error for UnknownVariantError {}
+
+mod ci_string;
+#[cfg(feature = "std")]
+mod constraint;
+mod id_tag;
+#[cfg(feature = "protobuf")]
+mod proto;
+#[cfg(any(all(test, feature = "chrono", feature = "url"), feature = "test-utils"))]
+pub mod test_utils;
+#[cfg(feature = "json-schema")]
+mod validation;
+
+pub use ci_string::{CiString20, CiString25, CiString255, CiString50, CiString500, CiStringError};
+#[cfg(feature = "std")]
+pub use constraint::{validate_all, ConstraintError, ConstraintViolation};
+pub use id_tag::IdTag;
+#[cfg(feature = "protobuf")]
+pub use proto::{ProtoField, ProtoMessage, ProtoType};
+
+#[cfg(feature = "json-schema")]
+pub use validation::{ValidationError, Violation};
+
 pub mod v1_6 {
     include!(env!("OCPPX_TYPES_SCHEMA_V16"));
+
+    /// The protobuf definitions of the types, in the `ocppx.v1_6` package,
+    /// with a `Request` and a `Response` message holding any payload, e.g.
+    /// for a gRPC gateway. To be saved as `ocppx/v1_6.proto`.
+    #[cfg(feature = "protobuf")]
+    pub const PROTO: &str = include_str!(env!("OCPPX_TYPES_PROTO_V16"));
+
+    #[cfg(feature = "protobuf")]
+    include!(env!("OCPPX_TYPES_PROTO_DESCRIPTORS_V16"));
+
+    /// The TypeScript definitions of the types, as they are on the wire,
+    /// with the `Requests` and `Responses` interfaces mapping the actions
+    /// to their payloads, e.g. for a Tauri frontend. To be saved as
+    /// `ocpp-1.6.d.ts`.
+    #[cfg(feature = "typescript")]
+    pub const TYPESCRIPT: &str = include_str!(env!("OCPPX_TYPES_TYPESCRIPT_V16"));
+
+    #[cfg(all(feature = "core", feature = "std"))]
+    mod data_transfer;
+    #[cfg(feature = "core")]
+    mod meter_value;
+
+    #[cfg(all(feature = "core", feature = "std"))]
+    pub use data_transfer::{DataTransferMessage, DataTransferRegistry};
+    #[cfg(feature = "core")]
+    pub use meter_value::MeterValueSampler;
+}
+
+/// The messages added to OCPP 1.6 by the Security Whitepaper, used by the
+/// security profiles 2 and 3.
+pub mod v1_6_security {
+    include!(env!("OCPPX_TYPES_SCHEMA_V16Security"));
+
+    /// The protobuf definitions of the types, in the `ocppx.v1_6_security`
+    /// package, see [`v1_6::PROTO`][super::v1_6::PROTO].
+    #[cfg(feature = "protobuf")]
+    pub const PROTO: &str = include_str!(env!("OCPPX_TYPES_PROTO_V16Security"));
+
+    #[cfg(feature = "protobuf")]
+    include!(env!("OCPPX_TYPES_PROTO_DESCRIPTORS_V16Security"));
+
+    /// The TypeScript definitions of the types, see
+    /// [`v1_6::TYPESCRIPT`][super::v1_6::TYPESCRIPT].
+    #[cfg(feature = "typescript")]
+    pub const TYPESCRIPT: &str = include_str!(env!("OCPPX_TYPES_TYPESCRIPT_V16Security"));
+}
+
+pub mod v2_0_1 {
+    include!(env!("OCPPX_TYPES_SCHEMA_V201"));
+
+    /// The protobuf definitions of the types, in the `ocppx.v2_0_1`
+    /// package, see [`v1_6::PROTO`][super::v1_6::PROTO].
+    #[cfg(feature = "protobuf")]
+    pub const PROTO: &str = include_str!(env!("OCPPX_TYPES_PROTO_V201"));
+
+    #[cfg(feature = "protobuf")]
+    include!(env!("OCPPX_TYPES_PROTO_DESCRIPTORS_V201"));
+
+    /// The TypeScript definitions of the types, see
+    /// [`v1_6::TYPESCRIPT`][super::v1_6::TYPESCRIPT].
+    #[cfg(feature = "typescript")]
+    pub const TYPESCRIPT: &str = include_str!(env!("OCPPX_TYPES_TYPESCRIPT_V201"));
+
+    #[cfg(all(feature = "std", feature = "chrono"))]
+    mod transaction_event;
+
+    #[cfg(all(feature = "std", feature = "chrono"))]
+    pub use transaction_event::{TransactionEventBuilder, TransactionEventError};
+}
+
+#[cfg(all(test, feature = "core"))]
+mod tests {
+    use super::v1_6::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_property_names_are_kept() {
+        let payload = json!({
+            "chargePointVendor": "VendorX",
+            "chargePointModel": "SingleSocketCharger",
+            "firmwareVersion": "1.0.0",
+        });
+        let request: BootNotificationRequest = serde_json::from_value(payload.clone()).unwrap();
+
+        assert_eq!(request.charge_point_vendor.as_str(), "VendorX");
+        assert_eq!(serde_json::to_value(&request).unwrap(), payload);
+    }
+
+    #[test]
+    fn test_with_nulls() {
+        let request = HeartbeatRequest::builder().build();
+        let response: StopTransactionResponse = serde_json::from_value(json!({})).unwrap();
+
+        assert_eq!(
+            crate::with_nulls(|| serde_json::to_value(&response)).unwrap(),
+            json!({ "idTagInfo": null })
+        );
+        assert_eq!(
+            crate::with_nulls(|| serde_json::to_value(&request)).unwrap(),
+            json!({})
+        );
+
+        // Only the serializations run by `with_nulls` are changed.
+        assert_eq!(serde_json::to_value(&response).unwrap(), json!({}));
+    }
+
+    #[test]
+    fn test_builder() {
+        let request = MeterValuesRequest::builder()
+            .connector_id(1)
+            .meter_value(vec![MeterValue::builder()
+                .timestamp(
+                    "2013-02-01T20:53:32.486Z"
+                        .parse::<crate::DateTime>()
+                        .unwrap(),
+                )
+                .sampled_value(vec![SampledValue::builder().value("12.34").build()])
+                .build()])
+            .build();
+
+        assert_eq!(request.connector_id, 1);
+        assert!(request.transaction_id.is_none());
+        assert_eq!(request.meter_value[0].sampled_value[0].value, "12.34");
+    }
+
+    #[test]
+    fn test_enum_strings() {
+        assert_eq!(
+            "Energy.Active.Import.Register".parse::<SampledValueMeasurand>(),
+            Ok(SampledValueMeasurand::EnergyActiveImportRegister)
+        );
+        assert_eq!(
+            SampledValueMeasurand::PowerOffered.to_string(),
+            "Power.Offered"
+        );
+        assert_eq!(
+            SampledValueMeasurand::try_from("Foo"),
+            Err(super::UnknownVariantError {
+                enum_name: "SampledValueMeasurand",
+                variant: "Foo".to_owned(),
+            })
+        );
+
+        assert_eq!(
+            SampledValueMeasurand::iter().count(),
+            SampledValueMeasurand::VARIANTS.len()
+        );
+        assert!(SampledValueMeasurand::iter()
+            .all(|measurand| measurand.as_str().parse() == Ok(measurand)));
+        assert!(Action::iter().all(|action| action.as_str().parse() == Ok(action)));
+    }
+
+    #[test]
+    fn test_enums_are_prefixed_and_deduplicated() {
+        let response: BootNotificationResponse = serde_json::from_value(json!({
+            "status": "Pending",
+            "currentTime": "2013-02-01T20:53:32.486Z",
+            "interval": 300,
+        }))
+        .unwrap();
+        assert_eq!(response.status, BootNotificationStatus::Pending);
+
+        // `ResetStatus` and `ClearCacheStatus` have the same variants.
+        let status: ResetStatus = ClearCacheStatus::Rejected;
+        assert_eq!(status.to_string(), "Rejected");
+    }
+
+    #[test]
+    fn test_numbers_are_not_truncated() {
+        use super::v2_0_1;
+
+        let payload = json!({
+            "evseId": 1,
+            "meterValue": [{
+                "timestamp": "2013-02-01T20:53:32.486Z",
+                "sampledValue": [
+                    {
+                        "value": 12.34,
+                        "measurand": "Energy.Active.Import.Register",
+                        "unitOfMeasure": { "unit": "kWh" },
+                    },
+                    { "value": 7.4, "measurand": "Power.Active.Import" },
+                ],
+            }],
+        });
+        let request: v2_0_1::MeterValuesRequest = serde_json::from_value(payload.clone()).unwrap();
+
+        assert_eq!(request.evse_id, 1);
+        assert_eq!(serde_json::to_value(&request).unwrap(), payload);
+    }
+
+    /// The generated code must not change from one build to another. Run
+    /// with `OCPPX_TYPES_UPDATE_GOLDEN=1` to update the golden file after
+    /// a change of the code generator.
+    #[test]
+    #[cfg(not(any(feature = "extra-fields", feature = "inline-strings")))]
+    fn test_generated_code_is_stable() {
+        let generated = include_str!(env!("OCPPX_TYPES_SCHEMA_V16Security"));
+        let golden_path = concat!(env!("CARGO_MANIFEST_DIR"), "/golden/v1_6_security.rs");
+
+        if std::env::var_os("OCPPX_TYPES_UPDATE_GOLDEN").is_some() {
+            std::fs::write(golden_path, generated).unwrap();
+        }
+
+        assert!(
+            generated == std::fs::read_to_string(golden_path).unwrap(),
+            "the generated code differs from `golden/v1_6_security.rs`"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "extra-fields")]
+    fn test_extra_fields_are_kept() {
+        let payload = json!({
+            "chargePointVendor": "ocppx",
+            "chargePointModel": "sim",
+            "vendorExtension": { "firmwareChannel": "beta" },
+        });
+        let request: BootNotificationRequest = serde_json::from_value(payload.clone()).unwrap();
+
+        assert_eq!(request.extra["vendorExtension"]["firmwareChannel"], "beta");
+        assert_eq!(serde_json::to_value(&request).unwrap(), payload);
+    }
+
+    #[test]
+    #[cfg(feature = "inline-strings")]
+    fn test_inline_strings() {
+        let id_token: super::v2_0_1::IdToken =
+            serde_json::from_value(json!({ "idToken": "ABCDEF0123456789", "type": "ISO14443" }))
+                .unwrap();
+
+        assert_eq!(id_token.id_token.as_str(), "ABCDEF0123456789");
+        assert_eq!(id_token.id_token.capacity(), 36);
+
+        // The capacity is the maximum length of the property.
+        assert!(serde_json::from_value::<super::v2_0_1::IdToken>(json!({
+            "idToken": "AN-ID-TOKEN-LONGER-THAN-36-CHARACTERS",
+            "type": "ISO14443",
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn test_security_messages() {
+        use super::{v1_6_security, OcppRequest};
+
+        let request: v1_6_security::SecurityEventNotificationRequest =
+            serde_json::from_value(json!({
+                "type": "FirmwareUpdated",
+                "timestamp": "2013-02-01T20:53:32.486Z",
+            }))
+            .unwrap();
+
+        assert_eq!(request.r#type.as_str(), "FirmwareUpdated");
+        assert_eq!(
+            v1_6_security::SecurityEventNotificationRequest::ACTION,
+            "SecurityEventNotification"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "protobuf")]
+    fn test_proto() {
+        let proto = super::v1_6::PROTO;
+
+        assert!(proto.contains("package ocppx.v1_6;"));
+        assert!(proto.contains(
+            "message BootNotificationResponse {\n  google.protobuf.Timestamp current_time = 1;\n  int32 interval = 2;\n  BootNotificationStatus status = 3;\n}"
+        ));
+        assert!(proto.contains("  optional string firmware_version = 5;\n"));
+        assert!(proto.contains("  repeated MeterValue meter_value = 2;\n"));
+        assert!(proto.contains(
+            "  STATUS_NOTIFICATION_ERROR_CODE_EV_COMMUNICATION_ERROR = 2; // `EVCommunicationError` on the wire.\n"
+        ));
+        assert!(proto.contains("    AuthorizeRequest authorize = 1;\n"));
+    }
+
+    #[test]
+    #[cfg(feature = "typescript")]
+    fn test_typescript() {
+        let typescript = super::v1_6::TYPESCRIPT;
+
+        // The extra fields are kept with `extra-fields`.
+        assert!(typescript.contains(&format!(
+            "export interface BootNotificationResponse {{\n  currentTime: string;\n  interval: number;\n  status: BootNotificationStatus;\n{}}}",
+            if cfg!(feature = "extra-fields") {
+                "  [property: string]: unknown;\n"
+            } else {
+                ""
+            }
+        )));
+        assert!(typescript.contains("  firmwareVersion?: string;\n"));
+        assert!(typescript.contains("  meterValue: MeterValue[];\n"));
+        assert!(typescript.contains("export type ResetType = \"Hard\" | \"Soft\";\n"));
+        assert!(typescript.contains("  Authorize: AuthorizeRequest;\n"));
+    }
 }
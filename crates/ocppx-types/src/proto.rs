@@ -0,0 +1,50 @@
+/// A message of the protobuf definitions of a version, e.g.
+/// `v1_6::PROTO_MESSAGES`, to transcode the payloads between JSON and
+/// protobuf.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtoMessage {
+    pub name: &'static str,
+    pub fields: &'static [ProtoField],
+}
+
+/// A field of a [`ProtoMessage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtoField {
+    pub number: u32,
+    /// The name of the property, on the JSON wire, e.g. `idTag`.
+    pub json_name: &'static str,
+    pub ty: ProtoType,
+    pub repeated: bool,
+    /// Not `optional`: without presence, the default value is not on the
+    /// protobuf wire.
+    pub required: bool,
+}
+
+/// The type of a [`ProtoField`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtoType {
+    String,
+    /// A `string`, holding a JSON `number`: a decimal would lose its
+    /// precision as a `double`.
+    Decimal,
+    Bool,
+    Int32,
+    Int64,
+    Double,
+    /// A `google.protobuf.Timestamp`, holding an RFC 3339 date.
+    Timestamp,
+    /// A `google.protobuf.Value`, holding any JSON value.
+    Value,
+    /// A [`ProtoMessage`], by name.
+    Message(&'static str),
+    /// An enum, with the variants on the JSON wire: the variant `n` is the
+    /// value `n + 1`, `0` is unspecified.
+    Enum(&'static [&'static str]),
+}
+
+impl ProtoMessage {
+    /// The message `name` among `messages`.
+    pub fn find(messages: &'static [ProtoMessage], name: &str) -> Option<&'static ProtoMessage> {
+        messages.iter().find(|message| message.name == name)
+    }
+}
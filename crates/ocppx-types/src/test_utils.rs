@@ -0,0 +1,248 @@
+//! Arbitrary values of the generated types, for property-based tests.
+//!
+//! Every generated struct and enum implements [`Arbitrary`], and each
+//! version has a `check_round_trips` function, e.g.
+//! [`v1_6::check_round_trips`][crate::v1_6::check_round_trips], checking
+//! that the arbitrary values of all its types survive a round trip through
+//! JSON. The values are not necessarily valid against the constraints of
+//! the schemas, e.g. the length of the strings.
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+/// A type whose values can be generated at random.
+pub trait Arbitrary: Sized {
+    fn arbitrary<R: Rng + ?Sized>(rng: &mut R) -> Self;
+}
+
+impl Arbitrary for bool {
+    fn arbitrary<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        rng.random()
+    }
+}
+
+impl Arbitrary for i32 {
+    fn arbitrary<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        // The bounds are more likely than at random.
+        match rng.random_range(0..8) {
+            0 => [0, i32::MIN, i32::MAX][rng.random_range(0..3)],
+            _ => rng.random(),
+        }
+    }
+}
+
+impl Arbitrary for i64 {
+    fn arbitrary<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        match rng.random_range(0..8) {
+            0 => [0, i64::MIN, i64::MAX][rng.random_range(0..3)],
+            _ => rng.random(),
+        }
+    }
+}
+
+impl Arbitrary for f64 {
+    fn arbitrary<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        // Exactly representable in decimal, so that the comparison is not
+        // about the precision of the float parser.
+        f64::from(rng.random::<i32>()) / 8.
+    }
+}
+
+#[cfg(feature = "decimal")]
+impl Arbitrary for rust_decimal::Decimal {
+    fn arbitrary<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        rust_decimal::Decimal::new(rng.random::<i32>().into(), rng.random_range(0..4))
+    }
+}
+
+impl Arbitrary for String {
+    fn arbitrary<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        // The characters to escape, and the multi-byte ones.
+        const CHARACTERS: &[char] = &['a', 'Z', '0', ' ', '"', '\\', '\n', '\u{1}', 'é', '🔌'];
+
+        (0..rng.random_range(0..12))
+            .map(|_| CHARACTERS[rng.random_range(0..CHARACTERS.len())])
+            .collect()
+    }
+}
+
+#[cfg(feature = "inline-strings")]
+impl<const CAPACITY: usize> Arbitrary for arrayvec::ArrayString<CAPACITY> {
+    fn arbitrary<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let mut string = Self::new();
+
+        // Stop at the first character that does not fit.
+        for character in String::arbitrary(rng).chars() {
+            if string.try_push(character).is_err() {
+                break;
+            }
+        }
+
+        string
+    }
+}
+
+macro_rules! arbitrary_ci_strings {
+    ($($name:ident),*) => {
+        $(
+            impl Arbitrary for crate::$name {
+                fn arbitrary<R: Rng + ?Sized>(rng: &mut R) -> Self {
+                    // Printable ASCII only, with the characters to escape.
+                    const CHARACTERS: &[char] = &['a', 'Z', '0', ' ', '"', '\\'];
+
+                    (0..rng.random_range(0..12.min(Self::MAX_LENGTH)))
+                        .map(|_| CHARACTERS[rng.random_range(0..CHARACTERS.len())])
+                        .collect::<String>()
+                        .try_into()
+                        .expect("a valid `CiString`")
+                }
+            }
+        )*
+    };
+}
+
+arbitrary_ci_strings!(CiString20, CiString25, CiString50, CiString255, CiString500);
+
+impl Arbitrary for crate::IdTag {
+    fn arbitrary<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        // Normalized already, to be the same once deserialized.
+        const CHARACTERS: &[char] = &['A', 'Z', '0', '9', '"', '\\'];
+
+        (0..rng.random_range(0..12))
+            .map(|_| CHARACTERS[rng.random_range(0..CHARACTERS.len())])
+            .collect::<String>()
+            .try_into()
+            .expect("a valid `IdTag`")
+    }
+}
+
+impl Arbitrary for DateTime<Utc> {
+    fn arbitrary<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        // Up to the end of 9999, the last year of RFC 3339.
+        DateTime::from_timestamp_millis(rng.random_range(0..253_402_300_800_000))
+            .unwrap_or_default()
+    }
+}
+
+impl Arbitrary for url::Url {
+    fn arbitrary<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let base = ["https://example.org/", "ftp://user@example.org:2121/"];
+
+        url::Url::parse(base[rng.random_range(0..base.len())])
+            .and_then(|base| base.join(&rng.random::<u32>().to_string()))
+            .expect("The URL is valid")
+    }
+}
+
+impl Arbitrary for Value {
+    /// Anything but `null` at the top, which would be deserialized as
+    /// `None` in an `Option<Value>`.
+    fn arbitrary<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        fn value<R: Rng + ?Sized>(rng: &mut R, depth: usize) -> Value {
+            match rng.random_range(if depth == 0 { 1 } else { 0 }..if depth < 2 { 6 } else { 4 }) {
+                0 => Value::Null,
+                1 => Value::Bool(rng.random()),
+                2 => Value::from(i64::arbitrary(rng)),
+                3 => Value::String(String::arbitrary(rng)),
+                4 => Value::Array(
+                    (0..rng.random_range(0..3))
+                        .map(|_| value(rng, depth + 1))
+                        .collect(),
+                ),
+                _ => Value::Object(
+                    (0..rng.random_range(0..3))
+                        .map(|_| (String::arbitrary(rng), value(rng, depth + 1)))
+                        .collect(),
+                ),
+            }
+        }
+
+        value(rng, 0)
+    }
+}
+
+impl<T: Arbitrary> Arbitrary for Option<T> {
+    fn arbitrary<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        rng.random::<bool>().then(|| T::arbitrary(rng))
+    }
+}
+
+impl<T: Arbitrary> Arbitrary for Vec<T> {
+    fn arbitrary<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        (0..rng.random_range(0..3))
+            .map(|_| T::arbitrary(rng))
+            .collect()
+    }
+}
+
+/// A value is not the same once serialized and deserialized back.
+#[derive(Error, Debug)]
+pub enum RoundTripError {
+    #[error("cannot serialize or deserialize a `{type_name}`: `{json}`")]
+    Json {
+        type_name: &'static str,
+        json: String,
+        #[source]
+        error: serde_json::Error,
+    },
+
+    #[error("a `{type_name}` differs once deserialized: `{json}` became `{round_tripped}`")]
+    Mismatch {
+        type_name: &'static str,
+        json: String,
+        round_tripped: String,
+    },
+}
+
+/// Check that an arbitrary `T` is the same once serialized and deserialized
+/// back, e.g. that no property is renamed one way and not the other, or
+/// that no optional property becomes required.
+pub fn check_round_trip<T, R>(rng: &mut R) -> Result<(), RoundTripError>
+where
+    T: Arbitrary + Serialize + DeserializeOwned,
+    R: Rng + ?Sized,
+{
+    let type_name = std::any::type_name::<T>();
+    let json_error = |json: &str| {
+        let json = json.to_owned();
+
+        move |error| RoundTripError::Json {
+            type_name,
+            json,
+            error,
+        }
+    };
+
+    let json = serde_json::to_string(&T::arbitrary(rng)).map_err(json_error(""))?;
+    let value = serde_json::from_str::<T>(&json).map_err(json_error(&json))?;
+    let round_tripped = serde_json::to_string(&value).map_err(json_error(&json))?;
+
+    if round_tripped != json {
+        return Err(RoundTripError::Mismatch {
+            type_name,
+            json,
+            round_tripped,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_round_trips() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for _ in 0..256 {
+            crate::v1_6::check_round_trips(&mut rng).unwrap();
+            crate::v1_6_security::check_round_trips(&mut rng).unwrap();
+            crate::v2_0_1::check_round_trips(&mut rng).unwrap();
+        }
+    }
+}
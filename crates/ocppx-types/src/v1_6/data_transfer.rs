@@ -0,0 +1,245 @@
+use super::{DataTransferRequest, DataTransferResponse, DataTransferStatus};
+use crate::{CiString255, CiString50};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, fmt};
+
+/// A vendor extension carried by `DataTransfer`, paired with its response.
+///
+/// The payloads are sent as JSON in the `data` of `DataTransfer`. A payload
+/// serialized as `null`, e.g. `()`, is sent without `data`.
+///
+/// ```
+/// # use ocppx_types::v1_6::*;
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Serialize, Deserialize)]
+/// #[serde(rename_all = "camelCase")]
+/// struct GetTariff {
+///     connector_id: i32,
+/// }
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Tariff {
+///     price: f64,
+/// }
+///
+/// impl DataTransferMessage for GetTariff {
+///     type Response = Tariff;
+///
+///     const VENDOR_ID: &'static str = "org.example";
+///     const MESSAGE_ID: Option<&'static str> = Some("GetTariff");
+/// }
+///
+/// let request = GetTariff { connector_id: 1 }.to_request().unwrap();
+///
+/// assert_eq!(request.data.as_deref(), Some(r#"{"connectorId":1}"#));
+/// ```
+pub trait DataTransferMessage: Serialize + DeserializeOwned {
+    type Response: Serialize + DeserializeOwned;
+
+    /// The `vendorId`, usually a reversed DNS name.
+    const VENDOR_ID: &'static str;
+
+    /// The `messageId`, if the vendor has several messages.
+    const MESSAGE_ID: Option<&'static str>;
+
+    /// The `DataTransfer` carrying this message.
+    fn to_request(&self) -> serde_json::Result<DataTransferRequest> {
+        let invalid = |name, value, error| {
+            serde::ser::Error::custom(format!("invalid `{name}` `{value}`: {error}"))
+        };
+        let vendor_id = CiString255::try_from(Self::VENDOR_ID)
+            .map_err(|error| invalid("vendorId", Self::VENDOR_ID, error))?;
+        let message_id = Self::MESSAGE_ID
+            .map(|message_id| {
+                CiString50::try_from(message_id)
+                    .map_err(|error| invalid("messageId", message_id, error))
+            })
+            .transpose()?;
+
+        Ok(DataTransferRequest::builder()
+            .vendor_id(vendor_id)
+            .message_id_opt(message_id)
+            .data_opt(to_data(self)?)
+            .build())
+    }
+
+    /// The response carried by the response of the `DataTransfer`, if it
+    /// has been accepted.
+    fn from_response(
+        response: &DataTransferResponse,
+    ) -> serde_json::Result<Option<Self::Response>> {
+        match response.status {
+            DataTransferStatus::Accepted => from_data(response.data.as_deref()).map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+type Handler = Box<dyn Fn(Option<&str>) -> DataTransferResponse + Send + Sync>;
+
+/// Handle the `DataTransfer`s with the handlers of the
+/// [`DataTransferMessage`]s, by `vendorId` and `messageId`.
+///
+/// The `DataTransfer`s of an unknown vendor are answered with
+/// `UnknownVendorId`, those of a known vendor but with an unknown message
+/// with `UnknownMessageId`, and those whose `data` cannot be deserialized
+/// with `Rejected`.
+#[derive(Default)]
+pub struct DataTransferRegistry {
+    vendors: HashMap<&'static str, HashMap<Option<&'static str>, Handler>>,
+}
+
+impl DataTransferRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle the `M` messages. The handler answers with the response to
+    /// accept the message, or with the status to refuse it, e.g.
+    /// `Rejected`. A previous handler of `M` is replaced.
+    pub fn register<M, F>(&mut self, handler: F) -> &mut Self
+    where
+        M: DataTransferMessage,
+        F: Fn(M) -> Result<M::Response, DataTransferStatus> + Send + Sync + 'static,
+    {
+        let handler = move |data: Option<&str>| {
+            let response = from_data(data)
+                .map_err(|_| DataTransferStatus::Rejected)
+                .and_then(&handler)
+                .and_then(|response| to_data(&response).map_err(|_| DataTransferStatus::Rejected));
+
+            match response {
+                Ok(data) => DataTransferResponse::builder()
+                    .status(DataTransferStatus::Accepted)
+                    .data_opt(data)
+                    .build(),
+                Err(status) => DataTransferResponse::builder().status(status).build(),
+            }
+        };
+
+        self.vendors
+            .entry(M::VENDOR_ID)
+            .or_default()
+            .insert(M::MESSAGE_ID, Box::new(handler));
+
+        self
+    }
+
+    /// Handle a `DataTransfer`.
+    pub fn handle(&self, request: &DataTransferRequest) -> DataTransferResponse {
+        let status = |status| DataTransferResponse::builder().status(status).build();
+
+        let Some(messages) = self.vendors.get(request.vendor_id.as_str()) else {
+            return status(DataTransferStatus::UnknownVendorId);
+        };
+
+        match messages.get(&request.message_id.as_deref()) {
+            Some(handler) => handler(request.data.as_deref()),
+            None => status(DataTransferStatus::UnknownMessageId),
+        }
+    }
+}
+
+impl fmt::Debug for DataTransferRegistry {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_map()
+            .entries(
+                self.vendors
+                    .iter()
+                    .map(|(vendor_id, messages)| (vendor_id, messages.keys().collect::<Vec<_>>())),
+            )
+            .finish()
+    }
+}
+
+fn to_data<T>(payload: &T) -> serde_json::Result<Option<String>>
+where
+    T: Serialize,
+{
+    Ok(match serde_json::to_value(payload)? {
+        Value::Null => None,
+        value => Some(value.to_string()),
+    })
+}
+
+fn from_data<T>(data: Option<&str>) -> serde_json::Result<T>
+where
+    T: DeserializeOwned,
+{
+    serde_json::from_str(data.unwrap_or("null"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct SetPrice {
+        connector_id: i32,
+        price: f64,
+    }
+
+    impl DataTransferMessage for SetPrice {
+        type Response = ();
+
+        const VENDOR_ID: &'static str = "org.example";
+        const MESSAGE_ID: Option<&'static str> = Some("SetPrice");
+    }
+
+    #[test]
+    fn test_data_transfer_registry() {
+        let mut registry = DataTransferRegistry::new();
+        registry.register(|message: SetPrice| match message.connector_id {
+            1 => Ok(()),
+            _ => Err(DataTransferStatus::Rejected),
+        });
+
+        let handle = |vendor_id: &str, message_id: &str, data: &str| {
+            registry
+                .handle(
+                    &DataTransferRequest::builder()
+                        .vendor_id(CiString255::try_from(vendor_id).unwrap())
+                        .message_id(CiString50::try_from(message_id).unwrap())
+                        .data(data)
+                        .build(),
+                )
+                .status
+        };
+
+        let request = SetPrice {
+            connector_id: 1,
+            price: 0.3,
+        }
+        .to_request()
+        .unwrap();
+        let response = registry.handle(&request);
+        assert_eq!(response.status, DataTransferStatus::Accepted);
+        assert_eq!(response.data, None);
+        assert_eq!(SetPrice::from_response(&response).unwrap(), Some(()));
+
+        assert_eq!(
+            handle(
+                "org.example",
+                "SetPrice",
+                r#"{"connectorId":2,"price":0.3}"#
+            ),
+            DataTransferStatus::Rejected
+        );
+        assert_eq!(
+            handle("org.example", "SetPrice", "not JSON"),
+            DataTransferStatus::Rejected
+        );
+        assert_eq!(
+            handle("org.example", "GetPrice", "{}"),
+            DataTransferStatus::UnknownMessageId
+        );
+        assert_eq!(
+            handle("com.example", "SetPrice", "{}"),
+            DataTransferStatus::UnknownVendorId
+        );
+    }
+}
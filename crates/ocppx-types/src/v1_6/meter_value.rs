@@ -0,0 +1,222 @@
+use super::{
+    MeterValue, SampledValue, SampledValueContext, SampledValueLocation, SampledValueMeasurand,
+    SampledValuePhase, SampledValueUnit,
+};
+use crate::DateTime;
+use alloc::{string::ToString, vec, vec::Vec};
+use core::fmt::Display;
+
+impl MeterValue {
+    /// Build a `MeterValue` sampled at `timestamp` with a
+    /// [`MeterValueSampler`], one measurand at a time.
+    pub fn sampler(timestamp: DateTime) -> MeterValueSampler {
+        MeterValueSampler {
+            timestamp,
+            context: None,
+            sampled_value: vec![],
+        }
+    }
+}
+
+/// A fluent builder of [`MeterValue`], that takes care of the measurands,
+/// the units, and the contexts of the sampled values.
+///
+/// ```
+/// # use ocppx_types::{v1_6::*, DateTime};
+/// let timestamp: DateTime = "2013-02-01T20:53:32.486Z".parse().unwrap();
+/// let meter_value = MeterValue::sampler(timestamp)
+///     .context(SampledValueContext::SamplePeriodic)
+///     .energy_active_import_register(12345, SampledValueUnit::Wh)
+///     .current_import(16.2, SampledValueUnit::A)
+///     .phase(SampledValuePhase::L1)
+///     .soc(42)
+///     .build();
+///
+/// assert_eq!(meter_value.sampled_value.len(), 3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MeterValueSampler {
+    timestamp: DateTime,
+    context: Option<SampledValueContext>,
+    sampled_value: Vec<SampledValue>,
+}
+
+macro_rules! measurands {
+    (
+        $( $method:ident => $measurand:ident ),* $(,)?
+        ;
+        $( $fixed_method:ident => $fixed_measurand:ident ( $( $unit:ident )? ) ),* $(,)?
+    ) => {
+        impl MeterValueSampler {
+            $(
+                #[doc = concat!("Add a `", stringify!($measurand), "` sample.")]
+                pub fn $method(self, value: impl Display, unit: SampledValueUnit) -> Self {
+                    self.sample(SampledValueMeasurand::$measurand, value, Some(unit))
+                }
+            )*
+
+            $(
+                #[doc = concat!("Add a `", stringify!($fixed_measurand), "` sample.")]
+                pub fn $fixed_method(self, value: impl Display) -> Self {
+                    self.sample(
+                        SampledValueMeasurand::$fixed_measurand,
+                        value,
+                        None $( .or(Some(SampledValueUnit::$unit)) )?,
+                    )
+                }
+            )*
+        }
+    };
+}
+
+measurands! {
+    energy_active_export_register => EnergyActiveExportRegister,
+    energy_active_import_register => EnergyActiveImportRegister,
+    energy_reactive_export_register => EnergyReactiveExportRegister,
+    energy_reactive_import_register => EnergyReactiveImportRegister,
+    energy_active_export_interval => EnergyActiveExportInterval,
+    energy_active_import_interval => EnergyActiveImportInterval,
+    energy_reactive_export_interval => EnergyReactiveExportInterval,
+    energy_reactive_import_interval => EnergyReactiveImportInterval,
+    power_active_export => PowerActiveExport,
+    power_active_import => PowerActiveImport,
+    power_offered => PowerOffered,
+    power_reactive_export => PowerReactiveExport,
+    power_reactive_import => PowerReactiveImport,
+    current_import => CurrentImport,
+    current_export => CurrentExport,
+    current_offered => CurrentOffered,
+    voltage => Voltage,
+    temperature => Temperature;
+
+    power_factor => PowerFactor(),
+    frequency => Frequency(),
+    soc => SoC(Percent),
+    rpm => RPM(),
+}
+
+impl MeterValueSampler {
+    /// The context of the samples that do not define one.
+    pub fn context(mut self, context: SampledValueContext) -> Self {
+        self.context = Some(context);
+
+        self
+    }
+
+    /// Add a sample of `measurand`.
+    pub fn sample(
+        mut self,
+        measurand: SampledValueMeasurand,
+        value: impl Display,
+        unit: Option<SampledValueUnit>,
+    ) -> Self {
+        self.sampled_value.push(
+            SampledValue::builder()
+                .measurand(measurand)
+                .unit_opt(unit)
+                .value(value.to_string())
+                .build(),
+        );
+
+        self
+    }
+
+    /// The phase of the last sample.
+    pub fn phase(mut self, phase: SampledValuePhase) -> Self {
+        if let Some(sampled_value) = self.sampled_value.last_mut() {
+            sampled_value.phase = Some(phase);
+        }
+
+        self
+    }
+
+    /// The location of the last sample.
+    pub fn location(mut self, location: SampledValueLocation) -> Self {
+        if let Some(sampled_value) = self.sampled_value.last_mut() {
+            sampled_value.location = Some(location);
+        }
+
+        self
+    }
+
+    /// The context of the last sample, overriding [`Self::context`].
+    pub fn sample_context(mut self, context: SampledValueContext) -> Self {
+        if let Some(sampled_value) = self.sampled_value.last_mut() {
+            sampled_value.context = Some(context);
+        }
+
+        self
+    }
+
+    pub fn build(self) -> MeterValue {
+        let context = self.context;
+
+        MeterValue::builder()
+            .sampled_value(
+                self.sampled_value
+                    .into_iter()
+                    .map(|mut sampled_value| {
+                        sampled_value.context = sampled_value.context.or(context);
+
+                        sampled_value
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .timestamp(self.timestamp)
+            .build()
+    }
+}
+
+impl From<MeterValueSampler> for MeterValue {
+    fn from(sampler: MeterValueSampler) -> Self {
+        sampler.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sampler() {
+        let meter_value = MeterValue::sampler("2013-02-01T20:53:32.486Z".parse().unwrap())
+            .context(SampledValueContext::SamplePeriodic)
+            .energy_active_import_register(12345, SampledValueUnit::Wh)
+            .location(SampledValueLocation::Outlet)
+            .power_active_import(7.4, SampledValueUnit::KW)
+            .phase(SampledValuePhase::L1N)
+            .sample_context(SampledValueContext::Trigger)
+            .soc(80)
+            .build();
+
+        assert_eq!(
+            serde_json::to_value(&meter_value).unwrap(),
+            json!({
+                "timestamp": "2013-02-01T20:53:32.486Z",
+                "sampledValue": [
+                    {
+                        "value": "12345",
+                        "context": "Sample.Periodic",
+                        "measurand": "Energy.Active.Import.Register",
+                        "location": "Outlet",
+                        "unit": "Wh",
+                    },
+                    {
+                        "value": "7.4",
+                        "context": "Trigger",
+                        "measurand": "Power.Active.Import",
+                        "phase": "L1-N",
+                        "unit": "kW",
+                    },
+                    {
+                        "value": "80",
+                        "context": "Sample.Periodic",
+                        "measurand": "SoC",
+                        "unit": "Percent",
+                    },
+                ],
+            })
+        );
+    }
+}
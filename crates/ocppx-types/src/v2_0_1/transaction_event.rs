@@ -0,0 +1,370 @@
+use super::{
+    ChargingStateEnum, IdToken, MeterValue, ReasonEnum, Transaction, TransactionEventEnum,
+    TransactionEventRequest, TriggerReasonEnum, EVSE,
+};
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+
+/// The event cannot be sent in the current state of the transaction.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionEventError {
+    #[error("the transaction has not started yet")]
+    NotStarted,
+
+    #[error("the transaction has already started")]
+    AlreadyStarted,
+
+    #[error("the transaction has ended")]
+    Ended,
+
+    /// Only with the `inline-strings` feature.
+    #[error("the transaction ID is too long")]
+    TransactionIdTooLong,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Idle,
+    Started,
+    Ended,
+}
+
+/// Generate the `TransactionEvent`s of a transaction, in sequence: one
+/// `Started`, any number of `Updated`, and one `Ended`.
+///
+/// It numbers the events with `seqNo`, sends the EVSE with the first event
+/// only, and embeds what happened since the previous event: the ID token
+/// once it is set, the charging state when it changes, and the meter
+/// values. The time spent charging is reported by the `Ended` event.
+///
+/// ```
+/// # use ocppx_types::v2_0_1::*;
+/// let now = chrono::Utc::now();
+/// let mut events = TransactionEventBuilder::new("TX001", EVSE::builder().id(1).build());
+///
+/// events.set_charging_state(ChargingStateEnum::EVConnected);
+/// let started = events.started(TriggerReasonEnum::CablePluggedIn, now).unwrap();
+///
+/// # let id_token: IdToken = serde_json::from_value(serde_json::json!({ "idToken": "ABC1", "type": "ISO14443" })).unwrap();
+/// events.set_id_token(id_token);
+/// let updated = events.updated(TriggerReasonEnum::Authorized, now).unwrap();
+///
+/// let ended = events.ended(TriggerReasonEnum::EVCommunicationLost, ReasonEnum::EVDisconnected, now).unwrap();
+///
+/// assert_eq!((started.seq_no, updated.seq_no, ended.seq_no), (0, 1, 2));
+/// assert!(started.evse.is_some() && updated.evse.is_none());
+/// assert!(updated.id_token.is_some() && ended.id_token.is_none());
+/// ```
+#[derive(Debug, Clone)]
+pub struct TransactionEventBuilder {
+    transaction_id: String,
+    evse: Option<EVSE>,
+    state: State,
+    seq_no: i32,
+    id_token: Option<IdToken>,
+    charging_state: Option<ChargingStateEnum>,
+    charging_state_changed: bool,
+    meter_value: Vec<MeterValue>,
+    remote_start_id: Option<i32>,
+    reservation_id: Option<i32>,
+    offline: bool,
+    /// When the current charging period has started, if charging.
+    charging_since: Option<DateTime<Utc>>,
+    time_spent_charging: i64,
+}
+
+impl TransactionEventBuilder {
+    /// A transaction on `evse`, which has not started yet.
+    pub fn new(transaction_id: impl Into<String>, evse: EVSE) -> Self {
+        Self {
+            transaction_id: transaction_id.into(),
+            evse: Some(evse),
+            state: State::Idle,
+            seq_no: 0,
+            id_token: None,
+            charging_state: None,
+            charging_state_changed: false,
+            meter_value: Vec::new(),
+            remote_start_id: None,
+            reservation_id: None,
+            offline: false,
+            charging_since: None,
+            time_spent_charging: 0,
+        }
+    }
+
+    /// A transaction that has already started, e.g. before a reboot, whose
+    /// next event is numbered `next_seq_no`.
+    pub fn resume(transaction_id: impl Into<String>, next_seq_no: i32) -> Self {
+        Self {
+            evse: None,
+            state: State::Started,
+            seq_no: next_seq_no,
+            ..Self::new(transaction_id, EVSE::builder().id(0).build())
+        }
+    }
+
+    pub fn transaction_id(&self) -> &str {
+        &self.transaction_id
+    }
+
+    /// The `seqNo` of the next event.
+    pub fn next_seq_no(&self) -> i32 {
+        self.seq_no
+    }
+
+    /// Whether the `Started` event has been generated, and not the `Ended`
+    /// one yet.
+    pub fn is_ongoing(&self) -> bool {
+        self.state == State::Started
+    }
+
+    /// Send the ID token that has authorized the transaction with the next
+    /// event.
+    pub fn set_id_token(&mut self, id_token: IdToken) {
+        self.id_token = Some(id_token);
+    }
+
+    /// Send the charging state with the next event, if it has changed.
+    pub fn set_charging_state(&mut self, charging_state: ChargingStateEnum) {
+        if self.charging_state != Some(charging_state) {
+            self.charging_state = Some(charging_state);
+            self.charging_state_changed = true;
+        }
+    }
+
+    /// Send a meter value with the next event.
+    pub fn add_meter_value(&mut self, meter_value: MeterValue) {
+        self.meter_value.push(meter_value);
+    }
+
+    /// Send the ID of the `RequestStartTransaction` that has started the
+    /// transaction with the next event.
+    pub fn set_remote_start_id(&mut self, remote_start_id: i32) {
+        self.remote_start_id = Some(remote_start_id);
+    }
+
+    /// Send the ID of the reservation ended by the transaction with the
+    /// next event.
+    pub fn set_reservation_id(&mut self, reservation_id: i32) {
+        self.reservation_id = Some(reservation_id);
+    }
+
+    /// Flag the next events as generated while offline.
+    pub fn set_offline(&mut self, offline: bool) {
+        self.offline = offline;
+    }
+
+    /// The `Started` event.
+    pub fn started(
+        &mut self,
+        trigger_reason: TriggerReasonEnum,
+        timestamp: DateTime<Utc>,
+    ) -> Result<TransactionEventRequest, TransactionEventError> {
+        match self.state {
+            State::Idle => {}
+            State::Started => return Err(TransactionEventError::AlreadyStarted),
+            State::Ended => return Err(TransactionEventError::Ended),
+        }
+
+        self.state = State::Started;
+
+        self.event(
+            TransactionEventEnum::Started,
+            trigger_reason,
+            None,
+            timestamp,
+        )
+    }
+
+    /// An `Updated` event.
+    pub fn updated(
+        &mut self,
+        trigger_reason: TriggerReasonEnum,
+        timestamp: DateTime<Utc>,
+    ) -> Result<TransactionEventRequest, TransactionEventError> {
+        self.check_ongoing()?;
+
+        self.event(
+            TransactionEventEnum::Updated,
+            trigger_reason,
+            None,
+            timestamp,
+        )
+    }
+
+    /// The `Ended` event.
+    pub fn ended(
+        &mut self,
+        trigger_reason: TriggerReasonEnum,
+        stopped_reason: ReasonEnum,
+        timestamp: DateTime<Utc>,
+    ) -> Result<TransactionEventRequest, TransactionEventError> {
+        self.check_ongoing()?;
+        self.state = State::Ended;
+
+        self.event(
+            TransactionEventEnum::Ended,
+            trigger_reason,
+            Some(stopped_reason),
+            timestamp,
+        )
+    }
+
+    fn check_ongoing(&self) -> Result<(), TransactionEventError> {
+        match self.state {
+            State::Idle => Err(TransactionEventError::NotStarted),
+            State::Started => Ok(()),
+            State::Ended => Err(TransactionEventError::Ended),
+        }
+    }
+
+    fn event(
+        &mut self,
+        event_type: TransactionEventEnum,
+        trigger_reason: TriggerReasonEnum,
+        stopped_reason: Option<ReasonEnum>,
+        timestamp: DateTime<Utc>,
+    ) -> Result<TransactionEventRequest, TransactionEventError> {
+        let transaction_id = crate::bounded_string::<36>(&self.transaction_id)
+            .ok_or(TransactionEventError::TransactionIdTooLong)?;
+        let ended = event_type == TransactionEventEnum::Ended;
+
+        // The charging periods are accounted for up to this event.
+        if let Some(charging_since) = self.charging_since.take() {
+            self.time_spent_charging += (timestamp - charging_since).num_seconds().max(0);
+        }
+
+        if self.charging_state == Some(ChargingStateEnum::Charging) && !ended {
+            self.charging_since = Some(timestamp);
+        }
+
+        let charging_state = self
+            .charging_state
+            .filter(|_| std::mem::take(&mut self.charging_state_changed));
+
+        let seq_no = self.seq_no;
+        self.seq_no += 1;
+
+        Ok(TransactionEventRequest::builder()
+            .event_type(event_type)
+            .timestamp(timestamp)
+            .trigger_reason(trigger_reason)
+            .seq_no(seq_no)
+            .offline_opt(self.offline.then_some(true))
+            .transaction_info(
+                Transaction::builder()
+                    .transaction_id(transaction_id)
+                    .charging_state_opt(charging_state)
+                    .time_spent_charging_opt(ended.then_some(self.time_spent_charging as i32))
+                    // `Local` is the default stopped reason.
+                    .stopped_reason_opt(
+                        stopped_reason.filter(|reason| *reason != ReasonEnum::Local),
+                    )
+                    .remote_start_id_opt(self.remote_start_id.take())
+                    .build(),
+            )
+            .evse_opt(self.evse.take())
+            .id_token_opt(self.id_token.take())
+            .meter_value_opt(
+                (!self.meter_value.is_empty()).then(|| std::mem::take(&mut self.meter_value)),
+            )
+            .reservation_id_opt(self.reservation_id.take())
+            .build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2_0_1::{SampledValue, TransactionEventEnum};
+    use chrono::TimeDelta;
+
+    #[test]
+    fn test_transaction_events() {
+        let started_at = "2013-02-01T20:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let at = |minutes| started_at + TimeDelta::minutes(minutes);
+        let mut events = TransactionEventBuilder::new("TX001", EVSE::builder().id(1).build());
+
+        assert_eq!(
+            events
+                .updated(TriggerReasonEnum::MeterValuePeriodic, at(0))
+                .unwrap_err(),
+            TransactionEventError::NotStarted
+        );
+
+        events.set_charging_state(ChargingStateEnum::Charging);
+        events.set_remote_start_id(42);
+        let started = events
+            .started(TriggerReasonEnum::RemoteStart, at(0))
+            .unwrap();
+        assert_eq!(started.event_type, TransactionEventEnum::Started);
+        assert_eq!(started.transaction_info.remote_start_id, Some(42));
+        assert_eq!(
+            started.transaction_info.charging_state,
+            Some(ChargingStateEnum::Charging)
+        );
+        assert_eq!(
+            events
+                .started(TriggerReasonEnum::RemoteStart, at(0))
+                .unwrap_err(),
+            TransactionEventError::AlreadyStarted
+        );
+
+        // The charging state is only sent when it changes, and the meter
+        // values once.
+        events.set_charging_state(ChargingStateEnum::Charging);
+        events.add_meter_value(
+            MeterValue::builder()
+                .timestamp(at(10))
+                .sampled_value(vec![SampledValue::builder().value(1000).build()])
+                .build(),
+        );
+        let updated = events
+            .updated(TriggerReasonEnum::MeterValuePeriodic, at(10))
+            .unwrap();
+        assert_eq!(updated.seq_no, 1);
+        assert_eq!(updated.transaction_info.charging_state, None);
+        assert_eq!(
+            updated.meter_value.map(|meter_value| meter_value.len()),
+            Some(1)
+        );
+
+        events.set_charging_state(ChargingStateEnum::SuspendedEV);
+        let updated = events
+            .updated(TriggerReasonEnum::ChargingStateChanged, at(30))
+            .unwrap();
+        assert_eq!(
+            updated.transaction_info.charging_state,
+            Some(ChargingStateEnum::SuspendedEV)
+        );
+        assert!(updated.meter_value.is_none());
+
+        let ended = events
+            .ended(TriggerReasonEnum::StopAuthorized, ReasonEnum::Local, at(40))
+            .unwrap();
+        assert_eq!(ended.seq_no, 3);
+        assert_eq!(ended.transaction_info.stopped_reason, None);
+        // Charging from 0 to 30 minutes.
+        assert_eq!(ended.transaction_info.time_spent_charging, Some(1800));
+        assert!(!events.is_ongoing());
+        assert_eq!(
+            events
+                .updated(TriggerReasonEnum::MeterValuePeriodic, at(50))
+                .unwrap_err(),
+            TransactionEventError::Ended
+        );
+
+        // After a reboot, the sequence goes on.
+        let mut events = TransactionEventBuilder::resume("TX002", 7);
+        let ended = events
+            .ended(
+                TriggerReasonEnum::ResetCommand,
+                ReasonEnum::ImmediateReset,
+                at(0),
+            )
+            .unwrap();
+        assert_eq!(ended.seq_no, 7);
+        assert!(ended.evse.is_none());
+    }
+}
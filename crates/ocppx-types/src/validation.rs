@@ -0,0 +1,145 @@
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+use thiserror::Error;
+
+/// A payload does not match its JSON schema.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    pub violations: Vec<Violation>,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("the payload does not match its schema")?;
+
+        for violation in &self.violations {
+            write!(formatter, "\n  {violation}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A particular violation of a JSON schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// JSON Pointer to the invalid part of the payload, e.g.
+    /// `/meterValue/0/timestamp`.
+    pub instance_path: String,
+    /// JSON Pointer to the violated keyword in the schema.
+    pub schema_path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            formatter,
+            "`{instance_path}`: {message}",
+            instance_path = self.instance_path,
+            message = self.message,
+        )
+    }
+}
+
+/// Validators, compiled lazily from the schemas, and shared afterwards.
+pub(crate) struct Validators(Mutex<BTreeMap<&'static str, Arc<jsonschema::Validator>>>);
+
+impl Validators {
+    pub(crate) const fn new() -> Self {
+        Self(Mutex::new(BTreeMap::new()))
+    }
+
+    pub(crate) fn validate(
+        &self,
+        name: &'static str,
+        schema: &'static str,
+        payload: &Value,
+    ) -> Result<(), ValidationError> {
+        let validator = self
+            .0
+            .lock()
+            .unwrap()
+            .entry(name)
+            .or_insert_with(|| {
+                let schema = serde_json::from_str(schema).expect("The schema is not valid JSON");
+
+                Arc::new(jsonschema::validator_for(&schema).expect("The schema is not valid"))
+            })
+            .clone();
+
+        let violations = validator
+            .iter_errors(payload)
+            .map(|error| Violation {
+                instance_path: error.instance_path().as_str().to_owned(),
+                schema_path: error.schema_path().as_str().to_owned(),
+                message: error.to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError { violations })
+        }
+    }
+}
+
+#[cfg(all(test, feature = "core"))]
+mod tests {
+    use crate::{v1_6, v2_0_1};
+    use serde_json::json;
+
+    #[test]
+    fn test_validate() {
+        assert!(v1_6::validate(
+            v1_6::Action::BootNotification,
+            &json!({"chargePointVendor": "VendorX", "chargePointModel": "Model"}),
+        )
+        .is_ok());
+
+        let error = v1_6::validate(
+            v1_6::Action::BootNotification,
+            &json!({"chargePointVendor": "VendorX", "chargePointModel": 42}),
+        )
+        .unwrap_err();
+
+        assert_eq!(error.violations.len(), 1);
+        assert_eq!(error.violations[0].instance_path, "/chargePointModel");
+    }
+
+    #[test]
+    fn test_validate_with_references() {
+        let error = v2_0_1::validate(
+            v2_0_1::Action::BootNotification,
+            &json!({"reason": "PowerUp", "chargingStation": {"model": "Model"}}),
+        )
+        .unwrap_err();
+
+        assert_eq!(error.violations.len(), 1);
+        assert_eq!(error.violations[0].instance_path, "/chargingStation");
+    }
+
+    #[test]
+    fn test_validate_with_overlay() {
+        // `Celsius` is added to the units of the vendored StopTransaction
+        // schema by an overlay, see `build.rs`.
+        assert!(v1_6::validate(
+            v1_6::Action::StopTransaction,
+            &json!({
+                "meterStop": 1000,
+                "timestamp": "2013-02-01T20:53:32.486Z",
+                "transactionId": 1,
+                "transactionData": [{
+                    "timestamp": "2013-02-01T20:53:32.486Z",
+                    "sampledValue": [{"value": "21", "measurand": "Temperature", "unit": "Celsius"}],
+                }],
+            }),
+        )
+        .is_ok());
+    }
+}
@@ -0,0 +1,26 @@
+//! Arbitrary bytes, as received in a WebSocket frame, into the OCPP-J
+//! frame parsers.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ocppx_rpc::{BorrowedMessage, Message};
+
+fuzz_target!(|frame: &[u8]| {
+    if let Ok(message) = BorrowedMessage::from_slice(frame) {
+        let _ = message.unique_id();
+        let _ = message.to_owned();
+    }
+
+    let Ok(frame) = std::str::from_utf8(frame) else {
+        return;
+    };
+
+    // A parsed frame is serialized back to an equivalent frame.
+    if let Ok(message) = frame.parse::<Message>() {
+        let serialized = message.to_string();
+
+        assert_eq!(serialized.parse::<Message>().unwrap(), message);
+        assert!(BorrowedMessage::parse(&serialized).is_ok());
+    }
+});
@@ -0,0 +1,29 @@
+//! Arbitrary JSON arrays, i.e. well-formed JSON but not necessarily
+//! well-formed OCPP-J frames, into the OCPP-J frame parsers.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ocppx_fuzz::Json;
+use ocppx_rpc::{BorrowedMessage, Message};
+use serde_json::Value;
+
+fuzz_target!(|input: (Option<u8>, Vec<Json>)| {
+    let (type_id, elements) = input;
+    let frame = Value::Array(
+        type_id
+            .map(Value::from)
+            .into_iter()
+            .chain(elements.into_iter().map(|Json(element)| element))
+            .collect(),
+    )
+    .to_string();
+
+    if let Ok(message) = BorrowedMessage::parse(&frame) {
+        let _ = message.to_owned();
+    }
+
+    if let Ok(message) = frame.parse::<Message>() {
+        assert_eq!(message.to_string().parse::<Message>().unwrap(), message);
+    }
+});
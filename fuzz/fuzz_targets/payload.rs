@@ -0,0 +1,42 @@
+//! Arbitrary JSON payloads into the deserializers of the payloads of every
+//! action, for every OCPP version.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use ocppx_fuzz::Json;
+use ocppx_types::{v1_6, v1_6_security, v2_0_1};
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    version: u8,
+    action: u8,
+    response: bool,
+    payload: Json,
+}
+
+macro_rules! deserialize {
+    ($version:ident, $input:expr) => {{
+        let actions = $version::Action::VARIANTS;
+        let action = actions[usize::from($input.action) % actions.len()];
+        let payload = $input.payload.0;
+
+        // A deserialized payload is serialized back.
+        if $input.response {
+            if let Ok(response) = $version::Response::from_payload(action, payload) {
+                response.to_payload().unwrap();
+            }
+        } else if let Ok(request) = $version::Request::from_payload(action, payload) {
+            request.to_payload().unwrap();
+        }
+    }};
+}
+
+fuzz_target!(|input: Input| {
+    match input.version % 3 {
+        0 => deserialize!(v1_6, input),
+        1 => deserialize!(v1_6_security, input),
+        _ => deserialize!(v2_0_1, input),
+    }
+});
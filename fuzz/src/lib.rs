@@ -0,0 +1,89 @@
+//! Shared inputs of the fuzz targets.
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+use serde_json::{Map, Number, Value};
+
+/// Deeper values are cut, the parsers refuse them anyway.
+const MAX_DEPTH: usize = 16;
+
+/// The property names and the strings likely to reach the deserializers
+/// of the payloads past their first field.
+const DICTIONARY: &[&str] = &[
+    "connectorId",
+    "idTag",
+    "idTagInfo",
+    "status",
+    "errorCode",
+    "transactionId",
+    "meterStart",
+    "meterStop",
+    "meterValue",
+    "sampledValue",
+    "timestamp",
+    "value",
+    "unit",
+    "measurand",
+    "chargingProfile",
+    "chargingSchedule",
+    "chargingSchedulePeriod",
+    "startPeriod",
+    "limit",
+    "chargePointVendor",
+    "chargePointModel",
+    "currentTime",
+    "interval",
+    "vendorId",
+    "messageId",
+    "data",
+    "key",
+    "type",
+    "Accepted",
+    "Rejected",
+    "Available",
+    "Charging",
+    "2013-02-01T20:53:32.486Z",
+    "9999-12-31T23:59:59Z",
+];
+
+/// An arbitrary JSON value, biased towards the shapes of OCPP payloads.
+#[derive(Debug)]
+pub struct Json(pub Value);
+
+impl<'a> Arbitrary<'a> for Json {
+    fn arbitrary(input: &mut Unstructured<'a>) -> Result<Self> {
+        value(input, 0).map(Self)
+    }
+}
+
+fn value(input: &mut Unstructured<'_>, depth: usize) -> Result<Value> {
+    let kinds = if depth < MAX_DEPTH { 8 } else { 6 };
+
+    Ok(match input.choose_index(kinds)? {
+        0 => Value::Null,
+        1 => Value::Bool(input.arbitrary()?),
+        2 => Value::Number(i64::arbitrary(input)?.into()),
+        3 => Number::from_f64(input.arbitrary()?).map_or(Value::Null, Value::Number),
+        4 => Value::String(input.arbitrary()?),
+        5 => Value::String((*input.choose(DICTIONARY)?).to_owned()),
+        6 => Value::Array(
+            (0..input.choose_index(8)?)
+                .map(|_| value(input, depth + 1))
+                .collect::<Result<_>>()?,
+        ),
+        _ => {
+            let mut object = Map::new();
+
+            for _ in 0..input.choose_index(8)? {
+                let key = if input.arbitrary()? {
+                    (*input.choose(DICTIONARY)?).to_owned()
+                } else {
+                    input.arbitrary()?
+                };
+
+                object.insert(key, value(input, depth + 1)?);
+            }
+
+            Value::Object(object)
+        }
+    })
+}